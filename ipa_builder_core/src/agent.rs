@@ -0,0 +1,886 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::app_config::AppConfig;
+use crate::ipa_logic::{BuildOptions, BuildOutput, BuildPhase, BuildProgress, CancelToken, IpaError};
+use crate::network::NetworkSettings;
+use crate::transfer::ThrottledReader;
+
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:8750";
+/// Read by `ipa_builder agent` when no `--token` is given.
+pub const TOKEN_ENV_VAR: &str = "IPA_BUILDER_AGENT_TOKEN";
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Times an interrupted input upload is resumed before the build fails.
+const UPLOAD_ATTEMPTS: u32 = 4;
+
+/// Where the GUI sends builds when remote building is enabled in the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteAgent {
+    /// e.g. `http://buildbox:8750`
+    pub url: String,
+    pub token: Option<String>,
+    /// Upload rate limit for input ZIPs in bytes per second; 0 is unlimited.
+    pub upload_bytes_per_sec: u64,
+    pub network: NetworkSettings,
+}
+
+/// Body of `POST /builds`. Hooks and output staging are not sent: they run on the machine that
+/// dispatched the build.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentBuildRequest {
+    pub app_name: String,
+    pub output_ipa_name: String,
+    pub release_notes: Option<String>,
+    pub dedupe_frameworks: bool,
+    #[serde(default)]
+    pub exclude_debug_symbols: bool,
+    #[serde(default)]
+    pub strip_bitcode: bool,
+    #[serde(default)]
+    pub package_from_input_zip: bool,
+    #[serde(default)]
+    pub compression: crate::compression::CompressionSettings,
+    #[serde(default)]
+    pub deterministic: bool,
+    #[serde(default)]
+    pub strip_junk_files: bool,
+    #[serde(default)]
+    pub plist_overrides: crate::info_plist::PlistOverrides,
+    #[serde(default)]
+    pub keep_architectures: Vec<String>,
+    #[serde(default)]
+    pub permissions: crate::permissions::PermissionRules,
+    /// Recorded in the IPA's `BuildInfo.json`; `output_ipa_name` already has it filled in.
+    #[serde(default)]
+    pub channel: String,
+    /// SHA-256 of the input ZIP. When set, the upload can be resumed, even by a later build of
+    /// the same input, and is verified once complete.
+    #[serde(default)]
+    pub input_sha256: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentBuildState {
+    WaitingForInput,
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Body of `GET /builds/{id}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentBuildStatus {
+    pub state: AgentBuildState,
+    pub progress: Option<BuildProgress>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub is_simulator_build: bool,
+    #[serde(default)]
+    pub bundle: Option<crate::info_plist::BundleVariables>,
+}
+
+struct AgentJob {
+    request: AgentBuildRequest,
+    work_dir: tempfile::TempDir,
+    status: AgentBuildStatus,
+    ipa_path: Option<PathBuf>,
+    /// Set when the build is deleted, so a running build stops instead of failing on its
+    /// vanished work directory.
+    cancel: CancelToken,
+    /// When `status.state` last changed; expired jobs are swept after `JOB_TTL`.
+    updated: Instant,
+}
+
+impl AgentJob {
+    fn set_state(&mut self, state: AgentBuildState) {
+        self.status.state = state;
+        self.updated = Instant::now();
+    }
+}
+
+type Jobs = Arc<Mutex<HashMap<String, AgentJob>>>;
+
+/// Where partial uploads are kept between attempts, as `<sha256>.part`.
+#[derive(Debug, Default)]
+struct Uploads {
+    dir: PathBuf,
+    /// Checksums of the inputs being uploaded right now; each has one writer at a time.
+    in_flight: Mutex<HashSet<String>>,
+}
+
+/// Marks an input as being uploaded until dropped.
+struct UploadSlot<'a> {
+    uploads: &'a Uploads,
+    sha256: String,
+}
+
+impl Drop for UploadSlot<'_> {
+    fn drop(&mut self) {
+        self.uploads.in_flight.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.sha256);
+    }
+}
+
+impl Uploads {
+    /// Claims the upload of `sha256`, or `None` when another request is already writing it.
+    fn begin(&self, sha256: &str) -> Option<UploadSlot<'_>> {
+        let claimed = self.in_flight.lock().unwrap_or_else(|e| e.into_inner()).insert(sha256.to_string());
+        claimed.then(|| UploadSlot { uploads: self, sha256: sha256.to_string() })
+    }
+
+    fn part_path(&self, sha256: &str) -> PathBuf {
+        self.dir.join(format!("{}.part", sha256))
+    }
+
+    /// Bytes of the input with `sha256` received so far.
+    fn received(&self, sha256: &str) -> u64 {
+        fs::metadata(self.part_path(sha256)).map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn header_value(request: &Request, name: &'static str) -> Option<u64> {
+    request.headers().iter().find(|h| h.field.equiv(name)).and_then(|h| h.value.as_str().trim().parse().ok())
+}
+
+fn lock(jobs: &Jobs) -> MutexGuard<'_, HashMap<String, AgentJob>> {
+    jobs.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Requests handled at once; further connections wait for a free worker.
+const REQUEST_WORKERS: usize = 8;
+/// Finished builds, and builds whose input never arrived, are deleted this long after their last
+/// state change.
+const JOB_TTL: Duration = Duration::from_secs(60 * 60);
+/// Partial uploads nobody resumed for this long are deleted.
+const UPLOAD_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Handles requests until the server is closed. Builds run one at a time, in upload order.
+/// Interrupted uploads are kept in `uploads_dir` so they can be resumed.
+pub fn serve(server: Server, token: Option<String>, uploads_dir: PathBuf) {
+    let jobs: Jobs = Arc::new(Mutex::new(HashMap::new()));
+    let token = token.map(|t| BearerToken::new(&t));
+    let uploads = Uploads { dir: uploads_dir, ..Default::default() };
+    let last_sweep = Mutex::new(Instant::now());
+    let (queue, queued) = mpsc::channel::<String>();
+    let (server, jobs_ref, token, uploads, last_sweep) = (&server, &jobs, token.as_ref(), &uploads, &last_sweep);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for id in queued {
+                run_job(jobs_ref, &id);
+            }
+        });
+        // Uploads and downloads can take minutes; several workers keep them from blocking
+        // status polls without a thread per connection.
+        for _ in 0..REQUEST_WORKERS {
+            let queue = queue.clone();
+            scope.spawn(move || loop {
+                match server.recv_timeout(SWEEP_INTERVAL) {
+                    Ok(Some(request)) => handle_request(request, jobs_ref, &queue, token, uploads),
+                    Ok(None) => {}
+                    Err(e) => {
+                        log::error!("Agent stopped accepting requests: {}", e);
+                        break;
+                    }
+                }
+                let due = {
+                    let mut last_sweep = last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+                    let due = last_sweep.elapsed() >= SWEEP_INTERVAL;
+                    if due {
+                        *last_sweep = Instant::now();
+                    }
+                    due
+                };
+                if due {
+                    sweep(jobs_ref, uploads, JOB_TTL, UPLOAD_TTL);
+                }
+            });
+        }
+        drop(queue);
+    });
+}
+
+/// The agent's bearer token, compared without leaking how much of a guess was right.
+struct BearerToken {
+    key: hmac::Key,
+    tag: hmac::Tag,
+}
+
+impl BearerToken {
+    fn new(token: &str) -> Self {
+        // Comparing HMACs under a random key hides the token's length as well as its bytes.
+        let key = hmac::Key::generate(hmac::HMAC_SHA256, &ring::rand::SystemRandom::new()).expect("system random number generator");
+        let tag = hmac::sign(&key, format!("Bearer {}", token).as_bytes());
+        BearerToken { key, tag }
+    }
+
+    fn matches(&self, authorization: &str) -> bool {
+        hmac::verify(&self.key, authorization.as_bytes(), self.tag.as_ref()).is_ok()
+    }
+}
+
+/// Deletes jobs that finished or never got their input more than `job_ttl` ago, and partial
+/// uploads not written to for `upload_ttl`.
+fn sweep(jobs: &Jobs, uploads: &Uploads, job_ttl: Duration, upload_ttl: Duration) {
+    let expired: Vec<AgentJob> = {
+        let mut jobs = lock(jobs);
+        let ids: Vec<String> = jobs
+            .iter()
+            .filter(|(_, job)| !matches!(job.status.state, AgentBuildState::Queued | AgentBuildState::Running) && job.updated.elapsed() >= job_ttl)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.iter().filter_map(|id| jobs.remove(id)).collect()
+    };
+    if !expired.is_empty() {
+        log::info!("Agent deleted {} expired build(s)", expired.len());
+    }
+    // Work directories are deleted here, outside the lock.
+    drop(expired);
+
+    let Ok(entries) = fs::read_dir(&uploads.dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(sha256) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".part")) else { continue };
+        let stale = entry.metadata().and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok()).is_some_and(|age| age >= upload_ttl);
+        // The slot keeps a resumed upload from being deleted under its writer.
+        if let Some(_slot) = stale.then(|| uploads.begin(sha256)).flatten() {
+            match fs::remove_file(&path) {
+                Ok(()) => log::info!("Agent deleted stale partial upload {}", path.display()),
+                Err(e) => log::warn!("Cannot delete stale partial upload {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+fn handle_request(mut request: Request, jobs: &Jobs, queue: &mpsc::Sender<String>, token: Option<&BearerToken>, uploads: &Uploads) {
+    if let Some(expected) = token {
+        let authorized = request.headers().iter().any(|h| h.field.equiv("Authorization") && expected.matches(h.value.as_str()));
+        if !authorized {
+            respond_error(request, 401, "missing or wrong bearer token");
+            return;
+        }
+    }
+
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let method = request.method().clone();
+    log::debug!("Agent request: {} {}", method, path);
+    match (method, segments.as_slice()) {
+        (Method::Get, ["health"]) => respond_json(request, 200, &serde_json::json!({ "version": env!("CARGO_PKG_VERSION") })),
+        (Method::Post, ["builds"]) => {
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().take(1 << 20).read_to_string(&mut body) {
+                return respond_error(request, 400, &format!("cannot read body: {}", e));
+            }
+            let build_request: AgentBuildRequest = match serde_json::from_str(&body) {
+                Ok(r) => r,
+                Err(e) => return respond_error(request, 400, &format!("invalid build request: {}", e)),
+            };
+            if let Err(reason) = crate::naming::validate_ipa_file_name(&build_request.output_ipa_name) {
+                return respond_error(request, 400, &format!("invalid output_ipa_name: {}", reason));
+            }
+            if build_request.input_sha256.as_deref().is_some_and(|sha| !is_sha256(sha)) {
+                return respond_error(request, 400, "input_sha256 must be 64 hex digits");
+            }
+            let work_dir = match tempfile::tempdir() {
+                Ok(d) => d,
+                Err(e) => return respond_error(request, 500, &format!("cannot create work dir: {}", e)),
+            };
+            let id = uuid::Uuid::new_v4().to_string();
+            lock(jobs).insert(
+                id.clone(),
+                AgentJob {
+                    request: build_request,
+                    work_dir,
+                    status: AgentBuildStatus { state: AgentBuildState::WaitingForInput, progress: None, error: None, warnings: Vec::new(), is_simulator_build: false, bundle: None },
+                    ipa_path: None,
+                    cancel: CancelToken::default(),
+                    updated: Instant::now(),
+                },
+            );
+            respond_json(request, 201, &serde_json::json!({ "id": id }));
+        }
+        (Method::Get, ["builds", id, "input"]) => {
+            let sha256 = match lock(jobs).get(*id) {
+                Some(job) => job.request.input_sha256.clone(),
+                None => return respond_error(request, 404, "unknown build"),
+            };
+            let received = sha256.map(|sha| uploads.received(&sha)).unwrap_or(0);
+            respond_json(request, 200, &serde_json::json!({ "received": received }));
+        }
+        (Method::Put, ["builds", id, "input"]) => {
+            let (input_path, sha256) = match lock(jobs).get_mut(*id) {
+                Some(job) if job.status.state == AgentBuildState::WaitingForInput => {
+                    // Each upload attempt restarts the build's expiry.
+                    job.updated = Instant::now();
+                    (job.work_dir.path().join("input.zip"), job.request.input_sha256.clone())
+                }
+                Some(_) => return respond_error(request, 409, "input was already uploaded"),
+                None => return respond_error(request, 404, "unknown build"),
+            };
+            match sha256 {
+                Some(sha256) => {
+                    // tus-style resumable upload: the body continues the partial upload at
+                    // Upload-Offset, and the input is complete at Upload-Length bytes.
+                    let (Some(offset), Some(length)) = (header_value(&request, "Upload-Offset"), header_value(&request, "Upload-Length")) else {
+                        return respond_error(request, 400, "resumable uploads need Upload-Offset and Upload-Length");
+                    };
+                    if offset > length {
+                        return respond_error(request, 400, "Upload-Offset is past Upload-Length");
+                    }
+                    // Two builds of the same input would otherwise append to the same part file.
+                    let Some(_slot) = uploads.begin(&sha256) else {
+                        return respond_error(request, 409, "this input is already being uploaded");
+                    };
+                    let part_path = uploads.part_path(&sha256);
+                    let received = uploads.received(&sha256);
+                    if offset != received {
+                        return respond_json(request, 409, &serde_json::json!({ "error": format!("upload resumes at byte {}", received), "received": received }));
+                    }
+                    let appended = fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&part_path)
+                        .and_then(|mut file| io::copy(&mut request.as_reader().take(length - offset), &mut file));
+                    if let Err(e) = appended {
+                        return respond_error(request, 500, &format!("upload interrupted at byte {}: {}", uploads.received(&sha256), e));
+                    }
+                    let received = uploads.received(&sha256);
+                    if received < length {
+                        // Not complete yet: the client continues from GET .../input.
+                        let _ = request.respond(Response::empty(204));
+                        return;
+                    }
+                    if crate::transfer::sha256_file(&part_path).ok().as_deref() != Some(sha256.as_str()) {
+                        let _ = fs::remove_file(&part_path);
+                        return respond_error(request, 422, "input checksum mismatch; upload it again");
+                    }
+                    let moved = fs::rename(&part_path, &input_path).or_else(|_| fs::copy(&part_path, &input_path).and_then(|_| fs::remove_file(&part_path)));
+                    if let Err(e) = moved {
+                        return respond_error(request, 500, &format!("cannot store input: {}", e));
+                    }
+                }
+                None => {
+                    let copied = File::create(&input_path).and_then(|mut file| io::copy(request.as_reader(), &mut file));
+                    if let Err(e) = copied {
+                        return respond_error(request, 500, &format!("cannot store input: {}", e));
+                    }
+                }
+            }
+            match lock(jobs).get_mut(*id) {
+                Some(job) => job.set_state(AgentBuildState::Queued),
+                None => return respond_error(request, 404, "unknown build"),
+            }
+            let _ = queue.send(id.to_string());
+            respond_json(request, 202, &serde_json::json!({ "id": id }));
+        }
+        (Method::Get, ["builds", id]) => {
+            let status = lock(jobs).get(*id).map(|job| job.status.clone());
+            match status {
+                Some(status) => respond_json(request, 200, &status),
+                None => respond_error(request, 404, "unknown build"),
+            }
+        }
+        (Method::Get, ["builds", id, "ipa"]) => {
+            let ipa_path = lock(jobs).get(*id).and_then(|job| job.ipa_path.clone());
+            match ipa_path.map(|p| File::open(&p)) {
+                Some(Ok(file)) => {
+                    let _ = request.respond(Response::from_file(file).with_header(content_type("application/octet-stream")));
+                }
+                Some(Err(e)) => respond_error(request, 500, &format!("cannot open IPA: {}", e)),
+                None => respond_error(request, 404, "no IPA for this build"),
+            }
+        }
+        (Method::Delete, ["builds", id]) => {
+            // The job's work directory is deleted when it is dropped.
+            match lock(jobs).remove(*id) {
+                Some(job) => {
+                    job.cancel.cancel();
+                    respond_json(request, 200, &serde_json::json!({ "id": id }))
+                }
+                None => respond_error(request, 404, "unknown build"),
+            }
+        }
+        _ => respond_error(request, 404, "not found"),
+    }
+}
+
+fn run_job(jobs: &Jobs, id: &str) {
+    let (config, options, output_dir) = {
+        let mut jobs = lock(jobs);
+        let job = match jobs.get_mut(id) {
+            Some(job) => job,
+            None => return, // deleted while queued
+        };
+        job.set_state(AgentBuildState::Running);
+        let config = AppConfig {
+            id: id.to_string(),
+            app_name: job.request.app_name.clone(),
+            input_zip_path: job.work_dir.path().join("input.zip").to_string_lossy().into_owned(),
+            output_ipa_name: job.request.output_ipa_name.clone(),
+            strip_junk_files: job.request.strip_junk_files,
+            plist_overrides: job.request.plist_overrides.clone(),
+            keep_architectures: job.request.keep_architectures.clone(),
+            permissions: job.request.permissions.clone(),
+            channel: job.request.channel.clone(),
+            ..Default::default()
+        };
+        let options = BuildOptions {
+            release_notes: job.request.release_notes.clone(),
+            dedupe_frameworks: job.request.dedupe_frameworks,
+            exclude_debug_symbols: job.request.exclude_debug_symbols,
+            strip_bitcode: job.request.strip_bitcode,
+            package_from_input_zip: job.request.package_from_input_zip,
+            compression: job.request.compression.clone(),
+            deterministic: job.request.deterministic,
+            cancel: job.cancel.clone(),
+            ..Default::default()
+        };
+        (config, options, job.work_dir.path().join("out"))
+    };
+    log::info!("Agent building '{}' ({})", config.app_name, id);
+
+    let result = fs::create_dir_all(&output_dir)
+        .map_err(IpaError::from)
+        .and_then(|_| {
+            crate::ipa_logic::generate_ipa_with_progress(&config, &output_dir, &options, &|p| {
+                if let Some(job) = lock(jobs).get_mut(id) {
+                    job.status.progress = Some(p);
+                }
+            })
+        });
+
+    if let Some(job) = lock(jobs).get_mut(id) {
+        match result {
+            Ok(output) => {
+                job.set_state(AgentBuildState::Succeeded);
+                job.status.warnings = output.warnings;
+                job.status.is_simulator_build = output.is_simulator_build;
+                job.status.bundle = output.bundle;
+                job.ipa_path = Some(output.ipa_path);
+            }
+            Err(e) => {
+                log::error!("Agent build {} failed: {}", id, e);
+                job.set_state(AgentBuildState::Failed);
+                job.status.error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("valid header")
+}
+
+fn respond_json<T: Serialize>(request: Request, status: u16, body: &T) {
+    let json = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    let _ = request.respond(Response::from_string(json).with_status_code(status).with_header(content_type("application/json")));
+}
+
+fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, &serde_json::json!({ "error": message }));
+}
+
+/// Runs the build for `config` on `agent`: uploads the input ZIP, relays the agent's progress and
+/// downloads the IPA into `output_dir`.
+pub fn build_remotely(
+    agent: &RemoteAgent,
+    config: &AppConfig,
+    output_dir: &Path,
+    options: &BuildOptions,
+    progress: &dyn Fn(BuildProgress),
+) -> Result<BuildOutput, IpaError> {
+    let input_path = Path::new(&config.input_zip_path);
+    if !input_path.exists() {
+        return Err(IpaError::InputFileNotFound(input_path.to_path_buf()));
+    }
+    if !output_dir.is_dir() {
+        return Err(IpaError::OutputDirectoryInvalid(output_dir.to_path_buf()));
+    }
+    let ipa_name = crate::channels::expand_name(config.output_ipa_name.trim(), &config.channel);
+    if let Err(reason) = crate::naming::validate_ipa_file_name(&ipa_name) {
+        return Err(IpaError::InvalidIpaName { suggestion: crate::naming::sanitize_ipa_file_name(&ipa_name), name: ipa_name, reason });
+    }
+    if config.signing.is_enabled() {
+        // The identity stays on this machine; agents have no way to receive it.
+        return Err(IpaError::BundleSigning("builds signed with a developer identity can't run on a remote agent; build locally".to_string()));
+    }
+    if !config.inject_dylibs.is_empty() {
+        // Like the identity, the files to inject only exist on this machine.
+        return Err(IpaError::Injection("builds that inject dylibs can't run on a remote agent; build locally".to_string()));
+    }
+    if !config.dsym_path.trim().is_empty() && config.dsym_placement == crate::dsym::DsymPlacement::InsideIpa {
+        return Err(IpaError::Dsym("builds with the dSYM inside the IPA can't run on a remote agent; build locally or put it next to the IPA".to_string()));
+    }
+
+    // Names using Info.plist values are resolved once the agent has read them.
+    let early_output_path = if crate::info_plist::BundleVariables::is_used_in(&ipa_name) {
+        None
+    } else {
+        Some(crate::naming::resolve_output_path(output_dir, &ipa_name, config.output_exists, chrono::Local::now().naive_local())?)
+    };
+    let output_path = |status: &AgentBuildStatus| match (&early_output_path, &status.bundle) {
+        (Some(path), _) => Ok(path.clone()),
+        (None, Some(bundle)) => crate::naming::resolve_templated_output_path(output_dir, &ipa_name, bundle, config.output_exists, chrono::Local::now().naive_local()),
+        (None, None) => Err(IpaError::RemoteAgent(format!("'{}' uses Info.plist values, but the agent did not report them", ipa_name))),
+    };
+
+    let input_sha256 = crate::transfer::sha256_file(input_path)?;
+    let client = AgentClient::new(agent)?;
+    let id = client.create_build(&AgentBuildRequest {
+        app_name: config.app_name.clone(),
+        output_ipa_name: ipa_name.clone(),
+        release_notes: options.release_notes.clone(),
+        dedupe_frameworks: options.dedupe_frameworks,
+        exclude_debug_symbols: options.exclude_debug_symbols,
+        strip_bitcode: options.strip_bitcode,
+        package_from_input_zip: options.package_from_input_zip,
+        compression: options.compression.clone(),
+        deterministic: options.deterministic,
+        strip_junk_files: config.strip_junk_files,
+        plist_overrides: config.effective_plist_overrides(),
+        keep_architectures: config.keep_architectures.clone(),
+        permissions: config.permissions.clone(),
+        channel: config.channel.clone(),
+        input_sha256: Some(input_sha256),
+    })?;
+    let result = run_remote_build(&client, &id, input_path, &output_path, &options.cancel, progress);
+    if let Err(e) = client.delete_build(&id) {
+        log::warn!("Failed to clean up remote build {}: {}", id, e);
+    }
+    result
+}
+
+fn run_remote_build(
+    client: &AgentClient,
+    id: &str,
+    input_path: &Path,
+    final_ipa_path: &dyn Fn(&AgentBuildStatus) -> Result<PathBuf, IpaError>,
+    cancel: &CancelToken,
+    progress: &dyn Fn(BuildProgress),
+) -> Result<BuildOutput, IpaError> {
+    client.upload_input(id, input_path, &|done, total| progress(BuildProgress { phase: BuildPhase::Uploading, done, total }))?;
+
+    let status = loop {
+        // The caller deletes the remote build, which cancels it on the agent too.
+        cancel.check()?;
+        let status = client.status(id)?;
+        if let Some(p) = status.progress {
+            progress(p);
+        }
+        match status.state {
+            AgentBuildState::Succeeded => break status,
+            AgentBuildState::Failed => {
+                return Err(IpaError::RemoteAgent(status.error.unwrap_or_else(|| "build failed".to_string())))
+            }
+            _ => thread::sleep(STATUS_POLL_INTERVAL),
+        }
+    };
+
+    let final_ipa_path = final_ipa_path(&status)?;
+    let mut part_path = final_ipa_path.as_os_str().to_owned();
+    part_path.push(".part");
+    let part_path = PathBuf::from(part_path);
+    client.download_ipa(id, &part_path, &|done, total| progress(BuildProgress { phase: BuildPhase::Downloading, done, total }))?;
+    fs::rename(&part_path, &final_ipa_path)?;
+    log::info!("Downloaded remote build to {}", final_ipa_path.display());
+    Ok(BuildOutput { ipa_path: final_ipa_path, warnings: status.warnings, is_simulator_build: status.is_simulator_build, bundle: status.bundle, sha256: None, uploaded_url: None })
+}
+
+/// Counts bytes as they pass through, for upload and download progress. Reports every 1% or
+/// every MiB, whichever is larger.
+pub(crate) struct ProgressReader<'a, R> {
+    inner: R,
+    done: u64,
+    total: u64,
+    reported_step: u64,
+    on_progress: &'a dyn Fn(u64, u64),
+}
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    pub(crate) fn new(inner: R, total: u64, on_progress: &'a dyn Fn(u64, u64)) -> Self {
+        on_progress(0, total);
+        Self { inner, done: 0, total, reported_step: 0, on_progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.done += n as u64;
+        let step = self.done / (self.total / 100).max(1 << 20);
+        if step != self.reported_step || (n == 0 || self.done == self.total) {
+            self.reported_step = step;
+            (self.on_progress)(self.done, self.total);
+        }
+        Ok(n)
+    }
+}
+
+struct AgentClient {
+    base_url: String,
+    token: Option<String>,
+    upload_bytes_per_sec: u64,
+    http: ureq::Agent,
+}
+
+impl AgentClient {
+    fn new(agent: &RemoteAgent) -> Result<Self, IpaError> {
+        Ok(Self {
+            base_url: agent.url.trim().trim_end_matches('/').to_string(),
+            token: agent.token.clone().filter(|t| !t.is_empty()),
+            upload_bytes_per_sec: agent.upload_bytes_per_sec,
+            http: agent.network.http_agent().map_err(IpaError::RemoteAgent)?,
+        })
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = self.http.request(method, &format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    fn create_build(&self, build_request: &AgentBuildRequest) -> Result<String, IpaError> {
+        let response = self.request("POST", "/builds").send_json(build_request).map_err(agent_error)?;
+        let body: serde_json::Value = response.into_json()?;
+        body["id"].as_str().map(str::to_string).ok_or_else(|| IpaError::RemoteAgent("agent did not return a build id".to_string()))
+    }
+
+    /// Uploads the input from where the agent's partial copy ends, resuming after dropped
+    /// connections. The agent keeps partial uploads by checksum, so an upload cut short in an
+    /// earlier build continues too.
+    fn upload_input(&self, id: &str, input_path: &Path, on_progress: &dyn Fn(u64, u64)) -> Result<(), IpaError> {
+        let total = fs::metadata(input_path)?.len();
+        let mut backoff = Duration::from_secs(1);
+        for attempt in 1..=UPLOAD_ATTEMPTS {
+            let received = self.received_input(id)?.min(total);
+            if received > 0 {
+                log::info!("Resuming upload of {} at {} of {}", input_path.display(), received, total);
+            }
+            let mut file = File::open(input_path)?;
+            file.seek(SeekFrom::Start(received))?;
+            let on_progress = |done, _| on_progress(received + done, total);
+            let reader = ProgressReader::new(ThrottledReader::new(file, self.upload_bytes_per_sec), total - received, &on_progress);
+            let sent = self
+                .request("PUT", &format!("/builds/{}/input", id))
+                .set("Content-Length", &(total - received).to_string())
+                .set("Upload-Offset", &received.to_string())
+                .set("Upload-Length", &total.to_string())
+                .send(reader);
+            match sent {
+                Ok(response) if response.status() == 204 => log::warn!("Agent received only part of the input; resuming"),
+                Ok(_) => return Ok(()),
+                // The agent stores what arrived, so the next attempt only sends the rest.
+                Err(ureq::Error::Transport(e)) if attempt < UPLOAD_ATTEMPTS => {
+                    log::warn!("Upload attempt {}/{} failed: {}", attempt, UPLOAD_ATTEMPTS, e);
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                Err(e) => return Err(agent_error(e)),
+            }
+        }
+        Err(IpaError::RemoteAgent(format!("upload still incomplete after {} attempts", UPLOAD_ATTEMPTS)))
+    }
+
+    fn received_input(&self, id: &str) -> Result<u64, IpaError> {
+        let body: serde_json::Value = self.request("GET", &format!("/builds/{}/input", id)).call().map_err(agent_error)?.into_json()?;
+        Ok(body["received"].as_u64().unwrap_or(0))
+    }
+
+    fn status(&self, id: &str) -> Result<AgentBuildStatus, IpaError> {
+        Ok(self.request("GET", &format!("/builds/{}", id)).call().map_err(agent_error)?.into_json()?)
+    }
+
+    fn download_ipa(&self, id: &str, dest: &Path, on_progress: &dyn Fn(u64, u64)) -> Result<(), IpaError> {
+        let response = self.request("GET", &format!("/builds/{}/ipa", id)).call().map_err(agent_error)?;
+        let total = response.header("Content-Length").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let mut reader = ProgressReader::new(response.into_reader(), total, on_progress);
+        io::copy(&mut reader, &mut File::create(dest)?)?;
+        Ok(())
+    }
+
+    fn delete_build(&self, id: &str) -> Result<(), IpaError> {
+        self.request("DELETE", &format!("/builds/{}", id)).call().map_err(agent_error)?;
+        Ok(())
+    }
+}
+
+fn agent_error(e: ureq::Error) -> IpaError {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let message = response
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|body| body["error"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            IpaError::RemoteAgent(format!("HTTP {} {}", code, message).trim().to_string())
+        }
+        ureq::Error::Transport(t) => IpaError::RemoteAgent(t.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_build_round_trip() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let uploads_dir = tempfile::tempdir().unwrap();
+        let uploads_path = uploads_dir.path().to_path_buf();
+        thread::spawn(move || serve(server, Some("secret".to_string()), uploads_path));
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        crate::fixtures::write_fixture(&crate::fixtures::FixtureSpec::default(), &input).unwrap();
+        let mut config = AppConfig {
+            app_name: "Remote".to_string(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: "Remote-{channel}.ipa".to_string(),
+            channel: "beta".to_string(),
+            ..Default::default()
+        };
+        // Permission rules apply on the agent like they do locally.
+        config.permissions.overrides.push(crate::permissions::PermissionOverride {
+            pattern: "Payload/Runner.app/Info.plist".to_string(),
+            mode: crate::permissions::PermissionMode::Executable,
+        });
+
+        let wrong_token = RemoteAgent { url: url.clone(), token: Some("nope".to_string()), upload_bytes_per_sec: 0, network: NetworkSettings::default() };
+        let err = build_remotely(&wrong_token, &config, dir.path(), &BuildOptions::default(), &|_| {}).unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+
+        let agent = RemoteAgent { url, token: Some("secret".to_string()), upload_bytes_per_sec: 1 << 20, network: NetworkSettings::default() };
+        let phases = Mutex::new(Vec::new());
+        let output = build_remotely(&agent, &config, dir.path(), &BuildOptions::default(), &|p| phases.lock().unwrap().push(p.phase)).unwrap();
+        assert_eq!(output.ipa_path, dir.path().join("Remote-beta.ipa"));
+        let info = crate::ipa_logic::read_build_info(&output.ipa_path).unwrap().unwrap();
+        assert_eq!(info.channel.as_deref(), Some("beta"));
+        let mut archive = zip::ZipArchive::new(File::open(&output.ipa_path).unwrap()).unwrap();
+        assert!(archive.by_name("Payload/Runner.app/Runner").is_ok());
+        assert_eq!(archive.by_name("Payload/Runner.app/Info.plist").unwrap().unix_mode().map(|m| m & 0o777), Some(0o755));
+        let phases = phases.into_inner().unwrap();
+        assert_eq!(phases.first(), Some(&BuildPhase::Uploading));
+        assert_eq!(phases.last(), Some(&BuildPhase::Downloading));
+
+        // An upload cut off halfway is resumed by the next build of the same input.
+        let bytes = fs::read(&input).unwrap();
+        let client = AgentClient::new(&agent).unwrap();
+        let request = AgentBuildRequest {
+            app_name: "Remote".to_string(),
+            output_ipa_name: "Remote.ipa".to_string(),
+            release_notes: None,
+            dedupe_frameworks: false,
+            exclude_debug_symbols: false,
+            strip_bitcode: false,
+            package_from_input_zip: false,
+            compression: Default::default(),
+            deterministic: false,
+            strip_junk_files: false,
+            plist_overrides: Default::default(),
+            keep_architectures: Vec::new(),
+            permissions: Default::default(),
+            channel: String::new(),
+            input_sha256: Some(crate::transfer::sha256_file(&input).unwrap()),
+        };
+        let id = client.create_build(&request).unwrap();
+        let half = bytes.len() / 2;
+        let past_end = client
+            .request("PUT", &format!("/builds/{}/input", id))
+            .set("Upload-Offset", &(bytes.len() + 1).to_string())
+            .set("Upload-Length", &bytes.len().to_string())
+            .send_bytes(&[]);
+        assert!(matches!(past_end, Err(ureq::Error::Status(400, _))));
+        let response = client
+            .request("PUT", &format!("/builds/{}/input", id))
+            .set("Upload-Offset", "0")
+            .set("Upload-Length", &bytes.len().to_string())
+            .send_bytes(&bytes[..half])
+            .unwrap();
+        assert_eq!(response.status(), 204);
+        assert_eq!(client.received_input(&id).unwrap(), half as u64);
+        client.delete_build(&id).unwrap();
+
+        let uploaded = Mutex::new(Vec::new());
+        let config = AppConfig { output_exists: crate::naming::OutputExistsPolicy::NumberSuffix, ..config };
+        build_remotely(&agent, &config, dir.path(), &BuildOptions::default(), &|p| {
+            if p.phase == BuildPhase::Uploading {
+                uploaded.lock().unwrap().push(p.done);
+            }
+        })
+        .unwrap();
+        let uploaded = uploaded.into_inner().unwrap();
+        assert_eq!((uploaded.first(), uploaded.last()), (Some(&(half as u64)), Some(&(bytes.len() as u64))));
+        assert!(fs::read_dir(uploads_dir.path()).unwrap().next().is_none());
+
+        let uploads = Uploads::default();
+        let slot = uploads.begin(&request.input_sha256.clone().unwrap()).unwrap();
+        assert!(uploads.begin(&slot.sha256).is_none());
+        drop(slot);
+        assert!(uploads.begin(request.input_sha256.as_deref().unwrap()).is_some());
+    }
+
+    #[test]
+    fn test_bearer_token_matches_only_the_exact_header() {
+        let token = BearerToken::new("secret");
+        assert!(token.matches("Bearer secret"));
+        for wrong in ["Bearer secre", "Bearer secret2", "bearer secret", "secret", ""] {
+            assert!(!token.matches(wrong), "{}", wrong);
+        }
+    }
+
+    #[test]
+    fn test_sweep_deletes_expired_jobs_and_stale_uploads() {
+        let uploads_dir = tempfile::tempdir().unwrap();
+        let uploads = Uploads { dir: uploads_dir.path().to_path_buf(), ..Default::default() };
+        let jobs: Jobs = Arc::new(Mutex::new(HashMap::new()));
+        for (id, state) in [("done", AgentBuildState::Succeeded), ("failed", AgentBuildState::Failed), ("abandoned", AgentBuildState::WaitingForInput), ("running", AgentBuildState::Running), ("queued", AgentBuildState::Queued)] {
+            let job = AgentJob {
+                request: serde_json::from_value(serde_json::json!({ "app_name": "A", "output_ipa_name": "A.ipa", "release_notes": null, "dedupe_frameworks": false })).unwrap(),
+                work_dir: tempfile::tempdir().unwrap(),
+                status: AgentBuildStatus { state, progress: None, error: None, warnings: Vec::new(), is_simulator_build: false, bundle: None },
+                ipa_path: None,
+                cancel: CancelToken::default(),
+                updated: Instant::now(),
+            };
+            lock(&jobs).insert(id.to_string(), job);
+        }
+        let (stale, in_flight) = ("a".repeat(64), "b".repeat(64));
+        fs::write(uploads.part_path(&stale), b"partial").unwrap();
+        fs::write(uploads.part_path(&in_flight), b"partial").unwrap();
+        let work_dir = lock(&jobs)["done"].work_dir.path().to_path_buf();
+
+        // Nothing has expired yet.
+        sweep(&jobs, &uploads, JOB_TTL, UPLOAD_TTL);
+        assert_eq!(lock(&jobs).len(), 5);
+        assert!(uploads.part_path(&stale).exists());
+
+        let _slot = uploads.begin(&in_flight).unwrap();
+        sweep(&jobs, &uploads, Duration::ZERO, Duration::ZERO);
+        let mut left: Vec<String> = lock(&jobs).keys().cloned().collect();
+        left.sort();
+        assert_eq!(left, ["queued", "running"]);
+        assert!(!work_dir.exists());
+        assert!(!uploads.part_path(&stale).exists());
+        assert!(uploads.part_path(&in_flight).exists());
+    }
+}
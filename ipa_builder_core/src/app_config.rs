@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::bundle_signing::BundleSigning;
+use crate::dsym::DsymPlacement;
+use crate::info_plist::PlistOverrides;
+use crate::naming::OutputExistsPolicy;
+use crate::notifications::NotificationPreferences;
+use crate::object_storage::S3Destination;
+use crate::app_store_connect::AppStoreConnect;
+use crate::ota_manifest::OtaManifest;
+use crate::permissions::PermissionRules;
+use crate::webhook::Webhook;
+
+/// One saved IPA generation setup: where the input comes from and what the IPA is called.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppConfig {
+    pub id: String, 
+    pub app_name: String,
+    pub input_zip_path: String,
+    pub output_ipa_name: String,
+    /// [`crate::channels::BuildChannel`] of the next build, picked on the app's row; empty
+    /// builds without one.
+    #[serde(default)]
+    pub channel: String,
+    pub created_at: DateTime<Utc>,
+    pub last_generated_at: Option<DateTime<Utc>>,
+    /// Optional CHANGELOG file; its latest section is used as release notes when none are typed in.
+    #[serde(default)]
+    pub changelog_path: Option<String>,
+    /// Extra environment variables passed to the pre/post-build hooks and usable as `{{NAME}}`
+    /// in hook command lines.
+    #[serde(default)]
+    pub env_vars: BTreeMap<String, String>,
+    /// Files or directories copied next to every IPA built from this config, e.g. symbol maps
+    /// or release notes; see [`crate::sidecar`].
+    #[serde(default)]
+    pub sidecars: Vec<String>,
+    /// `.dSYM` folder, or a ZIP of one, shipped with every IPA built from this config; see
+    /// [`crate::dsym`]. Empty for none.
+    #[serde(default)]
+    pub dsym_path: String,
+    #[serde(default)]
+    pub dsym_placement: DsymPlacement,
+    /// Remove `__MACOSX/`, `.DS_Store`, `._*` and `Thumbs.db` from the bundle before packaging;
+    /// see [`crate::junk`].
+    #[serde(default = "default_strip_junk_files")]
+    pub strip_junk_files: bool,
+    /// Values written into the bundle's `Info.plist` before packaging; see [`crate::info_plist`].
+    #[serde(default)]
+    pub plist_overrides: PlistOverrides,
+    /// Architectures kept in fat Mach-O binaries, e.g. `["arm64"]`; the other slices are removed
+    /// before packaging. Empty keeps every slice. See [`crate::thinning`].
+    #[serde(default)]
+    pub keep_architectures: Vec<String>,
+    /// `.dylib` files and `.framework` bundles copied into `Frameworks/` and loaded by the main
+    /// executable; see [`crate::injection`].
+    #[serde(default)]
+    pub inject_dylibs: Vec<String>,
+    /// Stamp a new `CFBundleVersion` into every build: one more than `build_counter`.
+    #[serde(default)]
+    pub auto_increment_build: bool,
+    /// Last build number stamped by `auto_increment_build`; only ever goes up.
+    #[serde(default)]
+    pub build_counter: u64,
+    /// What to do when the output IPA already exists.
+    #[serde(default)]
+    pub output_exists: OutputExistsPolicy,
+    /// Keep this many of the newest IPAs of this config in the output directory after each
+    /// successful build; 0 keeps all. See [`crate::retention`].
+    #[serde(default)]
+    pub keep_last_builds: u32,
+    /// Move builds beyond `keep_last_builds` to a trash subfolder instead of deleting them.
+    #[serde(default)]
+    pub trash_old_builds: bool,
+    /// Rebuild whenever the input ZIP changes on disk, while the app is open; see
+    /// [`crate::input_watch`].
+    #[serde(default)]
+    pub auto_rebuild: bool,
+    /// Sign the app with a developer identity while packaging; see [`crate::bundle_signing`].
+    #[serde(default)]
+    pub signing: BundleSigning,
+    /// Write an over-the-air install manifest next to every IPA; see [`crate::ota_manifest`].
+    #[serde(default)]
+    pub ota_manifest: OtaManifest,
+    /// Upload IPAs to App Store Connect for TestFlight; see [`crate::app_store_connect`].
+    #[serde(default)]
+    pub app_store_connect: AppStoreConnect,
+    /// Upload every successful build to an S3-compatible bucket; see [`crate::object_storage`].
+    #[serde(default)]
+    pub object_storage: S3Destination,
+    /// Extra rules for which files in the Payload are executable; see [`crate::permissions`].
+    #[serde(default)]
+    pub permissions: PermissionRules,
+    /// Notified after this config's builds, in addition to the webhooks in the settings.
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Which build events notify, and how; see [`crate::notifications`].
+    #[serde(default)]
+    pub notifications: NotificationPreferences,
+}
+
+/// A field of [`AppConfig`] that [`AppConfig::validate`] can reject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigField {
+    AppName,
+    InputZip,
+    OutputIpaName,
+    EnvVars,
+    PlistOverrides,
+    InjectDylibs,
+    Dsym,
+    OtaManifest,
+    AppStoreConnect,
+    ObjectStorage,
+    Permissions,
+    Webhooks,
+    Notifications,
+}
+
+/// What is wrong with each invalid field, as found by [`AppConfig::validate`].
+pub type ConfigErrors = BTreeMap<ConfigField, String>;
+
+fn default_strip_junk_files() -> bool {
+    true
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            app_name: String::new(),
+            input_zip_path: String::new(),
+            output_ipa_name: String::new(),
+            channel: String::new(),
+            created_at: Utc::now(),
+            last_generated_at: None,
+            changelog_path: None,
+            env_vars: BTreeMap::new(),
+            sidecars: Vec::new(),
+            dsym_path: String::new(),
+            dsym_placement: DsymPlacement::default(),
+            strip_junk_files: true,
+            plist_overrides: PlistOverrides::default(),
+            keep_architectures: Vec::new(),
+            inject_dylibs: Vec::new(),
+            auto_increment_build: false,
+            build_counter: 0,
+            output_exists: OutputExistsPolicy::default(),
+            keep_last_builds: 0,
+            trash_old_builds: false,
+            auto_rebuild: false,
+            signing: BundleSigning::default(),
+            ota_manifest: OtaManifest::default(),
+            app_store_connect: AppStoreConnect::default(),
+            object_storage: S3Destination::default(),
+            permissions: PermissionRules::default(),
+            webhooks: Vec::new(),
+            notifications: NotificationPreferences::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Checks the fields the Add and Edit dialogs let users change, with one message per
+    /// invalid field so each can be shown next to its input.
+    pub fn validate(&self) -> Result<(), ConfigErrors> {
+        let mut errors = ConfigErrors::new();
+        if self.app_name.trim().is_empty() {
+            errors.insert(ConfigField::AppName, "Application name cannot be empty.".to_string());
+        }
+        if self.input_zip_path.trim().is_empty() {
+            errors.insert(ConfigField::InputZip, "Select an input ZIP file.".to_string());
+        }
+        if let Err(reason) = crate::naming::validate_ipa_file_name(self.output_ipa_name.trim()) {
+            errors.insert(ConfigField::OutputIpaName, format!("Invalid file name: {}", reason));
+        }
+        if let Some(reason) = self.env_vars.keys().find_map(|key| crate::hooks::validate_env_var_name(key).err()) {
+            errors.insert(ConfigField::EnvVars, format!("Invalid variable: {}", reason));
+        }
+        let checks: [(ConfigField, Result<(), String>); 9] = [
+            (ConfigField::PlistOverrides, self.plist_overrides.validate()),
+            (ConfigField::InjectDylibs, crate::injection::validate(&self.inject_dylibs)),
+            (ConfigField::Dsym, crate::dsym::validate(&self.dsym_path)),
+            (ConfigField::OtaManifest, self.ota_manifest.validate()),
+            (ConfigField::AppStoreConnect, self.app_store_connect.validate()),
+            (ConfigField::ObjectStorage, self.object_storage.validate()),
+            (ConfigField::Permissions, self.permissions.validate()),
+            (ConfigField::Webhooks, crate::webhook::validate_all(&self.webhooks)),
+            (ConfigField::Notifications, self.notifications.validate()),
+        ];
+        for (field, result) in checks {
+            if let Err(reason) = result {
+                errors.insert(field, reason);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Build number the next build stamps, when `auto_increment_build` is on.
+    pub fn next_build_number(&self) -> Option<u64> {
+        self.auto_increment_build.then(|| self.build_counter + 1)
+    }
+
+    /// `plist_overrides` with the next build number, which takes precedence over a fixed one.
+    pub fn effective_plist_overrides(&self) -> PlistOverrides {
+        match self.next_build_number() {
+            Some(number) => PlistOverrides { bundle_version: number.to_string(), ..self.plist_overrides.clone() },
+            None => self.plist_overrides.clone(),
+        }
+    }
+
+    /// Applies `keep_last_builds` to the output directory of a successful build, returning what
+    /// to tell the user about it. Only builds for the same channel count when the output name
+    /// contains `{channel}`. Failures are reported but never fail the build.
+    pub fn clean_up_old_builds(&self, ipa_path: &std::path::Path) -> Option<String> {
+        let output_dir = ipa_path.parent()?;
+        let name = crate::channels::expand_name(self.output_ipa_name.trim(), &self.channel);
+        match crate::retention::apply_retention(output_dir, &name, self.keep_last_builds as usize, self.trash_old_builds) {
+            Ok(outcome) => {
+                for path in &outcome.removed {
+                    log::info!("Retention: removed old build {}", path.display());
+                }
+                outcome.summary()
+            }
+            Err(e) => {
+                log::warn!("Failed to clean up old builds in {}: {}", output_dir.display(), e);
+                Some(format!("Could not clean up old builds: {}", e))
+            }
+        }
+    }
+
+    /// Records that `built`, a copy of this config taken when its build started, succeeded.
+    pub fn record_successful_build(&mut self, built: &AppConfig) {
+        self.last_generated_at = Some(Utc::now());
+        if let Some(number) = built.next_build_number() {
+            self.build_counter = self.build_counter.max(number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_counter_only_goes_up() {
+        let mut config = AppConfig { build_counter: 41, ..Default::default() };
+        assert_eq!(config.next_build_number(), None);
+        config.record_successful_build(&config.clone());
+        assert_eq!(config.build_counter, 41);
+
+        config.auto_increment_build = true;
+        config.plist_overrides.bundle_version = "7".to_string();
+        assert_eq!(config.effective_plist_overrides().bundle_version, "42");
+        let started = config.clone();
+        config.build_counter = 50; // edited while the build ran
+        config.record_successful_build(&started);
+        assert_eq!(config.build_counter, 50);
+        config.build_counter = 10;
+        config.record_successful_build(&started);
+        assert_eq!(config.build_counter, 42);
+        assert!(config.last_generated_at.is_some());
+    }
+
+    #[test]
+    fn test_clean_up_counts_only_builds_for_the_same_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for (i, name) in ["Shop-beta-1.ipa", "Shop-release-1.ipa", "Shop-beta-2.ipa", "Shop-beta-3.ipa"].iter().enumerate() {
+            std::fs::write(dir.path().join(name), b"ipa").unwrap();
+            let file = std::fs::File::options().write(true).open(dir.path().join(name)).unwrap();
+            file.set_modified(start + std::time::Duration::from_secs(60 * i as u64)).unwrap();
+        }
+        let config = AppConfig { output_ipa_name: "Shop-{channel}-{build}.ipa".to_string(), channel: "beta".to_string(), keep_last_builds: 2, ..Default::default() };
+        assert!(config.clean_up_old_builds(&dir.path().join("Shop-beta-3.ipa")).is_some());
+        assert!(!dir.path().join("Shop-beta-1.ipa").exists());
+        assert!(dir.path().join("Shop-release-1.ipa").is_file() && dir.path().join("Shop-beta-2.ipa").is_file());
+    }
+
+    #[test]
+    fn test_validate_reports_each_invalid_field() {
+        let mut config = AppConfig {
+            app_name: "Shop".to_string(),
+            input_zip_path: "build/Runner.app.zip".to_string(),
+            output_ipa_name: "Shop.ipa".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+
+        config.app_name = " ".to_string();
+        config.output_ipa_name = "Shop".to_string();
+        config.env_vars.insert("1BAD".to_string(), String::new());
+        config.dsym_path = "Shop.app".to_string();
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<ConfigField> = errors.keys().copied().collect();
+        assert_eq!(fields, [ConfigField::AppName, ConfigField::OutputIpaName, ConfigField::EnvVars, ConfigField::Dsym]);
+        assert!(errors[&ConfigField::Dsym].contains("not a .dSYM"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+/// Tool that writes the detached signature next to every IPA.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureTool {
+    #[default]
+    None,
+    /// `minisign -S`, writing `<ipa>.minisig`. The secret key must not be password protected
+    /// (`minisign -G -W`), since there is no terminal to ask for it.
+    Minisign,
+    /// `gpg --detach-sign --armor`, writing `<ipa>.asc`. Passphrases come from gpg-agent.
+    Gpg,
+}
+
+impl SignatureTool {
+    pub const ALL: [SignatureTool; 3] = [SignatureTool::None, SignatureTool::Minisign, SignatureTool::Gpg];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SignatureTool::None => "Don't sign",
+            SignatureTool::Minisign => "minisign",
+            SignatureTool::Gpg => "GPG",
+        }
+    }
+
+    pub fn is_enabled(self) -> bool {
+        self != SignatureTool::None
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            SignatureTool::None => "",
+            SignatureTool::Minisign => "minisig",
+            SignatureTool::Gpg => "asc",
+        }
+    }
+}
+
+/// Detached signatures of built IPAs, so recipients can check an IPA came from this machine with
+/// `minisign -V` or `gpg --verify`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ArtifactSigning {
+    pub tool: SignatureTool,
+    /// Secret key file for minisign; key id, fingerprint or email for GPG (empty for the default
+    /// key).
+    pub key: String,
+}
+
+impl ArtifactSigning {
+    pub fn is_enabled(&self) -> bool {
+        self.tool.is_enabled()
+    }
+
+    /// `Shop.ipa.minisig` or `Shop.ipa.asc` next to the IPA.
+    pub fn signature_path(&self, ipa_path: &Path) -> PathBuf {
+        let mut name = ipa_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(self.tool.extension());
+        ipa_path.with_file_name(name)
+    }
+
+    /// Signatures any tool may have written for `ipa_path`, for cleaning up along with the IPA.
+    pub fn existing_signatures(ipa_path: &Path) -> Vec<PathBuf> {
+        SignatureTool::ALL
+            .into_iter()
+            .filter(|tool| tool.is_enabled())
+            .map(|tool| ArtifactSigning { tool, key: String::new() }.signature_path(ipa_path))
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// The command that signs `ipa_path`. `trusted_comment` is signed along with a minisign
+    /// signature and shown by `minisign -V`; GPG has no equivalent.
+    fn command(&self, ipa_path: &Path, trusted_comment: &str) -> Option<Command> {
+        let signature = self.signature_path(ipa_path);
+        let key = self.key.trim();
+        let mut cmd = match self.tool {
+            SignatureTool::None => return None,
+            SignatureTool::Minisign => {
+                let mut cmd = Command::new("minisign");
+                cmd.arg("-S");
+                if !key.is_empty() {
+                    cmd.arg("-s").arg(key);
+                }
+                cmd.arg("-m").arg(ipa_path).arg("-x").arg(&signature).arg("-t").arg(trusted_comment);
+                cmd
+            }
+            SignatureTool::Gpg => {
+                let mut cmd = Command::new("gpg");
+                cmd.args(["--batch", "--yes", "--detach-sign", "--armor"]);
+                if !key.is_empty() {
+                    cmd.arg("--local-user").arg(key);
+                }
+                cmd.arg("--output").arg(&signature).arg(ipa_path);
+                cmd
+            }
+        };
+        cmd.stdin(Stdio::null());
+        Some(cmd)
+    }
+
+    /// Signs `ipa_path`, replacing an earlier signature of the same file. Returns the signature
+    /// path, or `None` when signing is off.
+    pub fn sign(&self, ipa_path: &Path, trusted_comment: &str) -> Result<Option<PathBuf>, String> {
+        let Some(mut cmd) = self.command(ipa_path, trusted_comment) else {
+            return Ok(None);
+        };
+        let program = cmd.get_program().to_string_lossy().into_owned();
+        let output = cmd.output().map_err(|e| format!("failed to start {}: {}", program, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{} {} ({})", program, output.status, stderr.trim()));
+        }
+        let signature = self.signature_path(ipa_path);
+        log::info!("Signed {} with {}", ipa_path.display(), program);
+        Ok(Some(signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_commands_write_next_to_the_ipa() {
+        let ipa = Path::new("out/Shop.ipa");
+        let args = |signing: &ArtifactSigning| -> Vec<String> {
+            let cmd = signing.command(ipa, "Shop 1.4.0 (42)").unwrap();
+            cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect()
+        };
+
+        let minisign = ArtifactSigning { tool: SignatureTool::Minisign, key: " keys/build.key ".to_string() };
+        assert_eq!(minisign.signature_path(ipa), Path::new("out/Shop.ipa.minisig"));
+        assert_eq!(args(&minisign), ["-S", "-s", "keys/build.key", "-m", "out/Shop.ipa", "-x", "out/Shop.ipa.minisig", "-t", "Shop 1.4.0 (42)"]);
+
+        let gpg = ArtifactSigning { tool: SignatureTool::Gpg, key: String::new() };
+        assert_eq!(args(&gpg), ["--batch", "--yes", "--detach-sign", "--armor", "--output", "out/Shop.ipa.asc", "out/Shop.ipa"]);
+
+        let off = ArtifactSigning::default();
+        assert!(!off.is_enabled());
+        assert_eq!(off.sign(ipa, "").unwrap(), None);
+    }
+}
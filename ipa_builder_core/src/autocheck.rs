@@ -0,0 +1,358 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::app_config::AppConfig;
+use crate::ipa_logic::BuildOptions;
+use crate::output_watch::WATCHER_RETRY_INTERVAL;
+
+/// [`AppConfig::id`] of the configs AutoCheck builds, which are not saved with the others.
+pub const AUTOCHECK_CONFIG_ID: &str = "autocheck";
+
+#[derive(Debug, Clone)]
+pub struct AutoCheckConfig {
+    pub watch_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub app_name: String,
+    pub output_ipa_name: String,
+    pub build_options: BuildOptions,
+    /// Build each ready ZIP on the watcher thread and delete it afterwards. When false, the
+    /// runner only reports it with [`AutoCheckMessage::Ready`], so the caller can build it
+    /// along with its other builds and then call [`delete_source_zip_with_retry`].
+    pub build_inline: bool,
+}
+
+impl AutoCheckConfig {
+    /// The config a ZIP found at `input` is built with.
+    pub fn app_config(&self, input: &Path) -> AppConfig {
+        AppConfig {
+            id: AUTOCHECK_CONFIG_ID.to_string(),
+            app_name: self.app_name.clone(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: self.output_ipa_name.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Deletes a built input ZIP, retrying for up to `max_wait` while another process still has
+/// it open.
+pub fn delete_source_zip_with_retry(path: &Path, max_wait: Duration) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    while start.elapsed() < max_wait {
+        match std::fs::remove_file(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                let msg = e.to_string();
+                thread::sleep(Duration::from_millis(250));
+                if !path.exists() {
+                    return Ok(());
+                }
+                if start.elapsed() >= max_wait {
+                    return Err(msg);
+                }
+            }
+        }
+    }
+    Err("timeout".to_string())
+}
+
+/// How long after its last modification a manual build treats the input ZIP as possibly still
+/// being written.
+pub const INPUT_SETTLE_TIME: Duration = Duration::from_secs(3);
+
+/// The readiness check AutoCheck waits on, for manual builds: fails when the input ZIP at `path`
+/// was modified within [`INPUT_SETTLE_TIME`] or its central directory cannot be read, which
+/// usually means Xcode or a copy is still writing it. Paths that are not local files, such as
+/// URLs, pass; the build reports problems with those itself.
+pub fn input_readiness(path: &Path) -> Result<(), String> {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta,
+        _ => return Ok(()),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if let Some(age) = meta.modified().ok().and_then(|t| t.elapsed().ok()).filter(|age| *age < INPUT_SETTLE_TIME) {
+        return Err(format!("{} was modified {:.1}s ago and may still be being written", name, age.as_secs_f32()));
+    }
+    std::fs::File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| zip::ZipArchive::new(file).map(drop).map_err(|e| e.to_string()))
+        .map_err(|e| format!("{} is not a complete ZIP yet ({})", name, e))
+}
+
+#[derive(Debug, Clone)]
+pub enum AutoCheckMessage {
+    Status(String),
+    /// A ZIP finished being written and is ready to build; only sent without
+    /// [`AutoCheckConfig::build_inline`].
+    Ready(PathBuf),
+    /// The watch directory disappeared or the watcher failed; AutoCheck keeps trying to re-attach.
+    WatcherLost(String),
+    WatcherRestored,
+}
+
+pub struct AutoCheckRunner {
+    stop_flag: Arc<AtomicBool>,
+    join_handle: Option<thread::JoinHandle<()>>,
+    rx: mpsc::Receiver<AutoCheckMessage>,
+}
+
+impl AutoCheckRunner {
+    pub fn start(cfg: AutoCheckConfig) -> Result<Self, String> {
+        if !cfg.watch_dir.is_dir() {
+            return Err(format!("Watch directory is invalid: {}", cfg.watch_dir.display()));
+        }
+        if !cfg.output_dir.is_dir() {
+            return Err(format!("Output directory is invalid: {}", cfg.output_dir.display()));
+        }
+        if cfg.app_name.trim().is_empty() {
+            return Err("App name cannot be empty".to_string());
+        }
+        if let Err(reason) = crate::naming::validate_ipa_file_name(&cfg.output_ipa_name) {
+            return Err(format!(
+                "Output IPA name is invalid: {} (try '{}')",
+                reason,
+                crate::naming::sanitize_ipa_file_name(&cfg.output_ipa_name)
+            ));
+        }
+
+        let (tx, rx) = mpsc::channel::<AutoCheckMessage>();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_thread = Arc::clone(&stop_flag);
+
+        let join_handle = thread::spawn(move || {
+            let _ = tx.send(AutoCheckMessage::Status(format!(
+                "AutoCheck started. Watching: {}",
+                cfg.watch_dir.display()
+            )));
+
+            let (event_tx, event_rx) = mpsc::channel::<notify::Result<Event>>();
+            // `None` while the watch directory is unavailable; re-attached by the health check.
+            let mut watcher: Option<RecommendedWatcher> = None;
+            let mut lost = false;
+            let mut next_health_check = Instant::now();
+
+            while !stop_flag_thread.load(Ordering::Relaxed) {
+                if Instant::now() >= next_health_check {
+                    next_health_check = Instant::now() + WATCHER_RETRY_INTERVAL;
+                    if watcher.is_some() && !cfg.watch_dir.is_dir() {
+                        watcher = None;
+                        lost = true;
+                        let _ = tx.send(AutoCheckMessage::WatcherLost(format!(
+                            "{} is no longer available",
+                            cfg.watch_dir.display()
+                        )));
+                    }
+                    if watcher.is_none() && cfg.watch_dir.is_dir() {
+                        match attach_watcher(&cfg.watch_dir, event_tx.clone()) {
+                            Ok(w) => {
+                                watcher = Some(w);
+                                if std::mem::take(&mut lost) {
+                                    let _ = tx.send(AutoCheckMessage::WatcherRestored);
+                                }
+                            }
+                            Err(e) if !lost => {
+                                lost = true;
+                                let _ = tx.send(AutoCheckMessage::WatcherLost(e));
+                            }
+                            Err(_) => {}
+                        }
+                    }
+                }
+
+                match event_rx.recv_timeout(Duration::from_millis(250)) {
+                    Ok(Ok(ev)) => {
+                        for path in ev.paths {
+                            if stop_flag_thread.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            if !is_candidate_runner_zip(&path) {
+                                continue;
+                            }
+
+                            let _ = tx.send(AutoCheckMessage::Status(format!(
+                                "Detected candidate: {}",
+                                path.display()
+                            )));
+
+                            if let Err(e) = wait_until_file_ready(&path, Duration::from_secs(15)) {
+                                let _ = tx.send(AutoCheckMessage::Status(format!(
+                                    "Skipped (not ready): {} ({})",
+                                    path.display(),
+                                    e
+                                )));
+                                continue;
+                            }
+
+                            if !cfg.build_inline {
+                                let _ = tx.send(AutoCheckMessage::Ready(path));
+                                continue;
+                            }
+                            let app_config = cfg.app_config(&path);
+                            match crate::ipa_logic::generate_ipa_with_options(&app_config, &cfg.output_dir, &cfg.build_options) {
+                                Ok(out) => {
+                                    let _ = tx.send(AutoCheckMessage::Status(format!(
+                                        "Generated: {}",
+                                        out.ipa_path.display()
+                                    )));
+                                    for warning in &out.warnings {
+                                        let _ = tx.send(AutoCheckMessage::Status(format!("Warning: {}", warning)));
+                                    }
+
+                                    match delete_source_zip_with_retry(&path, Duration::from_secs(5)) {
+                                        Ok(()) => {
+                                            let _ = tx.send(AutoCheckMessage::Status(format!(
+                                                "Deleted source: {}",
+                                                path.display()
+                                            )));
+                                        }
+                                        Err(e) => {
+                                            let _ = tx.send(AutoCheckMessage::Status(format!(
+                                                "Generated but failed to delete source {}: {}",
+                                                path.display(),
+                                                e
+                                            )));
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    let _ = tx.send(AutoCheckMessage::Status(format!(
+                                        "Generation error for {}: {}",
+                                        path.display(),
+                                        e
+                                    )));
+                                }
+                            }
+                        }
+                    }
+                    Ok(Err(e)) => {
+                        // The watcher may be in an undefined state after an error; re-create it.
+                        watcher = None;
+                        lost = true;
+                        next_health_check = Instant::now() + WATCHER_RETRY_INTERVAL;
+                        let _ = tx.send(AutoCheckMessage::WatcherLost(format!(
+                            "Watcher event error: {}",
+                            e
+                        )));
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        break;
+                    }
+                }
+            }
+
+            let _ = tx.send(AutoCheckMessage::Status("AutoCheck stopped.".to_string()));
+        });
+
+        Ok(Self {
+            stop_flag,
+            join_handle: Some(join_handle),
+            rx,
+        })
+    }
+
+    /// False once the worker thread has exited without [`stop`](Self::stop) being called,
+    /// e.g. after a panic.
+    pub fn is_alive(&self) -> bool {
+        self.join_handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    pub fn try_recv(&self) -> Option<AutoCheckMessage> {
+        self.rx.try_recv().ok()
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.join_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn attach_watcher(dir: &Path, event_tx: mpsc::Sender<notify::Result<Event>>) -> Result<RecommendedWatcher, String> {
+    let mut watcher = RecommendedWatcher::new(
+        move |res| {
+            let _ = event_tx.send(res);
+        },
+        Config::default(),
+    )
+    .map_err(|e| format!("AutoCheck watcher init error: {}", e))?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("AutoCheck watcher start error: {}", e))?;
+    Ok(watcher)
+}
+
+fn is_candidate_runner_zip(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+
+    let file_name = match path.file_name().and_then(|s| s.to_str()) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let lower = file_name.to_ascii_lowercase();
+    lower.starts_with("runner.app") && lower.ends_with(".zip")
+}
+
+fn wait_until_file_ready(path: &Path, max_wait: Duration) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let mut last_len: Option<u64> = None;
+
+    while start.elapsed() < max_wait {
+        let meta = match std::fs::metadata(path) {
+            Ok(m) => m,
+            Err(_) => {
+                thread::sleep(Duration::from_millis(250));
+                continue;
+            }
+        };
+
+        let len = meta.len();
+        if last_len == Some(len) && std::fs::File::open(path).is_ok() {
+            return Ok(());
+        }
+        last_len = Some(len);
+        thread::sleep(Duration::from_millis(400));
+    }
+
+    Err("timeout".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_input_readiness_flags_fresh_and_truncated_zips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Runner.app.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        zip.start_file("Runner.app/Info.plist", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"plist").unwrap();
+        zip.finish().unwrap();
+        let age = |path: &Path| {
+            let file = std::fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(60)).unwrap();
+        };
+
+        assert!(input_readiness(&path).unwrap_err().contains("may still be being written"));
+        age(&path);
+        assert_eq!(input_readiness(&path), Ok(()));
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        age(&path);
+        assert!(input_readiness(&path).unwrap_err().contains("not a complete ZIP"));
+        assert_eq!(input_readiness(&dir.path().join("missing.zip")), Ok(()));
+    }
+}
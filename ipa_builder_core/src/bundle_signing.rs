@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::code_signature::{self, CodeSignatureRequest, SigningIdentity};
+use crate::{codesign, macho};
+
+/// Developer identity and provisioning profile a config's app is signed with while packaging.
+/// Signing runs in-process (see [`crate::code_signature`]), so it works on Linux and Windows
+/// build machines too, without a keychain or Apple's tools.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct BundleSigning {
+    /// `.p12`/`.pfx` file, or a `.pem` file holding both the certificate and its private key.
+    /// Empty turns signing off.
+    pub identity_path: String,
+    /// File holding the `.p12` password; empty when it has none. Kept in a file so the password
+    /// isn't stored in `apps.json`.
+    pub password_file: String,
+    /// Embedded as `embedded.mobileprovision` before signing; empty keeps the bundle's own.
+    pub provisioning_profile: String,
+    /// `.entitlements` plist for the main app, replacing the entitlements it was built with.
+    /// Empty keeps the existing ones.
+    pub entitlements: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdentityKind {
+    P12,
+    Pem,
+}
+
+/// How a signed bundle is sealed into the bundle containing it.
+struct SealedCode {
+    cdhash: [u8; 32],
+    requirement: String,
+}
+
+impl BundleSigning {
+    pub fn is_enabled(&self) -> bool {
+        !self.identity_path.trim().is_empty()
+    }
+
+    /// Checks that the files exist and the identity is a `.p12`, `.pfx` or `.pem`. Cheap enough
+    /// to run on every frame; [`Self::identity`] also opens the identity.
+    pub fn validate(&self) -> Result<(), String> {
+        let identity = Path::new(self.identity_path.trim());
+        if !identity.is_file() {
+            return Err(format!("Signing identity not found: {}", identity.display()));
+        }
+        if self.identity_kind().is_none() {
+            return Err(format!("Signing identity must be a .p12, .pfx or .pem file: {}", identity.display()));
+        }
+        for (label, path) in [("Password file", &self.password_file), ("Provisioning profile", &self.provisioning_profile), ("Entitlements", &self.entitlements)] {
+            let path = Path::new(path.trim());
+            if !path.as_os_str().is_empty() && !path.is_file() {
+                return Err(format!("{} not found: {}", label, path.display()));
+            }
+        }
+        let entitlements = self.entitlements.trim();
+        if !entitlements.is_empty() {
+            let bytes = fs::read(entitlements).map_err(|e| format!("Failed to read {}: {}", entitlements, e))?;
+            crate::codesign::entitlement_entries(&bytes)?;
+        }
+        Ok(())
+    }
+
+    fn identity_kind(&self) -> Option<IdentityKind> {
+        let extension = Path::new(self.identity_path.trim()).extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "p12" | "pfx" => Some(IdentityKind::P12),
+            "pem" => Some(IdentityKind::Pem),
+            _ => None,
+        }
+    }
+
+    /// Validates the settings and opens the identity with the password from
+    /// [`Self::password_file`], so a wrong password shows up before anything is extracted.
+    pub fn identity(&self) -> Result<SigningIdentity, String> {
+        self.validate()?;
+        let path = self.identity_path.trim();
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        match self.identity_kind() {
+            Some(IdentityKind::Pem) => SigningIdentity::from_pem(&bytes),
+            _ => {
+                let password_file = self.password_file.trim();
+                let password = if password_file.is_empty() {
+                    String::new()
+                } else {
+                    let password = fs::read_to_string(password_file).map_err(|e| format!("Failed to read {}: {}", password_file, e))?;
+                    password.trim_end_matches(['\r', '\n']).to_string()
+                };
+                SigningIdentity::from_p12(&bytes, &password)
+            }
+        }
+        .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Embeds the provisioning profile and signs `app_dir` in place: nested apps, extensions
+    /// and frameworks first, then loose dylibs, then the app, each with a new
+    /// `_CodeSignature/CodeResources` seal. Binaries keep their existing entitlements, except
+    /// the main app when [`Self::entitlements`] is set.
+    pub fn sign_bundle(&self, app_dir: &Path) -> Result<(), String> {
+        let identity = self.identity()?;
+        let profile = self.provisioning_profile.trim();
+        if !profile.is_empty() {
+            fs::copy(profile, app_dir.join("embedded.mobileprovision")).map_err(|e| format!("Failed to embed the provisioning profile: {}", e))?;
+        }
+        let entitlements = match self.entitlements.trim() {
+            "" => None,
+            path => Some(fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?),
+        };
+        sign_bundle_dir(&identity, app_dir, entitlements.as_deref())?;
+        log::info!("Signed {} with {}", app_dir.display(), identity.common_name().unwrap_or_else(|| self.identity_path.trim().to_string()));
+        Ok(())
+    }
+}
+
+/// Bundles with a main executable and a seal of their own.
+fn is_code_bundle(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| matches!(ext.to_str(), Some("app" | "appex" | "framework" | "xpc"))) && codesign::main_executable(path).is_some()
+}
+
+/// Signs `bundle` and everything nested in it, then seals its files and signs its main
+/// executable with `entitlements`, or the ones it has.
+fn sign_bundle_dir(identity: &SigningIdentity, bundle: &Path, entitlements: Option<&[u8]>) -> Result<SealedCode, String> {
+    let executable = codesign::main_executable(bundle).ok_or_else(|| format!("{} has no main executable", bundle.display()))?;
+    let info_plist = fs::read(bundle.join("Info.plist")).ok();
+    let identifier = info_plist
+        .as_deref()
+        .and_then(|bytes| crate::info_plist::parse_dictionary(bytes).ok())
+        .and_then(|info| info.get("CFBundleIdentifier")?.as_string().map(str::to_string))
+        .unwrap_or_else(|| bundle.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+
+    let mut files = BTreeMap::new();
+    let mut files2 = BTreeMap::new();
+    let mut nested_bundle: Option<PathBuf> = None;
+    let walk = WalkDir::new(bundle)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !(e.depth() == 1 && e.file_name() == "_CodeSignature"));
+    for entry in walk {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", bundle.display(), e))?;
+        let path = entry.path();
+        let relative = path.strip_prefix(bundle).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        let inside_nested = nested_bundle.as_deref().is_some_and(|nested| path.starts_with(nested));
+        if !inside_nested {
+            nested_bundle = None;
+        }
+        let file_name = entry.file_name().to_string_lossy();
+        if path == executable || file_name == ".DS_Store" || matches!(relative.as_str(), "Info.plist" | "PkgInfo") {
+            continue;
+        }
+        if entry.path_is_symlink() {
+            if !inside_nested {
+                let target = fs::read_link(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                files2.insert(relative, plist::Value::Dictionary(plist::Dictionary::from_iter([("symlink".to_string(), plist::Value::String(target.to_string_lossy().into_owned()))])));
+            }
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if !inside_nested && is_code_bundle(path) {
+                let sealed = sign_bundle_dir(identity, path, None)?;
+                files2.insert(
+                    relative,
+                    plist::Value::Dictionary(plist::Dictionary::from_iter([
+                        ("cdhash".to_string(), plist::Value::Data(sealed.cdhash[..20].to_vec())),
+                        ("requirement".to_string(), plist::Value::String(sealed.requirement)),
+                    ])),
+                );
+                nested_bundle = Some(path.to_path_buf());
+            }
+            continue;
+        }
+        if !inside_nested && codesign::file_is_macho(path) {
+            // Loose dylibs are signed on their own, named after the file like codesign does.
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            sign_file(identity, path, &CodeSignatureRequest { identifier: &name, ..Default::default() })?;
+        }
+
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let sha1 = plist::Value::Data(ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, &bytes).as_ref().to_vec());
+        let optional = relative.split('/').any(|part| part.ends_with(".lproj") && part != "Base.lproj");
+        let mut entry = plist::Dictionary::from_iter([("hash".to_string(), sha1.clone())]);
+        if optional {
+            entry.insert("optional".to_string(), plist::Value::Boolean(true));
+        }
+        files.insert(relative.clone(), if optional { plist::Value::Dictionary(entry.clone()) } else { sha1 });
+        if !inside_nested {
+            entry.insert("hash2".to_string(), plist::Value::Data(Sha256::digest(&bytes).to_vec()));
+            files2.insert(relative, plist::Value::Dictionary(entry));
+        }
+    }
+
+    let seal = plist::Value::Dictionary(plist::Dictionary::from_iter([
+        ("files".to_string(), plist::Value::Dictionary(files.into_iter().collect())),
+        ("files2".to_string(), plist::Value::Dictionary(files2.into_iter().collect())),
+        ("rules".to_string(), rules(false)),
+        ("rules2".to_string(), rules(true)),
+    ]));
+    let mut code_resources = Vec::new();
+    seal.to_writer_xml(&mut code_resources).map_err(|e| format!("Failed to write CodeResources: {}", e))?;
+    fs::create_dir_all(bundle.join("_CodeSignature")).map_err(|e| format!("Failed to create _CodeSignature: {}", e))?;
+    fs::write(bundle.join("_CodeSignature/CodeResources"), &code_resources).map_err(|e| format!("Failed to write CodeResources: {}", e))?;
+
+    let existing_entitlements;
+    let entitlements = match entitlements {
+        Some(entitlements) => Some(entitlements),
+        None => {
+            existing_entitlements = existing_entitlements_xml(&executable);
+            existing_entitlements.as_ref().map(|xml| xml.as_bytes())
+        }
+    };
+    let request = CodeSignatureRequest { identifier: &identifier, entitlements, info_plist: info_plist.as_deref(), code_resources: Some(&code_resources) };
+    let cdhash = sign_file(identity, &executable, &request)?;
+    Ok(SealedCode { cdhash, requirement: identity.designated_requirement(&identifier) })
+}
+
+fn sign_file(identity: &SigningIdentity, path: &Path, request: &CodeSignatureRequest) -> Result<[u8; 32], String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let signed = code_signature::sign_macho(&bytes, identity, request).map_err(|e| format!("Failed to sign {}: {}", path.display(), e))?;
+    fs::write(path, &signed.bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(signed.cdhash)
+}
+
+/// The entitlements embedded in the first slice's current signature, if any.
+fn existing_entitlements_xml(executable: &Path) -> Option<String> {
+    let bytes = fs::read(executable).ok()?;
+    let slice = macho::slices(&bytes).ok()?.first()?.bytes(&bytes).ok()?.to_vec();
+    macho::code_signature(&slice).ok()??.entitlements_xml
+}
+
+/// `codesign`'s default resource rules: version 1 (`rules`) for old systems and version 2
+/// (`rules2`), which also seals nested code and symlinks.
+fn rules(version2: bool) -> plist::Value {
+    let rule = |weight: Option<i64>, flags: &[&str]| {
+        let mut rule = plist::Dictionary::new();
+        for flag in flags {
+            rule.insert(flag.to_string(), plist::Value::Boolean(true));
+        }
+        if let Some(weight) = weight {
+            rule.insert("weight".to_string(), plist::Value::Integer(weight.into()));
+        }
+        plist::Value::Dictionary(rule)
+    };
+    let mut rules = BTreeMap::from([
+        ("^.*".to_string(), plist::Value::Boolean(true)),
+        ("^.*\\.lproj/".to_string(), rule(Some(1000), &["optional"])),
+        ("^.*\\.lproj/locversion.plist$".to_string(), rule(Some(1100), &["omit"])),
+        ("^Base\\.lproj/".to_string(), rule(Some(1010), &[])),
+    ]);
+    if !version2 {
+        rules.insert("^version.plist$".to_string(), plist::Value::Boolean(true));
+    } else {
+        rules.insert(".*\\.dSYM($|/)".to_string(), rule(Some(11), &[]));
+        rules.insert("^(.*/)?\\.DS_Store$".to_string(), rule(Some(2000), &["omit"]));
+        rules.insert("^Info\\.plist$".to_string(), rule(Some(20), &["omit"]));
+        rules.insert("^PkgInfo$".to_string(), rule(Some(20), &["omit"]));
+        rules.insert("^embedded\\.provisionprofile$".to_string(), rule(Some(20), &[]));
+        rules.insert("^version\\.plist$".to_string(), rule(Some(20), &[]));
+    }
+    plist::Value::Dictionary(rules.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_signature::test_support::p12_identity;
+    use crate::macho::test_support::thin_executable;
+    use crate::macho::CPU_TYPE_ARM64;
+    use std::io::Write;
+
+    fn info_plist(executable: &str, identifier: &str) -> Vec<u8> {
+        let info = plist::Dictionary::from_iter([
+            ("CFBundleExecutable".to_string(), plist::Value::String(executable.to_string())),
+            ("CFBundleIdentifier".to_string(), plist::Value::String(identifier.to_string())),
+        ]);
+        let mut bytes = Vec::new();
+        plist::Value::Dictionary(info).to_writer_xml(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn zip_payload(root: &Path, ipa: &Path) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(ipa).unwrap());
+        for entry in WalkDir::new(root).sort_by_file_name().into_iter().map(Result::unwrap).filter(|e| e.file_type().is_file()) {
+            let name = entry.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(&fs::read(entry.path()).unwrap()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_signing_checks_files_and_opens_the_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let p12 = dir.path().join("dist.p12");
+        fs::write(&p12, p12_identity("secret")).unwrap();
+        let password = dir.path().join("password.txt");
+        fs::write(&password, "secret\n").unwrap();
+        let signing = BundleSigning { identity_path: p12.display().to_string(), password_file: password.display().to_string(), ..Default::default() };
+        assert!(signing.is_enabled() && signing.validate().is_ok());
+        assert!(!BundleSigning::default().is_enabled());
+        assert_eq!(signing.identity().unwrap().team_id().as_deref(), Some("ABCDE12345"));
+        fs::write(&password, "wrong").unwrap();
+        assert!(signing.validate().is_ok());
+        assert!(signing.identity().err().unwrap().contains("check the password"));
+
+        let missing_profile = BundleSigning { provisioning_profile: "nope.mobileprovision".to_string(), ..signing.clone() };
+        assert!(missing_profile.validate().unwrap_err().contains("Provisioning profile not found"));
+        let entitlements = dir.path().join("Shop.entitlements");
+        fs::write(&entitlements, b"not a plist").unwrap();
+        let bad_entitlements = BundleSigning { entitlements: entitlements.display().to_string(), ..signing.clone() };
+        assert!(bad_entitlements.validate().unwrap_err().contains("Invalid entitlements plist"));
+        let cer = dir.path().join("dist.cer");
+        fs::write(&cer, b"certificate").unwrap();
+        assert!(BundleSigning { identity_path: cer.display().to_string(), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_signed_bundle_passes_the_seal_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        let app = root.join("Payload/Shop.app");
+        let framework = app.join("Frameworks/Cart.framework");
+        fs::create_dir_all(&framework).unwrap();
+        fs::create_dir_all(app.join("en.lproj")).unwrap();
+        fs::write(app.join("Info.plist"), info_plist("Shop", "com.example.shop")).unwrap();
+        fs::write(app.join("Shop"), thin_executable(CPU_TYPE_ARM64)).unwrap();
+        fs::write(app.join("Assets.car"), b"assets").unwrap();
+        fs::write(app.join("en.lproj/Main.strings"), b"\"Buy\" = \"Buy\";").unwrap();
+        fs::write(app.join("Frameworks/libswiftCore.dylib"), thin_executable(CPU_TYPE_ARM64)).unwrap();
+        fs::write(framework.join("Info.plist"), info_plist("Cart", "com.example.cart")).unwrap();
+        fs::write(framework.join("Cart"), thin_executable(CPU_TYPE_ARM64)).unwrap();
+        let p12 = dir.path().join("dev.p12");
+        fs::write(&p12, p12_identity("")).unwrap();
+        let entitlements = dir.path().join("Shop.entitlements");
+        fs::write(&entitlements, b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><plist version=\"1.0\"><dict><key>get-task-allow</key><true/></dict></plist>").unwrap();
+        let signing = BundleSigning { identity_path: p12.display().to_string(), entitlements: entitlements.display().to_string(), ..Default::default() };
+        signing.sign_bundle(&app).unwrap();
+
+        let ipa = dir.path().join("Shop.ipa");
+        zip_payload(&root, &ipa);
+        let report = codesign::verify_ipa_signature(&ipa).unwrap();
+        let main = report.main_binary().unwrap();
+        assert_eq!((main.identifier.as_deref(), main.team_id.as_deref()), (Some("com.example.shop"), Some("ABCDE12345")));
+        assert_eq!(main.authority.as_deref(), Some("Apple Development: Test (ABCDE12345)"));
+        assert!(main.entitlements_xml.as_deref().unwrap().contains("get-task-allow"));
+        assert!(report.binaries.iter().all(|b| b.signed && b.problem.is_none()), "{:?}", report.binaries);
+        assert!(report.seal.as_ref().unwrap().is_intact(), "{:?}", report.seal);
+
+        let seal = crate::info_plist::read_dictionary(&app.join("_CodeSignature/CodeResources")).unwrap();
+        let files2 = seal.get("files2").unwrap().as_dictionary().unwrap();
+        let nested = files2.get("Frameworks/Cart.framework").unwrap().as_dictionary().unwrap();
+        assert!(nested.get("requirement").unwrap().as_string().unwrap().starts_with("identifier \"com.example.cart\""));
+        assert_eq!(files2.get("en.lproj/Main.strings").unwrap().as_dictionary().unwrap().get("optional"), Some(&plist::Value::Boolean(true)));
+        assert!(!files2.contains_key("Shop") && !files2.contains_key("Info.plist"));
+
+        fs::write(app.join("Assets.car"), b"changed").unwrap();
+        zip_payload(&root, &ipa);
+        assert_eq!(codesign::check_ipa_seal(&ipa).unwrap().unwrap().modified, ["Assets.car"]);
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppConfig;
+
+/// Output names can contain this to include the build's channel, e.g. `Shop-{channel}.ipa`.
+/// Builds without a channel replace it with nothing.
+pub const CHANNEL_PLACEHOLDER: &str = "{channel}";
+
+/// A release channel such as `beta`, picked when a build starts. It is part of the output name
+/// through [`CHANNEL_PLACEHOLDER`], recorded in the history and metrics, and decides which of the
+/// app's upload destinations the build goes to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BuildChannel {
+    pub name: String,
+    /// Upload to App Store Connect, when the app has an API key.
+    pub app_store_connect: bool,
+    /// Upload to the share service in the settings, when one is chosen.
+    pub share: bool,
+    /// Upload to the app's S3 bucket, when it has one.
+    pub object_storage: bool,
+}
+
+impl BuildChannel {
+    /// `alpha` for quick tester links, `beta` also kept in the bucket, and `release` for
+    /// App Store Connect.
+    pub fn defaults() -> Vec<BuildChannel> {
+        let channel = |name: &str, app_store_connect, share, object_storage| BuildChannel { name: name.to_string(), app_store_connect, share, object_storage };
+        vec![channel("alpha", false, true, false), channel("beta", false, true, true), channel("release", true, false, true)]
+    }
+
+    /// Makes `config`'s builds go to this channel's destinations, among those `config` has set
+    /// up, instead of following its own "after every build" settings.
+    pub fn route(&self, config: &mut AppConfig) {
+        config.app_store_connect.upload_after_build = self.app_store_connect;
+        config.object_storage.enabled &= self.object_storage;
+    }
+
+    /// Short list of the destinations for the UI, e.g. `App Store Connect, bucket`.
+    pub fn destinations(&self) -> String {
+        let names = [(self.app_store_connect, "App Store Connect"), (self.share, "share service"), (self.object_storage, "bucket")];
+        let chosen: Vec<&str> = names.iter().filter(|(on, _)| *on).map(|(_, name)| *name).collect();
+        if chosen.is_empty() { "no uploads".to_string() } else { chosen.join(", ") }
+    }
+}
+
+/// The channel called `name`, ignoring case.
+pub fn find<'a>(channels: &'a [BuildChannel], name: &str) -> Option<&'a BuildChannel> {
+    let name = name.trim();
+    channels.iter().find(|c| c.name.trim().eq_ignore_ascii_case(name))
+}
+
+/// Channel names end up in file names, so they are limited to letters, digits, `-`, `_` and `.`.
+pub fn validate(channels: &[BuildChannel]) -> Result<(), String> {
+    for (i, channel) in channels.iter().enumerate() {
+        let name = channel.name.trim();
+        if name.is_empty() {
+            return Err("Enter a name for every channel, or remove it.".to_string());
+        }
+        if let Some(c) = name.chars().find(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+            return Err(format!("Channel '{}' contains {:?}; use letters, digits, '-', '_' and '.'", name, c));
+        }
+        if channels[..i].iter().any(|other| other.name.trim().eq_ignore_ascii_case(name)) {
+            return Err(format!("There are two channels called '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// `template` with [`CHANNEL_PLACEHOLDER`] replaced by `channel`.
+pub fn expand_name(template: &str, channel: &str) -> String {
+    template.replace(CHANNEL_PLACEHOLDER, channel.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channels_route_uploads_and_name_outputs() {
+        let channels = BuildChannel::defaults();
+        assert!(validate(&channels).is_ok());
+        let release = find(&channels, "Release").unwrap();
+        assert_eq!(release.destinations(), "App Store Connect, bucket");
+
+        let mut config = AppConfig { output_ipa_name: "Shop-{channel}.ipa".to_string(), ..Default::default() };
+        config.app_store_connect.upload_after_build = false;
+        config.object_storage.enabled = true;
+        release.route(&mut config);
+        assert!(config.app_store_connect.upload_after_build && config.object_storage.enabled);
+        find(&channels, "alpha").unwrap().route(&mut config);
+        assert!(!config.app_store_connect.upload_after_build && !config.object_storage.enabled);
+        // A channel can't upload to a bucket the app doesn't have.
+        release.route(&mut config);
+        assert!(!config.object_storage.enabled);
+
+        assert_eq!(expand_name(&config.output_ipa_name, "beta"), "Shop-beta.ipa");
+        assert_eq!(expand_name(&config.output_ipa_name, ""), "Shop-.ipa");
+
+        let mut invalid = channels.clone();
+        invalid.push(BuildChannel { name: "BETA".to_string(), ..Default::default() });
+        assert!(validate(&invalid).unwrap_err().contains("two channels"));
+        assert!(validate(&[BuildChannel { name: "beta/2".to_string(), ..Default::default() }]).is_err());
+    }
+}
@@ -0,0 +1,615 @@
+//! Embedded code signatures written without Apple's tools: the code directory, designated
+//! requirement, entitlements and CMS signature `codesign` puts behind `LC_CODE_SIGNATURE`,
+//! signed with a developer identity exported from Keychain Access.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cms::cert::x509::attr::Attribute;
+use cms::cert::x509::der::asn1::{ObjectIdentifier, OctetString, SetOfVec, UtcTime};
+use cms::cert::x509::der::{Any, Decode, Encode, Tag};
+use cms::cert::x509::spki::AlgorithmIdentifierOwned;
+use cms::cert::x509::Certificate;
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo, SignerInfos};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, RsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, RSA_PKCS1_SHA256};
+use rustls_pki_types::pem::{PemObject, SectionKind};
+use sha2::{Digest, Sha256};
+
+use crate::macho::{self, CSMAGIC_BLOBWRAPPER, CSMAGIC_CODEDIRECTORY, CSMAGIC_EMBEDDED_ENTITLEMENTS, CSMAGIC_EMBEDDED_SIGNATURE};
+
+const CSMAGIC_REQUIREMENT: u32 = 0xFADE0C00;
+const CSMAGIC_REQUIREMENTS: u32 = 0xFADE0C01;
+const CSMAGIC_EMBEDDED_DER_ENTITLEMENTS: u32 = 0xFADE7172;
+
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_INFOSLOT: usize = 1;
+const CSSLOT_REQUIREMENTS: u32 = 2;
+const CSSLOT_RESOURCEDIR: usize = 3;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CSSLOT_DER_ENTITLEMENTS: u32 = 7;
+const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+const SPECIAL_SLOTS: usize = 7;
+
+const CS_EXECSEG_MAIN_BINARY: u64 = 0x1;
+const CS_EXECSEG_ALLOW_UNSIGNED: u64 = 0x10;
+const PAGE_SIZE: usize = 4096;
+
+const OID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+const OID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const OID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const OID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const OID_SIGNING_TIME: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.5");
+const OID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const OID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const OID_ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+/// Apple's signed attributes listing the code directory hashes, as a plist and as DER.
+const OID_APPLE_CDHASHES: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113635.100.9.1");
+const OID_APPLE_CDHASHES_DER: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113635.100.9.2");
+const OID_COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+const OID_ORGANIZATIONAL_UNIT: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.11");
+/// Marks Apple's Worldwide Developer Relations intermediate certificates, DER encoded.
+const APPLE_WWDR_MARKER: [u8; 10] = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x63, 0x64, 0x06, 0x02, 0x01];
+
+enum SigningKey {
+    Rsa(RsaKeyPair),
+    EcdsaP256(EcdsaKeyPair),
+}
+
+impl SigningKey {
+    fn from_pkcs8(der: &[u8]) -> Result<Self, String> {
+        if let Ok(key) = RsaKeyPair::from_pkcs8(der) {
+            return Ok(SigningKey::Rsa(key));
+        }
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, der, &SystemRandom::new())
+            .map(SigningKey::EcdsaP256)
+            .map_err(|_| "The private key is neither RSA nor ECDSA P-256".to_string())
+    }
+
+    fn public_key(&self) -> &[u8] {
+        match self {
+            SigningKey::Rsa(key) => key.public_key().as_ref(),
+            SigningKey::EcdsaP256(key) => key.public_key().as_ref(),
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        let rng = SystemRandom::new();
+        match self {
+            SigningKey::Rsa(key) => {
+                let mut signature = vec![0; key.public().modulus_len()];
+                key.sign(&RSA_PKCS1_SHA256, &rng, message, &mut signature).map_err(|_| "RSA signing failed".to_string())?;
+                Ok(signature)
+            }
+            SigningKey::EcdsaP256(key) => key.sign(&rng, message).map(|s| s.as_ref().to_vec()).map_err(|_| "ECDSA signing failed".to_string()),
+        }
+    }
+
+    fn algorithm(&self) -> AlgorithmIdentifierOwned {
+        match self {
+            SigningKey::Rsa(_) => AlgorithmIdentifierOwned { oid: OID_RSA_ENCRYPTION, parameters: Some(Any::null()) },
+            SigningKey::EcdsaP256(_) => AlgorithmIdentifierOwned { oid: OID_ECDSA_WITH_SHA256, parameters: None },
+        }
+    }
+}
+
+/// A signing certificate with its private key and the intermediate certificates up to Apple's
+/// root, as exported from Keychain Access.
+pub struct SigningIdentity {
+    certificate: Certificate,
+    chain: Vec<Certificate>,
+    key: SigningKey,
+}
+
+impl SigningIdentity {
+    /// Opens a PKCS#12 (`.p12`/`.pfx`) file.
+    pub fn from_p12(bytes: &[u8], password: &str) -> Result<Self, String> {
+        let keystore = p12_keystore::KeyStore::from_pkcs12(bytes, password).map_err(|e| format!("Failed to open the identity, check the password: {}", e))?;
+        let (_, key_chain) = keystore.private_key_chain().ok_or_else(|| "The identity holds no private key".to_string())?;
+        let certificates = key_chain
+            .chain()
+            .iter()
+            .map(|cert| Certificate::from_der(cert.as_der()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid certificate in the identity: {}", e))?;
+        Self::new(SigningKey::from_pkcs8(key_chain.key())?, certificates)
+    }
+
+    /// Reads a PEM file holding the certificate, its intermediates and an unencrypted PKCS#8 or
+    /// PKCS#1 private key.
+    pub fn from_pem(bytes: &[u8]) -> Result<Self, String> {
+        let mut key = None;
+        let mut certificates = Vec::new();
+        for section in <(SectionKind, Vec<u8>)>::pem_slice_iter(bytes) {
+            let (kind, der) = section.map_err(|e| format!("Invalid PEM file: {}", e))?;
+            match kind {
+                SectionKind::Certificate => certificates.push(Certificate::from_der(&der).map_err(|e| format!("Invalid certificate in the identity: {}", e))?),
+                SectionKind::PrivateKey => key = Some(SigningKey::from_pkcs8(&der)?),
+                SectionKind::RsaPrivateKey => key = Some(SigningKey::Rsa(RsaKeyPair::from_der(&der).map_err(|e| format!("Invalid RSA private key: {}", e))?)),
+                _ => {}
+            }
+        }
+        Self::new(key.ok_or_else(|| "The identity holds no unencrypted private key".to_string())?, certificates)
+    }
+
+    fn new(key: SigningKey, mut certificates: Vec<Certificate>) -> Result<Self, String> {
+        let leaf = certificates
+            .iter()
+            .position(|cert| cert.tbs_certificate.subject_public_key_info.subject_public_key.raw_bytes() == key.public_key())
+            .ok_or_else(|| "None of the identity's certificates belongs to its private key".to_string())?;
+        let certificate = certificates.remove(leaf);
+        Ok(SigningIdentity { certificate, chain: certificates, key })
+    }
+
+    /// The certificate's common name, e.g. `Apple Distribution: Example Corp (ABCDE12345)`.
+    pub fn common_name(&self) -> Option<String> {
+        self.subject_field(OID_COMMON_NAME)
+    }
+
+    /// The team ID Apple puts in the certificate's organizational unit.
+    pub fn team_id(&self) -> Option<String> {
+        self.subject_field(OID_ORGANIZATIONAL_UNIT)
+    }
+
+    fn subject_field(&self, oid: ObjectIdentifier) -> Option<String> {
+        self.certificate
+            .tbs_certificate
+            .subject
+            .0
+            .iter()
+            .flat_map(|rdn| rdn.0.iter())
+            .find(|atv| atv.oid == oid)
+            .map(|atv| String::from_utf8_lossy(atv.value.value()).into_owned())
+    }
+
+    /// The designated requirement in `codesign`'s text form, as sealed for nested code.
+    pub fn designated_requirement(&self, identifier: &str) -> String {
+        format!(
+            "identifier \"{}\" and anchor apple generic and certificate leaf[subject.CN] = \"{}\" and certificate 1[field.1.2.840.113635.100.6.2.1] /* exists */",
+            identifier,
+            self.common_name().unwrap_or_default()
+        )
+    }
+}
+
+/// What the code directory of a binary covers besides its pages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodeSignatureRequest<'a> {
+    pub identifier: &'a str,
+    /// Entitlements plist (XML or binary); `None` for binaries without entitlements.
+    pub entitlements: Option<&'a [u8]>,
+    /// The bundle's `Info.plist`, for the main executable of a bundle.
+    pub info_plist: Option<&'a [u8]>,
+    /// The bundle's `_CodeSignature/CodeResources`, for the main executable of a bundle.
+    pub code_resources: Option<&'a [u8]>,
+}
+
+/// A Mach-O file with a new signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedBinary {
+    pub bytes: Vec<u8>,
+    /// SHA-256 of the first slice's code directory, which sealing bundles refer to it by.
+    pub cdhash: [u8; 32],
+}
+
+/// Signs every slice of a thin or fat Mach-O file, replacing any signature it has.
+pub fn sign_macho(bytes: &[u8], identity: &SigningIdentity, request: &CodeSignatureRequest) -> Result<SignedBinary, String> {
+    let entitlements = request.entitlements.map(crate::info_plist::parse_dictionary).transpose()?;
+    let entitlement_blobs = match (&entitlements, request.entitlements) {
+        (Some(dictionary), Some(raw)) => {
+            // The XML blob holds the file as it is, converted when it's a binary plist.
+            let mut xml = raw.to_vec();
+            if !raw.starts_with(b"<?xml") {
+                xml.clear();
+                plist::Value::Dictionary(dictionary.clone()).to_writer_xml(&mut xml).map_err(|e| e.to_string())?;
+            }
+            Some((blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &xml), blob(CSMAGIC_EMBEDDED_DER_ENTITLEMENTS, &der_entitlements(dictionary)?)))
+        }
+        _ => None,
+    };
+    let allow_unsigned = entitlements.as_ref().and_then(|e| e.get("get-task-allow")?.as_boolean()).unwrap_or(false);
+    let requirements = requirements_blob(request.identifier, identity);
+
+    let mut special = [[0u8; 32]; SPECIAL_SLOTS];
+    let mut hash_slot = |slot: usize, data: Option<&[u8]>| {
+        if let Some(data) = data {
+            special[slot - 1] = Sha256::digest(data).into();
+        }
+    };
+    hash_slot(CSSLOT_INFOSLOT, request.info_plist);
+    hash_slot(CSSLOT_REQUIREMENTS as usize, Some(&requirements));
+    hash_slot(CSSLOT_RESOURCEDIR, request.code_resources);
+    hash_slot(CSSLOT_ENTITLEMENTS as usize, entitlement_blobs.as_ref().map(|(xml, _)| xml.as_slice()));
+    hash_slot(CSSLOT_DER_ENTITLEMENTS as usize, entitlement_blobs.as_ref().map(|(_, der)| der.as_slice()));
+
+    let team_id = identity.team_id();
+    let signing_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut first_cdhash = None;
+    let bytes = macho::rewrite_slices(bytes, |slice| {
+        let fixed_size = 12 + 5 * 8 + requirements.len() + entitlement_blobs.as_ref().map_or(0, |(xml, der)| xml.len() + der.len());
+        // Certificates, signed attributes and a signature of up to 4096 bits.
+        let cms_size = 8 + [&identity.certificate].into_iter().chain(&identity.chain).map(|c| c.to_der().map_or(0, |d| d.len())).sum::<usize>() + 2048;
+        let mut reserved = 0;
+        let space = macho::reserve_code_signature(slice, |code_limit| {
+            reserved = (code_directory_size(request.identifier, team_id.as_deref(), code_limit) + fixed_size + cms_size).next_multiple_of(16);
+            reserved
+        })
+        .map_err(|e| e.to_string())?;
+        let mut exec_seg_flags = if space.file_type == macho::MH_EXECUTE { CS_EXECSEG_MAIN_BINARY } else { 0 };
+        if allow_unsigned {
+            exec_seg_flags |= CS_EXECSEG_ALLOW_UNSIGNED;
+        }
+        let code_directory = code_directory(&space, request.identifier, team_id.as_deref(), &special, exec_seg_flags);
+        let cdhash: [u8; 32] = Sha256::digest(&code_directory).into();
+        first_cdhash.get_or_insert(cdhash);
+        let cms = cms_signature(&cdhash, identity, signing_time)?;
+
+        let mut blobs = vec![(CSSLOT_CODEDIRECTORY, code_directory), (CSSLOT_REQUIREMENTS, requirements.clone())];
+        if let Some((xml, der)) = &entitlement_blobs {
+            blobs.push((CSSLOT_ENTITLEMENTS, xml.clone()));
+            blobs.push((CSSLOT_DER_ENTITLEMENTS, der.clone()));
+        }
+        blobs.push((CSSLOT_SIGNATURESLOT, blob(CSMAGIC_BLOBWRAPPER, &cms)));
+        let signature = super_blob(&blobs);
+        if signature.len() > reserved {
+            return Err(format!("Code signature of {} bytes doesn't fit the {} bytes reserved for it", signature.len(), reserved));
+        }
+        let mut image = space.image;
+        image.extend_from_slice(&signature);
+        image.resize(image.len() + reserved - signature.len(), 0);
+        Ok(image)
+    })?;
+    Ok(SignedBinary { bytes, cdhash: first_cdhash.unwrap_or_default() })
+}
+
+fn blob(magic: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + payload.len());
+    out.extend_from_slice(&magic.to_be_bytes());
+    out.extend_from_slice(&((8 + payload.len()) as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+fn super_blob(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let header = 12 + 8 * blobs.len();
+    let length = header + blobs.iter().map(|(_, b)| b.len()).sum::<usize>();
+    let mut out = Vec::with_capacity(length);
+    for v in [CSMAGIC_EMBEDDED_SIGNATURE, length as u32, blobs.len() as u32] {
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+    let mut offset = header;
+    for (slot, blob) in blobs {
+        out.extend_from_slice(&slot.to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        offset += blob.len();
+    }
+    for (_, blob) in blobs {
+        out.extend_from_slice(blob);
+    }
+    out
+}
+
+/// Length of a version 0x20400 code directory: an 88-byte header, the identifier and team ID,
+/// then a SHA-256 hash per special slot and per 4 KB page.
+fn code_directory_size(identifier: &str, team_id: Option<&str>, code_limit: usize) -> usize {
+    88 + identifier.len() + 1 + team_id.map_or(0, |t| t.len() + 1) + 32 * (SPECIAL_SLOTS + code_limit.div_ceil(PAGE_SIZE))
+}
+
+fn code_directory(space: &macho::SignatureSpace, identifier: &str, team_id: Option<&str>, special: &[[u8; 32]; SPECIAL_SLOTS], exec_seg_flags: u64) -> Vec<u8> {
+    let code_limit = space.image.len();
+    let ident_offset = 88;
+    let team_offset = ident_offset + identifier.len() + 1;
+    let hash_offset = team_offset + team_id.map_or(0, |t| t.len() + 1) + 32 * SPECIAL_SLOTS;
+    let code_slots = code_limit.div_ceil(PAGE_SIZE);
+
+    let mut cd = Vec::with_capacity(code_directory_size(identifier, team_id, code_limit));
+    let length = code_directory_size(identifier, team_id, code_limit) as u32;
+    for v in [CSMAGIC_CODEDIRECTORY, length, 0x20400, 0, hash_offset as u32, ident_offset as u32, SPECIAL_SLOTS as u32, code_slots as u32, code_limit as u32] {
+        cd.extend_from_slice(&v.to_be_bytes());
+    }
+    // hashSize, hashType (SHA-256), platform, pageSize (log2), then spare2 and scatterOffset.
+    cd.extend_from_slice(&[32, 2, 0, 12]);
+    cd.extend_from_slice(&[0; 8]);
+    cd.extend_from_slice(&(if team_id.is_some() { team_offset as u32 } else { 0 }).to_be_bytes());
+    cd.extend_from_slice(&[0; 4]);
+    for v in [0, space.text_segment.0, space.text_segment.1, exec_seg_flags] {
+        cd.extend_from_slice(&v.to_be_bytes());
+    }
+    cd.extend_from_slice(identifier.as_bytes());
+    cd.push(0);
+    if let Some(team) = team_id {
+        cd.extend_from_slice(team.as_bytes());
+        cd.push(0);
+    }
+    // Special slots are numbered backwards from the code slots.
+    for hash in special.iter().rev() {
+        cd.extend_from_slice(hash);
+    }
+    for page in space.image.chunks(PAGE_SIZE) {
+        cd.extend_from_slice(&Sha256::digest(page));
+    }
+    cd
+}
+
+/// The designated requirement `codesign` derives for an Apple-issued certificate, compiled to
+/// the binary requirement language.
+fn requirements_blob(identifier: &str, identity: &SigningIdentity) -> Vec<u8> {
+    const OP_IDENT: u32 = 2;
+    const OP_AND: u32 = 6;
+    const OP_CERT_FIELD: u32 = 11;
+    const OP_CERT_GENERIC: u32 = 14;
+    const OP_APPLE_GENERIC_ANCHOR: u32 = 15;
+    const MATCH_EXISTS: u32 = 0;
+    const MATCH_EQUAL: u32 = 1;
+
+    let mut expr = Vec::new();
+    let word = |expr: &mut Vec<u8>, v: u32| expr.extend_from_slice(&v.to_be_bytes());
+    let data = |expr: &mut Vec<u8>, bytes: &[u8]| {
+        expr.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        expr.extend_from_slice(bytes);
+        expr.resize(expr.len().next_multiple_of(4), 0);
+    };
+    word(&mut expr, OP_AND);
+    word(&mut expr, OP_IDENT);
+    data(&mut expr, identifier.as_bytes());
+    word(&mut expr, OP_AND);
+    word(&mut expr, OP_APPLE_GENERIC_ANCHOR);
+    word(&mut expr, OP_AND);
+    word(&mut expr, OP_CERT_FIELD);
+    word(&mut expr, 0);
+    data(&mut expr, b"subject.CN");
+    word(&mut expr, MATCH_EQUAL);
+    data(&mut expr, identity.common_name().unwrap_or_default().as_bytes());
+    word(&mut expr, OP_CERT_GENERIC);
+    word(&mut expr, 1);
+    data(&mut expr, &APPLE_WWDR_MARKER);
+    word(&mut expr, MATCH_EXISTS);
+
+    let mut requirement = 1u32.to_be_bytes().to_vec();
+    requirement.extend_from_slice(&expr);
+    let requirement = blob(CSMAGIC_REQUIREMENT, &requirement);
+    // One designated requirement (type 3) right after the 20-byte header.
+    let mut payload = Vec::new();
+    for v in [1u32, 3, 20] {
+        payload.extend_from_slice(&v.to_be_bytes());
+    }
+    payload.extend_from_slice(&requirement);
+    blob(CSMAGIC_REQUIREMENTS, &payload)
+}
+
+/// Entitlements in the DER form iOS 15 and later read: `[APPLICATION 16] { INTEGER 1,
+/// [CONTEXT 16] { SEQUENCE { key, value } ... } }` with keys sorted.
+fn der_entitlements(entitlements: &plist::Dictionary) -> Result<Vec<u8>, String> {
+    let mut body = der(0x02, &[1]);
+    body.extend(der_value(&plist::Value::Dictionary(entitlements.clone()))?);
+    Ok(der(0x70, &body))
+}
+
+fn der_value(value: &plist::Value) -> Result<Vec<u8>, String> {
+    Ok(match value {
+        plist::Value::Boolean(b) => der(0x01, &[if *b { 0xFF } else { 0 }]),
+        plist::Value::String(s) => der(0x0C, s.as_bytes()),
+        plist::Value::Integer(i) => {
+            let value = i.as_signed().ok_or_else(|| format!("Entitlement value {} is out of range", i))?;
+            let bytes = value.to_be_bytes();
+            // Minimal two's complement: drop leading bytes that only repeat the sign bit.
+            let skip = (0..7).take_while(|&n| (bytes[n] == 0 && bytes[n + 1] < 0x80) || (bytes[n] == 0xFF && bytes[n + 1] >= 0x80)).count();
+            der(0x02, &bytes[skip..])
+        }
+        plist::Value::Array(items) => der(0x30, &items.iter().map(der_value).collect::<Result<Vec<_>, _>>()?.concat()),
+        plist::Value::Dictionary(dictionary) => {
+            let mut entries: Vec<(&String, &plist::Value)> = dictionary.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let mut body = Vec::new();
+            for (key, value) in entries {
+                body.extend(der(0x30, &[der(0x0C, key.as_bytes()), der_value(value)?].concat()));
+            }
+            der(0xB0, &body)
+        }
+        _ => return Err("Entitlements may only hold booleans, strings, integers, arrays and dictionaries".to_string()),
+    })
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let length = (content.len() as u64).to_be_bytes();
+        let skip = length.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (8 - skip) as u8);
+        out.extend_from_slice(&length[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// A detached CMS signature over the code directory, with the signed attributes `codesign`
+/// adds: the code directory hashes as a plist and as DER.
+fn cms_signature(cdhash: &[u8; 32], identity: &SigningIdentity, signing_time: Duration) -> Result<Vec<u8>, String> {
+    let encode = |e: cms::cert::x509::der::Error| format!("Failed to encode the CMS signature: {}", e);
+    let attribute = |oid: ObjectIdentifier, value: Any| -> Result<Attribute, String> {
+        Ok(Attribute { oid, values: SetOfVec::try_from(vec![value]).map_err(encode)? })
+    };
+    let mut cdhashes = Vec::new();
+    let plist = plist::Value::Dictionary(plist::Dictionary::from_iter([(
+        "cdhashes".to_string(),
+        plist::Value::Array(vec![plist::Value::Data(cdhash[..20].to_vec())]),
+    )]));
+    plist.to_writer_xml(&mut cdhashes).map_err(|e| e.to_string())?;
+    let hash_entry = [OID_SHA256.to_der().map_err(encode)?, OctetString::new(cdhash.to_vec()).map_err(encode)?.to_der().map_err(encode)?].concat();
+    let time = UtcTime::from_unix_duration(Duration::from_secs(signing_time.as_secs())).map_err(encode)?;
+
+    let signed_attrs = SetOfVec::try_from(vec![
+        attribute(OID_CONTENT_TYPE, Any::encode_from(&OID_DATA).map_err(encode)?)?,
+        attribute(OID_SIGNING_TIME, Any::encode_from(&time).map_err(encode)?)?,
+        attribute(OID_MESSAGE_DIGEST, Any::encode_from(&OctetString::new(cdhash.to_vec()).map_err(encode)?).map_err(encode)?)?,
+        attribute(OID_APPLE_CDHASHES, Any::encode_from(&OctetString::new(cdhashes).map_err(encode)?).map_err(encode)?)?,
+        attribute(OID_APPLE_CDHASHES_DER, Any::new(Tag::Sequence, hash_entry).map_err(encode)?)?,
+    ])
+    .map_err(encode)?;
+    let signature = identity.key.sign(&signed_attrs.to_der().map_err(encode)?)?;
+
+    let sha256 = AlgorithmIdentifierOwned { oid: OID_SHA256, parameters: None };
+    let signer = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: identity.certificate.tbs_certificate.issuer.clone(),
+            serial_number: identity.certificate.tbs_certificate.serial_number.clone(),
+        }),
+        digest_alg: sha256.clone(),
+        signed_attrs: Some(signed_attrs),
+        signature_algorithm: identity.key.algorithm(),
+        signature: OctetString::new(signature).map_err(encode)?,
+        unsigned_attrs: None,
+    };
+    let certificates = [&identity.certificate].into_iter().chain(&identity.chain).map(|c| CertificateChoices::Certificate(c.clone())).collect::<Vec<_>>();
+    let signed_data = SignedData {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::try_from(vec![sha256]).map_err(encode)?,
+        encap_content_info: EncapsulatedContentInfo { econtent_type: OID_DATA, econtent: None },
+        certificates: Some(CertificateSet(SetOfVec::try_from(certificates).map_err(encode)?)),
+        crls: None,
+        signer_infos: SignerInfos(SetOfVec::try_from(vec![signer]).map_err(encode)?),
+    };
+    ContentInfo { content_type: OID_SIGNED_DATA, content: Any::encode_from(&signed_data).map_err(encode)? }
+        .to_der()
+        .map_err(encode)
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::str::FromStr;
+
+    use cms::cert::x509::der::asn1::BitString;
+    use cms::cert::x509::name::RdnSequence;
+    use cms::cert::x509::serial_number::SerialNumber;
+    use cms::cert::x509::spki::SubjectPublicKeyInfoOwned;
+    use cms::cert::x509::time::{Time, Validity};
+    use cms::cert::x509::{TbsCertificate, Version};
+
+    use super::*;
+
+    /// A `.p12` with a self-signed P-256 certificate named like an Apple development one.
+    pub(crate) fn p12_identity(password: &str) -> Vec<u8> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+        let name = RdnSequence::from_str("CN=Apple Development: Test (ABCDE12345),OU=ABCDE12345").unwrap();
+        let time = |secs| Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(secs)).unwrap());
+        let curve = Any::encode_from(&ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7")).unwrap();
+        let tbs_certificate = TbsCertificate {
+            version: Version::V3,
+            serial_number: SerialNumber::new(&[1]).unwrap(),
+            signature: SigningKey::EcdsaP256(EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap()).algorithm(),
+            issuer: name.clone(),
+            validity: Validity { not_before: time(1_700_000_000), not_after: time(2_400_000_000) },
+            subject: name,
+            subject_public_key_info: SubjectPublicKeyInfoOwned {
+                algorithm: AlgorithmIdentifierOwned { oid: ObjectIdentifier::new_unwrap("1.2.840.10045.2.1"), parameters: Some(curve) },
+                subject_public_key: BitString::from_bytes(key.public_key().as_ref()).unwrap(),
+            },
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: None,
+        };
+        let signature = key.sign(&rng, &tbs_certificate.to_der().unwrap()).unwrap();
+        let certificate = Certificate {
+            signature_algorithm: tbs_certificate.signature.clone(),
+            tbs_certificate,
+            signature: BitString::from_bytes(signature.as_ref()).unwrap(),
+        };
+        let certificate = p12_keystore::Certificate::from_der(&certificate.to_der().unwrap()).unwrap();
+        let mut keystore = p12_keystore::KeyStore::new();
+        let chain = p12_keystore::PrivateKeyChain::new(pkcs8.as_ref(), [1], [certificate]);
+        keystore.add_entry("test", p12_keystore::KeyStoreEntry::PrivateKeyChain(chain));
+        keystore.writer(password).write().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::p12_identity;
+    use super::*;
+    use crate::macho::test_support::thin_executable;
+    use crate::macho::CPU_TYPE_ARM64;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+
+    /// The superblob's blobs by slot.
+    fn signature_blobs(image: &[u8]) -> (usize, Vec<(u32, Vec<u8>)>) {
+        let lc = macho::load_commands(image).unwrap().into_iter().find(|c| c.cmd == macho::LC_CODE_SIGNATURE).unwrap();
+        let dataoff = macho::read_u32_le(image, lc.offset + 8).unwrap() as usize;
+        let superblob = &image[dataoff..];
+        let count = macho::read_u32_be(superblob, 8).unwrap() as usize;
+        let blobs = (0..count)
+            .map(|i| {
+                let slot = macho::read_u32_be(superblob, 12 + i * 8).unwrap();
+                let offset = macho::read_u32_be(superblob, 16 + i * 8).unwrap() as usize;
+                let length = macho::read_u32_be(superblob, offset + 4).unwrap() as usize;
+                (slot, superblob[offset..offset + length].to_vec())
+            })
+            .collect();
+        (dataoff, blobs)
+    }
+
+    #[test]
+    fn test_signature_covers_the_pages_and_verifies_with_the_certificate() {
+        let identity = SigningIdentity::from_p12(&p12_identity("pw"), "pw").unwrap();
+        assert_eq!(identity.common_name().as_deref(), Some("Apple Development: Test (ABCDE12345)"));
+        assert!(SigningIdentity::from_p12(&p12_identity("pw"), "nope").is_err());
+        let entitlements = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><plist version=\"1.0\"><dict><key>get-task-allow</key><true/></dict></plist>";
+        let request = CodeSignatureRequest { identifier: "com.example.shop", entitlements: Some(entitlements), info_plist: Some(b"<plist/>"), ..Default::default() };
+        let unsigned = thin_executable(CPU_TYPE_ARM64);
+        let signed = sign_macho(&unsigned, &identity, &request).unwrap();
+
+        let info = macho::code_signature(&signed.bytes).unwrap().unwrap();
+        assert_eq!((info.identifier.as_deref(), info.team_id.as_deref()), (Some("com.example.shop"), Some("ABCDE12345")));
+        assert!(info.has_cms_signature && info.entitlements_xml.unwrap().contains("get-task-allow"));
+        assert_eq!(info.certificate_names, ["Apple Development: Test (ABCDE12345)"]);
+
+        let (code_limit, blobs) = signature_blobs(&signed.bytes);
+        assert_eq!(code_limit, unsigned.len());
+        assert_eq!(&signed.bytes[1024..code_limit], &unsigned[1024..]);
+        let slots: Vec<u32> = blobs.iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(slots, [CSSLOT_CODEDIRECTORY, CSSLOT_REQUIREMENTS, CSSLOT_ENTITLEMENTS, CSSLOT_DER_ENTITLEMENTS, CSSLOT_SIGNATURESLOT]);
+        let cd = &blobs[0].1;
+        assert_eq!(<[u8; 32]>::from(Sha256::digest(cd)), signed.cdhash);
+        let hash_offset = macho::read_u32_be(cd, 16).unwrap() as usize;
+        for (i, page) in signed.bytes[..code_limit].chunks(PAGE_SIZE).enumerate() {
+            assert_eq!(&cd[hash_offset + i * 32..hash_offset + (i + 1) * 32], Sha256::digest(page).as_slice(), "page {}", i);
+        }
+        assert_eq!(&cd[hash_offset - 32..hash_offset], Sha256::digest(b"<plist/>").as_slice(), "Info.plist slot");
+        assert_eq!(&cd[hash_offset - 5 * 32..hash_offset - 4 * 32], Sha256::digest(&blobs[2].1).as_slice(), "entitlements slot");
+        assert_eq!(macho::read_u32_be(cd, 84), Some((CS_EXECSEG_MAIN_BINARY | CS_EXECSEG_ALLOW_UNSIGNED) as u32));
+
+        let content = ContentInfo::from_der(&blobs[4].1[8..]).unwrap();
+        let signed_data: SignedData = content.content.decode_as().unwrap();
+        let signer = signed_data.signer_infos.0.get(0).unwrap();
+        let attrs = signer.signed_attrs.as_ref().unwrap();
+        let digest = attrs.iter().find(|a| a.oid == OID_MESSAGE_DIGEST).unwrap().values.get(0).unwrap();
+        assert_eq!(digest.decode_as::<OctetString>().unwrap().as_bytes(), signed.cdhash);
+        let public_key = identity.certificate.tbs_certificate.subject_public_key_info.subject_public_key.raw_bytes();
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key).verify(&attrs.to_der().unwrap(), signer.signature.as_bytes()).unwrap();
+
+        // Signing again reuses the space, even with a different identifier.
+        let resigned = sign_macho(&signed.bytes, &identity, &CodeSignatureRequest { identifier: "com.example.other", ..Default::default() }).unwrap();
+        assert_eq!(signature_blobs(&resigned.bytes).0, code_limit);
+        assert_eq!(macho::code_signature(&resigned.bytes).unwrap().unwrap().identifier.as_deref(), Some("com.example.other"));
+    }
+
+    #[test]
+    fn test_der_entitlements() {
+        let entitlements = crate::info_plist::parse_dictionary(
+            b"<plist version=\"1.0\"><dict><key>team</key><string>AB</string><key>beta</key><integer>200</integer><key>groups</key><array><true/></array></dict></plist>",
+        )
+        .unwrap();
+        let expected: &[u8] = &[
+            0x70, 0x2C, 0x02, 0x01, 0x01, 0xB0, 0x27, //
+            0x30, 0x0A, 0x0C, 0x04, b'b', b'e', b't', b'a', 0x02, 0x02, 0x00, 0xC8, //
+            0x30, 0x0D, 0x0C, 0x06, b'g', b'r', b'o', b'u', b'p', b's', 0x30, 0x03, 0x01, 0x01, 0xFF, //
+            0x30, 0x0A, 0x0C, 0x04, b't', b'e', b'a', b'm', 0x0C, 0x02, b'A', b'B',
+        ];
+        assert_eq!(der_entitlements(&entitlements).unwrap(), expected);
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+
+use crate::macho;
+
+/// Signature state of a single Mach-O binary inside the app bundle.
+#[derive(Serialize, Debug, Clone)]
+pub struct BinarySignature {
+    /// Path relative to the `.app` bundle, e.g. `Frameworks/Flutter.framework/Flutter`.
+    pub path: String,
+    pub architectures: Vec<String>,
+    pub signed: bool,
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    /// Leaf certificate common name, e.g. `Apple Distribution: Example Corp (ABCDE12345)`.
+    /// `None` for ad-hoc signatures.
+    pub authority: Option<String>,
+    /// Result of `codesign --verify`; `None` when it could not be run (non-macOS hosts).
+    pub valid: Option<bool>,
+    pub problem: Option<String>,
+    /// Entitlements plist embedded in the signature, as XML.
+    pub entitlements_xml: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SignatureReport {
+    pub ipa_path: PathBuf,
+    pub app_bundle: String,
+    pub binaries: Vec<BinarySignature>,
+    /// Whether signatures were cryptographically checked with `codesign`, or only parsed.
+    pub cryptographically_verified: bool,
+    /// Resource seal check; `None` when the app has no `CodeResources`.
+    pub seal: Option<SealCheck>,
+}
+
+impl SignatureReport {
+    pub fn is_valid(&self) -> bool {
+        !self.binaries.is_empty()
+            && self.binaries.iter().all(|b| b.signed && b.valid != Some(false) && b.problem.is_none())
+            && self.seal.as_ref().is_none_or(SealCheck::is_intact)
+    }
+
+    pub fn main_binary(&self) -> Option<&BinarySignature> {
+        self.binaries.first()
+    }
+}
+
+/// Checks the code signature of an IPA's main executable and embedded frameworks/dylibs.
+///
+/// Signing identity and team are read straight from the embedded signature blobs on every
+/// platform. On macOS, each bundle is additionally checked with `codesign --verify --strict`.
+pub fn verify_ipa_signature(ipa_path: &Path) -> Result<SignatureReport, String> {
+    let extract_dir = tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    archive
+        .extract(extract_dir.path())
+        .map_err(|e| format!("Failed to extract {}: {}", ipa_path.display(), e))?;
+
+    let app_dir = payload_app_dir(extract_dir.path())?;
+    let use_codesign = codesign_available();
+
+    let mut binaries = Vec::new();
+    for (bundle_path, binary_path) in signable_binaries(&app_dir) {
+        let relative = binary_path
+            .strip_prefix(&app_dir)
+            .unwrap_or(&binary_path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut entry = inspect_binary(&binary_path, relative);
+        if use_codesign && entry.signed {
+            match run_codesign_verify(&bundle_path) {
+                Ok(()) => entry.valid = Some(true),
+                Err(e) => {
+                    entry.valid = Some(false);
+                    entry.problem = Some(e);
+                }
+            }
+        }
+        binaries.push(entry);
+    }
+
+    if binaries.is_empty() {
+        return Err(format!("No Mach-O executable found in {}", app_dir.display()));
+    }
+
+    Ok(SignatureReport {
+        ipa_path: ipa_path.to_path_buf(),
+        app_bundle: app_dir.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        binaries,
+        cryptographically_verified: use_codesign,
+        seal: check_ipa_seal(ipa_path)?,
+    })
+}
+
+/// Returns `Payload/<Name>.app` inside an extracted IPA.
+pub fn payload_app_dir(extracted_root: &Path) -> Result<PathBuf, String> {
+    let payload = extracted_root.join("Payload");
+    let entries = fs::read_dir(&payload).map_err(|_| "IPA has no Payload directory".to_string())?;
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| p.is_dir() && p.extension().is_some_and(|ext| ext == "app"))
+        .ok_or_else(|| "Payload contains no .app bundle".to_string())
+}
+
+/// The main executable of a bundle: `CFBundleExecutable` from its `Info.plist` (XML or binary).
+/// Without a usable Info.plist, the file named after the bundle as Xcode does, otherwise the
+/// first top-level Mach-O file.
+pub fn main_executable(app_dir: &Path) -> Option<PathBuf> {
+    let declared = crate::info_plist::read_dictionary(&app_dir.join("Info.plist"))
+        .ok()
+        .and_then(|info| info.get("CFBundleExecutable")?.as_string().map(|name| app_dir.join(name)))
+        .filter(|path| path.is_file());
+    if declared.is_some() {
+        return declared;
+    }
+    let stem = app_dir.file_stem()?;
+    let by_name = app_dir.join(stem);
+    if by_name.is_file() {
+        return Some(by_name);
+    }
+    let mut candidates: Vec<PathBuf> = fs::read_dir(app_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && file_is_macho(p))
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
+/// Lists `(bundle_or_file_to_verify, binary)` pairs: the main executable first, then
+/// frameworks, loose dylibs and app extensions.
+pub fn signable_binaries(app_dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut result = Vec::new();
+    if let Some(main) = main_executable(app_dir) {
+        result.push((app_dir.to_path_buf(), main));
+    }
+
+    let mut nested: Vec<PathBuf> = Vec::new();
+    for sub in ["Frameworks", "PlugIns"] {
+        if let Ok(entries) = fs::read_dir(app_dir.join(sub)) {
+            nested.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+        }
+    }
+    nested.sort();
+    for path in nested {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match ext {
+            "framework" | "appex" => {
+                if let Some(binary) = main_executable(&path) {
+                    result.push((path.clone(), binary));
+                }
+            }
+            "dylib" if path.is_file() => result.push((path.clone(), path.clone())),
+            _ => {}
+        }
+    }
+    result
+}
+
+pub(crate) fn file_is_macho(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+        .is_ok_and(|_| crate::ipa_logic::is_macho(&magic))
+}
+
+fn inspect_binary(binary_path: &Path, relative: String) -> BinarySignature {
+    let mut entry = BinarySignature {
+        path: relative,
+        architectures: Vec::new(),
+        signed: false,
+        identifier: None,
+        team_id: None,
+        authority: None,
+        valid: None,
+        problem: None,
+        entitlements_xml: None,
+    };
+    let bytes = match fs::read(binary_path) {
+        Ok(b) => b,
+        Err(e) => {
+            entry.problem = Some(format!("Failed to read binary: {}", e));
+            return entry;
+        }
+    };
+    let slice_bytes = match macho::slices(&bytes).and_then(|slices| {
+        entry.architectures = slices.iter().map(|s| s.arch_name()).collect();
+        slices.first().ok_or(macho::MachOError::NotMachO)?.bytes(&bytes).map(|b| b.to_vec())
+    }) {
+        Ok(b) => b,
+        Err(e) => {
+            entry.problem = Some(e.to_string());
+            return entry;
+        }
+    };
+    match macho::code_signature(&slice_bytes) {
+        Ok(Some(info)) => {
+            entry.signed = true;
+            entry.identifier = info.identifier;
+            entry.team_id = info.team_id;
+            entry.authority = leaf_certificate_name(&info.certificate_names);
+            entry.entitlements_xml = info.entitlements_xml;
+            if !info.has_cms_signature {
+                entry.problem = Some("ad-hoc signature (no signing certificate)".to_string());
+            }
+        }
+        Ok(None) => entry.problem = Some("not signed".to_string()),
+        Err(e) => entry.problem = Some(format!("unreadable signature: {}", e)),
+    }
+    entry
+}
+
+/// Lists embedded frameworks, dylibs and extensions whose signature would make the app fail
+/// to install on a device: missing `_CodeSignature`, unsigned binaries, or a team ID that
+/// differs from the main app's. Nothing is reported when the main app itself is unsigned,
+/// since such builds have to be re-signed as a whole anyway.
+pub fn nested_signing_warnings(app_dir: &Path) -> Vec<String> {
+    let mut binaries = signable_binaries(app_dir).into_iter();
+    let main = match binaries.next() {
+        Some((_, main_binary)) => inspect_binary(&main_binary, String::new()),
+        None => return Vec::new(),
+    };
+    if !main.signed {
+        return Vec::new();
+    }
+
+    let mut warnings = Vec::new();
+    for (bundle_path, binary_path) in binaries {
+        let relative = bundle_path
+            .strip_prefix(app_dir)
+            .unwrap_or(&bundle_path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let is_bundle = bundle_path != binary_path;
+        if is_bundle && !bundle_path.join("_CodeSignature").join("CodeResources").is_file() {
+            warnings.push(format!("{}: missing _CodeSignature", relative));
+            continue;
+        }
+        let entry = inspect_binary(&binary_path, relative.clone());
+        if !entry.signed {
+            warnings.push(format!("{}: {}", relative, entry.problem.unwrap_or_else(|| "not signed".to_string())));
+        } else if entry.team_id != main.team_id {
+            warnings.push(format!(
+                "{}: signed by team {} but the app is signed by team {}",
+                relative,
+                entry.team_id.as_deref().unwrap_or("(none)"),
+                main.team_id.as_deref().unwrap_or("(none)")
+            ));
+        }
+    }
+    warnings
+}
+
+/// Files of a signed app that don't match its `_CodeSignature/CodeResources` seal. Any of them
+/// makes installation fail, also through sideloading tools that re-sign only the binaries.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SealCheck {
+    /// Files whose SHA-256 differs from the sealed one, relative to the `.app`.
+    pub modified: Vec<String>,
+    /// Sealed files that are not in the bundle.
+    pub missing: Vec<String>,
+    /// Files added after signing, which the seal doesn't cover.
+    pub unsealed: Vec<String>,
+    /// Nested frameworks, plug-ins and extensions sealed by the app but without a
+    /// `CodeResources` of their own.
+    pub unsigned_bundles: Vec<String>,
+}
+
+impl SealCheck {
+    pub fn is_intact(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.unsealed.is_empty() && self.unsigned_bundles.is_empty()
+    }
+
+    /// One warning per kind of problem, naming the first few files.
+    pub fn warnings(&self) -> Vec<String> {
+        let list = |files: &[String]| {
+            let mut text = files.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+            if files.len() > 5 {
+                text.push_str(&format!(" and {} more", files.len() - 5));
+            }
+            text
+        };
+        [
+            (&self.modified, "modified after signing"),
+            (&self.missing, "sealed but missing"),
+            (&self.unsealed, "added after signing"),
+            (&self.unsigned_bundles, "nested bundles without a signature"),
+        ]
+        .into_iter()
+        .filter(|(files, _)| !files.is_empty())
+        .map(|(files, what)| format!("Code signature broken, {} file(s) {}: {}", files.len(), what, list(files)))
+        .collect()
+    }
+}
+
+/// Checks every file of the IPA's main app against the SHA-256 hashes in its
+/// `_CodeSignature/CodeResources` (`files2`), and nested bundles against theirs. Straight from
+/// the archive, without extracting it. `Ok(None)` when the app isn't signed.
+///
+/// Files the seal doesn't list count as unsealed except the executable, `Info.plist`,
+/// `PkgInfo` and `.DS_Store` files, which `codesign` never seals; the seal's `rules2` patterns
+/// are not evaluated. Entries with only a SHA-1 `hash` are checked for presence.
+pub fn check_ipa_seal(ipa_path: &Path) -> Result<Option<SealCheck>, String> {
+    let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    let names: BTreeSet<String> = archive.file_names().filter(|n| !n.ends_with('/')).map(str::to_string).collect();
+    let Some(app) = names.iter().find_map(|name| {
+        let rest = name.strip_prefix("Payload/")?;
+        let (app, _) = rest.split_once('/')?;
+        app.ends_with(".app").then(|| format!("Payload/{}/", app))
+    }) else {
+        return Err("Payload contains no .app bundle".to_string());
+    };
+    if !names.contains(&format!("{}_CodeSignature/CodeResources", app)) {
+        return Ok(None);
+    }
+    let mut check = SealCheck::default();
+    check_bundle_seal(&mut archive, &names, &app, "", &mut check)?;
+    Ok(Some(check))
+}
+
+/// Checks the bundle at archive prefix `bundle` (ending in `/`), reporting paths prefixed
+/// with `display_prefix`.
+fn check_bundle_seal(
+    archive: &mut zip::ZipArchive<File>,
+    names: &BTreeSet<String>,
+    bundle: &str,
+    display_prefix: &str,
+    check: &mut SealCheck,
+) -> Result<(), String> {
+    let seal = read_plist_entry(archive, &format!("{}_CodeSignature/CodeResources", bundle))?;
+    let files = seal
+        .get("files2")
+        .and_then(|f| f.as_dictionary())
+        .ok_or_else(|| format!("{}_CodeSignature/CodeResources has no files2", bundle))?;
+    let executable = read_plist_entry(archive, &format!("{}Info.plist", bundle))
+        .ok()
+        .and_then(|info| info.get("CFBundleExecutable")?.as_string().map(str::to_string));
+
+    let mut nested = Vec::new();
+    for (key, value) in files {
+        let entry = value.as_dictionary();
+        let field = |name: &str| entry.and_then(|e| e.get(name));
+        let path = format!("{}{}", bundle, key);
+        let shown = format!("{}{}", display_prefix, key);
+        if field("cdhash").is_some() && !names.contains(&path) {
+            nested.push(format!("{}/", key));
+            let nested_bundle = format!("{}/", path);
+            if names.contains(&format!("{}_CodeSignature/CodeResources", nested_bundle)) {
+                check_bundle_seal(archive, names, &nested_bundle, &format!("{}/", shown), check)?;
+            } else if names.iter().any(|n| n.starts_with(&nested_bundle)) {
+                check.unsigned_bundles.push(shown);
+            } else {
+                check.missing.push(shown);
+            }
+        } else if !names.contains(&path) {
+            if !field("optional").and_then(|o| o.as_boolean()).unwrap_or(false) {
+                check.missing.push(shown);
+            }
+        } else if let Some(sealed) = field("hash2").and_then(|h| h.as_data()) {
+            let mut hasher = Sha256::new();
+            let mut entry = archive.by_name(&path).map_err(|e| e.to_string())?;
+            io::copy(&mut entry, &mut hasher).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            if hasher.finalize().as_slice() != sealed {
+                check.modified.push(shown);
+            }
+        }
+    }
+
+    for name in names.range(bundle.to_string()..).take_while(|n| n.starts_with(bundle)) {
+        let relative = &name[bundle.len()..];
+        let file_name = relative.rsplit('/').next().unwrap_or(relative);
+        let exempt = relative.starts_with("_CodeSignature/")
+            || Some(relative) == executable.as_deref()
+            || matches!(relative, "Info.plist" | "PkgInfo")
+            || file_name == ".DS_Store"
+            || nested.iter().any(|dir| relative.starts_with(dir.as_str()));
+        if !exempt && !files.contains_key(relative) {
+            check.unsealed.push(format!("{}{}", display_prefix, relative));
+        }
+    }
+    Ok(())
+}
+
+fn read_plist_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Result<plist::Dictionary, String> {
+    let mut bytes = Vec::new();
+    archive
+        .by_name(name)
+        .map_err(|e| format!("{}: {}", name, e))?
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    crate::info_plist::parse_dictionary(&bytes).map_err(|e| format!("{}: {}", name, e))
+}
+
+/// Picks the developer certificate among the CMS certificate names. Apple's leaf certificates
+/// are named `<Kind>: <Name> (<TEAM>)`, while the CA certificates have no colon.
+pub fn leaf_certificate_name(names: &[String]) -> Option<String> {
+    names
+        .iter()
+        .find(|n| n.contains(": "))
+        .or_else(|| names.first())
+        .cloned()
+}
+
+/// The entitlements in a plist (XML or binary, such as an `.entitlements` file or
+/// [`BinarySignature::entitlements_xml`]) as key and value text, sorted by key.
+pub fn entitlement_entries(plist_bytes: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let value = plist::Value::from_reader(std::io::Cursor::new(plist_bytes)).map_err(|e| format!("Invalid entitlements plist: {}", e))?;
+    let dict = value.into_dictionary().ok_or("Entitlements plist is not a dictionary")?;
+    let mut entries: Vec<(String, String)> = dict.into_iter().map(|(key, value)| (key, describe_plist_value(&value))).collect();
+    entries.sort();
+    Ok(entries)
+}
+
+/// `true`, `group.com.example.shop, group.com.example.kiosk` or `{aps-environment: production}`.
+fn describe_plist_value(value: &plist::Value) -> String {
+    match value {
+        plist::Value::Boolean(b) => b.to_string(),
+        plist::Value::String(s) => s.clone(),
+        plist::Value::Integer(i) => i.to_string(),
+        plist::Value::Real(r) => r.to_string(),
+        plist::Value::Array(items) => items.iter().map(describe_plist_value).collect::<Vec<_>>().join(", "),
+        plist::Value::Dictionary(dict) => {
+            let inner: Vec<String> = dict.iter().map(|(k, v)| format!("{}: {}", k, describe_plist_value(v))).collect();
+            format!("{{{}}}", inner.join(", "))
+        }
+        plist::Value::Data(data) => format!("<{} bytes>", data.len()),
+        plist::Value::Date(date) => format!("{:?}", date),
+        _ => "?".to_string(),
+    }
+}
+
+pub fn codesign_available() -> bool {
+    cfg!(target_os = "macos")
+        && Command::new("codesign")
+            .arg("--help")
+            .output()
+            .is_ok()
+}
+
+fn run_codesign_verify(path: &Path) -> Result<(), String> {
+    let output = Command::new("codesign")
+        .args(["--verify", "--strict"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("failed to run codesign: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::test_support::thin_macho;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    fn write_ipa(path: &Path, files: &[(&str, Vec<u8>)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, bytes) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_reports_unsigned_framework() {
+        let dir = tempdir().unwrap();
+        let ipa = dir.path().join("App.ipa");
+        write_ipa(&ipa, &[
+            ("Payload/App.app/Info.plist", b"plist".to_vec()),
+            ("Payload/App.app/App", thin_macho(macho::CPU_TYPE_ARM64, Some(("com.example.app", Some("ABCDE12345"))))),
+            ("Payload/App.app/Frameworks/Lib.framework/Lib", thin_macho(macho::CPU_TYPE_ARM64, None)),
+        ]);
+
+        let report = verify_ipa_signature(&ipa).unwrap();
+        assert_eq!(report.app_bundle, "App.app");
+        assert_eq!(report.binaries.len(), 2);
+
+        let main = report.main_binary().unwrap();
+        assert_eq!(main.path, "App");
+        assert!(main.signed);
+        assert_eq!(main.team_id.as_deref(), Some("ABCDE12345"));
+
+        let framework = &report.binaries[1];
+        assert_eq!(framework.path, "Frameworks/Lib.framework/Lib");
+        assert!(!framework.signed);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_nested_signing_warnings() {
+        let dir = tempdir().unwrap();
+        let app_dir = dir.path().join("App.app");
+        let frameworks = app_dir.join("Frameworks");
+        fs::create_dir_all(frameworks.join("Unsigned.framework")).unwrap();
+        fs::create_dir_all(frameworks.join("Other.framework/_CodeSignature")).unwrap();
+        fs::write(app_dir.join("App"), thin_macho(macho::CPU_TYPE_ARM64, Some(("com.example.app", Some("ABCDE12345"))))).unwrap();
+        fs::write(frameworks.join("Unsigned.framework/Unsigned"), thin_macho(macho::CPU_TYPE_ARM64, None)).unwrap();
+        fs::write(frameworks.join("Other.framework/Other"), thin_macho(macho::CPU_TYPE_ARM64, Some(("com.other", Some("ZZZZZ99999"))))).unwrap();
+        fs::write(frameworks.join("Other.framework/_CodeSignature/CodeResources"), b"plist").unwrap();
+        fs::write(frameworks.join("libok.dylib"), thin_macho(macho::CPU_TYPE_ARM64, Some(("libok", Some("ABCDE12345"))))).unwrap();
+
+        let warnings = nested_signing_warnings(&app_dir);
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].starts_with("Frameworks/Other.framework: signed by team ZZZZZ99999"));
+        assert_eq!(warnings[1], "Frameworks/Unsigned.framework: missing _CodeSignature");
+
+        fs::write(app_dir.join("App"), thin_macho(macho::CPU_TYPE_ARM64, None)).unwrap();
+        assert!(nested_signing_warnings(&app_dir).is_empty());
+
+        assert_eq!(main_executable(&app_dir), Some(app_dir.join("App")));
+        fs::write(app_dir.join("Shop"), thin_macho(macho::CPU_TYPE_ARM64, None)).unwrap();
+        let info: plist::Dictionary = [("CFBundleExecutable".to_string(), plist::Value::from("Shop"))].into_iter().collect();
+        plist::Value::Dictionary(info).to_file_binary(app_dir.join("Info.plist")).unwrap();
+        assert_eq!(main_executable(&app_dir), Some(app_dir.join("Shop")));
+    }
+
+    #[test]
+    fn test_seal_check_finds_changed_files() {
+        let sealed = |contents: &[u8]| {
+            let mut entry = plist::Dictionary::new();
+            entry.insert("hash2".into(), plist::Value::Data(Sha256::digest(contents).to_vec()));
+            plist::Value::Dictionary(entry)
+        };
+        let mut files = plist::Dictionary::new();
+        files.insert("Assets.car".into(), sealed(b"assets"));
+        files.insert("embedded.mobileprovision".into(), sealed(b"profile"));
+        files.insert("en.lproj/Main.strings".into(), sealed(b"strings"));
+        files.insert("Frameworks/Lib.framework".into(), plist::Value::Dictionary([("cdhash".to_string(), plist::Value::Data(vec![1; 20]))].into_iter().collect()));
+        let mut seal = plist::Dictionary::new();
+        seal.insert("files2".into(), plist::Value::Dictionary(files));
+        let mut code_resources = Vec::new();
+        plist::Value::Dictionary(seal).to_writer_xml(&mut code_resources).unwrap();
+        let mut info = Vec::new();
+        plist::Value::Dictionary([("CFBundleExecutable".to_string(), plist::Value::from("App"))].into_iter().collect()).to_writer_xml(&mut info).unwrap();
+
+        let dir = tempdir().unwrap();
+        let ipa = dir.path().join("App.ipa");
+        write_ipa(&ipa, &[
+            ("Payload/App.app/Info.plist", info.clone()),
+            ("Payload/App.app/App", b"binary".to_vec()),
+            ("Payload/App.app/_CodeSignature/CodeResources", code_resources),
+            ("Payload/App.app/Assets.car", b"assets".to_vec()),
+            ("Payload/App.app/embedded.mobileprovision", b"another profile".to_vec()),
+            ("Payload/App.app/Debug.txt", b"added".to_vec()),
+            ("Payload/App.app/Frameworks/Lib.framework/Lib", b"lib".to_vec()),
+        ]);
+        let check = check_ipa_seal(&ipa).unwrap().unwrap();
+        assert_eq!(check.modified, ["embedded.mobileprovision"]);
+        assert_eq!(check.missing, ["en.lproj/Main.strings"]);
+        assert_eq!(check.unsealed, ["Debug.txt"]);
+        assert_eq!(check.unsigned_bundles, ["Frameworks/Lib.framework"]);
+        assert_eq!(check.warnings()[0], "Code signature broken, 1 file(s) modified after signing: embedded.mobileprovision");
+
+        write_ipa(&ipa, &[("Payload/App.app/Info.plist", info), ("Payload/App.app/App", b"binary".to_vec())]);
+        assert_eq!(check_ipa_seal(&ipa).unwrap(), None);
+    }
+
+    #[test]
+    fn test_entitlement_entries() {
+        let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+  <key>get-task-allow</key><false/>
+  <key>application-identifier</key><string>ABCDE12345.com.example.shop</string>
+  <key>com.apple.security.application-groups</key><array><string>group.shop</string><string>group.kiosk</string></array>
+</dict></plist>"#;
+        let entries = entitlement_entries(xml).unwrap();
+        assert_eq!(entries[0], ("application-identifier".to_string(), "ABCDE12345.com.example.shop".to_string()));
+        assert_eq!(entries[1].1, "group.shop, group.kiosk");
+        assert_eq!(entries[2], ("get-task-allow".to_string(), "false".to_string()));
+        assert!(entitlement_entries(b"<plist><array/></plist>").is_err());
+    }
+
+    #[test]
+    fn test_leaf_certificate_name() {
+        let names = vec![
+            "Apple Worldwide Developer Relations Certification Authority".to_string(),
+            "Apple Distribution: Example Corp (ABCDE12345)".to_string(),
+            "Apple Root CA".to_string(),
+        ];
+        assert_eq!(leaf_certificate_name(&names).as_deref(), Some("Apple Distribution: Example Corp (ABCDE12345)"));
+    }
+}
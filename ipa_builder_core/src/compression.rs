@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::CompressionMethod;
+
+/// Formats that are already compressed, so deflating them again costs time and saves little.
+pub const ALREADY_COMPRESSED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "car", "heic", "mp3", "mp4", "m4a", "mov", "zip", "gz"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadCompression {
+    #[default]
+    Deflated,
+    Stored,
+}
+
+/// How files are compressed into the IPA. Directories are always stored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CompressionSettings {
+    pub method: PayloadCompression,
+    /// Deflate level from 0 (fastest) to 9 (smallest); values above 9 are treated as 9.
+    pub level: u32,
+    /// Extensions, without the dot and matched case-insensitively, of files that are always
+    /// stored, e.g. [`ALREADY_COMPRESSED_EXTENSIONS`].
+    pub store_extensions: Vec<String>,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self { method: PayloadCompression::Deflated, level: 6, store_extensions: Vec::new() }
+    }
+}
+
+impl CompressionSettings {
+    /// Zip options for the file at `path`, before its permissions are set.
+    pub fn file_options(&self, path: &Path) -> FileOptions {
+        let stored_by_rule = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.store_extensions.iter().any(|rule| rule.trim().trim_start_matches('.').eq_ignore_ascii_case(ext)));
+        if self.method == PayloadCompression::Stored || stored_by_rule {
+            FileOptions::default().compression_method(CompressionMethod::Stored)
+        } else {
+            FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(Some(self.level.min(9) as i32))
+        }
+    }
+
+    /// Short description for logs and the settings dialog, e.g. `Deflate level 6, storing png, car`.
+    pub fn summary(&self) -> String {
+        let mut text = match self.method {
+            PayloadCompression::Deflated => format!("Deflate level {}", self.level.min(9)),
+            PayloadCompression::Stored => "Stored (no compression)".to_string(),
+        };
+        let rules: Vec<&str> = self.store_extensions.iter().map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+        if self.method == PayloadCompression::Deflated && !rules.is_empty() {
+            text.push_str(&format!(", storing {}", rules.join(", ")));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_rules_store_matching_files() {
+        let settings = CompressionSettings {
+            level: 12,
+            store_extensions: vec![".PNG".to_string(), "car".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(settings.summary(), "Deflate level 9, storing .PNG, car");
+
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("build/Payload/App.app");
+        fs::create_dir_all(&app_dir).unwrap();
+        for name in ["icon.png", "Assets.car", "main.jsbundle"] {
+            fs::write(app_dir.join(name), vec![b'a'; 4096]).unwrap();
+        }
+        let ipa = dir.path().join("App.ipa");
+        crate::ipa_logic::write_payload_zip_with_settings(&dir.path().join("build"), &ipa, &settings).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&ipa).unwrap()).unwrap();
+        let method = |archive: &mut zip::ZipArchive<File>, name: &str| archive.by_name(name).unwrap().compression();
+        assert_eq!(method(&mut archive, "Payload/App.app/icon.png"), CompressionMethod::Stored);
+        assert_eq!(method(&mut archive, "Payload/App.app/Assets.car"), CompressionMethod::Stored);
+        assert_eq!(method(&mut archive, "Payload/App.app/main.jsbundle"), CompressionMethod::Deflated);
+    }
+}
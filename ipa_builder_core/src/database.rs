@@ -0,0 +1,66 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+
+/// Opens the SQLite database at `path`, creating it and its directory if needed, and applies
+/// `schema` (which must be idempotent, e.g. `CREATE TABLE IF NOT EXISTS`).
+///
+/// The default rollback journal is kept rather than WAL, so the database is always a single
+/// file that "Export everything" can copy.
+pub fn open(path: &Path, schema: &str) -> rusqlite::Result<Connection> {
+    if let Some(parent_dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent_dir) {
+            log::error!("Failed to create directory for {}: {}", path.display(), e);
+        }
+    }
+    let connection = Connection::open(path)?;
+    // The GUI and headless commands may write at the same time.
+    connection.busy_timeout(std::time::Duration::from_secs(5))?;
+    connection.execute_batch(schema)?;
+    Ok(connection)
+}
+
+/// `time` as stored in timestamp columns: RFC 3339 in UTC with a fixed width, so the text
+/// sorts chronologically and a date such as `2024-03-10` works as a range bound.
+pub fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+pub fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// The JSONL file that held the data of `db_path` before it moved to SQLite, e.g.
+/// `metrics.jsonl` next to `metrics.sqlite`.
+pub fn legacy_jsonl_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("jsonl")
+}
+
+/// Reads every line of a JSONL file that parses as `T`. Unreadable lines, such as the torn
+/// last line an append cut short by a power loss leaves behind, are skipped with a warning.
+pub fn read_jsonl<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    let bytes = std::fs::read(path)?;
+    let mut items = Vec::new();
+    for line in bytes.split(|&b| b == b'\n') {
+        let line = String::from_utf8_lossy(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(item) => items.push(item),
+            Err(e) => log::warn!("Skipping unreadable line of {} '{}': {}", path.display(), line, e),
+        }
+    }
+    Ok(items)
+}
+
+/// Renames an imported JSONL file to `<name>.jsonl.migrated`, so it is imported only once but
+/// kept as a backup.
+pub fn retire_legacy_jsonl(path: &Path) -> io::Result<()> {
+    let mut migrated = path.as_os_str().to_owned();
+    migrated.push(".migrated");
+    std::fs::rename(path, PathBuf::from(migrated))
+}
@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+
+/// Result of installing an IPA on a USB-connected device, with the installer's full output.
+#[derive(Debug, Clone)]
+pub struct InstallOutcome {
+    pub ipa_path: PathBuf,
+    pub success: bool,
+    /// Combined stdout and stderr of the installer.
+    pub log: String,
+    /// Human-readable cause and fix for a recognized failure.
+    pub diagnosis: Option<String>,
+}
+
+/// An iOS device attached over USB, as reported by libimobiledevice.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConnectedDevice {
+    pub udid: String,
+    /// `DeviceName`, e.g. "Jane's iPhone"; empty if `ideviceinfo` could not read it.
+    pub name: String,
+    /// `ProductType`, e.g. `iPhone15,2`.
+    pub product_type: String,
+    /// `ProductVersion`, e.g. `17.4.1`.
+    pub ios_version: String,
+}
+
+impl ConnectedDevice {
+    /// The name, or the UDID when the name is unknown.
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.udid
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// Lists the devices attached over USB with `idevice_id -l`, reading each one's name, model and
+/// iOS version with `ideviceinfo -s`, which answers even before the device trusts this computer.
+pub fn list_devices() -> Result<Vec<ConnectedDevice>, String> {
+    let output = Command::new("idevice_id")
+        .arg("-l")
+        .output()
+        .map_err(|e| format!("Failed to run idevice_id: {}\nInstall libimobiledevice (e.g. `brew install libimobiledevice` or `apt install libimobiledevice-utils`).", e))?;
+    if !output.status.success() {
+        return Err(format!("idevice_id failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let devices = parse_udids(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .map(|udid| {
+            let info = Command::new("ideviceinfo").args(["-s", "-u", &udid]).output();
+            match info {
+                Ok(info) if info.status.success() => parse_device_info(udid, &String::from_utf8_lossy(&info.stdout)),
+                _ => ConnectedDevice { udid, ..Default::default() },
+            }
+        })
+        .collect();
+    Ok(devices)
+}
+
+/// Runs [`list_devices`] on a background thread.
+pub fn spawn_list_devices() -> mpsc::Receiver<Result<Vec<ConnectedDevice>, String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(list_devices());
+    });
+    rx
+}
+
+/// UDIDs from `idevice_id -l`, one per line; newer versions append ` (USB)`.
+fn parse_udids(output: &str) -> Vec<String> {
+    output.lines().filter_map(|line| line.split_whitespace().next()).map(str::to_string).collect()
+}
+
+/// Reads the `Key: value` lines of `ideviceinfo` output.
+fn parse_device_info(udid: String, output: &str) -> ConnectedDevice {
+    let mut device = ConnectedDevice { udid, ..Default::default() };
+    for (key, value) in output.lines().filter_map(|line| line.split_once(": ")) {
+        let field = match key.trim() {
+            "DeviceName" => &mut device.name,
+            "ProductType" => &mut device.product_type,
+            "ProductVersion" => &mut device.ios_version,
+            _ => continue,
+        };
+        *field = value.trim().to_string();
+    }
+    device
+}
+
+/// Installs `ipa_path` on a connected device with libimobiledevice's `ideviceinstaller`.
+/// `udid` selects the device when several are attached.
+pub fn install_on_device(ipa_path: &Path, udid: Option<&str>) -> InstallOutcome {
+    let mut cmd = Command::new("ideviceinstaller");
+    if let Some(udid) = udid {
+        cmd.args(["-u", udid]);
+    }
+    cmd.arg("-i").arg(ipa_path);
+
+    let (success, log) = match cmd.output() {
+        Ok(output) => {
+            let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+            log.push_str(&String::from_utf8_lossy(&output.stderr));
+            // Older ideviceinstaller versions exit with 0 even when the device rejected the app.
+            let success = output.status.success() && !log.contains("ERROR:");
+            (success, log)
+        }
+        Err(e) => (
+            false,
+            format!("Failed to run ideviceinstaller: {}\nInstall libimobiledevice (e.g. `brew install ideviceinstaller` or `apt install ideviceinstaller`).", e),
+        ),
+    };
+    let diagnosis = if success { None } else { diagnose_install_log(&log) };
+    InstallOutcome {
+        ipa_path: ipa_path.to_path_buf(),
+        success,
+        log,
+        diagnosis,
+    }
+}
+
+/// Runs [`install_on_device`] on a background thread.
+pub fn spawn_install(ipa_path: PathBuf, udid: Option<String>) -> mpsc::Receiver<InstallOutcome> {
+    spawn(move || install_on_device(&ipa_path, udid.as_deref()))
+}
+
+/// Runs [`install_on_booted_simulator`] on a background thread.
+pub fn spawn_simulator_install(ipa_path: PathBuf) -> mpsc::Receiver<InstallOutcome> {
+    spawn(move || install_on_booted_simulator(&ipa_path))
+}
+
+fn spawn(job: impl FnOnce() -> InstallOutcome + Send + 'static) -> mpsc::Receiver<InstallOutcome> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(job());
+    });
+    rx
+}
+
+/// Whether the app's main executable was built for the simulator (see [`crate::macho::is_simulator_binary`]).
+pub fn is_simulator_app(app_dir: &Path) -> bool {
+    crate::codesign::main_executable(app_dir)
+        .and_then(|exe| std::fs::read(exe).ok())
+        .and_then(|bytes| crate::macho::is_simulator_binary(&bytes).ok())
+        .unwrap_or(false)
+}
+
+/// Installs a simulator build on the booted simulator with `xcrun simctl install`, which
+/// takes the `.app` bundle rather than the IPA. macOS only.
+pub fn install_on_booted_simulator(ipa_path: &Path) -> InstallOutcome {
+    let result = (|| -> Result<String, String> {
+        let extract_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+        let file = std::fs::File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+        zip::ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(extract_dir.path()))
+            .map_err(|e| format!("Failed to extract {}: {}", ipa_path.display(), e))?;
+        let app_dir = crate::codesign::payload_app_dir(extract_dir.path())?;
+        let output = Command::new("xcrun")
+            .args(["simctl", "install", "booted"])
+            .arg(&app_dir)
+            .output()
+            .map_err(|e| format!("Failed to run xcrun simctl: {}\nInstall Xcode and its command line tools.", e))?;
+        let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+        log.push_str(&String::from_utf8_lossy(&output.stderr));
+        if output.status.success() {
+            Ok(log)
+        } else {
+            Err(log)
+        }
+    })();
+    let (success, log) = match result {
+        Ok(log) => (true, log),
+        Err(log) => (false, log),
+    };
+    let diagnosis = if success { None } else { diagnose_install_log(&log) };
+    InstallOutcome {
+        ipa_path: ipa_path.to_path_buf(),
+        success,
+        log,
+        diagnosis,
+    }
+}
+
+/// Known installation_proxy / MobileInstallation errors and what to do about them.
+const KNOWN_INSTALL_ERRORS: &[(&str, &str)] = &[
+    ("Failed to run ideviceinstaller", "ideviceinstaller is not installed or not on PATH."),
+    ("Failed to run xcrun simctl", "Simulator installs need Xcode on macOS."),
+    ("No devices are booted", "No simulator is running. Boot one from Xcode or with `xcrun simctl boot <device>`."),
+    ("No device found", "No device is connected. Connect the device over USB, unlock it and tap \"Trust This Computer\"."),
+    ("Could not connect to lockdownd", "The device is locked or does not trust this computer. Unlock it and accept the trust prompt."),
+    ("0xe8008015", "No valid provisioning profile was found for this app. Re-sign the IPA with a profile that matches its bundle ID."),
+    ("A valid provisioning profile for this executable was not found", "No valid provisioning profile was found for this app. Re-sign the IPA with a profile that matches its bundle ID."),
+    ("0xe800801c", "The device's UDID is not in the provisioning profile. Register the device and regenerate the profile."),
+    ("ApplicationVerificationFailed", "The app's code signature could not be verified. Check the signing warnings and re-sign every framework."),
+    ("0xe8008016", "The app was signed with entitlements that the provisioning profile does not allow."),
+    ("MismatchedApplicationIdentifierEntitlement", "An app with the same bundle ID but a different team is already installed. Delete it from the device first."),
+    ("0xe8008018", "The signing certificate is no longer valid (expired or revoked). Re-sign with a current certificate."),
+    ("0xe8008017", "The app's code signature is invalid, usually because files were changed after signing."),
+    ("DeviceOSVersionTooLow", "The device's iOS version is lower than the app's MinimumOSVersion."),
+    ("0xe8000067", "The device's iOS version is lower than the app's MinimumOSVersion."),
+    ("PackageInspectionFailed", "The IPA is malformed. Check that Payload/<App>.app contains Info.plist and the executable."),
+    ("IncorrectArchitecture", "The app was built for a different architecture (e.g. a simulator build). Build for a device instead."),
+];
+
+/// Maps installer output to an actionable explanation, if the error is a known one.
+pub fn diagnose_install_log(log: &str) -> Option<String> {
+    KNOWN_INSTALL_ERRORS
+        .iter()
+        .find(|(needle, _)| log.contains(needle))
+        .map(|(_, hint)| hint.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_install_log() {
+        let log = "Installing 'com.example.shop'\nERROR: Install failed. Got error \"ApplicationVerificationFailed\" with code 0xe8008015: A valid provisioning profile for this executable was not found.";
+        assert!(diagnose_install_log(log).unwrap().contains("provisioning profile"));
+        assert!(diagnose_install_log("ERROR: No device found.").unwrap().contains("Trust This Computer"));
+        assert_eq!(diagnose_install_log("ERROR: something new"), None);
+    }
+
+    #[test]
+    fn test_parse_device_list() {
+        let udids = parse_udids("00008110-001A2B3C4D5E801E (USB)\n\nf1e2d3c4b5a697887766554433221100ffeeddcc\n");
+        assert_eq!(udids, ["00008110-001A2B3C4D5E801E", "f1e2d3c4b5a697887766554433221100ffeeddcc"]);
+        let info = "BasebandVersion: 2.50.01\nDeviceName: Jane's iPhone\nProductType: iPhone15,2\nProductVersion: 17.4.1\n";
+        let device = parse_device_info(udids[0].clone(), info);
+        assert_eq!((device.display_name(), device.product_type.as_str(), device.ios_version.as_str()), ("Jane's iPhone", "iPhone15,2", "17.4.1"));
+        assert_eq!(parse_device_info("abc".to_string(), "").display_name(), "abc");
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+
+/// Directory at the root of the IPA, next to `Payload/`, that [`DsymPlacement::InsideIpa`]
+/// puts the dSYM in.
+pub const SYMBOLS_DIR_NAME: &str = "Symbols";
+
+/// Where [`crate::app_config::AppConfig::dsym_path`] travels with the IPA.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DsymPlacement {
+    /// `<ipa name>.dSYM.zip` next to the IPA; see [`zip_path`].
+    #[default]
+    NextToIpa,
+    /// [`SYMBOLS_DIR_NAME`] inside the IPA. iOS ignores it, but it makes the IPA larger.
+    InsideIpa,
+}
+
+impl DsymPlacement {
+    pub const ALL: [DsymPlacement; 2] = [Self::NextToIpa, Self::InsideIpa];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NextToIpa => "Next to the IPA (.dSYM.zip)",
+            Self::InsideIpa => "Inside the IPA (Symbols/)",
+        }
+    }
+}
+
+/// `<ipa stem>.dSYM.zip` next to the IPA.
+pub fn zip_path(ipa_path: &Path) -> PathBuf {
+    let stem = ipa_path.file_stem().unwrap_or_default().to_string_lossy();
+    ipa_path.with_file_name(format!("{}.dSYM.zip", stem))
+}
+
+/// Checks a configured dSYM: empty, an existing `.dSYM` folder, or an existing `.zip` of one.
+pub fn validate(path: &str) -> Result<(), String> {
+    let declared = path.trim();
+    if declared.is_empty() {
+        return Ok(());
+    }
+    let path = Path::new(declared);
+    let extension = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "dsym" if path.is_dir() => Ok(()),
+        "zip" if path.is_file() => Ok(()),
+        "dsym" | "zip" => Err(format!("{} does not exist", declared)),
+        _ => Err(format!("{} is not a .dSYM folder or a .zip of one", declared)),
+    }
+}
+
+/// Puts the dSYM at `dsym` into [`SYMBOLS_DIR_NAME`] below `build_root`: a folder is copied, a
+/// ZIP extracted.
+pub fn add_to_build_root(dsym: &Path, build_root: &Path) -> Result<(), String> {
+    let symbols = build_root.join(SYMBOLS_DIR_NAME);
+    if dsym.is_dir() {
+        let dest = symbols.join(dsym.file_name().unwrap_or_default());
+        crate::ipa_logic::copy_dir_all(dsym, &dest, &mut || Ok(())).map_err(|e| format!("Failed to copy {}: {}", dsym.display(), e))?;
+    } else {
+        let mut archive = File::open(dsym)
+            .map_err(|e| e.to_string())
+            .and_then(|f| zip::ZipArchive::new(f).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to open {}: {}", dsym.display(), e))?;
+        archive.extract(&symbols).map_err(|e| format!("Failed to extract {}: {}", dsym.display(), e))?;
+    }
+    log::info!("Added {} to the IPA's {}/", dsym.display(), SYMBOLS_DIR_NAME);
+    Ok(())
+}
+
+/// Writes the dSYM at `dsym` to [`zip_path`] of `ipa_path`, replacing an older one: a folder is
+/// compressed, a ZIP copied as it is and verified like the staged IPA.
+pub fn write_next_to(dsym: &Path, ipa_path: &Path, attempts: u32) -> Result<PathBuf, String> {
+    let dest = zip_path(ipa_path);
+    if dsym.is_dir() {
+        if let Err(e) = zip_folder(dsym, &dest) {
+            let _ = fs::remove_file(&dest);
+            return Err(format!("Failed to write {}: {}", dest.display(), e));
+        }
+    } else {
+        crate::transfer::copy_verified_with_retry(dsym, &dest, attempts, Duration::from_millis(500))
+            .map_err(|e| format!("Failed to copy {}: {}", dsym.display(), e))?;
+    }
+    log::info!("Wrote the dSYM to {}", dest.display());
+    Ok(dest)
+}
+
+/// Compresses `folder` into `dest` with the folder itself at the root, like Finder's Compress.
+fn zip_folder(folder: &Path, dest: &Path) -> zip::result::ZipResult<()> {
+    let base = folder.parent().unwrap_or(Path::new(""));
+    let mut zip_writer = zip::ZipWriter::new(File::create(dest)?);
+    for entry in WalkDir::new(folder).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        let name = entry.path().strip_prefix(base).unwrap_or(entry.path()).to_string_lossy().replace('\\', "/");
+        if entry.file_type().is_dir() {
+            zip_writer.add_directory(name, FileOptions::default().unix_permissions(0o755))?;
+        } else {
+            zip_writer.start_file(name, FileOptions::default().unix_permissions(0o644))?;
+            io::copy(&mut File::open(entry.path())?, &mut zip_writer)?;
+        }
+    }
+    zip_writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_dsym_folder_is_zipped_or_copied_into_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let dsym = dir.path().join("Runner.app.dSYM");
+        fs::create_dir_all(dsym.join("Contents/Resources/DWARF")).unwrap();
+        fs::write(dsym.join("Contents/Resources/DWARF/Runner"), "dwarf").unwrap();
+        assert_eq!(validate(&dsym.to_string_lossy()), Ok(()));
+        assert!(validate("Runner.app").unwrap_err().contains("not a .dSYM"));
+
+        let written = write_next_to(&dsym, &dir.path().join("Shop.ipa"), 1).unwrap();
+        assert_eq!(written, dir.path().join("Shop.dSYM.zip"));
+        let mut archive = zip::ZipArchive::new(File::open(&written).unwrap()).unwrap();
+        let mut dwarf = String::new();
+        archive.by_name("Runner.app.dSYM/Contents/Resources/DWARF/Runner").unwrap().read_to_string(&mut dwarf).unwrap();
+        assert_eq!(dwarf, "dwarf");
+
+        let from_folder = dir.path().join("from_folder");
+        add_to_build_root(&dsym, &from_folder).unwrap();
+        let from_zip = dir.path().join("from_zip");
+        add_to_build_root(&written, &from_zip).unwrap();
+        for root in [from_folder, from_zip] {
+            assert_eq!(fs::read_to_string(root.join("Symbols/Runner.app.dSYM/Contents/Resources/DWARF/Runner")).unwrap(), "dwarf");
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use zip::write::FileOptions;
+
+/// Shape of a mock `Runner.app.zip` written by [`write_fixture`].
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    /// Name of the `.app` and its executable.
+    pub app_name: String,
+    pub bundle_id: String,
+    /// Folders the `.app` sits in inside the ZIP, e.g. `build/ios`; empty for the root. The
+    /// build looks for the app at most three levels deep.
+    pub prefix: String,
+    /// Total size of the filler resources, spread over files of at most [`MAX_RESOURCE_FILE`].
+    pub resource_bytes: u64,
+    /// How many folders deep the resources go below `assets/`.
+    pub depth: usize,
+    /// Number of embedded frameworks, `Frameworks/Lib<n>.framework`.
+    pub frameworks: usize,
+    /// Gives each framework a macOS-style `Versions/` layout held together by symlinks.
+    pub symlinks: bool,
+    /// Uses accented, CJK and emoji characters in resource folder and file names.
+    pub unicode_names: bool,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            app_name: "Runner".to_string(),
+            bundle_id: "com.example.runner".to_string(),
+            prefix: String::new(),
+            resource_bytes: 64 * 1024,
+            depth: 2,
+            frameworks: 1,
+            symlinks: false,
+            unicode_names: false,
+        }
+    }
+}
+
+pub const MAX_RESOURCE_FILE: u64 = 1024 * 1024;
+
+const UNICODE_FOLDERS: [&str; 4] = ["Ébauches", "日本語", "Ünïcödé", "emoji 🚀"];
+
+/// Writes a ZIP shaped like `flutter build ios` output to `dest`: an app with an `Info.plist`,
+/// an arm64 Mach-O executable, an app icon, frameworks and filler resources. Resource contents
+/// are pseudo-random, so they compress like real assets, but the same spec always writes the
+/// same ZIP. Returns the number of entries written.
+pub fn write_fixture(spec: &FixtureSpec, dest: &Path) -> Result<usize, String> {
+    let app_name = spec.app_name.trim();
+    if app_name.is_empty() || app_name.contains('/') {
+        return Err(format!("'{}' is not a valid app name", spec.app_name));
+    }
+    let prefix = spec.prefix.trim_matches('/');
+    let app_root = if prefix.is_empty() { format!("{}.app", app_name) } else { format!("{}/{}.app", prefix, app_name) };
+
+    let mut zip = zip::ZipWriter::new(File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?);
+    let result = (|| -> zip::result::ZipResult<()> {
+        add(&mut zip, format!("{}/Info.plist", app_root), &info_plist(app_name, &spec.bundle_id, true), 0o644)?;
+        add(&mut zip, format!("{}/{}", app_root, app_name), &mach_o_executable(), 0o755)?;
+        add(&mut zip, format!("{}/AppIcon60x60@2x.png", app_root), b"\x89PNG\r\n\x1a\n", 0o644)?;
+
+        for n in 1..=spec.frameworks {
+            let name = format!("Lib{}", n);
+            let framework = format!("{}/Frameworks/{}.framework", app_root, name);
+            let plist = info_plist(&name, &format!("{}.lib{}", spec.bundle_id, n), false);
+            if spec.symlinks {
+                add(&mut zip, format!("{}/Versions/A/{}", framework, name), &mach_o_executable(), 0o755)?;
+                add(&mut zip, format!("{}/Versions/A/Resources/Info.plist", framework), &plist, 0o644)?;
+                zip.add_symlink(format!("{}/Versions/Current", framework), "A", FileOptions::default())?;
+                zip.add_symlink(format!("{}/{}", framework, name), format!("Versions/Current/{}", name), FileOptions::default())?;
+                zip.add_symlink(format!("{}/Resources", framework), "Versions/Current/Resources", FileOptions::default())?;
+            } else {
+                add(&mut zip, format!("{}/{}", framework, name), &mach_o_executable(), 0o755)?;
+                add(&mut zip, format!("{}/Info.plist", framework), &plist, 0o644)?;
+            }
+        }
+
+        let mut folder = format!("{}/assets", app_root);
+        let mut folders = Vec::new();
+        for level in 1..=spec.depth.max(1) {
+            let name = if spec.unicode_names { format!("{} {}", UNICODE_FOLDERS[(level - 1) % UNICODE_FOLDERS.len()], level) } else { format!("level{}", level) };
+            folder = format!("{}/{}", folder, name);
+            folders.push(folder.clone());
+        }
+        let files = spec.resource_bytes.div_ceil(MAX_RESOURCE_FILE);
+        let mut rng = 0x9E37_79B9_7F4A_7C15u64;
+        for i in 0..files {
+            let size = (spec.resource_bytes - i * MAX_RESOURCE_FILE).min(MAX_RESOURCE_FILE) as usize;
+            let bytes: Vec<u8> = (0..size)
+                .map(|_| {
+                    rng ^= rng << 13;
+                    rng ^= rng >> 7;
+                    rng ^= rng << 17;
+                    rng as u8
+                })
+                .collect();
+            let name = if spec.unicode_names { format!("données_{}.bin", i) } else { format!("resource_{}.bin", i) };
+            add(&mut zip, format!("{}/{}", folders[i as usize % folders.len()], name), &bytes, 0o644)?;
+        }
+        zip.finish()?;
+        Ok(())
+    })();
+    match result.and_then(|_| zip::ZipArchive::new(File::open(dest)?)) {
+        Ok(archive) => Ok(archive.len()),
+        Err(e) => {
+            let _ = std::fs::remove_file(dest);
+            Err(format!("Failed to write {}: {}", dest.display(), e))
+        }
+    }
+}
+
+fn add(zip: &mut zip::ZipWriter<File>, name: String, bytes: &[u8], mode: u32) -> zip::result::ZipResult<()> {
+    zip.start_file(name, FileOptions::default().unix_permissions(mode))?;
+    zip.write_all(bytes)?;
+    Ok(())
+}
+
+fn info_plist(executable: &str, bundle_id: &str, is_app: bool) -> Vec<u8> {
+    let mut info = plist::Dictionary::new();
+    info.insert("CFBundleExecutable".into(), executable.into());
+    info.insert("CFBundleIdentifier".into(), bundle_id.into());
+    info.insert("CFBundlePackageType".into(), if is_app { "APPL" } else { "FMWK" }.into());
+    info.insert("CFBundleShortVersionString".into(), "1.0.0".into());
+    info.insert("CFBundleVersion".into(), "1".into());
+    if is_app {
+        info.insert("MinimumOSVersion".into(), "12.0".into());
+        info.insert("CFBundleIconFiles".into(), plist::Value::Array(vec!["AppIcon60x60".into()]));
+    }
+    let mut bytes = Vec::new();
+    plist::Value::Dictionary(info).to_writer_xml(&mut bytes).expect("writing a plist to memory cannot fail");
+    bytes
+}
+
+/// An empty thin arm64 Mach-O executable: just the 64-bit header.
+fn mach_o_executable() -> Vec<u8> {
+    const MH_MAGIC_64: u32 = 0xFEEDFACF;
+    const MH_EXECUTE: u32 = 2;
+    [MH_MAGIC_64, crate::macho::CPU_TYPE_ARM64, 0, MH_EXECUTE, 0, 0, 0, 0].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::AppConfig;
+
+    #[test]
+    fn test_fixture_builds_into_a_valid_ipa() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        let spec = FixtureSpec {
+            prefix: "build/ios".to_string(),
+            resource_bytes: MAX_RESOURCE_FILE + 10,
+            depth: 5,
+            frameworks: 2,
+            symlinks: true,
+            unicode_names: true,
+            ..Default::default()
+        };
+        assert_eq!(write_fixture(&spec, &input).unwrap(), 3 + 2 * 5 + 2);
+        let archive = zip::ZipArchive::new(File::open(&input).unwrap()).unwrap();
+        assert!(archive.file_names().any(|n| n.ends_with("Ébauches 1/日本語 2/données_1.bin")));
+
+        let config = AppConfig {
+            input_zip_path: input.to_string_lossy().into_owned(),
+            app_name: "Fixture".to_string(),
+            output_ipa_name: "Fixture.ipa".to_string(),
+            ..Default::default()
+        };
+        let ipa = crate::ipa_logic::generate_ipa(&config, dir.path()).unwrap();
+        let report = crate::validator::validate_ipa(&ipa).unwrap();
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::app_config::AppConfig;
+use crate::ipa_logic::BuildOutput;
+
+/// Builds the variables available to hooks for `config`: the built-in `IPA_BUILDER_*` values
+/// plus the config's own environment variables (which may not override the built-ins). The
+/// output IPA and the app's bundle values are only known after a successful build.
+pub fn hook_variables(config: &AppConfig, output_dir: &Path, output: Option<&BuildOutput>, success: Option<bool>) -> BTreeMap<String, String> {
+    let mut vars: BTreeMap<String, String> = config
+        .env_vars
+        .iter()
+        .filter(|(key, _)| !key.starts_with("IPA_BUILDER_"))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    vars.insert("IPA_BUILDER_APP_ID".to_string(), config.id.clone());
+    vars.insert("IPA_BUILDER_APP_NAME".to_string(), config.app_name.clone());
+    vars.insert("IPA_BUILDER_INPUT_ZIP".to_string(), config.input_zip_path.clone());
+    vars.insert("IPA_BUILDER_OUTPUT_DIR".to_string(), output_dir.display().to_string());
+    vars.insert("IPA_BUILDER_OUTPUT_IPA_NAME".to_string(), config.output_ipa_name.clone());
+    if !config.channel.trim().is_empty() {
+        vars.insert("IPA_BUILDER_CHANNEL".to_string(), config.channel.trim().to_string());
+    }
+    if let Some(output) = output {
+        vars.insert("IPA_BUILDER_OUTPUT_IPA".to_string(), output.ipa_path.display().to_string());
+        if let Some(sha256) = &output.sha256 {
+            vars.insert("IPA_BUILDER_SHA256".to_string(), sha256.clone());
+        }
+        if let Some(url) = &output.uploaded_url {
+            vars.insert("IPA_BUILDER_UPLOAD_URL".to_string(), url.clone());
+        }
+        if config.ota_manifest.enabled {
+            vars.insert("IPA_BUILDER_OTA_MANIFEST".to_string(), crate::ota_manifest::manifest_path(&output.ipa_path).display().to_string());
+        }
+        for (key, value) in output.bundle.iter().flat_map(|bundle| bundle.hook_variables()) {
+            vars.insert(key.to_string(), value.to_string());
+        }
+    }
+    if let Some(success) = success {
+        vars.insert("IPA_BUILDER_SUCCESS".to_string(), if success { "1" } else { "0" }.to_string());
+    }
+    vars
+}
+
+/// Replaces `{{NAME}}` placeholders with values from `vars`, passed through `quote`. Unknown
+/// placeholders are kept.
+pub fn expand_template(template: &str, vars: &BTreeMap<String, String>, quote: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match vars.get(name) {
+                    Some(value) => out.push_str(&quote(value)),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `value` as a single `sh` word: single-quoted, with embedded quotes written as `'\''`.
+pub fn quote_sh(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// `value` with every character `cmd` treats specially, whitespace included, escaped with `^`.
+pub fn quote_cmd(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '^' | '&' | '|' | '<' | '>' | '(' | ')' | '%' | '!' | '"') || c.is_whitespace() {
+            out.push('^');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Runs a hook command line through the platform shell with `vars` in its environment and
+/// substituted into `{{NAME}}` placeholders. Substituted values are quoted for the shell, since
+/// some come from the input's Info.plist. A non-zero exit status is an error.
+pub fn run_hook(command_line: &str, vars: &BTreeMap<String, String>) -> Result<(), String> {
+    let quote = if cfg!(target_os = "windows") { quote_cmd } else { quote_sh };
+    let expanded = expand_template(command_line, vars, quote);
+    log::info!("Running hook: {}", expanded);
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    let output = cmd
+        .arg(&expanded)
+        .envs(vars)
+        .output()
+        .map_err(|e| format!("failed to start hook: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} ({})", output.status, stderr.trim()))
+    }
+}
+
+/// Environment variable names must be usable from both `sh` and `cmd`.
+pub fn validate_env_var_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    match chars.next() {
+        None => return Err("name is empty".to_string()),
+        Some(c) if !(c.is_ascii_alphabetic() || c == '_') => {
+            return Err(format!("'{}' must start with a letter or underscore", name))
+        }
+        _ => {}
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("'{}' may only contain letters, digits and underscores", name));
+    }
+    if name.starts_with("IPA_BUILDER_") {
+        return Err(format!("'{}' uses the reserved IPA_BUILDER_ prefix", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_variables_and_templates() {
+        let mut config = AppConfig {
+            id: "abc".to_string(),
+            app_name: "Shop".to_string(),
+            output_ipa_name: "shop.ipa".to_string(),
+            ..Default::default()
+        };
+        config.env_vars.insert("FLAVOR".to_string(), "staging".to_string());
+        config.env_vars.insert("IPA_BUILDER_APP_NAME".to_string(), "spoofed".to_string());
+
+        let output = BuildOutput {
+            ipa_path: "/out/shop.ipa".into(),
+            warnings: Vec::new(),
+            is_simulator_build: false,
+            bundle: Some(crate::info_plist::BundleVariables { bundle_id: "com.example.shop".to_string(), short_version: "1.4".to_string(), build: "42".to_string() }),
+            sha256: None,
+            uploaded_url: None,
+        };
+        let vars = hook_variables(&config, Path::new("/out"), Some(&output), Some(true));
+        assert_eq!(vars["IPA_BUILDER_APP_NAME"], "Shop");
+        assert_eq!(vars["IPA_BUILDER_BUILD"], "42");
+        assert_eq!(
+            expand_template("upload {{IPA_BUILDER_OUTPUT_IPA}} --flavor {{ FLAVOR }} {{MISSING}} {{", &vars, quote_sh),
+            "upload '/out/shop.ipa' --flavor 'staging' {{MISSING}} {{"
+        );
+        assert_eq!(quote_sh("it's"), "'it'\\''s'");
+        assert_eq!(quote_cmd("a & \"b\"%PATH%"), "a^ ^&^ ^\"b^\"^%PATH^%");
+
+        assert!(validate_env_var_name("API_TOKEN").is_ok());
+        assert!(validate_env_var_name("1ABC").is_err());
+        assert!(validate_env_var_name("MY-VAR").is_err());
+        assert!(validate_env_var_name("IPA_BUILDER_X").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook_passes_env_and_reports_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker.txt");
+        let mut vars = BTreeMap::new();
+        vars.insert("FLAVOR".to_string(), "staging".to_string());
+        vars.insert("MARKER".to_string(), marker.display().to_string());
+
+        run_hook("printf \"$FLAVOR\" > {{MARKER}}", &vars).unwrap();
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "staging");
+        assert!(run_hook("exit 3", &vars).is_err());
+
+        // Values from a crafted Info.plist stay literal instead of running as commands.
+        let hostile = "1; touch pwned $(touch pwned) `touch pwned` 'x' \"y\"";
+        vars.insert("IPA_BUILDER_BUILD".to_string(), hostile.to_string());
+        vars.insert("IPA_BUILDER_OUTPUT_DIR".to_string(), dir.path().display().to_string());
+        run_hook("cd {{IPA_BUILDER_OUTPUT_DIR}} && printf %s {{IPA_BUILDER_BUILD}} > {{MARKER}}", &vars).unwrap();
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), hostile);
+        assert!(!dir.path().join("pwned").exists());
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// `Info.plist` values replaced in the copied bundle before packaging, e.g. to rebrand an app or
+/// bump its build number without a new Xcode build. Empty fields leave the bundle's value alone.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PlistOverrides {
+    /// `CFBundleDisplayName`, the name under the home screen icon.
+    pub display_name: String,
+    /// `CFBundleIdentifier`.
+    pub bundle_identifier: String,
+    /// `CFBundleShortVersionString`, e.g. `1.4.0`.
+    pub short_version: String,
+    /// `CFBundleVersion`, the build number.
+    pub bundle_version: String,
+}
+
+impl PlistOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Checks the identifier and version fields that are set.
+    pub fn validate(&self) -> Result<(), String> {
+        let identifier = self.bundle_identifier.trim();
+        if !identifier.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+            return Err(format!("Bundle identifier '{}' may only contain letters, digits, '.' and '-'", identifier));
+        }
+        for (label, version) in [("Version", self.short_version.trim()), ("Build number", self.bundle_version.trim())] {
+            if !version.is_empty() && (version.split('.').any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) || version.split('.').count() > 3) {
+                return Err(format!("{} '{}' must be up to three dot-separated numbers, e.g. 1.4.2", label, version));
+            }
+        }
+        Ok(())
+    }
+
+    /// The keys to set with their trimmed values.
+    fn entries(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("CFBundleDisplayName", &self.display_name),
+            ("CFBundleIdentifier", &self.bundle_identifier),
+            ("CFBundleShortVersionString", &self.short_version),
+            ("CFBundleVersion", &self.bundle_version),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key, value.trim()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+
+    /// Rewrites the `Info.plist` at `path` with the overrides, keeping its XML or binary format
+    /// and key order (see [`encode_like`]); new keys go at the end. Returns the changes made,
+    /// e.g. `CFBundleVersion: 41 → 42`.
+    pub fn apply(&self, path: &Path) -> Result<Vec<String>, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut value = plist::Value::from_reader(Cursor::new(&bytes)).map_err(|e| format!("Invalid Info.plist: {}", e))?;
+        let dict = value.as_dictionary_mut().ok_or("Info.plist is not a dictionary")?;
+
+        let mut changes = Vec::new();
+        for (key, new_value) in self.entries() {
+            let old_value = dict.get(key).and_then(|v| v.as_string()).unwrap_or_default().to_string();
+            if old_value != new_value {
+                changes.push(format!("{}: {} → {}", key, if old_value.is_empty() { "(none)" } else { &old_value }, new_value));
+                dict.insert(key.to_string(), new_value.into());
+            }
+        }
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+
+        let output = encode_like(&bytes, &value).map_err(|e| format!("Failed to encode Info.plist: {}", e))?;
+        fs::write(path, output).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(changes)
+    }
+}
+
+/// Encodes `value` the way `original` was written, since some tools choke on a plist whose
+/// format changed: binary stays binary, and XML keeps its indentation, line endings and final
+/// newline, and escapes only `&`, `<` and `>` like Xcode. Re-encoding an unmodified Xcode plist
+/// gives back the same bytes.
+fn encode_like(original: &[u8], value: &plist::Value) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    if original.starts_with(b"bplist") {
+        value.to_writer_binary(&mut output).map_err(|e| e.to_string())?;
+        return Ok(output);
+    }
+    // The first indented line is a child of the root element, one level deep.
+    let indent = original
+        .windows(2)
+        .position(|w| w[0] == b'\n' && (w[1] == b'\t' || w[1] == b' '))
+        .map(|newline| {
+            let line = &original[newline + 1..];
+            (line[0], line.iter().take_while(|&&c| c == line[0]).count())
+        });
+    let options = match indent {
+        Some((indent_char, count)) => plist::XmlWriteOptions::default().indent(indent_char, count),
+        None => plist::XmlWriteOptions::default().indent(b'\t', 0),
+    };
+    value.to_writer_xml_with_options(&mut output, &options).map_err(|e| e.to_string())?;
+    let xml = String::from_utf8(output).map_err(|e| format!("XML is not UTF-8: {}", e))?;
+    let mut xml = xml.replace("&quot;", "\"").replace("&apos;", "'");
+    if original.ends_with(b"\n") && !xml.ends_with('\n') {
+        xml.push('\n');
+    }
+    if original.windows(2).any(|w| w == b"\r\n") {
+        xml = xml.replace('\n', "\r\n");
+    }
+    Ok(xml.into_bytes())
+}
+
+/// Parses a plist in XML or binary format into its root dictionary. Xcode and Flutter often
+/// produce binary `Info.plist` files, so every plist read goes through the `plist` crate rather
+/// than looking at the text.
+pub fn parse_dictionary(bytes: &[u8]) -> Result<plist::Dictionary, String> {
+    plist::Value::from_reader(Cursor::new(bytes))
+        .map_err(|e| format!("Invalid plist: {}", e))?
+        .into_dictionary()
+        .ok_or_else(|| "plist is not a dictionary".to_string())
+}
+
+/// Reads the `Info.plist` at `path` with [`parse_dictionary`].
+pub fn read_dictionary(path: &Path) -> Result<plist::Dictionary, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_dictionary(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Placeholders output names may contain, filled in from the app's `Info.plist`.
+pub const NAME_PLACEHOLDERS: [&str; 3] = ["{bundle_id}", "{short_version}", "{build}"];
+
+/// `Info.plist` values of the packaged app, after the overrides. Output names can use them as
+/// [`NAME_PLACEHOLDERS`], and hooks get them as `IPA_BUILDER_BUNDLE_ID`,
+/// `IPA_BUILDER_SHORT_VERSION` and `IPA_BUILDER_BUILD`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct BundleVariables {
+    /// `CFBundleIdentifier`.
+    pub bundle_id: String,
+    /// `CFBundleShortVersionString`.
+    pub short_version: String,
+    /// `CFBundleVersion`.
+    pub build: String,
+}
+
+impl BundleVariables {
+    /// Reads the values from the `Info.plist` at `path`; missing keys are left empty.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let dict = read_dictionary(path)?;
+        let string = |key: &str| dict.get(key).and_then(|v| v.as_string()).unwrap_or_default().trim().to_string();
+        Ok(Self {
+            bundle_id: string("CFBundleIdentifier"),
+            short_version: string("CFBundleShortVersionString"),
+            build: string("CFBundleVersion"),
+        })
+    }
+
+    /// Whether `template` uses any of the [`NAME_PLACEHOLDERS`].
+    pub fn is_used_in(template: &str) -> bool {
+        NAME_PLACEHOLDERS.iter().any(|p| template.contains(p))
+    }
+
+    /// Replaces the [`NAME_PLACEHOLDERS`] in `template`.
+    pub fn expand(&self, template: &str) -> String {
+        template
+            .replace("{bundle_id}", &self.bundle_id)
+            .replace("{short_version}", &self.short_version)
+            .replace("{build}", &self.build)
+    }
+
+    /// The values as hook environment variables.
+    pub fn hook_variables(&self) -> [(&'static str, &str); 3] {
+        [
+            ("IPA_BUILDER_BUNDLE_ID", &self.bundle_id),
+            ("IPA_BUILDER_SHORT_VERSION", &self.short_version),
+            ("IPA_BUILDER_BUILD", &self.build),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XCODE_PLIST: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+\t<key>CFBundleVersion</key>
+\t<string>41</string>
+\t<key>NSCameraUsageDescription</key>
+\t<string>Scan \"QR\" codes &amp; receipts</string>
+\t<key>CFBundleIdentifier</key>
+\t<string>com.example.shop</string>
+\t<key>UIRequiredDeviceCapabilities</key>
+\t<array>
+\t\t<string>arm64</string>
+\t</array>
+\t<key>UIFileSharingEnabled</key>
+\t<true/>
+</dict>
+</plist>
+";
+
+    #[test]
+    fn test_overrides_keep_the_plist_format() {
+        let mut dict = plist::Dictionary::new();
+        dict.insert("CFBundleIdentifier".into(), "com.example.shop".into());
+        dict.insert("CFBundleVersion".into(), "41".into());
+        dict.insert("UIRequiredDeviceCapabilities".into(), plist::Value::Array(vec!["arm64".into()]));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Info.plist");
+        plist::Value::Dictionary(dict).to_file_binary(&path).unwrap();
+
+        let overrides = PlistOverrides {
+            display_name: "Shop Beta".to_string(),
+            bundle_version: " 42 ".to_string(),
+            bundle_identifier: "com.example.shop".to_string(),
+            ..Default::default()
+        };
+        let changes = overrides.apply(&path).unwrap();
+        assert_eq!(changes, vec!["CFBundleDisplayName: (none) → Shop Beta", "CFBundleVersion: 41 → 42"]);
+
+        assert!(fs::read(&path).unwrap().starts_with(b"bplist"));
+        let value = plist::Value::from_file(&path).unwrap();
+        let dict = value.as_dictionary().unwrap();
+        assert_eq!(dict.get("CFBundleVersion").and_then(|v| v.as_string()), Some("42"));
+        assert!(dict.get("UIRequiredDeviceCapabilities").is_some());
+        assert!(overrides.apply(&path).unwrap().is_empty());
+        assert!(PlistOverrides { short_version: "  ".to_string(), ..Default::default() }.is_empty());
+
+        assert!(overrides.validate().is_ok());
+        assert!(PlistOverrides { bundle_identifier: "com.example/shop".to_string(), ..Default::default() }.validate().is_err());
+        assert!(PlistOverrides { short_version: "1.4.0-beta".to_string(), ..Default::default() }.validate().is_err());
+        assert!(PlistOverrides { bundle_version: "1.2.3.4".to_string(), ..Default::default() }.validate().is_err());
+
+        let bundle = BundleVariables::read(&path).unwrap();
+        assert_eq!(bundle, BundleVariables { bundle_id: "com.example.shop".to_string(), short_version: String::new(), build: "42".to_string() });
+        assert!(BundleVariables::is_used_in("Shop-{build}.ipa") && !BundleVariables::is_used_in("Shop-{version}.ipa"));
+        assert_eq!(bundle.expand("{bundle_id}-{build}.ipa"), "com.example.shop-42.ipa");
+    }
+
+    #[test]
+    fn test_patching_round_trips_xml_layout_and_key_order() {
+        let value = plist::Value::from_reader(Cursor::new(XCODE_PLIST)).unwrap();
+        assert_eq!(String::from_utf8(encode_like(XCODE_PLIST.as_bytes(), &value).unwrap()).unwrap(), XCODE_PLIST);
+        let crlf = XCODE_PLIST.replace('\n', "\r\n").replace('\t', "    ");
+        assert_eq!(String::from_utf8(encode_like(crlf.as_bytes(), &value).unwrap()).unwrap(), crlf);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Info.plist");
+        fs::write(&path, XCODE_PLIST).unwrap();
+        let overrides = PlistOverrides { bundle_version: "42".to_string(), display_name: "Shop".to_string(), ..Default::default() };
+        overrides.apply(&path).unwrap();
+        let expected = XCODE_PLIST
+            .replace("<string>41</string>", "<string>42</string>")
+            .replace("\t<true/>\n", "\t<true/>\n\t<key>CFBundleDisplayName</key>\n\t<string>Shop</string>\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+
+        value.to_file_binary(&path).unwrap();
+        overrides.apply(&path).unwrap();
+        let patched = plist::Value::from_file(&path).unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(b"bplist"));
+        let keys: Vec<&str> = patched.as_dictionary().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["CFBundleVersion", "NSCameraUsageDescription", "CFBundleIdentifier", "UIRequiredDeviceCapabilities", "UIFileSharingEnabled", "CFBundleDisplayName"]);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Checks [`crate::app_config::AppConfig::inject_dylibs`]: every entry must be an existing
+/// `.dylib` file or `.framework` directory, and no two may have the same name.
+pub fn validate(items: &[String]) -> Result<(), String> {
+    let mut names = BTreeSet::new();
+    for declared in items.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let path = Path::new(declared);
+        let is_dylib = path.extension().is_some_and(|ext| ext == "dylib");
+        let is_framework = path.extension().is_some_and(|ext| ext == "framework");
+        if !is_dylib && !is_framework {
+            return Err(format!("{} is not a .dylib or .framework", declared));
+        }
+        if (is_dylib && !path.is_file()) || (is_framework && !path.is_dir()) {
+            return Err(format!("{} does not exist", declared));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if !names.insert(name.clone()) {
+            return Err(format!("more than one injected item is named {}", name));
+        }
+    }
+    Ok(())
+}
+
+/// Copies each `.dylib` or `.framework` in `items` into the app's `Frameworks/` and makes the main
+/// executable load it with an `LC_LOAD_DYLIB`, like `insert_dylib`. Returns the install names
+/// added. Items the app already ships are refused rather than replaced.
+pub fn inject(app_dir: &Path, items: &[String]) -> Result<Vec<String>, String> {
+    validate(items)?;
+    let items: Vec<&Path> = items.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).map(Path::new).collect();
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+    let executable = crate::codesign::main_executable(app_dir).ok_or("the app has no main executable")?;
+    let frameworks = app_dir.join("Frameworks");
+    fs::create_dir_all(&frameworks).map_err(|e| format!("Failed to create {}: {}", frameworks.display(), e))?;
+
+    let mut install_names = Vec::with_capacity(items.len());
+    for item in items {
+        let name = item.file_name().unwrap_or_default();
+        let dest = frameworks.join(name);
+        if dest.exists() {
+            return Err(format!("Frameworks/{} already exists in the app", name.to_string_lossy()));
+        }
+        let binary = if item.is_dir() {
+            crate::ipa_logic::copy_dir_all(item, &dest, &mut || Ok(()))
+                .map_err(|e| format!("Failed to copy {}: {}", item.display(), e))?;
+            let binary = crate::codesign::main_executable(&dest).ok_or_else(|| format!("{} has no binary", item.display()))?;
+            format!("{}/{}", name.to_string_lossy(), binary.file_name().unwrap_or_default().to_string_lossy())
+        } else {
+            fs::copy(item, &dest).map_err(|e| format!("Failed to copy {}: {}", item.display(), e))?;
+            name.to_string_lossy().into_owned()
+        };
+        install_names.push(format!("@executable_path/Frameworks/{}", binary));
+    }
+
+    let mut bytes = fs::read(&executable).map_err(|e| format!("Failed to read {}: {}", executable.display(), e))?;
+    for install_name in &install_names {
+        if let Some(patched) = crate::macho::insert_load_dylib(&bytes, install_name).map_err(|e| format!("Cannot add {} to the executable: {}", install_name, e))? {
+            bytes = patched;
+        }
+    }
+    fs::write(&executable, bytes).map_err(|e| format!("Failed to write {}: {}", executable.display(), e))?;
+    Ok(install_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::{self, test_support};
+
+    #[test]
+    fn test_injects_dylibs_and_frameworks() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("Shop.app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("Shop"), test_support::thin_macho_with_bitcode(macho::CPU_TYPE_ARM64)).unwrap();
+        let tweaks = dir.path().join("tweaks");
+        fs::create_dir_all(tweaks.join("Reveal.framework")).unwrap();
+        fs::write(tweaks.join("libTweak.dylib"), test_support::thin_macho(macho::CPU_TYPE_ARM64, None)).unwrap();
+        fs::write(tweaks.join("Reveal.framework/RevealServer"), test_support::thin_macho(macho::CPU_TYPE_ARM64, None)).unwrap();
+        let items = [tweaks.join("libTweak.dylib"), tweaks.join("Reveal.framework")].map(|p| p.display().to_string()).to_vec();
+
+        let added = inject(&app, &items).unwrap();
+        assert_eq!(added, ["@executable_path/Frameworks/libTweak.dylib", "@executable_path/Frameworks/Reveal.framework/RevealServer"]);
+        assert!(app.join("Frameworks/libTweak.dylib").is_file() && app.join("Frameworks/Reveal.framework/RevealServer").is_file());
+        assert_eq!(macho::linked_dylibs(&fs::read(app.join("Shop")).unwrap()).unwrap(), added);
+        assert!(inject(&app, &items).unwrap_err().contains("already exists"));
+
+        assert!(validate(&[tweaks.join("libMissing.dylib").display().to_string()]).unwrap_err().contains("does not exist"));
+        assert!(validate(&["tweak.so".to_string()]).unwrap_err().contains("not a .dylib"));
+    }
+}
@@ -0,0 +1,306 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::network::NetworkSettings;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// A cached download, keyed by where it came from. Several entries can share one blob when
+/// their contents are identical.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    /// The URL or network path as written in the app configuration.
+    pub source: String,
+    /// ETag / Last-Modified / size of the source when it was fetched; a change means the
+    /// cached copy is stale.
+    pub validator: String,
+    pub sha256: String,
+    pub file_name: String,
+    pub size: u64,
+    pub fetched_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+    pub hits: u64,
+}
+
+/// Local copies of input ZIPs that live on web servers or network shares, stored under the
+/// data directory as `<sha256>/<file name>` and evicted least-recently-used first once they
+/// take more than `max_bytes`.
+#[derive(Debug, Clone)]
+pub struct InputCache {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    /// Proxy and CA bundle used for URL inputs.
+    pub network: NetworkSettings,
+}
+
+/// Whether `input` has to go through the cache: an `http(s)://` URL or a UNC path.
+pub fn is_remote_input(input: &str) -> bool {
+    let input = input.trim();
+    is_url(input) || input.starts_with(r"\\") || input.starts_with("//")
+}
+
+pub fn is_url(input: &str) -> bool {
+    let lower = input.trim().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+impl InputCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes, network: NetworkSettings::default() }
+    }
+
+    /// Returns a local path for `source`, fetching it only when it is not cached yet or the
+    /// source changed since. `progress` receives bytes done and total (0 when unknown).
+    pub fn resolve(&self, source: &str, progress: &dyn Fn(u64, u64)) -> Result<PathBuf, String> {
+        let source = source.trim();
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let mut entries = self.entries();
+        let cached = entries.iter().position(|e| e.source == source && self.blob_path(e).is_file());
+
+        let validator = match current_validator(source, &self.network) {
+            Ok(v) => v,
+            Err(e) => match cached {
+                // Offline (or offline mode) or share unreachable: a stale copy beats no build.
+                Some(idx) => {
+                    log::warn!("Could not check {} ({}); using the cached copy", source, e);
+                    return self.touch(&mut entries, idx);
+                }
+                None => return Err(e),
+            },
+        };
+        if let Some(idx) = cached.filter(|&idx| entries[idx].validator == validator) {
+            log::info!("Input cache hit for {}", source);
+            return self.touch(&mut entries, idx);
+        }
+
+        log::info!("Fetching {} into the input cache", source);
+        let part_path = self.dir.join(format!("{}.part", uuid::Uuid::new_v4()));
+        let fetched = fetch_hashed(source, &part_path, &self.network, progress);
+        let (sha256, size) = match fetched {
+            Ok(f) => f,
+            Err(e) => {
+                let _ = fs::remove_file(&part_path);
+                return Err(e);
+            }
+        };
+        let file_name = source_file_name(source);
+        let blob_dir = self.dir.join(&sha256);
+        let blob = blob_dir.join(&file_name);
+        if blob.is_file() {
+            let _ = fs::remove_file(&part_path);
+        } else {
+            fs::create_dir_all(&blob_dir)
+                .and_then(|_| fs::rename(&part_path, &blob))
+                .map_err(|e| format!("Failed to store {} in the input cache: {}", source, e))?;
+        }
+
+        let now = Utc::now();
+        entries.retain(|e| e.source != source);
+        entries.push(CacheEntry {
+            source: source.to_string(),
+            validator,
+            sha256,
+            file_name,
+            size,
+            fetched_at: now,
+            last_used: now,
+            hits: 0,
+        });
+        self.save(&entries)?;
+        self.evict()?;
+        Ok(blob)
+    }
+
+    fn touch(&self, entries: &mut [CacheEntry], idx: usize) -> Result<PathBuf, String> {
+        entries[idx].last_used = Utc::now();
+        entries[idx].hits += 1;
+        let path = self.blob_path(&entries[idx]);
+        self.save(entries)?;
+        Ok(path)
+    }
+
+    fn blob_path(&self, entry: &CacheEntry) -> PathBuf {
+        self.dir.join(&entry.sha256).join(&entry.file_name)
+    }
+
+    /// Cached entries, most recently used first.
+    pub fn entries(&self) -> Vec<CacheEntry> {
+        let mut entries: Vec<CacheEntry> = fs::read_to_string(self.dir.join(INDEX_FILE_NAME))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+        entries
+    }
+
+    /// Bytes on disk, counting blobs shared by several entries once.
+    pub fn total_size(&self) -> u64 {
+        blob_bytes(&self.entries())
+    }
+
+    /// Drops least recently used entries until the cache fits in `max_bytes`. The most
+    /// recently used entry is always kept so the build that just fetched it can proceed.
+    /// Returns the number of bytes freed.
+    pub fn evict(&self) -> Result<u64, String> {
+        let mut entries = self.entries();
+        let before = blob_bytes(&entries);
+        while entries.len() > 1 && blob_bytes(&entries) > self.max_bytes {
+            let evicted = entries.pop().expect("more than one entry");
+            log::info!("Evicting {} from the input cache", evicted.source);
+        }
+        self.save(&entries)?;
+        self.remove_unreferenced_blobs(&entries);
+        Ok(before - blob_bytes(&entries))
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.save(&[])?;
+        self.remove_unreferenced_blobs(&[]);
+        Ok(())
+    }
+
+    fn save(&self, entries: &[CacheEntry]) -> Result<(), String> {
+        let index = self.dir.join(INDEX_FILE_NAME);
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE_NAME));
+        let json = serde_json::to_string_pretty(entries).map_err(|e| format!("Failed to serialize cache index: {}", e))?;
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, json))
+            .and_then(|_| fs::rename(&tmp, &index))
+            .map_err(|e| format!("Failed to write {}: {}", index.display(), e))
+    }
+
+    fn remove_unreferenced_blobs(&self, entries: &[CacheEntry]) {
+        let Ok(dirs) = fs::read_dir(&self.dir) else { return };
+        for dir in dirs.filter_map(|d| d.ok()).map(|d| d.path()).filter(|p| p.is_dir()) {
+            let name = dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            if !entries.iter().any(|e| e.sha256 == name) {
+                if let Err(e) = fs::remove_dir_all(&dir) {
+                    log::warn!("Failed to remove {}: {}", dir.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Bytes used by `entries` on disk, counting shared blobs once.
+pub fn blob_bytes(entries: &[CacheEntry]) -> u64 {
+    let mut seen = std::collections::HashSet::new();
+    entries.iter().filter(|e| seen.insert(&e.sha256)).map(|e| e.size).sum()
+}
+
+/// Cheap fingerprint of the source that changes when its contents do. URLs are checked
+/// through `network`'s proxy.
+pub fn current_validator(source: &str, network: &NetworkSettings) -> Result<String, String> {
+    if is_url(source) {
+        let response = network.http_agent()?.head(source).call().map_err(|e| format!("Failed to reach {}: {}", source, e))?;
+        let header = |name| response.header(name).unwrap_or("").to_string();
+        Ok(format!("etag={};modified={};length={}", header("ETag"), header("Last-Modified"), header("Content-Length")))
+    } else {
+        let metadata = fs::metadata(source).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        Ok(format!("size={};modified={}", metadata.len(), modified))
+    }
+}
+
+/// Copies `source` to `dest`, returning its SHA-256 and size.
+fn fetch_hashed(source: &str, dest: &Path, network: &NetworkSettings, progress: &dyn Fn(u64, u64)) -> Result<(String, u64), String> {
+    let (mut reader, total): (Box<dyn Read>, u64) = if is_url(source) {
+        let response = network.http_agent()?.get(source).call().map_err(|e| format!("Failed to download {}: {}", source, e))?;
+        let total = response.header("Content-Length").and_then(|l| l.parse().ok()).unwrap_or(0);
+        (Box::new(response.into_reader()), total)
+    } else {
+        let file = File::open(source).map_err(|e| format!("Failed to open {}: {}", source, e))?;
+        let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+        (Box::new(file), total)
+    };
+
+    let write_err = |e: io::Error| format!("Failed to fetch {}: {}", source, e);
+    let mut out = File::create(dest).map_err(write_err)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut done = 0u64;
+    let mut last_reported = 0u64;
+    progress(0, total);
+    loop {
+        let read = reader.read(&mut buffer).map_err(write_err)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        out.write_all(&buffer[..read]).map_err(write_err)?;
+        done += read as u64;
+        if done - last_reported >= 1024 * 1024 {
+            progress(done, total);
+            last_reported = done;
+        }
+    }
+    out.sync_all().map_err(write_err)?;
+    progress(done, total.max(done));
+    Ok((format!("{:x}", hasher.finalize()), done))
+}
+
+/// Last path segment of a URL or path, without any query string.
+fn source_file_name(source: &str) -> String {
+    source
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(source)
+        .rsplit(['/', '\\'])
+        .find(|segment| !segment.is_empty())
+        .filter(|segment| !segment.contains(':'))
+        .unwrap_or("input.zip")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuses_cached_copy_and_evicts_lru() {
+        let dir = tempfile::tempdir().unwrap();
+        let share = dir.path().join("share");
+        fs::create_dir_all(&share).unwrap();
+        let (a, b) = (share.join("a.zip"), share.join("b.zip"));
+        fs::write(&a, vec![1u8; 600]).unwrap();
+        fs::write(&b, vec![2u8; 600]).unwrap();
+        let cache = InputCache::new(dir.path().join("cache"), 1000);
+
+        let first = cache.resolve(&a.to_string_lossy(), &|_, _| {}).unwrap();
+        let second = cache.resolve(&a.to_string_lossy(), &|_, _| {}).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.file_name().unwrap(), "a.zip");
+        assert_eq!(cache.entries()[0].hits, 1);
+
+        // Fetching b pushes the cache over its limit, so the older a is evicted.
+        cache.resolve(&b.to_string_lossy(), &|_, _| {}).unwrap();
+        let entries = cache.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, b.to_string_lossy());
+        assert!(!first.exists());
+        assert_eq!(cache.total_size(), 600);
+
+        cache.clear().unwrap();
+        assert!(cache.entries().is_empty());
+
+        let offline = InputCache { network: NetworkSettings { offline: true, ..Default::default() }, ..cache.clone() };
+        let err = offline.resolve("https://ci.example.com/Runner.app.zip", &|_, _| {}).unwrap_err();
+        assert!(err.starts_with("Offline mode is on"), "{}", err);
+        assert!(offline.resolve(&a.to_string_lossy(), &|_, _| {}).is_ok());
+
+        assert!(is_remote_input("https://ci.example.com/Runner.app.zip"));
+        assert!(is_remote_input(r"\\nas\builds\Runner.app.zip"));
+        assert!(!is_remote_input("/Users/me/Runner.app.zip"));
+        assert_eq!(source_file_name("https://ci.example.com/a/Runner.app.zip?token=1"), "Runner.app.zip");
+    }
+}
@@ -0,0 +1,127 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use notify::event::ModifyKind;
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+enum WatchEvent {
+    Changed,
+    Error(String),
+}
+
+/// Watches one config's input ZIP so it can be rebuilt when the file changes. Unlike AutoCheck,
+/// which picks up new ZIPs anywhere in a folder, this follows a single known file.
+///
+/// The parent directory is watched rather than the file itself, so a ZIP replaced by a rename
+/// (as most build tools and copy utilities do) or deleted and written again is still seen.
+pub struct InputZipWatcher {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<WatchEvent>,
+    error: Option<String>,
+}
+
+impl InputZipWatcher {
+    pub fn start(path: &Path) -> Result<Self, String> {
+        if crate::input_cache::is_url(&path.to_string_lossy()) {
+            return Err("Only local input ZIPs can be watched".to_string());
+        }
+        let (Some(dir), Some(file_name)) = (path.parent().filter(|d| d.is_dir()), path.file_name()) else {
+            return Err(format!("The folder of {} does not exist", path.display()));
+        };
+        let file_name: OsString = file_name.to_os_string();
+
+        let (tx, rx) = mpsc::channel::<WatchEvent>();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| match res {
+                Ok(ev) => {
+                    let relevant = matches!(
+                        ev.kind,
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+                    );
+                    if relevant && ev.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) {
+                        let _ = tx.send(WatchEvent::Changed);
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Input ZIP watcher error: {}", e);
+                    let _ = tx.send(WatchEvent::Error(e.to_string()));
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| format!("Input watcher init error: {}", e))?;
+
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Input watcher start error: {}", e))?;
+
+        log::info!("Watching input ZIP for changes: {}", path.display());
+        Ok(Self { path: path.to_path_buf(), _watcher: watcher, rx, error: None })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file was written, created or renamed into place since the last call. A single
+    /// copy produces many events; they are all folded into one `true`.
+    pub fn take_changed(&mut self) -> bool {
+        let mut changed = false;
+        for event in self.rx.try_iter() {
+            match event {
+                WatchEvent::Changed => changed = true,
+                WatchEvent::Error(e) => self.error = Some(e),
+            }
+        }
+        changed
+    }
+
+    /// The last error the watcher reported; the caller should start a new one.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for_change(watcher: &mut InputZipWatcher) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if watcher.take_changed() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_reports_changes_to_the_watched_file_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        std::fs::write(&input, b"v1").unwrap();
+        let mut watcher = InputZipWatcher::start(&input).unwrap();
+
+        std::fs::write(dir.path().join("Other.app.zip"), b"other").unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!watcher.take_changed());
+
+        std::fs::write(&input, b"v2").unwrap();
+        assert!(wait_for_change(&mut watcher));
+
+        let staged = dir.path().join("Runner.app.zip.part");
+        std::fs::write(&staged, b"v3").unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        watcher.take_changed();
+        std::fs::rename(&staged, &input).unwrap();
+        assert!(wait_for_change(&mut watcher));
+
+        assert!(InputZipWatcher::start(&dir.path().join("missing/Runner.app.zip")).is_err());
+        assert!(InputZipWatcher::start(Path::new("https://ci.example.com/Runner.app.zip")).is_err());
+    }
+}
@@ -0,0 +1,1700 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use zip::result::ZipError;
+use zip::write::FileOptions;
+use walkdir::WalkDir;
+use tempfile::tempdir;
+use thiserror::Error;
+
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppConfig;
+use crate::compression::CompressionSettings;
+use crate::permissions::PermissionRules;
+use crate::dsym::DsymPlacement;
+use crate::info_plist::BundleVariables;
+use crate::naming::{resolve_output_path, resolve_templated_output_path, sanitize_ipa_file_name, validate_ipa_file_name};
+
+#[derive(Error, Debug)]
+pub enum IpaError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] ZipError),
+    #[error("WalkDir error: {0}")]
+    WalkDir(#[from] walkdir::Error),
+    #[error("Temporary directory creation failed: {0}")]
+    TempDir(std::io::Error),
+    #[error("Input file '{0}' not found")]
+    InputFileNotFound(PathBuf),
+    #[error("Output directory '{0}' not found or is not a directory")]
+    OutputDirectoryInvalid(PathBuf),
+    #[error("The structure of the zip file is not as expected. Could not find a top-level .app directory or a nested one.")]
+    UnexpectedZipStructure(PathBuf),
+    #[error("Failed to create Payload directory at {0}")]
+    PayloadCreationFailed(PathBuf),
+    #[error("Failed to move/copy .app bundle to Payload directory: {0}")]
+    MoveToPayloadFailed(PathBuf),
+    #[error("Final IPA file name '{name}' is invalid: {reason}. Suggested name: '{suggestion}'")]
+    InvalidIpaName { name: String, reason: String, suggestion: String },
+    #[error("Generated IPA has invalid structure: {0}")]
+    InvalidIpaStructure(String),
+    #[error("Failed to copy the IPA to '{path}': {reason}")]
+    OutputCopyFailed { path: PathBuf, reason: String },
+    #[error("{stage} hook failed: {reason}")]
+    HookFailed { stage: &'static str, reason: String },
+    #[error("Build worker stopped unexpectedly")]
+    WorkerStopped,
+    #[error("Remote build agent: {0}")]
+    RemoteAgent(String),
+    #[error("Failed to fetch the input ZIP: {0}")]
+    InputFetch(String),
+    #[error("Sidecar artifacts: {0}")]
+    Sidecar(String),
+    #[error("dSYM: {0}")]
+    Dsym(String),
+    #[error("Info.plist overrides: {0}")]
+    PlistOverrides(String),
+    #[error("'{0}' already exists; rename the output IPA or change what the app does when it exists")]
+    OutputExists(PathBuf),
+    #[error("Code signing failed: {0}")]
+    BundleSigning(String),
+    #[error("Dylib injection: {0}")]
+    Injection(String),
+    #[error("Signing the IPA failed: {0}")]
+    ArtifactSigning(String),
+    #[error("OTA manifest: {0}")]
+    OtaManifest(String),
+    #[error("Uploading to the bucket failed: {0}")]
+    ObjectStorage(String),
+    /// The config's channel is not among the channels in the settings, e.g. after a rename.
+    #[error("Unknown channel '{0}'")]
+    UnknownChannel(String),
+    #[error("Build cancelled")]
+    Cancelled,
+}
+
+/// Stops a running build from another thread. The build checks it between archive entries and
+/// files, cleans up its partial output and fails with [`IpaError::Cancelled`].
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn check(&self) -> Result<(), IpaError> {
+        if self.is_cancelled() {
+            Err(IpaError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+/// Knobs for a single generation that are not part of the `AppConfig`: global settings
+/// and whatever the user supplied when triggering the build.
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Write the IPA to a local temp directory first, then copy it to the output
+    /// directory with checksum verification and retries.
+    pub stage_output_locally: bool,
+    pub output_copy_attempts: u32,
+    pub release_notes: Option<String>,
+    /// Shell command run before extraction; a failure aborts the build.
+    pub pre_build_hook: Option<String>,
+    /// Shell command run after the build, whether it succeeded or not.
+    pub post_build_hook: Option<String>,
+    /// Delete framework copies in app extensions that are identical to the app's own, when the
+    /// extension can load them from the app instead.
+    pub dedupe_frameworks: bool,
+    /// Leave `.dSYM`, `.bcsymbolmap` and `.swiftmodule` items found inside the `.app` out of
+    /// the Payload.
+    pub exclude_debug_symbols: bool,
+    /// Remove `__LLVM` bitcode segments from the app's binaries; see
+    /// [`crate::thinning::strip_bitcode`].
+    pub strip_bitcode: bool,
+    /// Copy the `.app` entries of the input ZIP into the IPA as they are, without extracting and
+    /// recompressing them, when the input allows it (see [`crate::remap::plan`]). Ignored when the
+    /// Payload has to be modified.
+    pub package_from_input_zip: bool,
+    /// Compression method, level and per-extension rules for files in the IPA. Entries copied by
+    /// [`Self::package_from_input_zip`] keep the input's compression.
+    pub compression: CompressionSettings,
+    /// Give every entry written by IPA Builder, and the BuildInfo `built_at`, the time from
+    /// [`reproducible_timestamp`], so building the same input twice gives byte-identical IPAs.
+    pub deterministic: bool,
+    /// Build on an `ipa_builder agent` instead of this machine. Hooks still run locally.
+    pub remote_agent: Option<crate::agent::RemoteAgent>,
+    /// Where inputs on web servers and network shares are cached. URL inputs need one.
+    pub input_cache: Option<crate::input_cache::InputCache>,
+    /// Detached signature written next to every successful build, including remote ones.
+    pub artifact_signing: crate::artifact_signing::ArtifactSigning,
+    /// Write `<name>.ipa.sha256` next to every successful build; see
+    /// [`crate::transfer::write_checksum_file`].
+    pub write_checksum: bool,
+    /// Proxy and offline mode for uploads to the config's [`crate::object_storage::S3Destination`].
+    pub network: crate::network::NetworkSettings,
+    /// Rate limit of those uploads in bytes per second; 0 is unlimited.
+    pub upload_bytes_per_sec: u64,
+    pub cancel: CancelToken,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            stage_output_locally: false,
+            output_copy_attempts: 4,
+            release_notes: None,
+            pre_build_hook: None,
+            post_build_hook: None,
+            dedupe_frameworks: false,
+            exclude_debug_symbols: false,
+            strip_bitcode: false,
+            package_from_input_zip: false,
+            compression: CompressionSettings::default(),
+            deterministic: false,
+            remote_agent: None,
+            input_cache: None,
+            artifact_signing: crate::artifact_signing::ArtifactSigning::default(),
+            write_checksum: false,
+            network: crate::network::NetworkSettings::default(),
+            upload_bytes_per_sec: 0,
+            cancel: CancelToken::default(),
+        }
+    }
+}
+
+/// Size of the buffer payload files are streamed through when compressing.
+const ZIP_COPY_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Files at least this large get ZIP64 sizes. Deflate can grow incompressible data slightly, so
+/// it stays well below the 4 GiB limit of the classic headers.
+const ZIP64_FILE_THRESHOLD: u64 = u32::MAX as u64 - 64 * 1024 * 1024;
+
+/// Name of the build metadata file stored at the root of every generated IPA, next to `Payload/`.
+pub const BUILD_INFO_FILE_NAME: &str = "BuildInfo.json";
+
+/// Metadata about how an IPA was produced, embedded as [`BUILD_INFO_FILE_NAME`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuildInfo {
+    pub app_name: String,
+    pub source_zip: String,
+    pub built_at: DateTime<Utc>,
+    pub builder_version: String,
+    pub release_notes: Option<String>,
+    /// The build's [`crate::channels::BuildChannel`], if it had one.
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Files copied to [`crate::sidecar::sidecar_dir`] with this IPA.
+    #[serde(default)]
+    pub sidecars: Vec<crate::sidecar::SidecarFile>,
+    /// Everything the build changed in the `.app` relative to the input, so an audit can tell
+    /// exactly what was modified.
+    #[serde(default)]
+    pub changes: Vec<PayloadChange>,
+}
+
+/// One way the Payload differs from the `.app` in the input ZIP.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PayloadChange {
+    pub kind: PayloadChangeKind,
+    /// Relative to the `.app` bundle, with `/` separators; empty for the bundle as a whole.
+    pub path: String,
+    /// What changed, e.g. `0644 → 0755` or `CFBundleVersion: 41 → 42`.
+    #[serde(default)]
+    pub detail: String,
+}
+
+impl PayloadChange {
+    pub fn new(kind: PayloadChangeKind, path: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { kind, path: path.into(), detail: detail.into() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadChangeKind {
+    /// macOS/Windows metadata left out (see [`crate::junk::is_junk`]).
+    JunkRemoved,
+    /// A symbolic link stored as a regular file holding its target.
+    SymlinkFlattened,
+    PlistPatched,
+    DylibInjected,
+    Thinned,
+    BitcodeStripped,
+    DebugArtifactExcluded,
+    FrameworkDeduplicated,
+    Signed,
+    /// The file was stored with different Unix permissions than it had in the input.
+    PermissionsChanged,
+}
+
+impl BuildInfo {
+    pub fn new(config: &AppConfig, options: &BuildOptions) -> Self {
+        Self {
+            app_name: config.app_name.clone(),
+            source_zip: Path::new(&config.input_zip_path)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            built_at: if options.deterministic { reproducible_timestamp() } else { Utc::now() },
+            builder_version: env!("CARGO_PKG_VERSION").to_string(),
+            release_notes: options.release_notes.clone(),
+            channel: Some(config.channel.trim().to_string()).filter(|c| !c.is_empty()),
+            sidecars: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+/// Time used by [`BuildOptions::deterministic`] builds: `SOURCE_DATE_EPOCH` (seconds since the
+/// Unix epoch, the reproducible-builds convention) when it is set to a time a ZIP can hold,
+/// otherwise 1980-01-01 00:00 UTC, the earliest one.
+pub fn reproducible_timestamp() -> DateTime<Utc> {
+    let dos_epoch = DateTime::<Utc>::from_naive_utc_and_offset(
+        chrono::NaiveDate::from_ymd_opt(1980, 1, 1).unwrap_or_default().and_hms_opt(0, 0, 0).unwrap_or_default(),
+        Utc,
+    );
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .filter(|time| *time >= dos_epoch && time.year() <= 2107)
+        .unwrap_or(dos_epoch)
+}
+
+/// `time` as a ZIP entry modification time.
+pub(crate) fn zip_time(time: DateTime<Utc>) -> zip::DateTime {
+    zip::DateTime::from_date_and_time(time.year() as u16, time.month() as u8, time.day() as u8, time.hour() as u8, time.minute() as u8, time.second() as u8)
+        .unwrap_or_default()
+}
+
+/// Reads the [`BuildInfo`] embedded in an IPA, if it was produced by IPA Builder.
+pub fn read_build_info(ipa_path: &Path) -> Result<Option<BuildInfo>, IpaError> {
+    let mut archive = zip::ZipArchive::new(File::open(ipa_path)?)?;
+    let mut entry = match archive.by_name(BUILD_INFO_FILE_NAME) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut json = String::new();
+    entry.read_to_string(&mut json)?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| IpaError::InvalidIpaStructure(format!("Unreadable {}: {}", BUILD_INFO_FILE_NAME, e)))
+}
+
+
+/// Generates an IPA file from a Runner.app.zip file.
+///
+/// Steps:
+/// 1. Create a temporary directory.
+/// 2. Extract the input `Runner.app.zip` into the temporary directory.
+/// 3. Locate the `.app` bundle (it might be nested, e.g., `SomeFolder/Runner.app` or just `Runner.app`).
+/// 4. Create a `Payload` directory in a new temporary location for IPA creation.
+/// 5. Move/copy the found `.app` bundle into this `Payload` directory.
+/// 6. Compress the `Payload` directory into a new .zip file.
+/// 7. Rename this .zip file to `app_name.ipa` and save it to the `output_directory`.
+pub fn generate_ipa(config: &AppConfig, output_dir: &Path) -> Result<PathBuf, IpaError> {
+    generate_ipa_with_options(config, output_dir, &BuildOptions::default()).map(|output| output.ipa_path)
+}
+
+/// A successfully generated IPA plus anything worth pointing out about it.
+#[derive(Debug, Clone)]
+pub struct BuildOutput {
+    pub ipa_path: PathBuf,
+    /// Non-fatal problems, e.g. embedded frameworks that will break installation.
+    pub warnings: Vec<String>,
+    /// The app was built for the simulator and cannot be installed on devices.
+    pub is_simulator_build: bool,
+    /// The packaged app's `Info.plist` values, when they could be read.
+    pub bundle: Option<BundleVariables>,
+    /// SHA-256 of the IPA, when [`BuildOptions::write_checksum`] wrote it next to the IPA.
+    pub sha256: Option<String>,
+    /// URL of the IPA in the config's S3 bucket, when it was uploaded there.
+    pub uploaded_url: Option<String>,
+}
+
+/// Stage of a running build, reported through [`generate_ipa_with_progress`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildPhase {
+    PreBuildHook,
+    /// Downloading or copying a remote input into the input cache; counted in bytes.
+    FetchingInput,
+    /// Sending the input ZIP to a remote agent; counted in bytes.
+    Uploading,
+    Extracting,
+    Copying,
+    Analyzing,
+    /// Signing the app bundle with the config's developer identity.
+    Signing,
+    Compressing,
+    CopyingOutput,
+    /// Fetching the IPA from a remote agent; counted in bytes.
+    Downloading,
+    /// Checking the packaged app against its code signature seal.
+    Verifying,
+    /// Sending the IPA and the files next to it to the config's S3 bucket; counted in bytes.
+    UploadingOutput,
+    PostBuildHook,
+}
+
+impl BuildPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            BuildPhase::PreBuildHook => "Running pre-build hook",
+            BuildPhase::FetchingInput => "Fetching input",
+            BuildPhase::Uploading => "Uploading to build agent",
+            BuildPhase::Extracting => "Extracting",
+            BuildPhase::Copying => "Copying",
+            BuildPhase::Analyzing => "Analyzing",
+            BuildPhase::Signing => "Signing",
+            BuildPhase::Compressing => "Compressing",
+            BuildPhase::CopyingOutput => "Copying to output",
+            BuildPhase::Downloading => "Downloading from build agent",
+            BuildPhase::Verifying => "Verifying signature",
+            BuildPhase::UploadingOutput => "Uploading to bucket",
+            BuildPhase::PostBuildHook => "Running post-build hook",
+        }
+    }
+
+    /// Share of the overall build completed before this phase starts, and the phase's own share.
+    fn span(self) -> (f32, f32) {
+        match self {
+            BuildPhase::PreBuildHook => (0.0, 0.0),
+            // Shown as steps of their own before extraction starts and after the IPA is written.
+            BuildPhase::FetchingInput | BuildPhase::Uploading | BuildPhase::UploadingOutput => (0.0, 1.0),
+            BuildPhase::Extracting => (0.0, 0.3),
+            BuildPhase::Copying => (0.3, 0.2),
+            BuildPhase::Analyzing => (0.5, 0.05),
+            BuildPhase::Signing => (0.55, 0.0),
+            BuildPhase::Compressing => (0.55, 0.4),
+            BuildPhase::CopyingOutput | BuildPhase::Downloading => (0.95, 0.05),
+            BuildPhase::Verifying | BuildPhase::PostBuildHook => (1.0, 0.0),
+        }
+    }
+}
+
+/// Progress within a [`BuildPhase`], counted in archive entries, files or bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildProgress {
+    pub phase: BuildPhase,
+    pub done: u64,
+    pub total: u64,
+}
+
+impl BuildProgress {
+    /// Estimated completion of the whole build, from 0.0 to 1.0.
+    pub fn overall_fraction(&self) -> f32 {
+        let (start, share) = self.phase.span();
+        let within = if self.total == 0 { 0.0 } else { self.done as f32 / self.total as f32 };
+        (start + share * within).min(1.0)
+    }
+
+    /// e.g. `120/800` or `1.5 MB/40.0 MB`; empty when the phase has no count.
+    pub fn detail(&self) -> String {
+        match (self.phase, self.total) {
+            (_, 0) => String::new(),
+            (BuildPhase::FetchingInput | BuildPhase::Uploading | BuildPhase::Downloading | BuildPhase::UploadingOutput, total) => {
+                format!("{}/{}", crate::size_analysis::format_size(self.done), crate::size_analysis::format_size(total))
+            }
+            (_, total) => format!("{}/{}", self.done, total),
+        }
+    }
+}
+
+/// Reports at most about a hundred updates per phase, plus the first and the last.
+fn report_progress(progress: &dyn Fn(BuildProgress), phase: BuildPhase, done: u64, total: u64) {
+    let step = (total / 100).max(1);
+    if done == 0 || done == total || done.is_multiple_of(step) {
+        progress(BuildProgress { phase, done, total });
+    }
+}
+
+/// Same as [`generate_ipa`], with explicit [`BuildOptions`]. The configured hooks run around
+/// the build with the variables from [`crate::hooks::hook_variables`].
+pub fn generate_ipa_with_options(config: &AppConfig, output_dir: &Path, options: &BuildOptions) -> Result<BuildOutput, IpaError> {
+    generate_ipa_with_progress(config, output_dir, options, &|_| {})
+}
+
+/// Same as [`generate_ipa_with_options`], calling `progress` as the build moves through its phases.
+pub fn generate_ipa_with_progress(
+    config: &AppConfig,
+    output_dir: &Path,
+    options: &BuildOptions,
+    progress: &dyn Fn(BuildProgress),
+) -> Result<BuildOutput, IpaError> {
+    if let Some(hook) = options.pre_build_hook.as_deref().filter(|h| !h.trim().is_empty()) {
+        report_progress(progress, BuildPhase::PreBuildHook, 0, 1);
+        let vars = crate::hooks::hook_variables(config, output_dir, None, None);
+        crate::hooks::run_hook(hook, &vars).map_err(|reason| IpaError::HookFailed { stage: "Pre-build", reason })?;
+    }
+
+    let result = crate::sidecar::collect(&config.sidecars).map_err(IpaError::Sidecar).and_then(|sidecars| {
+        crate::dsym::validate(&config.dsym_path).map_err(IpaError::Dsym)?;
+        config.ota_manifest.validate().map_err(IpaError::OtaManifest)?;
+        config.object_storage.validate().map_err(IpaError::ObjectStorage)?;
+        let config = resolve_input(config, options, progress)?;
+        options.cancel.check()?;
+        let mut output = match &options.remote_agent {
+            Some(agent) => crate::agent::build_remotely(agent, &config, output_dir, options, progress).and_then(|mut output| {
+                output.warnings.extend(validate_generated_ipa(&output.ipa_path)?);
+                Ok(output)
+            }),
+            None => build_ipa(&config, output_dir, options, &sidecars, progress),
+        }?;
+        report_progress(progress, BuildPhase::Verifying, 0, 1);
+        match crate::codesign::check_ipa_seal(&output.ipa_path) {
+            Ok(Some(seal)) => output.warnings.extend(seal.warnings()),
+            Ok(None) => {}
+            Err(e) => output.warnings.push(format!("Could not check the code signature: {}", e)),
+        }
+        report_progress(progress, BuildPhase::Verifying, 1, 1);
+        crate::sidecar::copy_next_to(&sidecars, &output.ipa_path, options.output_copy_attempts).map_err(IpaError::Sidecar)?;
+        if !config.dsym_path.trim().is_empty() && config.dsym_placement == DsymPlacement::NextToIpa {
+            crate::dsym::write_next_to(Path::new(config.dsym_path.trim()), &output.ipa_path, options.output_copy_attempts).map_err(IpaError::Dsym)?;
+        }
+        let comment = format!("{}, built by IPA Builder {}", config.app_name, env!("CARGO_PKG_VERSION"));
+        options.artifact_signing.sign(&output.ipa_path, &comment).map_err(IpaError::ArtifactSigning)?;
+        if options.write_checksum {
+            output.sha256 = Some(crate::transfer::write_checksum_file(&output.ipa_path)?);
+        }
+        if config.ota_manifest.enabled {
+            let bundle = output.bundle.as_ref().ok_or_else(|| IpaError::OtaManifest("the app's Info.plist could not be read".to_string()))?;
+            config.ota_manifest.write_next_to(&output.ipa_path, bundle, &config.app_name).map_err(IpaError::OtaManifest)?;
+        }
+        if config.object_storage.enabled {
+            let report = |done, total| progress(BuildProgress { phase: BuildPhase::UploadingOutput, done, total });
+            let url = config.object_storage.upload_build(&output.ipa_path, &options.network, options.upload_bytes_per_sec, &report).map_err(IpaError::ObjectStorage)?;
+            output.uploaded_url = Some(url);
+        }
+        Ok(output)
+    });
+
+    if let Some(hook) = options.post_build_hook.as_deref().filter(|h| !h.trim().is_empty()) {
+        report_progress(progress, BuildPhase::PostBuildHook, 0, 1);
+        let vars = crate::hooks::hook_variables(config, output_dir, result.as_ref().ok(), Some(result.is_ok()));
+        let hook_result = crate::hooks::run_hook(hook, &vars);
+        if let (Ok(_), Err(reason)) = (&result, hook_result) {
+            return Err(IpaError::HookFailed { stage: "Post-build", reason });
+        }
+    }
+    result
+}
+
+/// Points `config` at a local copy of its input when the input is a URL or on a network share.
+fn resolve_input(config: &AppConfig, options: &BuildOptions, progress: &dyn Fn(BuildProgress)) -> Result<AppConfig, IpaError> {
+    if !crate::input_cache::is_remote_input(&config.input_zip_path) {
+        return Ok(config.clone());
+    }
+    let local = match &options.input_cache {
+        Some(cache) => cache
+            .resolve(&config.input_zip_path, &|done, total| progress(BuildProgress { phase: BuildPhase::FetchingInput, done, total }))
+            .map_err(IpaError::InputFetch)?,
+        None if crate::input_cache::is_url(&config.input_zip_path) => {
+            return Err(IpaError::InputFetch("URL inputs need the input cache, which has no data directory".to_string()))
+        }
+        // Network paths can still be read in place.
+        None => return Ok(config.clone()),
+    };
+    Ok(AppConfig { input_zip_path: local.to_string_lossy().into_owned(), ..config.clone() })
+}
+
+fn build_ipa(
+    config: &AppConfig,
+    output_dir: &Path,
+    options: &BuildOptions,
+    sidecars: &[crate::sidecar::SidecarFile],
+    progress: &dyn Fn(BuildProgress),
+) -> Result<BuildOutput, IpaError> {
+    log::info!("Starting IPA generation for '{}' from '{}'", config.app_name, std::path::Path::new(&config.input_zip_path).display());
+
+    if !std::path::Path::new(&config.input_zip_path).exists() {
+        return Err(IpaError::InputFileNotFound(config.input_zip_path.clone().into()));
+    }
+    if !output_dir.is_dir() {
+        return Err(IpaError::OutputDirectoryInvalid(output_dir.to_path_buf()));
+    }
+    let ipa_file_name_str = crate::channels::expand_name(config.output_ipa_name.trim(), &config.channel);
+    if let Err(reason) = validate_ipa_file_name(&ipa_file_name_str) {
+        return Err(IpaError::InvalidIpaName {
+            suggestion: sanitize_ipa_file_name(&ipa_file_name_str),
+            name: ipa_file_name_str,
+            reason,
+        });
+    }
+    // Resolved up front so a build that must not overwrite fails before doing any work, unless
+    // the name needs values from the app's Info.plist.
+    let early_ipa_path = if BundleVariables::is_used_in(&ipa_file_name_str) {
+        None
+    } else {
+        Some(resolve_output_path(output_dir, &ipa_file_name_str, config.output_exists, Local::now().naive_local())?)
+    };
+
+    // 1. Open the input Runner.app.zip
+    let input_file = File::open(&config.input_zip_path)?;
+    let mut archive = zip::ZipArchive::new(input_file)?;
+    let ipa_build_temp_dir = tempdir().map_err(IpaError::TempDir)?;
+    let plist_overrides = config.effective_plist_overrides();
+    let mut changes = Vec::new();
+    if config.signing.is_enabled() {
+        config.signing.identity().map_err(IpaError::BundleSigning)?;
+    }
+    let dsym_inside_ipa = !config.dsym_path.trim().is_empty() && config.dsym_placement == DsymPlacement::InsideIpa;
+    // Removing files from the Payload, editing its Info.plist or binaries, changing its file
+    // permissions, signing it or adding symbols next to it needs it on disk.
+    let remap_plan = if options.package_from_input_zip
+        && !options.dedupe_frameworks
+        && !options.exclude_debug_symbols
+        && !options.strip_bitcode
+        && plist_overrides.is_empty()
+        && config.keep_architectures.is_empty()
+        && config.inject_dylibs.is_empty()
+        && !config.signing.is_enabled()
+        && config.permissions.is_empty()
+        && !dsym_inside_ipa
+    {
+        crate::remap::plan(&mut archive).map(|mut plan| {
+            if config.strip_junk_files {
+                let stripped = plan.strip_junk();
+                if !stripped.is_empty() {
+                    log::info!("Leaving {} macOS/Windows metadata entries out of the IPA", stripped.len());
+                }
+                changes.extend(stripped.into_iter().map(|path| PayloadChange::new(PayloadChangeKind::JunkRemoved, path, "")));
+            }
+            plan
+        })
+    } else {
+        None
+    };
+
+    let read_bundle = |app_dir: &Path| match BundleVariables::read(&app_dir.join("Info.plist")) {
+        Ok(bundle) => Some(bundle),
+        Err(e) => {
+            log::warn!("Bundle values unavailable to output names and hooks: {}", e);
+            None
+        }
+    };
+    let (mut warnings, is_simulator_build, bundle) = match &remap_plan {
+        Some(plan) => {
+            log::info!("Packaging '{}' straight from '{}' without extracting it", plan.prefix, config.input_zip_path);
+            report_progress(progress, BuildPhase::Analyzing, 0, 1);
+            let analysis_temp_dir = tempdir().map_err(IpaError::TempDir)?;
+            let app_dir = analysis_temp_dir.path().join(&plan.bundle_name);
+            crate::remap::extract_for_analysis(&mut archive, plan, &app_dir)?;
+            let (warnings, is_simulator_build) = analyze_payload(config, options, &app_dir, plan.debug_artifacts(), &mut changes)?;
+            (warnings, is_simulator_build, read_bundle(&app_dir))
+        }
+        None => {
+            // 2. Extract the input into a temporary directory
+            let extract_temp_dir = tempdir().map_err(IpaError::TempDir)?;
+            log::debug!("Created extraction temp dir: {}", extract_temp_dir.path().display());
+            let symlinks = extract_with_progress(&mut archive, extract_temp_dir.path(), &options.cancel, progress)?;
+            log::info!("Extracted '{}' to '{}'", std::path::Path::new(&config.input_zip_path).file_name().unwrap_or_default().to_string_lossy(), extract_temp_dir.path().display());
+
+            // 3. Locate the .app bundle
+            let app_bundle_path = find_app_bundle(extract_temp_dir.path())?;
+
+            let app_bundle_to_payload = app_bundle_path.ok_or_else(|| IpaError::UnexpectedZipStructure(extract_temp_dir.path().to_path_buf()))?;
+            log::info!("Identified app bundle to be packaged: {}", app_bundle_to_payload.display());
+
+            // 4. Create a `Payload` directory in a new temporary location for IPA creation.
+            let payload_dir = ipa_build_temp_dir.path().join("Payload");
+            fs::create_dir_all(&payload_dir).map_err(|_e| IpaError::PayloadCreationFailed(payload_dir.clone()))?;
+            log::debug!("Created Payload directory: {}", payload_dir.display());
+
+            // 5. Copy the found `.app` bundle into this `Payload` directory.
+            let dest_app_path_in_payload = payload_dir.join(app_bundle_to_payload.file_name().unwrap_or_else(|| std::ffi::OsStr::new("Runner.app")));
+
+            let files_to_copy = WalkDir::new(&app_bundle_to_payload).into_iter().filter_map(|e| e.ok()).filter(|e| !e.file_type().is_dir()).count() as u64;
+            let mut files_copied = 0;
+            report_progress(progress, BuildPhase::Copying, 0, files_to_copy);
+            copy_dir_all(&app_bundle_to_payload, &dest_app_path_in_payload, &mut || {
+                files_copied += 1;
+                report_progress(progress, BuildPhase::Copying, files_copied, files_to_copy);
+                if options.cancel.is_cancelled() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "cancelled"));
+                }
+                Ok(())
+            })
+                .map_err(|e| {
+                    if options.cancel.is_cancelled() {
+                        return IpaError::Cancelled;
+                    }
+                    log::error!("Failed to copy {} to {}: {}", app_bundle_to_payload.display(), dest_app_path_in_payload.display(), e);
+                    IpaError::MoveToPayloadFailed(dest_app_path_in_payload.clone())
+                })?;
+            log::info!("Copied '{}' to '{}'", app_bundle_to_payload.file_name().unwrap_or_default().to_string_lossy(), dest_app_path_in_payload.display());
+            // Repacking an IPA: keep what the pipeline put next to its Payload/.
+            if app_bundle_to_payload.parent() == Some(extract_temp_dir.path().join("Payload").as_path()) {
+                let kept = copy_root_entries(extract_temp_dir.path(), ipa_build_temp_dir.path(), config.strip_junk_files)?;
+                if !kept.is_empty() {
+                    log::info!("Kept the input IPA's root entries: {}", kept.join(", "));
+                }
+            }
+            for (path, target) in &symlinks {
+                if let Ok(relative) = path.strip_prefix(&app_bundle_to_payload) {
+                    let relative = relative.to_string_lossy().replace('\\', "/");
+                    changes.push(PayloadChange::new(PayloadChangeKind::SymlinkFlattened, relative, format!("was a link to {}", target)));
+                }
+            }
+            if config.strip_junk_files {
+                let (removed, freed) = crate::junk::remove_junk(&dest_app_path_in_payload)?;
+                if !removed.is_empty() {
+                    log::info!("Removed {} macOS/Windows metadata items from the Payload ({}): {}", removed.len(), crate::size_analysis::format_size(freed), removed.join(", "));
+                }
+                changes.extend(removed.into_iter().map(|path| PayloadChange::new(PayloadChangeKind::JunkRemoved, path, "")));
+            }
+
+            let plist_changes = if plist_overrides.is_empty() {
+                Vec::new()
+            } else {
+                plist_overrides.apply(&dest_app_path_in_payload.join("Info.plist")).map_err(IpaError::PlistOverrides)?
+            };
+            if !plist_changes.is_empty() {
+                log::info!("Applied Info.plist overrides: {}", plist_changes.join(", "));
+            }
+            changes.extend(plist_changes.iter().map(|change| PayloadChange::new(PayloadChangeKind::PlistPatched, "Info.plist", change.as_str())));
+
+            let injected = crate::injection::inject(&dest_app_path_in_payload, &config.inject_dylibs).map_err(IpaError::Injection)?;
+            if !injected.is_empty() {
+                log::info!("Injected into the main executable: {}", injected.join(", "));
+            }
+            changes.extend(injected.iter().map(|install_name| {
+                PayloadChange::new(PayloadChangeKind::DylibInjected, install_name.trim_start_matches("@executable_path/"), "loaded by the main executable")
+            }));
+
+            report_progress(progress, BuildPhase::Analyzing, 0, 1);
+            let thinning = crate::thinning::thin_bundle(&dest_app_path_in_payload, &config.keep_architectures)?;
+            if !thinning.thinned.is_empty() {
+                log::info!("Thinned {} binaries to {} ({} saved): {}", thinning.thinned.len(), config.keep_architectures.join(", "), crate::size_analysis::format_size(thinning.bytes_saved), thinning.thinned.join(", "));
+            }
+            let kept = format!("kept {}", config.keep_architectures.join(", "));
+            changes.extend(thinning.thinned.iter().map(|path| PayloadChange::new(PayloadChangeKind::Thinned, path.as_str(), kept.as_str())));
+            let bitcode = if options.strip_bitcode {
+                crate::thinning::strip_bitcode(&dest_app_path_in_payload)?
+            } else {
+                Default::default()
+            };
+            if !bitcode.thinned.is_empty() {
+                log::info!("Stripped bitcode from {} binaries ({} saved): {}", bitcode.thinned.len(), crate::size_analysis::format_size(bitcode.bytes_saved), bitcode.thinned.join(", "));
+            }
+            changes.extend(bitcode.thinned.iter().map(|path| PayloadChange::new(PayloadChangeKind::BitcodeStripped, path.as_str(), "")));
+            let debug_artifacts = crate::size_analysis::find_debug_artifacts(&dest_app_path_in_payload);
+            let (mut warnings, is_simulator_build) = analyze_payload(config, options, &dest_app_path_in_payload, debug_artifacts, &mut changes)?;
+            warnings.extend(thinning.skipped.iter().map(|path| format!("{} has no {} slice; left unchanged", path, config.keep_architectures.join("/"))));
+            warnings.extend(bitcode.skipped.iter().map(|path| format!("Could not strip bitcode from {}; left unchanged", path)));
+            if config.signing.is_enabled() {
+                options.cancel.check()?;
+                report_progress(progress, BuildPhase::Signing, 0, 1);
+                config.signing.sign_bundle(&dest_app_path_in_payload).map_err(IpaError::BundleSigning)?;
+                let identity = Path::new(&config.signing.identity_path).file_name().unwrap_or_default().to_string_lossy();
+                changes.push(PayloadChange::new(PayloadChangeKind::Signed, "", format!("signed with {}", identity)));
+            } else if !plist_changes.is_empty() && dest_app_path_in_payload.join("_CodeSignature").exists() {
+                warnings.push("Info.plist was changed by the plist overrides, so the app's existing signature is no longer valid; re-sign the IPA before installing it.".to_string());
+            } else if !(thinning.thinned.is_empty() && bitcode.thinned.is_empty() && injected.is_empty()) && dest_app_path_in_payload.join("_CodeSignature").exists() {
+                warnings.push("Binaries were injected, thinned or stripped of bitcode, so the app's existing signature is no longer valid; re-sign the IPA before installing it.".to_string());
+            }
+            changes.extend(permission_changes(ipa_build_temp_dir.path(), &dest_app_path_in_payload, &config.permissions)?);
+            if dsym_inside_ipa {
+                crate::dsym::add_to_build_root(Path::new(config.dsym_path.trim()), ipa_build_temp_dir.path()).map_err(IpaError::Dsym)?;
+            }
+            (warnings, is_simulator_build, read_bundle(&dest_app_path_in_payload))
+        }
+    };
+    let final_ipa_path = match early_ipa_path {
+        Some(path) => path,
+        None => {
+            let bundle = bundle.as_ref().ok_or_else(|| IpaError::InvalidIpaStructure(format!("'{}' uses Info.plist values, but the app's Info.plist could not be read", ipa_file_name_str)))?;
+            resolve_templated_output_path(output_dir, &ipa_file_name_str, bundle, config.output_exists, Local::now().naive_local())?
+        }
+    };
+
+    // 6. Compress the `Payload` directory into a new .zip file.
+    let staging_dir = if options.stage_output_locally {
+        Some(tempdir().map_err(IpaError::TempDir)?)
+    } else {
+        None
+    };
+    let zip_target_path = match &staging_dir {
+        Some(dir) => dir.path().join(final_ipa_path.file_name().unwrap_or_default()),
+        None => final_ipa_path.clone(),
+    };
+    let build_info = BuildInfo { sidecars: sidecars.to_vec(), changes, ..BuildInfo::new(config, options) };
+    let build_info = serde_json::to_vec_pretty(&build_info)
+        .map_err(|e| IpaError::InvalidIpaStructure(format!("Failed to serialize build info: {}", e)))?;
+    let mut on_entry = |done, total| {
+        report_progress(progress, BuildPhase::Compressing, done, total);
+        options.cancel.check()
+    };
+    let fixed_time = options.deterministic.then(|| zip_time(reproducible_timestamp()));
+    let zipped = match &remap_plan {
+        Some(plan) => crate::remap::write_ipa(&mut archive, plan, &build_info, &zip_target_path, fixed_time, &mut on_entry),
+        None => fs::write(ipa_build_temp_dir.path().join(BUILD_INFO_FILE_NAME), build_info)
+            .map_err(IpaError::from)
+            .and_then(|_| write_payload_zip_with_progress(ipa_build_temp_dir.path(), &zip_target_path, &options.compression, &config.permissions, fixed_time, &mut on_entry)),
+    };
+    if let Err(e) = zipped {
+        // Don't leave a truncated IPA in the output directory.
+        let _ = fs::remove_file(&zip_target_path);
+        return Err(e);
+    }
+    log::info!("Successfully created IPA: {}", zip_target_path.display());
+
+    warnings.extend(validate_generated_ipa(&zip_target_path)?);
+
+    if staging_dir.is_some() {
+        options.cancel.check()?;
+        report_progress(progress, BuildPhase::CopyingOutput, 0, 1);
+        log::info!("Copying staged IPA to {}", final_ipa_path.display());
+        crate::transfer::copy_verified_with_retry(
+            &zip_target_path,
+            &final_ipa_path,
+            options.output_copy_attempts,
+            std::time::Duration::from_millis(500),
+        )
+        .map_err(|reason| IpaError::OutputCopyFailed { path: final_ipa_path.clone(), reason })?;
+    }
+
+    Ok(BuildOutput { ipa_path: final_ipa_path, warnings, is_simulator_build, bundle, sha256: None, uploaded_url: None })
+}
+
+/// Inspects the `.app` at `app_dir` for problems worth a warning and, when the options ask for
+/// it, removes debug artifacts and duplicate frameworks from it, adding what it removed to
+/// `changes`. Returns the warnings and whether it is a simulator build.
+fn analyze_payload(
+    config: &AppConfig,
+    options: &BuildOptions,
+    app_dir: &Path,
+    debug_artifacts: Vec<crate::size_analysis::DebugArtifact>,
+    changes: &mut Vec<PayloadChange>,
+) -> Result<(Vec<String>, bool), IpaError> {
+    let is_simulator_build = crate::device_install::is_simulator_app(app_dir);
+    if is_simulator_build {
+        log::info!("'{}' is a simulator build", config.app_name);
+    }
+    if options.exclude_debug_symbols && !debug_artifacts.is_empty() {
+        let freed = crate::size_analysis::remove_debug_artifacts(app_dir, &debug_artifacts)?;
+        log::info!("Excluded {} debug artifacts from the Payload, saving {}", debug_artifacts.len(), crate::size_analysis::format_size(freed));
+        changes.extend(debug_artifacts.iter().map(|artifact| {
+            PayloadChange::new(PayloadChangeKind::DebugArtifactExcluded, artifact.path.as_str(), crate::size_analysis::format_size(artifact.size))
+        }));
+    }
+    let mut warnings = crate::codesign::nested_signing_warnings(app_dir);
+    warnings.extend(debug_artifacts.iter().map(|artifact| artifact.describe(options.exclude_debug_symbols)));
+    let duplicates = crate::size_analysis::find_duplicate_frameworks(app_dir);
+    if options.dedupe_frameworks && duplicates.iter().any(|d| !d.removable.is_empty()) {
+        let freed = crate::size_analysis::remove_duplicate_frameworks(app_dir, &duplicates)?;
+        log::info!("Removed duplicate frameworks from app extensions, saving {}", crate::size_analysis::format_size(freed));
+        for duplicate in &duplicates {
+            changes.extend(duplicate.removable.iter().map(|path| PayloadChange::new(PayloadChangeKind::FrameworkDeduplicated, path.as_str(), format!("duplicate of {}", duplicate.kept))));
+        }
+    }
+    for duplicate in &duplicates {
+        if !options.dedupe_frameworks || !duplicate.blocked.is_empty() {
+            warnings.push(duplicate.describe());
+        }
+    }
+    for warning in &warnings {
+        log::warn!("Build warning for {}: {}", config.app_name, warning);
+    }
+    Ok((warnings, is_simulator_build))
+}
+
+/// Compresses `build_root` (the `Payload` directory plus any top-level metadata such as
+/// [`BUILD_INFO_FILE_NAME`]) into an IPA at `dest`, marking Mach-O binaries and dylibs as executable.
+pub fn write_payload_zip(build_root: &Path, dest: &Path) -> Result<(), IpaError> {
+    write_payload_zip_with_settings(build_root, dest, &CompressionSettings::default())
+}
+
+/// [`write_payload_zip`] with explicit [`CompressionSettings`].
+pub fn write_payload_zip_with_settings(build_root: &Path, dest: &Path, compression: &CompressionSettings) -> Result<(), IpaError> {
+    write_payload_zip_with_progress(build_root, dest, compression, &PermissionRules::default(), None, &mut |_, _| Ok(()))
+}
+
+/// [`write_payload_zip`], calling `on_entry(done, total)` after each entry is written. An error
+/// from `on_entry` stops the compression. Entries are written in file name order, with
+/// `fixed_time` as their modification time when given instead of the current time, and the
+/// Unix mode `permissions` gives them.
+fn write_payload_zip_with_progress(
+    build_root: &Path,
+    dest: &Path,
+    compression: &CompressionSettings,
+    permissions: &PermissionRules,
+    fixed_time: Option<zip::DateTime>,
+    on_entry: &mut dyn FnMut(u64, u64) -> Result<(), IpaError>,
+) -> Result<(), IpaError> {
+    let total_entries = WalkDir::new(build_root).min_depth(1).into_iter().filter_map(|e| e.ok()).count() as u64;
+    let mut entries_written = 0;
+    on_entry(0, total_entries)?;
+    let ipa_file = File::create(dest)?;
+    let mut zip_writer = zip::ZipWriter::new(ipa_file);
+    let with_time = |options: FileOptions| match fixed_time {
+        Some(time) => options.last_modified_time(time),
+        None => options,
+    };
+    let dir_options = with_time(FileOptions::default())
+        .compression_method(zip::CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    log::info!("Starting compression of {} to {} ({})", build_root.display(), dest.display(), compression.summary());
+    let walkdir_base = build_root; // Base for stripping prefix
+    // Files are streamed through this so multi-GB assets never have to fit in memory; the first
+    // chunk doubles as the header used to detect Mach-O executables.
+    let mut buffer = vec![0u8; ZIP_COPY_BUFFER_SIZE];
+
+    for entry_result in WalkDir::new(build_root).min_depth(1).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        let path = entry_result.path();
+        // Path in zip should be relative to *inside* build_root, e.g., "Payload/AppName.app/file"
+        let name_in_zip = path.strip_prefix(walkdir_base).unwrap(); 
+
+        let zip_entry_name = zip_name_from_relative_path(name_in_zip, path.is_dir());
+        if zip_entry_name.is_empty() {
+            continue;
+        }
+
+        if path.is_file() {
+            let mut f = File::open(path)?;
+            let size = f.metadata()?.len();
+            let header_len = read_up_to(&mut f, &mut buffer)?;
+
+            let perm = permissions.mode_for(&zip_entry_name, &buffer[..header_len]);
+            let file_options = with_time(compression.file_options(path))
+                .unix_permissions(perm)
+                .large_file(size >= ZIP64_FILE_THRESHOLD);
+
+            log::trace!("Adding file to zip: {:?} as {}", path, zip_entry_name);
+            zip_writer.start_file(zip_entry_name, file_options)?;
+            zip_writer.write_all(&buffer[..header_len])?;
+            loop {
+                let n = read_up_to(&mut f, &mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                zip_writer.write_all(&buffer[..n])?;
+            }
+        } else {
+            log::trace!("Adding directory to zip: {:?} as {}", path, zip_entry_name);
+            zip_writer.add_directory(zip_entry_name, dir_options)?;
+        }
+        entries_written += 1;
+        on_entry(entries_written, total_entries)?;
+    }
+    zip_writer.finish()?;
+    Ok(())
+}
+
+/// Fills `buf` from `reader` until it is full or the reader is exhausted, returning the bytes read.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Finds the first `.app` bundle (a directory with an `Info.plist`) up to three levels below `root`.
+pub fn find_app_bundle(root: &Path) -> Result<Option<PathBuf>, walkdir::Error> {
+    for entry_result in WalkDir::new(root).min_depth(1).max_depth(3) {
+        let entry = entry_result?;
+        let path = entry.path();
+        // Info.plist is a good indicator of an app bundle
+        if path.is_dir() && path.extension().is_some_and(|ext| ext == "app") && path.join("Info.plist").exists() {
+            log::info!("Found candidate .app bundle: {}", path.display());
+            return Ok(Some(path.to_path_buf()));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether the root entry `name` of an input that is already an IPA goes into the new IPA:
+/// everything next to `Payload/`, such as `SwiftSupport/` or `WatchKitSupport2/`, except the
+/// input's own [`BUILD_INFO_FILE_NAME`], and macOS metadata when junk is stripped.
+pub(crate) fn keeps_root_entry(name: &str, strip_junk: bool) -> bool {
+    let top = name.split('/').next().unwrap_or_default();
+    !top.is_empty() && top != "Payload" && top != BUILD_INFO_FILE_NAME && !(strip_junk && crate::junk::is_junk(name))
+}
+
+/// Copies the entries [`keeps_root_entry`] keeps from the root of an extracted IPA to
+/// `build_root`, returning their names.
+fn copy_root_entries(extracted_root: &Path, build_root: &Path, strip_junk: bool) -> Result<Vec<String>, IpaError> {
+    let mut kept = Vec::new();
+    for entry in fs::read_dir(extracted_root)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !keeps_root_entry(&name, strip_junk) {
+            continue;
+        }
+        let dest = build_root.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(entry.path(), &dest, &mut || Ok(()))?;
+            if strip_junk {
+                crate::junk::remove_junk(&dest)?;
+            }
+            kept.push(format!("{}/", name));
+        } else {
+            fs::copy(entry.path(), &dest)?;
+            kept.push(name);
+        }
+    }
+    kept.sort();
+    Ok(kept)
+}
+
+/// Checks the structure of a generated IPA with [`crate::validator`]. The first error fails the
+/// build; warnings are returned for [`BuildOutput::warnings`].
+fn validate_generated_ipa(ipa_path: &Path) -> Result<Vec<String>, IpaError> {
+    let mut archive = zip::ZipArchive::new(File::open(ipa_path)?)?;
+    let report = crate::validator::validate_archive(&mut archive);
+    if let Some(error) = report.errors().next() {
+        return Err(IpaError::InvalidIpaStructure(error.to_string()));
+    }
+    let warnings: Vec<String> = report.warnings().map(|w| w.to_string()).collect();
+    for warning in &warnings {
+        log::warn!("{}", warning);
+    }
+    Ok(warnings)
+}
+
+fn zip_name_from_relative_path(relative_path: &Path, is_dir: bool) -> String {
+    let mut s = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if is_dir && !s.is_empty() && !s.ends_with('/') {
+        s.push('/');
+    }
+
+    s
+}
+
+/// The files below `app_dir` that [`write_payload_zip`] stores with other permissions than they
+/// have on disk, which were restored from the input ZIP. `build_root` is the folder holding
+/// `Payload/`, against which `permissions` are matched.
+#[cfg(unix)]
+fn permission_changes(build_root: &Path, app_dir: &Path, permissions: &PermissionRules) -> std::io::Result<Vec<PayloadChange>> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut changes = Vec::new();
+    for entry in WalkDir::new(app_dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let mut header = [0u8; 4];
+        let header_len = read_up_to(&mut File::open(entry.path())?, &mut header)?;
+        let current = entry.metadata()?.permissions().mode() & 0o777;
+        let entry_name = zip_name_from_relative_path(entry.path().strip_prefix(build_root).unwrap_or(entry.path()), false);
+        let stored = permissions.mode_for(&entry_name, &header[..header_len]);
+        if current != stored {
+            let relative = entry.path().strip_prefix(app_dir).unwrap_or(entry.path()).to_string_lossy().into_owned();
+            changes.push(PayloadChange::new(PayloadChangeKind::PermissionsChanged, relative, format!("{:04o} → {:04o}", current, stored)));
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(not(unix))]
+fn permission_changes(_build_root: &Path, _app_dir: &Path, _permissions: &PermissionRules) -> std::io::Result<Vec<PayloadChange>> {
+    Ok(Vec::new())
+}
+
+pub fn is_macho(bytes: &[u8]) -> bool {
+    if bytes.len() < 4 {
+        return false;
+    }
+    let magic = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    matches!(
+        magic,
+        0xFEEDFACE
+            | 0xFEEDFACF
+            | 0xCAFEBABE
+            | 0xBEBAFECA
+            | 0xCEFAEDFE
+            | 0xCFFAEDFE
+    )
+}
+
+/// Extracts every entry of `archive` below `dest`, like [`zip::ZipArchive::extract`], reporting
+/// progress per entry. Symbolic links become regular files holding their target; their paths
+/// and targets are returned.
+fn extract_with_progress(
+    archive: &mut zip::ZipArchive<File>,
+    dest: &Path,
+    cancel: &CancelToken,
+    progress: &dyn Fn(BuildProgress),
+) -> Result<Vec<(PathBuf, String)>, IpaError> {
+    let total = archive.len() as u64;
+    let mut symlinks = Vec::new();
+    report_progress(progress, BuildPhase::Extracting, 0, total);
+    for i in 0..archive.len() {
+        cancel.check()?;
+        let mut file = archive.by_index(i)?;
+        let out_path = match file.enclosed_name() {
+            Some(relative) => dest.join(relative),
+            None => return Err(ZipError::InvalidArchive("Invalid file path").into()),
+        };
+        if file.is_dir() {
+            fs::create_dir_all(&out_path)?;
+        } else {
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(&out_path)?;
+            std::io::copy(&mut file, &mut out_file)?;
+        }
+        if file.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            symlinks.push((out_path.clone(), fs::read_to_string(&out_path).unwrap_or_default()));
+        } else {
+            #[cfg(unix)]
+            if let Some(mode) = file.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&out_path, fs::Permissions::from_mode(mode))?;
+            }
+        }
+        report_progress(progress, BuildPhase::Extracting, i as u64 + 1, total);
+    }
+    Ok(symlinks)
+}
+
+/// Copies `src` recursively, calling `on_file` after each file; an error from it stops the copy.
+pub(crate) fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>, on_file: &mut dyn FnMut() -> std::io::Result<()>) -> std::io::Result<()> {
+    fs::create_dir_all(dst.as_ref())?;
+    for entry_result in fs::read_dir(src.as_ref())? {
+        let entry = entry_result?;
+        let ty = entry.file_type()?;
+        let src_path = entry.path();
+        let dst_path = dst.as_ref().join(entry.file_name());
+        if ty.is_dir() {
+            copy_dir_all(&src_path, &dst_path, on_file)?;
+        } else {
+            fs::copy(&src_path, &dst_path)?;
+            on_file()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use uuid::Uuid;
+
+    // Helper to create a mock .app bundle structure within a directory
+    fn create_mock_app_bundle(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+        fs::create_dir_all(app_dir)?;
+        let mut info = plist::Dictionary::new();
+        info.insert("CFBundleIdentifier".into(), "com.example.runner".into());
+        info.insert("CFBundleExecutable".into(), app_name.into());
+        info.insert("CFBundleShortVersionString".into(), "1.0".into());
+        info.insert("CFBundleVersion".into(), "7".into());
+        plist::Value::Dictionary(info).to_file_xml(app_dir.join("Info.plist")).map_err(std::io::Error::other)?;
+        File::create(app_dir.join(app_name))?.write_all(b"Mock executable")?;
+        Ok(())
+    }
+
+    // Helper to create a mock zip file containing a .app bundle
+    fn create_mock_app_zip(zip_path: &Path, app_bundle_name: &str, internal_path_prefix: Option<&str>) -> std::io::Result<()> {
+        let temp_source_dir = tempdir().unwrap();
+        let app_bundle_source_path = if let Some(prefix) = internal_path_prefix {
+            temp_source_dir.path().join(prefix).join(format!("{}.app", app_bundle_name))
+        } else {
+            temp_source_dir.path().join(format!("{}.app", app_bundle_name))
+        };
+        create_mock_app_bundle(&app_bundle_source_path, app_bundle_name)?;
+
+        let file = File::create(zip_path)?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+        let walkdir = WalkDir::new(temp_source_dir.path());
+        let mut buffer = Vec::new();
+
+        for entry_result in walkdir.into_iter().filter_map(|e| e.ok()) {
+            let path = entry_result.path();
+            let name = path.strip_prefix(temp_source_dir.path()).unwrap();
+
+            if path.is_file() {
+                zip.start_file(name.to_string_lossy().into_owned(), options)?;
+                let mut f = File::open(path)?;
+                f.read_to_end(&mut buffer)?;
+                zip.write_all(&buffer)?;
+                buffer.clear();
+            } else if !name.as_os_str().is_empty() {
+                zip.add_directory(name.to_string_lossy().into_owned(), options)?;
+            }
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_simple_ipa_generation_runner_app() {
+        let temp_root = tempdir().unwrap();
+        let input_dir = temp_root.path().join("input");
+        let output_dir = temp_root.path().join("output");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let mock_zip_path = input_dir.join("TestRunner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap(); // Creates Runner.app at root of zip
+
+        let app_name = "MyTestApp".to_string();
+        let config = AppConfig {
+            id: Uuid::new_v4().to_string(),
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: app_name.clone(),
+            output_ipa_name: format!("{}.ipa", app_name),
+            ..Default::default()
+        };
+
+        let result = generate_ipa(&config, &output_dir);
+        assert!(result.is_ok(), "generate_ipa failed: {:?}", result.err());
+
+        let output_ipa_path = output_dir.join("MyTestApp.ipa");
+        assert!(output_ipa_path.exists(), "Output IPA file was not created.");
+
+        let ipa_file = File::open(output_ipa_path).unwrap();
+        let mut archive = zip::ZipArchive::new(ipa_file).unwrap();
+        assert!(archive.by_name("Payload/Runner.app/Info.plist").is_ok());
+        assert!(archive.by_name("Payload/Runner.app/Runner").is_ok());
+    }
+
+    #[test]
+    fn test_nested_app_bundle_generation() {
+        let temp_root = tempdir().unwrap();
+        let input_dir = temp_root.path().join("input_nested");
+        let output_dir = temp_root.path().join("output_nested");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let mock_zip_path = input_dir.join("MyProject.app.zip");
+        // Creates a zip with SomeFolder/MyProject.app
+        create_mock_app_zip(&mock_zip_path, "MyProject", Some("SomeFolder")).unwrap(); 
+
+        let app_name = "NestedAppTest".to_string();
+        let config = AppConfig {
+            id: Uuid::new_v4().to_string(),
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: app_name.clone(),
+            output_ipa_name: format!("{}.ipa", app_name),
+            ..Default::default()
+        };
+
+        let result = generate_ipa(&config, &output_dir);
+        assert!(result.is_ok(), "generate_ipa for nested failed: {:?}", result.err());
+
+        let output_ipa_path = output_dir.join("NestedAppTest.ipa");
+        assert!(output_ipa_path.exists(), "Output IPA file for nested was not created.");
+
+        let ipa_file = File::open(output_ipa_path).unwrap();
+        let mut archive = zip::ZipArchive::new(ipa_file).unwrap();
+        assert!(archive.by_name("Payload/MyProject.app/Info.plist").is_ok());
+        assert!(archive.by_name("Payload/MyProject.app/MyProject").is_ok());
+    }
+
+    #[test]
+    fn test_staged_output_is_copied_and_verified() {
+        let temp_root = tempdir().unwrap();
+        let input_dir = temp_root.path().join("input_staged");
+        let output_dir = temp_root.path().join("output_staged");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let mock_zip_path = input_dir.join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap();
+
+        let config = AppConfig {
+            id: Uuid::new_v4().to_string(),
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Staged".to_string(),
+            output_ipa_name: "Staged.ipa".to_string(),
+            ..Default::default()
+        };
+        let options = BuildOptions { stage_output_locally: true, ..Default::default() };
+
+        let output_path = generate_ipa_with_options(&config, &output_dir, &options).unwrap().ipa_path;
+        assert_eq!(output_path, output_dir.join("Staged.ipa"));
+        let leftovers: Vec<_> = fs::read_dir(&output_dir).unwrap().filter_map(|e| e.ok()).map(|e| e.file_name()).collect();
+        assert_eq!(leftovers.len(), 1, "unexpected files in output: {:?}", leftovers);
+        assert!(validate_generated_ipa(&output_path).is_ok());
+    }
+
+    #[test]
+    fn test_build_info_is_embedded_with_release_notes() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap();
+        let mapping_path = temp_root.path().join("mapping.txt");
+        fs::write(&mapping_path, "a -> b").unwrap();
+
+        let config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Notes".to_string(),
+            output_ipa_name: "Notes.ipa".to_string(),
+            sidecars: vec![mapping_path.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let options = BuildOptions { release_notes: Some("- Fixed login".to_string()), ..Default::default() };
+
+        let output_path = generate_ipa_with_options(&config, temp_root.path(), &options).unwrap().ipa_path;
+        let info = read_build_info(&output_path).unwrap().expect("BuildInfo.json missing");
+        assert_eq!(info.app_name, "Notes");
+        assert_eq!(info.source_zip, "Runner.app.zip");
+        assert_eq!(info.release_notes.as_deref(), Some("- Fixed login"));
+        assert_eq!(info.sidecars.len(), 1);
+        assert_eq!(info.sidecars[0].sha256, crate::transfer::sha256_file(&mapping_path).unwrap());
+        assert!(temp_root.path().join("Notes_sidecars").join("mapping.txt").is_file());
+    }
+
+    #[test]
+    fn test_build_info_lists_payload_changes() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        let mut info = Vec::new();
+        plist::Value::Dictionary(plist::Dictionary::from_iter([("CFBundleExecutable", "Runner"), ("CFBundleVersion", "7")].map(|(k, v)| (k.to_string(), plist::Value::from(v)))))
+            .to_writer_xml(&mut info)
+            .unwrap();
+        let mut zip = zip::ZipWriter::new(File::create(&mock_zip_path).unwrap());
+        for (name, bytes) in [("Runner.app/Info.plist", &info[..]), ("Runner.app/Runner", &[0xCF, 0xFA, 0xED, 0xFE]), ("Runner.app/.DS_Store", b"junk")] {
+            zip.start_file(name, FileOptions::default().unix_permissions(0o644)).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.add_symlink("Runner.app/Current", "Runner", FileOptions::default()).unwrap();
+        zip.finish().unwrap();
+
+        let mut config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Audited".to_string(),
+            output_ipa_name: "Audited.ipa".to_string(),
+            ..Default::default()
+        };
+        config.plist_overrides.bundle_version = "8".to_string();
+        let output_path = generate_ipa(&config, temp_root.path()).unwrap();
+        let changes = read_build_info(&output_path).unwrap().unwrap().changes;
+        let summary: Vec<_> = changes.iter().map(|c| (c.kind, c.path.as_str(), c.detail.as_str())).collect();
+        assert!(summary.contains(&(PayloadChangeKind::SymlinkFlattened, "Current", "was a link to Runner")), "{:?}", summary);
+        assert!(summary.contains(&(PayloadChangeKind::JunkRemoved, ".DS_Store", "")));
+        assert!(summary.contains(&(PayloadChangeKind::PlistPatched, "Info.plist", "CFBundleVersion: 7 → 8")));
+        #[cfg(unix)]
+        assert!(summary.contains(&(PayloadChangeKind::PermissionsChanged, "Runner", "0644 → 0755")));
+    }
+
+    #[test]
+    fn test_package_from_input_zip_without_extracting() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", Some("build/ios")).unwrap();
+        let config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Direct".to_string(),
+            output_ipa_name: "Direct.ipa".to_string(),
+            ..Default::default()
+        };
+        let options = BuildOptions { package_from_input_zip: true, ..Default::default() };
+
+        let phases = std::cell::RefCell::new(Vec::new());
+        let output = generate_ipa_with_progress(&config, temp_root.path(), &options, &|p| phases.borrow_mut().push(p.phase)).unwrap();
+        assert!(!phases.borrow().contains(&BuildPhase::Extracting));
+        let mut archive = zip::ZipArchive::new(File::open(&output.ipa_path).unwrap()).unwrap();
+        let mut executable = String::new();
+        archive.by_name("Payload/Runner.app/Runner").unwrap().read_to_string(&mut executable).unwrap();
+        assert_eq!(executable, "Mock executable");
+        assert_eq!(read_build_info(&output.ipa_path).unwrap().unwrap().app_name, "Direct");
+    }
+
+    #[test]
+    fn test_repacked_ipa_keeps_root_folders() {
+        let temp_root = tempdir().unwrap();
+        let input = temp_root.path().join("Store.ipa");
+        create_mock_app_zip(&input, "Runner", Some("Payload")).unwrap();
+        let mut zip = zip::ZipWriter::new_append(fs::OpenOptions::new().read(true).write(true).open(&input).unwrap()).unwrap();
+        let options = FileOptions::default().unix_permissions(0o644);
+        for (name, contents) in [
+            ("WatchKitSupport2/WK", "watch stub"),
+            ("MessagesApplicationExtensionSupport/MessagesApplicationExtensionStub", "messages stub"),
+            ("WatchKitSupport2/.DS_Store", "junk"),
+            (BUILD_INFO_FILE_NAME, "{}"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        for package_from_input_zip in [false, true] {
+            let config = AppConfig {
+                input_zip_path: input.to_string_lossy().into_owned(),
+                app_name: "Repack".to_string(),
+                output_ipa_name: format!("Repack-{}.ipa", package_from_input_zip),
+                strip_junk_files: true,
+                ..Default::default()
+            };
+            let options = BuildOptions { package_from_input_zip, ..Default::default() };
+            let output = generate_ipa_with_options(&config, temp_root.path(), &options).unwrap();
+            assert!(!output.warnings.iter().any(|w| w.contains("next to Payload/")), "{:?}", output.warnings);
+            let mut archive = zip::ZipArchive::new(File::open(&output.ipa_path).unwrap()).unwrap();
+            let mut stub = String::new();
+            archive.by_name("WatchKitSupport2/WK").unwrap().read_to_string(&mut stub).unwrap();
+            assert_eq!(stub, "watch stub");
+            assert!(archive.by_name("MessagesApplicationExtensionSupport/MessagesApplicationExtensionStub").is_ok());
+            assert!(archive.by_name("WatchKitSupport2/.DS_Store").is_err());
+            assert_eq!(archive.file_names().filter(|name| *name == BUILD_INFO_FILE_NAME).count(), 1);
+            assert_eq!(read_build_info(&output.ipa_path).unwrap().unwrap().app_name, "Repack");
+        }
+    }
+
+    #[test]
+    fn test_output_name_uses_info_plist_values() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap();
+        let mut config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Templated".to_string(),
+            output_ipa_name: "{bundle_id}-{short_version}+{build}.ipa".to_string(),
+            output_exists: crate::naming::OutputExistsPolicy::Fail,
+            ..Default::default()
+        };
+        let output = generate_ipa_with_options(&config, temp_root.path(), &BuildOptions::default()).unwrap();
+        assert_eq!(output.ipa_path, temp_root.path().join("com.example.runner-1.0+7.ipa"));
+        assert_eq!(output.bundle.unwrap().build, "7");
+        assert!(matches!(generate_ipa(&config, temp_root.path()), Err(IpaError::OutputExists(_))));
+
+        config.plist_overrides.bundle_version = "8".to_string();
+        let output = generate_ipa(&config, temp_root.path()).unwrap();
+        assert_eq!(output, temp_root.path().join("com.example.runner-1.0+8.ipa"));
+    }
+
+    #[test]
+    fn test_deterministic_builds_are_byte_identical() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", Some("build")).unwrap();
+        let config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Repro".to_string(),
+            output_ipa_name: "Repro.ipa".to_string(),
+            ..Default::default()
+        };
+
+        for package_from_input_zip in [false, true] {
+            let options = BuildOptions { deterministic: true, package_from_input_zip, ..Default::default() };
+            let mut hashes = Vec::new();
+            for run in ["first", "second"] {
+                let output_dir = temp_root.path().join(format!("{}-{}", run, package_from_input_zip));
+                fs::create_dir_all(&output_dir).unwrap();
+                let output = generate_ipa_with_options(&config, &output_dir, &options).unwrap();
+                hashes.push(crate::transfer::sha256_file(&output.ipa_path).unwrap());
+                // DOS timestamps have a two second resolution.
+                std::thread::sleep(std::time::Duration::from_millis(if run == "first" { 2100 } else { 0 }));
+            }
+            assert_eq!(hashes[0], hashes[1], "package_from_input_zip: {}", package_from_input_zip);
+        }
+
+        let ipa = temp_root.path().join("second-false").join("Repro.ipa");
+        let mut archive = zip::ZipArchive::new(File::open(&ipa).unwrap()).unwrap();
+        let modified = archive.by_name("Payload/Runner.app/Info.plist").unwrap().last_modified();
+        let expected = zip_time(reproducible_timestamp());
+        assert_eq!((modified.year(), modified.month(), modified.day(), modified.hour()), (expected.year(), expected.month(), expected.day(), expected.hour()));
+        assert_eq!(read_build_info(&ipa).unwrap().unwrap().built_at, reproducible_timestamp());
+    }
+
+    #[test]
+    fn test_progress_reports_phases_in_order() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap();
+        let config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Progress".to_string(),
+            output_ipa_name: "Progress.ipa".to_string(),
+            ..Default::default()
+        };
+
+        let reports = std::cell::RefCell::new(Vec::new());
+        generate_ipa_with_progress(&config, temp_root.path(), &BuildOptions::default(), &|p| reports.borrow_mut().push(p)).unwrap();
+        let reports = reports.into_inner();
+
+        let mut phases: Vec<BuildPhase> = reports.iter().map(|p| p.phase).collect();
+        phases.dedup();
+        assert_eq!(phases, vec![BuildPhase::Extracting, BuildPhase::Copying, BuildPhase::Analyzing, BuildPhase::Compressing, BuildPhase::Verifying]);
+        let fractions: Vec<f32> = reports.iter().map(|p| p.overall_fraction()).collect();
+        assert!(fractions.windows(2).all(|w| w[0] <= w[1]), "progress went backwards: {:?}", fractions);
+        let last = reports.last().unwrap();
+        assert_eq!(last.done, last.total);
+    }
+
+    #[test]
+    fn test_cancel_while_compressing_removes_partial_ipa() {
+        let temp_root = tempdir().unwrap();
+        let mock_zip_path = temp_root.path().join("Runner.app.zip");
+        create_mock_app_zip(&mock_zip_path, "Runner", None).unwrap();
+        let config = AppConfig {
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: "Cancel".to_string(),
+            output_ipa_name: "Cancel.ipa".to_string(),
+            ..Default::default()
+        };
+        let options = BuildOptions::default();
+
+        let result = generate_ipa_with_progress(&config, temp_root.path(), &options, &|p| {
+            if p.phase == BuildPhase::Compressing && p.done > 0 {
+                options.cancel.cancel();
+            }
+        });
+        assert!(matches!(result, Err(IpaError::Cancelled)));
+        assert!(!temp_root.path().join("Cancel.ipa").exists());
+    }
+
+    #[test]
+    fn test_large_asset_is_streamed_into_ipa() {
+        const ASSET_SIZE: u64 = 384 * 1024 * 1024;
+        let temp_root = tempdir().unwrap();
+        let build_root = temp_root.path().join("build");
+        let app_dir = build_root.join("Payload").join("Big.app");
+        fs::create_dir_all(&app_dir).unwrap();
+        fs::write(app_dir.join("Big"), [0xCF, 0xFA, 0xED, 0xFE, 0x07, 0x00]).unwrap();
+        // Sparse, so the fixture costs no disk space; zeros still have to be read and deflated.
+        let asset = File::create(app_dir.join("Assets.car")).unwrap();
+        asset.set_len(ASSET_SIZE).unwrap();
+        drop(asset);
+
+        let ipa_path = temp_root.path().join("Big.ipa");
+        write_payload_zip(&build_root, &ipa_path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&ipa_path).unwrap()).unwrap();
+        assert_eq!(archive.by_name("Payload/Big.app/Big").unwrap().unix_mode(), Some(0o100755));
+        let mut entry = archive.by_name("Payload/Big.app/Assets.car").unwrap();
+        assert_eq!(entry.size(), ASSET_SIZE);
+        assert_eq!(entry.unix_mode(), Some(0o100644));
+        // Reading to the end also verifies the CRC written while streaming.
+        assert_eq!(std::io::copy(&mut entry, &mut std::io::sink()).unwrap(), ASSET_SIZE);
+    }
+
+    #[test]
+    fn test_zip64_entries_are_repackaged() {
+        // ZIP64 headers on small entries, so the default suite covers reading and repackaging
+        // them without writing gigabytes.
+        let temp_root = tempdir().unwrap();
+        let input_path = temp_root.path().join("Game.ipa");
+        let mut writer = zip::ZipWriter::new(File::create(&input_path).unwrap());
+        for (name, contents) in [("Payload/Game.app/Info.plist", "plist"), ("Payload/Game.app/Levels.pak", "level data")] {
+            writer.start_file(name, FileOptions::default().large_file(true)).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&input_path).unwrap()).unwrap();
+        let remap = crate::remap::plan(&mut archive).unwrap();
+        let repackaged = temp_root.path().join("Repackaged.ipa");
+        crate::remap::write_ipa(&mut archive, &remap, b"{}", &repackaged, None, &mut |_, _| Ok(())).unwrap();
+
+        let mut output = zip::ZipArchive::new(File::open(&repackaged).unwrap()).unwrap();
+        let mut levels = String::new();
+        output.by_name("Payload/Game.app/Levels.pak").unwrap().read_to_string(&mut levels).unwrap();
+        assert_eq!(levels, "level data");
+        assert!(output.by_name(BUILD_INFO_FILE_NAME).is_ok());
+    }
+
+    #[test]
+    #[ignore = "writes and reads more than 4 GiB; run with `cargo test -- --ignored`"]
+    fn test_payload_over_4gb_is_written_and_read_as_zip64() {
+        const ASSET_SIZE: u64 = 4 * 1024 * 1024 * 1024 + 1024 * 1024;
+        let temp_root = tempdir().unwrap();
+        let build_root = temp_root.path().join("build");
+        let app_dir = build_root.join("Payload").join("Game.app");
+        fs::create_dir_all(&app_dir).unwrap();
+        fs::write(app_dir.join("Info.plist"), "plist").unwrap();
+        let asset = File::create(app_dir.join("Levels.pak")).unwrap();
+        asset.set_len(ASSET_SIZE).unwrap();
+        drop(asset);
+
+        // Stored, so the test measures ZIP64 handling rather than deflating 4 GiB of zeros.
+        let stored = CompressionSettings { method: crate::compression::PayloadCompression::Stored, ..Default::default() };
+        let ipa_path = temp_root.path().join("Game.ipa");
+        write_payload_zip_with_settings(&build_root, &ipa_path, &stored).unwrap();
+        fs::remove_file(app_dir.join("Levels.pak")).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&ipa_path).unwrap()).unwrap();
+        assert_eq!(archive.by_name("Payload/Game.app/Levels.pak").unwrap().size(), ASSET_SIZE);
+        let mut plist = String::new();
+        archive.by_name("Payload/Game.app/Info.plist").unwrap().read_to_string(&mut plist).unwrap();
+        assert_eq!(plist, "plist");
+
+        // The IPA as an input ZIP: packaging it without extracting puts BuildInfo.json past 4 GiB.
+        let remap = crate::remap::plan(&mut archive).unwrap();
+        let repackaged = temp_root.path().join("Repackaged.ipa");
+        crate::remap::write_ipa(&mut archive, &remap, b"{}", &repackaged, None, &mut |_, _| Ok(())).unwrap();
+        drop(archive);
+        fs::remove_file(&ipa_path).unwrap();
+
+        let mut output = zip::ZipArchive::new(File::open(&repackaged).unwrap()).unwrap();
+        assert_eq!(output.by_name("Payload/Game.app/Levels.pak").unwrap().size(), ASSET_SIZE);
+        let mut build_info = output.by_name(BUILD_INFO_FILE_NAME).unwrap();
+        assert!(build_info.header_start() > u32::MAX as u64);
+        let mut contents = String::new();
+        build_info.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "{}");
+    }
+
+     #[test]
+    fn test_input_file_not_found() {
+        let temp_root = tempdir().unwrap();
+        let output_dir = temp_root.path().join("output_notfound");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let app_name = "NotFoundTest".to_string();
+        let config = AppConfig {
+            id: Uuid::new_v4().to_string(),
+            input_zip_path: PathBuf::from("non_existent_file.zip").to_string_lossy().into_owned(),
+            app_name: app_name.clone(),
+            output_ipa_name: format!("{}.ipa", app_name),
+            ..Default::default()
+        };
+
+        let result = generate_ipa(&config, &output_dir);
+        assert!(matches!(result, Err(IpaError::InputFileNotFound(_))));
+    }
+
+    #[test]
+    fn test_app_bundle_not_found_in_zip() {
+        let temp_root = tempdir().unwrap();
+        let input_dir = temp_root.path().join("input_no_app");
+        let output_dir = temp_root.path().join("output_no_app");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let mock_zip_path = input_dir.join("Empty.zip");
+        // Create an empty zip or a zip without a .app directory
+        let file = File::create(&mock_zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        zip.start_file("readme.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"empty").unwrap();
+        zip.finish().unwrap();
+
+        let app_name = "NoAppBundleTest".to_string();
+        let config = AppConfig {
+            id: Uuid::new_v4().to_string(),
+            input_zip_path: mock_zip_path.to_string_lossy().into_owned(),
+            app_name: app_name.clone(),
+            output_ipa_name: format!("{}.ipa", app_name),
+            ..Default::default()
+        };
+
+        let result = generate_ipa(&config, &output_dir);
+        assert!(matches!(result, Err(IpaError::UnexpectedZipStructure(_))));
+    }
+
+    /// Entry names, modes and contents an input ZIP can have, including ones no real build
+    /// produces: `..` segments, empty names, symlinks, unreadable modes and truncated Mach-Os.
+    fn arbitrary_entry() -> impl proptest::strategy::Strategy<Value = (String, Option<u32>, Vec<u8>)> {
+        use proptest::prelude::*;
+        let segment = prop::sample::select(vec![
+            "Runner.app", "Payload", "Info.plist", "Runner", "Frameworks", "App.framework", "App", "PlugIns", "Share.appex", "Watch",
+            "Face.app", "_CodeSignature", "CodeResources", "._Info.plist", ".DS_Store", "App.framework.dSYM", "données 🚀", "..", "",
+        ]);
+        let name = prop::collection::vec(segment, 1..6).prop_map(|parts| parts.join("/"));
+        let mode = prop::option::of(prop::sample::select(vec![0o100644u32, 0o100755, 0o120777, 0o040755, 0o100000]));
+        let tail = || prop::collection::vec(any::<u8>(), 0..48);
+        let contents = prop_oneof![
+            tail(),
+            Just(b"<plist><dict><key>CFBundleExecutable</key><string>Runner</string></dict></plist>".to_vec()),
+            tail().prop_map(|t| [&[0xCF, 0xFA, 0xED, 0xFE][..], &t].concat()),
+            tail().prop_map(|t| [&[0xCA, 0xFE, 0xBA, 0xBE][..], &t].concat()),
+        ];
+        (name, mode, contents)
+    }
+
+    /// Builds `input` with the options that read the Payload. The input and a successful build's
+    /// IPA also go through the validator.
+    fn build_adversarial_input(input: &Path, output_dir: &Path, options: BuildOptions) {
+        let mut config = AppConfig {
+            input_zip_path: input.to_string_lossy().into_owned(),
+            app_name: "Fuzz".to_string(),
+            output_ipa_name: "Fuzz.ipa".to_string(),
+            strip_junk_files: true,
+            keep_architectures: vec!["arm64".to_string()],
+            ..Default::default()
+        };
+        config.plist_overrides.bundle_version = "9".to_string();
+        let _ = crate::validator::validate_ipa(input);
+        if let Ok(output) = generate_ipa_with_options(&config, output_dir, &options) {
+            let report = crate::validator::validate_ipa(&output.ipa_path).unwrap();
+            assert!(report.is_valid(), "{:?}", report.findings);
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::test_runner::Config { cases: 48, ..Default::default() })]
+
+        #[test]
+        fn test_adversarial_inputs_fail_without_panicking(
+            entries in proptest::collection::vec(arbitrary_entry(), 1..12),
+            flags in proptest::array::uniform4(proptest::bool::ANY),
+        ) {
+            let dir = tempdir().unwrap();
+            let input = dir.path().join("Runner.app.zip");
+            let mut zip = zip::ZipWriter::new(File::create(&input).unwrap());
+            let app = [
+                ("Runner.app/Info.plist".to_string(), None, b"<plist><dict><key>CFBundleExecutable</key><string>Runner</string></dict></plist>".to_vec()),
+                ("Runner.app/Runner".to_string(), Some(0o100755), vec![0xCF, 0xFA, 0xED, 0xFE]),
+            ];
+            for (name, mode, contents) in app.iter().chain(&entries) {
+                let options = FileOptions::default();
+                let _ = match mode {
+                    Some(0o120777) => zip.add_symlink(name.as_str(), String::from_utf8_lossy(contents), options),
+                    Some(0o040755) => zip.add_directory(name.as_str(), options),
+                    Some(mode) => zip.start_file(name.as_str(), options.unix_permissions(*mode)).and_then(|_| Ok(zip.write_all(contents)?)),
+                    None => zip.start_file(name.as_str(), options).and_then(|_| Ok(zip.write_all(contents)?)),
+                };
+            }
+            zip.finish().unwrap();
+            let options = BuildOptions {
+                strip_bitcode: flags[0],
+                exclude_debug_symbols: flags[1],
+                dedupe_frameworks: flags[2],
+                package_from_input_zip: flags[3],
+                ..Default::default()
+            };
+            build_adversarial_input(&input, dir.path(), options);
+        }
+
+        #[test]
+        fn test_corrupted_archives_fail_without_panicking(
+            edits in proptest::collection::vec((proptest::num::usize::ANY, proptest::num::u8::ANY), 1..8),
+            truncate_at in proptest::option::of(proptest::num::usize::ANY),
+        ) {
+            let dir = tempdir().unwrap();
+            let input = dir.path().join("Runner.app.zip");
+            let spec = crate::fixtures::FixtureSpec { resource_bytes: 256, symlinks: true, ..Default::default() };
+            crate::fixtures::write_fixture(&spec, &input).unwrap();
+            let mut bytes = fs::read(&input).unwrap();
+            for (at, value) in edits {
+                let len = bytes.len();
+                bytes[at % len] = value;
+            }
+            if let Some(at) = truncate_at {
+                bytes.truncate(at % (bytes.len() + 1));
+            }
+            fs::write(&input, bytes).unwrap();
+            build_adversarial_input(&input, dir.path(), BuildOptions::default());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+/// Whether `relative_path` (with `/` separators) is file manager metadata that has no place in
+/// an IPA: anything in `__MACOSX/`, `.DS_Store`, AppleDouble `._*` files and `Thumbs.db`.
+pub fn is_junk(relative_path: &str) -> bool {
+    relative_path
+        .split('/')
+        .any(|c| c == "__MACOSX" || c == ".DS_Store" || c.starts_with("._") || c.eq_ignore_ascii_case("Thumbs.db"))
+}
+
+/// Deletes the junk below `dir`. Returns the removed paths, relative to `dir`, and the bytes freed.
+pub fn remove_junk(dir: &Path) -> io::Result<(Vec<String>, u64)> {
+    let mut removed = Vec::new();
+    let mut freed = 0;
+    let mut walker = WalkDir::new(dir).min_depth(1).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path()).to_string_lossy().replace('\\', "/");
+        if !is_junk(&relative) {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            freed += WalkDir::new(entry.path())
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum::<u64>();
+            fs::remove_dir_all(entry.path())?;
+            walker.skip_current_dir();
+        } else {
+            freed += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            fs::remove_file(entry.path())?;
+        }
+        removed.push(relative);
+    }
+    Ok((removed, freed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junk_is_removed_and_the_bundle_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("Runner.app");
+        fs::create_dir_all(app.join("__MACOSX/Frameworks")).unwrap();
+        fs::create_dir_all(app.join("Frameworks/App.framework")).unwrap();
+        fs::write(app.join("__MACOSX/Frameworks/._App"), [0u8; 10]).unwrap();
+        fs::write(app.join(".DS_Store"), [0u8; 6]).unwrap();
+        fs::write(app.join("Frameworks/App.framework/._App"), [0u8; 4]).unwrap();
+        fs::write(app.join("Frameworks/App.framework/App"), "binary").unwrap();
+        fs::write(app.join("THUMBS.DB"), "").unwrap();
+        fs::write(app.join("Info.plist"), "plist").unwrap();
+
+        let (mut removed, freed) = remove_junk(&app).unwrap();
+        removed.sort();
+        assert_eq!(removed, vec![".DS_Store", "Frameworks/App.framework/._App", "THUMBS.DB", "__MACOSX"]);
+        assert_eq!(freed, 20);
+        assert!(app.join("Info.plist").is_file() && app.join("Frameworks/App.framework/App").is_file());
+        assert!(!app.join("__MACOSX").exists());
+
+        assert!(!is_junk("Frameworks/App.framework/App") && !is_junk("Base.lproj/Main.storyboardc"));
+    }
+}
@@ -0,0 +1,67 @@
+//! IPA generation for IPA Builder without the GUI: turn a `Runner.app.zip` into an installable
+//! `.ipa`, watch folders for new ZIPs (AutoCheck) and record usage metrics.
+//!
+//! ```no_run
+//! use ipa_builder_core::{generate_ipa_with_options, AppConfig, BuildOptions};
+//!
+//! let config = AppConfig {
+//!     app_name: "Shop".to_string(),
+//!     input_zip_path: "build/Runner.app.zip".to_string(),
+//!     output_ipa_name: "shop.ipa".to_string(),
+//!     ..Default::default()
+//! };
+//! let output = generate_ipa_with_options(&config, std::path::Path::new("out"), &BuildOptions::default())?;
+//! println!("{} ({} warnings)", output.ipa_path.display(), output.warnings.len());
+//! # Ok::<(), ipa_builder_core::IpaError>(())
+//! ```
+//!
+//! The re-exports below are the stable API. The modules are public for the IPA Builder app
+//! itself and may change between versions.
+
+pub mod agent;
+pub mod app_config;
+pub mod app_store_connect;
+pub mod artifact_signing;
+pub mod autocheck;
+pub mod bundle_signing;
+pub mod channels;
+pub mod code_signature;
+pub mod codesign;
+pub mod compression;
+pub mod database;
+pub mod device_install;
+pub mod dsym;
+pub mod fixtures;
+pub mod hooks;
+pub mod info_plist;
+pub mod injection;
+pub mod input_cache;
+pub mod input_watch;
+pub mod ipa_logic;
+pub mod junk;
+pub mod macho;
+pub mod metrics;
+pub mod naming;
+pub mod network;
+pub mod notifications;
+pub mod object_storage;
+pub mod ota_manifest;
+pub mod permissions;
+pub mod output_watch;
+pub mod remap;
+pub mod retention;
+pub mod share;
+pub mod sidecar;
+pub mod size_analysis;
+pub mod thinning;
+pub mod transfer;
+pub mod validator;
+pub mod webhook;
+
+pub use app_config::AppConfig;
+pub use autocheck::{AutoCheckConfig, AutoCheckMessage, AutoCheckRunner};
+pub use ipa_logic::{
+    generate_ipa, generate_ipa_with_options, generate_ipa_with_progress, read_build_info, BuildInfo, BuildOptions,
+    BuildOutput, BuildPhase, BuildProgress, IpaError,
+};
+pub use metrics::{MetricEntry, MetricEvent, MetricsCollector};
@@ -0,0 +1,1080 @@
+use thiserror::Error;
+
+// Minimal, dependency-free reader for the parts of Mach-O files we care about:
+// fat/universal headers, load commands and the embedded code signature blobs.
+
+const FAT_MAGIC: u32 = 0xCAFEBABE;
+const FAT_MAGIC_64: u32 = 0xCAFEBABF;
+const MH_MAGIC: u32 = 0xFEEDFACE;
+const MH_MAGIC_64: u32 = 0xFEEDFACF;
+
+pub const LC_CODE_SIGNATURE: u32 = 0x1d;
+pub const LC_RPATH: u32 = 0x8000_001c;
+/// `file_type` of a main executable, as opposed to a dylib or bundle.
+pub const MH_EXECUTE: u32 = 2;
+const LC_SEGMENT: u32 = 0x1;
+const LC_SYMTAB: u32 = 0x2;
+const LC_DYSYMTAB: u32 = 0xb;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+const LC_REEXPORT_DYLIB: u32 = 0x8000_001f;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_SEGMENT_SPLIT_INFO: u32 = 0x1e;
+const LC_DYLD_INFO: u32 = 0x22;
+const LC_DYLD_INFO_ONLY: u32 = 0x8000_0022;
+const LC_FUNCTION_STARTS: u32 = 0x26;
+const LC_DATA_IN_CODE: u32 = 0x29;
+const LC_DYLIB_CODE_SIGN_DRS: u32 = 0x2b;
+const LC_LINKER_OPTIMIZATION_HINT: u32 = 0x2e;
+const LC_ATOM_INFO: u32 = 0x36;
+const LC_DYLD_EXPORTS_TRIE: u32 = 0x8000_0033;
+const LC_DYLD_CHAINED_FIXUPS: u32 = 0x8000_0034;
+const LC_VERSION_MIN_IPHONEOS: u32 = 0x25;
+const LC_BUILD_VERSION: u32 = 0x32;
+
+pub const PLATFORM_IOS: u32 = 2;
+pub const PLATFORM_IOSSIMULATOR: u32 = 7;
+const PLATFORM_TVOSSIMULATOR: u32 = 8;
+const PLATFORM_WATCHOSSIMULATOR: u32 = 9;
+const PLATFORM_VISIONOSSIMULATOR: u32 = 12;
+
+pub(crate) const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xFADE0CC0;
+pub(crate) const CSMAGIC_CODEDIRECTORY: u32 = 0xFADE0C02;
+pub(crate) const CSMAGIC_BLOBWRAPPER: u32 = 0xFADE0B01;
+pub(crate) const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xFADE7171;
+
+pub const CPU_TYPE_X86: u32 = 7;
+pub const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+pub const CPU_TYPE_ARM: u32 = 12;
+pub const CPU_TYPE_ARM64: u32 = 0x0100_000C;
+pub const CPU_TYPE_ARM64_32: u32 = 0x0200_000C;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MachOError {
+    #[error("not a Mach-O file")]
+    NotMachO,
+    #[error("truncated Mach-O data while reading {0}")]
+    Truncated(&'static str),
+    #[error("unsupported Mach-O layout: {0}")]
+    Unsupported(String),
+}
+
+/// One architecture slice of a (possibly fat) Mach-O file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachOSlice {
+    pub cpu_type: u32,
+    pub cpu_subtype: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl MachOSlice {
+    pub fn arch_name(&self) -> String {
+        arch_name(self.cpu_type, self.cpu_subtype)
+    }
+
+    pub fn bytes<'a>(&self, file_bytes: &'a [u8]) -> Result<&'a [u8], MachOError> {
+        let start = usize::try_from(self.offset).map_err(|_| MachOError::Truncated("slice offset"))?;
+        let end = start
+            .checked_add(usize::try_from(self.size).map_err(|_| MachOError::Truncated("slice size"))?)
+            .ok_or(MachOError::Truncated("slice size"))?;
+        file_bytes.get(start..end).ok_or(MachOError::Truncated("slice"))
+    }
+}
+
+/// A load command of a thin Mach-O image; `offset` is relative to the start of the slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadCommand {
+    pub cmd: u32,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Header fields of a thin, little-endian Mach-O image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachOHeader {
+    pub is_64: bool,
+    pub cpu_type: u32,
+    pub cpu_subtype: u32,
+    pub file_type: u32,
+    pub ncmds: u32,
+    pub sizeofcmds: u32,
+    pub header_size: usize,
+}
+
+/// Information extracted from an embedded code signature (`LC_CODE_SIGNATURE`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeSignatureInfo {
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    /// Whether a CMS (certificate based) signature is present, as opposed to an ad-hoc one.
+    pub has_cms_signature: bool,
+    /// Common names of the certificates found in the CMS blob, leaf first when ordered by the signer.
+    pub certificate_names: Vec<String>,
+    pub entitlements_xml: Option<String>,
+}
+
+pub fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64_le(bytes: &[u8], offset: usize) -> Option<u64> {
+    bytes.get(offset..offset + 8).map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+}
+
+fn read_u64_be(bytes: &[u8], offset: usize) -> Option<u64> {
+    bytes.get(offset..offset + 8).map(|b| u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+}
+
+pub fn is_fat(bytes: &[u8]) -> bool {
+    matches!(read_u32_be(bytes, 0), Some(FAT_MAGIC) | Some(FAT_MAGIC_64))
+        // Java class files share 0xCAFEBABE; a sane arch count tells them apart.
+        && read_u32_be(bytes, 4).is_some_and(|n| n > 0 && n < 32)
+}
+
+/// Lists the architecture slices of a fat or thin Mach-O file.
+pub fn slices(bytes: &[u8]) -> Result<Vec<MachOSlice>, MachOError> {
+    let magic = read_u32_be(bytes, 0).ok_or(MachOError::Truncated("magic"))?;
+    if is_fat(bytes) {
+        let is_64 = magic == FAT_MAGIC_64;
+        let count = read_u32_be(bytes, 4).ok_or(MachOError::Truncated("fat header"))? as usize;
+        let entry_size = if is_64 { 32 } else { 20 };
+        // The counts come from the file: don't reserve more entries than it could hold.
+        let mut result = Vec::with_capacity(count.min(bytes.len() / entry_size));
+        for i in 0..count {
+            let base = 8 + i * entry_size;
+            let cpu_type = read_u32_be(bytes, base).ok_or(MachOError::Truncated("fat arch"))?;
+            let cpu_subtype = read_u32_be(bytes, base + 4).ok_or(MachOError::Truncated("fat arch"))?;
+            let (offset, size) = if is_64 {
+                (
+                    read_u64_be(bytes, base + 8).ok_or(MachOError::Truncated("fat arch"))?,
+                    read_u64_be(bytes, base + 16).ok_or(MachOError::Truncated("fat arch"))?,
+                )
+            } else {
+                (
+                    read_u32_be(bytes, base + 8).ok_or(MachOError::Truncated("fat arch"))? as u64,
+                    read_u32_be(bytes, base + 12).ok_or(MachOError::Truncated("fat arch"))? as u64,
+                )
+            };
+            result.push(MachOSlice { cpu_type, cpu_subtype, offset, size });
+        }
+        return Ok(result);
+    }
+
+    let header = parse_header(bytes)?;
+    Ok(vec![MachOSlice {
+        cpu_type: header.cpu_type,
+        cpu_subtype: header.cpu_subtype,
+        offset: 0,
+        size: bytes.len() as u64,
+    }])
+}
+
+/// Parses the header of a thin Mach-O image.
+pub fn parse_header(slice: &[u8]) -> Result<MachOHeader, MachOError> {
+    let magic = read_u32_le(slice, 0).ok_or(MachOError::Truncated("magic"))?;
+    let is_64 = match magic {
+        MH_MAGIC_64 => true,
+        MH_MAGIC => false,
+        _ => {
+            return match read_u32_be(slice, 0) {
+                Some(MH_MAGIC) | Some(MH_MAGIC_64) => Err(MachOError::Unsupported("big-endian Mach-O".to_string())),
+                _ => Err(MachOError::NotMachO),
+            };
+        }
+    };
+    let field = |offset| read_u32_le(slice, offset).ok_or(MachOError::Truncated("mach header"));
+    Ok(MachOHeader {
+        is_64,
+        cpu_type: field(4)?,
+        cpu_subtype: field(8)?,
+        file_type: field(12)?,
+        ncmds: field(16)?,
+        sizeofcmds: field(20)?,
+        header_size: if is_64 { 32 } else { 28 },
+    })
+}
+
+pub fn load_commands(slice: &[u8]) -> Result<Vec<LoadCommand>, MachOError> {
+    let header = parse_header(slice)?;
+    let mut offset = header.header_size;
+    let mut commands = Vec::with_capacity((header.ncmds as usize).min(slice.len() / 8));
+    for _ in 0..header.ncmds {
+        let cmd = read_u32_le(slice, offset).ok_or(MachOError::Truncated("load command"))?;
+        let size = read_u32_le(slice, offset + 4).ok_or(MachOError::Truncated("load command"))? as usize;
+        if size < 8 || offset + size > slice.len() {
+            return Err(MachOError::Truncated("load command size"));
+        }
+        commands.push(LoadCommand { cmd, offset, size });
+        offset += size;
+    }
+    Ok(commands)
+}
+
+/// Parses the embedded code signature of a thin Mach-O image. Returns `Ok(None)` when the
+/// image carries no `LC_CODE_SIGNATURE` at all.
+pub fn code_signature(slice: &[u8]) -> Result<Option<CodeSignatureInfo>, MachOError> {
+    let commands = load_commands(slice)?;
+    let lc = match commands.iter().find(|c| c.cmd == LC_CODE_SIGNATURE) {
+        Some(lc) => lc,
+        None => return Ok(None),
+    };
+    let dataoff = read_u32_le(slice, lc.offset + 8).ok_or(MachOError::Truncated("LC_CODE_SIGNATURE"))? as usize;
+    let datasize = read_u32_le(slice, lc.offset + 12).ok_or(MachOError::Truncated("LC_CODE_SIGNATURE"))? as usize;
+    let blob = slice
+        .get(dataoff..dataoff.saturating_add(datasize))
+        .ok_or(MachOError::Truncated("code signature"))?;
+
+    if read_u32_be(blob, 0) != Some(CSMAGIC_EMBEDDED_SIGNATURE) {
+        return Err(MachOError::Unsupported("code signature is not an embedded signature superblob".to_string()));
+    }
+    let count = read_u32_be(blob, 8).ok_or(MachOError::Truncated("superblob"))? as usize;
+    let mut info = CodeSignatureInfo::default();
+    for i in 0..count {
+        let blob_offset = read_u32_be(blob, 12 + i * 8 + 4).ok_or(MachOError::Truncated("superblob index"))? as usize;
+        let magic = read_u32_be(blob, blob_offset).ok_or(MachOError::Truncated("blob"))?;
+        let length = read_u32_be(blob, blob_offset + 4).ok_or(MachOError::Truncated("blob"))? as usize;
+        let sub = blob
+            .get(blob_offset..blob_offset.saturating_add(length))
+            .ok_or(MachOError::Truncated("blob"))?;
+        match magic {
+            // Alternate code directories (SHA-256 next to SHA-1) repeat the same identity.
+            CSMAGIC_CODEDIRECTORY if info.identifier.is_none() => {
+                let version = read_u32_be(sub, 8).unwrap_or(0);
+                info.identifier = read_u32_be(sub, 20).and_then(|o| c_string_at(sub, o as usize));
+                if version >= 0x20200 {
+                    info.team_id = read_u32_be(sub, 48).filter(|o| *o != 0).and_then(|o| c_string_at(sub, o as usize));
+                }
+            }
+            CSMAGIC_BLOBWRAPPER => {
+                let cms = &sub[8.min(sub.len())..];
+                info.has_cms_signature = !cms.is_empty();
+                info.certificate_names = der_common_names(cms);
+            }
+            CSMAGIC_EMBEDDED_ENTITLEMENTS => {
+                info.entitlements_xml = Some(String::from_utf8_lossy(&sub[8.min(sub.len())..]).into_owned());
+            }
+            _ => {}
+        }
+    }
+    Ok(Some(info))
+}
+
+/// Returns the `LC_RPATH` entries of a thin Mach-O image, e.g. `@executable_path/Frameworks`.
+pub fn rpaths(slice: &[u8]) -> Result<Vec<String>, MachOError> {
+    let mut result = Vec::new();
+    for lc in load_commands(slice)?.iter().filter(|c| c.cmd == LC_RPATH) {
+        let path_offset = read_u32_le(slice, lc.offset + 8).ok_or(MachOError::Truncated("LC_RPATH"))? as usize;
+        let command = &slice[lc.offset..lc.offset + lc.size];
+        result.push(c_string_at(command, path_offset).ok_or(MachOError::Truncated("LC_RPATH path"))?);
+    }
+    Ok(result)
+}
+
+/// Platform from `LC_BUILD_VERSION` (e.g. [`PLATFORM_IOSSIMULATOR`]). Binaries built for old
+/// deployment targets only carry `LC_VERSION_MIN_IPHONEOS`, reported as [`PLATFORM_IOS`].
+pub fn build_platform(slice: &[u8]) -> Result<Option<u32>, MachOError> {
+    for lc in load_commands(slice)? {
+        match lc.cmd {
+            LC_BUILD_VERSION => {
+                return read_u32_le(slice, lc.offset + 8).map(Some).ok_or(MachOError::Truncated("LC_BUILD_VERSION"));
+            }
+            LC_VERSION_MIN_IPHONEOS => return Ok(Some(PLATFORM_IOS)),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a (possibly fat) binary only runs in a simulator: every slice is either marked with a
+/// simulator platform, or is an Intel slice, which no iOS device can run.
+pub fn is_simulator_binary(bytes: &[u8]) -> Result<bool, MachOError> {
+    let slices = slices(bytes)?;
+    for slice in &slices {
+        let platform = build_platform(slice.bytes(bytes)?)?;
+        let simulator_platform = matches!(
+            platform,
+            Some(PLATFORM_IOSSIMULATOR | PLATFORM_TVOSSIMULATOR | PLATFORM_WATCHOSSIMULATOR | PLATFORM_VISIONOSSIMULATOR)
+        );
+        let intel = matches!(slice.cpu_type, CPU_TYPE_X86 | CPU_TYPE_X86_64);
+        if !simulator_platform && !intel {
+            return Ok(false);
+        }
+    }
+    Ok(!slices.is_empty())
+}
+
+/// Drops the slices of a fat Mach-O whose architecture (see [`arch_name`]) is not in `keep`,
+/// like `lipo -extract`. A single remaining slice is written as a thin file; each slice keeps
+/// its own code signature. `Ok(None)` when nothing would be dropped, which includes thin files.
+pub fn thin(bytes: &[u8], keep: &[String]) -> Result<Option<Vec<u8>>, MachOError> {
+    if !is_fat(bytes) {
+        return Ok(None);
+    }
+    let entries = fat_entries(bytes)?;
+    let total = entries.len();
+    let kept: Vec<FatEntry> = entries.into_iter().filter(|e| keep.contains(&e.slice.arch_name())).collect();
+    if kept.len() == total {
+        return Ok(None);
+    }
+    match kept.as_slice() {
+        [] => Err(MachOError::Unsupported(format!("no {} slice", keep.join("/")))),
+        [only] => Ok(Some(only.slice.bytes(bytes)?.to_vec())),
+        _ => {
+            let images = kept.iter().map(|e| Ok((e, e.slice.bytes(bytes)?))).collect::<Result<Vec<_>, MachOError>>()?;
+            write_fat(bytes, &images).map(Some)
+        }
+    }
+}
+
+/// Removes the `__LLVM` bitcode segment of every slice, like `bitcode_strip -r`. The segment's
+/// file contents are cut out and everything after it moves up; its address range stays
+/// reserved, so no pointer inside the image changes. `Ok(None)` when there is no bitcode.
+pub fn strip_bitcode(bytes: &[u8]) -> Result<Option<Vec<u8>>, MachOError> {
+    if !is_fat(bytes) {
+        return strip_bitcode_slice(bytes);
+    }
+    let entries = fat_entries(bytes)?;
+    let mut stripped = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        stripped.push(strip_bitcode_slice(entry.slice.bytes(bytes)?)?);
+    }
+    if stripped.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    let images = entries
+        .iter()
+        .zip(&stripped)
+        .map(|(e, s)| Ok((e, s.as_deref().map_or_else(|| e.slice.bytes(bytes), Ok)?)))
+        .collect::<Result<Vec<_>, MachOError>>()?;
+    write_fat(bytes, &images).map(Some)
+}
+
+/// A slice of a fat file plus the alignment its header asks for.
+struct FatEntry {
+    slice: MachOSlice,
+    align: u32,
+}
+
+fn fat_entries(bytes: &[u8]) -> Result<Vec<FatEntry>, MachOError> {
+    let entry_size = if read_u32_be(bytes, 0) == Some(FAT_MAGIC_64) { 32 } else { 20 };
+    let align_field = if entry_size == 32 { 24 } else { 16 };
+    slices(bytes)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, slice)| {
+            let align = read_u32_be(bytes, 8 + index * entry_size + align_field).ok_or(MachOError::Truncated("fat arch"))?;
+            if align > 20 {
+                return Err(MachOError::Unsupported(format!("slice alignment 2^{}", align)));
+            }
+            Ok(FatEntry { slice, align })
+        })
+        .collect()
+}
+
+/// Writes a fat file in the same 32- or 64-bit format as `original`, holding `images`.
+fn write_fat(original: &[u8], images: &[(&FatEntry, &[u8])]) -> Result<Vec<u8>, MachOError> {
+    let is_64 = read_u32_be(original, 0) == Some(FAT_MAGIC_64);
+    let entry_size = if is_64 { 32 } else { 20 };
+    let mut out = Vec::new();
+    out.extend_from_slice(&if is_64 { FAT_MAGIC_64 } else { FAT_MAGIC }.to_be_bytes());
+    out.extend_from_slice(&(images.len() as u32).to_be_bytes());
+    let mut offset = (8 + images.len() * entry_size) as u64;
+    let mut placed = Vec::with_capacity(images.len());
+    for (entry, image) in images {
+        offset = offset.next_multiple_of(1 << entry.align);
+        let size = image.len() as u64;
+        out.extend_from_slice(&entry.slice.cpu_type.to_be_bytes());
+        out.extend_from_slice(&entry.slice.cpu_subtype.to_be_bytes());
+        if is_64 {
+            out.extend_from_slice(&offset.to_be_bytes());
+            out.extend_from_slice(&size.to_be_bytes());
+            out.extend_from_slice(&entry.align.to_be_bytes());
+            out.extend_from_slice(&0u32.to_be_bytes());
+        } else {
+            let narrow = |v: u64| u32::try_from(v).map_err(|_| MachOError::Unsupported("slice beyond 4 GB in a 32-bit fat header".to_string()));
+            out.extend_from_slice(&narrow(offset)?.to_be_bytes());
+            out.extend_from_slice(&narrow(size)?.to_be_bytes());
+            out.extend_from_slice(&entry.align.to_be_bytes());
+        }
+        placed.push((offset, *image));
+        offset += size;
+    }
+    for (offset, image) in placed {
+        out.resize(offset as usize, 0);
+        out.extend_from_slice(image);
+    }
+    Ok(out)
+}
+
+/// Field offsets of the `LC_SEGMENT`/`LC_SEGMENT_64` command and `section`/`section_64` layouts.
+struct SegmentLayout {
+    vmsize: usize,
+    fileoff: usize,
+    filesize: usize,
+    nsects: usize,
+    sections: usize,
+    section_size: usize,
+    sect_size: usize,
+    sect_offset: usize,
+    sect_reloff: usize,
+}
+
+const SEGMENT_64: SegmentLayout = SegmentLayout { vmsize: 32, fileoff: 40, filesize: 48, nsects: 64, sections: 72, section_size: 80, sect_size: 40, sect_offset: 48, sect_reloff: 56 };
+const SEGMENT_32: SegmentLayout = SegmentLayout { vmsize: 28, fileoff: 32, filesize: 36, nsects: 48, sections: 56, section_size: 68, sect_size: 36, sect_offset: 40, sect_reloff: 48 };
+
+/// Offsets of the 32-bit file offset fields in the load commands that point into `__LINKEDIT`.
+fn linkedit_offset_fields(cmd: u32) -> &'static [usize] {
+    match cmd {
+        LC_SYMTAB => &[8, 16],
+        LC_DYSYMTAB => &[32, 40, 48, 56, 64, 72],
+        LC_DYLD_INFO | LC_DYLD_INFO_ONLY => &[8, 16, 24, 32, 40],
+        LC_CODE_SIGNATURE | LC_SEGMENT_SPLIT_INFO | LC_FUNCTION_STARTS | LC_DATA_IN_CODE | LC_DYLIB_CODE_SIGN_DRS
+        | LC_LINKER_OPTIMIZATION_HINT | LC_DYLD_EXPORTS_TRIE | LC_DYLD_CHAINED_FIXUPS | LC_ATOM_INFO => &[8],
+        _ => &[],
+    }
+}
+
+fn strip_bitcode_slice(slice: &[u8]) -> Result<Option<Vec<u8>>, MachOError> {
+    let header = parse_header(slice)?;
+    let (segment_cmd, layout) = if header.is_64 { (LC_SEGMENT_64, &SEGMENT_64) } else { (LC_SEGMENT, &SEGMENT_32) };
+    let commands = load_commands(slice)?;
+    let word = |offset: usize| -> Result<u64, MachOError> {
+        if header.is_64 {
+            read_u64_le(slice, offset).ok_or(MachOError::Truncated("segment"))
+        } else {
+            read_u32_le(slice, offset).map(u64::from).ok_or(MachOError::Truncated("segment"))
+        }
+    };
+    let Some(llvm) = commands.iter().find(|c| c.cmd == segment_cmd && slice.get(c.offset + 8..c.offset + 24) == Some(&b"__LLVM\0\0\0\0\0\0\0\0\0\0"[..])) else {
+        return Ok(None);
+    };
+    let start = word(llvm.offset + layout.fileoff)? as usize;
+    let removed = word(llvm.offset + layout.filesize)? as usize;
+    if removed == 0 {
+        return Ok(None);
+    }
+    // Segments after the bitcode move up by `removed`, so it must keep them page aligned.
+    if !removed.is_multiple_of(4096) || start.checked_add(removed).is_none_or(|end| end > slice.len()) {
+        return Err(MachOError::Unsupported(format!("__LLVM segment of {} bytes at {}", removed, start)));
+    }
+    let end = start + removed;
+
+    let mut out = slice.to_vec();
+    let put_word = |out: &mut Vec<u8>, offset: usize, value: u64| {
+        if header.is_64 {
+            out[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        } else {
+            out[offset..offset + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+    };
+    let shift_u32 = |out: &mut Vec<u8>, offset: usize| -> Result<(), MachOError> {
+        let value = read_u32_le(out, offset).ok_or(MachOError::Truncated("load command"))? as usize;
+        if value >= end {
+            out[offset..offset + 4].copy_from_slice(&((value - removed) as u32).to_le_bytes());
+        }
+        Ok(())
+    };
+    for lc in &commands {
+        if lc.cmd == segment_cmd {
+            let is_llvm = lc.offset == llvm.offset;
+            let fileoff = word(lc.offset + layout.fileoff)? as usize;
+            if is_llvm {
+                put_word(&mut out, lc.offset + layout.filesize, 0);
+            } else if fileoff >= end {
+                put_word(&mut out, lc.offset + layout.fileoff, (fileoff - removed) as u64);
+            }
+            let nsects = read_u32_le(slice, lc.offset + layout.nsects).ok_or(MachOError::Truncated("segment"))? as usize;
+            for i in 0..nsects {
+                let section = lc.offset + layout.sections + i * layout.section_size;
+                if section + layout.section_size > lc.offset + lc.size {
+                    return Err(MachOError::Truncated("section"));
+                }
+                if is_llvm {
+                    put_word(&mut out, section + layout.sect_size, 0);
+                    out[section + layout.sect_offset..section + layout.sect_offset + 4].fill(0);
+                } else {
+                    shift_u32(&mut out, section + layout.sect_offset)?;
+                    shift_u32(&mut out, section + layout.sect_reloff)?;
+                }
+            }
+        } else {
+            for field in linkedit_offset_fields(lc.cmd) {
+                if field + 4 > lc.size {
+                    return Err(MachOError::Truncated("load command"));
+                }
+                shift_u32(&mut out, lc.offset + field)?;
+            }
+        }
+    }
+    out.drain(start..end);
+    Ok(Some(out))
+}
+
+/// Adds an `LC_LOAD_DYLIB` for `install_name` (e.g. `@executable_path/Frameworks/libTweak.dylib`)
+/// to every slice, like `insert_dylib`. The command goes into the padding between the load
+/// commands and the first section, which `ld` leaves for this purpose. An existing code signature
+/// stays in place but no longer matches. `Ok(None)` when every slice already loads it.
+pub fn insert_load_dylib(bytes: &[u8], install_name: &str) -> Result<Option<Vec<u8>>, MachOError> {
+    if !is_fat(bytes) {
+        return insert_load_dylib_slice(bytes, install_name);
+    }
+    let entries = fat_entries(bytes)?;
+    let mut patched = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        patched.push(insert_load_dylib_slice(entry.slice.bytes(bytes)?, install_name)?);
+    }
+    if patched.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    let images = entries
+        .iter()
+        .zip(&patched)
+        .map(|(e, p)| Ok((e, p.as_deref().map_or_else(|| e.slice.bytes(bytes), Ok)?)))
+        .collect::<Result<Vec<_>, MachOError>>()?;
+    write_fat(bytes, &images).map(Some)
+}
+
+/// The install names of the `LC_LOAD_DYLIB`-style commands of a thin image.
+pub fn linked_dylibs(slice: &[u8]) -> Result<Vec<String>, MachOError> {
+    let mut result = Vec::new();
+    for lc in load_commands(slice)?.iter().filter(|c| matches!(c.cmd, LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB)) {
+        let command = &slice[lc.offset..lc.offset + lc.size];
+        let name_offset = read_u32_le(slice, lc.offset + 8).ok_or(MachOError::Truncated("dylib command"))? as usize;
+        result.push(c_string_at(command, name_offset).ok_or(MachOError::Truncated("dylib name"))?);
+    }
+    Ok(result)
+}
+
+fn insert_load_dylib_slice(slice: &[u8], install_name: &str) -> Result<Option<Vec<u8>>, MachOError> {
+    if linked_dylibs(slice)?.iter().any(|name| name == install_name) {
+        return Ok(None);
+    }
+    let header = parse_header(slice)?;
+    let align = if header.is_64 { 8 } else { 4 };
+    let cmdsize = (24 + install_name.len() + 1).next_multiple_of(align);
+    let commands_end = reserve_command_space(slice, cmdsize)?;
+
+    let mut out = slice.to_vec();
+    let mut command = Vec::with_capacity(cmdsize);
+    // Timestamp 2 and version 1.0 like insert_dylib; dyld ignores them.
+    for v in [LC_LOAD_DYLIB, cmdsize as u32, 24, 2, 0x1_0000, 0x1_0000] {
+        command.extend_from_slice(&v.to_le_bytes());
+    }
+    command.extend_from_slice(install_name.as_bytes());
+    command.resize(cmdsize, 0);
+    out[commands_end..commands_end + cmdsize].copy_from_slice(&command);
+    out[16..20].copy_from_slice(&(header.ncmds + 1).to_le_bytes());
+    out[20..24].copy_from_slice(&(header.sizeofcmds + cmdsize as u32).to_le_bytes());
+    Ok(Some(out))
+}
+
+/// Checks that `size` bytes of zeroed padding follow the load commands, before the first
+/// section's contents, and returns where they start.
+fn reserve_command_space(slice: &[u8], size: usize) -> Result<usize, MachOError> {
+    let header = parse_header(slice)?;
+    let (segment_cmd, layout) = if header.is_64 { (LC_SEGMENT_64, &SEGMENT_64) } else { (LC_SEGMENT, &SEGMENT_32) };
+    let commands_end = header.header_size + header.sizeofcmds as usize;
+    // Whatever file content comes first bounds the space available for load commands.
+    let mut first_content = slice.len();
+    for lc in load_commands(slice)?.iter().filter(|c| c.cmd == segment_cmd) {
+        let nsects = read_u32_le(slice, lc.offset + layout.nsects).ok_or(MachOError::Truncated("segment"))? as usize;
+        for i in 0..nsects {
+            let offset = read_u32_le(slice, lc.offset + layout.sections + i * layout.section_size + layout.sect_offset)
+                .ok_or(MachOError::Truncated("section"))? as usize;
+            if offset != 0 {
+                first_content = first_content.min(offset);
+            }
+        }
+    }
+    let padding = slice.get(commands_end..first_content).unwrap_or_default();
+    if padding.len() < size || padding[..size].iter().any(|&b| b != 0) {
+        return Err(MachOError::Unsupported(format!(
+            "only {} bytes free after the load commands, {} needed; relink with -headerpad_max_install_names",
+            padding.iter().take_while(|&&b| b == 0).count(),
+            size
+        )));
+    }
+    Ok(commands_end)
+}
+
+/// A thin image laid out for a new code signature by [`reserve_code_signature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureSpace {
+    /// The image up to where the signature goes (`LC_CODE_SIGNATURE`'s `dataoff`), which is what
+    /// the signature's page hashes cover.
+    pub image: Vec<u8>,
+    pub file_type: u32,
+    /// File offset and size of the `__TEXT` segment.
+    pub text_segment: (u64, u64),
+}
+
+/// Makes room for a code signature of `signature_size(code_limit)` bytes at the end of a thin
+/// image, replacing the existing one: `LC_CODE_SIGNATURE` is added to the header padding when
+/// missing, and `__LINKEDIT` grows to cover the signature. The signature itself is appended to
+/// [`SignatureSpace::image`] by the caller.
+pub fn reserve_code_signature(slice: &[u8], signature_size: impl FnOnce(usize) -> usize) -> Result<SignatureSpace, MachOError> {
+    let header = parse_header(slice)?;
+    let (segment_cmd, layout) = if header.is_64 { (LC_SEGMENT_64, &SEGMENT_64) } else { (LC_SEGMENT, &SEGMENT_32) };
+    let commands = load_commands(slice)?;
+    let word = |bytes: &[u8], offset: usize| -> Result<u64, MachOError> {
+        if header.is_64 {
+            read_u64_le(bytes, offset).ok_or(MachOError::Truncated("segment"))
+        } else {
+            read_u32_le(bytes, offset).map(u64::from).ok_or(MachOError::Truncated("segment"))
+        }
+    };
+    let segment = |name: &[u8]| {
+        commands.iter().find(|c| {
+            c.cmd == segment_cmd && slice.get(c.offset + 8..c.offset + 24).is_some_and(|n| n.split(|&b| b == 0).next() == Some(name))
+        })
+    };
+    let linkedit = segment(b"__LINKEDIT").ok_or_else(|| MachOError::Unsupported("no __LINKEDIT segment".to_string()))?;
+    let text_segment = match segment(b"__TEXT") {
+        Some(text) => (word(slice, text.offset + layout.fileoff)?, word(slice, text.offset + layout.filesize)?),
+        None => (0, 0),
+    };
+
+    let mut out = slice.to_vec();
+    let existing = commands.iter().find(|c| c.cmd == LC_CODE_SIGNATURE).copied();
+    let (command_offset, code_limit) = match existing {
+        Some(lc) => (lc.offset, read_u32_le(slice, lc.offset + 8).ok_or(MachOError::Truncated("LC_CODE_SIGNATURE"))? as usize),
+        None => {
+            let offset = reserve_command_space(slice, 16)?;
+            out[offset..offset + 8].copy_from_slice(&[LC_CODE_SIGNATURE.to_le_bytes(), 16u32.to_le_bytes()].concat());
+            out[16..20].copy_from_slice(&(header.ncmds + 1).to_le_bytes());
+            out[20..24].copy_from_slice(&(header.sizeofcmds + 16).to_le_bytes());
+            (offset, slice.len().next_multiple_of(16))
+        }
+    };
+    let linkedit_start = word(slice, linkedit.offset + layout.fileoff)? as usize;
+    if code_limit < linkedit_start || code_limit > slice.len().next_multiple_of(16) {
+        return Err(MachOError::Unsupported("code signature outside __LINKEDIT".to_string()));
+    }
+    let size = signature_size(code_limit);
+    let filesize = (code_limit + size - linkedit_start) as u64;
+    let vmsize = word(slice, linkedit.offset + layout.vmsize)?.max(filesize.next_multiple_of(0x4000));
+    let put_word = |out: &mut Vec<u8>, offset: usize, value: u64| {
+        if header.is_64 {
+            out[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        } else {
+            out[offset..offset + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+    };
+    put_word(&mut out, linkedit.offset + layout.filesize, filesize);
+    put_word(&mut out, linkedit.offset + layout.vmsize, vmsize);
+    out[command_offset + 8..command_offset + 12].copy_from_slice(&(code_limit as u32).to_le_bytes());
+    out[command_offset + 12..command_offset + 16].copy_from_slice(&(size as u32).to_le_bytes());
+    out.resize(code_limit, 0);
+    Ok(SignatureSpace { image: out, file_type: header.file_type, text_segment })
+}
+
+/// Rewrites every slice of a fat file with `rewrite` and puts them back together; a thin file is
+/// rewritten as a whole.
+pub fn rewrite_slices(bytes: &[u8], mut rewrite: impl FnMut(&[u8]) -> Result<Vec<u8>, String>) -> Result<Vec<u8>, String> {
+    if !is_fat(bytes) {
+        return rewrite(bytes);
+    }
+    let entries = fat_entries(bytes).map_err(|e| e.to_string())?;
+    let mut rewritten = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        rewritten.push(rewrite(entry.slice.bytes(bytes).map_err(|e| e.to_string())?)?);
+    }
+    let images: Vec<(&FatEntry, &[u8])> = entries.iter().zip(&rewritten).map(|(e, r)| (e, r.as_slice())).collect();
+    write_fat(bytes, &images).map_err(|e| e.to_string())
+}
+
+fn c_string_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let rest = bytes.get(offset..)?;
+    let end = rest.iter().position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+/// Scans DER data for X.520 commonName attributes (OID 2.5.4.3) and returns their values in
+/// order of appearance, without duplicates. This is not a full ASN.1 parser, but certificate
+/// subjects and issuers in CMS signatures are always encoded this way.
+pub fn der_common_names(der: &[u8]) -> Vec<String> {
+    const CN_OID: [u8; 5] = [0x06, 0x03, 0x55, 0x04, 0x03];
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i + CN_OID.len() + 2 <= der.len() {
+        if der[i..i + CN_OID.len()] == CN_OID {
+            let tag = der[i + CN_OID.len()];
+            let len = der[i + CN_OID.len() + 1] as usize;
+            let start = i + CN_OID.len() + 2;
+            // UTF8String, PrintableString, T61String, IA5String with short-form lengths.
+            if matches!(tag, 0x0c | 0x13 | 0x14 | 0x16) && len < 0x80 {
+                if let Some(value) = der.get(start..start + len) {
+                    let name = String::from_utf8_lossy(value).into_owned();
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            i = start;
+        } else {
+            i += 1;
+        }
+    }
+    names
+}
+
+pub fn arch_name(cpu_type: u32, cpu_subtype: u32) -> String {
+    let subtype = cpu_subtype & 0x00FF_FFFF;
+    match (cpu_type, subtype) {
+        (CPU_TYPE_ARM64, 2) => "arm64e".to_string(),
+        (CPU_TYPE_ARM64, _) => "arm64".to_string(),
+        (CPU_TYPE_ARM64_32, _) => "arm64_32".to_string(),
+        (CPU_TYPE_ARM, 9) => "armv7".to_string(),
+        (CPU_TYPE_ARM, 11) => "armv7s".to_string(),
+        (CPU_TYPE_ARM, 12) => "armv7k".to_string(),
+        (CPU_TYPE_ARM, _) => "arm".to_string(),
+        (CPU_TYPE_X86_64, _) => "x86_64".to_string(),
+        (CPU_TYPE_X86, _) => "i386".to_string(),
+        _ => format!("cpu({:#x}/{:#x})", cpu_type, cpu_subtype),
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support {
+    use super::*;
+
+    /// Builds a thin 64-bit arm64 Mach-O image with an optional embedded code signature.
+    pub fn thin_macho(cpu_type: u32, signature: Option<(&str, Option<&str>)>) -> Vec<u8> {
+        let mut image = Vec::new();
+        let ncmds: u32 = if signature.is_some() { 1 } else { 0 };
+        let sizeofcmds: u32 = if signature.is_some() { 16 } else { 0 };
+        for v in [MH_MAGIC_64, cpu_type, 0, 2, ncmds, sizeofcmds, 0, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        if let Some((identifier, team_id)) = signature {
+            let code_dir = code_directory(identifier, team_id);
+            let superblob_len = 12 + 8 + code_dir.len();
+            let dataoff = (32 + 16) as u32;
+            for v in [LC_CODE_SIGNATURE, 16, dataoff, superblob_len as u32] {
+                image.extend_from_slice(&v.to_le_bytes());
+            }
+            for v in [CSMAGIC_EMBEDDED_SIGNATURE, superblob_len as u32, 1, 0, 20] {
+                image.extend_from_slice(&v.to_be_bytes());
+            }
+            image.extend_from_slice(&code_dir);
+        }
+        image
+    }
+
+    /// Builds an unsigned thin 64-bit Mach-O image with the given `LC_RPATH` entries.
+    pub fn thin_macho_with_rpaths(cpu_type: u32, rpaths: &[&str]) -> Vec<u8> {
+        let commands: Vec<Vec<u8>> = rpaths
+            .iter()
+            .map(|path| {
+                let size = (12 + path.len() + 1).next_multiple_of(8);
+                let mut lc = Vec::with_capacity(size);
+                for v in [LC_RPATH, size as u32, 12] {
+                    lc.extend_from_slice(&v.to_le_bytes());
+                }
+                lc.extend_from_slice(path.as_bytes());
+                lc.resize(size, 0);
+                lc
+            })
+            .collect();
+        let sizeofcmds: usize = commands.iter().map(Vec::len).sum();
+        let mut image = Vec::new();
+        for v in [MH_MAGIC_64, cpu_type, 0, 2, commands.len() as u32, sizeofcmds as u32, 0, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        for lc in commands {
+            image.extend_from_slice(&lc);
+        }
+        image
+    }
+
+    /// Builds a thin 64-bit Mach-O image laid out like a bitcode build: a 4 KB `__TEXT`, an
+    /// 8 KB `__LLVM` segment with a `__bundle` section, then `__LINKEDIT` holding the symbol
+    /// table and a code signature.
+    pub fn thin_macho_with_bitcode(cpu_type: u32) -> Vec<u8> {
+        let segment = |name: &str, vmaddr: u64, fileoff: u64, size: u64, section: Option<&str>| {
+            let mut lc = Vec::new();
+            for v in [LC_SEGMENT_64, if section.is_some() { 152 } else { 72 }] {
+                lc.extend_from_slice(&v.to_le_bytes());
+            }
+            let mut name16 = [0u8; 16];
+            name16[..name.len()].copy_from_slice(name.as_bytes());
+            lc.extend_from_slice(&name16);
+            for v in [vmaddr, size, fileoff, size] {
+                lc.extend_from_slice(&v.to_le_bytes());
+            }
+            for v in [1u32, 1, u32::from(section.is_some()), 0] {
+                lc.extend_from_slice(&v.to_le_bytes());
+            }
+            if let Some(sect) = section {
+                let mut sect16 = [0u8; 16];
+                sect16[..sect.len()].copy_from_slice(sect.as_bytes());
+                lc.extend_from_slice(&sect16);
+                lc.extend_from_slice(&name16);
+                for v in [vmaddr, size - 192] {
+                    lc.extend_from_slice(&v.to_le_bytes());
+                }
+                for v in [fileoff as u32, 0, 0, 0, 0, 0, 0, 0] {
+                    lc.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            lc
+        };
+        let mut commands = Vec::new();
+        commands.extend(segment("__TEXT", 0x1_0000_0000, 0, 4096, None));
+        commands.extend(segment("__LLVM", 0x1_0000_1000, 4096, 8192, Some("__bundle")));
+        commands.extend(segment("__LINKEDIT", 0x1_0000_3000, 12288, 4096, None));
+        for v in [LC_SYMTAB, 24, 12288, 2, 12320, 16] {
+            commands.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [LC_CODE_SIGNATURE, 16, 12400, 64] {
+            commands.extend_from_slice(&v.to_le_bytes());
+        }
+        let mut image = Vec::new();
+        for v in [MH_MAGIC_64, cpu_type, 0, 2, 5, commands.len() as u32, 0, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        image.extend_from_slice(&commands);
+        image.resize(4096, 0);
+        image.resize(12288, 0xBC);
+        image.extend((0..4096).map(|i| (i % 251) as u8));
+        image
+    }
+
+    /// Builds an unsigned thin 64-bit executable as the linker lays it out: a 4 KB `__TEXT`
+    /// whose `__text` section starts at 1 KB, leaving header padding, then a 4 KB `__LINKEDIT`
+    /// holding the symbol table.
+    pub fn thin_executable(cpu_type: u32) -> Vec<u8> {
+        let mut commands = Vec::new();
+        for (name, vmaddr, fileoff, section) in [("__TEXT", 0x1_0000_0000u64, 0u64, true), ("__LINKEDIT", 0x1_0000_1000, 4096, false)] {
+            for v in [LC_SEGMENT_64, if section { 152 } else { 72 }] {
+                commands.extend_from_slice(&v.to_le_bytes());
+            }
+            let mut name16 = [0u8; 16];
+            name16[..name.len()].copy_from_slice(name.as_bytes());
+            commands.extend_from_slice(&name16);
+            for v in [vmaddr, 4096, fileoff, 4096] {
+                commands.extend_from_slice(&v.to_le_bytes());
+            }
+            for v in [5u32, 5, u32::from(section), 0] {
+                commands.extend_from_slice(&v.to_le_bytes());
+            }
+            if section {
+                commands.extend_from_slice(b"__text\0\0\0\0\0\0\0\0\0\0");
+                commands.extend_from_slice(&name16);
+                for v in [vmaddr + 1024, 3072] {
+                    commands.extend_from_slice(&v.to_le_bytes());
+                }
+                for v in [1024u32, 2, 0, 0, 0x8000_0400, 0, 0, 0] {
+                    commands.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+        }
+        for v in [LC_SYMTAB, 24, 4096, 2, 4128, 16] {
+            commands.extend_from_slice(&v.to_le_bytes());
+        }
+        let mut image = Vec::new();
+        for v in [MH_MAGIC_64, cpu_type, 0, MH_EXECUTE, 3, commands.len() as u32, 0x0020_0085, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        image.extend_from_slice(&commands);
+        image.resize(1024, 0);
+        image.resize(4096, 0xC3);
+        image.extend((0..4096).map(|i| (i % 241) as u8));
+        image
+    }
+
+    /// Builds an unsigned thin 64-bit Mach-O image with an `LC_BUILD_VERSION` for `platform`.
+    pub fn thin_macho_for_platform(cpu_type: u32, platform: u32) -> Vec<u8> {
+        let mut image = Vec::new();
+        for v in [MH_MAGIC_64, cpu_type, 0, 2, 1, 24, 0, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [LC_BUILD_VERSION, 24, platform, 0x000E_0000, 0x0011_0000, 0] {
+            image.extend_from_slice(&v.to_le_bytes());
+        }
+        image
+    }
+
+    fn code_directory(identifier: &str, team_id: Option<&str>) -> Vec<u8> {
+        let header_len = 52usize;
+        let ident_offset = header_len;
+        let team_offset = ident_offset + identifier.len() + 1;
+        let mut fields = [0u32; 13];
+        fields[0] = CSMAGIC_CODEDIRECTORY;
+        fields[2] = 0x20400;
+        fields[5] = ident_offset as u32;
+        fields[12] = if team_id.is_some() { team_offset as u32 } else { 0 };
+        let mut cd: Vec<u8> = fields.iter().flat_map(|v| v.to_be_bytes()).collect();
+        cd.extend_from_slice(identifier.as_bytes());
+        cd.push(0);
+        if let Some(team) = team_id {
+            cd.extend_from_slice(team.as_bytes());
+            cd.push(0);
+        }
+        let len = cd.len() as u32;
+        cd[4..8].copy_from_slice(&len.to_be_bytes());
+        cd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::thin_macho;
+    use super::*;
+
+    #[test]
+    fn test_thin_and_fat_slices() {
+        let arm64 = thin_macho(CPU_TYPE_ARM64, None);
+        let slices_thin = slices(&arm64).unwrap();
+        assert_eq!(slices_thin.len(), 1);
+        assert_eq!(slices_thin[0].arch_name(), "arm64");
+
+        let mut fat = Vec::new();
+        for v in [FAT_MAGIC, 2] {
+            fat.extend_from_slice(&v.to_be_bytes());
+        }
+        let x86 = thin_macho(CPU_TYPE_X86_64, None);
+        let first = 4096u32;
+        let second = first + 4096;
+        for (cpu, off, len) in [(CPU_TYPE_ARM64, first, arm64.len() as u32), (CPU_TYPE_X86_64, second, x86.len() as u32)] {
+            for v in [cpu, 0, off, len, 12] {
+                fat.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        fat.resize(first as usize, 0);
+        fat.extend_from_slice(&arm64);
+        fat.resize(second as usize, 0);
+        fat.extend_from_slice(&x86);
+
+        let fat_slices = slices(&fat).unwrap();
+        let names: Vec<String> = fat_slices.iter().map(|s| s.arch_name()).collect();
+        assert_eq!(names, vec!["arm64", "x86_64"]);
+        assert_eq!(parse_header(fat_slices[1].bytes(&fat).unwrap()).unwrap().cpu_type, CPU_TYPE_X86_64);
+    }
+
+    #[test]
+    fn test_thinning_drops_unwanted_slices() {
+        let (arm64, armv7, x86) = (thin_macho(CPU_TYPE_ARM64, None), thin_macho(CPU_TYPE_ARM, None), thin_macho(CPU_TYPE_X86_64, None));
+        let mut fat = Vec::new();
+        for v in [FAT_MAGIC, 3] {
+            fat.extend_from_slice(&v.to_be_bytes());
+        }
+        let offsets = [4096u32, 8192, 12288];
+        for ((cpu, subtype, len), off) in [(CPU_TYPE_ARM, 9, armv7.len()), (CPU_TYPE_ARM64, 0, arm64.len()), (CPU_TYPE_X86_64, 3, x86.len())].into_iter().zip(offsets) {
+            for v in [cpu, subtype, off, len as u32, 12] {
+                fat.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        for (image, off) in [&armv7, &arm64, &x86].into_iter().zip(offsets) {
+            fat.resize(off as usize, 0);
+            fat.extend_from_slice(image);
+        }
+        let keep = |archs: &[&str]| archs.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        let two = thin(&fat, &keep(&["arm64", "armv7"])).unwrap().unwrap();
+        let names: Vec<String> = slices(&two).unwrap().iter().map(|s| s.arch_name()).collect();
+        assert_eq!(names, ["armv7", "arm64"]);
+        assert_eq!(slices(&two).unwrap()[1].bytes(&two).unwrap(), arm64.as_slice());
+        assert!(slices(&two).unwrap().iter().all(|s| s.offset % 4096 == 0));
+
+        assert_eq!(thin(&fat, &keep(&["arm64"])).unwrap().unwrap(), arm64);
+        assert_eq!(thin(&fat, &keep(&["arm64", "armv7", "x86_64"])).unwrap(), None);
+        assert_eq!(thin(&arm64, &keep(&["x86_64"])).unwrap(), None);
+        assert!(thin(&fat, &keep(&["arm64e"])).is_err());
+    }
+
+    #[test]
+    fn test_bitcode_segment_is_cut_out() {
+        let image = test_support::thin_macho_with_bitcode(CPU_TYPE_ARM64);
+        let stripped = strip_bitcode(&image).unwrap().unwrap();
+        assert_eq!(stripped.len(), image.len() - 8192);
+        assert_eq!(&stripped[4096..], &image[12288..]);
+        assert!(!stripped.windows(2).any(|w| w == [0xBC, 0xBC]));
+
+        let commands = load_commands(&stripped).unwrap();
+        let segment = |i: usize| commands[i].offset;
+        assert_eq!(read_u64_le(&stripped, segment(1) + 48), Some(0), "__LLVM filesize");
+        assert_eq!(read_u64_le(&stripped, segment(1) + 32), Some(8192), "__LLVM keeps its address range");
+        assert_eq!(read_u64_le(&stripped, segment(1) + 72 + 40), Some(0), "__bundle size");
+        assert_eq!(read_u64_le(&stripped, segment(2) + 40), Some(4096), "__LINKEDIT fileoff");
+        assert_eq!((read_u32_le(&stripped, segment(3) + 8), read_u32_le(&stripped, segment(3) + 16)), (Some(4096), Some(4128)));
+        assert_eq!(read_u32_le(&stripped, segment(4) + 8), Some(4208), "LC_CODE_SIGNATURE dataoff");
+        assert_eq!(strip_bitcode(&stripped).unwrap(), None);
+
+        let plain = thin_macho(CPU_TYPE_X86_64, None);
+        let mut fat = Vec::new();
+        for v in [FAT_MAGIC, 2, CPU_TYPE_ARM64, 0, 16384, image.len() as u32, 14, CPU_TYPE_X86_64, 3, 32768, plain.len() as u32, 12] {
+            fat.extend_from_slice(&v.to_be_bytes());
+        }
+        fat.resize(16384, 0);
+        fat.extend_from_slice(&image);
+        fat.resize(32768, 0);
+        fat.extend_from_slice(&plain);
+        let stripped_fat = strip_bitcode(&fat).unwrap().unwrap();
+        let fat_slices = slices(&stripped_fat).unwrap();
+        assert_eq!(fat_slices[0].bytes(&stripped_fat).unwrap(), stripped.as_slice());
+        assert_eq!(fat_slices[1].bytes(&stripped_fat).unwrap(), plain.as_slice());
+        assert!(fat_slices[0].offset.is_multiple_of(16384) && fat_slices[1].offset.is_multiple_of(4096));
+        assert_eq!(strip_bitcode(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_load_dylib_is_inserted_into_header_padding() {
+        let image = test_support::thin_macho_with_bitcode(CPU_TYPE_ARM64);
+        let name = "@executable_path/Frameworks/libTweak.dylib";
+        let patched = insert_load_dylib(&image, name).unwrap().unwrap();
+        assert_eq!(patched.len(), image.len());
+        assert_eq!(linked_dylibs(&patched).unwrap(), [name]);
+        let header = parse_header(&patched).unwrap();
+        assert_eq!((header.ncmds, header.sizeofcmds), (6, parse_header(&image).unwrap().sizeofcmds + 72));
+        assert_eq!(&patched[4096..], &image[4096..]);
+        assert_eq!(insert_load_dylib(&patched, name).unwrap(), None);
+
+        // A section starting right after the load commands leaves no room.
+        let mut tight = image.clone();
+        let commands = load_commands(&tight).unwrap();
+        let llvm_section = commands[1].offset + 72 + 48;
+        let end = 32 + parse_header(&tight).unwrap().sizeofcmds;
+        tight[llvm_section..llvm_section + 4].copy_from_slice(&(end + 16).to_le_bytes());
+        assert!(matches!(insert_load_dylib(&tight, name), Err(MachOError::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_code_signature_identity() {
+        let unsigned = thin_macho(CPU_TYPE_ARM64, None);
+        assert_eq!(code_signature(&unsigned).unwrap(), None);
+
+        let signed = thin_macho(CPU_TYPE_ARM64, Some(("com.example.app", Some("ABCDE12345"))));
+        let info = code_signature(&signed).unwrap().unwrap();
+        assert_eq!(info.identifier.as_deref(), Some("com.example.app"));
+        assert_eq!(info.team_id.as_deref(), Some("ABCDE12345"));
+        assert!(!info.has_cms_signature);
+    }
+
+    #[test]
+    fn test_rpaths() {
+        let image = super::test_support::thin_macho_with_rpaths(CPU_TYPE_ARM64, &["@executable_path/Frameworks", "@executable_path/../../Frameworks"]);
+        assert_eq!(rpaths(&image).unwrap(), vec!["@executable_path/Frameworks", "@executable_path/../../Frameworks"]);
+        assert!(rpaths(&thin_macho(CPU_TYPE_ARM64, None)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_simulator_detection() {
+        use super::test_support::thin_macho_for_platform;
+        assert!(is_simulator_binary(&thin_macho_for_platform(CPU_TYPE_ARM64, PLATFORM_IOSSIMULATOR)).unwrap());
+        assert!(!is_simulator_binary(&thin_macho_for_platform(CPU_TYPE_ARM64, PLATFORM_IOS)).unwrap());
+        assert!(is_simulator_binary(&thin_macho(CPU_TYPE_X86_64, None)).unwrap());
+        assert!(!is_simulator_binary(&thin_macho(CPU_TYPE_ARM64, None)).unwrap());
+    }
+
+    #[test]
+    fn test_garbage_is_not_macho() {
+        assert_eq!(slices(b"not a binary at all"), Err(MachOError::NotMachO));
+        assert!(matches!(slices(&[0xCF, 0xFA, 0xED, 0xFE, 0, 0]), Err(MachOError::Truncated(_))));
+    }
+}
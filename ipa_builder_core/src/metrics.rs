@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::io;
+use uuid::Uuid;
+
+use crate::database;
+
+/// File name of the metrics database in the data directory.
+pub const METRICS_DB_FILE_NAME: &str = "metrics.sqlite";
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum MetricEvent {
+    AppLaunched,
+    OutputDirectorySet,
+    AppAdded {
+        app_name: String,
+    },
+    AppRemoved {
+        app_name: String,
+    },
+    AppRenamed {
+        old_app_name: String,
+        new_app_name: String,
+    },
+    IpaGenerated {
+        app_name: String,
+        success: bool,
+        duration_ms: u128,
+        output_size_bytes: u64,
+        /// The build's release channel; `None` for builds without one and older entries.
+        #[serde(default)]
+        channel: Option<String>,
+    },
+    AppConfigEdited {
+        app_id: String, // Using app_id to identify which config was edited
+    },
+    // Could add more like ThemeChanged, ConfigOpened etc.
+}
+
+impl MetricEvent {
+    /// The variant's name, stored in its own column so events of one kind can be queried.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MetricEvent::AppLaunched => "AppLaunched",
+            MetricEvent::OutputDirectorySet => "OutputDirectorySet",
+            MetricEvent::AppAdded { .. } => "AppAdded",
+            MetricEvent::AppRemoved { .. } => "AppRemoved",
+            MetricEvent::AppRenamed { .. } => "AppRenamed",
+            MetricEvent::IpaGenerated { .. } => "IpaGenerated",
+            MetricEvent::AppConfigEdited { .. } => "AppConfigEdited",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetricEntry {
+    pub id: Uuid, // Unique ID for each metric entry
+    pub timestamp: DateTime<Utc>,
+    pub event: MetricEvent,
+    pub country_code: Option<String>, // To be added later if possible
+    pub sent_to_server: bool, // To track if this metric has been uploaded
+}
+
+impl MetricEntry {
+    pub fn new(event: MetricEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            event,
+            country_code: None, // Placeholder for now
+            sent_to_server: false,
+        }
+    }
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS metrics (
+        id TEXT PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        event TEXT NOT NULL,
+        success INTEGER,
+        duration_ms INTEGER,
+        country_code TEXT,
+        sent_to_server INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS metrics_kind_timestamp ON metrics (kind, timestamp);
+    CREATE INDEX IF NOT EXISTS metrics_timestamp ON metrics (timestamp);";
+
+const ENTRY_COLUMNS: &str = "id, timestamp, event, country_code, sent_to_server";
+
+/// Usage metrics in a SQLite database in the data directory, queried where they are needed
+/// instead of being kept in memory.
+#[derive(Debug)]
+pub struct MetricsCollector {
+    db_path: PathBuf,
+    /// `None` when the database couldn't be opened; metrics are then dropped with an error.
+    connection: Option<Connection>,
+    /// Wait for every write to reach the disk (SQLite `synchronous = FULL`), so a recorded
+    /// metric survives a power loss.
+    pub sync_writes: bool,
+    /// The dashboard statistics, queried once per day or change rather than every frame.
+    stats: Cell<Option<GenerationStats>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GenerationStats {
+    day: NaiveDate,
+    today: usize,
+    all_time: usize,
+    avg_ms: Option<u128>,
+}
+
+impl MetricsCollector {
+    /// Opens the database at `db_path`. On first use, the entries of `metrics.jsonl` next to it,
+    /// where metrics were kept before, are imported and the file renamed to
+    /// `metrics.jsonl.migrated`.
+    pub fn new(db_path: PathBuf) -> Self {
+        let connection = match database::open(&db_path, SCHEMA) {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                log::error!("Failed to open metrics {}: {}", db_path.display(), e);
+                None
+            }
+        };
+        let mut collector = Self { db_path, connection, sync_writes: true, stats: Cell::new(None) };
+        if let Err(e) = collector.import_legacy_jsonl() {
+            log::error!("Failed to import {}: {}", database::legacy_jsonl_path(&collector.db_path).display(), e);
+        }
+        collector
+    }
+
+    fn import_legacy_jsonl(&mut self) -> io::Result<()> {
+        let legacy = database::legacy_jsonl_path(&self.db_path);
+        let Some(connection) = self.connection.as_mut().filter(|_| legacy.is_file()) else {
+            return Ok(());
+        };
+        let entries: Vec<MetricEntry> = database::read_jsonl(&legacy)?;
+        let tx = connection.transaction().map_err(io::Error::other)?;
+        for entry in &entries {
+            insert(&tx, entry, "INSERT OR IGNORE").map_err(io::Error::other)?;
+        }
+        tx.commit().map_err(io::Error::other)?;
+        database::retire_legacy_jsonl(&legacy)?;
+        log::info!("Imported {} metrics from {}", entries.len(), legacy.display());
+        Ok(())
+    }
+
+    pub fn record(&mut self, event: MetricEvent) {
+        let entry = MetricEntry::new(event);
+        self.stats.set(None);
+        if let Err(e) = self.append(&entry) {
+            log::error!("Failed to write metric to {}: {}", self.db_path.display(), e);
+        }
+    }
+
+    fn append(&self, entry: &MetricEntry) -> io::Result<()> {
+        let connection = self.connection()?;
+        connection.pragma_update(None, "synchronous", if self.sync_writes { "FULL" } else { "NORMAL" }).map_err(io::Error::other)?;
+        insert(connection, entry, "INSERT").map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn connection(&self) -> io::Result<&Connection> {
+        self.connection
+            .as_ref()
+            .ok_or_else(|| io::Error::other(format!("The metrics database {} is not open", self.db_path.display())))
+    }
+
+    /// Entries that were not uploaded yet, oldest first.
+    pub fn load_unsent_metrics(&self) -> io::Result<Vec<MetricEntry>> {
+        self.query(&format!("SELECT {} FROM metrics WHERE sent_to_server = 0 ORDER BY timestamp", ENTRY_COLUMNS), [])
+    }
+
+    /// Flags the entries as uploaded, in place.
+    pub fn mark_metrics_as_sent(&self, sent_ids: &[Uuid]) -> io::Result<()> {
+        if sent_ids.is_empty() {
+            return Ok(());
+        }
+        let connection = self.connection()?;
+        let mut statement = connection.prepare("UPDATE metrics SET sent_to_server = 1 WHERE id = ?1").map_err(io::Error::other)?;
+        for id in sent_ids {
+            statement.execute([id.to_string()]).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    /// Entries recorded from `since` (included) until `until` (excluded), oldest first; either
+    /// bound may be left open.
+    pub fn entries_between(&self, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> io::Result<Vec<MetricEntry>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM metrics WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2) ORDER BY timestamp",
+                ENTRY_COLUMNS
+            ),
+            params![since.map(database::timestamp), until.map(database::timestamp)],
+        )
+    }
+
+    /// Deletes the entries recorded before `cutoff` and returns how many there were.
+    pub fn prune_before(&self, cutoff: DateTime<Utc>) -> io::Result<usize> {
+        self.stats.set(None);
+        self.connection()?.execute("DELETE FROM metrics WHERE timestamp < ?1", [database::timestamp(cutoff)]).map_err(io::Error::other)
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> io::Result<Vec<MetricEntry>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(sql).map_err(io::Error::other)?;
+        let rows = statement
+            .query_map(params, |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get(3)?, row.get(4)?))
+            })
+            .map_err(io::Error::other)?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, timestamp, event, country_code, sent_to_server) = row.map_err(io::Error::other)?;
+            let parsed = Uuid::parse_str(&id).ok().zip(database::parse_timestamp(&timestamp)).zip(serde_json::from_str(&event).ok());
+            match parsed {
+                Some(((id, timestamp), event)) => entries.push(MetricEntry { id, timestamp, event, country_code, sent_to_server }),
+                None => log::warn!("Skipping unreadable metric {} in {}", id, self.db_path.display()),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Runs an aggregate over successful generations, logging errors as no result.
+    fn successful_generations<T: rusqlite::types::FromSql>(&self, select: &str, since: Option<&str>) -> Option<T> {
+        let sql = format!("SELECT {} FROM metrics WHERE kind = 'IpaGenerated' AND success = 1 AND (?1 IS NULL OR timestamp >= ?1)", select);
+        let result = self.connection().map_err(|e| e.to_string()).and_then(|connection| {
+            connection.query_row(&sql, [since], |row| row.get::<_, Option<T>>(0)).optional().map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(value) => value.flatten(),
+            Err(e) => {
+                log::error!("Failed to query metrics {}: {}", self.db_path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn stats(&self) -> GenerationStats {
+        let day = Utc::now().date_naive();
+        if let Some(stats) = self.stats.get().filter(|s| s.day == day) {
+            return stats;
+        }
+        let stats = GenerationStats {
+            day,
+            today: self.successful_generations::<i64>("COUNT(*)", Some(&day.to_string())).unwrap_or(0) as usize,
+            all_time: self.successful_generations::<i64>("COUNT(*)", None).unwrap_or(0) as usize,
+            avg_ms: self.successful_generations::<i64>("SUM(duration_ms) / COUNT(*)", None).map(|ms| ms as u128),
+        };
+        self.stats.set(Some(stats));
+        stats
+    }
+
+    // Methods for dashboard statistics
+    pub fn generations_today(&self) -> usize {
+        self.stats().today
+    }
+
+    pub fn generations_all_time(&self) -> usize {
+        self.stats().all_time
+    }
+
+    pub fn avg_generation_speed_ms(&self) -> Option<u128> {
+        self.stats().avg_ms
+    }
+}
+
+fn insert(connection: &Connection, entry: &MetricEntry, verb: &str) -> rusqlite::Result<usize> {
+    let (success, duration_ms) = match &entry.event {
+        MetricEvent::IpaGenerated { success, duration_ms, .. } => (Some(*success), Some(i64::try_from(*duration_ms).unwrap_or(i64::MAX))),
+        _ => (None, None),
+    };
+    let event = serde_json::to_string(&entry.event).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    connection.execute(
+        &format!(
+            "{} INTO metrics (id, timestamp, kind, event, success, duration_ms, country_code, sent_to_server) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            verb
+        ),
+        params![
+            entry.id.to_string(),
+            database::timestamp(entry.timestamp),
+            entry.event.kind(),
+            event,
+            success,
+            duration_ms,
+            entry.country_code,
+            entry.sent_to_server
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_jsonl_is_imported_then_queried_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join(METRICS_DB_FILE_NAME);
+        let old = MetricEntry { timestamp: Utc::now() - chrono::Duration::days(40), ..MetricEntry::new(MetricEvent::AppLaunched) };
+        let generated = MetricEntry::new(MetricEvent::IpaGenerated { app_name: "Shop".to_string(), success: true, duration_ms: 1500, output_size_bytes: 10, channel: None });
+        let mut jsonl = std::fs::File::create(dir.path().join("metrics.jsonl")).unwrap();
+        for entry in [&old, &generated] {
+            writeln!(jsonl, "{}", serde_json::to_string(entry).unwrap()).unwrap();
+        }
+        // A power loss in the middle of the next append.
+        write!(jsonl, "{{\"id\":\"7c1e").unwrap();
+        drop(jsonl);
+
+        let mut collector = MetricsCollector::new(db_path.clone());
+        assert!(!dir.path().join("metrics.jsonl").exists());
+        assert!(dir.path().join("metrics.jsonl.migrated").exists());
+        collector.record(MetricEvent::IpaGenerated { app_name: "Shop".to_string(), success: true, duration_ms: 500, output_size_bytes: 10, channel: None });
+        collector.record(MetricEvent::IpaGenerated { app_name: "Shop".to_string(), success: false, duration_ms: 9, output_size_bytes: 0, channel: None });
+        assert_eq!((collector.generations_today(), collector.generations_all_time()), (2, 2));
+        assert_eq!(collector.avg_generation_speed_ms(), Some(1000));
+
+        // Reopening doesn't import again.
+        let collector = MetricsCollector::new(db_path.clone());
+        let unsent = collector.load_unsent_metrics().unwrap();
+        assert_eq!(unsent.len(), 4);
+        assert_eq!(unsent[0].id, old.id);
+        collector.mark_metrics_as_sent(&[old.id, generated.id]).unwrap();
+        assert_eq!(collector.load_unsent_metrics().unwrap().len(), 2);
+
+        let last_month = Utc::now() - chrono::Duration::days(30);
+        assert_eq!(collector.entries_between(Some(last_month), None).unwrap().len(), 3);
+        assert_eq!(collector.entries_between(None, Some(last_month)).unwrap()[0].id, old.id);
+        assert_eq!(collector.prune_before(last_month).unwrap(), 1);
+        assert_eq!(MetricsCollector::new(db_path).entries_between(None, None).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::info_plist::BundleVariables;
+use crate::ipa_logic::IpaError;
+
+/// Output file name rules that hold on every filesystem we may write to. The output
+/// directory can be an SMB share backed by Windows even when we run on Linux, so the
+/// Windows rules are always applied.
+const FORBIDDEN_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const RESERVED_STEMS: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+/// Most filesystems (NTFS, ext4, APFS) cap a single path component at 255 bytes/units.
+pub const MAX_FILE_NAME_BYTES: usize = 255;
+const IPA_EXTENSION: &str = ".ipa";
+
+/// Checks that `name` is a portable `.ipa` file name, returning a human readable reason otherwise.
+pub fn validate_ipa_file_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("name cannot be empty".to_string());
+    }
+    if name != name.trim() {
+        return Err("name cannot start or end with whitespace".to_string());
+    }
+    if !name.to_lowercase().ends_with(IPA_EXTENSION) {
+        return Err("name must end with .ipa".to_string());
+    }
+    if let Some(c) = name.chars().find(|c| FORBIDDEN_CHARS.contains(c) || c.is_control()) {
+        return Err(format!("character {:?} is not allowed on Windows filesystems", c));
+    }
+    let stem = &name[..name.len() - IPA_EXTENSION.len()];
+    if stem.is_empty() {
+        return Err("name needs a file name before .ipa".to_string());
+    }
+    if stem.ends_with('.') || stem.ends_with(' ') {
+        return Err("name cannot end with a dot or space before .ipa".to_string());
+    }
+    let base = stem.split('.').next().unwrap_or(stem).trim_end();
+    if RESERVED_STEMS.iter().any(|r| r.eq_ignore_ascii_case(base)) {
+        return Err(format!("'{}' is a reserved device name on Windows", base));
+    }
+    if name.len() > MAX_FILE_NAME_BYTES {
+        return Err(format!("name is {} bytes long, the limit is {}", name.len(), MAX_FILE_NAME_BYTES));
+    }
+    Ok(())
+}
+
+/// Produces the closest portable `.ipa` file name to `name`.
+pub fn sanitize_ipa_file_name(name: &str) -> String {
+    let trimmed = name.trim();
+    let stem = if trimmed.to_lowercase().ends_with(IPA_EXTENSION) {
+        &trimmed[..trimmed.len() - IPA_EXTENSION.len()]
+    } else {
+        trimmed
+    };
+
+    let mut stem: String = stem
+        .chars()
+        .map(|c| if FORBIDDEN_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+    let trimmed_len = stem.trim_end_matches(['.', ' ']).len();
+    stem.truncate(trimmed_len);
+    if stem.trim().is_empty() {
+        stem = "app".to_string();
+    }
+
+    let base = stem.split('.').next().unwrap_or(&stem).trim_end();
+    if RESERVED_STEMS.iter().any(|r| r.eq_ignore_ascii_case(base)) {
+        stem.insert(base.len(), '_');
+    }
+
+    let max_stem_bytes = MAX_FILE_NAME_BYTES - IPA_EXTENSION.len();
+    if stem.len() > max_stem_bytes {
+        let mut cut = max_stem_bytes;
+        while !stem.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        stem.truncate(cut);
+    }
+
+    format!("{}{}", stem, IPA_EXTENSION)
+}
+
+/// What a build does when its IPA already exists in the output directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputExistsPolicy {
+    #[default]
+    Overwrite,
+    /// Fail with [`IpaError::OutputExists`] before doing any work.
+    Fail,
+    /// `app (2).ipa`, `app (3).ipa`, ...
+    NumberSuffix,
+    /// `app 2024-05-01 143005.ipa`, numbered as well if two builds finish in the same second.
+    TimestampSuffix,
+}
+
+impl OutputExistsPolicy {
+    pub const ALL: [OutputExistsPolicy; 4] = [Self::Overwrite, Self::Fail, Self::NumberSuffix, Self::TimestampSuffix];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Overwrite => "Overwrite it",
+            Self::Fail => "Fail the build",
+            Self::NumberSuffix => "Add a number: app (2).ipa",
+            Self::TimestampSuffix => "Add the date and time",
+        }
+    }
+}
+
+/// Where the IPA named `file_name` is written in `output_dir` under `policy`. `now` is local time,
+/// used by [`OutputExistsPolicy::TimestampSuffix`]. Suffixed names are shortened to stay within
+/// [`MAX_FILE_NAME_BYTES`] and checked again.
+pub fn resolve_output_path(output_dir: &Path, file_name: &str, policy: OutputExistsPolicy, now: NaiveDateTime) -> Result<PathBuf, IpaError> {
+    let path = output_dir.join(file_name);
+    if !path.exists() {
+        return Ok(path);
+    }
+    let stem = &file_name[..file_name.len() - IPA_EXTENSION.len()];
+    let numbered = |suffix: &str| {
+        (2..)
+            .map(|n| suffixed_name(stem, &format!("{} ({})", suffix, n)))
+            .find(|candidate| !output_dir.join(candidate).exists())
+            .expect("unbounded range")
+    };
+    let name = match policy {
+        OutputExistsPolicy::Overwrite => return Ok(path),
+        OutputExistsPolicy::Fail => return Err(IpaError::OutputExists(path)),
+        OutputExistsPolicy::NumberSuffix => numbered(""),
+        OutputExistsPolicy::TimestampSuffix => {
+            let stamp = format!(" {}", now.format("%Y-%m-%d %H%M%S"));
+            let stamped = suffixed_name(stem, &stamp);
+            if output_dir.join(&stamped).exists() { numbered(&stamp) } else { stamped }
+        }
+    };
+    if let Err(reason) = validate_ipa_file_name(&name) {
+        return Err(IpaError::InvalidIpaName { suggestion: sanitize_ipa_file_name(&name), name, reason });
+    }
+    Ok(output_dir.join(name))
+}
+
+/// `{stem}{suffix}.ipa`, cutting `stem` at a character boundary so the suffix still fits.
+fn suffixed_name(stem: &str, suffix: &str) -> String {
+    let mut cut = stem.len().min(MAX_FILE_NAME_BYTES.saturating_sub(suffix.len() + IPA_EXTENSION.len()));
+    while !stem.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}{}{}", &stem[..cut], suffix, IPA_EXTENSION)
+}
+
+/// Fills the `{bundle_id}`-style placeholders of `file_name` in from `bundle`, then resolves the
+/// result like [`resolve_output_path`].
+pub fn resolve_templated_output_path(output_dir: &Path, file_name: &str, bundle: &BundleVariables, policy: OutputExistsPolicy, now: NaiveDateTime) -> Result<PathBuf, IpaError> {
+    let name = bundle.expand(file_name);
+    if let Err(reason) = validate_ipa_file_name(&name) {
+        return Err(IpaError::InvalidIpaName { suggestion: sanitize_ipa_file_name(&name), name, reason });
+    }
+    resolve_output_path(output_dir, &name, policy, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_windows_rules() {
+        assert!(validate_ipa_file_name("MyApp v1.2.ipa").is_ok());
+        assert!(validate_ipa_file_name("MyApp.IPA").is_ok());
+        assert!(validate_ipa_file_name("My:App.ipa").is_err());
+        assert!(validate_ipa_file_name("sub/App.ipa").is_err());
+        assert!(validate_ipa_file_name("con.ipa").is_err());
+        assert!(validate_ipa_file_name("LPT1.beta.ipa").is_err());
+        assert!(validate_ipa_file_name("App..ipa").is_err());
+        assert!(validate_ipa_file_name("App.zip").is_err());
+        assert!(validate_ipa_file_name(&format!("{}.ipa", "a".repeat(252))).is_err());
+    }
+
+    #[test]
+    fn test_sanitized_names_are_valid() {
+        let long_name = "é".repeat(200);
+        let inputs = ["My:App?.ipa", "CON.ipa", "aux.beta", "  trailing. .ipa", "", long_name.as_str(), "a<b>|c"];
+        for input in inputs {
+            let sanitized = sanitize_ipa_file_name(input);
+            assert!(validate_ipa_file_name(&sanitized).is_ok(), "{:?} -> {:?}", input, sanitized);
+        }
+        assert_eq!(sanitize_ipa_file_name("My:App?.ipa"), "My_App_.ipa");
+        assert_eq!(sanitize_ipa_file_name("CON.ipa"), "CON_.ipa");
+        assert_eq!(sanitize_ipa_file_name("Valid.ipa"), "Valid.ipa");
+    }
+
+    #[test]
+    fn test_existing_outputs_follow_the_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(14, 30, 5).unwrap();
+        let resolve = |policy| resolve_output_path(dir.path(), "Shop.IPA", policy, now);
+        assert_eq!(resolve(OutputExistsPolicy::Fail).unwrap(), dir.path().join("Shop.IPA"));
+
+        std::fs::write(dir.path().join("Shop.IPA"), "").unwrap();
+        std::fs::write(dir.path().join("Shop (2).ipa"), "").unwrap();
+        assert_eq!(resolve(OutputExistsPolicy::Overwrite).unwrap(), dir.path().join("Shop.IPA"));
+        assert!(matches!(resolve(OutputExistsPolicy::Fail), Err(IpaError::OutputExists(_))));
+        assert_eq!(resolve(OutputExistsPolicy::NumberSuffix).unwrap(), dir.path().join("Shop (3).ipa"));
+        assert_eq!(resolve(OutputExistsPolicy::TimestampSuffix).unwrap(), dir.path().join("Shop 2024-05-01 143005.ipa"));
+        std::fs::write(dir.path().join("Shop 2024-05-01 143005.ipa"), "").unwrap();
+        assert_eq!(resolve(OutputExistsPolicy::TimestampSuffix).unwrap(), dir.path().join("Shop 2024-05-01 143005 (2).ipa"));
+    }
+
+    #[test]
+    fn test_suffixes_keep_long_names_within_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(14, 30, 5).unwrap();
+        // A 250-byte stem whose two-byte characters straddle the 247 bytes left beside " (2).ipa".
+        let long = format!("{}{}.ipa", "a".repeat(246), "é".repeat(2));
+        assert_eq!(long.len(), 254);
+        std::fs::write(dir.path().join(&long), "").unwrap();
+        for policy in [OutputExistsPolicy::NumberSuffix, OutputExistsPolicy::TimestampSuffix] {
+            let path = resolve_output_path(dir.path(), &long, policy, now).unwrap();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            assert!(validate_ipa_file_name(name).is_ok(), "{}", name);
+            assert!(name.starts_with(&"a".repeat(200)), "{}", name);
+        }
+        let numbered = resolve_output_path(dir.path(), &long, OutputExistsPolicy::NumberSuffix, now).unwrap();
+        assert_eq!(numbered.file_name().unwrap().to_str().unwrap(), format!("{} (2).ipa", "a".repeat(246)));
+    }
+}
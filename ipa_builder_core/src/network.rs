@@ -0,0 +1,97 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+
+/// Proxy and certificate settings every outgoing HTTP request goes through: agent uploads,
+/// input downloads and anything added later. Behind a TLS-inspecting corporate proxy, set the
+/// proxy's CA as the CA bundle.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// `http://proxy.corp:3128`, optionally with `user:password@`. Empty uses the `ALL_PROXY`,
+    /// `HTTPS_PROXY` or `HTTP_PROXY` environment variable, if set.
+    pub proxy_url: String,
+    /// PEM file of root certificates trusted in addition to the built-in Mozilla roots.
+    pub ca_bundle_path: String,
+    /// Air-gapped mode: no HTTP request is made at all. See [`OFFLINE_DISABLED_FEATURES`].
+    pub offline: bool,
+}
+
+/// What offline mode turns off, for the UI.
+pub const OFFLINE_DISABLED_FEATURES: [&str; 5] = [
+    "Remote build agent: builds run on this machine",
+    "Downloading http(s):// inputs: only already cached copies are used",
+    "App Store Connect uploads",
+    "Share service uploads (Diawi)",
+    "Build webhooks",
+];
+
+impl NetworkSettings {
+    /// A `ureq` agent using these settings. Fails in offline mode, and when the proxy URL is
+    /// invalid or the CA bundle can't be read or holds no usable certificate.
+    pub fn http_agent(&self) -> Result<ureq::Agent, String> {
+        if self.offline {
+            return Err("Offline mode is on; network access is disabled in Settings".to_string());
+        }
+        let mut builder = ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(10));
+        let proxy_url = self.proxy_url.trim();
+        builder = if proxy_url.is_empty() {
+            builder.try_proxy_from_env(true)
+        } else {
+            builder.proxy(ureq::Proxy::new(proxy_url).map_err(|e| format!("Invalid proxy URL {}: {}", proxy_url, e))?)
+        };
+        let ca_bundle = self.ca_bundle_path.trim();
+        if !ca_bundle.is_empty() {
+            builder = builder.tls_config(Arc::new(tls_config(Path::new(ca_bundle))?));
+        }
+        Ok(builder.build())
+    }
+}
+
+/// TLS client config trusting the Mozilla roots plus every certificate in `ca_bundle`.
+fn tls_config(ca_bundle: &Path) -> Result<rustls::ClientConfig, String> {
+    let certs = CertificateDer::pem_file_iter(ca_bundle)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read CA bundle {}: {}", ca_bundle.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in CA bundle {}", ca_bundle.display()));
+    }
+    let mut roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    for cert in certs {
+        roots.add(cert).map_err(|e| format!("Unusable certificate in CA bundle {}: {}", ca_bundle.display(), e))?;
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_and_ca_bundle_are_checked() {
+        assert!(NetworkSettings::default().http_agent().is_ok());
+        let offline = NetworkSettings { offline: true, ..Default::default() };
+        assert!(offline.http_agent().unwrap_err().starts_with("Offline mode is on"));
+        let proxy = NetworkSettings { proxy_url: " http://user:pw@proxy.corp:3128 ".to_string(), ..Default::default() };
+        assert!(proxy.http_agent().is_ok());
+        let bad_proxy = NetworkSettings { proxy_url: "ftp://proxy.corp".to_string(), ..Default::default() };
+        assert!(bad_proxy.http_agent().unwrap_err().starts_with("Invalid proxy URL"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = NetworkSettings { ca_bundle_path: dir.path().join("corp.pem").display().to_string(), ..Default::default() };
+        assert!(missing.http_agent().unwrap_err().starts_with("Failed to read CA bundle"));
+        let empty = dir.path().join("empty.pem");
+        std::fs::write(&empty, "not a certificate\n").unwrap();
+        let empty = NetworkSettings { ca_bundle_path: empty.display().to_string(), ..Default::default() };
+        assert!(empty.http_agent().unwrap_err().starts_with("No certificates found"));
+    }
+}
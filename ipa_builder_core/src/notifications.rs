@@ -0,0 +1,345 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::size_analysis::format_size;
+use crate::webhook::BuildEvent;
+
+/// Something about a finished build that can be notified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    Succeeded,
+    Failed,
+    /// A successful build whose IPA is larger than the config's size budget.
+    SizeBudgetExceeded,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 3] = [NotificationEvent::Succeeded, NotificationEvent::Failed, NotificationEvent::SizeBudgetExceeded];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationEvent::Succeeded => "Build succeeded",
+            NotificationEvent::Failed => "Build failed",
+            NotificationEvent::SizeBudgetExceeded => "Over size budget",
+        }
+    }
+}
+
+/// Where notifications go. Toasts and desktop notifications only exist in the GUI.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Channels {
+    /// A message in the corner of the IPA Builder window.
+    pub toast: bool,
+    /// The operating system's notification center.
+    pub desktop: bool,
+    /// The webhooks in the settings and the config's own.
+    pub webhook: bool,
+    /// The config's email recipients, through the sendmail command in the settings.
+    pub email: bool,
+}
+
+impl Default for Channels {
+    fn default() -> Self {
+        Self { toast: true, desktop: false, webhook: true, email: false }
+    }
+}
+
+impl Channels {
+    pub const NONE: Channels = Channels { toast: false, desktop: false, webhook: false, email: false };
+
+    fn union(self, other: Channels) -> Channels {
+        Channels {
+            toast: self.toast || other.toast,
+            desktop: self.desktop || other.desktop,
+            webhook: self.webhook || other.webhook,
+            email: self.email || other.email,
+        }
+    }
+}
+
+/// Which build events of a config notify, and through which channels.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    pub succeeded: Channels,
+    pub failed: Channels,
+    pub size_budget_exceeded: Channels,
+    /// IPA size in MB above which a successful build also raises
+    /// [`NotificationEvent::SizeBudgetExceeded`]; 0 is no budget.
+    pub size_budget_mb: u64,
+    pub email_recipients: Vec<String>,
+    /// HTTPS URL of the app's icon, shown in Slack and Discord messages.
+    pub icon_url: String,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        let alert = Channels { desktop: true, ..Channels::default() };
+        Self {
+            succeeded: Channels::default(),
+            failed: alert,
+            size_budget_exceeded: alert,
+            size_budget_mb: 0,
+            email_recipients: Vec::new(),
+            icon_url: String::new(),
+        }
+    }
+}
+
+impl NotificationPreferences {
+    pub fn channels(&self, event: NotificationEvent) -> &Channels {
+        match event {
+            NotificationEvent::Succeeded => &self.succeeded,
+            NotificationEvent::Failed => &self.failed,
+            NotificationEvent::SizeBudgetExceeded => &self.size_budget_exceeded,
+        }
+    }
+
+    pub fn channels_mut(&mut self, event: NotificationEvent) -> &mut Channels {
+        match event {
+            NotificationEvent::Succeeded => &mut self.succeeded,
+            NotificationEvent::Failed => &mut self.failed,
+            NotificationEvent::SizeBudgetExceeded => &mut self.size_budget_exceeded,
+        }
+    }
+
+    /// The size budget in bytes, if there is one.
+    pub fn size_budget_bytes(&self) -> Option<u64> {
+        (self.size_budget_mb > 0).then(|| self.size_budget_mb * 1024 * 1024)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(address) = self.email_recipients.iter().find(|a| !a.contains('@') || a.contains(char::is_whitespace)) {
+            return Err(format!("'{}' is not an email address", address));
+        }
+        let icon = self.icon_url.trim();
+        if !icon.is_empty() && !icon.starts_with("https://") {
+            return Err("The icon URL must start with https://".to_string());
+        }
+        let emails = NotificationEvent::ALL.iter().any(|&e| self.channels(e).email);
+        if emails && self.email_recipients.is_empty() {
+            return Err("Add an email recipient, or turn off email notifications.".to_string());
+        }
+        Ok(())
+    }
+
+    /// The events `build` raises: whether it succeeded, and whether it went over the budget.
+    pub fn events_for(&self, build: &BuildEvent) -> Vec<NotificationEvent> {
+        if !build.success {
+            return vec![NotificationEvent::Failed];
+        }
+        let mut events = vec![NotificationEvent::Succeeded];
+        if build.size_budget_bytes.is_some() {
+            events.push(NotificationEvent::SizeBudgetExceeded);
+        }
+        events
+    }
+
+    /// Every channel that one of `build`'s events goes to.
+    pub fn channels_for(&self, build: &BuildEvent) -> Channels {
+        self.events_for(build).into_iter().fold(Channels::NONE, |channels, event| channels.union(*self.channels(event)))
+    }
+}
+
+/// Where email notifications are sent from; in the application settings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct EmailSettings {
+    /// A sendmail-compatible program (sendmail, msmtp, Postfix's sendmail), run with `-t -i`
+    /// and the message on stdin.
+    pub sendmail_command: String,
+    /// `From` address; empty leaves it to the mail program.
+    pub from: String,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self { sendmail_command: "sendmail".to_string(), from: String::new() }
+    }
+}
+
+/// Short title of `build`'s notification, e.g. `Shop 1.4 (42) built`.
+pub fn title(build: &BuildEvent) -> String {
+    let version = match (&build.version, &build.build) {
+        (Some(version), Some(number)) => format!(" {} ({})", version, number),
+        _ => String::new(),
+    };
+    match (build.success, build.size_budget_bytes) {
+        (false, _) => format!("{} build failed", build.app_name),
+        (true, Some(_)) => format!("{}{} is over its size budget", build.app_name, version),
+        (true, None) => format!("{}{} built", build.app_name, version),
+    }
+}
+
+/// A few lines on `build` for desktop notifications and emails.
+pub fn body(build: &BuildEvent) -> String {
+    let mut lines = Vec::new();
+    if let Some(error) = &build.error {
+        lines.push(error.clone());
+    }
+    if let Some(size) = build.size_bytes {
+        let budget = build.size_budget_bytes.map(|b| format!(" (budget {})", format_size(b))).unwrap_or_default();
+        lines.push(format!("Size: {}{}", format_size(size), budget));
+    }
+    lines.push(format!("Duration: {:.1}s", build.duration_ms as f64 / 1000.0));
+    if let Some(url) = &build.ipa_url {
+        lines.push(format!("Download: {}", url));
+    } else if let Some(path) = &build.ipa_path {
+        lines.push(format!("Output: {}", path));
+    }
+    if !build.warnings.is_empty() {
+        lines.push(format!("{} warning(s)", build.warnings.len()));
+    }
+    lines.join("\n")
+}
+
+/// The RFC 5322 message [`send_email`] pipes to the mail program. Line breaks in the header
+/// values are replaced, so no value can add headers of its own.
+fn email_message(settings: &EmailSettings, recipients: &[String], subject: &str, body: &str) -> String {
+    let mut message = String::new();
+    if !settings.from.trim().is_empty() {
+        message.push_str(&format!("From: {}\r\n", header_value(settings.from.trim())));
+    }
+    let recipients: Vec<String> = recipients.iter().map(|r| header_value(r)).collect();
+    message.push_str(&format!("To: {}\r\n", recipients.join(", ")));
+    message.push_str(&format!("Subject: {}\r\n", encoded_words(&header_value(subject))));
+    message.push_str("MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n");
+    message.push_str(&body.replace('\n', "\r\n"));
+    message.push_str("\r\n");
+    message
+}
+
+fn header_value(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// `text` as RFC 2047 `=?UTF-8?Q?...?=` encoded words when it isn't plain ASCII, each at most 75
+/// characters long, on folded lines.
+fn encoded_words(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    let mut words = vec![String::new()];
+    for c in text.chars() {
+        let mut encoded = String::new();
+        for byte in c.to_string().bytes() {
+            match byte {
+                b' ' => encoded.push('_'),
+                b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' | b'!' | b'*' | b'+' | b'-' | b'/' => encoded.push(byte as char),
+                _ => encoded.push_str(&format!("={:02X}", byte)),
+            }
+        }
+        // `=?UTF-8?Q?` and `?=` take 12 of the 75 characters.
+        if words.last().is_some_and(|word| word.len() + encoded.len() > 63) {
+            words.push(String::new());
+        }
+        words.last_mut().expect("never empty").push_str(&encoded);
+    }
+    words.iter().map(|word| format!("=?UTF-8?Q?{}?=", word)).collect::<Vec<_>>().join("\r\n ")
+}
+
+/// Emails `build`'s notification to `recipients` through the configured sendmail command.
+pub fn send_email(settings: &EmailSettings, recipients: &[String], build: &BuildEvent) -> Result<(), String> {
+    let command = settings.sendmail_command.trim();
+    let message = email_message(settings, recipients, &title(build), &format!("{}\n\n{}", build.text, body(build)));
+    let mut child = Command::new(command)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes()).map_err(|e| format!("Failed to pass the message to {}: {}", command, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("{} failed: {}", command, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed ({}): {}", command, output.status, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+/// Shows a notification in the operating system's notification center: `osascript` on macOS,
+/// a PowerShell toast on Windows and `notify-send` elsewhere.
+pub fn show_desktop_notification(title: &str, body: &str) -> Result<(), String> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut c = Command::new("osascript");
+        c.args(["-e", "on run argv", "-e", "display notification (item 2 of argv) with title (item 1 of argv)", "-e", "end run", title, body]);
+        c
+    } else if cfg!(target_os = "windows") {
+        // The text is passed in the environment so it needs no quoting in the script.
+        let script = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+            $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+            $text = $xml.GetElementsByTagName('text'); \
+            $text.Item(0).AppendChild($xml.CreateTextNode($env:IPA_BUILDER_TITLE)) > $null; \
+            $text.Item(1).AppendChild($xml.CreateTextNode($env:IPA_BUILDER_BODY)) > $null; \
+            [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('IPA Builder').Show([Windows.UI.Notifications.ToastNotification]::new($xml))";
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", script]).env("IPA_BUILDER_TITLE", title).env("IPA_BUILDER_BODY", body);
+        c
+    } else {
+        let mut c = Command::new("notify-send");
+        c.args(["--app-name=IPA Builder", title, body]);
+        c
+    };
+    let output = cmd.output().map_err(|e| format!("Failed to show a desktop notification: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("Desktop notification failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_pick_their_channels() {
+        let prefs = NotificationPreferences {
+            succeeded: Channels::NONE,
+            size_budget_exceeded: Channels { email: true, ..Channels::NONE },
+            size_budget_mb: 50,
+            email_recipients: vec!["qa@example.com".to_string()],
+            ..Default::default()
+        };
+        assert!(prefs.validate().is_ok());
+
+        let quiet = BuildEvent { success: true, app_name: "Shop".to_string(), size_bytes: Some(1024), ..Default::default() };
+        assert_eq!(prefs.events_for(&quiet), [NotificationEvent::Succeeded]);
+        assert_eq!(prefs.channels_for(&quiet), Channels::NONE);
+
+        let big = BuildEvent {
+            size_bytes: Some(60 * 1024 * 1024),
+            size_budget_bytes: prefs.size_budget_bytes(),
+            version: Some("1.4".to_string()),
+            build: Some("42".to_string()),
+            ..quiet.clone()
+        };
+        assert_eq!(prefs.channels_for(&big), Channels { email: true, ..Channels::NONE });
+        assert_eq!(title(&big), "Shop 1.4 (42) is over its size budget");
+        assert!(body(&big).starts_with("Size: 60.0 MB (budget 50.0 MB)\n"), "{}", body(&big));
+
+        let failed = BuildEvent { success: false, error: Some("No .app found".to_string()), ..quiet };
+        assert_eq!(prefs.channels_for(&failed), prefs.failed);
+
+        let message = email_message(&EmailSettings { from: "builds@example.com".to_string(), ..Default::default() }, &prefs.email_recipients, &title(&failed), "line 1\nline 2");
+        assert_eq!(message, "From: builds@example.com\r\nTo: qa@example.com\r\nSubject: Shop build failed\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\nline 1\r\nline 2\r\n");
+
+        let hostile = EmailSettings { from: "builds@example.com\r\nBcc: all@example.com".to_string(), ..Default::default() };
+        let message = email_message(&hostile, &["qa@example.com\nCc: boss@example.com".to_string()], "Café ✓\r\nX-Spam: yes", "");
+        let headers = message.split("\r\n\r\n").next().unwrap();
+        assert!(headers.lines().all(|line| !line.starts_with("Bcc") && !line.starts_with("Cc") && !line.starts_with("X-Spam")), "{}", headers);
+        assert!(headers.contains("Subject: =?UTF-8?Q?Caf=C3=A9_=E2=9C=93__X-Spam=3A_yes?=\r\nMIME"), "{}", headers);
+        let long = encoded_words(&"é".repeat(40));
+        assert!(long.split("\r\n ").all(|word| word.len() <= 75 && word.starts_with("=?UTF-8?Q?") && word.ends_with("?=")), "{}", long);
+        assert_eq!(long.matches("=C3=A9").count(), 40);
+
+        let no_recipients = NotificationPreferences { failed: Channels { email: true, ..Channels::NONE }, ..Default::default() };
+        assert!(no_recipients.validate().is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::info_plist::BundleVariables;
+
+/// The `manifest.plist` that `itms-services://?action=download-manifest&url=…` links download
+/// to install an IPA over the air, written next to every IPA built from a config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct OtaManifest {
+    pub enabled: bool,
+    /// HTTPS URL of the folder the IPA is uploaded to; the IPA's URL is this plus its file name.
+    pub base_url: String,
+    /// Shown by iOS in the install prompt; empty uses the app name.
+    pub title: String,
+}
+
+impl OtaManifest {
+    /// Checks the base URL when the manifest is enabled; iOS refuses OTA installs over plain HTTP.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let base_url = self.base_url.trim();
+        if base_url.is_empty() {
+            return Err("Enter the HTTPS URL the IPA will be served from.".to_string());
+        }
+        if !base_url.starts_with("https://") {
+            return Err("The base URL must start with https://; iOS only installs over HTTPS.".to_string());
+        }
+        Ok(())
+    }
+
+    /// URL the IPA at `ipa_path` is downloaded from.
+    pub fn ipa_url(&self, ipa_path: &Path) -> String {
+        let file_name = ipa_path.file_name().unwrap_or_default().to_string_lossy();
+        format!("{}/{}", self.base_url.trim().trim_end_matches('/'), percent_encode(&file_name))
+    }
+
+    /// The `itms-services://` link that installs the IPA at `ipa_path` once it and its manifest
+    /// are uploaded to `base_url`.
+    pub fn install_link_for(&self, ipa_path: &Path) -> String {
+        install_link(&self.ipa_url(&manifest_path(ipa_path)))
+    }
+
+    /// Writes [`manifest_path`] for `ipa_path`, using the bundle ID and version of the packaged
+    /// app. Returns the manifest's path.
+    pub fn write_next_to(&self, ipa_path: &Path, bundle: &BundleVariables, app_name: &str) -> Result<PathBuf, String> {
+        self.validate()?;
+        let title = Some(self.title.trim()).filter(|t| !t.is_empty()).unwrap_or(app_name);
+        let version = [&bundle.short_version, &bundle.build].into_iter().find(|v| !v.is_empty()).map_or("1.0", String::as_str);
+        let plist = manifest_plist(&self.ipa_url(ipa_path), &bundle.bundle_id, version, title)?;
+        let path = manifest_path(ipa_path);
+        fs::write(&path, plist).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// `Shop.manifest.plist` for `Shop.ipa`, so the IPAs of several apps can share an output
+/// directory. Install links name the file, so it needn't be called `manifest.plist`.
+pub fn manifest_path(ipa_path: &Path) -> PathBuf {
+    let stem = ipa_path.file_stem().unwrap_or_default().to_string_lossy();
+    ipa_path.with_file_name(format!("{}.manifest.plist", stem))
+}
+
+/// The `itms-services://` link that installs the app described by the manifest at `manifest_url`.
+pub fn install_link(manifest_url: &str) -> String {
+    format!("itms-services://?action=download-manifest&url={}", percent_encode(manifest_url))
+}
+
+/// Builds the `manifest.plist` that `itms-services://` downloads to install `ipa_url`.
+pub fn manifest_plist(ipa_url: &str, bundle_id: &str, version: &str, title: &str) -> Result<Vec<u8>, String> {
+    if bundle_id.trim().is_empty() {
+        return Err("Info.plist has no CFBundleIdentifier".to_string());
+    }
+    let mut asset = plist::Dictionary::new();
+    asset.insert("kind".into(), "software-package".into());
+    asset.insert("url".into(), ipa_url.into());
+
+    let mut metadata = plist::Dictionary::new();
+    metadata.insert("bundle-identifier".into(), bundle_id.into());
+    metadata.insert("bundle-version".into(), version.into());
+    metadata.insert("kind".into(), "software".into());
+    metadata.insert("title".into(), title.into());
+
+    let mut item = plist::Dictionary::new();
+    item.insert("assets".into(), plist::Value::Array(vec![asset.into()]));
+    item.insert("metadata".into(), metadata.into());
+
+    let mut root = plist::Dictionary::new();
+    root.insert("items".into(), plist::Value::Array(vec![item.into()]));
+
+    let mut out = Vec::new();
+    plist::Value::Dictionary(root)
+        .to_writer_xml(&mut out)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    Ok(out)
+}
+
+/// Moves the manifest of the IPA renamed from `from` to `to`, if it has one, pointing its
+/// download URL at the new file name.
+pub fn rename_with_ipa(from: &Path, to: &Path) -> Result<(), String> {
+    let old_path = manifest_path(from);
+    if !old_path.is_file() {
+        return Ok(());
+    }
+    let mut manifest = plist::Value::from_file(&old_path).map_err(|e| format!("Failed to read {}: {}", old_path.display(), e))?;
+    let old_name = percent_encode(&from.file_name().unwrap_or_default().to_string_lossy());
+    let new_name = percent_encode(&to.file_name().unwrap_or_default().to_string_lossy());
+    let assets = manifest
+        .as_dictionary_mut()
+        .and_then(|root| root.get_mut("items"))
+        .and_then(plist::Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_dictionary_mut()?.get_mut("assets")?.as_array_mut())
+        .flatten();
+    for asset in assets {
+        if let Some(plist::Value::String(url)) = asset.as_dictionary_mut().and_then(|a| a.get_mut("url")) {
+            if let Some(base) = url.strip_suffix(old_name.as_str()) {
+                *url = format!("{}{}", base, new_name);
+            }
+        }
+    }
+    let new_path = manifest_path(to);
+    manifest.to_file_xml(&new_path).map_err(|e| format!("Failed to write {}: {}", new_path.display(), e))?;
+    fs::remove_file(&old_path).map_err(|e| format!("Failed to remove {}: {}", old_path.display(), e))
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters.
+pub fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::AppConfig;
+    use crate::fixtures::{write_fixture, FixtureSpec};
+    use crate::ipa_logic::{generate_ipa, IpaError};
+
+    fn manifest_value(path: &Path, key: &str) -> String {
+        let manifest = plist::Value::from_file(path).unwrap();
+        let item = &manifest.as_dictionary().unwrap()["items"].as_array().unwrap()[0];
+        let item = item.as_dictionary().unwrap();
+        match key {
+            "url" => item["assets"].as_array().unwrap()[0].as_dictionary().unwrap()["url"].as_string().unwrap().to_string(),
+            _ => item["metadata"].as_dictionary().unwrap()[key].as_string().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_manifest_is_written_next_to_every_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_fixture(&FixtureSpec::default(), &input).unwrap();
+        let mut config = AppConfig {
+            app_name: "Shop".to_string(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: "Shop Beta.ipa".to_string(),
+            ota_manifest: OtaManifest { enabled: true, base_url: "http://example.com/builds".to_string(), title: String::new() },
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(matches!(generate_ipa(&config, dir.path()), Err(IpaError::OtaManifest(_))));
+
+        config.ota_manifest.base_url = "https://example.com/builds/".to_string();
+        let ipa = generate_ipa(&config, dir.path()).unwrap();
+        let manifest = dir.path().join("Shop Beta.manifest.plist");
+        assert_eq!(manifest_path(&ipa), manifest);
+        assert_eq!(manifest_value(&manifest, "url"), "https://example.com/builds/Shop%20Beta.ipa");
+        assert_eq!(manifest_value(&manifest, "bundle-identifier"), FixtureSpec::default().bundle_id);
+        assert_eq!(manifest_value(&manifest, "bundle-version"), "1.0.0");
+        assert_eq!(manifest_value(&manifest, "title"), "Shop");
+
+        let renamed = dir.path().join("Shop 1.0.ipa");
+        fs::rename(&ipa, &renamed).unwrap();
+        rename_with_ipa(&ipa, &renamed).unwrap();
+        assert!(!manifest.exists());
+        assert_eq!(manifest_value(&manifest_path(&renamed), "url"), "https://example.com/builds/Shop%201.0.ipa");
+        assert_eq!(
+            config.ota_manifest.install_link_for(Path::new("out/Shop.ipa")),
+            "itms-services://?action=download-manifest&url=https%3A%2F%2Fexample.com%2Fbuilds%2FShop.manifest.plist"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::event::ModifyKind;
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How often watchers check that their directory still exists, and how long they wait between
+/// attempts to re-attach once it is gone.
+pub const WATCHER_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+enum WatchEvent {
+    Changed(PathBuf),
+    Error(String),
+}
+
+/// Watches the output directory so the UI can notice when generated IPAs are
+/// deleted, moved away or restored by something other than this application.
+pub struct OutputDirWatcher {
+    watch_dir: PathBuf,
+    _watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<WatchEvent>,
+    error: Option<String>,
+    next_health_check: Instant,
+}
+
+impl OutputDirWatcher {
+    pub fn start(watch_dir: &Path) -> Result<Self, String> {
+        if !watch_dir.is_dir() {
+            return Err(format!("Output directory is invalid: {}", watch_dir.display()));
+        }
+
+        let (tx, rx) = mpsc::channel::<WatchEvent>();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| match res {
+                Ok(ev) => {
+                    let relevant = matches!(
+                        ev.kind,
+                        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+                    );
+                    if relevant {
+                        for path in ev.paths {
+                            let _ = tx.send(WatchEvent::Changed(path));
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Output directory watcher error: {}", e);
+                    let _ = tx.send(WatchEvent::Error(e.to_string()));
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| format!("Output watcher init error: {}", e))?;
+
+        watcher
+            .watch(watch_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Output watcher start error: {}", e))?;
+
+        log::info!("Watching output directory for external changes: {}", watch_dir.display());
+        Ok(Self {
+            watch_dir: watch_dir.to_path_buf(),
+            _watcher: watcher,
+            rx,
+            error: None,
+            next_health_check: Instant::now(),
+        })
+    }
+
+    pub fn watch_dir(&self) -> &Path {
+        &self.watch_dir
+    }
+
+    /// Drains all paths that were created, removed or renamed since the last call.
+    pub fn drain_changed_paths(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for event in self.rx.try_iter() {
+            match event {
+                WatchEvent::Changed(path) => changed.push(path),
+                WatchEvent::Error(e) => self.error = Some(e),
+            }
+        }
+        changed
+    }
+
+    /// Why this watcher can no longer be trusted (it reported an error, or the directory was
+    /// unmounted or removed), checked at most every [`WATCHER_RETRY_INTERVAL`]. The caller should
+    /// drop the watcher and start a new one once the directory is back.
+    pub fn check_health(&mut self) -> Option<String> {
+        if let Some(e) = &self.error {
+            return Some(format!("watcher error: {}", e));
+        }
+        if Instant::now() < self.next_health_check {
+            return None;
+        }
+        self.next_health_check = Instant::now() + WATCHER_RETRY_INTERVAL;
+        if self.watch_dir.is_dir() {
+            None
+        } else {
+            Some(format!("{} is no longer available", self.watch_dir.display()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drains the watcher until a change to `name` arrives, or gives up after five seconds.
+    fn wait_for_change(watcher: &mut OutputDirWatcher, name: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if watcher.drain_changed_paths().iter().any(|path| path.file_name().is_some_and(|n| n == name)) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_reports_created_and_removed_ipas() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = OutputDirWatcher::start(dir.path()).unwrap();
+        assert!(watcher.drain_changed_paths().is_empty());
+
+        let ipa = dir.path().join("Shop.ipa");
+        std::fs::write(&ipa, b"ipa").unwrap();
+        assert!(wait_for_change(&mut watcher, "Shop.ipa"));
+        std::thread::sleep(Duration::from_millis(200));
+        watcher.drain_changed_paths();
+
+        std::fs::remove_file(&ipa).unwrap();
+        assert!(wait_for_change(&mut watcher, "Shop.ipa"));
+        assert_eq!(watcher.check_health(), None);
+    }
+
+    #[test]
+    fn test_reports_removed_watch_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let watch_dir = dir.path().join("out");
+        std::fs::create_dir(&watch_dir).unwrap();
+        let mut watcher = OutputDirWatcher::start(&watch_dir).unwrap();
+        assert_eq!(watcher.check_health(), None);
+
+        std::fs::remove_dir(&watch_dir).unwrap();
+        watcher.next_health_check = Instant::now();
+        assert!(watcher.check_health().unwrap().contains("no longer available"));
+        assert!(OutputDirWatcher::start(&watch_dir).is_err());
+    }
+}
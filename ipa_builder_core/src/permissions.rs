@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+/// Unix mode of files iOS has to be able to run: Mach-O binaries, dylibs and anything the
+/// rules mark executable.
+pub const EXECUTABLE_MODE: u32 = 0o755;
+/// Unix mode of every other file.
+pub const REGULAR_MODE: u32 = 0o644;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionMode {
+    #[default]
+    Executable,
+    Regular,
+}
+
+impl PermissionMode {
+    pub const ALL: [PermissionMode; 2] = [PermissionMode::Executable, PermissionMode::Regular];
+
+    pub fn mode(self) -> u32 {
+        match self {
+            PermissionMode::Executable => EXECUTABLE_MODE,
+            PermissionMode::Regular => REGULAR_MODE,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PermissionMode::Executable => "Executable (0755)",
+            PermissionMode::Regular => "Not executable (0644)",
+        }
+    }
+}
+
+/// Gives every file whose entry name matches `pattern` the mode `mode`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PermissionOverride {
+    /// Matched against the full entry name in the IPA, e.g. `Payload/Shop.app/Scripts/run.sh`.
+    /// `*` matches any run of characters, `/` included, and `?` any single one.
+    pub pattern: String,
+    pub mode: PermissionMode,
+}
+
+/// How the Unix permissions of files written into the IPA are chosen. Mach-O binaries and
+/// `.dylib`s are always executable; these rules cover what that misses, such as shell scripts
+/// and Node binaries bundled as resources.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct PermissionRules {
+    /// Extensions, without the dot and matched case-insensitively, of files that are also
+    /// executable, e.g. `sh` or `node`.
+    pub executable_extensions: Vec<String>,
+    /// Checked in order before anything else; the first match decides.
+    pub overrides: Vec<PermissionOverride>,
+}
+
+impl PermissionRules {
+    /// No rules beyond the built-in Mach-O and `.dylib` detection.
+    pub fn is_empty(&self) -> bool {
+        self.executable_extensions.iter().all(|e| e.trim().is_empty()) && self.overrides.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ext) = self.executable_extensions.iter().find(|e| e.contains(['/', '\\', '*'])) {
+            return Err(format!("'{}' is not a file extension", ext));
+        }
+        if self.overrides.iter().any(|o| o.pattern.trim().is_empty()) {
+            return Err("Enter a path pattern for every permission override, or remove it.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Unix mode of the file stored as `entry_name`, whose content starts with `header` (four
+    /// bytes are enough to recognise a Mach-O binary).
+    pub fn mode_for(&self, entry_name: &str, header: &[u8]) -> u32 {
+        if let Some(rule) = self.overrides.iter().find(|o| glob_matches(o.pattern.trim(), entry_name)) {
+            return rule.mode.mode();
+        }
+        if crate::ipa_logic::is_macho(header) {
+            return EXECUTABLE_MODE;
+        }
+        let file_name = entry_name.rsplit('/').next().unwrap_or(entry_name);
+        let extension = file_name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+        let executable = extension == "dylib"
+            || (!extension.is_empty()
+                && self.executable_extensions.iter().any(|rule| rule.trim().trim_start_matches('.').eq_ignore_ascii_case(extension)));
+        if executable { EXECUTABLE_MODE } else { REGULAR_MODE }
+    }
+
+    /// Short description for the Edit dialog, e.g. `sh, node executable; 1 override`.
+    pub fn summary(&self) -> String {
+        let extensions: Vec<&str> = self.executable_extensions.iter().map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+        let mut parts = Vec::new();
+        if !extensions.is_empty() {
+            parts.push(format!("{} executable", extensions.join(", ")));
+        }
+        match self.overrides.len() {
+            0 => {}
+            1 => parts.push("1 override".to_string()),
+            n => parts.push(format!("{} overrides", n)),
+        }
+        parts.join("; ")
+    }
+}
+
+/// Whether `text` matches `pattern` in full, with `*` for any run of characters and `?` for one.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and of the text it was tried against, to backtrack to.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_decide_before_the_heuristics() {
+        let macho = [0xCF, 0xFA, 0xED, 0xFE];
+        let defaults = PermissionRules::default();
+        assert_eq!(defaults.mode_for("Payload/Shop.app/Shop", &macho), 0o755);
+        assert_eq!(defaults.mode_for("Payload/Shop.app/Frameworks/libswift.dylib", b"text"), 0o755);
+        assert_eq!(defaults.mode_for("Payload/Shop.app/Scripts/run.sh", b"#!/b"), 0o644);
+
+        let rules = PermissionRules {
+            executable_extensions: vec![".SH".to_string(), "node".to_string()],
+            overrides: vec![
+                PermissionOverride { pattern: "*/Scripts/*.txt".to_string(), mode: PermissionMode::Regular },
+                PermissionOverride { pattern: "*/Scripts/*".to_string(), mode: PermissionMode::Executable },
+                PermissionOverride { pattern: "Payload/Shop.app/Stub?".to_string(), mode: PermissionMode::Regular },
+            ],
+        };
+        assert_eq!(rules.mode_for("Payload/Shop.app/Scripts/lib/run", b"#!/b"), 0o755);
+        assert_eq!(rules.mode_for("Payload/Shop.app/Scripts/README.txt", b"read"), 0o644);
+        assert_eq!(rules.mode_for("Payload/Shop.app/nodejs/bin/app.node", b"text"), 0o755);
+        assert_eq!(rules.mode_for("Payload/Shop.app/setup.sh", b"#!/b"), 0o755);
+        // Overrides win over Mach-O detection.
+        assert_eq!(rules.mode_for("Payload/Shop.app/Stub1", &macho), 0o644);
+        assert_eq!(rules.mode_for("Payload/Shop.app/Stub12", &macho), 0o755);
+        assert_eq!(rules.mode_for("Payload/Shop.app/sh", b"text"), 0o644);
+        assert_eq!(rules.summary(), ".SH, node executable; 3 overrides");
+
+        let blank = PermissionRules { overrides: vec![PermissionOverride::default()], ..Default::default() };
+        assert!(blank.validate().is_err());
+        assert!(PermissionRules { executable_extensions: vec!["*.sh".to_string()], ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::ipa_logic::{IpaError, BUILD_INFO_FILE_NAME};
+use crate::size_analysis::DebugArtifact;
+
+/// How deep below the archive root the `.app` may be, like [`crate::ipa_logic::find_app_bundle`].
+const MAX_BUNDLE_DEPTH: usize = 3;
+
+/// An input ZIP whose `.app` can be copied into an IPA entry by entry, still compressed, instead
+/// of being extracted and compressed again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemapPlan {
+    /// Entry name prefix of the bundle in the input, e.g. `build/Runner.app/`.
+    pub prefix: String,
+    /// e.g. `Runner.app`.
+    pub bundle_name: String,
+    entries: Vec<RemapEntry>,
+    /// When the input is an IPA, the entries next to its `Payload/` that are carried over, see
+    /// [`crate::ipa_logic::keeps_root_entry`]; `relative` holds their full name.
+    root_entries: Vec<RemapEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RemapEntry {
+    index: usize,
+    /// Below the bundle, e.g. `Frameworks/Flutter.framework/Flutter`; empty for the bundle itself.
+    relative: String,
+    size: u64,
+    is_dir: bool,
+}
+
+/// Returns a plan when the archive holds a single `.app` (apps nested inside it are fine) whose
+/// entries all have safe names, Unix permissions and a compression method iOS can install.
+/// Anything else, e.g. a ZIP made on Windows, returns `None` and has to be extracted.
+pub fn plan(archive: &mut ZipArchive<File>) -> Option<RemapPlan> {
+    let mut candidates: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| name.strip_suffix("Info.plist"))
+        .filter(|prefix| prefix.ends_with(".app/") && prefix.matches('/').count() <= MAX_BUNDLE_DEPTH)
+        .map(str::to_string)
+        .collect();
+    candidates.sort_by_key(|prefix| (prefix.matches('/').count(), prefix.clone()));
+    let prefix = candidates.first()?.clone();
+    if candidates.iter().any(|other| !other.starts_with(&prefix)) {
+        log::debug!("Input has more than one .app; it will be extracted");
+        return None;
+    }
+
+    let repacking = prefix.starts_with("Payload/") && prefix.matches('/').count() == 2;
+    let mut entries = Vec::new();
+    let mut root_entries = Vec::new();
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index).ok()?;
+        let (relative, list) = match file.name().strip_prefix(&prefix) {
+            Some(relative) => (relative, &mut entries),
+            None if repacking && crate::ipa_logic::keeps_root_entry(file.name(), false) => (file.name(), &mut root_entries),
+            None => continue,
+        };
+        let installable = file.is_dir()
+            || (file.unix_mode().is_some() && matches!(file.compression(), CompressionMethod::Stored | CompressionMethod::Deflated));
+        if file.enclosed_name().is_none() || !installable {
+            log::debug!("Input entry '{}' cannot be copied as is; the input will be extracted", file.name());
+            return None;
+        }
+        list.push(RemapEntry {
+            index,
+            relative: relative.trim_end_matches('/').to_string(),
+            size: file.size(),
+            is_dir: file.is_dir(),
+        });
+    }
+    let bundle_name = prefix.trim_end_matches('/').rsplit('/').next()?.to_string();
+    Some(RemapPlan { prefix, bundle_name, entries, root_entries })
+}
+
+impl RemapPlan {
+    /// Leaves the entries [`crate::junk::is_junk`] matches out of the IPA, returning their paths
+    /// relative to the bundle.
+    pub fn strip_junk(&mut self) -> Vec<String> {
+        let (junk, kept): (Vec<RemapEntry>, _) = std::mem::take(&mut self.entries).into_iter().partition(|e| crate::junk::is_junk(&e.relative));
+        self.entries = kept;
+        self.root_entries.retain(|e| !crate::junk::is_junk(&e.relative));
+        junk.into_iter().map(|e| e.relative).collect()
+    }
+
+    /// Debug artifacts in the bundle, from the entry names and sizes.
+    pub fn debug_artifacts(&self) -> Vec<DebugArtifact> {
+        crate::size_analysis::debug_artifacts_in_listing(self.entries.iter().map(|e| (e.relative.as_str(), e.size)))
+    }
+}
+
+/// Extracts what the build's checks read into `app_dir`: bundle executables, dylibs,
+/// `Info.plist`s, code signatures and frameworks that app extensions embed as well. Resources
+/// such as asset catalogs stay in the archive.
+pub fn extract_for_analysis(archive: &mut ZipArchive<File>, plan: &RemapPlan, app_dir: &Path) -> Result<(), IpaError> {
+    let shared_frameworks: BTreeSet<&str> = plan
+        .entries
+        .iter()
+        .filter_map(|e| match e.relative.split('/').collect::<Vec<_>>()[..] {
+            ["PlugIns", appex, "Frameworks", name, ..] if appex.ends_with(".appex") => Some(name),
+            _ => None,
+        })
+        .collect();
+    fs::create_dir_all(app_dir)?;
+    for entry in plan.entries.iter().filter(|e| !e.is_dir && needed_for_analysis(&e.relative, &shared_frameworks)) {
+        let mut file = archive.by_index(entry.index)?;
+        let out_path = app_dir.join(&entry.relative);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut File::create(&out_path)?)?;
+    }
+    Ok(())
+}
+
+fn needed_for_analysis(relative: &str, shared_frameworks: &BTreeSet<&str>) -> bool {
+    if relative.ends_with("Info.plist") || relative.ends_with("_CodeSignature/CodeResources") {
+        return true;
+    }
+    let parts: Vec<&str> = relative.split('/').collect();
+    // An extension is laid out like the app itself.
+    let within_bundle = match parts[..] {
+        ["PlugIns", appex, ref rest @ ..] if appex.ends_with(".appex") && !rest.is_empty() => rest,
+        ref all => all,
+    };
+    match within_bundle {
+        // Executables have no extension; resources at the root do.
+        [name] => !name.contains('.'),
+        ["Frameworks", name, ..] if shared_frameworks.contains(name) => true,
+        ["Frameworks", _dylib] => true,
+        ["Frameworks", framework, name] => framework.ends_with(".framework") && !name.contains('.'),
+        _ => false,
+    }
+}
+
+/// Writes an IPA at `dest` with the bundle's entries copied unchanged to `Payload/<bundle>/`,
+/// the kept root entries of an input IPA under their own names, plus `build_info` as
+/// [`BUILD_INFO_FILE_NAME`]. Entries added here get `fixed_time` as their
+/// modification time when given; copied ones keep the input's. `on_entry(done, total)` is called
+/// after each entry; an error from it stops the copy.
+pub fn write_ipa(
+    archive: &mut ZipArchive<File>,
+    plan: &RemapPlan,
+    build_info: &[u8],
+    dest: &Path,
+    fixed_time: Option<zip::DateTime>,
+    on_entry: &mut dyn FnMut(u64, u64) -> Result<(), IpaError>,
+) -> Result<(), IpaError> {
+    let total = (plan.entries.len() + plan.root_entries.len()) as u64 + 1;
+    on_entry(0, total)?;
+    let mut zip_writer = zip::ZipWriter::new(File::create(dest)?);
+    let base_options = match fixed_time {
+        Some(time) => FileOptions::default().last_modified_time(time),
+        None => FileOptions::default(),
+    };
+    let dir_options = base_options.compression_method(CompressionMethod::Stored).unix_permissions(0o755);
+    let bundle_dir = format!("Payload/{}/", plan.bundle_name);
+    zip_writer.add_directory("Payload/", dir_options)?;
+    zip_writer.add_directory(bundle_dir.as_str(), dir_options)?;
+
+    let mut done = 0;
+    for entry in &plan.entries {
+        if entry.is_dir {
+            if !entry.relative.is_empty() {
+                zip_writer.add_directory(format!("{}{}/", bundle_dir, entry.relative), dir_options)?;
+            }
+        } else {
+            let file = archive.by_index_raw(entry.index)?;
+            zip_writer.raw_copy_file_rename(file, format!("{}{}", bundle_dir, entry.relative))?;
+        }
+        done += 1;
+        on_entry(done, total)?;
+    }
+    for entry in &plan.root_entries {
+        if entry.is_dir {
+            zip_writer.add_directory(format!("{}/", entry.relative), dir_options)?;
+        } else {
+            zip_writer.raw_copy_file(archive.by_index_raw(entry.index)?)?;
+        }
+        done += 1;
+        on_entry(done, total)?;
+    }
+
+    let file_options = base_options.compression_method(CompressionMethod::Deflated).unix_permissions(0o644);
+    zip_writer.start_file(BUILD_INFO_FILE_NAME, file_options)?;
+    io::Write::write_all(&mut zip_writer, build_info)?;
+    zip_writer.finish()?;
+    on_entry(total, total)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn write_input(path: &Path, entries: &[(&str, &[u8], Option<u32>)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, bytes, mode) in entries {
+            let mut options = FileOptions::default();
+            if let Some(mode) = mode {
+                options = options.unix_permissions(*mode);
+            }
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_bundle_entries_are_copied_without_extracting() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_input(&input, &[
+            ("build/Runner.app/Info.plist", b"plist", Some(0o644)),
+            ("build/Runner.app/Runner", &[0xCF, 0xFA, 0xED, 0xFE], Some(0o755)),
+            ("build/Runner.app/Assets.car", &[7u8; 4096], Some(0o644)),
+            ("build/Runner.app/Runner.app.dSYM/Contents/Resources/DWARF/Runner", &[0u8; 64], Some(0o644)),
+            ("build/Runner.app/Watch/Face.app/Info.plist", b"plist", Some(0o644)),
+            ("build/Runner.app/._Info.plist", b"appledouble", Some(0o644)),
+            ("build/README.txt", b"not packaged", Some(0o644)),
+        ]);
+        let mut archive = ZipArchive::new(File::open(&input).unwrap()).unwrap();
+        let mut remap = plan(&mut archive).unwrap();
+        assert_eq!(remap.strip_junk(), ["._Info.plist"]);
+        assert_eq!((remap.prefix.as_str(), remap.bundle_name.as_str()), ("build/Runner.app/", "Runner.app"));
+        assert_eq!(remap.debug_artifacts(), vec![DebugArtifact { path: "Runner.app.dSYM".to_string(), size: 64 }]);
+
+        let app_dir = dir.path().join("analysis").join("Runner.app");
+        extract_for_analysis(&mut archive, &remap, &app_dir).unwrap();
+        assert!(app_dir.join("Runner").is_file() && app_dir.join("Watch/Face.app/Info.plist").is_file());
+        assert!(!app_dir.join("Assets.car").exists());
+
+        let ipa = dir.path().join("Runner.ipa");
+        write_ipa(&mut archive, &remap, b"{}", &ipa, None, &mut |_, _| Ok(())).unwrap();
+        let mut output = ZipArchive::new(File::open(&ipa).unwrap()).unwrap();
+        assert_eq!(output.by_name("Payload/Runner.app/Runner").unwrap().unix_mode().map(|m| m & 0o777), Some(0o755));
+        let mut assets = Vec::new();
+        output.by_name("Payload/Runner.app/Assets.car").unwrap().read_to_end(&mut assets).unwrap();
+        assert_eq!(assets, vec![7u8; 4096]);
+        assert!(output.by_name(BUILD_INFO_FILE_NAME).is_ok());
+        assert!(output.by_name("Payload/Runner.app/._Info.plist").is_err());
+        assert!(output.by_name("Payload/README.txt").is_err() && output.by_name("build/README.txt").is_err());
+
+        // Which app to package is up to find_app_bundle when there are several.
+        write_input(&input, &[("Runner.app/Info.plist", b"plist", None), ("Other.app/Info.plist", b"plist", None)]);
+        assert!(plan(&mut ZipArchive::new(File::open(&input).unwrap()).unwrap()).is_none());
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::info_plist::NAME_PLACEHOLDERS;
+
+/// Subfolder of the output directory that old builds are moved to instead of being deleted.
+pub const TRASH_DIR_NAME: &str = "ipa-builder-trash";
+
+/// Old IPAs removed by [`apply_retention`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RetentionOutcome {
+    pub removed: Vec<PathBuf>,
+    pub bytes: u64,
+    pub moved_to_trash: bool,
+}
+
+impl RetentionOutcome {
+    /// `Deleted 3 old builds (1.2 GB freed)`, or `None` when nothing was removed.
+    pub fn summary(&self) -> Option<String> {
+        if self.removed.is_empty() {
+            return None;
+        }
+        let count = self.removed.len();
+        let builds = if count == 1 { "build" } else { "builds" };
+        let size = crate::size_analysis::format_size(self.bytes);
+        Some(if self.moved_to_trash {
+            format!("Moved {} old {} ({}) to {}", count, builds, size, TRASH_DIR_NAME)
+        } else {
+            format!("Deleted {} old {} ({} freed)", count, builds, size)
+        })
+    }
+}
+
+/// Whether `file_name` is an IPA built from a config whose output is `ipa_name`: the name itself
+/// or one of the variants [`crate::naming::resolve_output_path`] writes, `Shop (2).ipa` and
+/// `Shop 2024-05-01 143005.ipa`. `Shop Beta.ipa` is not a variant of `Shop.ipa`.
+///
+/// `ipa_name` may be a template: each of the [`NAME_PLACEHOLDERS`] matches any value an
+/// `Info.plist` gives it, so `Shop-{build}.ipa` covers `Shop-41.ipa` and `Shop-42 (2).ipa`.
+pub fn is_output_variant(file_name: &str, ipa_name: &str) -> bool {
+    let split = |name: &str| name.len().checked_sub(4).filter(|&i| name.is_char_boundary(i) && name[i..].eq_ignore_ascii_case(".ipa")).map(|i| name[..i].to_string());
+    let (Some(stem), Some(ipa_stem)) = (split(file_name), split(ipa_name)) else {
+        return false;
+    };
+    template_match_ends(&stem, &ipa_stem).into_iter().any(|end| is_variant_suffix(&stem[end..]))
+}
+
+/// Byte offsets in `name` where a prefix of it matching `template` ends. Placeholders match a
+/// possibly empty run of letters, digits, `.`, `-` and `_`, which is what bundle IDs and
+/// versions are made of, so they never swallow a ` (2)` suffix.
+fn template_match_ends(name: &str, template: &str) -> Vec<usize> {
+    let placeholder = NAME_PLACEHOLDERS.iter().filter_map(|p| template.find(p).map(|start| (start, p.len()))).min();
+    let Some((start, len)) = placeholder else {
+        return if name.starts_with(template) { vec![template.len()] } else { Vec::new() };
+    };
+    let (literal, rest) = (&template[..start], &template[start + len..]);
+    let Some(after) = name.strip_prefix(literal) else {
+        return Vec::new();
+    };
+    let run = after.find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '-' | '_'))).unwrap_or(after.len());
+    (0..=run)
+        .filter(|&taken| after.is_char_boundary(taken))
+        .flat_map(|taken| template_match_ends(&after[taken..], rest).into_iter().map(move |end| literal.len() + taken + end))
+        .collect()
+}
+
+/// Whether `rest`, what follows the output name in a file name, is empty or a suffix
+/// [`crate::naming::resolve_output_path`] adds.
+fn is_variant_suffix(rest: &str) -> bool {
+    if rest.is_empty() {
+        return true;
+    }
+    let Some(mut rest) = rest.strip_prefix(' ') else {
+        return false;
+    };
+    // Optional timestamp, then an optional "(N)".
+    if rest.len() >= 17 && chrono::NaiveDateTime::parse_from_str(&rest[..17], "%Y-%m-%d %H%M%S").is_ok() {
+        rest = rest[17..].strip_prefix(' ').unwrap_or(&rest[17..]);
+        if rest.is_empty() {
+            return true;
+        }
+    }
+    rest.strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Keeps the `keep` most recently modified builds of `ipa_name`, which may be a template, in
+/// `output_dir` and deletes the rest, or moves them to [`TRASH_DIR_NAME`], along with their detached signatures and checksum
+/// files. `keep` of 0 keeps everything.
+pub fn apply_retention(output_dir: &Path, ipa_name: &str, keep: usize, move_to_trash: bool) -> io::Result<RetentionOutcome> {
+    let mut outcome = RetentionOutcome { moved_to_trash: move_to_trash, ..Default::default() };
+    if keep == 0 {
+        return Ok(outcome);
+    }
+    let mut builds: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(output_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_output_variant(&entry.file_name().to_string_lossy(), ipa_name) {
+            builds.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    builds.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+    for (_, size, path) in builds.into_iter().skip(keep) {
+        let mut companions = crate::artifact_signing::ArtifactSigning::existing_signatures(&path);
+        companions.extend([crate::transfer::checksum_path(&path), crate::ota_manifest::manifest_path(&path)].into_iter().filter(|p| p.is_file()));
+        for file in std::iter::once(&path).chain(&companions) {
+            if move_to_trash {
+                let trash = output_dir.join(TRASH_DIR_NAME);
+                fs::create_dir_all(&trash)?;
+                fs::rename(file, trash.join(file.file_name().unwrap_or_default()))?;
+            } else {
+                fs::remove_file(file)?;
+            }
+        }
+        outcome.bytes += size;
+        outcome.removed.push(path);
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_only_the_newest_builds_of_the_config_are_kept() {
+        assert!(is_output_variant("Shop.ipa", "Shop.ipa") && is_output_variant("Shop (12).ipa", "Shop.ipa"));
+        assert!(is_output_variant("Shop 2024-05-01 143005.ipa", "Shop.ipa") && is_output_variant("Shop 2024-05-01 143005 (2).ipa", "Shop.ipa"));
+        assert!(!is_output_variant("Shop Beta.ipa", "Shop.ipa") && !is_output_variant("Shop ().ipa", "Shop.ipa"));
+        assert!(!is_output_variant("Shop.zip", "Shop.ipa"));
+        assert!(is_output_variant("Shop-1.4-42.ipa", "Shop-{short_version}-{build}.ipa") && is_output_variant("Shop-1.4-42 (2).ipa", "Shop-{short_version}-{build}.ipa"));
+        assert!(is_output_variant("com.example.shop-7 2024-05-01 143005.ipa", "{bundle_id}-{build}.ipa"));
+        assert!(!is_output_variant("Shop-1.4 Beta.ipa", "Shop-{short_version}.ipa") && !is_output_variant("Blog-42.ipa", "Shop-{build}.ipa"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let names = ["Shop.ipa", "Shop (2).ipa", "Shop (3).ipa", "Shop Beta.ipa"];
+        let start = SystemTime::now() - Duration::from_secs(3600);
+        for (i, name) in names.iter().enumerate() {
+            fs::write(dir.path().join(name), vec![0u8; 1024 * (i + 1)]).unwrap();
+            let file = fs::File::options().write(true).open(dir.path().join(name)).unwrap();
+            file.set_modified(start + Duration::from_secs(60 * i as u64)).unwrap();
+        }
+
+        fs::write(dir.path().join("Shop.ipa.minisig"), "signature").unwrap();
+        fs::write(dir.path().join("Shop.ipa.sha256"), "checksum").unwrap();
+
+        let outcome = apply_retention(dir.path(), "Shop.ipa", 2, false).unwrap();
+        assert_eq!(outcome.removed, vec![dir.path().join("Shop.ipa")]);
+        assert!(!dir.path().join("Shop.ipa.minisig").exists() && !dir.path().join("Shop.ipa.sha256").exists());
+        assert_eq!(outcome.summary().as_deref(), Some("Deleted 1 old build (1.0 KB freed)"));
+
+        let outcome = apply_retention(dir.path(), "Shop.ipa", 1, true).unwrap();
+        assert_eq!(outcome.bytes, 2048);
+        assert!(dir.path().join(TRASH_DIR_NAME).join("Shop (2).ipa").is_file());
+        assert!(dir.path().join("Shop (3).ipa").is_file() && dir.path().join("Shop Beta.ipa").is_file());
+        assert!(apply_retention(dir.path(), "Shop.ipa", 0, false).unwrap().summary().is_none());
+    }
+
+    #[test]
+    fn test_retention_matches_templated_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = SystemTime::now() - Duration::from_secs(3600);
+        for (i, name) in ["Shop-41.ipa", "Shop-42.ipa", "Shop-43.ipa", "Shop-43 (2).ipa", "Blog-44.ipa"].iter().enumerate() {
+            fs::write(dir.path().join(name), vec![0u8; 1024]).unwrap();
+            let file = fs::File::options().write(true).open(dir.path().join(name)).unwrap();
+            file.set_modified(start + Duration::from_secs(60 * i as u64)).unwrap();
+        }
+        let mut outcome = apply_retention(dir.path(), "Shop-{build}.ipa", 2, false).unwrap();
+        outcome.removed.sort();
+        assert_eq!(outcome.removed, [dir.path().join("Shop-41.ipa"), dir.path().join("Shop-42.ipa")]);
+        assert!(dir.path().join("Shop-43 (2).ipa").is_file() && dir.path().join("Blog-44.ipa").is_file());
+    }
+}
@@ -0,0 +1,335 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::ProgressReader;
+use crate::network::NetworkSettings;
+use crate::transfer::ThrottledReader;
+
+const DIAWI_UPLOAD_URL: &str = "https://upload.diawi.com/";
+const DIAWI_STATUS_URL: &str = "https://upload.diawi.com/status";
+/// How long Diawi may take to process an upload before giving up on the link.
+const DIAWI_PROCESSING_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A service that hosts an uploaded IPA and hands back a short install link for testers.
+/// Connectors only talk HTTP; [`spawn_share`] builds the agent, so proxy, CA bundle and offline
+/// mode apply to every service.
+pub trait ShareConnector: Send {
+    /// Shown in status messages, e.g. "Diawi".
+    fn name(&self) -> &'static str;
+    /// Uploads `ipa_path`, at most `bytes_per_sec` (0 is unlimited), and returns the link to share.
+    fn upload(&self, ipa_path: &Path, http: &ureq::Agent, bytes_per_sec: u64, progress: &dyn Fn(Option<f32>, String)) -> Result<String, String>;
+}
+
+/// Share service the GUI uploads to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShareService {
+    #[default]
+    None,
+    Diawi,
+}
+
+impl ShareService {
+    pub const ALL: [ShareService; 2] = [ShareService::None, ShareService::Diawi];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShareService::None => "Don't share",
+            ShareService::Diawi => "Diawi",
+        }
+    }
+}
+
+/// Upload of built IPAs to a share service such as Diawi, for a short link testers can open
+/// on their phone.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ShareSettings {
+    pub service: ShareService,
+    pub diawi: DiawiSettings,
+    /// Upload every successful device build right away.
+    pub upload_after_build: bool,
+}
+
+impl ShareSettings {
+    /// The connector of the chosen service; `None` when sharing is off.
+    pub fn connector(&self) -> Option<Box<dyn ShareConnector>> {
+        match self.service {
+            ShareService::None => None,
+            ShareService::Diawi => Some(Box::new(Diawi::new(self.diawi.clone()))),
+        }
+    }
+
+    /// Checks the chosen service's settings without contacting it.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.service {
+            ShareService::None => Ok(()),
+            ShareService::Diawi if self.diawi.token.trim().is_empty() => {
+                Err("Enter a Diawi API token (diawi.com → Profile → API access).".to_string())
+            }
+            ShareService::Diawi => Ok(()),
+        }
+    }
+}
+
+/// Options of Diawi's upload API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct DiawiSettings {
+    pub token: String,
+    /// Testers must enter it before installing; empty for none.
+    pub password: String,
+    /// Shown on the install page.
+    pub comment: String,
+    /// Let testers find the app by their device's UDID on diawi.com.
+    pub find_by_udid: bool,
+    /// List the app on the account's Wall of Apps.
+    pub wall_of_apps: bool,
+}
+
+/// Diawi's upload API: the IPA is posted as a form, then Diawi processes it in a job whose status
+/// is polled until it has a link.
+pub struct Diawi {
+    settings: DiawiSettings,
+    upload_url: String,
+    status_url: String,
+    poll_interval: Duration,
+}
+
+impl Diawi {
+    pub fn new(settings: DiawiSettings) -> Self {
+        Self {
+            settings,
+            upload_url: DIAWI_UPLOAD_URL.to_string(),
+            status_url: DIAWI_STATUS_URL.to_string(),
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+
+    fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let settings = &self.settings;
+        let mut fields = vec![("token", settings.token.trim().to_string())];
+        if !settings.password.is_empty() {
+            fields.push(("password", settings.password.clone()));
+        }
+        if !settings.comment.trim().is_empty() {
+            fields.push(("comment", settings.comment.trim().to_string()));
+        }
+        fields.push(("find_by_udid", u8::from(settings.find_by_udid).to_string()));
+        fields.push(("wall_of_apps", u8::from(settings.wall_of_apps).to_string()));
+        fields
+    }
+
+    fn wait_for_link(&self, http: &ureq::Agent, job: &str, progress: &dyn Fn(Option<f32>, String)) -> Result<String, String> {
+        let started = Instant::now();
+        loop {
+            let status: serde_json::Value = http
+                .get(&self.status_url)
+                .query("token", self.settings.token.trim())
+                .query("job", job)
+                .call()
+                .map_err(|e| service_error("Diawi", e))?
+                .into_json()
+                .map_err(|e| format!("Unreadable Diawi status: {}", e))?;
+            let message = status["message"].as_str().unwrap_or_default();
+            match status["status"].as_i64() {
+                Some(2000) => {
+                    return status["link"].as_str().map(str::to_string).ok_or_else(|| "Diawi did not return a link".to_string());
+                }
+                Some(2001) => progress(Some(1.0), format!("Processing on Diawi… {}", message).trim_end().to_string()),
+                _ => return Err(format!("Diawi could not process the IPA: {}", message).trim_end_matches([':', ' ']).to_string()),
+            }
+            if started.elapsed() > DIAWI_PROCESSING_TIMEOUT {
+                return Err(format!("Diawi was still processing the IPA after {} seconds", DIAWI_PROCESSING_TIMEOUT.as_secs()));
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+impl ShareConnector for Diawi {
+    fn name(&self) -> &'static str {
+        "Diawi"
+    }
+
+    fn upload(&self, ipa_path: &Path, http: &ureq::Agent, bytes_per_sec: u64, progress: &dyn Fn(Option<f32>, String)) -> Result<String, String> {
+        if self.settings.token.trim().is_empty() {
+            return Err("No Diawi API token is set".to_string());
+        }
+        let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+        let size = file.metadata().map_err(|e| format!("Failed to read {}: {}", ipa_path.display(), e))?.len();
+        let file_name = ipa_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let form = MultipartForm::new(&self.form_fields(), "file", &file_name);
+        let on_progress = |done: u64, total: u64| {
+            let fraction = if total == 0 { 1.0 } else { done as f32 / total as f32 };
+            progress(Some(fraction), format!("Uploading {} to Diawi", file_name));
+        };
+        let body = ProgressReader::new(ThrottledReader::new(file, bytes_per_sec), size, &on_progress);
+        let response: serde_json::Value = http
+            .post(&self.upload_url)
+            .set("Content-Type", &form.content_type())
+            .set("Content-Length", &form.content_length(size).to_string())
+            .send(form.body(body))
+            .map_err(|e| service_error("Diawi", e))?
+            .into_json()
+            .map_err(|e| format!("Unreadable Diawi response: {}", e))?;
+        let job = response["job"].as_str().ok_or_else(|| format!("Diawi did not start processing the IPA: {}", response))?;
+        self.wait_for_link(http, job, progress)
+    }
+}
+
+/// A `multipart/form-data` body of text fields and one file, streamed from disk rather than
+/// loaded into memory, since ureq has no multipart support.
+struct MultipartForm {
+    boundary: String,
+    head: Vec<u8>,
+    tail: Vec<u8>,
+}
+
+impl MultipartForm {
+    fn new(fields: &[(&str, String)], file_field: &str, file_name: &str) -> Self {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let boundary = format!("----IpaBuilderBoundary{:x}", nanos);
+        let mut head = String::new();
+        for (name, value) in fields {
+            head.push_str(&format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value));
+        }
+        head.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            file_field,
+            file_name.replace(['"', '\r', '\n'], "_")
+        ));
+        let tail = format!("\r\n--{}--\r\n", boundary).into_bytes();
+        Self { boundary, head: head.into_bytes(), tail }
+    }
+
+    fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    fn content_length(&self, file_size: u64) -> u64 {
+        self.head.len() as u64 + file_size + self.tail.len() as u64
+    }
+
+    fn body(self, file: impl Read) -> impl Read {
+        std::io::Cursor::new(self.head).chain(file).chain(std::io::Cursor::new(self.tail))
+    }
+}
+
+fn service_error(service: &str, e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            let detail = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|body| body["message"].as_str().map(str::to_string))
+                .unwrap_or(body);
+            format!("{} refused the request (HTTP {}): {}", service, code, detail.trim()).trim_end_matches([':', ' ']).to_string()
+        }
+        ureq::Error::Transport(t) => format!("Could not reach {}: {}", service, t),
+    }
+}
+
+/// Progress of an upload started with [`spawn_share`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShareMessage {
+    /// What the upload is doing, with the share of the file sent so far when known.
+    Progress { fraction: Option<f32>, line: String },
+    /// The upload ended with the link to share, or the reason it failed.
+    Finished(Result<String, String>),
+}
+
+/// Uploads `ipa_path` to the service chosen in `settings` on a background thread, at most
+/// `bytes_per_sec` (0 is unlimited).
+pub fn spawn_share(ipa_path: PathBuf, settings: ShareSettings, network: NetworkSettings, bytes_per_sec: u64) -> mpsc::Receiver<ShareMessage> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let result = share(&ipa_path, &settings, &network, bytes_per_sec, &|fraction, line| {
+            let _ = tx.send(ShareMessage::Progress { fraction, line });
+        });
+        let _ = tx.send(ShareMessage::Finished(result));
+    });
+    rx
+}
+
+fn share(ipa_path: &Path, settings: &ShareSettings, network: &NetworkSettings, bytes_per_sec: u64, progress: &dyn Fn(Option<f32>, String)) -> Result<String, String> {
+    if network.offline {
+        return Err("Offline mode is on; share service uploads are disabled in Settings".to_string());
+    }
+    settings.validate()?;
+    let connector = settings.connector().ok_or("No share service is chosen in Settings")?;
+    let http = network.http_agent()?;
+    let link = connector.upload(ipa_path, &http, bytes_per_sec, progress)?;
+    log::info!("Shared {} on {}: {}", ipa_path.display(), connector.name(), link);
+    Ok(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tiny_http::{Response, Server};
+
+    #[test]
+    fn test_diawi_upload_polls_until_the_link_is_ready() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let uploaded = Arc::new(Mutex::new(String::new()));
+        let received = uploaded.clone();
+        thread::spawn(move || {
+            let mut polls = 0;
+            for mut request in server.incoming_requests() {
+                let body = if request.url() == "/" {
+                    let mut form = String::new();
+                    request.as_reader().read_to_string(&mut form).unwrap();
+                    *received.lock().unwrap() = form;
+                    r#"{"job":"j42"}"#.to_string()
+                } else if request.url() == "/status?token=secret&job=j42" {
+                    polls += 1;
+                    if polls == 1 {
+                        r#"{"status":2001,"message":"Processing"}"#.to_string()
+                    } else {
+                        r#"{"status":2000,"hash":"aBcD","link":"https://i.diawi.com/aBcD"}"#.to_string()
+                    }
+                } else {
+                    r#"{"status":4000,"message":"Unknown job"}"#.to_string()
+                };
+                let _ = request.respond(Response::from_string(body));
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop.ipa");
+        std::fs::write(&ipa, b"PK fake ipa").unwrap();
+        let settings = DiawiSettings { token: " secret ".to_string(), comment: "Build 42".to_string(), ..Default::default() };
+        let diawi = Diawi {
+            settings,
+            upload_url: format!("{}/", base_url),
+            status_url: format!("{}/status", base_url),
+            poll_interval: Duration::from_millis(10),
+        };
+        let lines = Mutex::new(Vec::new());
+        let link = diawi.upload(&ipa, &ureq::agent(), 1 << 20, &|fraction, line| lines.lock().unwrap().push((fraction, line))).unwrap();
+        assert_eq!(link, "https://i.diawi.com/aBcD");
+        let form = uploaded.lock().unwrap().clone();
+        assert!(form.contains("name=\"token\"\r\n\r\nsecret\r\n"));
+        assert!(form.contains("name=\"comment\"\r\n\r\nBuild 42\r\n"));
+        assert!(!form.contains("name=\"password\""));
+        assert!(form.contains("filename=\"Shop.ipa\"\r\nContent-Type: application/octet-stream\r\n\r\nPK fake ipa\r\n--"));
+        let lines = lines.into_inner().unwrap();
+        assert_eq!(lines.iter().filter(|(_, line)| line.starts_with("Processing on Diawi")).count(), 1);
+
+        let unknown_job = diawi.wait_for_link(&ureq::agent(), "j1", &|_, _| {}).unwrap_err();
+        assert_eq!(unknown_job, "Diawi could not process the IPA: Unknown job");
+        let offline = NetworkSettings { offline: true, ..Default::default() };
+        let no_token = ShareSettings { service: ShareService::Diawi, ..Default::default() };
+        assert!(share(&ipa, &no_token, &offline, 0, &|_, _| {}).unwrap_err().starts_with("Offline mode is on"));
+        assert!(share(&ipa, &no_token, &NetworkSettings::default(), 0, &|_, _| {}).unwrap_err().contains("Diawi API token"));
+    }
+}
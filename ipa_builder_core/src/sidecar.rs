@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// A file from [`crate::app_config::AppConfig::sidecars`], copied next to the IPA and listed in
+/// its [`crate::ipa_logic::BuildInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SidecarFile {
+    /// Relative to the sidecar folder, e.g. `mapping.txt` or `symbols/app.ios-arm64.symbols`
+    /// for a file from a declared directory.
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+/// `<ipa stem>_sidecars/` next to the IPA.
+pub fn sidecar_dir(ipa_path: &Path) -> PathBuf {
+    let stem = ipa_path.file_stem().unwrap_or_default().to_string_lossy();
+    ipa_path.with_file_name(format!("{}_sidecars", stem))
+}
+
+/// Lists and hashes the declared files; a declared directory contributes every file below it,
+/// under the directory's name. Fails when a path is missing or two entries would have the
+/// same name, so a release never ships with part of its artifacts.
+pub fn collect(paths: &[String]) -> Result<Vec<SidecarFile>, String> {
+    let mut files = Vec::new();
+    for declared in paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let path = Path::new(declared);
+        let base = path.parent().unwrap_or(Path::new(""));
+        if path.is_file() {
+            files.push(describe(path, base)?);
+        } else if path.is_dir() {
+            let mut found: Vec<PathBuf> = WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect();
+            found.sort();
+            for file in found {
+                files.push(describe(&file, base)?);
+            }
+        } else {
+            return Err(format!("{} does not exist", declared));
+        }
+    }
+    let mut names = BTreeSet::new();
+    if let Some(duplicate) = files.iter().find(|f| !names.insert(f.name.as_str())) {
+        return Err(format!("more than one sidecar is named {}", duplicate.name));
+    }
+    Ok(files)
+}
+
+fn describe(path: &Path, base: &Path) -> Result<SidecarFile, String> {
+    let size = fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    let sha256 = crate::transfer::sha256_file(path).map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+    let name = path.strip_prefix(base).unwrap_or(path).to_string_lossy().replace('\\', "/");
+    Ok(SidecarFile { name, size, sha256, source: path.to_path_buf() })
+}
+
+/// Replaces the sidecar folder of `ipa_path` with copies of `files`, verified against their
+/// hashes like the staged IPA. Returns the folder, or `None` when there is nothing to copy.
+pub fn copy_next_to(files: &[SidecarFile], ipa_path: &Path, attempts: u32) -> Result<Option<PathBuf>, String> {
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let dir = sidecar_dir(ipa_path);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove old {}: {}", dir.display(), e))?;
+    }
+    for file in files {
+        let dest = dir.join(&file.name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        crate::transfer::copy_verified_with_retry(&file.source, &dest, attempts, Duration::from_millis(500))
+            .map_err(|e| format!("Failed to copy {}: {}", file.source.display(), e))?;
+    }
+    log::info!("Copied {} sidecar file(s) to {}", files.len(), dir.display());
+    Ok(Some(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_and_directories_are_copied_next_to_ipa() {
+        let dir = tempfile::tempdir().unwrap();
+        let symbols = dir.path().join("symbols");
+        fs::create_dir_all(&symbols).unwrap();
+        fs::write(symbols.join("app.ios-arm64.symbols"), "dwarf").unwrap();
+        fs::write(dir.path().join("NOTES.md"), "- Fixed login").unwrap();
+        let declared = vec![dir.path().join("NOTES.md").to_string_lossy().into_owned(), symbols.to_string_lossy().into_owned()];
+
+        let files = collect(&declared).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["NOTES.md", "symbols/app.ios-arm64.symbols"]);
+        assert_eq!(files[0].size, 13);
+
+        let out = dir.path().join("out");
+        fs::create_dir_all(out.join("Shop_sidecars")).unwrap();
+        fs::write(out.join("Shop_sidecars/stale.txt"), "old").unwrap();
+        let copied = copy_next_to(&files, &out.join("Shop.ipa"), 1).unwrap().unwrap();
+        assert_eq!(copied, out.join("Shop_sidecars"));
+        assert_eq!(fs::read_to_string(copied.join("symbols/app.ios-arm64.symbols")).unwrap(), "dwarf");
+        assert!(!copied.join("stale.txt").exists());
+
+        assert!(collect(&[dir.path().join("missing.txt").to_string_lossy().into_owned()]).is_err());
+        assert!(collect(&[declared[0].clone(), declared[0].clone()]).is_err());
+    }
+}
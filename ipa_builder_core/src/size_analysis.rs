@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// rpath that lets an app extension load frameworks from the containing app's `Frameworks/`.
+const HOST_APP_FRAMEWORKS_RPATH: &str = "@executable_path/../../Frameworks";
+
+/// A framework or dylib embedded with identical contents in the app and one or more extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateFramework {
+    /// File name, e.g. `Flutter.framework`.
+    pub name: String,
+    /// Size of a single copy in bytes.
+    pub size: u64,
+    /// The copy in the app's own `Frameworks/`, relative to the `.app` bundle.
+    pub kept: String,
+    /// Extension copies that can be deleted because the extension already loads frameworks
+    /// from the host app and is not signed yet.
+    pub removable: Vec<String>,
+    /// Extension copies that must stay, with the reason.
+    pub blocked: Vec<(String, String)>,
+}
+
+impl DuplicateFramework {
+    pub fn potential_savings(&self) -> u64 {
+        self.size * (self.removable.len() + self.blocked.len()) as u64
+    }
+
+    pub fn describe(&self) -> String {
+        let copies = self.removable.len() + self.blocked.len();
+        let mut text = format!(
+            "{} is embedded {} more time(s) in app extensions; {} could be saved",
+            self.name,
+            copies,
+            format_size(self.potential_savings())
+        );
+        if let Some((_, reason)) = self.blocked.first() {
+            text.push_str(&format!(" (not removable automatically: {})", reason));
+        }
+        text
+    }
+}
+
+/// Finds frameworks and dylibs in `PlugIns/*.appex/Frameworks` that are byte-identical to one
+/// in the app's `Frameworks/`.
+pub fn find_duplicate_frameworks(app_dir: &Path) -> Vec<DuplicateFramework> {
+    let app_frameworks = list_embedded(&app_dir.join("Frameworks"));
+    let mut duplicates: BTreeMap<String, DuplicateFramework> = BTreeMap::new();
+
+    let mut extensions: Vec<PathBuf> = fs::read_dir(app_dir.join("PlugIns"))
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    extensions.sort();
+
+    for appex in extensions.iter().filter(|p| p.extension().is_some_and(|e| e == "appex")) {
+        let blocked_reason = dedupe_blocker(appex);
+        for (name, path) in list_embedded(&appex.join("Frameworks")) {
+            let app_copy = match app_frameworks.get(&name) {
+                Some(p) => p,
+                None => continue,
+            };
+            let (app_hash, app_size) = match content_hash(app_copy) {
+                Ok(h) => h,
+                Err(_) => continue,
+            };
+            if content_hash(&path).ok().map(|(h, _)| h) != Some(app_hash) {
+                continue;
+            }
+            let relative = relative_to(app_dir, &path);
+            let entry = duplicates.entry(name.clone()).or_insert_with(|| DuplicateFramework {
+                name: name.clone(),
+                size: app_size,
+                kept: relative_to(app_dir, app_copy),
+                removable: Vec::new(),
+                blocked: Vec::new(),
+            });
+            match &blocked_reason {
+                None => entry.removable.push(relative),
+                Some(reason) => entry.blocked.push((relative, reason.clone())),
+            }
+        }
+    }
+    duplicates.into_values().collect()
+}
+
+/// Deletes the removable extension copies. Returns the number of bytes freed.
+pub fn remove_duplicate_frameworks(app_dir: &Path, duplicates: &[DuplicateFramework]) -> io::Result<u64> {
+    let mut freed = 0;
+    for duplicate in duplicates {
+        for relative in &duplicate.removable {
+            let path = app_dir.join(relative);
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+            log::info!("Removed duplicate {}", path.display());
+            freed += duplicate.size;
+        }
+    }
+    Ok(freed)
+}
+
+/// Debug information copied into the `.app` by mistake: a dSYM bundle, a bitcode symbol map
+/// or a Swift module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugArtifact {
+    /// Relative to the `.app` bundle.
+    pub path: String,
+    pub size: u64,
+}
+
+impl DebugArtifact {
+    pub fn describe(&self, excluded: bool) -> String {
+        if excluded {
+            format!("Excluded debug artifact {} ({})", self.path, format_size(self.size))
+        } else {
+            format!("{} ({}) is debug information that does not belong in an IPA", self.path, format_size(self.size))
+        }
+    }
+}
+
+const DEBUG_ARTIFACT_EXTENSIONS: &[&str] = &["dSYM", "bcsymbolmap", "swiftmodule"];
+
+/// Finds `.dSYM`, `.bcsymbolmap` and `.swiftmodule` items anywhere in the bundle. Contents of a
+/// match are not reported separately.
+pub fn find_debug_artifacts(app_dir: &Path) -> Vec<DebugArtifact> {
+    let mut artifacts = Vec::new();
+    let mut walker = WalkDir::new(app_dir).min_depth(1).sort_by_file_name().into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        if !is_debug_artifact(entry.path()) {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            walker.skip_current_dir();
+        }
+        let size = WalkDir::new(entry.path())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.metadata().ok())
+            .map(|m| m.len())
+            .sum();
+        artifacts.push(DebugArtifact { path: relative_to(app_dir, entry.path()), size });
+    }
+    artifacts
+}
+
+/// Same as [`find_debug_artifacts`] over `(path relative to the .app, size)` pairs, e.g. the
+/// entries of an archive, instead of a directory.
+pub fn debug_artifacts_in_listing<'a>(files: impl IntoIterator<Item = (&'a str, u64)>) -> Vec<DebugArtifact> {
+    let mut artifacts: BTreeMap<String, u64> = BTreeMap::new();
+    for (path, size) in files {
+        let parts: Vec<&str> = path.split('/').collect();
+        if let Some(end) = parts.iter().position(|part| is_debug_artifact(Path::new(part))) {
+            *artifacts.entry(parts[..=end].join("/")).or_default() += size;
+        }
+    }
+    artifacts.into_iter().map(|(path, size)| DebugArtifact { path, size }).collect()
+}
+
+fn is_debug_artifact(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| DEBUG_ARTIFACT_EXTENSIONS.iter().any(|d| ext.eq_ignore_ascii_case(d)))
+}
+
+/// Deletes the artifacts found by [`find_debug_artifacts`]. Returns the number of bytes freed.
+pub fn remove_debug_artifacts(app_dir: &Path, artifacts: &[DebugArtifact]) -> io::Result<u64> {
+    let mut freed = 0;
+    for artifact in artifacts {
+        let path = app_dir.join(&artifact.path);
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+        log::info!("Excluded {}", path.display());
+        freed += artifact.size;
+    }
+    Ok(freed)
+}
+
+/// Why the frameworks of `appex` must not be touched, or `None` when removing a duplicate is safe.
+fn dedupe_blocker(appex: &Path) -> Option<String> {
+    if appex.join("_CodeSignature").exists() {
+        return Some("extension is already signed".to_string());
+    }
+    let executable = match crate::codesign::main_executable(appex) {
+        Some(p) => p,
+        None => return Some("extension executable not found".to_string()),
+    };
+    let bytes = match fs::read(&executable) {
+        Ok(b) => b,
+        Err(e) => return Some(format!("cannot read extension executable: {}", e)),
+    };
+    let loads_from_host = crate::macho::slices(&bytes)
+        .ok()
+        .and_then(|slices| {
+            slices.iter().try_fold(true, |all, slice| {
+                let rpaths = crate::macho::rpaths(slice.bytes(&bytes).ok()?).ok()?;
+                Some(all && rpaths.iter().any(|r| r.trim_end_matches('/') == HOST_APP_FRAMEWORKS_RPATH))
+            })
+        })
+        .unwrap_or(false);
+    if loads_from_host {
+        None
+    } else {
+        Some(format!("extension has no {} rpath", HOST_APP_FRAMEWORKS_RPATH))
+    }
+}
+
+fn list_embedded(frameworks_dir: &Path) -> BTreeMap<String, PathBuf> {
+    fs::read_dir(frameworks_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "framework" || ext == "dylib"))
+                .filter_map(|p| Some((p.file_name()?.to_string_lossy().into_owned(), p)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// SHA-256 over the relative paths and contents of every file below `path`, plus total size.
+fn content_hash(path: &Path) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    let mut files: Vec<PathBuf> = WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    for file in files {
+        hasher.update(relative_to(path, &file).as_bytes());
+        let bytes = fs::read(&file)?;
+        size += bytes.len() as u64;
+        hasher.update(&bytes);
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+fn relative_to(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+/// What part of the app an IPA entry belongs to, for [`size_breakdown`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeCategory {
+    /// The app's main executable.
+    Executable,
+    /// Everything in the app's `Frameworks/`, Flutter and the Dart code included.
+    Frameworks,
+    /// Compiled asset catalogs (`Assets.car`) outside `Frameworks/`.
+    AssetCatalog,
+    /// `.lproj` localization bundles.
+    Localizations,
+    /// Everything else, including app extensions and files next to `Payload/`.
+    OtherResources,
+}
+
+impl SizeCategory {
+    pub const ALL: [SizeCategory; 5] = [Self::Executable, Self::Frameworks, Self::AssetCatalog, Self::Localizations, Self::OtherResources];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Executable => "Executable",
+            Self::Frameworks => "Frameworks",
+            Self::AssetCatalog => "Assets.car",
+            Self::Localizations => "Localizations",
+            Self::OtherResources => "Other resources",
+        }
+    }
+
+    /// The category of `path`, relative to the `.app`, given the name of the main executable.
+    pub fn of(path: &str, executable: &str) -> Self {
+        if path == executable {
+            Self::Executable
+        } else if path.starts_with("Frameworks/") {
+            Self::Frameworks
+        } else if path.rsplit('/').next() == Some("Assets.car") {
+            Self::AssetCatalog
+        } else if path.split('/').any(|part| part.ends_with(".lproj")) {
+            Self::Localizations
+        } else {
+            Self::OtherResources
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CategorySize {
+    pub bytes: u64,
+    pub compressed_bytes: u64,
+    pub files: usize,
+}
+
+/// Uncompressed and compressed size of an IPA per [`SizeCategory`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeBreakdown {
+    pub categories: BTreeMap<SizeCategory, CategorySize>,
+}
+
+impl SizeBreakdown {
+    pub fn get(&self, category: SizeCategory) -> CategorySize {
+        self.categories.get(&category).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> CategorySize {
+        self.categories.values().fold(CategorySize::default(), |total, c| CategorySize {
+            bytes: total.bytes + c.bytes,
+            compressed_bytes: total.compressed_bytes + c.compressed_bytes,
+            files: total.files + c.files,
+        })
+    }
+}
+
+/// Adds up the entries of the IPA at `ipa_path` by [`SizeCategory`], from the central
+/// directory and the app's `Info.plist`.
+pub fn size_breakdown(ipa_path: &Path) -> Result<SizeBreakdown, String> {
+    let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    let app_root = archive
+        .file_names()
+        .find(|name| name.starts_with("Payload/") && name.ends_with(".app/Info.plist") && name.matches('/').count() == 2)
+        .map(|name| name.trim_end_matches("Info.plist").to_string())
+        .ok_or("IPA has no Payload/<App>.app/Info.plist")?;
+    let mut plist = Vec::new();
+    archive
+        .by_name(&format!("{}Info.plist", app_root))
+        .and_then(|mut f| Ok(f.read_to_end(&mut plist)?))
+        .map_err(|e| format!("Failed to read Info.plist: {}", e))?;
+    let executable = crate::info_plist::parse_dictionary(&plist)
+        .ok()
+        .and_then(|info| info.get("CFBundleExecutable").and_then(|v| v.as_string()).map(str::to_string))
+        .unwrap_or_else(|| Path::new(app_root.trim_end_matches('/')).file_stem().unwrap_or_default().to_string_lossy().into_owned());
+
+    let mut breakdown = SizeBreakdown::default();
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(|e| format!("Unreadable entry {}: {}", i, e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let category = match entry.name().strip_prefix(app_root.as_str()) {
+            Some(path) => SizeCategory::of(path, &executable),
+            None => SizeCategory::OtherResources,
+        };
+        let size = breakdown.categories.entry(category).or_default();
+        size.bytes += entry.size();
+        size.compressed_bytes += entry.compressed_size();
+        size.files += 1;
+    }
+    Ok(breakdown)
+}
+
+/// `+1.2 MB` or `-340.0 KB`, for size changes between builds.
+pub fn format_size_change(before: u64, after: u64) -> String {
+    if after >= before {
+        format!("+{}", format_size(after - before))
+    } else {
+        format!("-{}", format_size(before - after))
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::test_support::thin_macho_with_rpaths;
+    use crate::macho::CPU_TYPE_ARM64;
+    use std::io::Write;
+
+    fn write_appex(app_dir: &Path, name: &str, rpaths: &[&str]) -> PathBuf {
+        let appex = app_dir.join("PlugIns").join(format!("{}.appex", name));
+        fs::create_dir_all(appex.join("Frameworks/Shared.framework")).unwrap();
+        fs::write(appex.join(name), thin_macho_with_rpaths(CPU_TYPE_ARM64, rpaths)).unwrap();
+        fs::write(appex.join("Frameworks/Shared.framework/Shared"), vec![7u8; 4096]).unwrap();
+        appex
+    }
+
+    #[test]
+    fn test_finds_and_removes_safe_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("App.app");
+        fs::create_dir_all(app_dir.join("Frameworks/Shared.framework")).unwrap();
+        fs::write(app_dir.join("Frameworks/Shared.framework/Shared"), vec![7u8; 4096]).unwrap();
+        let widget = write_appex(&app_dir, "Widget", &["@executable_path/../../Frameworks"]);
+        write_appex(&app_dir, "Share", &["@executable_path/Frameworks"]);
+
+        let duplicates = find_duplicate_frameworks(&app_dir);
+        assert_eq!(duplicates.len(), 1);
+        let duplicate = &duplicates[0];
+        assert_eq!(duplicate.kept, "Frameworks/Shared.framework");
+        assert_eq!(duplicate.removable, vec!["PlugIns/Widget.appex/Frameworks/Shared.framework".to_string()]);
+        assert_eq!(duplicate.blocked.len(), 1);
+        assert_eq!(duplicate.potential_savings(), 8192);
+
+        assert_eq!(remove_duplicate_frameworks(&app_dir, &duplicates).unwrap(), 4096);
+        assert!(!widget.join("Frameworks/Shared.framework").exists());
+        assert!(app_dir.join("PlugIns/Share.appex/Frameworks/Shared.framework").exists());
+    }
+
+    #[test]
+    fn test_finds_and_removes_debug_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("App.app");
+        fs::create_dir_all(app_dir.join("App.app.dSYM/Contents/Resources/DWARF")).unwrap();
+        fs::write(app_dir.join("App.app.dSYM/Contents/Resources/DWARF/App"), vec![0u8; 2048]).unwrap();
+        fs::create_dir_all(app_dir.join("Frameworks/Kit.framework/Modules/Kit.swiftmodule")).unwrap();
+        fs::write(app_dir.join("Frameworks/Kit.framework/Modules/Kit.swiftmodule/arm64.swiftdoc"), vec![0u8; 10]).unwrap();
+        fs::write(app_dir.join("Frameworks/Kit.framework/Kit"), vec![0u8; 10]).unwrap();
+        fs::write(app_dir.join("1A2B.bcsymbolmap"), vec![0u8; 100]).unwrap();
+
+        let artifacts = find_debug_artifacts(&app_dir);
+        let paths: Vec<&str> = artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["1A2B.bcsymbolmap", "App.app.dSYM", "Frameworks/Kit.framework/Modules/Kit.swiftmodule"]);
+        assert_eq!(artifacts[1].size, 2048);
+        let listed = debug_artifacts_in_listing([
+            ("App.app.dSYM/", 0),
+            ("App.app.dSYM/Contents/Resources/DWARF/App", 2048),
+            ("Frameworks/Kit.framework/Kit", 10),
+            ("1A2B.bcsymbolmap", 100),
+        ]);
+        assert_eq!(listed, artifacts[..2]);
+
+        assert_eq!(remove_debug_artifacts(&app_dir, &artifacts).unwrap(), 2158);
+        assert!(find_debug_artifacts(&app_dir).is_empty());
+        assert!(app_dir.join("Frameworks/Kit.framework/Kit").exists());
+    }
+
+    #[test]
+    fn test_size_breakdown_by_category() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop.ipa");
+        let mut zip = zip::ZipWriter::new(File::create(&ipa).unwrap());
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let info = b"<plist><dict><key>CFBundleExecutable</key><string>Shop</string></dict></plist>";
+        for (name, bytes) in [
+            ("Payload/Shop.app/Info.plist", &info[..]),
+            ("Payload/Shop.app/Shop", &[1; 100][..]),
+            ("Payload/Shop.app/Frameworks/App.framework/App", &[2; 300]),
+            ("Payload/Shop.app/Frameworks/App.framework/Assets.car", &[3; 50]),
+            ("Payload/Shop.app/Assets.car", &[4; 200]),
+            ("Payload/Shop.app/fr.lproj/Localizable.strings", &[5; 20]),
+            ("BuildInfo.json", &[6; 10]),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let breakdown = size_breakdown(&ipa).unwrap();
+        let bytes: Vec<u64> = SizeCategory::ALL.iter().map(|c| breakdown.get(*c).bytes).collect();
+        assert_eq!(bytes, [100, 350, 200, 20, 10 + info.len() as u64]);
+        assert_eq!(breakdown.total().files, 7);
+        assert_eq!(breakdown.get(SizeCategory::Frameworks).compressed_bytes, 350);
+        assert_eq!(format_size_change(2048, 1024), "-1.0 KB");
+    }
+}
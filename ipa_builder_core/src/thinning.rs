@@ -0,0 +1,120 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+/// Architectures offered for thinning, as named by [`crate::macho::arch_name`].
+pub const ARCHITECTURES: [&str; 6] = ["arm64", "arm64e", "armv7", "armv7s", "x86_64", "i386"];
+
+/// What [`thin_bundle`] or [`strip_bitcode`] did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThinningOutcome {
+    /// Binaries that were rewritten, relative to the `.app`.
+    pub thinned: Vec<String>,
+    pub bytes_saved: u64,
+    /// Binaries that could not be rewritten and were left as they were, e.g. fat binaries
+    /// without any of the kept architectures.
+    pub skipped: Vec<String>,
+}
+
+/// Removes the slices not in `keep` from every fat Mach-O in `app_dir`: the main executable,
+/// frameworks, dylibs and extensions. Thin binaries are left alone, whatever their
+/// architecture.
+pub fn thin_bundle(app_dir: &Path, keep: &[String]) -> io::Result<ThinningOutcome> {
+    if keep.is_empty() {
+        return Ok(ThinningOutcome::default());
+    }
+    rewrite_binaries(app_dir, crate::macho::is_fat, |bytes| crate::macho::thin(bytes, keep))
+}
+
+/// Removes the `__LLVM` bitcode segments from every Mach-O in `app_dir`, like
+/// `xcrun bitcode_strip -r`. The App Store no longer accepts bitcode, and it can make up most
+/// of a binary's size.
+pub fn strip_bitcode(app_dir: &Path) -> io::Result<ThinningOutcome> {
+    rewrite_binaries(app_dir, crate::ipa_logic::is_macho, crate::macho::strip_bitcode)
+}
+
+/// Rewrites the files of `app_dir` whose first bytes pass `detect` with `rewrite`, which returns
+/// `Ok(None)` for files it leaves unchanged.
+fn rewrite_binaries(
+    app_dir: &Path,
+    detect: fn(&[u8]) -> bool,
+    rewrite: impl Fn(&[u8]) -> Result<Option<Vec<u8>>, crate::macho::MachOError>,
+) -> io::Result<ThinningOutcome> {
+    let mut outcome = ThinningOutcome::default();
+    for entry in WalkDir::new(app_dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let path = entry.path();
+        let mut magic = [0u8; 8];
+        let detected = fs::File::open(path).and_then(|mut f| io::Read::read_exact(&mut f, &mut magic)).is_ok() && detect(&magic);
+        if !detected {
+            continue;
+        }
+        let relative = path.strip_prefix(app_dir).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        let bytes = fs::read(path)?;
+        match rewrite(&bytes) {
+            Ok(Some(rewritten)) => {
+                fs::write(path, &rewritten)?;
+                outcome.bytes_saved += bytes.len().saturating_sub(rewritten.len()) as u64;
+                outcome.thinned.push(relative);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Not rewriting {}: {}", relative, e);
+                outcome.skipped.push(relative);
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::{self, test_support::thin_macho};
+
+    fn fat(images: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for v in [0xCAFE_BABEu32, images.len() as u32] {
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+        for (i, (cpu, image)) in images.iter().enumerate() {
+            for v in [*cpu, 0, 4096 * (i as u32 + 1), image.len() as u32, 12] {
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        for (i, (_, image)) in images.iter().enumerate() {
+            out.resize(4096 * (i + 1), 0);
+            out.extend_from_slice(image);
+        }
+        out
+    }
+
+    #[test]
+    fn test_rewrites_binaries_in_the_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("Shop.app");
+        fs::create_dir_all(app.join("Frameworks/Lib.framework")).unwrap();
+        let arm64 = thin_macho(macho::CPU_TYPE_ARM64, None);
+        let x86 = thin_macho(macho::CPU_TYPE_X86_64, None);
+        fs::write(app.join("Shop"), fat(&[(macho::CPU_TYPE_ARM64, arm64.clone()), (macho::CPU_TYPE_X86_64, x86.clone())])).unwrap();
+        fs::write(app.join("Frameworks/Lib.framework/Lib"), fat(&[(macho::CPU_TYPE_X86, x86.clone()), (macho::CPU_TYPE_X86_64, x86.clone())])).unwrap();
+        fs::write(app.join("Frameworks/Thin.dylib"), &x86).unwrap();
+        fs::write(app.join("Info.plist"), b"<plist/>").unwrap();
+
+        let outcome = thin_bundle(&app, &["arm64".to_string()]).unwrap();
+        assert_eq!(outcome.thinned, ["Shop"]);
+        assert_eq!(outcome.skipped, ["Frameworks/Lib.framework/Lib"]);
+        assert_eq!(fs::read(app.join("Shop")).unwrap(), arm64);
+        assert_eq!(fs::read(app.join("Frameworks/Thin.dylib")).unwrap(), x86);
+        assert!(outcome.bytes_saved > 4096);
+        assert_eq!(thin_bundle(&app, &[]).unwrap(), ThinningOutcome::default());
+
+        let bitcode = macho::test_support::thin_macho_with_bitcode(macho::CPU_TYPE_ARM64);
+        fs::write(app.join("Frameworks/Thin.dylib"), &bitcode).unwrap();
+        let outcome = strip_bitcode(&app).unwrap();
+        assert_eq!(outcome.thinned, ["Frameworks/Thin.dylib"]);
+        assert_eq!(outcome.bytes_saved, 8192);
+        assert_eq!(macho::strip_bitcode(&fs::read(app.join("Frameworks/Thin.dylib")).unwrap()).unwrap(), None);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+/// Returns the lowercase hex SHA-256 digest of the file at `path`.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The checksum file written next to `path` by [`write_checksum_file`]: `<name>.sha256`.
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    path.with_file_name(name)
+}
+
+/// Writes the SHA-256 of `path` to [`checksum_path`] in the `shasum`/`sha256sum` format, so
+/// recipients can check a download with `shasum -a 256 -c Shop.ipa.sha256`. Returns the digest.
+pub fn write_checksum_file(path: &Path) -> io::Result<String> {
+    let hash = sha256_file(path)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    std::fs::write(checksum_path(path), format!("{}  {}\n", hash, name))?;
+    Ok(hash)
+}
+
+/// Copies `src` to `dest` through a temporary `.part` file next to `dest`, verifying the
+/// SHA-256 of the copy before renaming it into place. Failed attempts are retried with
+/// exponential backoff, which papers over transient SMB/NFS errors mid-transfer.
+pub fn copy_verified_with_retry(src: &Path, dest: &Path, attempts: u32, initial_backoff: Duration) -> Result<(), String> {
+    let expected = sha256_file(src).map_err(|e| format!("Failed to hash {}: {}", src.display(), e))?;
+    let file_name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("Destination {} has no file name", dest.display()))?;
+    let part_path = dest.with_file_name(format!(".{}.part", file_name));
+
+    let attempts = attempts.max(1);
+    let mut backoff = initial_backoff;
+    let mut last_error = String::new();
+    for attempt in 1..=attempts {
+        match copy_once(src, dest, &part_path, &expected) {
+            Ok(()) => {
+                if attempt > 1 {
+                    log::info!("Copied {} to {} on attempt {}", src.display(), dest.display(), attempt);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Copy attempt {}/{} to {} failed: {}", attempt, attempts, dest.display(), e);
+                last_error = e;
+                let _ = std::fs::remove_file(&part_path);
+                if attempt < attempts {
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+    Err(format!("gave up after {} attempts: {}", attempts, last_error))
+}
+
+fn copy_once(src: &Path, dest: &Path, part_path: &Path, expected: &str) -> Result<(), String> {
+    std::fs::copy(src, part_path).map_err(|e| format!("copy failed: {}", e))?;
+    let actual = sha256_file(part_path).map_err(|e| format!("failed to re-read copy: {}", e))?;
+    if actual != expected {
+        return Err(format!("checksum mismatch (expected {}, got {})", expected, actual));
+    }
+    std::fs::rename(part_path, dest).map_err(|e| format!("rename failed: {}", e))
+}
+
+/// Caps how fast `inner` can be read, so uploads don't saturate a shared office link. Reads are
+/// limited to a tenth of a second's worth of bytes, and each sleeps until the average rate since
+/// the first read is back under the limit. A limit of 0 is unlimited.
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_sec: u64,
+    started: Option<Instant>,
+    read: u64,
+}
+
+impl<R> ThrottledReader<R> {
+    pub fn new(inner: R, bytes_per_sec: u64) -> Self {
+        Self { inner, bytes_per_sec, started: None, read: 0 }
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.bytes_per_sec == 0 {
+            return self.inner.read(buf);
+        }
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let chunk = buf.len().min((self.bytes_per_sec / 10).max(1) as usize);
+        let n = self.inner.read(&mut buf[..chunk])?;
+        self.read += n as u64;
+        let due = Duration::from_secs_f64(self.read as f64 / self.bytes_per_sec as f64);
+        if let Some(ahead) = due.checked_sub(started.elapsed()) {
+            thread::sleep(ahead);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_file_uses_the_shasum_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop 1.0.ipa");
+        std::fs::write(&ipa, b"abc").unwrap();
+        let hash = write_checksum_file(&ipa).unwrap();
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(checksum_path(&ipa), dir.path().join("Shop 1.0.ipa.sha256"));
+        assert_eq!(std::fs::read_to_string(checksum_path(&ipa)).unwrap(), format!("{}  Shop 1.0.ipa\n", hash));
+    }
+
+    #[test]
+    fn test_throttled_reader_keeps_to_the_limit() {
+        let data = vec![7u8; 50_000];
+        let started = Instant::now();
+        let mut copied = Vec::new();
+        io::copy(&mut ThrottledReader::new(data.as_slice(), 200_000), &mut copied).unwrap();
+        assert_eq!(copied, data);
+        assert!(started.elapsed() >= Duration::from_millis(240), "{:?}", started.elapsed());
+
+        let started = Instant::now();
+        io::copy(&mut ThrottledReader::new(data.as_slice(), 0), &mut io::sink()).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+}
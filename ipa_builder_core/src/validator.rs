@@ -0,0 +1,282 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Entries Xcode and App Store Connect put next to `Payload/`, besides IPA Builder's own.
+const KNOWN_TOP_LEVEL: [&str; 7] = [
+    "SwiftSupport",
+    "WatchKitSupport",
+    "WatchKitSupport2",
+    "MessagesApplicationExtensionSupport",
+    "iTunesMetadata.plist",
+    "iTunesArtwork",
+    "META-INF",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// iOS refuses to install or launch the IPA.
+    Error,
+    /// The IPA installs, but something in it is likely a mistake.
+    Warning,
+}
+
+/// One problem [`validate_ipa`] found. `path` is the entry inside the IPA it is about, or empty
+/// for the IPA as a whole.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|f| f.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|f| f.severity == Severity::Warning)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    fn push(&mut self, severity: Severity, path: impl Into<String>, message: impl Into<String>) {
+        self.findings.push(Finding { severity, path: path.into(), message: message.into() });
+    }
+}
+
+/// Checks the structure of the IPA at `path`; see [`validate_archive`].
+pub fn validate_ipa(path: &Path) -> Result<ValidationReport, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("{} is not a ZIP archive: {}", path.display(), e))?;
+    Ok(validate_archive(&mut archive))
+}
+
+/// Checks an IPA's structure and returns everything wrong with it, errors first:
+///
+/// * exactly one `Payload/<App>.app` with a readable `Info.plist`, no nested `Payload/`, and
+///   nothing unexpected next to `Payload/`;
+/// * a `CFBundleExecutable` that exists and is executable, and an app icon;
+/// * an `Info.plist` and executable in every app extension (`PlugIns/*.appex`,
+///   `Extensions/*.appex`) and watch app (`Watch/*.app`), whose bundle ID starts with the app's.
+pub fn validate_archive<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut modes = BTreeMap::new();
+    for i in 0..archive.len() {
+        if let Ok(file) = archive.by_index_raw(i) {
+            modes.insert(file.name().to_string(), file.unix_mode());
+        }
+    }
+    let names: BTreeSet<&str> = modes.keys().map(String::as_str).collect();
+
+    check_top_level(&names, &mut report);
+    let apps: BTreeSet<&str> = names
+        .iter()
+        .filter_map(|name| name.strip_prefix("Payload/"))
+        .filter_map(|rest| rest.split('/').next())
+        .filter(|first| first.ends_with(".app"))
+        .collect();
+    if apps.len() > 1 {
+        let list: Vec<_> = apps.iter().copied().collect();
+        report.push(Severity::Error, "Payload/", format!("contains more than one app: {}", list.join(", ")));
+    }
+    let Some(app) = apps.first() else {
+        report.push(Severity::Error, "Payload/", "contains no .app bundle");
+        return sorted(report);
+    };
+    let app_root = format!("Payload/{}/", app);
+
+    let mut read_info = |name: &str| -> Option<plist::Dictionary> {
+        let mut bytes = Vec::new();
+        archive.by_name(name).ok()?.read_to_end(&mut bytes).ok()?;
+        crate::info_plist::parse_dictionary(&bytes).ok()
+    };
+    let bundle_id = |info: &plist::Dictionary| info.get("CFBundleIdentifier").and_then(|v| v.as_string()).map(str::to_string);
+
+    let app_plist = format!("{}Info.plist", app_root);
+    let app_info = if !names.contains(app_plist.as_str()) {
+        report.push(Severity::Error, app_root.as_str(), "has no Info.plist");
+        None
+    } else {
+        let info = read_info(&app_plist);
+        if info.is_none() {
+            report.push(Severity::Error, app_plist.as_str(), "cannot be read as a property list");
+        }
+        info
+    };
+    if let Some(info) = &app_info {
+        match info.get("CFBundleExecutable").and_then(|v| v.as_string()) {
+            None => report.push(Severity::Error, app_plist.as_str(), "has no CFBundleExecutable; iOS cannot launch the app"),
+            Some(executable) => {
+                let path = format!("{}{}", app_root, executable);
+                match modes.get(&path) {
+                    None => report.push(Severity::Error, app_root.as_str(), format!("has no executable {}", executable)),
+                    Some(Some(mode)) if mode & 0o111 == 0 => {
+                        report.push(Severity::Warning, path, format!("is not executable (mode {:04o})", mode & 0o7777))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        let declares_icons = ["CFBundleIcons", "CFBundleIcons~ipad", "CFBundleIconFiles", "CFBundleIconFile"].iter().any(|key| info.contains_key(key));
+        let ships_icons = names.iter().filter_map(|name| name.strip_prefix(app_root.as_str())).any(|name| name.starts_with("AppIcon") && name.ends_with(".png"));
+        if !declares_icons && !ships_icons {
+            report.push(Severity::Warning, app_root.as_str(), "has no app icon set; the home screen shows a blank icon");
+        }
+    }
+    let app_id = app_info.as_ref().and_then(bundle_id);
+
+    let mut nested = BTreeSet::new();
+    for name in names.iter().filter_map(|name| name.strip_prefix(app_root.as_str())) {
+        let parts: Vec<&str> = name.split('/').collect();
+        for (i, pair) in parts.windows(2).enumerate() {
+            if matches!(pair, ["PlugIns" | "Extensions", b] if b.ends_with(".appex")) || matches!(pair, ["Watch", b] if b.ends_with(".app")) {
+                nested.insert(format!("{}{}", app_root, parts[..i + 2].join("/")));
+            }
+        }
+    }
+    for root in &nested {
+        let Some(info) = read_info(&format!("{}/Info.plist", root)) else {
+            report.push(Severity::Error, root.as_str(), "has no readable Info.plist");
+            continue;
+        };
+        let executable = match info.get("CFBundleExecutable").and_then(|v| v.as_string()) {
+            Some(name) => name.to_string(),
+            None => Path::new(root).file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        };
+        if !names.contains(format!("{}/{}", root, executable).as_str()) {
+            report.push(Severity::Error, root.as_str(), format!("has no executable {}", executable));
+        }
+        if let (Some(app_id), Some(id)) = (&app_id, bundle_id(&info)) {
+            if !id.starts_with(&format!("{}.", app_id)) {
+                report.push(
+                    Severity::Warning,
+                    root.as_str(),
+                    format!("has bundle ID {}, which is not prefixed with the app's bundle ID {}; iOS will refuse to install it", id, app_id),
+                );
+            }
+        }
+    }
+    sorted(report)
+}
+
+/// Flags entries next to `Payload/` that neither Xcode nor IPA Builder put there, and a
+/// `Payload/` inside the payload, left by zipping an IPA's contents twice.
+fn check_top_level(names: &BTreeSet<&str>, report: &mut ValidationReport) {
+    let mut stray = BTreeSet::new();
+    let mut nested_payload = BTreeSet::new();
+    for name in names {
+        let parts: Vec<&str> = name.split('/').collect();
+        let top = parts[0];
+        if top != "Payload" && top != crate::ipa_logic::BUILD_INFO_FILE_NAME && top != crate::dsym::SYMBOLS_DIR_NAME && !KNOWN_TOP_LEVEL.contains(&top) {
+            stray.insert(if parts.len() > 1 { format!("{}/", top) } else { top.to_string() });
+        }
+        if let Some(i) = parts.iter().skip(1).position(|part| *part == "Payload") {
+            if i + 2 < parts.len() || name.ends_with('/') {
+                nested_payload.insert(format!("{}/", parts[..i + 2].join("/")));
+            }
+        }
+    }
+    for path in nested_payload {
+        report.push(Severity::Error, path, "is a Payload/ inside the payload; the IPA was packaged twice");
+    }
+    for path in stray {
+        report.push(Severity::Warning, path, "is next to Payload/ but is not part of an IPA");
+    }
+}
+
+/// Errors before warnings, each in the order they were found.
+fn sorted(mut report: ValidationReport) -> ValidationReport {
+    report.findings.sort_by_key(|f| f.severity);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    #[test]
+    fn test_reports_structure_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop.ipa");
+        let plist = |entries: &[(&str, &str)]| {
+            let mut bytes = Vec::new();
+            let info = plist::Dictionary::from_iter(entries.iter().map(|(k, v)| (k.to_string(), plist::Value::from(*v))));
+            plist::Value::Dictionary(info).to_writer_xml(&mut bytes).unwrap();
+            bytes
+        };
+        let write_ipa = |entries: &[(&str, Vec<u8>, u32)]| {
+            let mut zip = zip::ZipWriter::new(File::create(&ipa).unwrap());
+            for (name, bytes, mode) in entries {
+                zip.start_file(*name, FileOptions::default().unix_permissions(*mode)).unwrap();
+                zip.write_all(bytes).unwrap();
+            }
+            zip.finish().unwrap();
+        };
+        let app = plist(&[("CFBundleIdentifier", "com.example.shop"), ("CFBundleExecutable", "Shop"), ("CFBundleIconFile", "AppIcon")]);
+        let bundle = |id: &str, exe: &str| plist(&[("CFBundleIdentifier", id), ("CFBundleExecutable", exe)]);
+        write_ipa(&[
+            ("Payload/Shop.app/Info.plist", app.clone(), 0o644),
+            ("Payload/Shop.app/Shop", b"binary".to_vec(), 0o755),
+            ("Payload/Shop.app/PlugIns/Share.appex/Info.plist", bundle("com.example.shop.share", "Share"), 0o644),
+            ("Payload/Shop.app/PlugIns/Share.appex/Share", b"binary".to_vec(), 0o755),
+            ("Payload/Shop.app/Watch/ShopWatch.app/Info.plist", bundle("com.example.watch", "ShopWatch"), 0o644),
+            ("Payload/Shop.app/Watch/ShopWatch.app/ShopWatch", b"binary".to_vec(), 0o755),
+            ("BuildInfo.json", b"{}".to_vec(), 0o644),
+        ]);
+        let report = validate_ipa(&ipa).unwrap();
+        assert!(report.is_valid());
+        let warnings: Vec<String> = report.warnings().map(Finding::to_string).collect();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].starts_with("Payload/Shop.app/Watch/ShopWatch.app: has bundle ID com.example.watch"));
+
+        write_ipa(&[
+            ("Payload/Shop.app/Info.plist", plist(&[("CFBundleIdentifier", "com.example.shop")]), 0o644),
+            ("Payload/Shop.app/Payload/Shop.app/Shop", b"binary".to_vec(), 0o644),
+            ("Payload/Shop.app/PlugIns/Share.appex/Info.plist", bundle("com.example.shop.share", "Share"), 0o644),
+            ("Payload/Shop.app/Extensions/Widget.appex/Widget", b"binary".to_vec(), 0o755),
+            ("notes.txt", b"stray".to_vec(), 0o644),
+        ]);
+        let findings: Vec<String> = validate_ipa(&ipa).unwrap().findings.iter().map(Finding::to_string).collect();
+        assert_eq!(
+            findings,
+            [
+                "Payload/Shop.app/Payload/: is a Payload/ inside the payload; the IPA was packaged twice",
+                "Payload/Shop.app/Info.plist: has no CFBundleExecutable; iOS cannot launch the app",
+                "Payload/Shop.app/Extensions/Widget.appex: has no readable Info.plist",
+                "Payload/Shop.app/PlugIns/Share.appex: has no executable Share",
+                "notes.txt: is next to Payload/ but is not part of an IPA",
+                "Payload/Shop.app/: has no app icon set; the home screen shows a blank icon",
+            ]
+        );
+
+        write_ipa(&[("Payload/Shop.app/Info.plist", app, 0o644), ("Payload/Shop.app/Shop", b"binary".to_vec(), 0o644)]);
+        let findings: Vec<String> = validate_ipa(&ipa).unwrap().findings.iter().map(Finding::to_string).collect();
+        assert_eq!(findings, ["Payload/Shop.app/Shop: is not executable (mode 0644)"]);
+    }
+}
@@ -0,0 +1,437 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppConfig;
+use crate::ipa_logic::BuildOutput;
+use crate::network::NetworkSettings;
+use crate::size_analysis::format_size;
+
+/// Tries per webhook; transport errors and 5xx responses are retried with a doubling backoff.
+const DELIVERY_ATTEMPTS: u32 = 3;
+/// Header carrying the hex HMAC-SHA256 of the body when the webhook has a secret.
+pub const SIGNATURE_HEADER: &str = "X-IPA-Builder-Signature";
+
+/// Shape of the JSON posted to a webhook.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The [`BuildEvent`] itself, for CI and custom receivers.
+    #[default]
+    Json,
+    /// A Slack incoming-webhook message with a colored attachment and a download button.
+    Slack,
+    /// A Discord webhook message with a rich embed.
+    Discord,
+}
+
+impl WebhookFormat {
+    pub const ALL: [WebhookFormat; 3] = [WebhookFormat::Json, WebhookFormat::Slack, WebhookFormat::Discord];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WebhookFormat::Json => "JSON",
+            WebhookFormat::Slack => "Slack",
+            WebhookFormat::Discord => "Discord",
+        }
+    }
+
+    /// The format a webhook URL's service expects, going by its host.
+    pub fn detect(url: &str) -> WebhookFormat {
+        let host = url.split("://").nth(1).unwrap_or(url).split('/').next().unwrap_or_default().to_ascii_lowercase();
+        if host == "hooks.slack.com" {
+            WebhookFormat::Slack
+        } else if host == "discord.com" || host == "discordapp.com" || host.ends_with(".discord.com") {
+            WebhookFormat::Discord
+        } else {
+            WebhookFormat::Json
+        }
+    }
+
+    /// The body posted for `event`.
+    pub fn payload(self, event: &BuildEvent) -> serde_json::Value {
+        match self {
+            WebhookFormat::Json => serde_json::to_value(event).unwrap_or_default(),
+            WebhookFormat::Slack => slack_message(event),
+            WebhookFormat::Discord => discord_message(event),
+        }
+    }
+}
+
+/// A URL that receives a JSON [`BuildEvent`] as a `POST` after builds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Webhook {
+    pub url: String,
+    pub format: WebhookFormat,
+    pub on_success: bool,
+    pub on_failure: bool,
+    /// When set, the body is signed with it in the [`SIGNATURE_HEADER`] header
+    /// (`sha256=<hex>`), so the receiver can check the request came from this builder.
+    pub secret: String,
+}
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Self { url: String::new(), format: WebhookFormat::Json, on_success: true, on_failure: true, secret: String::new() }
+    }
+}
+
+impl Webhook {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.url.trim();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(format!("Webhook URL '{}' must start with https:// or http://", url));
+        }
+        Ok(())
+    }
+
+    /// Whether the webhook wants `event`.
+    pub fn wants(&self, event: &BuildEvent) -> bool {
+        if event.success { self.on_success } else { self.on_failure }
+    }
+}
+
+/// Checks every webhook of a list, as stored in settings and configs.
+pub fn validate_all(webhooks: &[Webhook]) -> Result<(), String> {
+    webhooks.iter().try_for_each(Webhook::validate)
+}
+
+/// The JSON body posted to webhooks after a build.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct BuildEvent {
+    /// `build.succeeded` or `build.failed`.
+    pub event: String,
+    pub success: bool,
+    pub app_id: String,
+    pub app_name: String,
+    /// The build's release channel, e.g. `beta`.
+    pub channel: Option<String>,
+    pub bundle_id: Option<String>,
+    /// `CFBundleShortVersionString`.
+    pub version: Option<String>,
+    /// `CFBundleVersion`.
+    pub build: Option<String>,
+    pub duration_ms: u128,
+    pub size_bytes: Option<u64>,
+    /// The config's size budget, set only when the IPA is larger.
+    pub size_budget_bytes: Option<u64>,
+    pub ipa_path: Option<String>,
+    /// Where the IPA was uploaded, e.g. its S3 URL.
+    pub ipa_url: Option<String>,
+    /// The config's app icon URL, shown in Slack and Discord messages.
+    pub icon_url: Option<String>,
+    pub sha256: Option<String>,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+    pub finished_at: DateTime<Utc>,
+    /// One line for chat tools that show a `text` field, e.g. `✅ Shop 1.4 (42) built in 12.3s`.
+    pub text: String,
+}
+
+impl BuildEvent {
+    /// The event of a build of `config` that produced `result` in `duration_ms`. `sha256` is the
+    /// IPA's digest when the caller already has it.
+    pub fn new(config: &AppConfig, result: Result<&BuildOutput, String>, duration_ms: u128, sha256: Option<String>) -> Self {
+        let seconds = duration_ms as f64 / 1000.0;
+        let mut event = Self {
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            channel: Some(config.channel.trim().to_string()).filter(|c| !c.is_empty()),
+            icon_url: Some(config.notifications.icon_url.trim().to_string()).filter(|url| !url.is_empty()),
+            duration_ms,
+            finished_at: Utc::now(),
+            ..Default::default()
+        };
+        match result {
+            Ok(output) => {
+                let bundle = output.bundle.as_ref();
+                event.event = "build.succeeded".to_string();
+                event.success = true;
+                event.bundle_id = bundle.map(|b| b.bundle_id.clone());
+                event.version = bundle.map(|b| b.short_version.clone());
+                event.build = bundle.map(|b| b.build.clone());
+                event.size_bytes = std::fs::metadata(&output.ipa_path).ok().map(|m| m.len());
+                event.ipa_path = Some(output.ipa_path.display().to_string());
+                event.ipa_url = output.uploaded_url.clone();
+                event.sha256 = sha256.or_else(|| output.sha256.clone());
+                event.warnings = output.warnings.clone();
+                let version = match (&event.version, &event.build) {
+                    (Some(version), Some(build)) => format!(" {} ({})", version, build),
+                    _ => String::new(),
+                };
+                event.text = format!("✅ {}{} built in {:.1}s", config.app_name, version, seconds);
+                if let (Some(size), Some(budget)) = (event.size_bytes, config.notifications.size_budget_bytes()) {
+                    if size > budget {
+                        event.size_budget_bytes = Some(budget);
+                        event.text.push_str(&format!(", ⚠ {} is over the {} budget", format_size(size), format_size(budget)));
+                    }
+                }
+            }
+            Err(error) => {
+                event.event = "build.failed".to_string();
+                event.text = format!("❌ {} failed after {:.1}s: {}", config.app_name, seconds, error);
+                event.error = Some(error);
+            }
+        }
+        event
+    }
+}
+
+const SUCCESS_COLOR: u32 = 0x2EB886;
+const WARNING_COLOR: u32 = 0xDAA038;
+const FAILURE_COLOR: u32 = 0xD40E0D;
+
+fn color(event: &BuildEvent) -> u32 {
+    match (event.success, event.size_budget_bytes) {
+        (false, _) => FAILURE_COLOR,
+        (true, Some(_)) => WARNING_COLOR,
+        (true, None) => SUCCESS_COLOR,
+    }
+}
+
+/// Name and value of the facts shown in chat messages, skipping unknown ones.
+fn facts(event: &BuildEvent) -> Vec<(&'static str, String)> {
+    let mut facts = Vec::new();
+    if let (Some(version), Some(build)) = (&event.version, &event.build) {
+        facts.push(("Version", format!("{} ({})", version, build)));
+    }
+    if let Some(channel) = &event.channel {
+        facts.push(("Channel", channel.clone()));
+    }
+    if let Some(size) = event.size_bytes {
+        let budget = event.size_budget_bytes.map(|b| format!(", over the {} budget", format_size(b))).unwrap_or_default();
+        facts.push(("Size", format!("{}{}", format_size(size), budget)));
+    }
+    facts.push(("Duration", format!("{:.1}s", event.duration_ms as f64 / 1000.0)));
+    if let Some(bundle_id) = &event.bundle_id {
+        facts.push(("Bundle ID", bundle_id.clone()));
+    }
+    facts
+}
+
+/// Cuts `text` to at most `max` characters, as chat services reject longer fields.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Block Kit message for Slack incoming webhooks; `text` is the fallback shown in notifications.
+fn slack_message(event: &BuildEvent) -> serde_json::Value {
+    let mut headline = format!("*{}*", slack_escape(&crate::notifications::title(event)));
+    if let Some(error) = &event.error {
+        headline.push_str(&format!("\n```{}```", slack_escape(&truncate(error, 2000))));
+    }
+    let mut summary = serde_json::json!({ "type": "section", "text": { "type": "mrkdwn", "text": headline } });
+    if let Some(icon) = &event.icon_url {
+        summary["accessory"] = serde_json::json!({ "type": "image", "image_url": icon, "alt_text": event.app_name });
+    }
+    let fields: Vec<serde_json::Value> = facts(event)
+        .into_iter()
+        .map(|(name, value)| serde_json::json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, slack_escape(&value)) }))
+        .collect();
+    let mut blocks = vec![summary, serde_json::json!({ "type": "section", "fields": fields })];
+    if let Some(url) = &event.ipa_url {
+        blocks.push(serde_json::json!({
+            "type": "actions",
+            "elements": [{ "type": "button", "text": { "type": "plain_text", "text": "Download IPA" }, "url": url }],
+        }));
+    }
+    serde_json::json!({
+        "text": slack_escape(&event.text),
+        "attachments": [{ "color": format!("#{:06X}", color(event)), "blocks": blocks }],
+    })
+}
+
+/// Discord webhook message with one embed.
+fn discord_message(event: &BuildEvent) -> serde_json::Value {
+    let mut embed = serde_json::json!({
+        "title": truncate(&crate::notifications::title(event), 256),
+        "color": color(event),
+        "fields": facts(event).into_iter().map(|(name, value)| serde_json::json!({ "name": name, "value": value, "inline": true })).collect::<Vec<_>>(),
+        "timestamp": event.finished_at.to_rfc3339(),
+        "footer": { "text": "IPA Builder" },
+    });
+    let mut description = Vec::new();
+    if let Some(error) = &event.error {
+        description.push(format!("```{}```", truncate(error, 3500)));
+    }
+    if let Some(url) = &event.ipa_url {
+        embed["url"] = serde_json::json!(url);
+        description.push(format!("[Download IPA]({})", url));
+    }
+    if !description.is_empty() {
+        embed["description"] = serde_json::json!(description.join("\n"));
+    }
+    if let Some(icon) = &event.icon_url {
+        embed["thumbnail"] = serde_json::json!({ "url": icon });
+    }
+    serde_json::json!({ "username": "IPA Builder", "embeds": [embed] })
+}
+
+/// Posts `event` to `webhook` through `http`, retrying transport errors and 5xx answers.
+pub fn deliver(webhook: &Webhook, event: &BuildEvent, http: &ureq::Agent) -> Result<(), String> {
+    let url = webhook.url.trim();
+    let body = serde_json::to_vec(&webhook.format.payload(event)).map_err(|e| format!("Failed to serialize the event: {}", e))?;
+    let signature = Some(webhook.secret.trim()).filter(|s| !s.is_empty()).map(|secret| {
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()), &body);
+        format!("sha256={}", tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect::<String>())
+    });
+    let mut backoff = Duration::from_secs(1);
+    for attempt in 1..=DELIVERY_ATTEMPTS {
+        let mut request = http.post(url).set("Content-Type", "application/json").set("User-Agent", concat!("IPA Builder/", env!("CARGO_PKG_VERSION")));
+        if let Some(signature) = &signature {
+            request = request.set(SIGNATURE_HEADER, signature);
+        }
+        let reason = match request.send_bytes(&body) {
+            Ok(_) => return Ok(()),
+            Err(ureq::Error::Status(code, _)) if code >= 500 && attempt < DELIVERY_ATTEMPTS => format!("HTTP {}", code),
+            Err(ureq::Error::Transport(e)) if attempt < DELIVERY_ATTEMPTS => e.to_string(),
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                return Err(format!("{} answered HTTP {} {}", url, code, text.trim().chars().take(200).collect::<String>()).trim_end().to_string());
+            }
+            Err(ureq::Error::Transport(e)) => return Err(format!("Could not reach {}: {}", url, e)),
+        };
+        log::warn!("Webhook {} attempt {}/{} failed: {}", url, attempt, DELIVERY_ATTEMPTS, reason);
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+    Err(format!("{} could not be reached after {} attempts", url, DELIVERY_ATTEMPTS))
+}
+
+/// Delivers `event` to those of `webhooks` that want it, one after another. Returns an error
+/// line per failed webhook.
+pub fn deliver_all(webhooks: &[Webhook], event: &BuildEvent, network: &NetworkSettings) -> Vec<String> {
+    let targets: Vec<&Webhook> = webhooks.iter().filter(|w| !w.url.trim().is_empty() && w.wants(event)).collect();
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let http = match network.http_agent() {
+        Ok(http) => http,
+        Err(e) => return vec![format!("Webhooks not sent: {}", e)],
+    };
+    let mut failures = Vec::new();
+    for webhook in targets {
+        match deliver(webhook, event, &http) {
+            Ok(()) => log::info!("Sent {} to webhook {}", event.event, webhook.url.trim()),
+            Err(e) => {
+                log::warn!("Webhook failed: {}", e);
+                failures.push(e);
+            }
+        }
+    }
+    failures
+}
+
+/// [`deliver_all`] on a background thread; the receiver gets the failures once all are sent.
+pub fn spawn_deliveries(webhooks: Vec<Webhook>, event: BuildEvent, network: NetworkSettings) -> std::sync::mpsc::Receiver<Vec<String>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(deliver_all(&webhooks, &event, &network));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tiny_http::{Response, Server};
+
+    #[test]
+    fn test_events_are_posted_signed_to_matching_webhooks() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let signature = request.headers().iter().find(|h| h.field.equiv(SIGNATURE_HEADER)).map(|h| h.value.to_string());
+                let status = if request.url() == "/broken" { 404 } else { 200 };
+                log.lock().unwrap().push((request.url().to_string(), body, signature));
+                let _ = request.respond(Response::from_string("no such hook").with_status_code(status));
+            }
+        });
+
+        let config = AppConfig { id: "shop-id".to_string(), app_name: "Shop".to_string(), ..Default::default() };
+        let event = BuildEvent::new(&config, Err("Input file 'x.zip' not found".to_string()), 1500, None);
+        assert_eq!((event.event.as_str(), event.text.as_str()), ("build.failed", "❌ Shop failed after 1.5s: Input file 'x.zip' not found"));
+        let webhooks = [
+            Webhook { url: format!("{}/ci", base), secret: "s3cret".to_string(), ..Default::default() },
+            Webhook { url: format!("{}/successes-only", base), on_failure: false, ..Default::default() },
+            Webhook { url: format!("{}/broken", base), ..Default::default() },
+        ];
+        let failures = deliver_all(&webhooks, &event, &NetworkSettings::default());
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("HTTP 404 no such hook"), "{}", failures[0]);
+
+        let received = received.lock().unwrap();
+        let urls: Vec<&str> = received.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls, ["/ci", "/broken"]);
+        let (_, body, signature) = &received[0];
+        let posted: BuildEvent = serde_json::from_str(body).unwrap();
+        assert_eq!(posted, event);
+        let expected = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, b"s3cret"), body.as_bytes());
+        let expected_hex: String = expected.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(signature.as_deref(), Some(format!("sha256={}", expected_hex).as_str()));
+        assert!(received[1].2.is_none());
+
+        assert!(validate_all(&[Webhook { url: "hooks.example.com".to_string(), ..Default::default() }]).is_err());
+    }
+
+    #[test]
+    fn test_slack_and_discord_messages() {
+        assert_eq!(WebhookFormat::detect("https://hooks.slack.com/services/T0/B0/x"), WebhookFormat::Slack);
+        assert_eq!(WebhookFormat::detect("https://discord.com/api/webhooks/1/x"), WebhookFormat::Discord);
+        assert_eq!(WebhookFormat::detect("https://ci.example.com/hooks/slack"), WebhookFormat::Json);
+
+        let event = BuildEvent {
+            success: true,
+            app_name: "Shop <beta>".to_string(),
+            version: Some("1.4".to_string()),
+            build: Some("42".to_string()),
+            size_bytes: Some(3 * 1024 * 1024),
+            duration_ms: 12_300,
+            ipa_url: Some("https://cdn.example.com/Shop.ipa".to_string()),
+            icon_url: Some("https://cdn.example.com/icon.png".to_string()),
+            text: "✅ Shop <beta> 1.4 (42) built in 12.3s".to_string(),
+            ..Default::default()
+        };
+        let slack = WebhookFormat::Slack.payload(&event);
+        let blocks = &slack["attachments"][0]["blocks"];
+        assert_eq!(slack["text"], "✅ Shop &lt;beta&gt; 1.4 (42) built in 12.3s");
+        assert_eq!(slack["attachments"][0]["color"], "#2EB886");
+        assert_eq!(blocks[0]["text"]["text"], "*Shop &lt;beta&gt; 1.4 (42) built*");
+        assert_eq!(blocks[0]["accessory"]["image_url"], "https://cdn.example.com/icon.png");
+        assert_eq!(blocks[1]["fields"][1]["text"], "*Size*\n3.0 MB");
+        assert_eq!(blocks[2]["elements"][0]["url"], "https://cdn.example.com/Shop.ipa");
+
+        let failed = BuildEvent { success: false, error: Some("No .app found".to_string()), ipa_url: None, ..event };
+        let discord = WebhookFormat::Discord.payload(&failed);
+        let embed = &discord["embeds"][0];
+        assert_eq!(embed["title"], "Shop <beta> build failed");
+        assert_eq!(embed["color"], FAILURE_COLOR);
+        assert_eq!(embed["description"], "```No .app found```");
+        assert_eq!(embed["thumbnail"]["url"], "https://cdn.example.com/icon.png");
+        assert_eq!(embed["fields"][0], serde_json::json!({ "name": "Version", "value": "1.4 (42)", "inline": true }));
+
+        // A build that produced an IPA but failed afterwards, e.g. in a hook, shows both.
+        let hook_failed = BuildEvent { ipa_url: Some("https://cdn.example.com/Shop.ipa".to_string()), ..failed };
+        let discord = WebhookFormat::Discord.payload(&hook_failed);
+        assert_eq!(discord["embeds"][0]["description"], "```No .app found```\n[Download IPA](https://cdn.example.com/Shop.ipa)");
+    }
+}
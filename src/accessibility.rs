@@ -0,0 +1,135 @@
+use egui::{Color32, Key, KeyboardShortcut, Modifiers, Response, Stroke, Style, Visuals, WidgetInfo, WidgetType};
+
+pub const ADD_APP: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::N);
+pub const FOCUS_SEARCH: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+pub const OPEN_SETTINGS: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma);
+pub const OPEN_BUILD_HISTORY: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::H);
+pub const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub const REDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// The main window's shortcuts with what they do, for the top bar's help tooltip.
+pub const SHORTCUTS: &[(KeyboardShortcut, &str)] = &[
+    (ADD_APP, "Add application"),
+    (FOCUS_SEARCH, "Search"),
+    (OPEN_SETTINGS, "Settings"),
+    (OPEN_BUILD_HISTORY, "Build history"),
+    (UNDO, "Undo adding, editing or deleting an app"),
+    (REDO, "Redo"),
+];
+
+/// Width of the outline around the focused widget.
+const FOCUS_STROKE_WIDTH: f32 = 2.0;
+
+/// Gives an icon-only button, e.g. `🗑️`, the name screen readers announce instead of the icon.
+pub fn labeled(response: Response, label: &str) -> Response {
+    response.widget_info(|| WidgetInfo::labeled(WidgetType::Button, label));
+    response
+}
+
+/// egui draws the focused widget in its "active" style; a thick outline in the selection color
+/// makes it easy to follow Tab navigation in both themes. Returns whether `visuals` changed, as
+/// the theme switch replaces them.
+pub fn apply_focus_visuals(visuals: &mut Visuals) -> bool {
+    let stroke = Stroke::new(FOCUS_STROKE_WIDTH, visuals.selection.stroke.color);
+    if visuals.widgets.active.bg_stroke == stroke {
+        return false;
+    }
+    visuals.widgets.active.bg_stroke = stroke;
+    true
+}
+
+/// Black on white (or white on black in dark mode) with outlined widgets, for low vision and
+/// bright rooms. Status colors are kept distinguishable from the text.
+pub fn apply_high_contrast(visuals: &mut Visuals) {
+    let (background, text, accent) = if visuals.dark_mode {
+        (Color32::BLACK, Color32::WHITE, Color32::from_rgb(255, 221, 0))
+    } else {
+        (Color32::WHITE, Color32::BLACK, Color32::from_rgb(0, 60, 200))
+    };
+    visuals.override_text_color = Some(text);
+    visuals.panel_fill = background;
+    visuals.window_fill = background;
+    visuals.extreme_bg_color = background;
+    visuals.faint_bg_color = if visuals.dark_mode { Color32::from_gray(30) } else { Color32::from_gray(225) };
+    visuals.window_stroke = Stroke::new(2.0, text);
+    visuals.hyperlink_color = accent;
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke = Stroke::new(2.0, background);
+    visuals.warn_fg_color = if visuals.dark_mode { accent } else { Color32::from_rgb(150, 70, 0) };
+    visuals.error_fg_color = if visuals.dark_mode { Color32::from_rgb(255, 110, 110) } else { Color32::from_rgb(180, 0, 0) };
+    for widget in [&mut visuals.widgets.noninteractive, &mut visuals.widgets.inactive] {
+        widget.bg_stroke = Stroke::new(1.0, text);
+        widget.fg_stroke = Stroke::new(1.0, text);
+    }
+    visuals.widgets.inactive.weak_bg_fill = background;
+    visuals.widgets.inactive.bg_fill = background;
+    for widget in [&mut visuals.widgets.hovered, &mut visuals.widgets.active, &mut visuals.widgets.open] {
+        widget.weak_bg_fill = accent;
+        widget.bg_fill = accent;
+        widget.fg_stroke = Stroke::new(2.0, background);
+    }
+}
+
+/// `style` with the Settings → Accessibility options applied on top of egui's default theme for
+/// its light/dark mode, so turning an option off restores the normal look.
+pub fn accessible_style(style: &Style, high_contrast: bool, reduce_motion: bool) -> Style {
+    let mut visuals = if style.visuals.dark_mode { Visuals::dark() } else { Visuals::light() };
+    if high_contrast {
+        apply_high_contrast(&mut visuals);
+    }
+    apply_focus_visuals(&mut visuals);
+    Style {
+        visuals,
+        animation_time: if reduce_motion { 0.0 } else { Style::default().animation_time },
+        ..style.clone()
+    }
+}
+
+/// A spinner, or a static hourglass when animations are turned off.
+pub fn busy_indicator(ui: &mut egui::Ui, reduce_motion: bool) -> Response {
+    if reduce_motion {
+        ui.label("⏳")
+    } else {
+        ui.spinner()
+    }
+}
+
+/// One line per shortcut, e.g. `Ctrl+N  Add application`, formatted for this platform.
+pub fn shortcut_help(ctx: &egui::Context) -> String {
+    let mut lines: Vec<String> = SHORTCUTS.iter().map(|(shortcut, action)| format!("{}  {}", ctx.format_shortcut(shortcut), action)).collect();
+    lines.push("Tab / Shift+Tab  Move between controls".to_string());
+    lines.push("Space / Enter  Press the focused button".to_string());
+    lines.push("Esc  Leave a text field, then close the topmost dialog".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcuts_are_distinct_and_focus_is_visible() {
+        for (i, (shortcut, _)) in SHORTCUTS.iter().enumerate() {
+            assert!(SHORTCUTS[i + 1..].iter().all(|(other, _)| other != shortcut));
+        }
+        for mut visuals in [Visuals::dark(), Visuals::light()] {
+            assert!(apply_focus_visuals(&mut visuals));
+            assert!(!apply_focus_visuals(&mut visuals));
+            assert_eq!(visuals.widgets.active.bg_stroke.width, FOCUS_STROKE_WIDTH);
+        }
+    }
+
+    #[test]
+    fn test_display_options_apply_and_revert() {
+        let default = Style::default();
+        let accessible = accessible_style(&default, true, true);
+        assert_eq!(accessible.visuals.panel_fill, Color32::BLACK);
+        assert_eq!(accessible.visuals.override_text_color, Some(Color32::WHITE));
+        assert_eq!(accessible.animation_time, 0.0);
+
+        let restored = accessible_style(&accessible, false, false);
+        assert_eq!(restored.visuals.panel_fill, Visuals::dark().panel_fill);
+        assert_eq!(restored.animation_time, default.animation_time);
+        assert_eq!(accessible_style(&restored, false, false), restored);
+    }
+}
@@ -1,21 +1,118 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use uuid::Uuid;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
-use crate::config_utils::{get_data_dir_path};
-use crate::metrics::{MetricEvent, MetricsCollector};
-use crate::autocheck::{AutoCheckConfig, AutoCheckMessage, AutoCheckRunner};
+pub use ipa_builder_core::AppConfig;
+use crate::config_utils::{get_apps_import_file_path, get_config_dir_path, get_data_dir_path};
+use crate::metrics::{MetricEvent, MetricsCollector, METRICS_DB_FILE_NAME};
+use crate::audit::{AuditAction, AuditLog};
+use crate::build_history::{BuildHistory, BuildRecord, DailyTotals, DateRange, BUILD_HISTORY_DB_FILE_NAME};
+use crate::build_runner::{BuildRunner, FinishedBuild, PendingBuild};
+use crate::build_queue::{BuildQueue, Priority, QueueSource};
+use crate::autocheck::{AutoCheckConfig, AutoCheckMessage, AutoCheckRunner, AUTOCHECK_CONFIG_ID};
+use crate::output_watch::{OutputDirWatcher, WATCHER_RETRY_INTERVAL};
+use crate::input_watch::InputZipWatcher;
+use crate::naming::{sanitize_ipa_file_name, validate_ipa_file_name, OutputExistsPolicy};
+use crate::settings::AppSettings;
+use crate::input_cache::CacheEntry;
+use crate::codesign::SignatureReport;
+use crate::validator::{Severity, ValidationReport};
+use crate::ipa_contents::ContentNode;
+use crate::size_analysis::{SizeBreakdown, SizeCategory};
+use crate::device_install::{ConnectedDevice, InstallOutcome};
+use crate::resign::{BatchResignMessage, BatchResignRunner, ResignOptions};
+use crate::taskbar::{JobProgress, TaskbarProgress};
+use crate::toasts::{ToastKind, Toasts};
+use crate::dashboard::{DashboardRow, SortColumn, TableSort};
+use crate::compression::{PayloadCompression, ALREADY_COMPRESSED_EXTENSIONS};
+use crate::time_display::DisplayTimeZone;
+use crate::accessibility::labeled;
+use crate::bundle_info::BundleInfo;
+use crate::artifact_ledger::{AppStorage, Artifact};
+use crate::naming_audit::{AuditEntry, AuditStatus};
+use crate::provisioning::ProfileInfo;
+use crate::info_plist::PlistOverrides;
+use crate::projects::{Project, ProjectRun};
+use crate::undo::{Applied, ConfigChange, UndoStack};
+use crate::state_store::{StateStore, StoreKind};
+use crate::qr::QrView;
+use crate::artifact_signing::{ArtifactSigning, SignatureTool};
+use crate::app_config::{ConfigErrors, ConfigField};
+use crate::bundle_signing::BundleSigning;
+use crate::ota_manifest::OtaManifest;
+use crate::object_storage::S3Destination;
+use crate::permissions::{PermissionMode, PermissionOverride, PermissionRules};
+use crate::app_store_connect::{AppStoreConnect, UploadMessage};
+use crate::dsym::DsymPlacement;
+use crate::network::{NetworkSettings, OFFLINE_DISABLED_FEATURES};
+use crate::share::{DiawiSettings, ShareMessage, ShareService};
+use crate::webhook::{BuildEvent, Webhook, WebhookFormat};
+use crate::channels::BuildChannel;
+use crate::notifications::{EmailSettings, NotificationEvent, NotificationPreferences};
 use egui_extras::{Column, TableBuilder};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct AppConfig {
-    pub id: String, 
-    pub app_name: String,
-    pub input_zip_path: String,
-    pub output_ipa_name: String,
-    pub created_at: DateTime<Utc>,
-    pub last_generated_at: Option<DateTime<Utc>>,
+/// How often the Devices panel lists the connected devices again while it is open.
+const DEVICE_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// egui id of the search field, focused by [`crate::accessibility::FOCUS_SEARCH`].
+const SEARCH_FIELD_ID: &str = "dashboard_search";
+
+/// Title and entries of the read-only entitlements inspector.
+type EntitlementsView = (String, Result<Vec<(String, String)>, String>);
+
+/// Page of the Settings window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum SettingsTab {
+    #[default]
+    General,
+    Notifications,
+}
+
+/// An App Store Connect upload started from the GUI, shown under the last generated IPA.
+struct AscUpload {
+    ipa_path: PathBuf,
+    /// `None` once Transporter has finished.
+    rx: Option<std::sync::mpsc::Receiver<UploadMessage>>,
+    fraction: Option<f32>,
+    last_line: String,
+    result: Option<Result<(), String>>,
+}
+
+/// A share service upload started from the GUI, shown under the last generated IPA.
+struct ShareUpload {
+    ipa_path: PathBuf,
+    service: ShareService,
+    /// `None` once the upload has finished.
+    rx: Option<std::sync::mpsc::Receiver<ShareMessage>>,
+    fraction: Option<f32>,
+    last_line: String,
+    /// The install link, or why the upload failed.
+    result: Option<Result<String, String>>,
+}
+
+/// A build's size breakdown and the one of the app's previous successful build, each with a
+/// label saying which build it is.
+struct SizeReportView {
+    current: (String, SizeBreakdown),
+    previous: Option<(String, SizeBreakdown)>,
+}
+
+/// What the history chart shows; it is queried again when any of it changes.
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryChartKey {
+    range: DateRange,
+    app_id: Option<String>,
+    zone: DisplayTimeZone,
+    today: chrono::NaiveDate,
+    records: usize,
+}
+
+/// Builds per day drawn above the build history list.
+struct HistoryChart {
+    key: HistoryChartKey,
+    days: Vec<DailyTotals>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -23,30 +120,226 @@ pub struct AppConfig {
 pub struct IpaBuilderApp {
     output_directory: Option<String>,
     app_configs: Vec<AppConfig>,
+    /// `Info.plist` metadata per config id, from the input ZIP when added and from each built IPA.
+    bundle_infos: HashMap<String, BundleInfo>,
+    /// `embedded.mobileprovision` details per config id, gathered like `bundle_infos`.
+    profiles: HashMap<String, ProfileInfo>,
     status_message: String,
     dark_mode: bool,
     show_config_dialog: bool, 
     config_dialog_output_dir_input: String,
 
     search_query: String,
+    table_sort: TableSort,
     show_add_app_dialog: bool,
     add_app_name_input: String,
     add_app_zip_path_input: Option<String>,
     add_app_output_name_input: String,
+    #[serde(skip)]
+    add_app_errors: ConfigErrors,
 
     show_edit_dialog_for_idx: Option<usize>,
     edit_app_name_input: String,
     edit_input_zip_path_input: Option<String>,
+    /// Problems found when Save was last clicked, shown next to their fields until fixed.
+    #[serde(skip)]
+    edit_errors: ConfigErrors,
     edit_output_ipa_name_input: String,
+    edit_changelog_path_input: String,
+    edit_env_vars_input: Vec<(String, String)>,
+    edit_sidecars_input: Vec<String>,
+    edit_dsym_path_input: String,
+    edit_dsym_placement_input: DsymPlacement,
+    edit_strip_junk_input: bool,
+    edit_keep_architectures_input: Vec<String>,
+    edit_inject_dylibs_input: Vec<String>,
+    edit_plist_overrides_input: PlistOverrides,
+    edit_auto_increment_input: bool,
+    edit_auto_rebuild_input: bool,
+    edit_output_exists_input: OutputExistsPolicy,
+    edit_build_counter_input: u64,
+    edit_keep_last_builds_input: u32,
+    edit_trash_old_builds_input: bool,
+    edit_signing_input: BundleSigning,
+    edit_ota_manifest_input: OtaManifest,
+    edit_app_store_connect_input: AppStoreConnect,
+    edit_object_storage_input: S3Destination,
+    edit_permissions_input: PermissionRules,
+    edit_webhooks_input: Vec<Webhook>,
+    edit_notifications_input: NotificationPreferences,
+    /// Text of the email recipients field, parsed into `edit_notifications_input` as it is typed.
+    edit_email_recipients_input: String,
+    /// Text of the "Also executable" field, parsed into `edit_permissions_input` as it is typed.
+    edit_executable_extensions_input: String,
+    /// "Test key" in the Edit dialog: the request in flight, then its result.
+    #[serde(skip)]
+    asc_check_rx: Option<std::sync::mpsc::Receiver<Result<(), String>>>,
+    #[serde(skip)]
+    asc_check_result: Option<Result<(), String>>,
+    /// Webhook and email notifications in flight, each answering with its failures.
+    #[serde(skip)]
+    notification_deliveries: Vec<std::sync::mpsc::Receiver<Vec<String>>>,
+    #[serde(skip)]
+    toasts: Toasts,
 
     show_delete_confirm_for_idx: Option<usize>,
 
+    viewer_mode: bool,
+    #[serde(skip)]
+    viewer_mode_locked: bool,
+
+    settings: AppSettings,
+    show_settings_dialog: bool,
+    #[serde(skip)]
+    settings_tab: SettingsTab,
+    #[serde(skip)]
+    show_compression_dialog: bool,
+    #[serde(skip)]
+    compression_extensions_input: String,
+
     #[serde(skip)]
     metrics_collector: MetricsCollector,
-    generating_app_idx: Option<usize>,
+    #[serde(skip)]
+    audit_log: AuditLog,
+    #[serde(skip)]
+    build_history: BuildHistory,
+    show_build_history: bool,
+    projects: Vec<Project>,
+    #[serde(skip)]
+    show_projects: bool,
+    /// The "Build project" in progress, started one member at a time.
+    #[serde(skip)]
+    project_run: Option<ProjectRun>,
+    history_range: DateRange,
+    /// Text of the custom range's From/To fields, applied once both parse.
+    #[serde(skip)]
+    history_range_inputs: (String, String),
+    #[serde(skip)]
+    history_chart: Option<HistoryChart>,
+    /// Build history indices picked for the next release manifest.
+    #[serde(skip)]
+    release_selection: BTreeSet<usize>,
+    /// Sign the release manifest with the artifact signing key.
+    #[serde(skip)]
+    sign_release_manifest: bool,
+    /// App id the history window is narrowed to, when opened from the app's row.
+    #[serde(skip)]
+    history_app_id: Option<String>,
+    release_notes_dialog_for_idx: Option<usize>,
+    release_notes_input: String,
+    #[serde(skip)]
+    build_runner: Option<BuildRunner>,
+    /// A manual build waiting for its input ZIP to finish being written.
+    #[serde(skip)]
+    pending_build: Option<PendingBuild>,
+    /// Input cache index shown in Settings; `None` until loaded and after it changes.
+    #[serde(skip)]
+    input_cache_entries: Option<Vec<CacheEntry>>,
 
     #[serde(skip)]
     last_generated_ipa_path: Option<PathBuf>,
+    #[serde(skip)]
+    last_generated_config_id: Option<String>,
+    #[serde(skip)]
+    last_generated_ipa_missing: bool,
+    /// SHA-256 of the last generated IPA, shown with a copy button.
+    #[serde(skip)]
+    last_generated_sha256: Option<String>,
+    #[serde(skip)]
+    last_build_warnings: Vec<String>,
+    #[serde(skip)]
+    last_build_is_simulator: bool,
+    #[serde(skip)]
+    taskbar_progress: TaskbarProgress,
+    #[serde(skip)]
+    output_watcher: Option<OutputDirWatcher>,
+    /// Why the output directory is not being watched, while re-attachment is pending.
+    #[serde(skip)]
+    output_watcher_alert: Option<String>,
+    #[serde(skip)]
+    output_watcher_retry_at: Option<std::time::Instant>,
+    /// Watchers of the input ZIPs of configs with `auto_rebuild`, by config id.
+    #[serde(skip)]
+    input_watchers: HashMap<String, InputZipWatcher>,
+    /// Why a config's input ZIP is not being watched, and when to try again.
+    #[serde(skip)]
+    input_watch_errors: HashMap<String, (String, std::time::Instant)>,
+    /// Builds waiting for the current one to finish, shown in the Build Queue panel.
+    #[serde(skip)]
+    build_queue: BuildQueue,
+    #[serde(skip)]
+    build_queue_checked_at: Option<std::time::Instant>,
+    /// Whether the running build came from the queue rather than from a project run.
+    #[serde(skip)]
+    running_from_queue: bool,
+    #[serde(skip)]
+    signature_report: Option<Result<SignatureReport, String>>,
+    /// Structure check of an IPA, shown in the Validation window; `None` while it is closed.
+    #[serde(skip)]
+    validation_report: Option<(PathBuf, Result<ValidationReport, String>)>,
+    /// Archive tree shown in the Inspect window; `None` while it is closed.
+    #[serde(skip)]
+    ipa_contents: Option<(PathBuf, Result<ContentNode, String>)>,
+    /// Shown in the Size Breakdown window; `None` while it is closed.
+    #[serde(skip)]
+    size_report: Option<SizeReportView>,
+    #[serde(skip)]
+    entitlements_view: Option<EntitlementsView>,
+    /// Artifact ledger shown in the Storage window; `None` while it is closed.
+    #[serde(skip)]
+    storage_ledger: Option<Vec<AppStorage>>,
+    /// Serves the output directory to devices on the LAN while running.
+    #[cfg(feature = "ota-server")]
+    #[serde(skip)]
+    ota_server: Option<crate::ota_server::OtaServer>,
+    /// Superseded artifacts waiting for the user to confirm their deletion.
+    #[serde(skip)]
+    storage_cleanup_pending: Option<Vec<Artifact>>,
+    /// IPAs in the output directory checked against their config's output name, each with
+    /// whether it is selected for renaming; `None` while the Naming Audit window is closed.
+    #[serde(skip)]
+    naming_audit: Option<Result<Vec<(AuditEntry, bool)>, String>>,
+    /// Adds, edits and deletes of this session, for Undo and Redo.
+    #[serde(skip)]
+    undo_stack: UndoStack,
+    /// Install or download link shown as a QR code for scanning with a phone.
+    #[serde(skip)]
+    qr_view: Option<QrView>,
+    #[serde(skip)]
+    asc_upload: Option<AscUpload>,
+    #[serde(skip)]
+    share_upload: Option<ShareUpload>,
+    /// Results of the environment checks; `None` while the Diagnostics window is closed.
+    #[serde(skip)]
+    diagnostics: Option<Vec<crate::diagnostics::Check>>,
+    #[serde(skip)]
+    device_install_rx: Option<std::sync::mpsc::Receiver<InstallOutcome>>,
+    #[serde(skip)]
+    device_install_outcome: Option<InstallOutcome>,
+    /// Whether the side panel of connected devices is shown; kept across launches.
+    show_devices_panel: bool,
+    #[serde(skip)]
+    devices: Option<Result<Vec<ConnectedDevice>, String>>,
+    #[serde(skip)]
+    devices_rx: Option<std::sync::mpsc::Receiver<Result<Vec<ConnectedDevice>, String>>>,
+    #[serde(skip)]
+    devices_listed_at: Option<std::time::Instant>,
+
+    show_batch_resign_dialog: bool,
+    batch_resign_input_dir: Option<String>,
+    batch_resign_output_dir: Option<String>,
+    batch_resign_identity: String,
+    batch_resign_profile: Option<String>,
+    #[serde(skip)]
+    batch_resign_identities: Vec<String>,
+    #[serde(skip)]
+    batch_resign_runner: Option<BatchResignRunner>,
+    #[serde(skip)]
+    batch_resign_files: Vec<PathBuf>,
+    #[serde(skip)]
+    batch_resign_results: Vec<Option<Result<PathBuf, String>>>,
+    #[serde(skip)]
+    batch_resign_current: Option<usize>,
 
     autocheck_watch_dir: Option<String>,
     autocheck_app_name: String,
@@ -55,26 +348,67 @@ pub struct IpaBuilderApp {
 
     #[serde(skip)]
     autocheck_runner: Option<AutoCheckRunner>,
+    /// What the running AutoCheck builds the ZIPs it queues with; kept while it restarts.
+    #[serde(skip)]
+    autocheck_config: Option<AutoCheckConfig>,
     #[serde(skip)]
     autocheck_log: Vec<String>,
+    /// Set while the AutoCheck watcher is detached (directory gone or thread died).
+    #[serde(skip)]
+    autocheck_alert: Option<String>,
+    #[serde(skip)]
+    autocheck_restart_at: Option<std::time::Instant>,
 }
 
 impl IpaBuilderApp {
 
     fn poll_autocheck_messages(&mut self) {
         if let Some(runner) = &self.autocheck_runner {
+            let mut lines = Vec::new();
             while let Some(msg) = runner.try_recv() {
                 match msg {
-                    AutoCheckMessage::Status(s) => {
-                        self.status_message = s.clone();
-                        self.autocheck_log.push(s);
-                        if self.autocheck_log.len() > 200 {
-                            let drain = self.autocheck_log.len() - 200;
-                            self.autocheck_log.drain(0..drain);
-                        }
+                    AutoCheckMessage::Status(s) => lines.push(s),
+                    AutoCheckMessage::Ready(path) => {
+                        let input = path.to_string_lossy().into_owned();
+                        self.build_queue.push_input(AUTOCHECK_CONFIG_ID, Some(input), None, Priority::Normal, QueueSource::AutoCheck);
+                        lines.push(format!("Queued: {} ({} in the build queue)", path.display(), self.build_queue.len()));
+                    }
+                    AutoCheckMessage::WatcherLost(reason) => {
+                        lines.push(format!("Watcher lost: {}. Retrying...", reason));
+                        self.autocheck_alert = Some(reason);
+                    }
+                    AutoCheckMessage::WatcherRestored => {
+                        lines.push("Watcher re-attached.".to_string());
+                        self.autocheck_alert = None;
                     }
                 }
             }
+            if !runner.is_alive() {
+                lines.push("AutoCheck stopped unexpectedly. Restarting...".to_string());
+                self.autocheck_alert = Some("AutoCheck stopped unexpectedly".to_string());
+                self.autocheck_runner = None;
+                self.autocheck_restart_at = Some(std::time::Instant::now() + WATCHER_RETRY_INTERVAL);
+            }
+            for line in lines {
+                self.push_autocheck_log(line);
+            }
+        } else if self.autocheck_restart_at.is_some_and(|t| std::time::Instant::now() >= t) {
+            self.autocheck_restart_at = None;
+            self.start_autocheck();
+            if self.autocheck_runner.is_some() {
+                self.autocheck_alert = None;
+            } else {
+                self.autocheck_restart_at = Some(std::time::Instant::now() + WATCHER_RETRY_INTERVAL);
+            }
+        }
+    }
+
+    fn push_autocheck_log(&mut self, line: String) {
+        self.status_message = line.clone();
+        self.autocheck_log.push(line);
+        if self.autocheck_log.len() > 200 {
+            let drain = self.autocheck_log.len() - 200;
+            self.autocheck_log.drain(0..drain);
         }
     }
 
@@ -109,11 +443,14 @@ impl IpaBuilderApp {
             output_dir,
             app_name: self.autocheck_app_name.trim().to_string(),
             output_ipa_name: self.autocheck_output_ipa_name.trim().to_string(),
+            build_options: self.settings.build_options(),
+            build_inline: false,
         };
 
-        match AutoCheckRunner::start(cfg) {
+        match AutoCheckRunner::start(cfg.clone()) {
             Ok(runner) => {
                 self.autocheck_runner = Some(runner);
+                self.autocheck_config = Some(cfg);
                 self.status_message = "AutoCheck started.".to_string();
             }
             Err(e) => {
@@ -126,6 +463,9 @@ impl IpaBuilderApp {
         if let Some(mut runner) = self.autocheck_runner.take() {
             runner.stop();
         }
+        self.autocheck_config = None;
+        self.autocheck_alert = None;
+        self.autocheck_restart_at = None;
         self.status_message = "AutoCheck stopped.".to_string();
     }
 
@@ -135,12 +475,13 @@ impl IpaBuilderApp {
             ui.heading("AutoCheck");
 
             let running = self.autocheck_is_running();
+            let editable_settings = !running && !self.viewer_mode;
 
             ui.horizontal(|ui| {
                 ui.label("Watch folder:");
                 let watch_display = self.autocheck_watch_dir.clone().unwrap_or_default();
                 let mut editable = watch_display;
-                ui.add_enabled_ui(!running, |ui| {
+                ui.add_enabled_ui(editable_settings, |ui| {
                     ui.text_edit_singleline(&mut editable);
                     if ui.button("Browse...").clicked() {
                         match native_dialog::FileDialog::new().show_open_single_dir() {
@@ -166,7 +507,7 @@ impl IpaBuilderApp {
                 ui.label("Output folder:");
                 let output_display = self.autocheck_output_directory.clone().unwrap_or_default();
                 let mut editable = output_display;
-                ui.add_enabled_ui(!running, |ui| {
+                ui.add_enabled_ui(editable_settings, |ui| {
                     ui.text_edit_singleline(&mut editable);
                     if ui.button("Browse...").clicked() {
                         match native_dialog::FileDialog::new().show_open_single_dir() {
@@ -190,14 +531,14 @@ impl IpaBuilderApp {
 
             ui.horizontal(|ui| {
                 ui.label("App name:");
-                ui.add_enabled_ui(!running, |ui| {
+                ui.add_enabled_ui(editable_settings, |ui| {
                     ui.text_edit_singleline(&mut self.autocheck_app_name);
                 });
             });
 
             ui.horizontal(|ui| {
                 ui.label("Output IPA:");
-                ui.add_enabled_ui(!running, |ui| {
+                ui.add_enabled_ui(editable_settings, |ui| {
                     ui.text_edit_singleline(&mut self.autocheck_output_ipa_name);
                 });
             });
@@ -215,6 +556,9 @@ impl IpaBuilderApp {
             });
 
             ui.label(format!("Status: {}", if running { "Running" } else { "Stopped" }));
+            if let Some(alert) = &self.autocheck_alert {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {} — retrying automatically", alert));
+            }
 
             egui::ScrollArea::vertical()
                 .id_source("autocheck_log_scroll")
@@ -226,67 +570,434 @@ impl IpaBuilderApp {
                 });
         });
     }
+    /// Forces the read-only viewer mode for this session (e.g. from the `--viewer` launch flag),
+    /// so it cannot be switched off from the UI on shared kiosk machines.
+    pub fn lock_viewer_mode(&mut self) {
+        self.viewer_mode = true;
+        self.viewer_mode_locked = true;
+        self.show_add_app_dialog = false;
+        self.show_edit_dialog_for_idx = None;
+        self.show_delete_confirm_for_idx = None;
+    }
+
     pub fn post_load_setup(&mut self, _cc: &eframe::CreationContext<'_>) {
         log::info!("IpaBuilderApp::post_load_setup called.");
-        self.metrics_collector = MetricsCollector::new(get_data_dir_path().expect("Failed to get data dir for metrics post-load").join("metrics.jsonl"));
+        self.init_runtime_state();
+        #[cfg(feature = "ota-server")]
+        if self.settings.ota_server.start_on_launch {
+            self.toggle_ota_server();
+        }
+    }
+
+    /// Starts serving the output directory to devices, or stops if already serving.
+    #[cfg(feature = "ota-server")]
+    fn toggle_ota_server(&mut self) {
+        if let Some(server) = self.ota_server.take() {
+            drop(server);
+            self.status_message = "Stopped serving builds to devices.".to_string();
+            return;
+        }
+        let Some(output_dir) = self.output_directory.clone() else {
+            self.status_message = "Select an output directory before serving builds to devices.".to_string();
+            return;
+        };
+        match crate::ota_server::OtaServer::start(&self.settings.ota_server, Path::new(&output_dir), &self.settings.branding) {
+            Ok(server) => {
+                self.status_message = format!("Serving the builds in {} to devices at {}", output_dir, server.url());
+                self.ota_server = Some(server);
+            }
+            Err(e) => self.status_message = format!("Could not start the OTA server: {}", e),
+        }
+    }
+
+    #[cfg(feature = "ota-server")]
+    fn render_ota_server_button(&mut self, ui: &mut egui::Ui) {
+        let serving = self.ota_server.as_ref().map(|s| s.url().to_string());
+        let label = if serving.is_some() { "📡 Stop serving" } else { "📡 Serve to devices" };
+        if ui.button(label).on_hover_text("Let iPhones and iPads on this network install the builds in the output directory from Safari").clicked() {
+            self.toggle_ota_server();
+        }
+        if let Some(url) = serving {
+            ui.hyperlink(&url);
+            if labeled(ui.small_button("📋"), "Copy the install page address").on_hover_text("Copy").clicked() {
+                ui.output_mut(|o| o.copied_text = url.clone());
+            }
+            if labeled(ui.small_button("QR"), "Show the install page address as a QR code").on_hover_text("QR code").clicked() {
+                self.qr_view = Some(QrView::new("Install page", url));
+            }
+        }
+    }
+
+    /// Re-opens the data-directory stores and restarts watchers for the loaded state.
+    fn init_runtime_state(&mut self) {
+        let data_dir_path = get_data_dir_path().expect("Failed to get data dir for metrics post-load");
+        self.metrics_collector = MetricsCollector::new(data_dir_path.join(METRICS_DB_FILE_NAME));
+        self.metrics_collector.sync_writes = self.settings.sync_metrics_writes;
+        self.audit_log = AuditLog::new(data_dir_path.join("audit.jsonl"));
+        self.build_history = BuildHistory::new(data_dir_path.join(BUILD_HISTORY_DB_FILE_NAME));
+        self.prune_history();
+        self.restart_output_watcher();
+        self.sync_apps_import_file(false);
+    }
+
+    /// Deletes build history and metrics older than `history_retention_days`, if set.
+    fn prune_history(&mut self) {
+        if self.settings.history_retention_days == 0 {
+            return;
+        }
+        let cutoff = Utc::now() - chrono::Duration::days(self.settings.history_retention_days.into());
+        match (self.build_history.prune_before(cutoff), self.metrics_collector.prune_before(cutoff)) {
+            (Ok(builds), Ok(metrics)) if builds + metrics > 0 => {
+                log::info!("Pruned {} build(s) and {} metric(s) older than {} days", builds, metrics, self.settings.history_retention_days);
+            }
+            (Err(e), _) | (_, Err(e)) => log::error!("Failed to prune history: {}", e),
+            _ => {}
+        }
+    }
+
+    /// Merges configs declared in the `apps.json` drop-in file. A missing file is only
+    /// reported when the sync was requested explicitly.
+    fn sync_apps_import_file(&mut self, explicit: bool) {
+        let path = match get_apps_import_file_path() {
+            Some(p) => p,
+            None => return,
+        };
+        if !path.exists() {
+            if explicit {
+                self.status_message = format!("No {} found at {}.", crate::app_import::APPS_IMPORT_FILE_NAME, path.display());
+            }
+            return;
+        }
+        match crate::app_import::load_apps_import_file(&path) {
+            Ok(file) => {
+                let summary = crate::app_import::merge_imported_apps(&mut self.app_configs, &file);
+                log::info!("Synced {}: {} added, {} updated", path.display(), summary.added.len(), summary.updated.len());
+                for app_id in &summary.added {
+                    if let Some(app_name) = self.app_configs.iter().find(|c| &c.id == app_id).map(|c| c.app_name.clone()) {
+                        self.record_metric(MetricEvent::AppAdded { app_name: app_name.clone() });
+                        self.audit_log.record(AuditAction::ConfigAdded { app_id: app_id.clone(), app_name, source: "apps.json".to_string() });
+                    }
+                }
+                for app_id in &summary.updated {
+                    if let Some(app_name) = self.app_configs.iter().find(|c| &c.id == app_id).map(|c| c.app_name.clone()) {
+                        self.audit_log.record(AuditAction::ConfigEdited { app_id: app_id.clone(), app_name, source: "apps.json".to_string() });
+                    }
+                }
+                if explicit || !summary.added.is_empty() || !summary.updated.is_empty() {
+                    self.status_message = format!("Synced {}: {} added, {} updated.", crate::app_import::APPS_IMPORT_FILE_NAME, summary.added.len(), summary.updated.len());
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                self.status_message = format!("App import failed: {}", e);
+            }
+        }
+    }
+
+    fn restart_output_watcher(&mut self) {
+        let output_dir = match self.output_directory.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            Some(s) => PathBuf::from(s),
+            None => {
+                self.output_watcher = None;
+                self.output_watcher_alert = None;
+                return;
+            }
+        };
+        if self.output_watcher.as_ref().is_some_and(|w| w.watch_dir() == output_dir) {
+            return;
+        }
+        match OutputDirWatcher::start(&output_dir) {
+            Ok(watcher) => {
+                self.output_watcher = Some(watcher);
+                if self.output_watcher_alert.take().is_some() {
+                    self.status_message = format!("{} is available again; watching resumed.", output_dir.display());
+                    if let Some(last_path) = &self.last_generated_ipa_path {
+                        self.last_generated_ipa_missing = !last_path.exists();
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("{}", e);
+                self.output_watcher = None;
+                self.output_watcher_alert = Some(e);
+                self.output_watcher_retry_at = Some(std::time::Instant::now() + WATCHER_RETRY_INTERVAL);
+            }
+        }
+    }
+
+    /// Keeps one watcher per config with `auto_rebuild` on, and queues a rebuild of each config
+    /// whose input ZIP changed. Watchers that fail are retried every [`WATCHER_RETRY_INTERVAL`].
+    fn poll_input_watchers(&mut self) {
+        if self.viewer_mode {
+            self.input_watchers.clear();
+            return;
+        }
+        let wanted: HashMap<&str, &str> = self.app_configs.iter()
+            .filter(|c| c.auto_rebuild)
+            .map(|c| (c.id.as_str(), c.input_zip_path.trim()))
+            .collect();
+        self.input_watchers.retain(|id, watcher| wanted.get(id.as_str()).is_some_and(|path| watcher.path() == Path::new(path)) && watcher.error().is_none());
+        self.input_watch_errors.retain(|id, _| wanted.contains_key(id.as_str()));
+        self.build_queue.retain(|job| job.source != QueueSource::AutoRebuild || wanted.contains_key(job.config_id.as_str()));
+        let now = std::time::Instant::now();
+        for (id, path) in wanted {
+            if self.input_watchers.contains_key(id) || self.input_watch_errors.get(id).is_some_and(|(_, retry_at)| now < *retry_at) {
+                continue;
+            }
+            match InputZipWatcher::start(Path::new(path)) {
+                Ok(watcher) => {
+                    self.input_watch_errors.remove(id);
+                    self.input_watchers.insert(id.to_string(), watcher);
+                }
+                Err(e) => {
+                    log::warn!("Cannot watch {}: {}", path, e);
+                    self.input_watch_errors.insert(id.to_string(), (e, now + WATCHER_RETRY_INTERVAL));
+                }
+            }
+        }
+        for (id, watcher) in self.input_watchers.iter_mut() {
+            if watcher.take_changed() && !self.build_queue.contains_config(id) {
+                self.build_queue.push(id, None, Priority::Normal, QueueSource::AutoRebuild);
+                if let Some(config) = self.app_configs.iter().find(|c| &c.id == id) {
+                    self.status_message = format!("Input of '{}' changed; rebuild queued.", config.app_name);
+                }
+            }
+        }
+    }
+
+    fn poll_output_watcher(&mut self) {
+        let (changed, failure) = match self.output_watcher.as_mut() {
+            Some(watcher) => (watcher.drain_changed_paths(), watcher.check_health()),
+            None => {
+                if self.output_watcher_alert.is_some() && self.output_watcher_retry_at.is_some_and(|t| std::time::Instant::now() >= t) {
+                    self.restart_output_watcher();
+                }
+                return;
+            }
+        };
+        if let Some(reason) = failure {
+            log::warn!("Output directory watcher failed: {}", reason);
+            self.status_message = format!("Stopped watching the output directory: {}. Will re-attach when it is available.", reason);
+            self.output_watcher = None;
+            self.output_watcher_alert = Some(reason);
+            self.output_watcher_retry_at = Some(std::time::Instant::now() + WATCHER_RETRY_INTERVAL);
+        }
+        if let Some(last_path) = &self.last_generated_ipa_path {
+            if changed.iter().any(|p| p == last_path) {
+                let missing = !last_path.exists();
+                if missing && !self.last_generated_ipa_missing {
+                    self.status_message = format!("'{}' was deleted or moved outside of IPA Builder.", last_path.display());
+                }
+                self.last_generated_ipa_missing = missing;
+            }
+        }
     }
 }
 
 impl Default for IpaBuilderApp {
     fn default() -> Self {
         let data_dir_path = get_data_dir_path().expect("Failed to get data dir for metrics default");
-        let metrics_collector = MetricsCollector::new(data_dir_path.join("metrics.jsonl"));
+        Self::with_data_dir(&data_dir_path)
+    }
+}
+
+impl IpaBuilderApp {
+    /// A fresh app whose metrics, audit log and build history live in `data_dir_path`.
+    fn with_data_dir(data_dir_path: &Path) -> Self {
+        let metrics_collector = MetricsCollector::new(data_dir_path.join(METRICS_DB_FILE_NAME));
+        let audit_log = AuditLog::new(data_dir_path.join("audit.jsonl"));
+        let build_history = BuildHistory::new(data_dir_path.join(BUILD_HISTORY_DB_FILE_NAME));
         
         Self {
             output_directory: None,
             app_configs: Vec::new(),
+            bundle_infos: HashMap::new(),
+            profiles: HashMap::new(),
             status_message: "Welcome to IPA Builder!".to_string(),
             dark_mode: true,
             show_config_dialog: true, 
             config_dialog_output_dir_input: "".to_string(),
             metrics_collector,
+            audit_log,
+            build_history,
+            show_build_history: false,
+            projects: Vec::new(),
+            show_projects: false,
+            project_run: None,
+            history_range: DateRange::default(),
+            history_range_inputs: (String::new(), String::new()),
+            history_chart: None,
+            release_selection: BTreeSet::new(),
+            sign_release_manifest: false,
+            history_app_id: None,
+            release_notes_dialog_for_idx: None,
+            release_notes_input: String::new(),
             search_query: String::new(),
+            table_sort: TableSort::default(),
             show_add_app_dialog: false,
             add_app_name_input: "MyNewApp".to_string(),
             add_app_zip_path_input: None,
             add_app_output_name_input: "output.ipa".to_string(),
+            add_app_errors: ConfigErrors::new(),
             show_edit_dialog_for_idx: None,
             edit_app_name_input: String::new(),
             edit_input_zip_path_input: None,
+            edit_errors: ConfigErrors::new(),
             edit_output_ipa_name_input: String::new(),
+            edit_changelog_path_input: String::new(),
+            edit_env_vars_input: Vec::new(),
+            edit_sidecars_input: Vec::new(),
+            edit_dsym_path_input: String::new(),
+            edit_dsym_placement_input: DsymPlacement::default(),
+            edit_strip_junk_input: true,
+            edit_keep_architectures_input: Vec::new(),
+            edit_inject_dylibs_input: Vec::new(),
+            edit_plist_overrides_input: PlistOverrides::default(),
+            edit_auto_increment_input: false,
+            edit_auto_rebuild_input: false,
+            edit_output_exists_input: OutputExistsPolicy::default(),
+            edit_build_counter_input: 0,
+            edit_keep_last_builds_input: 0,
+            edit_trash_old_builds_input: false,
+            edit_signing_input: BundleSigning::default(),
+            edit_ota_manifest_input: OtaManifest::default(),
+            edit_app_store_connect_input: AppStoreConnect::default(),
+            edit_object_storage_input: S3Destination::default(),
+            edit_permissions_input: PermissionRules::default(),
+            edit_webhooks_input: Vec::new(),
+            edit_notifications_input: NotificationPreferences::default(),
+            edit_email_recipients_input: String::new(),
+            edit_executable_extensions_input: String::new(),
+            asc_check_rx: None,
+            notification_deliveries: Vec::new(),
+            toasts: Toasts::default(),
+            asc_check_result: None,
             show_delete_confirm_for_idx: None,
-            generating_app_idx: None,
+            viewer_mode: false,
+            viewer_mode_locked: false,
+            settings: AppSettings::default(),
+            show_settings_dialog: false,
+            settings_tab: SettingsTab::General,
+            show_compression_dialog: false,
+            compression_extensions_input: String::new(),
+            build_runner: None,
+            pending_build: None,
+            input_cache_entries: None,
             last_generated_ipa_path: None,
+            last_generated_config_id: None,
+            last_generated_ipa_missing: false,
+            last_generated_sha256: None,
+            last_build_warnings: Vec::new(),
+            last_build_is_simulator: false,
+            taskbar_progress: TaskbarProgress::default(),
+            output_watcher: None,
+            output_watcher_alert: None,
+            output_watcher_retry_at: None,
+            input_watchers: HashMap::new(),
+            input_watch_errors: HashMap::new(),
+            build_queue: BuildQueue::default(),
+            build_queue_checked_at: None,
+            running_from_queue: false,
+            signature_report: None,
+            validation_report: None,
+            ipa_contents: None,
+            size_report: None,
+            entitlements_view: None,
+            storage_ledger: None,
+            #[cfg(feature = "ota-server")]
+            ota_server: None,
+            storage_cleanup_pending: None,
+            naming_audit: None,
+            undo_stack: UndoStack::default(),
+            qr_view: None,
+            asc_upload: None,
+            share_upload: None,
+            diagnostics: None,
+            device_install_rx: None,
+            device_install_outcome: None,
+            show_devices_panel: false,
+            devices: None,
+            devices_rx: None,
+            devices_listed_at: None,
+            show_batch_resign_dialog: false,
+            batch_resign_input_dir: None,
+            batch_resign_output_dir: None,
+            batch_resign_identity: String::new(),
+            batch_resign_profile: None,
+            batch_resign_identities: Vec::new(),
+            batch_resign_runner: None,
+            batch_resign_files: Vec::new(),
+            batch_resign_results: Vec::new(),
+            batch_resign_current: None,
 
             autocheck_watch_dir: None,
             autocheck_app_name: "AutoCheckApp".to_string(),
             autocheck_output_ipa_name: "AutoCheckApp.ipa".to_string(),
             autocheck_output_directory: None,
             autocheck_runner: None,
+            autocheck_config: None,
             autocheck_log: Vec::new(),
+            autocheck_alert: None,
+            autocheck_restart_at: None,
         }
     }
 }
 
 impl eframe::App for IpaBuilderApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-            match serde_json::to_string(self) {
-                Ok(json_string) => {
-                    storage.set_string(eframe::APP_KEY, json_string);
-                    log::trace!("App state saved via storage.set_string");
-                }
-                Err(e) => {
-                    log::error!("Failed to serialize app state: {}", e);
+        // The file stores are written on exit; eframe storage is saved here, periodically too.
+        if self.viewer_mode_locked || StoreKind::from_env() != Ok(StoreKind::Eframe) {
+            return;
+        }
+        match serde_json::to_value(&*self) {
+            Ok(state) => {
+                if let Err(e) = storage.save(&state) {
+                    log::error!("{}", e);
                 }
+                log::trace!("App state saved to eframe storage");
             }
-
-            if let Some(mut runner) = self.autocheck_runner.take() {
-                runner.stop();
+            Err(e) => {
+                log::error!("Failed to serialize app state: {}", e);
             }
         }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(mut runner) = self.autocheck_runner.take() {
+            runner.stop();
+        }
+        // Locked viewer sessions are read-only and must not overwrite the shared state.
+        if self.viewer_mode_locked {
+            return;
+        }
+        if let Err(e) = crate::config_utils::save_app_state(self) {
+            log::error!("{}", e);
+        }
+    }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.poll_autocheck_messages();
+        self.poll_output_watcher();
+        self.poll_input_watchers();
+        if !self.input_watchers.is_empty() || !self.input_watch_errors.is_empty() || !self.build_queue.is_empty() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+        if self.output_watcher_alert.is_some() || self.autocheck_runner.is_some() || self.autocheck_restart_at.is_some() {
+            ctx.request_repaint_after(WATCHER_RETRY_INTERVAL);
+        }
+        self.poll_batch_resign();
+        self.poll_device_install();
+        self.poll_devices(ctx);
+        self.poll_app_store_connect(ctx);
+        self.poll_share_upload(ctx);
+        self.poll_notifications(ctx);
+        self.poll_build_runner();
+        self.start_next_queued_build();
+        if self.build_runner.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        let progress = self.current_job_progress();
+        self.taskbar_progress.update(ctx, progress);
 
         if self.output_directory.is_none() {
             self.show_config_dialog = true;
@@ -294,19 +1005,220 @@ impl eframe::App for IpaBuilderApp {
 
         if self.show_config_dialog {
             self.render_config_dialog(ctx);
+            self.render_diagnostics_window(ctx);
             return;
         }
 
+        self.handle_keyboard(ctx);
         self.render_main_ui(ctx);
-        self.render_add_app_dialog(ctx);
-        self.render_edit_dialog(ctx);
-        self.render_delete_confirm_dialog(ctx);
+        self.toasts.show(ctx);
+        self.render_settings_dialog(ctx);
+        self.render_compression_dialog(ctx);
+        self.render_signature_report_window(ctx);
+        self.render_validation_report_window(ctx);
+        self.render_ipa_contents_window(ctx);
+        self.render_size_report_window(ctx);
+        self.render_entitlements_window(ctx);
+        self.render_build_history_window(ctx);
+        self.render_storage_window(ctx);
+        self.render_naming_audit_window(ctx);
+        self.render_qr_window(ctx);
+        self.render_diagnostics_window(ctx);
+        self.render_projects_window(ctx);
+        self.render_device_install_window(ctx);
+        self.render_release_notes_dialog(ctx);
+        self.render_pending_build_dialog(ctx);
+        if !self.viewer_mode {
+            self.render_batch_resign_dialog(ctx);
+            self.render_add_app_dialog(ctx);
+            self.render_edit_dialog(ctx);
+            self.render_delete_confirm_dialog(ctx);
+        }
     }
 }
 
 impl IpaBuilderApp {
 
-    fn open_folder_containing_file(&self, file_path: &PathBuf) {
+    /// Accessibility display options, the main window's shortcuts and Esc closing the topmost
+    /// dialog. Esc first goes to a focused text field, which gives up focus, so typing is never
+    /// lost by accident.
+    fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        let style = ctx.style();
+        let accessible = crate::accessibility::accessible_style(&style, self.settings.high_contrast, self.settings.reduce_motion);
+        if accessible != *style {
+            ctx.set_style(accessible);
+        }
+        let nothing_focused = ctx.memory(|m| m.focused().is_none());
+        if nothing_focused && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.close_topmost_dialog();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::ADD_APP)) && !self.viewer_mode {
+            self.open_add_app_dialog();
+        }
+        // Text fields have their own undo, and dialogs hold configs by index.
+        if nothing_focused && !self.viewer_mode && !self.config_dialog_open() {
+            if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::REDO)) {
+                self.undo_config_change(false);
+            } else if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::UNDO)) {
+                self.undo_config_change(true);
+            }
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::FOCUS_SEARCH)) {
+            ctx.memory_mut(|m| m.request_focus(egui::Id::new(SEARCH_FIELD_ID)));
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::OPEN_SETTINGS)) {
+            self.show_settings_dialog = true;
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&crate::accessibility::OPEN_BUILD_HISTORY)) {
+            self.show_build_history = true;
+        }
+    }
+
+    fn config_dialog_open(&self) -> bool {
+        self.show_add_app_dialog
+            || self.show_edit_dialog_for_idx.is_some()
+            || self.show_delete_confirm_for_idx.is_some()
+            || self.release_notes_dialog_for_idx.is_some()
+    }
+
+    /// Undoes the last add, edit or delete of a config, or redoes the last undone one.
+    fn undo_config_change(&mut self, undo: bool) {
+        let result = if undo {
+            self.undo_stack.undo(&mut self.app_configs, &mut self.projects)
+        } else {
+            self.undo_stack.redo(&mut self.app_configs, &mut self.projects)
+        };
+        let (change, applied) = match result {
+            None => return,
+            Some(Err(e)) => {
+                self.status_message = e;
+                return;
+            }
+            Some(Ok(done)) => done,
+        };
+        let source = if undo { "undo" } else { "redo" }.to_string();
+        let app_name = |id: &str, configs: &[AppConfig]| configs.iter().find(|c| c.id == id).map(|c| c.app_name.clone()).unwrap_or_default();
+        match applied {
+            Applied::Inserted(app_id) => {
+                let app_name = app_name(&app_id, &self.app_configs);
+                self.audit_log.record(AuditAction::ConfigAdded { app_id: app_id.clone(), app_name, source });
+                if let Some(idx) = self.app_configs.iter().position(|c| c.id == app_id) {
+                    self.refresh_bundle_info_from_input(idx);
+                }
+            }
+            Applied::Replaced(app_id) => {
+                let app_name = app_name(&app_id, &self.app_configs);
+                self.audit_log.record(AuditAction::ConfigEdited { app_id: app_id.clone(), app_name, source });
+                if let Some(idx) = self.app_configs.iter().position(|c| c.id == app_id) {
+                    self.refresh_bundle_info_from_input(idx);
+                }
+            }
+            Applied::Removed(app_id) => {
+                self.bundle_infos.remove(&app_id);
+                self.profiles.remove(&app_id);
+                let app_name = match &change {
+                    ConfigChange::Added(config) | ConfigChange::Deleted { config, .. } => config.app_name.clone(),
+                    ConfigChange::Edited { after, .. } => after.app_name.clone(),
+                };
+                self.audit_log.record(AuditAction::ConfigDeleted { app_id, app_name });
+            }
+        }
+        self.status_message = format!("{} {}.", if undo { "Undid" } else { "Redid" }, change.label());
+    }
+
+    /// Closes the dialog drawn on top, nested ones first.
+    fn close_topmost_dialog(&mut self) {
+        if self.show_compression_dialog {
+            self.show_compression_dialog = false;
+        } else if self.qr_view.is_some() {
+            self.qr_view = None;
+        } else if self.diagnostics.is_some() {
+            self.diagnostics = None;
+        } else if self.show_delete_confirm_for_idx.is_some() {
+            self.show_delete_confirm_for_idx = None;
+        } else if self.show_edit_dialog_for_idx.is_some() {
+            self.show_edit_dialog_for_idx = None;
+        } else if self.show_add_app_dialog {
+            self.show_add_app_dialog = false;
+        } else if self.release_notes_dialog_for_idx.is_some() {
+            self.release_notes_dialog_for_idx = None;
+        } else if self.pending_build.is_some() {
+            self.pending_build = None;
+            self.project_run = None;
+        } else if self.show_settings_dialog {
+            self.show_settings_dialog = false;
+        } else if self.show_batch_resign_dialog {
+            self.show_batch_resign_dialog = false;
+        } else if self.show_build_history {
+            self.show_build_history = false;
+        } else if self.show_projects {
+            self.show_projects = false;
+        } else if self.storage_cleanup_pending.is_some() {
+            self.storage_cleanup_pending = None;
+        } else if self.storage_ledger.is_some() {
+            self.storage_ledger = None;
+        } else if self.naming_audit.is_some() {
+            self.naming_audit = None;
+        } else if self.entitlements_view.is_some() {
+            self.entitlements_view = None;
+        } else if self.signature_report.is_some() {
+            self.signature_report = None;
+        } else if self.validation_report.is_some() {
+            self.validation_report = None;
+        } else if self.ipa_contents.is_some() {
+            self.ipa_contents = None;
+        } else if self.size_report.is_some() {
+            self.size_report = None;
+        } else if self.device_install_outcome.is_some() {
+            self.device_install_outcome = None;
+        }
+    }
+
+    fn open_add_app_dialog(&mut self) {
+        self.show_add_app_dialog = true;
+        self.add_app_name_input = format!("MyNewApp{}", self.app_configs.len() + 1);
+        self.add_app_output_name_input = format!("app{}.ipa", self.app_configs.len() + 1);
+        self.add_app_zip_path_input = None;
+        self.add_app_errors.clear();
+    }
+
+    /// The config the Add dialog's inputs describe.
+    fn new_app_config(&self) -> AppConfig {
+        AppConfig {
+            id: Uuid::new_v4().to_string(),
+            app_name: self.add_app_name_input.trim().to_string(),
+            input_zip_path: self.add_app_zip_path_input.clone().unwrap_or_default(),
+            output_ipa_name: self.add_app_output_name_input.trim().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the config the Add dialog describes, or shows its validation errors. Returns whether
+    /// it was added.
+    fn add_app_from_dialog(&mut self) -> bool {
+        let new_app = self.new_app_config();
+        if let Err(errors) = new_app.validate() {
+            self.add_app_errors = errors;
+            return false;
+        }
+        self.audit_log.record(AuditAction::ConfigAdded {
+            app_id: new_app.id.clone(),
+            app_name: new_app.app_name.clone(),
+            source: "dialog".to_string(),
+        });
+        self.undo_stack.push(ConfigChange::Added(new_app.clone()));
+        self.app_configs.push(new_app);
+        self.refresh_bundle_info_from_input(self.app_configs.len() - 1);
+        self.status_message = format!("Application '{}' added.", self.add_app_name_input);
+        self.record_metric(MetricEvent::AppAdded { app_name: self.add_app_name_input.clone() });
+        // Reset inputs
+        self.add_app_name_input = "MyNewApp".to_string();
+        self.add_app_zip_path_input = None;
+        self.add_app_output_name_input = "output.ipa".to_string();
+        true
+    }
+
+    fn open_folder_containing_file(&self, file_path: &Path) {
         if let Some(parent_dir) = file_path.parent() {
             let command_name = if cfg!(target_os = "windows") {
                 "explorer"
@@ -324,41 +1236,2403 @@ impl IpaBuilderApp {
         }
     }
 
-    fn record_metric(&mut self, event_type: MetricEvent) {
-        self.metrics_collector.record(event_type);
+    fn verify_signature(&mut self, ipa_path: &Path) {
+        let result = crate::codesign::verify_ipa_signature(ipa_path);
+        self.status_message = match &result {
+            Ok(report) if report.is_valid() => format!(
+                "Signature of {} looks valid ({}).",
+                ipa_path.display(),
+                report.main_binary().and_then(|b| b.authority.clone()).unwrap_or_default()
+            ),
+            Ok(_) => format!("Signature problems found in {}.", ipa_path.display()),
+            Err(e) => format!("Signature verification failed: {}", e),
+        };
+        self.signature_report = Some(result);
     }
 
-    fn render_main_ui(&mut self, ctx: &egui::Context) {
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                ui.visuals_mut().button_frame = false;
-                egui::widgets::global_dark_light_mode_switch(ui);
-                ui.separator();
-                ui.heading("IPA Builder Dashboard");
-            });
-            ui.horizontal_wrapped(|ui| {
-                ui.label(format!("Today's Generations: {}", self.metrics_collector.generations_today()));
-                ui.separator();
-                ui.label(format!("Total Generations: {}", self.metrics_collector.generations_all_time()));
-                ui.separator();
-                if let Some(avg_speed) = self.metrics_collector.avg_generation_speed_ms() {
-                    ui.label(format!("Avg. Speed: {:.2}s", avg_speed as f64 / 1000.0));
-                } else {
-                    ui.label("Avg. Speed: N/A");
+    fn render_signature_report_window(&mut self, ctx: &egui::Context) {
+        let report = match &self.signature_report {
+            Some(r) => r,
+            None => return,
+        };
+        let mut open = true;
+        let mut to_view = None;
+        egui::Window::new("Signature Verification")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| match report {
+                Err(e) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                }
+                Ok(report) => {
+                    ui.label(report.ipa_path.display().to_string());
+                    if !report.cryptographically_verified {
+                        ui.small("Signature blobs were parsed only; run on macOS with codesign for cryptographic verification.");
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        egui::Grid::new("signature_report_grid").striped(true).show(ui, |ui| {
+                            ui.strong("Binary");
+                            ui.strong("Archs");
+                            ui.strong("Identifier");
+                            ui.strong("Team");
+                            ui.strong("Authority");
+                            ui.strong("Status");
+                            ui.strong("Entitlements");
+                            ui.end_row();
+                            for binary in &report.binaries {
+                                ui.label(&binary.path);
+                                ui.label(binary.architectures.join(", "));
+                                ui.label(binary.identifier.as_deref().unwrap_or("—"));
+                                ui.label(binary.team_id.as_deref().unwrap_or("—"));
+                                ui.label(binary.authority.as_deref().unwrap_or("—"));
+                                match (&binary.problem, binary.valid) {
+                                    (Some(problem), _) => ui.colored_label(ui.visuals().error_fg_color, problem),
+                                    (None, Some(true)) => ui.label("✔ valid"),
+                                    (None, _) => ui.label("✔ signed"),
+                                };
+                                match &binary.entitlements_xml {
+                                    Some(xml) => {
+                                        if labeled(ui.button("🔑 View"), &format!("View the entitlements of {}", binary.path)).clicked() {
+                                            to_view = Some((binary.path.clone(), crate::codesign::entitlement_entries(xml.as_bytes())));
+                                        }
+                                    }
+                                    None => {
+                                        ui.label("—");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if let Some(seal) = &report.seal {
+                        ui.separator();
+                        let problems = seal.warnings();
+                        if problems.is_empty() {
+                            ui.label("✔ All files match the CodeResources seal");
+                        }
+                        for problem in problems {
+                            ui.colored_label(ui.visuals().error_fg_color, problem);
+                        }
+                    }
+                }
+            });
+        if to_view.is_some() {
+            self.entitlements_view = to_view;
+        }
+        if !open {
+            self.signature_report = None;
+        }
+    }
+
+    fn validate_ipa(&mut self, ipa_path: &Path) {
+        let result = crate::validator::validate_ipa(ipa_path);
+        self.status_message = match &result {
+            Ok(report) if report.findings.is_empty() => format!("{} passed all structure checks.", ipa_path.display()),
+            Ok(report) => format!(
+                "{}: {} error(s), {} warning(s).",
+                ipa_path.display(),
+                report.errors().count(),
+                report.warnings().count()
+            ),
+            Err(e) => format!("Validation failed: {}", e),
+        };
+        self.validation_report = Some((ipa_path.to_path_buf(), result));
+    }
+
+    fn render_validation_report_window(&mut self, ctx: &egui::Context) {
+        let Some((path, report)) = &self.validation_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("IPA Validation")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(path.display().to_string());
+                ui.separator();
+                match report {
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    Ok(report) if report.findings.is_empty() => {
+                        ui.label("✔ No problems found");
+                    }
+                    Ok(report) => {
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("validation_report_grid").striped(true).show(ui, |ui| {
+                                ui.strong("Severity");
+                                ui.strong("Path");
+                                ui.strong("Problem");
+                                ui.end_row();
+                                for finding in &report.findings {
+                                    match finding.severity {
+                                        Severity::Error => ui.colored_label(ui.visuals().error_fg_color, "✖ Error"),
+                                        Severity::Warning => ui.colored_label(ui.visuals().warn_fg_color, "⚠ Warning"),
+                                    };
+                                    ui.label(if finding.path.is_empty() { "—" } else { &finding.path });
+                                    ui.label(&finding.message);
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                }
+            });
+        if !open {
+            self.validation_report = None;
+        }
+    }
+
+    fn inspect_ipa(&mut self, ipa_path: &Path) {
+        let result = crate::ipa_contents::read_contents(ipa_path);
+        if let Err(e) = &result {
+            self.status_message = format!("Cannot inspect {}: {}", ipa_path.display(), e);
+        }
+        self.ipa_contents = Some((ipa_path.to_path_buf(), result));
+    }
+
+    /// The IPA of the app's latest successful build that is still on disk.
+    fn latest_ipa_for(&self, app_id: &str) -> Option<PathBuf> {
+        self.build_history
+            .records()
+            .iter()
+            .rev()
+            .filter(|r| r.success && r.app_id == app_id)
+            .filter_map(|r| r.output_path.as_ref().map(PathBuf::from))
+            .find(|p| p.is_file())
+    }
+
+    fn render_ipa_contents_window(&mut self, ctx: &egui::Context) {
+        let Some((path, contents)) = &self.ipa_contents else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("IPA Contents")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label(path.display().to_string());
+                match contents {
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    Ok(root) => {
+                        ui.label(format!(
+                            "{} files, {} uncompressed, {} compressed",
+                            root.file_count(),
+                            crate::size_analysis::format_size(root.size),
+                            crate::size_analysis::format_size(root.compressed_size)
+                        ));
+                        ui.separator();
+                        egui::ScrollArea::vertical().max_height(450.0).show(ui, |ui| {
+                            for child in &root.children {
+                                render_content_node(ui, child);
+                            }
+                        });
+                    }
+                }
+            });
+        if !open {
+            self.ipa_contents = None;
+        }
+    }
+
+    /// Opens the size report of the build at `index` in the history, next to the app's previous
+    /// successful build with a breakdown.
+    fn show_size_report(&mut self, index: usize) {
+        let records = self.build_history.records();
+        let Some(record) = records.get(index) else {
+            return;
+        };
+        let Some(breakdown) = record.size_breakdown.clone() else {
+            self.status_message = "This build has no size breakdown.".to_string();
+            return;
+        };
+        let label = |r: &BuildRecord| format!("{} build of {}", r.app_name, self.settings.time_zone.display(r.timestamp));
+        let previous = records[..index]
+            .iter()
+            .rev()
+            .filter(|r| r.success && r.app_id == record.app_id)
+            .find_map(|r| r.size_breakdown.clone().map(|b| (label(r), b)));
+        self.size_report = Some(SizeReportView { current: (label(record), breakdown), previous });
+    }
+
+    fn render_size_report_window(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.size_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Size Breakdown")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let (label, current) = &report.current;
+                let total = current.total();
+                ui.strong(label);
+                ui.label(format!(
+                    "{} uncompressed, {} in the IPA, {} files",
+                    crate::size_analysis::format_size(total.bytes),
+                    crate::size_analysis::format_size(total.compressed_bytes),
+                    total.files
+                ));
+                if let Some((previous_label, previous)) = &report.previous {
+                    let before = previous.total();
+                    ui.label(format!(
+                        "{} uncompressed compared with the {}",
+                        crate::size_analysis::format_size_change(before.bytes, total.bytes),
+                        previous_label
+                    ));
+                }
+                ui.separator();
+                let previous = report.previous.as_ref().map(|(_, b)| b);
+                let largest = SizeCategory::ALL
+                    .iter()
+                    .flat_map(|c| [Some(current.get(*c).bytes), previous.map(|p| p.get(*c).bytes)])
+                    .flatten()
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                egui::Grid::new("size_report_grid").striped(true).show(ui, |ui| {
+                    ui.strong("Category");
+                    ui.strong("");
+                    ui.strong("Uncompressed");
+                    ui.strong("In IPA");
+                    if previous.is_some() {
+                        ui.strong("Change");
+                    }
+                    ui.end_row();
+                    for category in SizeCategory::ALL {
+                        let size = current.get(category);
+                        let before = previous.map(|p| p.get(category).bytes);
+                        ui.label(category.label());
+                        render_size_bar(ui, size.bytes as f32 / largest as f32, before.map(|b| b as f32 / largest as f32));
+                        ui.label(crate::size_analysis::format_size(size.bytes));
+                        ui.label(crate::size_analysis::format_size(size.compressed_bytes));
+                        if let Some(before) = before {
+                            let change = crate::size_analysis::format_size_change(before, size.bytes);
+                            if size.bytes > before {
+                                ui.colored_label(ui.visuals().warn_fg_color, change);
+                            } else {
+                                ui.label(change);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                if previous.is_some() {
+                    ui.small("The outline marks the previous build.");
+                }
+            });
+        if !open {
+            self.size_report = None;
+        }
+    }
+
+    fn view_entitlements_file(&mut self, path: &Path) {
+        let entries = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e)).and_then(|bytes| crate::codesign::entitlement_entries(&bytes));
+        self.entitlements_view = Some((path.display().to_string(), entries));
+    }
+
+    fn render_entitlements_window(&mut self, ctx: &egui::Context) {
+        let Some((title, entries)) = &self.entitlements_view else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Entitlements")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(title);
+                ui.separator();
+                match entries {
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    Ok(entries) if entries.is_empty() => {
+                        ui.label("No entitlements.");
+                    }
+                    Ok(entries) => {
+                        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                            egui::Grid::new("entitlements_grid").num_columns(2).striped(true).show(ui, |ui| {
+                                for (key, value) in entries {
+                                    ui.monospace(key);
+                                    ui.label(value);
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                }
+            });
+        if !open {
+            self.entitlements_view = None;
+        }
+    }
+
+    fn run_naming_audit(&mut self) {
+        let Some(output_dir) = self.output_directory.clone() else { return };
+        let audit = crate::naming_audit::audit_output_dir(Path::new(&output_dir), &self.app_configs, self.build_history.records());
+        self.naming_audit = Some(audit.map(|entries| {
+            entries.into_iter().map(|entry| {
+                let selected = matches!(entry.status, AuditStatus::Rename { .. });
+                (entry, selected)
+            }).collect()
+        }));
+    }
+
+    /// Renames the IPAs selected in the Naming Audit window and points their build history
+    /// records at the new names.
+    fn rename_selected_ipas(&mut self) {
+        let Some(Ok(entries)) = &self.naming_audit else { return };
+        let renames = entries.iter().filter(|(_, selected)| *selected).filter_map(|(entry, _)| match &entry.status {
+            AuditStatus::Rename { to } => Some((entry.path.as_path(), to.as_str())),
+            _ => None,
+        });
+        let (done, error) = crate::naming_audit::rename_ipas(renames);
+        for (from, to) in &done {
+            if let Err(e) = self.build_history.rename_output(&from.display().to_string(), &to.display().to_string()) {
+                log::error!("Failed to update the build history after renaming {}: {}", from.display(), e);
+            }
+            if self.last_generated_ipa_path.as_ref() == Some(from) {
+                self.last_generated_ipa_path = Some(to.clone());
+            }
+        }
+        self.status_message = match error {
+            None => format!("Renamed {} IPA(s) to their current output names.", done.len()),
+            Some(e) => format!("Renamed {} IPA(s), then stopped: {}", done.len(), e),
+        };
+        self.run_naming_audit();
+    }
+
+    fn run_diagnostics(&mut self) {
+        self.diagnostics = Some(crate::diagnostics::run_checks(self.output_directory.as_deref().map(Path::new)));
+    }
+
+    fn render_diagnostics_window(&mut self, ctx: &egui::Context) {
+        let Some(checks) = &self.diagnostics else {
+            return;
+        };
+        let mut open = true;
+        let mut rerun = false;
+        egui::Window::new("Diagnostics")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let failed = checks.iter().filter(|c| c.status == crate::diagnostics::CheckStatus::Fail).count();
+                ui.horizontal(|ui| {
+                    if failed == 0 {
+                        ui.label("Everything IPA Builder needs is in place.");
+                    } else {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("{} check(s) failed.", failed));
+                    }
+                    if ui.button("🔄 Run again").clicked() {
+                        rerun = true;
+                    }
+                    if ui.button("📋 Copy report").on_hover_text("For pasting into a support request").clicked() {
+                        ui.output_mut(|o| o.copied_text = crate::diagnostics::report(checks));
+                    }
+                });
+                ui.separator();
+                egui::Grid::new("diagnostics_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    for check in checks {
+                        let color = match check.status {
+                            crate::diagnostics::CheckStatus::Pass => ui.visuals().text_color(),
+                            crate::diagnostics::CheckStatus::Warn => ui.visuals().warn_fg_color,
+                            crate::diagnostics::CheckStatus::Fail => ui.visuals().error_fg_color,
+                        };
+                        ui.colored_label(color, check.status.icon());
+                        ui.strong(check.name);
+                        ui.vertical(|ui| {
+                            ui.label(&check.detail);
+                            if let Some(hint) = &check.hint {
+                                ui.small(hint);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            });
+        if rerun {
+            self.run_diagnostics();
+        }
+        if !open {
+            self.diagnostics = None;
+        }
+    }
+
+    fn render_qr_window(&mut self, ctx: &egui::Context) {
+        let Some(view) = &mut self.qr_view else {
+            return;
+        };
+        let mut open = true;
+        let color = self.settings.branding.qr_rgb();
+        egui::Window::new(format!("QR Code: {}", view.title))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match view.texture(ctx, color) {
+                    Ok(texture) => {
+                        let image = egui::Image::new(texture).fit_to_exact_size(egui::vec2(320.0, 320.0));
+                        ui.add(image).on_hover_text("Point the iPhone's camera at the code");
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.small(&view.data);
+                    if labeled(ui.small_button("📋"), "Copy the link").on_hover_text("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = view.data.clone());
+                    }
+                });
+            });
+        if !open {
+            self.qr_view = None;
+        }
+    }
+
+    fn render_naming_audit_window(&mut self, ctx: &egui::Context) {
+        let Some(audit) = &mut self.naming_audit else {
+            return;
+        };
+        let mut open = true;
+        let mut rescan = false;
+        let mut rename = false;
+        egui::Window::new("Naming Audit")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("IPAs in {}", self.output_directory.as_deref().unwrap_or_default()));
+                let entries = match audit {
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                        return;
+                    }
+                    Ok(entries) => entries,
+                };
+                let selected = entries.iter().filter(|(_, selected)| *selected).count();
+                ui.horizontal(|ui| {
+                    let stragglers = entries.iter().filter(|(e, _)| matches!(e.status, AuditStatus::Rename { .. })).count();
+                    ui.label(format!("{} IPA(s), {} not named after their app's current output name", entries.len(), stragglers));
+                    if ui.button("⟳ Rescan").clicked() {
+                        rescan = true;
+                    }
+                    if !self.viewer_mode && ui.add_enabled(selected > 0, egui::Button::new(format!("🏷 Rename {} selected", selected))).clicked() {
+                        rename = true;
+                    }
+                });
+                if entries.is_empty() {
+                    ui.label("No IPAs found.");
+                    return;
+                }
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("naming_audit_grid").num_columns(4).striped(true).show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("File");
+                        ui.strong("App");
+                        ui.strong("Status");
+                        ui.end_row();
+                        for (entry, selected) in entries.iter_mut() {
+                            match &entry.status {
+                                AuditStatus::Rename { .. } if !self.viewer_mode => {
+                                    labeled(ui.checkbox(selected, ""), &format!("Rename {}", entry.file_name()));
+                                }
+                                _ => {
+                                    ui.label("");
+                                }
+                            }
+                            ui.label(entry.file_name());
+                            match (&entry.app_name, entry.matched_by) {
+                                (Some(name), Some(by)) => ui.label(name).on_hover_text(format!("Matched by {}", by.label())),
+                                _ => ui.weak("—"),
+                            };
+                            match &entry.status {
+                                AuditStatus::UpToDate => ui.label("✔ Up to date"),
+                                AuditStatus::Rename { to } => ui.colored_label(ui.visuals().warn_fg_color, format!("→ {}", to)),
+                                AuditStatus::Unresolved { reason } => ui.weak("Current name unknown").on_hover_text(reason),
+                                AuditStatus::Unmatched => ui.weak("No matching app").on_hover_text("Not built from any current app, or from several apps with the same name"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if rename {
+            self.rename_selected_ipas();
+        } else if rescan {
+            self.run_naming_audit();
+        }
+        if !open {
+            self.naming_audit = None;
+        }
+    }
+
+    fn render_storage_window(&mut self, ctx: &egui::Context) {
+        let Some(ledger) = &self.storage_ledger else {
+            return;
+        };
+        let mut open = true;
+        let mut refresh = false;
+        let mut to_clean_up: Option<Vec<Artifact>> = None;
+        let mut folder_to_open = None;
+        egui::Window::new("Artifact Storage")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let on_disk: u64 = ledger.iter().map(AppStorage::bytes_on_disk).sum();
+                let superseded: u64 = ledger.iter().map(AppStorage::superseded_bytes).sum();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} on disk, {} superseded by newer builds", crate::size_analysis::format_size(on_disk), crate::size_analysis::format_size(superseded)));
+                    if ui.button("⟳ Refresh").clicked() {
+                        refresh = true;
+                    }
+                });
+                if ledger.is_empty() {
+                    ui.label("No builds recorded yet.");
+                    return;
+                }
+                ui.add_enabled_ui(!self.viewer_mode && superseded > 0, |ui| {
+                    if ui.button("🗑 Clean up all superseded").on_hover_text("Delete every IPA except the newest of each app").clicked() {
+                        to_clean_up = Some(ledger.iter().flat_map(|app| app.superseded().cloned()).collect());
+                    }
+                });
+                egui::ScrollArea::vertical().max_height(450.0).show(ui, |ui| {
+                    for app in ledger {
+                        let existing = app.artifacts.iter().filter(|a| a.exists).count();
+                        let header = format!("{}: {} ({} of {} IPAs still on disk)", app.app_name, crate::size_analysis::format_size(app.bytes_on_disk()), existing, app.artifacts.len());
+                        egui::CollapsingHeader::new(header).id_source(&app.app_id).show(ui, |ui| {
+                            if app.superseded_bytes() > 0 && !self.viewer_mode {
+                                let label = format!("🗑 Clean up {} superseded ({})", app.superseded().count(), crate::size_analysis::format_size(app.superseded_bytes()));
+                                if ui.button(label).clicked() {
+                                    to_clean_up = Some(app.superseded().cloned().collect());
+                                }
+                            }
+                            egui::Grid::new(("storage_grid", &app.app_id)).num_columns(4).striped(true).show(ui, |ui| {
+                                for artifact in &app.artifacts {
+                                    ui.label(self.settings.time_zone.display(artifact.built_at));
+                                    let path = artifact.path.display().to_string();
+                                    if artifact.exists {
+                                        if ui.link(&path).on_hover_text("Open containing folder").clicked() {
+                                            folder_to_open = Some(artifact.path.clone());
+                                        }
+                                    } else {
+                                        ui.weak(format!("{} (deleted)", path));
+                                    }
+                                    ui.label(crate::size_analysis::format_size(artifact.size_bytes));
+                                    match &artifact.sha256 {
+                                        Some(sha) => ui.monospace(&sha[..sha.len().min(12)]).on_hover_text(sha),
+                                        None => ui.label(""),
+                                    };
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+        if let Some(path) = folder_to_open {
+            self.open_folder_containing_file(&path);
+        }
+        if let Some(artifacts) = to_clean_up {
+            self.storage_cleanup_pending = Some(artifacts);
+        }
+        if let Some(artifacts) = self.storage_cleanup_pending.clone() {
+            let bytes: u64 = artifacts.iter().map(|a| a.size_bytes).sum();
+            let mut decided = None;
+            egui::Window::new("Clean Up Superseded Builds")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(format!("Delete {} superseded IPA(s) and their signatures, freeing {}? This cannot be undone.", artifacts.len(), crate::size_analysis::format_size(bytes)));
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            decided = Some(true);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            decided = Some(false);
+                        }
+                    });
+                });
+            match decided {
+                Some(true) => {
+                    self.status_message = match crate::artifact_ledger::remove_artifacts(&artifacts) {
+                        Ok(cleanup) => cleanup.summary(),
+                        Err(e) => format!("Clean-up failed: {}", e),
+                    };
+                    self.storage_cleanup_pending = None;
+                    refresh = true;
+                }
+                Some(false) => self.storage_cleanup_pending = None,
+                None => {}
+            }
+        }
+        if refresh {
+            self.storage_ledger = Some(crate::artifact_ledger::build_ledger(self.build_history.records()));
+        }
+        if !open {
+            self.storage_ledger = None;
+            self.storage_cleanup_pending = None;
+        }
+    }
+
+    fn start_batch_resign(&mut self) {
+        let input_dir = match self.batch_resign_input_dir.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            Some(s) => PathBuf::from(s),
+            None => {
+                self.status_message = "Batch re-sign: please select an input folder.".to_string();
+                return;
+            }
+        };
+        let output_dir = match self.batch_resign_output_dir.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            Some(s) => PathBuf::from(s),
+            None => {
+                self.status_message = "Batch re-sign: please select an output folder.".to_string();
+                return;
+            }
+        };
+        let options = ResignOptions {
+            identity: self.batch_resign_identity.trim().to_string(),
+            provisioning_profile: self
+                .batch_resign_profile
+                .as_ref()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from),
+            entitlements: None,
+        };
+
+        match BatchResignRunner::start(&input_dir, &output_dir, options) {
+            Ok(runner) => {
+                self.batch_resign_files = runner.files().to_vec();
+                self.batch_resign_results = vec![None; self.batch_resign_files.len()];
+                self.batch_resign_current = None;
+                self.status_message = format!("Re-signing {} IPAs...", self.batch_resign_files.len());
+                self.batch_resign_runner = Some(runner);
+            }
+            Err(e) => self.status_message = format!("Batch re-sign error: {}", e),
+        }
+    }
+
+    /// The job shown in the window title / taskbar, if any.
+    fn current_job_progress(&self) -> Option<JobProgress> {
+        if let Some(runner) = &self.build_runner {
+            let label = format!("Building {}", runner.config().app_name);
+            return Some(match runner.progress() {
+                Some(p) => JobProgress { label: format!("{}: {}", label, p.phase.label()), done: p.done as usize, total: Some(p.total as usize) },
+                None => JobProgress { label, done: 0, total: None },
+            });
+        }
+        if self.batch_resign_runner.is_some() {
+            return Some(JobProgress {
+                label: "Re-signing".to_string(),
+                done: self.batch_resign_results.iter().filter(|r| r.is_some()).count(),
+                total: Some(self.batch_resign_files.len()),
+            });
+        }
+        if self.device_install_rx.is_some() {
+            return Some(JobProgress { label: "Installing".to_string(), done: 0, total: None });
+        }
+        None
+    }
+
+    fn poll_batch_resign(&mut self) {
+        let runner = match &self.batch_resign_runner {
+            Some(r) => r,
+            None => return,
+        };
+        let mut finished = None;
+        while let Some(msg) = runner.try_recv() {
+            match msg {
+                BatchResignMessage::Started { index } => self.batch_resign_current = Some(index),
+                BatchResignMessage::Finished { index, result } => {
+                    if let Some(slot) = self.batch_resign_results.get_mut(index) {
+                        *slot = Some(result);
+                    }
+                }
+                BatchResignMessage::Done { cancelled } => finished = Some(cancelled),
+            }
+        }
+        if let Some(cancelled) = finished {
+            self.batch_resign_runner = None;
+            self.batch_resign_current = None;
+            let ok = self.batch_resign_results.iter().filter(|r| matches!(r, Some(Ok(_)))).count();
+            let failed = self.batch_resign_results.iter().filter(|r| matches!(r, Some(Err(_)))).count();
+            self.status_message = format!(
+                "Batch re-sign {}: {} succeeded, {} failed.",
+                if cancelled { "cancelled" } else { "finished" },
+                ok,
+                failed
+            );
+        }
+    }
+
+    fn render_batch_resign_dialog(&mut self, ctx: &egui::Context) {
+        if !self.show_batch_resign_dialog {
+            return;
+        }
+        let running = self.batch_resign_runner.is_some();
+        if running {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        let mut open = true;
+        egui::Window::new("Batch Re-sign")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!running, |ui| {
+                    egui::Grid::new("batch_resign_form").num_columns(2).show(ui, |ui| {
+                        ui.label("Input folder:");
+                        ui.horizontal(|ui| {
+                            let mut input = self.batch_resign_input_dir.clone().unwrap_or_default();
+                            ui.text_edit_singleline(&mut input);
+                            if ui.button("Browse...").clicked() {
+                                if let Ok(Some(path)) = native_dialog::FileDialog::new().show_open_single_dir() {
+                                    input = path.to_string_lossy().to_string();
+                                }
+                            }
+                            self.batch_resign_input_dir = Some(input).filter(|s| !s.trim().is_empty());
+                        });
+                        ui.end_row();
+
+                        ui.label("Output folder:");
+                        ui.horizontal(|ui| {
+                            let mut output = self.batch_resign_output_dir.clone().unwrap_or_default();
+                            ui.text_edit_singleline(&mut output);
+                            if ui.button("Browse...").clicked() {
+                                if let Ok(Some(path)) = native_dialog::FileDialog::new().show_open_single_dir() {
+                                    output = path.to_string_lossy().to_string();
+                                }
+                            }
+                            self.batch_resign_output_dir = Some(output).filter(|s| !s.trim().is_empty());
+                        });
+                        ui.end_row();
+
+                        ui.label("Signing identity:");
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("batch_resign_identity")
+                                .width(320.0)
+                                .selected_text(self.batch_resign_identity.clone())
+                                .show_ui(ui, |ui| {
+                                    for identity in &self.batch_resign_identities {
+                                        ui.selectable_value(&mut self.batch_resign_identity, identity.clone(), identity);
+                                    }
+                                });
+                            if ui.button("🔄").on_hover_text("Load identities from the keychain").clicked() {
+                                match crate::resign::list_signing_identities() {
+                                    Ok(identities) => {
+                                        self.status_message = format!("Found {} signing identities.", identities.len());
+                                        self.batch_resign_identities = identities;
+                                    }
+                                    Err(e) => self.status_message = format!("Could not list signing identities: {}", e),
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("");
+                        ui.text_edit_singleline(&mut self.batch_resign_identity)
+                            .on_hover_text("Certificate name or SHA-1 hash passed to codesign");
+                        ui.end_row();
+
+                        ui.label("Provisioning profile:");
+                        ui.horizontal(|ui| {
+                            let mut profile = self.batch_resign_profile.clone().unwrap_or_default();
+                            ui.text_edit_singleline(&mut profile);
+                            if ui.button("Browse...").clicked() {
+                                if let Ok(Some(path)) = native_dialog::FileDialog::new()
+                                    .add_filter("Provisioning profiles", &["mobileprovision"])
+                                    .show_open_single_file()
+                                {
+                                    profile = path.to_string_lossy().to_string();
+                                }
+                            }
+                            self.batch_resign_profile = Some(profile).filter(|s| !s.trim().is_empty());
+                        });
+                        ui.end_row();
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    if running {
+                        if ui.button("Cancel").clicked() {
+                            if let Some(runner) = &self.batch_resign_runner {
+                                runner.cancel();
+                            }
+                        }
+                        crate::accessibility::busy_indicator(ui, self.settings.reduce_motion);
+                    } else if ui.button("Re-sign all").clicked() {
+                        self.start_batch_resign();
+                    }
+                });
+
+                if !self.batch_resign_files.is_empty() {
+                    let done = self.batch_resign_results.iter().filter(|r| r.is_some()).count();
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / self.batch_resign_files.len() as f32)
+                            .text(format!("{} / {}", done, self.batch_resign_files.len())),
+                    );
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        egui::Grid::new("batch_resign_results").striped(true).show(ui, |ui| {
+                            for (index, file) in self.batch_resign_files.iter().enumerate() {
+                                ui.label(file.file_name().unwrap_or_default().to_string_lossy());
+                                match self.batch_resign_results.get(index).and_then(|r| r.as_ref()) {
+                                    Some(Ok(path)) => {
+                                        ui.label("✔").on_hover_text(path.display().to_string());
+                                    }
+                                    Some(Err(e)) => {
+                                        ui.colored_label(ui.visuals().error_fg_color, format!("✖ {}", e));
+                                    }
+                                    None if self.batch_resign_current == Some(index) => {
+                                        ui.label("Signing…");
+                                    }
+                                    None => {
+                                        ui.weak("Pending");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+            });
+        if !open {
+            self.show_batch_resign_dialog = false;
+        }
+    }
+
+    fn render_release_notes_dialog(&mut self, ctx: &egui::Context) {
+        let idx = match self.release_notes_dialog_for_idx {
+            Some(idx) if idx < self.app_configs.len() => idx,
+            Some(_) => {
+                self.release_notes_dialog_for_idx = None;
+                return;
+            }
+            None => return,
+        };
+        let mut close_dialog = false;
+        let mut generate = false;
+        egui::Window::new(format!("Release Notes: {}", self.app_configs[idx].app_name))
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("These notes are embedded in the IPA's BuildInfo.json and kept in the build history.");
+                ui.add(egui::TextEdit::multiline(&mut self.release_notes_input).desired_rows(8).desired_width(400.0));
+                if let Some(changelog) = self.app_configs[idx].changelog_path.clone() {
+                    if ui.button("Reload from CHANGELOG").on_hover_text(&changelog).clicked() {
+                        match crate::release_notes::load_from_changelog(Path::new(&changelog)) {
+                            Ok(notes) => self.release_notes_input = notes,
+                            Err(e) => self.status_message = e,
+                        }
+                    }
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Generate").clicked() {
+                        generate = true;
+                        close_dialog = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close_dialog = true;
+                    }
+                });
+            });
+        if generate {
+            let notes = std::mem::take(&mut self.release_notes_input);
+            self.generate_for_config(idx, Some(notes));
+        }
+        if close_dialog {
+            self.release_notes_dialog_for_idx = None;
+        }
+    }
+
+    fn render_pending_build_dialog(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.pending_build.as_mut() else { return };
+        let Some(idx) = self.app_configs.iter().position(|c| c.id == pending.config_id) else {
+            self.pending_build = None;
+            return;
+        };
+        if pending.waiting_since.is_some() && pending.last_checked.elapsed() >= std::time::Duration::from_millis(500) {
+            pending.last_checked = std::time::Instant::now();
+            match crate::autocheck::input_readiness(Path::new(&self.app_configs[idx].input_zip_path)) {
+                Ok(()) => {
+                    let release_notes = self.pending_build.take().and_then(|p| p.release_notes);
+                    self.start_build(idx, release_notes);
+                    return;
+                }
+                Err(reason) => pending.reason = reason,
+            }
+        }
+        let reduce_motion = self.settings.reduce_motion;
+        let mut start = false;
+        let mut cancel = false;
+        egui::Window::new(format!("Input Not Ready: {}", self.app_configs[idx].app_name))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("⚠ {}.", pending.reason));
+                ui.label("Building now may fail with a ZIP error or package an incomplete app.");
+                ui.add_space(10.0);
+                match pending.waiting_since {
+                    Some(since) => {
+                        ui.horizontal(|ui| {
+                            crate::accessibility::busy_indicator(ui, reduce_motion);
+                            ui.label(format!("Waiting for the file to settle ({}s)...", since.elapsed().as_secs()));
+                        });
+                        ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+                    }
+                    None => {
+                        if ui.button("Wait and build").on_hover_text("Build as soon as the file stops changing and reads as a complete ZIP.").clicked() {
+                            pending.waiting_since = Some(std::time::Instant::now());
+                        }
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Build anyway").clicked() {
+                        start = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if start {
+            let release_notes = self.pending_build.take().and_then(|p| p.release_notes);
+            self.start_build(idx, release_notes);
+        } else if cancel {
+            self.pending_build = None;
+            self.project_run = None;
+        }
+    }
+
+    fn render_projects_window(&mut self, ctx: &egui::Context) {
+        if !self.show_projects {
+            return;
+        }
+        let mut open = true;
+        let mut build_project = None;
+        let mut delete_project = None;
+        let can_build = self.build_runner.is_none() && self.project_run.is_none();
+        let editable = !self.viewer_mode;
+        let configs = &self.app_configs;
+        let projects = &mut self.projects;
+        egui::Window::new("Projects")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.small("Group related apps, e.g. an app, its watch app and its beta variant, to build them together. Apps are built after the apps they depend on; a failed build stops the rest.");
+                if editable && ui.button("➕ New project").clicked() {
+                    projects.push(Project { name: format!("Project {}", projects.len() + 1), ..Default::default() });
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (project_idx, project) in projects.iter_mut().enumerate() {
+                        ui.push_id(project.id.clone(), |ui| {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                let label = ui.label("Name:");
+                                ui.add_enabled(editable, egui::TextEdit::singleline(&mut project.name)).labelled_by(label.id);
+                                let build = ui.add_enabled(can_build && !project.members.is_empty(), egui::Button::new("▶ Build project"));
+                                if labeled(build, &format!("Build project {}", project.name)).clicked() {
+                                    build_project = Some(project_idx);
+                                }
+                                if editable && labeled(ui.button("🗑️"), &format!("Delete project {}", project.name)).on_hover_text("Delete project").clicked() {
+                                    delete_project = Some(project_idx);
+                                }
+                            });
+                            match project.build_order(configs) {
+                                Ok(order) if order.is_empty() => { ui.small("No apps yet."); }
+                                Ok(order) => {
+                                    let names: Vec<&str> = order.iter().filter_map(|id| configs.iter().find(|c| &c.id == id)).map(|c| c.app_name.as_str()).collect();
+                                    ui.small(format!("Build order: {}", names.join(" → ")));
+                                }
+                                Err(e) => { ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", e)); }
+                            }
+                            ui.add_enabled_ui(editable, |ui| {
+                                ui.collapsing("Apps", |ui| {
+                                    for config in configs {
+                                        let mut member = project.contains(&config.id);
+                                        if ui.checkbox(&mut member, &config.app_name).changed() {
+                                            project.set_member(&config.id, member);
+                                        }
+                                    }
+                                });
+                                let member_ids: Vec<String> = project.members.iter().map(|m| m.config_id.clone()).collect();
+                                let name = |id: &str| configs.iter().find(|c| c.id == id).map(|c| c.app_name.clone()).unwrap_or_else(|| id.to_string());
+                                if member_ids.len() > 1 {
+                                    ui.collapsing("Dependencies", |ui| {
+                                        for member in &mut project.members {
+                                            ui.horizontal_wrapped(|ui| {
+                                                ui.strong(name(&member.config_id));
+                                                ui.label("after:");
+                                                for other in member_ids.iter().filter(|id| **id != member.config_id) {
+                                                    let mut depends = member.depends_on.contains(other);
+                                                    if ui.checkbox(&mut depends, name(other)).changed() {
+                                                        if depends {
+                                                            member.depends_on.push(other.clone());
+                                                        } else {
+                                                            member.depends_on.retain(|id| id != other);
+                                                        }
+                                                    }
+                                                }
+                                            });
+                                        }
+                                    });
+                                }
+                                ui.collapsing("Shared defaults", |ui| {
+                                    ui.small("Used by members that don't set their own.");
+                                    egui::Grid::new("project_defaults_grid").num_columns(2).show(ui, |ui| {
+                                        let label = ui.label("Version (CFBundleShortVersionString):");
+                                        ui.add(egui::TextEdit::singleline(&mut project.defaults.short_version).hint_text("e.g. 2.1.0")).labelled_by(label.id);
+                                        ui.end_row();
+                                        let label = ui.label("CHANGELOG path:");
+                                        ui.text_edit_singleline(&mut project.defaults.changelog_path).labelled_by(label.id);
+                                        ui.end_row();
+                                    });
+                                });
+                            });
+                        });
+                    }
+                });
+            });
+        if let Some(project_idx) = delete_project {
+            self.projects.remove(project_idx);
+        }
+        if let Some(project_idx) = build_project {
+            self.start_project_build(project_idx);
+        }
+        if !open {
+            self.show_projects = false;
+        }
+    }
+
+    fn render_build_history_window(&mut self, ctx: &egui::Context) {
+        if !self.show_build_history {
+            return;
+        }
+        let mut open = true;
+        let mut folder_to_open = None;
+        let mut size_report_to_show = None;
+        let mut export_manifest_clicked = false;
+        egui::Window::new("Build History")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                if self.build_history.records().is_empty() {
+                    ui.label("No builds recorded yet.");
+                    return;
+                }
+                let zone = &self.settings.time_zone;
+                let today = zone.date(Utc::now());
+                if let Some(app_id) = self.history_app_id.clone() {
+                    ui.horizontal(|ui| {
+                        let name = self.app_configs.iter().find(|c| c.id == app_id).map(|c| c.app_name.as_str()).unwrap_or("a deleted app");
+                        ui.strong(format!("Builds of {}", name));
+                        if ui.button("Show all apps").clicked() {
+                            self.history_app_id = None;
+                        }
+                    });
+                }
+                render_date_range_picker(ui, &mut self.history_range, &mut self.history_range_inputs, today);
+                let in_range: Vec<(usize, &BuildRecord)> = self
+                    .build_history
+                    .records()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, r)| self.history_app_id.as_ref().is_none_or(|id| &r.app_id == id))
+                    .filter(|(_, r)| self.history_range.contains(zone.date(r.timestamp), today))
+                    .collect();
+                let key = HistoryChartKey {
+                    range: self.history_range,
+                    app_id: self.history_app_id.clone(),
+                    zone: zone.clone(),
+                    today,
+                    records: self.build_history.records().len(),
+                };
+                if self.history_chart.as_ref().is_none_or(|chart| chart.key != key) {
+                    let days = self.build_history.daily_totals(key.app_id.as_deref(), key.range, zone, today).unwrap_or_else(|e| {
+                        log::error!("Failed to query the build history: {}", e);
+                        Vec::new()
+                    });
+                    self.history_chart = Some(HistoryChart { key, days });
+                }
+                if let Some(chart) = &self.history_chart {
+                    render_daily_chart(ui, &chart.days);
+                }
+                let (total, per_app) = crate::build_history::stats(in_range.iter().map(|(_, r)| *r));
+                match (total.success_rate(), total.avg_duration_ms()) {
+                    (Some(rate), Some(avg)) => {
+                        ui.label(format!(
+                            "{} build(s), {:.0}% succeeded, {:.1}s on average, {} of IPAs",
+                            total.builds,
+                            rate,
+                            avg as f64 / 1000.0,
+                            crate::size_analysis::format_size(total.total_size_bytes)
+                        ));
+                    }
+                    _ => {
+                        ui.label("No builds in this range.");
+                    }
+                }
+                let per_channel = crate::build_history::stats_by_channel(in_range.iter().map(|(_, r)| *r));
+                if !per_channel.is_empty() {
+                    ui.collapsing("Per channel", |ui| {
+                        egui::Grid::new("history_channel_stats_grid").num_columns(5).striped(true).show(ui, |ui| {
+                            for (channel, stats) in &per_channel {
+                                ui.label(channel);
+                                ui.label(format!("{} build(s)", stats.builds));
+                                ui.label(format!("{:.0}% succeeded", stats.success_rate().unwrap_or(0.0)));
+                                ui.label(format!("{:.1}s avg", stats.avg_duration_ms().unwrap_or(0) as f64 / 1000.0));
+                                ui.label(crate::size_analysis::format_size(stats.total_size_bytes));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                if per_app.len() > 1 {
+                    ui.collapsing("Per app", |ui| {
+                        egui::Grid::new("history_stats_grid").num_columns(4).striped(true).show(ui, |ui| {
+                            for (app_name, stats) in &per_app {
+                                ui.label(app_name);
+                                ui.label(format!("{} build(s)", stats.builds));
+                                ui.label(format!("{} failed", stats.failed));
+                                ui.label(format!("{:.1}s avg", stats.avg_duration_ms().unwrap_or(0) as f64 / 1000.0));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} build(s) selected", self.release_selection.len()));
+                    let manifest = ui.add_enabled(!self.release_selection.is_empty(), egui::Button::new("📦 Release manifest…"))
+                        .on_hover_text("List the selected IPAs with their version, SHA-256, size and date in JSON or CSV, for handing off to distribution");
+                    export_manifest_clicked = manifest.clicked();
+                    if self.settings.artifact_signing.is_enabled() {
+                        ui.checkbox(&mut self.sign_release_manifest, format!("Sign with {}", self.settings.artifact_signing.tool.label()));
+                    }
+                    if !self.release_selection.is_empty() && ui.button("Clear").clicked() {
+                        self.release_selection.clear();
+                    }
+                });
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for &(i, record) in in_range.iter().rev().take(200) {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                let mut selected = self.release_selection.contains(&i);
+                                let selectable = record.success && record.output_path.is_some();
+                                if ui.add_enabled(selectable, egui::Checkbox::without_text(&mut selected)).on_hover_text("Include in the release manifest").changed() {
+                                    if selected {
+                                        self.release_selection.insert(i);
+                                    } else {
+                                        self.release_selection.remove(&i);
+                                    }
+                                }
+                                ui.label(if record.success { "✔" } else { "✖" });
+                                ui.label(self.settings.time_zone.display(record.timestamp));
+                                ui.strong(&record.app_name);
+                                if let Some(channel) = &record.channel {
+                                    ui.small(format!("[{}]", channel)).on_hover_text("Channel");
+                                }
+                                ui.label(format!("{:.1}s", record.duration_ms as f64 / 1000.0));
+                                if let Some(size) = record.size_bytes {
+                                    ui.label(crate::size_analysis::format_size(size));
+                                }
+                                if record.size_breakdown.is_some() && labeled(ui.button("📊"), "Size breakdown").on_hover_text("Size breakdown").clicked() {
+                                    size_report_to_show = Some(i);
+                                }
+                                if let Some(path) = &record.output_path {
+                                    let hover = match &record.sha256 {
+                                        Some(hash) => format!("Click to open containing folder\nSHA-256: {}", hash),
+                                        None => "Click to open containing folder".to_string(),
+                                    };
+                                    if ui.link(path).on_hover_text(hover).clicked() {
+                                        folder_to_open = Some(PathBuf::from(path));
+                                    }
+                                }
+                                if let Some(error) = &record.error {
+                                    ui.colored_label(ui.visuals().error_fg_color, error);
+                                }
+                                if !record.warnings.is_empty() {
+                                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", record.warnings.len()))
+                                        .on_hover_text(record.warnings.join("\n"));
+                                }
+                                if let Some(expires_at) = record.profile_expires_at {
+                                    let exact = format!("Provisioning profile expires {}", zone.display_with_zone(expires_at));
+                                    match crate::provisioning::expiry_warning(expires_at, Utc::now()) {
+                                        Some(warning) => ui.colored_label(ui.visuals().warn_fg_color, format!("⏳ {}", warning)).on_hover_text(exact),
+                                        None => ui.label("🔏").on_hover_text(exact),
+                                    };
+                                }
+                            });
+                            if let Some(notes) = &record.release_notes {
+                                ui.collapsing("Release notes", |ui| {
+                                    ui.label(notes);
+                                });
+                            }
+                            ui.separator();
+                        });
+                    }
+                });
+            });
+        if let Some(index) = size_report_to_show {
+            self.show_size_report(index);
+        }
+        if let Some(path) = folder_to_open {
+            self.open_folder_containing_file(&path);
+        }
+        if export_manifest_clicked {
+            self.export_release_manifest();
+        }
+        if !open {
+            self.show_build_history = false;
+        }
+    }
+
+    fn export_release_manifest(&mut self) {
+        let records: Vec<&BuildRecord> = self.release_selection.iter().filter_map(|&i| self.build_history.records().get(i)).collect();
+        let manifest = match crate::release_manifest::ReleaseManifest::from_records(&records, Utc::now()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                self.status_message = format!("Release manifest not written: {}", e);
+                return;
+            }
+        };
+        let file_name = format!("release_manifest_{}.json", chrono::Local::now().format("%Y%m%d"));
+        let dest = match native_dialog::FileDialog::new()
+            .add_filter("JSON files", &["json"])
+            .add_filter("CSV files", &["csv"])
+            .set_filename(&file_name)
+            .show_save_single_file()
+        {
+            Ok(Some(dest)) => dest,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Error opening save dialog: {:?}", e);
+                self.status_message = format!("Error opening save dialog: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = manifest.write(&dest, &self.settings.time_zone) {
+            self.status_message = format!("Failed to write the release manifest: {}", e);
+            return;
+        }
+        self.status_message = format!("Release manifest of {} IPA(s) written to {}.", manifest.artifacts.len(), dest.display());
+        if self.sign_release_manifest && self.settings.artifact_signing.is_enabled() {
+            match self.settings.artifact_signing.sign(&dest, &format!("Release manifest of {} IPA(s)", manifest.artifacts.len())) {
+                Ok(Some(signature)) => self.status_message.push_str(&format!(" Signed as {}.", signature.display())),
+                Ok(None) => {}
+                Err(e) => self.status_message.push_str(&format!(" ⚠ Signing failed: {}", e)),
+            }
+        }
+    }
+
+    fn export_install_page(&mut self, ipa_path: &Path) {
+        if self.settings.install_page_base_url.trim().is_empty() {
+            self.status_message = "Set the install page base URL in Settings first.".to_string();
+            self.show_settings_dialog = true;
+            return;
+        }
+        let release_notes = match crate::ipa_logic::read_build_info(ipa_path) {
+            Ok(info) => info.and_then(|i| i.release_notes),
+            Err(e) => {
+                log::warn!("Could not read build info from {}: {}", ipa_path.display(), e);
+                None
+            }
+        };
+        match crate::install_page::export_install_page(ipa_path, &self.settings.install_page_base_url, release_notes.as_deref(), &self.settings.branding) {
+            Ok(bundle_dir) => {
+                self.status_message = format!("Install page exported to {}. Upload the folder to {}.", bundle_dir.display(), self.settings.install_page_base_url.trim());
+                self.open_folder_containing_file(&bundle_dir.join("index.html"));
+            }
+            Err(e) => self.status_message = format!("Install page export failed: {}", e),
+        }
+    }
+
+    fn poll_device_install(&mut self) {
+        let outcome = match &self.device_install_rx {
+            Some(rx) => match rx.try_recv() {
+                Ok(outcome) => outcome,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.device_install_rx = None;
+                    return;
+                }
+            },
+            None => return,
+        };
+        self.device_install_rx = None;
+        self.status_message = if outcome.success {
+            format!("Installed {}.", outcome.ipa_path.display())
+        } else {
+            format!(
+                "Install failed: {}",
+                outcome.diagnosis.as_deref().unwrap_or("see the installer log for details")
+            )
+        };
+        self.device_install_outcome = Some(outcome);
+    }
+
+    /// Lists the connected devices again every few seconds while the Devices panel is open.
+    fn poll_devices(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.devices_rx {
+            match rx.try_recv() {
+                Ok(devices) => {
+                    self.devices = Some(devices);
+                    self.devices_rx = None;
+                    self.devices_listed_at = Some(std::time::Instant::now());
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.devices_rx = None,
+            }
+        }
+        if !self.show_devices_panel {
+            return;
+        }
+        let due = self.devices_listed_at.is_none_or(|at| at.elapsed() >= DEVICE_REFRESH_INTERVAL);
+        if due && self.devices_rx.is_none() {
+            self.devices_rx = Some(crate::device_install::spawn_list_devices());
+        }
+        ctx.request_repaint_after(std::time::Duration::from_millis(500));
+    }
+
+    fn render_devices_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_devices_panel {
+            return;
+        }
+        egui::SidePanel::right("devices_panel").resizable(true).default_width(240.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("📱 Devices");
+                if self.devices_rx.is_some() {
+                    crate::accessibility::busy_indicator(ui, self.settings.reduce_motion);
+                } else if labeled(ui.small_button("🔄"), "Refresh the device list").on_hover_text("Refresh").clicked() {
+                    self.devices_listed_at = None;
+                }
+            });
+            ui.separator();
+            let devices = match &self.devices {
+                None => {
+                    ui.label("Looking for devices…");
+                    return;
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                    return;
+                }
+                Some(Ok(devices)) => devices.clone(),
+            };
+            if devices.is_empty() {
+                ui.label("No device connected. Connect one over USB, unlock it and tap \"Trust This Computer\".");
+                return;
+            }
+            let ipa = self.last_generated_ipa_path.clone().filter(|_| !self.last_generated_ipa_missing && !self.last_build_is_simulator);
+            for device in devices {
+                ui.group(|ui| {
+                    ui.strong(device.display_name()).on_hover_text(format!("UDID: {}", device.udid));
+                    if !device.product_type.is_empty() {
+                        ui.small(format!("{} · iOS {}", device.product_type, device.ios_version));
+                    }
+                    let installing = self.device_install_rx.is_some();
+                    let install = ui.add_enabled(ipa.is_some() && !installing, egui::Button::new("Install on device"));
+                    let install = match &ipa {
+                        Some(path) => install.on_hover_text(format!("Install {}", path.file_name().unwrap_or_default().to_string_lossy())),
+                        None => install.on_disabled_hover_text("Build an app for devices first"),
+                    };
+                    if let (true, Some(path)) = (install.clicked(), &ipa) {
+                        self.status_message = format!("Installing {} on {}...", path.display(), device.display_name());
+                        self.device_install_rx = Some(crate::device_install::spawn_install(path.clone(), Some(device.udid.clone())));
+                    }
+                });
+            }
+        });
+    }
+
+    fn start_app_store_connect_upload(&mut self, ipa_path: PathBuf, settings: AppStoreConnect) {
+        if self.asc_upload.as_ref().is_some_and(|u| u.rx.is_some()) {
+            self.status_message = "An App Store Connect upload is already running.".to_string();
+            return;
+        }
+        let rx = crate::app_store_connect::spawn_upload(ipa_path.clone(), settings, self.settings.network.clone());
+        self.asc_upload = Some(AscUpload { ipa_path, rx: Some(rx), fraction: None, last_line: String::new(), result: None });
+    }
+
+    fn poll_app_store_connect(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.asc_check_rx {
+            match rx.try_recv() {
+                Ok(result) => {
+                    self.asc_check_result = Some(result);
+                    self.asc_check_rx = None;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(250)),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.asc_check_rx = None,
+            }
+        }
+        let Some(upload) = &mut self.asc_upload else { return };
+        let Some(rx) = &upload.rx else { return };
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        let mut finished = None;
+        for message in rx.try_iter() {
+            match message {
+                UploadMessage::Progress { fraction, line } => {
+                    upload.fraction = fraction.or(upload.fraction);
+                    upload.last_line = line;
+                }
+                UploadMessage::Finished(result) => finished = Some(result),
+            }
+        }
+        let Some(result) = finished else { return };
+        let file_name = upload.ipa_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        self.status_message = match &result {
+            Ok(()) => format!("{} uploaded to App Store Connect; it shows in TestFlight once Apple has processed it.", file_name),
+            Err(e) => format!("Upload of {} to App Store Connect failed: {}", file_name, e.lines().next().unwrap_or_default()),
+        };
+        upload.rx = None;
+        upload.result = Some(result);
+    }
+
+    fn render_app_store_connect_upload(&mut self, ui: &mut egui::Ui) {
+        let Some(upload) = &self.asc_upload else { return };
+        let mut dismiss = false;
+        ui.horizontal(|ui| {
+            ui.label("App Store Connect:");
+            match &upload.result {
+                None => {
+                    let bar = match upload.fraction {
+                        Some(fraction) => egui::ProgressBar::new(fraction).show_percentage(),
+                        None => egui::ProgressBar::new(0.0).text("Starting Transporter…").animate(!self.settings.reduce_motion),
+                    };
+                    ui.add(bar.desired_width(200.0)).on_hover_text(&upload.last_line);
+                }
+                Some(Ok(())) => {
+                    ui.label("✔ Uploaded; processing on Apple's side before it shows in TestFlight.");
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, "✖ Upload failed").on_hover_text(e);
+                    if labeled(ui.small_button("📋"), "Copy the upload error").on_hover_text("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = e.clone());
+                    }
+                }
+            }
+            if upload.result.is_some() && labeled(ui.small_button("✖"), "Dismiss the upload result").clicked() {
+                dismiss = true;
+            }
+        });
+        if dismiss {
+            self.asc_upload = None;
+        }
+    }
+
+    fn start_share_upload(&mut self, ipa_path: PathBuf) {
+        if self.share_upload.as_ref().is_some_and(|u| u.rx.is_some()) {
+            self.status_message = "A share upload is already running.".to_string();
+            return;
+        }
+        let share = self.settings.share.clone();
+        let rx = crate::share::spawn_share(ipa_path.clone(), share.clone(), self.settings.network.clone(), self.settings.upload_bytes_per_sec());
+        self.share_upload = Some(ShareUpload { ipa_path, service: share.service, rx: Some(rx), fraction: None, last_line: String::new(), result: None });
+    }
+
+    fn poll_share_upload(&mut self, ctx: &egui::Context) {
+        let Some(upload) = &mut self.share_upload else { return };
+        let Some(rx) = &upload.rx else { return };
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        let mut finished = None;
+        for message in rx.try_iter() {
+            match message {
+                ShareMessage::Progress { fraction, line } => {
+                    upload.fraction = fraction.or(upload.fraction);
+                    upload.last_line = line;
+                }
+                ShareMessage::Finished(result) => finished = Some(result),
+            }
+        }
+        let Some(result) = finished else { return };
+        let file_name = upload.ipa_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let service = upload.service.label();
+        self.status_message = match &result {
+            Ok(link) => format!("{} shared on {}: {}", file_name, service, link),
+            Err(e) => format!("Upload of {} to {} failed: {}", file_name, service, e.lines().next().unwrap_or_default()),
+        };
+        upload.rx = None;
+        upload.result = Some(result);
+    }
+
+    /// Sends `event` through the channels `config`'s notification preferences pick for it. Webhooks
+    /// and emails are sent in the background.
+    fn notify_build(&mut self, config: &AppConfig, event: BuildEvent) {
+        let channels = config.notifications.channels_for(&event);
+        let title = crate::notifications::title(&event);
+        if channels.toast {
+            let kind = match (event.success, event.size_budget_bytes) {
+                (false, _) => ToastKind::Error,
+                (true, Some(_)) => ToastKind::Warning,
+                (true, None) => ToastKind::Success,
+            };
+            self.toasts.push(kind, title.clone(), event.text.clone());
+        }
+        if channels.desktop {
+            let body = crate::notifications::body(&event);
+            std::thread::spawn(move || {
+                if let Err(e) = crate::notifications::show_desktop_notification(&title, &body) {
+                    log::warn!("{}", e);
+                }
+            });
+        }
+        if channels.email {
+            let (email, recipients, event) = (self.settings.email.clone(), config.notifications.email_recipients.clone(), event.clone());
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let failures = crate::notifications::send_email(&email, &recipients, &event).err().map(|e| format!("Email: {}", e));
+                let _ = tx.send(failures.into_iter().collect());
+            });
+            self.notification_deliveries.push(rx);
+        }
+        if channels.webhook {
+            let webhooks: Vec<Webhook> = self.settings.webhooks.iter().chain(&config.webhooks).cloned().collect();
+            if webhooks.iter().any(|w| w.wants(&event)) {
+                self.notification_deliveries.push(crate::webhook::spawn_deliveries(webhooks, event, self.settings.network.clone()));
+            }
+        }
+    }
+
+    fn poll_notifications(&mut self, ctx: &egui::Context) {
+        if self.notification_deliveries.is_empty() {
+            return;
+        }
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        let mut failures = Vec::new();
+        self.notification_deliveries.retain(|rx| match rx.try_recv() {
+            Ok(failed) => {
+                failures.extend(failed);
+                false
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => true,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => false,
+        });
+        if let Some(first) = failures.first() {
+            let more = if failures.len() > 1 { format!(" (and {} more)", failures.len() - 1) } else { String::new() };
+            self.status_message.push_str(&format!(" ⚠ Notification failed: {}{}.", first, more));
+        }
+    }
+
+    fn render_share_upload(&mut self, ui: &mut egui::Ui) {
+        let Some(upload) = &self.share_upload else { return };
+        let mut dismiss = false;
+        let mut qr = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", upload.service.label()));
+            match &upload.result {
+                None => {
+                    let bar = match upload.fraction {
+                        Some(fraction) => egui::ProgressBar::new(fraction).show_percentage(),
+                        None => egui::ProgressBar::new(0.0).text("Starting upload…").animate(!self.settings.reduce_motion),
+                    };
+                    ui.add(bar.desired_width(200.0)).on_hover_text(&upload.last_line);
+                }
+                Some(Ok(link)) => {
+                    ui.hyperlink(link);
+                    if labeled(ui.small_button("📋"), "Copy the share link").on_hover_text("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = link.clone());
+                        self.status_message = "Share link copied to the clipboard.".to_string();
+                    }
+                    if labeled(ui.small_button("QR"), "Show the share link as a QR code").on_hover_text("QR code").clicked() {
+                        let title = upload.ipa_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                        qr = Some(QrView::new(title, link.clone()));
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, "✖ Upload failed").on_hover_text(e);
+                    if labeled(ui.small_button("📋"), "Copy the upload error").on_hover_text("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = e.clone());
+                    }
+                }
+            }
+            if upload.result.is_some() && labeled(ui.small_button("✖"), "Dismiss the share result").clicked() {
+                dismiss = true;
+            }
+        });
+        if qr.is_some() {
+            self.qr_view = qr;
+        }
+        if dismiss {
+            self.share_upload = None;
+        }
+    }
+
+    fn render_device_install_window(&mut self, ctx: &egui::Context) {
+        if self.device_install_rx.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        let outcome = match &self.device_install_outcome {
+            Some(o) => o,
+            None => return,
+        };
+        let mut open = true;
+        egui::Window::new("Install Result")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(outcome.ipa_path.display().to_string());
+                if outcome.success {
+                    ui.label("✔ Installed successfully.");
+                } else {
+                    ui.colored_label(ui.visuals().error_fg_color, "✖ Installation failed.");
+                    if let Some(diagnosis) = &outcome.diagnosis {
+                        ui.strong(diagnosis);
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Installer log:");
+                    if ui.small_button("📋 Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = outcome.log.clone());
+                    }
+                });
+                egui::ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+                    ui.monospace(&outcome.log);
+                });
+            });
+        if !open {
+            self.device_install_outcome = None;
+        }
+    }
+
+    fn export_audit_log(&mut self) {
+        match native_dialog::FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .set_filename("ipa_builder_audit.csv")
+            .show_save_single_file()
+        {
+            Ok(Some(dest)) => match self.audit_log.export_csv(&dest, &self.settings.time_zone) {
+                Ok(count) => self.status_message = format!("Exported {} audit entries to {}.", count, dest.display()),
+                Err(e) => self.status_message = format!("Failed to export audit log: {}", e),
+            },
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("Error opening save dialog: {:?}", e);
+                self.status_message = format!("Error opening save dialog: {:?}", e);
+            }
+        }
+    }
+
+    /// Table text for `time`, relative when enabled in the settings, and the exact time for
+    /// its tooltip.
+    fn timestamp_text(&self, time: chrono::DateTime<Utc>) -> (String, String) {
+        let exact = self.settings.time_zone.display_with_zone(time);
+        if self.settings.relative_times {
+            (crate::time_display::relative(time, Utc::now()), exact)
+        } else {
+            (self.settings.time_zone.display(time), exact)
+        }
+    }
+
+    fn export_table(&mut self, rows: &[DashboardRow]) {
+        let file_name = format!("ipa_builder_apps_{}.csv", chrono::Local::now().format("%Y%m%d"));
+        match native_dialog::FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("JSON files", &["json"])
+            .set_filename(&file_name)
+            .show_save_single_file()
+        {
+            Ok(Some(dest)) => match crate::dashboard::export_rows(rows, &dest, &self.settings.time_zone) {
+                Ok(()) => self.status_message = format!("Exported {} rows to {}.", rows.len(), dest.display()),
+                Err(e) => self.status_message = format!("Failed to export table: {}", e),
+            },
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("Error opening save dialog: {:?}", e);
+                self.status_message = format!("Error opening save dialog: {:?}", e);
+            }
+        }
+    }
+
+    fn export_everything(&mut self) {
+        let (config_dir, data_dir) = match (get_config_dir_path(), get_data_dir_path()) {
+            (Some(c), Some(d)) => (c, d),
+            _ => {
+                self.status_message = "Could not determine config or data directory.".to_string();
+                return;
+            }
+        };
+        let file_name = format!("ipa_builder_export_{}.zip", chrono::Local::now().format("%Y%m%d"));
+        match native_dialog::FileDialog::new()
+            .add_filter("ZIP archives", &["zip"])
+            .set_filename(&file_name)
+            .show_save_single_file()
+        {
+            Ok(Some(dest)) => {
+                let result = serde_json::to_string_pretty(self)
+                    .map_err(|e| format!("Failed to serialize app state: {}", e))
+                    .and_then(|state| crate::migration::export_archive(&dest, &state, &config_dir, &data_dir));
+                self.status_message = match result {
+                    Ok(manifest) => format!("Exported {} files to {}.", manifest.entries.len(), dest.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("Error opening save dialog: {:?}", e);
+                self.status_message = format!("Error opening save dialog: {:?}", e);
+            }
+        }
+    }
+
+    /// Restores an "Export everything" archive, replacing the current apps, settings and history.
+    fn import_everything(&mut self) {
+        if self.build_runner.is_some() {
+            self.status_message = "Wait for the running build to finish before importing.".to_string();
+            return;
+        }
+        let (config_dir, data_dir) = match (get_config_dir_path(), get_data_dir_path()) {
+            (Some(c), Some(d)) => (c, d),
+            _ => {
+                self.status_message = "Could not determine config or data directory.".to_string();
+                return;
+            }
+        };
+        let archive = match native_dialog::FileDialog::new().add_filter("ZIP archives", &["zip"]).show_open_single_file() {
+            Ok(Some(path)) => path,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Error opening file dialog: {:?}", e);
+                self.status_message = format!("Error opening file dialog: {:?}", e);
+                return;
+            }
+        };
+        let result = crate::migration::import_archive(&archive, &config_dir, &data_dir).and_then(|manifest| {
+            let state_path = config_dir.join(crate::migration::APP_STATE_FILE_NAME);
+            let state = std::fs::read_to_string(&state_path).map_err(|e| format!("Failed to read {}: {}", state_path.display(), e))?;
+            let imported: IpaBuilderApp = serde_json::from_str(&state).map_err(|e| format!("Failed to load imported app state: {}", e))?;
+            Ok((manifest, imported))
+        });
+        match result {
+            Ok((manifest, mut imported)) => {
+                if let Some(mut runner) = self.autocheck_runner.take() {
+                    runner.stop();
+                }
+                imported.init_runtime_state();
+                imported.status_message = format!(
+                    "Imported {} apps from {} (exported {} on {}). Replaced files were kept as .bak.",
+                    imported.app_configs.len(),
+                    archive.display(),
+                    imported.settings.time_zone.display(manifest.exported_at),
+                    manifest.host
+                );
+                *self = imported;
+            }
+            Err(e) => self.status_message = format!("Import failed: {}", e),
+        }
+    }
+
+    fn record_metric(&mut self, event_type: MetricEvent) {
+        self.metrics_collector.record(event_type);
+    }
+
+    /// Starts building the config at `original_idx` on a worker thread, unless its input ZIP
+    /// looks like it is still being written; the build then waits in [`PendingBuild`] for the
+    /// user to decide. Without explicit `release_notes`, the latest section of the config's
+    /// CHANGELOG (if any) is used.
+    fn generate_for_config(&mut self, original_idx: usize, release_notes: Option<String>) {
+        if self.build_runner.is_some() || self.pending_build.is_some() {
+            self.queue_build(original_idx, release_notes, Priority::Normal);
+            return;
+        }
+        let config = &self.app_configs[original_idx];
+        if let Err(reason) = crate::autocheck::input_readiness(Path::new(&config.input_zip_path)) {
+            self.pending_build = Some(PendingBuild {
+                config_id: config.id.clone(),
+                release_notes,
+                reason,
+                waiting_since: None,
+                last_checked: std::time::Instant::now(),
+            });
+            return;
+        }
+        self.start_build(original_idx, release_notes);
+    }
+
+    /// Puts the config at `idx` in the build queue; it starts once the builds ahead of it are
+    /// done, or right away when nothing is building.
+    fn queue_build(&mut self, idx: usize, release_notes: Option<String>, priority: Priority) {
+        let config = &self.app_configs[idx];
+        self.build_queue.push(&config.id, release_notes, priority, QueueSource::Manual);
+        let position = self.build_queue.jobs().iter().position(|j| j.config_id == config.id).unwrap_or_default() + 1;
+        self.status_message = format!("{} queued ({} of {}).", config.app_name, position, self.build_queue.len());
+    }
+
+    /// Starts the first queued build whose input ZIP has settled, once nothing else is building.
+    /// During a project run only high-priority builds may start, between two members; see
+    /// `poll_build_runner`. Auto-rebuilds of a deleted input are dropped; they are queued again
+    /// when the file comes back. AutoCheck builds are dropped when their ZIP is gone or AutoCheck
+    /// was stopped.
+    fn start_next_queued_build(&mut self) {
+        if self.build_queue.is_empty() || self.build_runner.is_some() || self.pending_build.is_some() {
+            return;
+        }
+        if self.build_queue_checked_at.is_some_and(|at| at.elapsed() < std::time::Duration::from_millis(500)) {
+            return;
+        }
+        self.build_queue_checked_at = Some(std::time::Instant::now());
+        let configs = &self.app_configs;
+        let autocheck_on = self.autocheck_config.is_some();
+        let mut skipped = Vec::new();
+        self.build_queue.retain(|job| match configs.iter().find(|c| c.id == job.config_id) {
+            None if job.source == QueueSource::AutoCheck => autocheck_on && job.input_path.as_deref().is_some_and(|p| Path::new(p).exists()),
+            None => false,
+            Some(config) if job.source == QueueSource::AutoRebuild && !Path::new(&config.input_zip_path).exists() => {
+                skipped.push(config.app_name.clone());
+                false
+            }
+            Some(_) => true,
+        });
+        if !skipped.is_empty() {
+            self.status_message = format!("Input of {} was removed; rebuild skipped.", skipped.join(", "));
+        }
+        let at_least = if self.project_run.is_some() { Priority::High } else { Priority::Low };
+        let job = self.build_queue.take_ready(at_least, |job| match (&job.input_path, configs.iter().find(|c| c.id == job.config_id)) {
+            (Some(input), _) => crate::autocheck::input_readiness(Path::new(input)),
+            (None, Some(config)) => crate::autocheck::input_readiness(Path::new(&config.input_zip_path)),
+            (None, None) => Err("the app was deleted".to_string()),
+        });
+        let Some(job) = job else { return };
+        if let (QueueSource::AutoCheck, Some(input), Some(cfg)) = (job.source, &job.input_path, &self.autocheck_config) {
+            let runner = BuildRunner::start(cfg.app_config(Path::new(input)), cfg.output_dir.clone(), cfg.build_options.clone());
+            self.build_runner = Some(runner);
+            self.running_from_queue = true;
+            self.push_autocheck_log(format!("Building: {}", input));
+        } else if let Some(idx) = self.app_configs.iter().position(|c| c.id == job.config_id) {
+            self.start_build(idx, job.release_notes);
+            self.running_from_queue = self.build_runner.is_some();
+        }
+    }
+
+    /// The Build Queue panel: queued builds in run order, each with its priority, a drag handle
+    /// to reorder it and a button to take it out of the queue.
+    fn render_build_queue(&mut self, ui: &mut egui::Ui) {
+        if self.build_queue.is_empty() {
+            return;
+        }
+        let mut moved = None;
+        let mut reprioritized = None;
+        let mut removed = None;
+        egui::CollapsingHeader::new(format!("🗂 Build Queue ({})", self.build_queue.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for (i, job) in self.build_queue.jobs().iter().enumerate() {
+                    let app_name = match (&job.input_path, self.app_configs.iter().find(|c| c.id == job.config_id)) {
+                        (Some(input), _) => format!("AutoCheck: {}", Path::new(input).file_name().unwrap_or_default().to_string_lossy()),
+                        (None, Some(config)) => config.app_name.clone(),
+                        (None, None) => "(deleted app)".to_string(),
+                    };
+                    let row = ui.horizontal(|ui| {
+                        ui.dnd_drag_source(egui::Id::new(("build_queue_job", job.id)), i, |ui| {
+                            ui.label("☰");
+                        })
+                        .response
+                        .on_hover_text("Drag to reorder");
+                        let mut priority = job.priority;
+                        egui::ComboBox::from_id_source(("build_queue_priority", job.id))
+                            .selected_text(priority.label())
+                            .width(90.0)
+                            .show_ui(ui, |ui| {
+                                for option in Priority::ALL {
+                                    ui.selectable_value(&mut priority, option, option.label());
+                                }
+                            });
+                        if priority != job.priority {
+                            reprioritized = Some((job.id, priority));
+                        }
+                        ui.label(format!("{}. {}", i + 1, app_name));
+                        let (queued, exact) = self.timestamp_text(job.queued_at);
+                        ui.small(format!("{}, queued {}", job.source.label(), queued)).on_hover_text(exact);
+                        if let Some(reason) = &job.waiting_for {
+                            ui.colored_label(ui.visuals().warn_fg_color, "⏳").on_hover_text(format!("Waiting: {}", reason));
+                        }
+                        if labeled(ui.small_button("✖"), &format!("Remove {} from the queue", app_name)).on_hover_text("Remove from the queue").clicked() {
+                            removed = Some(job.id);
+                        }
+                    }).response;
+                    if let Some(from) = row.dnd_release_payload::<usize>() {
+                        moved = Some((*from, i));
+                    } else if row.dnd_hover_payload::<usize>().is_some_and(|from| *from != i) {
+                        ui.painter().hline(row.rect.x_range(), row.rect.top(), ui.visuals().selection.stroke);
+                    }
+                }
+            });
+        if let Some((from, to)) = moved {
+            self.build_queue.move_job(from, to);
+        }
+        if let Some((id, priority)) = reprioritized {
+            self.build_queue.set_priority(id, priority);
+        }
+        if let Some(id) = removed {
+            self.build_queue.remove(id);
+        }
+    }
+
+    /// Picks the channel of config `idx`'s next builds from its row, as an undoable edit.
+    fn set_channel(&mut self, idx: usize, channel: String) {
+        let edited = AppConfig { channel, ..self.app_configs[idx].clone() };
+        self.apply_config_edit(idx, edited, "channel picker");
+    }
+
+    fn start_build(&mut self, original_idx: usize, release_notes: Option<String>) {
+        let output_dir = match self.output_directory.as_ref() {
+            Some(dir) => PathBuf::from(dir),
+            None => return,
+        };
+        // Clone the AppConfig for this specific generation task
+        let mut app_config_for_generation = crate::projects::with_project_defaults(&self.projects, self.app_configs[original_idx].clone());
+        let channel_name = app_config_for_generation.channel.trim().to_string();
+        let unknown_channel = match crate::channels::find(&self.settings.channels, &channel_name) {
+            Some(channel) => {
+                channel.route(&mut app_config_for_generation);
+                false
+            }
+            None => !channel_name.is_empty(),
+        };
+
+        self.status_message = format!("Generating IPA for {}...", app_config_for_generation.app_name);
+        let mut build_options = self.settings.build_options();
+        build_options.release_notes = release_notes
+            .filter(|notes| !notes.trim().is_empty())
+            .or_else(|| changelog_release_notes(&app_config_for_generation));
+        if unknown_channel {
+            // Renamed or deleted since it was picked; building without its routing would upload
+            // to the wrong places.
+            self.build_runner = Some(BuildRunner::failed(app_config_for_generation, build_options, crate::ipa_logic::IpaError::UnknownChannel(channel_name)));
+            return;
+        }
+        let average = self.build_history.average_duration_ms(&app_config_for_generation.id).map(|ms| std::time::Duration::from_millis(ms as u64));
+        let mut runner = BuildRunner::start(app_config_for_generation, output_dir, build_options);
+        if let (Some(average), Some(limit)) = (average, crate::build_runner::watchdog_limit(average, self.settings.build_watchdog_multiplier)) {
+            runner = runner.with_watchdog(average, limit);
+        }
+        self.build_runner = Some(runner);
+    }
+
+    fn poll_build_runner(&mut self) {
+        let finished = match self.build_runner.as_mut().and_then(|r| r.poll()) {
+            Some(f) => f,
+            None => return,
+        };
+        if let Some(runner) = self.build_runner.take() {
+            let succeeded = finished.result.is_ok();
+            if runner.config().id == AUTOCHECK_CONFIG_ID {
+                self.finish_autocheck_build(&runner, &finished);
+            }
+            let from_queue = std::mem::take(&mut self.running_from_queue);
+            self.finish_generation(&runner, finished);
+            if from_queue {
+                // A high-priority build that ran between two project members; the project resumes.
+                self.continue_project_run(true);
+                return;
+            }
+            if succeeded && self.project_run.is_some() {
+                self.build_queue_checked_at = None;
+                self.start_next_queued_build();
+                if self.build_runner.is_some() {
+                    return;
+                }
+            }
+            self.continue_project_run(succeeded);
+        }
+        self.input_cache_entries = None;
+    }
+
+    /// Logs an AutoCheck build's outcome and, once it succeeded, deletes the ZIP it was built from,
+    /// as AutoCheck did when it built on its own thread.
+    fn finish_autocheck_build(&mut self, runner: &BuildRunner, finished: &FinishedBuild) {
+        let input = PathBuf::from(&runner.config().input_zip_path);
+        let mut lines = Vec::new();
+        match &finished.result {
+            Ok(output) => {
+                lines.push(format!("Generated: {}", output.ipa_path.display()));
+                lines.extend(output.warnings.iter().map(|w| format!("Warning: {}", w)));
+                match crate::autocheck::delete_source_zip_with_retry(&input, std::time::Duration::from_secs(5)) {
+                    Ok(()) => lines.push(format!("Deleted source: {}", input.display())),
+                    Err(e) => lines.push(format!("Generated but failed to delete source {}: {}", input.display(), e)),
+                }
+            }
+            Err(e) => lines.push(format!("Generation error for {}: {}", input.display(), e)),
+        }
+        for line in lines {
+            self.push_autocheck_log(line);
+        }
+    }
+
+    fn start_project_build(&mut self, project_idx: usize) {
+        if self.build_runner.is_some() {
+            return;
+        }
+        let Some(project) = self.projects.get(project_idx) else { return };
+        match project.build_order(&self.app_configs) {
+            Ok(order) => {
+                self.project_run = Some(ProjectRun::new(project, order));
+                self.continue_project_run(true);
+            }
+            Err(e) => self.status_message = e,
+        }
+    }
+
+    /// Starts the next member of the project being built, or ends the run when it is done or
+    /// the last build failed; members after a failure are not built.
+    fn continue_project_run(&mut self, last_succeeded: bool) {
+        let Some(run) = self.project_run.as_mut() else { return };
+        if !last_succeeded {
+            self.status_message.push_str(&format!(" Project '{}' stopped; {} app(s) not built.", run.project_name, run.remaining.len()));
+            self.project_run = None;
+            return;
+        }
+        let Some(config_id) = run.remaining.pop_front() else {
+            self.status_message = format!("Project '{}' built: {} app(s).", run.project_name, run.total);
+            self.project_run = None;
+            return;
+        };
+        match self.app_configs.iter().position(|c| c.id == config_id) {
+            Some(idx) => {
+                self.generate_for_config(idx, None);
+                if self.build_runner.is_none() && self.pending_build.is_none() {
+                    self.project_run = None;
+                }
+            }
+            None => {
+                self.status_message = format!("Project '{}' stopped: one of its apps was deleted.", run.project_name);
+                self.project_run = None;
+            }
+        }
+    }
+
+    /// Reads the `Info.plist` and provisioning profile of config `idx`'s input ZIP for the table. URLs and missing files are
+    /// skipped; the metadata then appears after the next build.
+    fn refresh_bundle_info_from_input(&mut self, idx: usize) {
+        let Some(config) = self.app_configs.get(idx) else { return };
+        let path = Path::new(&config.input_zip_path);
+        if !path.is_file() {
+            return;
+        }
+        match crate::bundle_info::read_from_input_zip(path) {
+            Ok(info) => {
+                self.bundle_infos.insert(config.id.clone(), info);
+            }
+            Err(e) => log::warn!("Could not read Info.plist from {}: {}", path.display(), e),
+        }
+        match crate::provisioning::read_from_input_zip(path) {
+            Ok(Some(profile)) => {
+                self.profiles.insert(config.id.clone(), profile);
+            }
+            Ok(None) => {
+                self.profiles.remove(&config.id);
+            }
+            Err(e) => log::warn!("Could not read the provisioning profile from {}: {}", path.display(), e),
+        }
+    }
+
+    /// Profile name and team, with devices and expiry below; red within
+    /// [`crate::provisioning::EXPIRY_CRITICAL_DAYS`] of expiring.
+    fn render_profile_cell(&self, ui: &mut egui::Ui, profile: &ProfileInfo) {
+        let now = Utc::now();
+        let name = profile.name.as_deref().unwrap_or("Unnamed profile");
+        let label = ui.label(match &profile.team_name {
+            Some(team) => format!("{} · {}", name, team),
+            None => name.to_string(),
+        });
+        label.on_hover_text(format!(
+            "{} profile\nTeam: {}\nExpires: {}\nProvisioned devices: {}",
+            profile.kind,
+            profile.team_name.as_deref().unwrap_or("unknown"),
+            self.settings.time_zone.display_with_zone(profile.expires_at),
+            profile.device_count
+        ));
+        let devices = match profile.device_count {
+            0 if profile.kind == "Enterprise" => "all devices".to_string(),
+            0 => profile.kind.clone(),
+            1 => "1 device".to_string(),
+            n => format!("{} devices", n),
+        };
+        match crate::provisioning::expiry_warning(profile.expires_at, now) {
+            Some(warning) => {
+                let color = if crate::provisioning::is_expiry_critical(profile.expires_at, now) { ui.visuals().error_fg_color } else { ui.visuals().warn_fg_color };
+                ui.colored_label(color, format!("⚠ {} · {}", devices, warning));
+            }
+            None => {
+                ui.small(format!("{} · expires {}", devices, profile.expires_at.format("%Y-%m-%d")));
+            }
+        }
+    }
+
+    fn finish_generation(&mut self, runner: &BuildRunner, finished: FinishedBuild) {
+        let app_config_for_generation = runner.config();
+        let build_options = runner.options();
+        let duration = runner.elapsed();
+        let channel = Some(app_config_for_generation.channel.trim().to_string()).filter(|c| !c.is_empty());
+        match finished.result {
+            Ok(output) => {
+                let event = BuildEvent::new(app_config_for_generation, Ok(&output), duration.as_millis(), finished.sha256.clone());
+                self.notify_build(app_config_for_generation, event);
+                let output_path = output.ipa_path;
+                self.last_generated_ipa_path = Some(output_path.clone()); // Store the path
+                self.last_generated_config_id = Some(app_config_for_generation.id.clone());
+                self.last_generated_ipa_missing = false;
+                self.status_message = format!("IPA for '{}' generated successfully in {:.2}s at: {}", app_config_for_generation.app_name, duration.as_secs_f32(), output_path.display());
+                if !output.warnings.is_empty() {
+                    self.status_message.push_str(&format!(" ({} warnings)", output.warnings.len()));
+                }
+                if build_options.artifact_signing.is_enabled() {
+                    let signature = build_options.artifact_signing.signature_path(&output_path);
+                    self.status_message.push_str(&format!(" Signed: {}.", signature.file_name().unwrap_or_default().to_string_lossy()));
+                }
+                if output.sha256.is_some() {
+                    let checksum = crate::transfer::checksum_path(&output_path);
+                    self.status_message.push_str(&format!(" Checksum: {}.", checksum.file_name().unwrap_or_default().to_string_lossy()));
+                }
+                if app_config_for_generation.ota_manifest.enabled {
+                    let manifest = crate::ota_manifest::manifest_path(&output_path);
+                    self.status_message.push_str(&format!(" OTA manifest: {}.", manifest.file_name().unwrap_or_default().to_string_lossy()));
+                }
+                if let Some(url) = &output.uploaded_url {
+                    self.status_message.push_str(&format!(" Uploaded to {}.", url));
+                }
+                self.last_generated_sha256 = finished.sha256.clone();
+                if let Some(cleanup) = app_config_for_generation.clean_up_old_builds(&output_path) {
+                    self.status_message.push_str(&format!(" {}.", cleanup));
+                }
+                let profile = crate::provisioning::read_from_ipa(&output_path).unwrap_or_else(|e| {
+                    log::warn!("Could not read the provisioning profile of {}: {}", output_path.display(), e);
+                    None
+                });
+                let profile_expires_at = profile.as_ref().map(|p| p.expires_at);
+                match profile {
+                    Some(profile) => self.profiles.insert(app_config_for_generation.id.clone(), profile),
+                    None => self.profiles.remove(&app_config_for_generation.id),
+                };
+                if let Some(warning) = profile_expires_at.and_then(|at| crate::provisioning::expiry_warning(at, Utc::now())) {
+                    self.status_message.push_str(&format!(" ⚠ {}; re-sign it before testers get locked out.", warning));
+                }
+                self.last_build_warnings = output.warnings.clone();
+                self.last_build_is_simulator = output.is_simulator_build;
+                let asc = &app_config_for_generation.app_store_connect;
+                if asc.enabled && asc.upload_after_build && !output.is_simulator_build {
+                    self.start_app_store_connect_upload(output_path.clone(), asc.clone());
+                }
+                let share_after_build = match crate::channels::find(&self.settings.channels, &app_config_for_generation.channel) {
+                    Some(channel) => channel.share,
+                    None => self.settings.share.upload_after_build,
+                };
+                if share_after_build && self.settings.share.service != ShareService::None && !output.is_simulator_build {
+                    self.start_share_upload(output_path.clone());
+                }
+                log::info!("IPA generated: {}", output_path.display());
+                if let Some(cfg_to_update) = self.app_configs.iter_mut().find(|c| c.id == app_config_for_generation.id) {
+                    cfg_to_update.record_successful_build(app_config_for_generation);
+                }
+                match crate::bundle_info::read_from_ipa(&output_path) {
+                    Ok(info) => {
+                        self.bundle_infos.insert(app_config_for_generation.id.clone(), info);
+                    }
+                    Err(e) => log::warn!("Could not read Info.plist from {}: {}", output_path.display(), e),
+                }
+                self.record_metric(MetricEvent::IpaGenerated { 
+                    app_name: app_config_for_generation.app_name.clone(), 
+                    success: true, 
+                    duration_ms: duration.as_millis(), 
+                    output_size_bytes: std::fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0),
+                    channel: channel.clone(),
+                });
+                self.audit_log.record(AuditAction::BuildTriggered {
+                    app_id: app_config_for_generation.id.clone(),
+                    app_name: app_config_for_generation.app_name.clone(),
+                    success: true,
+                    output_path: Some(output_path.display().to_string()),
+                    error: None,
+                });
+                self.build_history.record(BuildRecord {
+                    timestamp: Utc::now(),
+                    app_id: app_config_for_generation.id.clone(),
+                    app_name: app_config_for_generation.app_name.clone(),
+                    success: true,
+                    duration_ms: duration.as_millis(),
+                    output_path: Some(output_path.display().to_string()),
+                    release_notes: build_options.release_notes.clone(),
+                    error: None,
+                    warnings: output.warnings,
+                    sha256: finished.sha256,
+                    size_bytes: std::fs::metadata(&output_path).ok().map(|m| m.len()),
+                    profile_expires_at,
+                    size_breakdown: crate::size_analysis::size_breakdown(&output_path).map_err(|e| log::warn!("No size breakdown for {}: {}", output_path.display(), e)).ok(),
+                    channel,
+                });
+            }
+            Err(e) => {
+                if matches!(e, crate::ipa_logic::IpaError::Cancelled) {
+                    self.status_message = format!("Build for {} cancelled.", app_config_for_generation.app_name);
+                    log::info!("Build for {} cancelled", app_config_for_generation.app_name);
+                } else {
+                    self.status_message = format!("Error for {}: {}", app_config_for_generation.app_name, e);
+                    log::error!("Error generating IPA for {}: {}", app_config_for_generation.app_name, e);
+                    let event = BuildEvent::new(app_config_for_generation, Err(e.to_string()), duration.as_millis(), None);
+                    self.notify_build(app_config_for_generation, event);
+                }
+                self.record_metric(MetricEvent::IpaGenerated { 
+                    app_name: app_config_for_generation.app_name.clone(), 
+                    success: false, 
+                    duration_ms: duration.as_millis(), 
+                    output_size_bytes: 0,
+                    channel: channel.clone(),
+                });
+                self.audit_log.record(AuditAction::BuildTriggered {
+                    app_id: app_config_for_generation.id.clone(),
+                    app_name: app_config_for_generation.app_name.clone(),
+                    success: false,
+                    output_path: None,
+                    error: Some(e.to_string()),
+                });
+                self.build_history.record(BuildRecord {
+                    timestamp: Utc::now(),
+                    app_id: app_config_for_generation.id.clone(),
+                    app_name: app_config_for_generation.app_name.clone(),
+                    success: false,
+                    duration_ms: duration.as_millis(),
+                    output_path: None,
+                    release_notes: build_options.release_notes.clone(),
+                    error: Some(e.to_string()),
+                    warnings: Vec::new(),
+                    sha256: None,
+                    size_bytes: None,
+                    profile_expires_at: None,
+                    size_breakdown: None,
+                    channel,
+                });
+            }
+        }
+    }
+
+    fn render_main_ui(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.visuals_mut().button_frame = false;
+                egui::widgets::global_dark_light_mode_switch(ui);
+                ui.separator();
+                ui.heading("IPA Builder Dashboard");
+                ui.separator();
+                ui.add_enabled_ui(!self.viewer_mode_locked, |ui| {
+                    ui.checkbox(&mut self.viewer_mode, "👁 Viewer mode")
+                        .on_hover_text("Browse configs and trigger builds, but disable adding, editing and deleting")
+                        .on_disabled_hover_text("Viewer mode was enforced with the --viewer launch flag");
+                });
+                ui.separator();
+                if !self.viewer_mode {
+                    let undo_label = self.undo_stack.undo_label();
+                    let undo = ui.add_enabled(undo_label.is_some(), egui::Button::new("↶ Undo"))
+                        .on_hover_text(format!("Undo {} ({})", undo_label.unwrap_or_default(), ctx.format_shortcut(&crate::accessibility::UNDO)))
+                        .on_disabled_hover_text("Nothing to undo");
+                    if undo.clicked() {
+                        self.undo_config_change(true);
+                    }
+                    let redo_label = self.undo_stack.redo_label();
+                    let redo = ui.add_enabled(redo_label.is_some(), egui::Button::new("↷ Redo"))
+                        .on_hover_text(format!("Redo {} ({})", redo_label.unwrap_or_default(), ctx.format_shortcut(&crate::accessibility::REDO)))
+                        .on_disabled_hover_text("Nothing to redo");
+                    if redo.clicked() {
+                        self.undo_config_change(false);
+                    }
+                    ui.separator();
+                }
+                if ui.button("⚙ Settings").clicked() {
+                    self.show_settings_dialog = true;
+                }
+                if self.settings.network.offline {
+                    let text = egui::RichText::new("✈ Offline").color(ui.visuals().warn_fg_color);
+                    if ui.button(text).on_hover_text(offline_mode_summary()).clicked() {
+                        self.show_settings_dialog = true;
+                    }
+                }
+                if ui.button("🔏 Verify IPA…").clicked() {
+                    match native_dialog::FileDialog::new().add_filter("IPA files", &["ipa"]).show_open_single_file() {
+                        Ok(Some(path)) => self.verify_signature(&path),
+                        Ok(None) => {}
+                        Err(e) => self.status_message = format!("Error opening file dialog: {:?}", e),
+                    }
+                }
+                if ui.button("🩺 Validate IPA…").clicked() {
+                    match native_dialog::FileDialog::new().add_filter("IPA files", &["ipa"]).show_open_single_file() {
+                        Ok(Some(path)) => self.validate_ipa(&path),
+                        Ok(None) => {}
+                        Err(e) => self.status_message = format!("Error opening file dialog: {:?}", e),
+                    }
+                }
+                if !self.viewer_mode && ui.button("✍ Batch re-sign…").clicked() {
+                    self.show_batch_resign_dialog = true;
+                }
+                if ui.button("🕘 Build history").clicked() {
+                    self.history_app_id = None;
+                    self.show_build_history = true;
+                }
+                if ui.button("💾 Storage").on_hover_text("Disk space used by built IPAs, per app").clicked() {
+                    self.storage_ledger = Some(crate::artifact_ledger::build_ledger(self.build_history.records()));
+                }
+                if ui.button("🏷 Naming audit").on_hover_text("Find IPAs in the output directory not named after their app's current output name").clicked() {
+                    self.run_naming_audit();
+                }
+                ui.toggle_value(&mut self.show_devices_panel, "📱 Devices").on_hover_text("iPhones and iPads connected over USB, to install the last build on");
+                #[cfg(feature = "ota-server")]
+                self.render_ota_server_button(ui);
+                if ui.button("📁 Projects").on_hover_text("Build groups of related apps in dependency order").clicked() {
+                    self.show_projects = true;
+                }
+                if ui.button("📜 Export audit log…").clicked() {
+                    self.export_audit_log();
+                }
+                if ui.button("📦 Export everything…").on_hover_text("Apps, settings, metrics, audit log and build history in one archive").clicked() {
+                    self.export_everything();
+                }
+                if !self.viewer_mode && ui.button("📥 Import everything…").on_hover_text("Restore an archive from \"Export everything\", e.g. on a new machine").clicked() {
+                    self.import_everything();
+                }
+                if ui.button("🧰 Diagnostics").on_hover_text("Check the file dialog helper, directory permissions, free space and tools").clicked() {
+                    self.run_diagnostics();
+                }
+                ui.separator();
+                ui.label("⌨").on_hover_text(crate::accessibility::shortcut_help(ui.ctx()));
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!("Today's Generations: {}", self.metrics_collector.generations_today()));
+                ui.separator();
+                ui.label(format!("Total Generations: {}", self.metrics_collector.generations_all_time()));
+                ui.separator();
+                if let Some(avg_speed) = self.metrics_collector.avg_generation_speed_ms() {
+                    ui.label(format!("Avg. Speed: {:.2}s", avg_speed as f64 / 1000.0));
+                } else {
+                    ui.label("Avg. Speed: N/A");
                 }
             });
         });
 
+        self.render_devices_panel(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            let mut export_table_clicked = false;
             ui.horizontal(|ui| {
-                if ui.button("➕ Add Application").clicked() {
-                    self.show_add_app_dialog = true;
-                    self.add_app_name_input = format!("MyNewApp{}", self.app_configs.len() + 1);
-                    self.add_app_output_name_input = format!("app{}.ipa", self.app_configs.len() + 1);
-                    self.add_app_zip_path_input = None;
+                if !self.viewer_mode && ui.button("➕ Add Application").clicked() {
+                    self.open_add_app_dialog();
+                }
+                let import_hint = get_apps_import_file_path()
+                    .map(|p| format!("Merge configs declared in {}", p.display()))
+                    .unwrap_or_else(|| "Config directory unavailable".to_string());
+                if !self.viewer_mode && ui.button("🔄 Sync").on_hover_text(import_hint).clicked() {
+                    self.sync_apps_import_file(true);
                 }
-                ui.label("Search:");
-                ui.text_edit_singleline(&mut self.search_query);
+                let search_label = ui.label("Search:");
+                ui.add(egui::TextEdit::singleline(&mut self.search_query).id(egui::Id::new(SEARCH_FIELD_ID)))
+                    .labelled_by(search_label.id)
+                    .on_hover_text("Matches app names and input paths, plus past builds by IPA file name, SHA-256 or release notes");
+                export_table_clicked = ui.button("📤 Export table…").on_hover_text("Write the rows shown below, in this order, to CSV or JSON").clicked();
             });
             ui.separator();
 
@@ -367,15 +3641,26 @@ impl IpaBuilderApp {
             ui.separator();
 
             let lower_search_query = self.search_query.to_lowercase();
+            let matching_builds: Vec<BuildRecord> = self.build_history.search(&self.search_query).into_iter().cloned().collect();
             let config_indices_to_display: Vec<usize> = self.app_configs.iter().enumerate()
                 .filter(|(_, config)| {
                     self.search_query.is_empty() || 
                     config.app_name.to_lowercase().contains(&lower_search_query) ||
-                    config.input_zip_path.to_lowercase().contains(&lower_search_query)
+                    config.input_zip_path.to_lowercase().contains(&lower_search_query) ||
+                    self.bundle_infos.get(&config.id).and_then(|b| b.bundle_identifier.as_ref()).is_some_and(|id| id.to_lowercase().contains(&lower_search_query)) ||
+                    matching_builds.iter().any(|record| record.app_id == config.id)
                 })
                 .map(|(idx, _)| idx)
                 .collect();
+            let rows = crate::dashboard::dashboard_rows(&self.app_configs, &config_indices_to_display, self.build_history.records(), self.table_sort);
+            if export_table_clicked {
+                self.export_table(&rows);
+            }
 
+            if self.settings.relative_times {
+                // Keeps "just now" from going stale while the window sits idle.
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(30));
+            }
             let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
             let table = TableBuilder::new(ui)
                 .striped(true)
@@ -383,95 +3668,197 @@ impl IpaBuilderApp {
                 .column(Column::auto())
                 .column(Column::initial(200.0).clip(true))
                 .column(Column::initial(200.0).clip(true))
+                .column(Column::initial(160.0).clip(true))
+                .column(Column::auto())
+                .column(Column::initial(180.0).clip(true))
                 .column(Column::initial(150.0))
+                .column(Column::auto())
+                .column(Column::auto())
                 .column(Column::remainder())
                 .min_scrolled_height(0.0);
 
+            let mut sort_clicked = None;
             table.header(20.0, |mut header| {
-                header.col(|ui| { ui.strong("Name"); });
-                header.col(|ui| { ui.strong("Input ZIP"); });
-                header.col(|ui| { ui.strong("Output IPA"); });
-                header.col(|ui| { ui.strong("Created"); });
+                for (label, column) in [("Name", Some(SortColumn::Name)), ("Input ZIP", None), ("Output IPA", None), ("Bundle ID", None), ("Version", None), ("Profile", None), ("Created", Some(SortColumn::Created)), ("Builds", Some(SortColumn::Builds)), ("Last size", Some(SortColumn::LastSize))] {
+                    header.col(|ui| match column {
+                        Some(column) => {
+                            let text = egui::RichText::new(format!("{}{}", label, self.table_sort.indicator(column))).strong();
+                            if ui.add(egui::Button::new(text).frame(false)).on_hover_text("Sort").clicked() {
+                                sort_clicked = Some(column);
+                            }
+                        }
+                        None => { ui.strong(label); }
+                    });
+                }
                 header.col(|ui| { ui.strong("Actions"); });
             })
             .body(|mut body| {
-                for &original_idx in &config_indices_to_display {
-                            // Clone data needed for display to avoid borrowing `self.app_configs` in the row closure
-                            let display_app_name = self.app_configs[original_idx].app_name.clone();
-                            let display_last_gen_str = self.app_configs[original_idx].last_generated_at
-                                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string());
-                            let display_input_zip = self.app_configs[original_idx].input_zip_path.clone();
-                            let display_output_ipa = self.app_configs[original_idx].output_ipa_name.clone();
-                            let display_created_at = self.app_configs[original_idx].created_at.format("%Y-%m-%d %H:%M").to_string();
+                for row_data in &rows {
+                            let original_idx = row_data.index;
+                            let display_app_name = &row_data.app_name;
+                            let display_input_zip = &row_data.input_zip_path;
+                            let display_output_ipa = &row_data.output_ipa_name;
 
                             body.row(text_height + 4.0, |mut row| {
                                 row.col(|ui| {
-                                    ui.label(&display_app_name);
-                                    if let Some(gen_time_str) = &display_last_gen_str {
-                                        ui.small(format!("Last gen: {}", gen_time_str));
+                                    ui.label(display_app_name);
+                                    if let Some(expires_at) = row_data.profile_expires_at {
+                                        if let Some(warning) = crate::provisioning::expiry_warning(expires_at, Utc::now()) {
+                                            let color = if crate::provisioning::is_expiry_critical(expires_at, Utc::now()) { ui.visuals().error_fg_color } else { ui.visuals().warn_fg_color };
+                                            ui.colored_label(color, format!("⚠ {}", warning))
+                                                .on_hover_text(format!("The latest build's provisioning profile expires {}. Re-sign or rebuild with a renewed profile before testers get locked out.", self.settings.time_zone.display_with_zone(expires_at)));
+                                        }
+                                    }
+                                    if let Some(last_generated_at) = row_data.last_generated_at {
+                                        let (text, exact) = self.timestamp_text(last_generated_at);
+                                        ui.small(format!("Last gen: {}", text)).on_hover_text(exact);
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.label(display_input_zip);
+                                    if let Some(job) = self.build_queue.jobs().iter().find(|j| j.config_id == row_data.id) {
+                                        ui.small(format!("🗂 Queued ({})", job.source.label())).on_hover_text("Starts once the builds ahead of it in the Build Queue are done and its input ZIP has finished being written.");
+                                    }
+                                    if let Some((error, _)) = self.input_watch_errors.get(&row_data.id) {
+                                        ui.colored_label(ui.visuals().warn_fg_color, "⚠ Not watched").on_hover_text(format!("Auto-rebuild is on, but the input ZIP cannot be watched: {}. Retrying...", error));
+                                    } else if self.input_watchers.contains_key(&row_data.id) {
+                                        ui.small("👁 Auto-rebuild").on_hover_text("Rebuilt whenever this file changes");
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.label(display_output_ipa);
+                                });
+                                let bundle = self.bundle_infos.get(&row_data.id);
+                                row.col(|ui| {
+                                    if let Some(identifier) = bundle.and_then(|b| b.bundle_identifier.as_ref()) {
+                                        let label = ui.label(identifier);
+                                        if let Some(name) = bundle.and_then(|b| b.display_name.as_ref()) {
+                                            label.on_hover_text(format!("Display name: {}", name));
+                                        }
+                                    }
+                                });
+                                row.col(|ui| {
+                                    if let Some(bundle) = bundle {
+                                        let label = ui.label(bundle.version_label());
+                                        if let Some(minimum) = &bundle.minimum_os_version {
+                                            label.on_hover_text(format!("Minimum iOS: {}", minimum));
+                                        }
+                                    }
+                                });
+                                row.col(|ui| {
+                                    if let Some(profile) = self.profiles.get(&row_data.id) {
+                                        self.render_profile_cell(ui, profile);
                                     }
                                 });
                                 row.col(|ui| {
-                                    ui.label(&display_input_zip);
+                                    let (text, exact) = self.timestamp_text(row_data.created_at);
+                                    ui.label(text).on_hover_text(exact);
                                 });
                                 row.col(|ui| {
-                                    ui.label(&display_output_ipa);
+                                    let label = ui.label(row_data.builds.to_string());
+                                    if row_data.failed_builds > 0 {
+                                        label.on_hover_text(format!("{} failed", row_data.failed_builds));
+                                    }
                                 });
                                 row.col(|ui| {
-                                    ui.label(&display_created_at);
+                                    ui.label(row_data.last_size_bytes.map(crate::size_analysis::format_size).unwrap_or_default());
                                 });
                                 row.col(|ui| {
                                     ui.horizontal(|ui| {
-                                        if ui.button("✏️").on_hover_text("Edit").clicked() {
+                                        let app_name = &self.app_configs[original_idx].app_name;
+                                        if !self.viewer_mode && labeled(ui.button("✏️"), &format!("Edit {}", app_name)).on_hover_text("Edit").clicked() {
                                             self.edit_app_name_input = self.app_configs[original_idx].app_name.clone();
+                                            self.edit_errors.clear();
                                             self.edit_input_zip_path_input = Some(self.app_configs[original_idx].input_zip_path.clone());
                                             self.edit_output_ipa_name_input = self.app_configs[original_idx].output_ipa_name.clone();
+                                            self.edit_changelog_path_input = self.app_configs[original_idx].changelog_path.clone().unwrap_or_default();
+                                            self.edit_env_vars_input = self.app_configs[original_idx].env_vars.clone().into_iter().collect();
+                                            self.edit_sidecars_input = self.app_configs[original_idx].sidecars.clone();
+                                            self.edit_dsym_path_input = self.app_configs[original_idx].dsym_path.clone();
+                                            self.edit_dsym_placement_input = self.app_configs[original_idx].dsym_placement;
+                                            self.edit_strip_junk_input = self.app_configs[original_idx].strip_junk_files;
+                                            self.edit_keep_architectures_input = self.app_configs[original_idx].keep_architectures.clone();
+                                            self.edit_inject_dylibs_input = self.app_configs[original_idx].inject_dylibs.clone();
+                                            self.edit_plist_overrides_input = self.app_configs[original_idx].plist_overrides.clone();
+                                            self.edit_auto_increment_input = self.app_configs[original_idx].auto_increment_build;
+                                            self.edit_auto_rebuild_input = self.app_configs[original_idx].auto_rebuild;
+                                            self.edit_output_exists_input = self.app_configs[original_idx].output_exists;
+                                            self.edit_build_counter_input = self.app_configs[original_idx].build_counter;
+                                            self.edit_keep_last_builds_input = self.app_configs[original_idx].keep_last_builds;
+                                            self.edit_trash_old_builds_input = self.app_configs[original_idx].trash_old_builds;
+                                            self.edit_signing_input = self.app_configs[original_idx].signing.clone();
+                                            self.edit_ota_manifest_input = self.app_configs[original_idx].ota_manifest.clone();
+                                            self.edit_app_store_connect_input = self.app_configs[original_idx].app_store_connect.clone();
+                                            self.edit_object_storage_input = self.app_configs[original_idx].object_storage.clone();
+                                            self.edit_permissions_input = self.app_configs[original_idx].permissions.clone();
+                                            self.edit_webhooks_input = self.app_configs[original_idx].webhooks.clone();
+                                            self.edit_notifications_input = self.app_configs[original_idx].notifications.clone();
+                                            self.edit_email_recipients_input = self.edit_notifications_input.email_recipients.join(", ");
+                                            self.edit_executable_extensions_input = self.edit_permissions_input.executable_extensions.join(", ");
+                                            self.asc_check_result = None;
                                             self.show_edit_dialog_for_idx = Some(original_idx);
                                         }
-                                        let gen_button_text = if self.generating_app_idx == Some(original_idx) {
-                                            "⏳"
-                                        } else {
-                                            "▶️"
-                                        };
-                                        if ui.button(gen_button_text).on_hover_text("Generate IPA").clicked() {
-                                            if self.generating_app_idx.is_none() {
-                                                // Clone the AppConfig for this specific generation task
-                                                let app_config_for_generation = self.app_configs[original_idx].clone();
-
-                                                self.generating_app_idx = Some(original_idx);
-                                                self.status_message = format!("Generating IPA for {}...", app_config_for_generation.app_name);
-                                                let start_time = std::time::Instant::now();
-                                                match crate::ipa_logic::generate_ipa(&app_config_for_generation, std::path::Path::new(self.output_directory.as_ref().unwrap())) {
-                                                    Ok(output_path) => {
-                                                        let duration = start_time.elapsed();
-                                                        self.last_generated_ipa_path = Some(output_path.clone()); // Store the path
-                                                        self.status_message = format!("IPA for '{}' generated successfully in {:.2}s at: {}", app_config_for_generation.app_name, duration.as_secs_f32(), output_path.display());
-                                                        log::info!("IPA generated: {}", output_path.display());
-                                                        if let Some(cfg_to_update) = self.app_configs.get_mut(original_idx) {
-                                                            cfg_to_update.last_generated_at = Some(Utc::now());
+                                        if !self.settings.channels.is_empty() {
+                                            let config = &self.app_configs[original_idx];
+                                            let mut picked = config.channel.clone();
+                                            let selected = if picked.trim().is_empty() { "No channel" } else { picked.as_str() }.to_string();
+                                            ui.add_enabled_ui(!self.viewer_mode, |ui| {
+                                                egui::ComboBox::from_id_source(("row_channel", &config.id))
+                                                    .selected_text(selected)
+                                                    .width(80.0)
+                                                    .show_ui(ui, |ui| {
+                                                        ui.selectable_value(&mut picked, String::new(), "No channel");
+                                                        for channel in &self.settings.channels {
+                                                            ui.selectable_value(&mut picked, channel.name.trim().to_string(), channel.name.trim())
+                                                                .on_hover_text(format!("Uploads to: {}", channel.destinations()));
                                                         }
-                                                        self.record_metric(MetricEvent::IpaGenerated { 
-                                                            app_name: app_config_for_generation.app_name.clone(), 
-                                                            success: true, 
-                                                            duration_ms: duration.as_millis(), 
-                                                            output_size_bytes: std::fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0) 
-                                                        });
+                                                    })
+                                                    .response
+                                                    .on_hover_text("Channel of the next build: part of {channel} in the output name, recorded in the history, and picks where the build is uploaded");
+                                            });
+                                            if picked != config.channel {
+                                                self.set_channel(original_idx, picked);
+                                            }
+                                        }
+                                        match self.build_runner.as_ref().filter(|r| r.config().id == self.app_configs[original_idx].id) {
+                                            Some(runner) => render_row_build_progress(ui, runner),
+                                            None => {
+                                                let label = format!("Generate IPA for {}", self.app_configs[original_idx].app_name);
+                                                let button = labeled(ui.button("▶️"), &label).on_hover_text("Generate IPA (right-click to queue with a priority)");
+                                                if button.clicked() {
+                                                    self.generate_for_config(original_idx, None);
+                                                }
+                                                button.context_menu(|ui| {
+                                                    if ui.button("⏫ Queue with high priority").on_hover_text("Runs before every other queued build, even between the apps of a running project").clicked() {
+                                                        self.queue_build(original_idx, None, Priority::High);
+                                                        ui.close_menu();
                                                     }
-                                                    Err(e) => {
-                                                        self.status_message = format!("Error for {}: {}", app_config_for_generation.app_name, e);
-                                                        log::error!("Error generating IPA for {}: {}", app_config_for_generation.app_name, e);
-                                                        self.record_metric(MetricEvent::IpaGenerated { 
-                                                            app_name: app_config_for_generation.app_name.clone(), 
-                                                            success: false, 
-                                                            duration_ms: start_time.elapsed().as_millis(), 
-                                                            output_size_bytes: 0 
-                                                        });
+                                                    if ui.button("⏬ Queue with low priority").on_hover_text("Runs only once nothing else is queued").clicked() {
+                                                        self.queue_build(original_idx, None, Priority::Low);
+                                                        ui.close_menu();
                                                     }
-                                                }
-                                                self.generating_app_idx = None;
+                                                });
+                                            }
+                                        }
+                                        let label = format!("Generate IPA for {} with release notes", self.app_configs[original_idx].app_name);
+                                        if labeled(ui.button("📝"), &label).on_hover_text("Generate IPA with release notes…").clicked() {
+                                            self.release_notes_input = changelog_release_notes(&self.app_configs[original_idx]).unwrap_or_default();
+                                            self.release_notes_dialog_for_idx = Some(original_idx);
+                                        }
+                                        let label = format!("Inspect the latest IPA of {}", self.app_configs[original_idx].app_name);
+                                        if labeled(ui.button("🔍"), &label).on_hover_text("Inspect latest IPA").clicked() {
+                                            match self.latest_ipa_for(&self.app_configs[original_idx].id) {
+                                                Some(path) => self.inspect_ipa(&path),
+                                                None => self.status_message = format!("{} has no built IPA on disk to inspect.", self.app_configs[original_idx].app_name),
                                             }
                                         }
-                                        if ui.button("🗑️").clicked() {
+                                        let label = format!("Build history of {}", self.app_configs[original_idx].app_name);
+                                        if labeled(ui.button("🕘"), &label).on_hover_text("History").clicked() {
+                                            self.history_app_id = Some(self.app_configs[original_idx].id.clone());
+                                            self.show_build_history = true;
+                                        }
+                                        let label = format!("Delete {}", self.app_configs[original_idx].app_name);
+                                        if !self.viewer_mode && labeled(ui.button("🗑️"), &label).on_hover_text("Delete").clicked() {
                                             self.show_delete_confirm_for_idx = Some(original_idx);
                                         }
                                     });
@@ -479,17 +3866,200 @@ impl IpaBuilderApp {
                             });
                         } 
                     });
+            if let Some(column) = sort_clicked {
+                self.table_sort.toggle(column);
+            }
+            if !matching_builds.is_empty() {
+                ui.separator();
+                let mut folder_to_open = None;
+                ui.collapsing(format!("🕘 {} matching build(s)", matching_builds.len()), |ui| {
+                    for (i, record) in matching_builds.iter().take(50).enumerate() {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(if record.success { "✔" } else { "✖" });
+                                ui.label(self.settings.time_zone.display(record.timestamp));
+                                ui.strong(&record.app_name);
+                                if let Some(path) = &record.output_path {
+                                    if ui.link(path).on_hover_text("Click to open containing folder").clicked() {
+                                        folder_to_open = Some(PathBuf::from(path));
+                                    }
+                                }
+                                if let Some(hash) = &record.sha256 {
+                                    ui.monospace(&hash[..hash.len().min(12)]).on_hover_text(format!("SHA-256: {}", hash));
+                                }
+                            });
+                        });
+                    }
+                });
+                if let Some(path) = folder_to_open {
+                    self.open_folder_containing_file(&path);
+                }
+            }
             ui.separator();
+            if let Some(runner) = &mut self.build_runner {
+                if let Some(run) = &self.project_run {
+                    ui.label(format!("📁 Building project '{}': app {} of {}", run.project_name, run.position(), run.total));
+                }
+                let progress = runner.progress();
+                let text = match progress {
+                    Some(p) if p.total > 0 => format!("{}: {} ({})", runner.config().app_name, p.phase.label(), p.detail()),
+                    Some(p) => format!("{}: {}", runner.config().app_name, p.phase.label()),
+                    None => format!("{}: Starting", runner.config().app_name),
+                };
+                ui.horizontal(|ui| {
+                    crate::accessibility::busy_indicator(ui, self.settings.reduce_motion);
+                    ui.add(egui::ProgressBar::new(progress.map(|p| p.overall_fraction()).unwrap_or(0.0)).text(text));
+                });
+                if let Some(average) = runner.overdue().filter(|_| !runner.is_cancelling()) {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!(
+                                "⚠ This build has been running for {}s; it usually takes about {}s. Temp or output folders on a network share can make builds hang.",
+                                runner.elapsed().as_secs(),
+                                average.as_secs().max(1)
+                            ),
+                        );
+                        if ui.button("Keep waiting").clicked() {
+                            runner.keep_waiting();
+                        }
+                        if ui.button("✖ Cancel build").clicked() {
+                            runner.cancel();
+                        }
+                    });
+                }
+            }
+            if let Some(alert) = &self.output_watcher_alert {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ Output folder not watched: {} — retrying automatically", alert));
+            }
             ui.label(&self.status_message).highlight();
+            self.render_build_queue(ui);
 
-            if let Some(ref path) = self.last_generated_ipa_path {
+            if let Some(path) = self.last_generated_ipa_path.clone() {
                 ui.add_space(5.0);
                 ui.horizontal(|ui| {
                     ui.label("Last generated IPA:");
-                    if ui.link(path.display().to_string()).on_hover_text("Click to open containing folder").clicked() {
-                        self.open_folder_containing_file(path);
+                    if self.last_generated_ipa_missing {
+                        ui.add_enabled(false, egui::Link::new(path.display().to_string()))
+                            .on_disabled_hover_text("This file was deleted or moved outside of IPA Builder");
+                        let source_idx = self.last_generated_config_id.as_ref()
+                            .and_then(|id| self.app_configs.iter().position(|c| &c.id == id));
+                        if let Some(idx) = source_idx {
+                            if ui.button("🔁 Rebuild").clicked() {
+                                self.generate_for_config(idx, None);
+                            }
+                        }
+                    } else {
+                        if ui.link(path.display().to_string()).on_hover_text("Click to open containing folder").clicked() {
+                            if path.exists() {
+                                self.open_folder_containing_file(&path);
+                            } else {
+                                self.last_generated_ipa_missing = true;
+                                self.status_message = format!("'{}' no longer exists.", path.display());
+                            }
+                        }
+                        if ui.button("🔏 Verify signature").clicked() {
+                            self.verify_signature(&path);
+                        }
+                        if ui.button("🩺 Validate").clicked() {
+                            self.validate_ipa(&path);
+                        }
+                        if ui.button("🔍 Inspect").clicked() {
+                            self.inspect_ipa(&path);
+                        }
+                        if ui.button("📊 Sizes").clicked() {
+                            let shown = path.display().to_string();
+                            match self.build_history.records().iter().rposition(|r| r.output_path.as_deref() == Some(shown.as_str())) {
+                                Some(index) => self.show_size_report(index),
+                                None => self.status_message = format!("No build of {} is in the history.", shown),
+                            }
+                        }
+                        if ui.button("🌐 Export install page").clicked() {
+                            self.export_install_page(&path);
+                        }
+                        let asc = self.last_generated_config_id.as_ref()
+                            .and_then(|id| self.app_configs.iter().find(|c| &c.id == id))
+                            .map(|c| c.app_store_connect.clone())
+                            .filter(|asc| asc.enabled && !self.last_build_is_simulator);
+                        if let Some(asc) = asc {
+                            let uploading = self.asc_upload.as_ref().is_some_and(|u| u.rx.is_some());
+                            if ui.add_enabled(!uploading, egui::Button::new("☁ Upload to TestFlight"))
+                                .on_hover_text("Upload to App Store Connect with Transporter and the app's API key")
+                                .clicked()
+                            {
+                                self.start_app_store_connect_upload(path.clone(), asc);
+                            }
+                        }
+                        let service = self.settings.share.service;
+                        if service != ShareService::None && !self.last_build_is_simulator {
+                            let uploading = self.share_upload.as_ref().is_some_and(|u| u.rx.is_some());
+                            if ui.add_enabled(!uploading, egui::Button::new("🔗 Share"))
+                                .on_hover_text(format!("Upload to {} for a short install link", service.label()))
+                                .clicked()
+                            {
+                                self.start_share_upload(path.clone());
+                            }
+                        }
+                        if self.device_install_rx.is_some() {
+                            crate::accessibility::busy_indicator(ui, self.settings.reduce_motion);
+                            ui.label("Installing…");
+                        } else if self.last_build_is_simulator {
+                            if cfg!(target_os = "macos")
+                                && ui.button("📲 Install to booted simulator").on_hover_text("This is a simulator build; installs with xcrun simctl").clicked()
+                            {
+                                self.status_message = format!("Installing {} on the booted simulator...", path.display());
+                                self.device_install_rx = Some(crate::device_install::spawn_simulator_install(path.clone()));
+                            }
+                        } else if ui.button("📱 Install on device").on_hover_text("Install over USB with ideviceinstaller").clicked() {
+                            self.status_message = format!("Installing {} on the connected device...", path.display());
+                            self.device_install_rx = Some(crate::device_install::spawn_install(path.clone(), None));
+                        }
                     }
                 });
+                if let Some(hash) = self.last_generated_sha256.clone().filter(|_| !self.last_generated_ipa_missing) {
+                    ui.horizontal(|ui| {
+                        ui.label("SHA-256:");
+                        ui.monospace(&hash);
+                        if labeled(ui.small_button("📋"), "Copy the SHA-256").on_hover_text("Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = hash.clone());
+                            self.status_message = "SHA-256 copied to the clipboard.".to_string();
+                        }
+                    });
+                }
+                let ota = self.last_generated_config_id.as_ref()
+                    .and_then(|id| self.app_configs.iter().find(|c| &c.id == id))
+                    .map(|c| &c.ota_manifest)
+                    .filter(|ota| ota.enabled);
+                if let (Some(ota), Some(path)) = (ota, self.last_generated_ipa_path.as_ref().filter(|_| !self.last_generated_ipa_missing)) {
+                    let manifest = crate::ota_manifest::manifest_path(path);
+                    if manifest.is_file() {
+                        let link = ota.install_link_for(path);
+                        ui.horizontal(|ui| {
+                            ui.label("OTA install:");
+                            ui.monospace(manifest.file_name().unwrap_or_default().to_string_lossy())
+                                .on_hover_text(format!("Upload the IPA and this manifest to {}", ota.base_url.trim()));
+                            if labeled(ui.small_button("📋"), "Copy the install link").on_hover_text(&link).clicked() {
+                                ui.output_mut(|o| o.copied_text = link.clone());
+                                self.status_message = "Install link copied to the clipboard.".to_string();
+                            }
+                            if labeled(ui.small_button("QR"), "Show the install link as a QR code").on_hover_text("QR code").clicked() {
+                                let title = manifest.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                                self.qr_view = Some(QrView::new(title, link.clone()));
+                            }
+                        });
+                    }
+                }
+                self.render_app_store_connect_upload(ui);
+                self.render_share_upload(ui);
+                if !self.last_build_warnings.is_empty() {
+                    egui::CollapsingHeader::new(format!("⚠ {} build warnings", self.last_build_warnings.len()))
+                        .id_source("last_build_warnings")
+                        .show(ui, |ui| {
+                            for warning in &self.last_build_warnings {
+                                ui.colored_label(ui.visuals().warn_fg_color, warning);
+                            }
+                        });
+                }
             }
         });
     }
@@ -502,14 +4072,20 @@ impl IpaBuilderApp {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
-                    ui.label("Application Name (for display):");
-                    ui.text_edit_singleline(&mut self.add_app_name_input);
+                    if !self.add_app_errors.is_empty() {
+                        self.add_app_errors = self.new_app_config().validate().err().unwrap_or_default();
+                    }
+                    let label = ui.label("Application Name (for display):");
+                    ui.text_edit_singleline(&mut self.add_app_name_input).labelled_by(label.id);
+                    render_field_error(ui, &self.add_app_errors, ConfigField::AppName);
 
-                    ui.label("Runner.app.zip Path:");
+                    let label = ui.label("Runner.app.zip Path:");
                     ui.horizontal(|ui| {
-                        let zip_path_display = self.add_app_zip_path_input.as_ref().map_or_else(|| "Not selected".to_string(), |p| p.clone());
-                        ui.label(zip_path_display);
-                        if ui.button("Browse...").clicked() {
+                        let mut zip_path = self.add_app_zip_path_input.clone().unwrap_or_default();
+                        if ui.add(egui::TextEdit::singleline(&mut zip_path).hint_text("Path, network share or https:// URL")).labelled_by(label.id).changed() {
+                            self.add_app_zip_path_input = Some(zip_path.trim().to_string()).filter(|p| !p.is_empty());
+                        }
+                        if labeled(ui.button("Browse..."), "Browse for the Runner.app.zip").clicked() {
                             match native_dialog::FileDialog::new()
                                 .add_filter("Zip files", &["zip"])
                                 .show_open_single_file() {
@@ -524,37 +4100,17 @@ impl IpaBuilderApp {
                             }
                         }
                     });
+                    render_field_error(ui, &self.add_app_errors, ConfigField::InputZip);
                     
-                    ui.label("Output IPA Filename (e.g., myapp_v1.ipa):");
-                    ui.text_edit_singleline(&mut self.add_app_output_name_input);
+                    let label = ui.label("Output IPA Filename (e.g., myapp_v1.ipa):");
+                    ui.text_edit_singleline(&mut self.add_app_output_name_input).labelled_by(label.id);
+                    render_ipa_name_hint(ui, &mut self.add_app_output_name_input);
+                    render_field_error(ui, &self.add_app_errors, ConfigField::OutputIpaName);
 
                     ui.add_space(10.0);
                     ui.horizontal(|ui| {
-                        if ui.button("Add Application").clicked() {
-                            if self.add_app_name_input.trim().is_empty() {
-                                self.status_message = "Application name cannot be empty.".to_string();
-                            } else if self.add_app_zip_path_input.is_none() {
-                                self.status_message = "Please select an input ZIP file.".to_string();
-                            } else if self.add_app_output_name_input.trim().is_empty() || !self.add_app_output_name_input.ends_with(".ipa") {
-                                self.status_message = "Output filename must not be empty and end with .ipa".to_string();
-                            } else {
-                                let new_app = AppConfig {
-                                    id: Uuid::new_v4().to_string(),
-                                    app_name: self.add_app_name_input.trim().to_string(),
-                                    input_zip_path: self.add_app_zip_path_input.clone().unwrap(), // Safe due to check above
-                                    output_ipa_name: self.add_app_output_name_input.trim().to_string(),
-                                    created_at: Utc::now(),
-                                    last_generated_at: None,
-                                };
-                                self.app_configs.push(new_app);
-                                self.status_message = format!("Application '{}' added.", self.add_app_name_input);
-                                self.record_metric(MetricEvent::AppAdded { app_name: self.add_app_name_input.clone() });
-                                // Reset inputs
-                                self.add_app_name_input = "MyNewApp".to_string();
-                                self.add_app_zip_path_input = None;
-                                self.add_app_output_name_input = "output.ipa".to_string();
-                                close_dialog = true;
-                            }
+                        if ui.button("Add Application").clicked() && self.add_app_from_dialog() {
+                            close_dialog = true;
                         }
                         if ui.button("Cancel").clicked() {
                             close_dialog = true;
@@ -567,28 +4123,73 @@ impl IpaBuilderApp {
         }
     }
 
+    /// The config at `idx` with the Edit dialog's inputs applied.
+    /// Replaces config `idx` with `edited`, undoably, and records the edit in the audit log and
+    /// metrics. `source` names where it was made, e.g. `dialog`.
+    fn apply_config_edit(&mut self, idx: usize, edited: AppConfig, source: &str) {
+        self.undo_stack.push(ConfigChange::Edited { before: Box::new(self.app_configs[idx].clone()), after: Box::new(edited.clone()) });
+        self.audit_log.record(AuditAction::ConfigEdited { app_id: edited.id.clone(), app_name: edited.app_name.clone(), source: source.to_string() });
+        self.record_metric(MetricEvent::AppConfigEdited { app_id: edited.id.clone() });
+        self.app_configs[idx] = edited;
+    }
+
+    fn edited_config(&self, idx: usize) -> Option<AppConfig> {
+        let mut config = self.app_configs.get(idx)?.clone();
+        let trimmed_paths = |paths: &[String]| paths.iter().map(|path| path.trim().to_string()).filter(|path| !path.is_empty()).collect();
+        config.app_name = self.edit_app_name_input.trim().to_string();
+        config.input_zip_path = self.edit_input_zip_path_input.as_deref().unwrap_or_default().trim().to_string();
+        config.output_ipa_name = self.edit_output_ipa_name_input.trim().to_string();
+        config.changelog_path = Some(self.edit_changelog_path_input.trim().to_string()).filter(|s| !s.is_empty());
+        config.env_vars = self.edit_env_vars_input.iter()
+            .filter(|(key, _)| !key.trim().is_empty())
+            .map(|(key, value)| (key.trim().to_string(), value.clone()))
+            .collect();
+        config.sidecars = trimmed_paths(&self.edit_sidecars_input);
+        config.dsym_path = self.edit_dsym_path_input.trim().to_string();
+        config.dsym_placement = self.edit_dsym_placement_input;
+        config.strip_junk_files = self.edit_strip_junk_input;
+        config.keep_architectures = self.edit_keep_architectures_input.clone();
+        config.inject_dylibs = trimmed_paths(&self.edit_inject_dylibs_input);
+        config.plist_overrides = self.edit_plist_overrides_input.clone();
+        config.auto_increment_build = self.edit_auto_increment_input;
+        config.auto_rebuild = self.edit_auto_rebuild_input;
+        config.output_exists = self.edit_output_exists_input;
+        config.build_counter = self.edit_build_counter_input;
+        config.keep_last_builds = self.edit_keep_last_builds_input;
+        config.trash_old_builds = self.edit_trash_old_builds_input;
+        config.signing = self.edit_signing_input.clone();
+        config.ota_manifest = self.edit_ota_manifest_input.clone();
+        config.app_store_connect = self.edit_app_store_connect_input.clone();
+        config.object_storage = self.edit_object_storage_input.clone();
+        config.permissions = self.edit_permissions_input.clone();
+        config.webhooks = self.edit_webhooks_input.clone();
+        config.notifications = self.edit_notifications_input.clone();
+        Some(config)
+    }
+
     fn render_edit_dialog(&mut self, ctx: &egui::Context) {
         if let Some(idx) = self.show_edit_dialog_for_idx {
             let mut close_dialog = false;
             let original_app_name = self.app_configs.get(idx).map_or_else(String::new, |ac| ac.app_name.clone());
-            let app_id_to_edit = self.app_configs.get(idx).map(|ac| ac.id.clone());
 
             egui::Window::new(format!("Edit Configuration: {}", original_app_name))
                 .collapsible(false)
                 .resizable(true)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
-                    ui.label("Application Name:");
-                    ui.text_edit_singleline(&mut self.edit_app_name_input);
+                    if !self.edit_errors.is_empty() {
+                        self.edit_errors = self.edited_config(idx).and_then(|c| c.validate().err()).unwrap_or_default();
+                    }
+                    let label = ui.label("Application Name:");
+                    ui.text_edit_singleline(&mut self.edit_app_name_input).labelled_by(label.id);
+                    render_field_error(ui, &self.edit_errors, ConfigField::AppName);
                     ui.add_space(5.0);
 
-                    ui.label("Input Runner.app.zip Path:");
+                    let label = ui.label("Input Runner.app.zip Path:");
                     ui.horizontal(|ui| {
-                        let mut display_string_for_zip_path = self.edit_input_zip_path_input.as_deref().unwrap_or("Not selected").to_string();
-                        ui.add_enabled_ui(false, |dis_ui| {
-                            dis_ui.text_edit_singleline(&mut display_string_for_zip_path);
-                        });
-                        if ui.button("Browse...").clicked() {
+                        let zip_path = self.edit_input_zip_path_input.get_or_insert_with(String::new);
+                        ui.add(egui::TextEdit::singleline(zip_path).hint_text("Path, network share or https:// URL")).labelled_by(label.id);
+                        if labeled(ui.button("Browse..."), "Browse for the Runner.app.zip").clicked() {
                             if let Some(path) = native_dialog::FileDialog::new()
                                 .add_filter("ZIP archives", &["zip"])
                                 .set_filename("Runner.app.zip")
@@ -599,35 +4200,236 @@ impl IpaBuilderApp {
                             }
                         }
                     });
+                    render_field_error(ui, &self.edit_errors, ConfigField::InputZip);
+                    ui.add_space(5.0);
+
+                    let label = ui.label("Output IPA Filename:");
+                    ui.text_edit_singleline(&mut self.edit_output_ipa_name_input).labelled_by(label.id)
+                        .on_hover_text("{bundle_id}, {short_version} and {build} are replaced with the app's Info.plist values, e.g. Shop-{short_version}+{build}.ipa");
+                    render_ipa_name_hint(ui, &mut self.edit_output_ipa_name_input);
+                    render_field_error(ui, &self.edit_errors, ConfigField::OutputIpaName);
+                    ui.horizontal(|ui| {
+                        let label = ui.label("If the IPA already exists:");
+                        egui::ComboBox::from_id_source("edit_output_exists")
+                            .selected_text(self.edit_output_exists_input.label())
+                            .show_ui(ui, |ui| {
+                                for policy in OutputExistsPolicy::ALL {
+                                    ui.selectable_value(&mut self.edit_output_exists_input, policy, policy.label());
+                                }
+                            })
+                            .response
+                            .labelled_by(label.id);
+                    });
+                    ui.horizontal(|ui| {
+                        let label = ui.label("Keep last builds:");
+                        ui.add(egui::DragValue::new(&mut self.edit_keep_last_builds_input).clamp_range(0..=999))
+                            .labelled_by(label.id)
+                            .on_hover_text("Older IPAs of this app in the output folder are removed after each successful build. 0 keeps all.");
+                        if self.edit_keep_last_builds_input > 0 {
+                            ui.checkbox(&mut self.edit_trash_old_builds_input, format!("Move them to {} instead of deleting", crate::retention::TRASH_DIR_NAME));
+                        }
+                    });
+                    ui.add_space(5.0);
+
+                    let label = ui.label("CHANGELOG Path (optional, used for release notes):");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.edit_changelog_path_input).labelled_by(label.id);
+                        if labeled(ui.button("Browse..."), "Browse for the CHANGELOG").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new()
+                                .show_open_single_file()
+                                .unwrap_or(None)
+                            {
+                                self.edit_changelog_path_input = path.to_string_lossy().into_owned();
+                            }
+                        }
+                    });
+                    ui.add_space(5.0);
+
+                    ui.label("Hook Environment Variables:")
+                        .on_hover_text("Passed to the pre/post-build hooks and usable as {{NAME}} in hook commands");
+                    let mut remove_idx = None;
+                    egui::Grid::new("edit_env_vars_grid").show(ui, |ui| {
+                        for (i, (key, value)) in self.edit_env_vars_input.iter_mut().enumerate() {
+                            ui.add(egui::TextEdit::singleline(key).hint_text("NAME").desired_width(140.0));
+                            ui.add(egui::TextEdit::singleline(value).hint_text("value").desired_width(200.0));
+                            if labeled(ui.small_button("✖"), "Remove").on_hover_text("Remove").clicked() {
+                                remove_idx = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if let Some(i) = remove_idx {
+                        self.edit_env_vars_input.remove(i);
+                    }
+                    if ui.small_button("➕ Add variable").clicked() {
+                        self.edit_env_vars_input.push((String::new(), String::new()));
+                    }
+                    render_field_error(ui, &self.edit_errors, ConfigField::EnvVars);
+                    ui.add_space(5.0);
+
+                    ui.label("Sidecar Files:")
+                        .on_hover_text("Files or folders copied to <ipa name>_sidecars/ next to every IPA and listed in its BuildInfo.json, e.g. symbol maps or release notes");
+                    let mut remove_idx = None;
+                    for (i, path) in self.edit_sidecars_input.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(path).hint_text("File or folder").desired_width(300.0));
+                            if labeled(ui.small_button("✖"), "Remove").on_hover_text("Remove").clicked() {
+                                remove_idx = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove_idx {
+                        self.edit_sidecars_input.remove(i);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.small_button("➕ Add file...").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().show_open_single_file().unwrap_or(None) {
+                                self.edit_sidecars_input.push(path.to_string_lossy().into_owned());
+                            }
+                        }
+                        if ui.small_button("➕ Add folder...").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().show_open_single_dir().unwrap_or(None) {
+                                self.edit_sidecars_input.push(path.to_string_lossy().into_owned());
+                            }
+                        }
+                    });
+                    ui.add_space(5.0);
+
+                    let label = ui.label("dSYM (optional):")
+                        .on_hover_text("Debug symbols shipped with every IPA so crash reports can be symbolicated");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.edit_dsym_path_input).hint_text(".dSYM folder or .zip").desired_width(300.0))
+                            .labelled_by(label.id);
+                        if labeled(ui.button("Folder..."), "Browse for the dSYM folder").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().show_open_single_dir().unwrap_or(None) {
+                                self.edit_dsym_path_input = path.to_string_lossy().into_owned();
+                            }
+                        }
+                        if labeled(ui.button("ZIP..."), "Browse for a ZIP of the dSYM").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().add_filter("ZIP", &["zip"]).show_open_single_file().unwrap_or(None) {
+                                self.edit_dsym_path_input = path.to_string_lossy().into_owned();
+                            }
+                        }
+                    });
+                    if !self.edit_dsym_path_input.trim().is_empty() {
+                        egui::ComboBox::from_id_source("edit_dsym_placement")
+                            .selected_text(self.edit_dsym_placement_input.label())
+                            .show_ui(ui, |ui| {
+                                for placement in DsymPlacement::ALL {
+                                    ui.selectable_value(&mut self.edit_dsym_placement_input, placement, placement.label());
+                                }
+                            });
+                    }
+                    render_field_error(ui, &self.edit_errors, ConfigField::Dsym);
+                    ui.add_space(5.0);
+
+                    ui.label("Inject Dylibs:")
+                        .on_hover_text("Copied into the app's Frameworks/ and loaded by the main executable through an added LC_LOAD_DYLIB, like insert_dylib. The app must be re-signed afterwards.");
+                    let mut remove_idx = None;
+                    for (i, path) in self.edit_inject_dylibs_input.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(path).hint_text(".dylib or .framework").desired_width(300.0));
+                            if labeled(ui.small_button("✖"), "Remove").on_hover_text("Remove").clicked() {
+                                remove_idx = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove_idx {
+                        self.edit_inject_dylibs_input.remove(i);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.small_button("➕ Add dylib...").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().add_filter("Dynamic library", &["dylib"]).show_open_single_file().unwrap_or(None) {
+                                self.edit_inject_dylibs_input.push(path.to_string_lossy().into_owned());
+                            }
+                        }
+                        if ui.small_button("➕ Add framework...").clicked() {
+                            if let Some(path) = native_dialog::FileDialog::new().show_open_single_dir().unwrap_or(None) {
+                                self.edit_inject_dylibs_input.push(path.to_string_lossy().into_owned());
+                            }
+                        }
+                    });
+                    render_field_error(ui, &self.edit_errors, ConfigField::InjectDylibs);
+                    ui.add_space(5.0);
+
+                    ui.checkbox(&mut self.edit_strip_junk_input, "Strip macOS junk from the Payload")
+                        .on_hover_text("Removes __MACOSX/, .DS_Store, ._* AppleDouble files and Thumbs.db, which ZIPs made in Finder often contain");
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Keep architectures:")
+                            .on_hover_text("Removes the other slices from fat binaries (executable, frameworks, dylibs) before packaging. None checked keeps every slice.");
+                        for arch in crate::thinning::ARCHITECTURES {
+                            let mut kept = self.edit_keep_architectures_input.iter().any(|a| a == arch);
+                            if ui.checkbox(&mut kept, arch).changed() {
+                                if kept {
+                                    self.edit_keep_architectures_input.push(arch.to_string());
+                                } else {
+                                    self.edit_keep_architectures_input.retain(|a| a != arch);
+                                }
+                            }
+                        }
+                    });
                     ui.add_space(5.0);
 
-                    ui.label("Output IPA Filename:");
-                    ui.text_edit_singleline(&mut self.edit_output_ipa_name_input);
+                    let current = self.app_configs.get(idx).and_then(|c| self.bundle_infos.get(&c.id)).cloned().unwrap_or_default();
+                    render_plist_overrides(ui, &mut self.edit_plist_overrides_input, &current);
+                    render_field_error(ui, &self.edit_errors, ConfigField::PlistOverrides);
+                    if let Some(path) = render_bundle_signing(ui, &mut self.edit_signing_input) {
+                        self.view_entitlements_file(&path);
+                    }
+                    render_permission_rules(ui, &mut self.edit_permissions_input, &mut self.edit_executable_extensions_input);
+                    render_field_error(ui, &self.edit_errors, ConfigField::Permissions);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.edit_auto_increment_input, "Increment the build number on every build")
+                            .on_hover_text("Sets CFBundleVersion to one more than the last build number, instead of any build number override above");
+                        if self.edit_auto_increment_input {
+                            let label = ui.label("Last build number:");
+                            ui.add(egui::DragValue::new(&mut self.edit_build_counter_input).clamp_range(0..=u64::MAX)).labelled_by(label.id);
+                            ui.small(format!("next: {}", self.edit_build_counter_input + 1));
+                        }
+                    });
+                    ui.checkbox(&mut self.edit_auto_rebuild_input, "Rebuild automatically when the input ZIP changes")
+                        .on_hover_text("While IPA Builder is open, a new build is queued each time this file is written or replaced. Only local files can be watched; use AutoCheck to pick up new ZIPs in a folder.");
+                    render_ota_manifest(ui, &mut self.edit_ota_manifest_input, &self.edit_app_name_input);
+                    render_field_error(ui, &self.edit_errors, ConfigField::OtaManifest);
+                    render_s3_destination(ui, &mut self.edit_object_storage_input);
+                    render_field_error(ui, &self.edit_errors, ConfigField::ObjectStorage);
+                    egui::CollapsingHeader::new(format!("Webhooks ({})", self.edit_webhooks_input.len())).id_source("edit_webhooks").show(ui, |ui| {
+                        ui.small("Called after this app's builds, in addition to the webhooks in the settings.");
+                        render_webhooks(ui, &mut self.edit_webhooks_input, "edit_webhooks_grid");
+                    });
+                    render_field_error(ui, &self.edit_errors, ConfigField::Webhooks);
+                    render_notification_preferences(ui, &mut self.edit_notifications_input, &mut self.edit_email_recipients_input);
+                    render_field_error(ui, &self.edit_errors, ConfigField::Notifications);
+                    let checking = self.asc_check_rx.is_some();
+                    if render_app_store_connect(ui, &mut self.edit_app_store_connect_input, checking, self.asc_check_result.as_ref()) {
+                        let settings = self.edit_app_store_connect_input.clone();
+                        let network = self.settings.network.clone();
+                        let (tx, rx) = std::sync::mpsc::channel();
+                        std::thread::spawn(move || {
+                            let _ = tx.send(settings.check_credentials(&network));
+                        });
+                        self.asc_check_rx = Some(rx);
+                        self.asc_check_result = None;
+                    }
+                    render_field_error(ui, &self.edit_errors, ConfigField::AppStoreConnect);
                     ui.add_space(10.0);
 
                     ui.horizontal(|ui| {
                         if ui.button("Save Changes").clicked() {
-                            let app_name = self.edit_app_name_input.trim();
-                            let zip_path = self.edit_input_zip_path_input.as_deref().map(str::trim).filter(|s| !s.is_empty());
-                            let ipa_name = self.edit_output_ipa_name_input.trim();
-
-                            if app_name.is_empty() {
-                                self.status_message = "Application name cannot be empty.".to_string();
-                            } else if zip_path.is_none() {
-                                self.status_message = "Input ZIP path must be selected.".to_string();
-                            } else if ipa_name.is_empty() || !ipa_name.ends_with(".ipa") {
-                                self.status_message = "Output IPA name must not be empty and end with .ipa".to_string();
-                            } else {
-                                if let Some(ac) = self.app_configs.get_mut(idx) {
-                                    ac.app_name = app_name.to_string();
-                                    ac.input_zip_path = zip_path.unwrap().to_string(); // Safe due to check
-                                    ac.output_ipa_name = ipa_name.to_string();
-                                    self.status_message = format!("Configuration for '{}' updated.", ac.app_name);
-                                    if let Some(id_val) = app_id_to_edit {
-                                        self.record_metric(MetricEvent::AppConfigEdited { app_id: id_val });
+                            if let Some(edited) = self.edited_config(idx) {
+                                match edited.validate() {
+                                    Err(errors) => self.edit_errors = errors,
+                                    Ok(()) => {
+                                        self.edit_errors.clear();
+                                        let input_changed = self.app_configs[idx].input_zip_path != edited.input_zip_path;
+                                        self.status_message = format!("Configuration for '{}' updated.", edited.app_name);
+                                        self.apply_config_edit(idx, edited, "dialog");
+                                        if input_changed {
+                                            self.refresh_bundle_info_from_input(idx);
+                                        }
+                                        close_dialog = true;
                                     }
                                 }
-                                close_dialog = true;
                             }
                         }
                         if ui.button("Cancel").clicked() {
@@ -650,43 +4452,297 @@ impl IpaBuilderApp {
         }
     }
 
-    fn render_delete_confirm_dialog(&mut self, ctx: &egui::Context) {
-        if let Some(idx) = self.show_delete_confirm_for_idx {
-            if let Some(app_to_delete_ref) = self.app_configs.get(idx) { 
-                let app_name_for_dialog = app_to_delete_ref.app_name.clone(); // For dialog display
-                let mut close_dialog = false;
-
-                egui::Window::new(format!("Confirm Delete: '{}'", app_name_for_dialog))
-                    .collapsible(false)
-                    .resizable(false)
-                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
-                    .show(ctx, |ui| {
-                        ui.label(format!("Are you sure you want to delete the application '{}'?", app_name_for_dialog));
-                        ui.add_space(10.0);
-                        ui.label("This action cannot be undone.");
-                        ui.add_space(10.0);
+    fn render_delete_confirm_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(idx) = self.show_delete_confirm_for_idx {
+            if let Some(app_to_delete_ref) = self.app_configs.get(idx) { 
+                let app_name_for_dialog = app_to_delete_ref.app_name.clone(); // For dialog display
+                let mut close_dialog = false;
+
+                egui::Window::new(format!("Confirm Delete: '{}'", app_name_for_dialog))
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        ui.label(format!("Are you sure you want to delete the application '{}'?", app_name_for_dialog));
+                        ui.add_space(10.0);
+                        ui.label(format!("You can undo this with {} until IPA Builder is closed.", ctx.format_shortcut(&crate::accessibility::UNDO)));
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Delete").clicked() {
+                                let deleted_app_name = self.app_configs[idx].app_name.clone(); // Capture name just before removal
+                                if let Some(change) = ConfigChange::deleted(idx, &self.app_configs, &self.projects) {
+                                    self.undo_stack.push(change);
+                                }
+                                let deleted = self.app_configs.remove(idx);
+                                self.bundle_infos.remove(&deleted.id);
+                                self.profiles.remove(&deleted.id);
+                                for project in &mut self.projects {
+                                    project.set_member(&deleted.id, false);
+                                }
+                                self.audit_log.record(AuditAction::ConfigDeleted { app_id: deleted.id, app_name: deleted_app_name.clone() });
+                                self.status_message = format!("Application '{}' deleted.", deleted_app_name);
+                                self.metrics_collector.record(MetricEvent::AppRemoved { app_name: deleted_app_name });
+                                close_dialog = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                close_dialog = true;
+                            }
+                        });
+                    });
+
+                if close_dialog {
+                    self.show_delete_confirm_for_idx = None;
+                }
+            } else {
+                self.show_delete_confirm_for_idx = None; // Index out of bounds, close dialog
+                self.status_message = "Error: Could not find app to delete.".to_string();
+            }
+        }
+    }
+
+    fn render_settings_dialog(&mut self, ctx: &egui::Context) {
+        if !self.show_settings_dialog {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.settings_tab, SettingsTab::General, "⚙ General");
+                    ui.selectable_value(&mut self.settings_tab, SettingsTab::Notifications, "🔔 Notifications");
+                });
+                ui.separator();
+                ui.add_enabled_ui(!self.viewer_mode, |ui| {
+                    if self.settings_tab == SettingsTab::Notifications {
+                        ui.heading("Webhooks");
+                        ui.small("Called after every build that notifies webhooks; apps choose which builds do, and can add their own webhooks, in the Edit dialog. JSON receives the full build event; Slack and Discord get a formatted message with the app icon, version, size and download link.");
+                        render_webhooks(ui, &mut self.settings.webhooks, "settings_webhooks_grid");
+
+                        ui.heading("Email");
+                        ui.small("Used by apps that send email notifications; each app picks its recipients and events in the Edit dialog.");
+                        render_email_settings(ui, &mut self.settings.email);
+                        return;
+                    }
+                    ui.heading("Output");
+                    ui.checkbox(&mut self.settings.stage_output_locally, "Build locally, then copy to the output directory")
+                        .on_hover_text("Recommended when the output directory is an SMB/NFS share: the copy is verified with SHA-256 and retried with backoff.");
+                    ui.add_enabled_ui(self.settings.stage_output_locally, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Copy attempts:");
+                            ui.add(egui::DragValue::new(&mut self.settings.output_copy_attempts).clamp_range(1..=10));
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Compression: {}", self.settings.compression.summary()));
+                        if ui.button("🗜 Compression…").clicked() {
+                            self.compression_extensions_input = self.settings.compression.store_extensions.join(", ");
+                            self.show_compression_dialog = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Warn when a build takes longer than");
+                        ui.add(egui::DragValue::new(&mut self.settings.build_watchdog_multiplier).speed(0.1).clamp_range(0.0..=20.0).suffix("×"));
+                        ui.label("its average (0 = off)");
+                    })
+                    .response
+                    .on_hover_text("Compares with the app's last ten successful builds, and never warns before a minute. Catches builds hanging on network-mounted temp or output folders.");
+                    ui.checkbox(&mut self.settings.deterministic_output, "Reproducible output (byte-identical IPAs for the same input)")
+                        .on_hover_text("Writes entries in a stable order with a fixed timestamp (SOURCE_DATE_EPOCH when set, otherwise 1980-01-01), also used as the BuildInfo build time. Useful for caching and for verifying builds in CI.");
+                    ui.checkbox(&mut self.settings.package_from_input_zip, "Package straight from the input ZIP when possible")
+                        .on_hover_text("Copies the .app entries into the IPA still compressed instead of extracting and recompressing them. Used when the ZIP holds a single .app with Unix permissions and neither App Size option below is on.");
+                    if ui.checkbox(&mut self.settings.sync_metrics_writes, "Flush usage metrics to disk after every event")
+                        .on_hover_text("Waits for each metrics entry to reach the disk, so a power loss can't lose it. Turn off on slow network home directories.")
+                        .changed()
+                    {
+                        self.metrics_collector.sync_writes = self.settings.sync_metrics_writes;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Keep build history and metrics for:");
+                        ui.add(egui::DragValue::new(&mut self.settings.history_retention_days).clamp_range(0..=3650).suffix(" days"))
+                            .on_hover_text("Older builds and metrics are deleted when IPA Builder starts. 0 keeps everything.");
+                    });
+
+                    ui.heading("Display");
+                    render_time_zone_setting(ui, &mut self.settings.time_zone);
+                    ui.checkbox(&mut self.settings.relative_times, "Show relative times in the table (\"2 hours ago\")")
+                        .on_hover_text("The exact time is shown when hovering over a date.");
+                    ui.checkbox(&mut self.settings.high_contrast, "High contrast")
+                        .on_hover_text("Black and white with outlined controls; follows the light/dark switch.");
+                    ui.checkbox(&mut self.settings.reduce_motion, "Reduce motion")
+                        .on_hover_text("Turns off animations and replaces spinners with a static ⏳.");
+
+                    ui.heading("Install Pages");
+                    ui.horizontal(|ui| {
+                        ui.label("Base URL:");
+                        ui.text_edit_singleline(&mut self.settings.install_page_base_url)
+                            .on_hover_text("HTTPS folder the exported <name>_install bundle will be served from, e.g. https://builds.example.com/shop");
+                    });
+                    render_branding_settings(ui, &mut self.settings.branding);
+                    #[cfg(feature = "ota-server")]
+                    {
+                        ui.heading("OTA Server");
+                        render_ota_server_settings(ui, &mut self.settings.ota_server);
+                    }
+
+                    ui.heading("App Size");
+                    ui.checkbox(&mut self.settings.dedupe_frameworks, "Remove duplicate frameworks from app extensions")
+                        .on_hover_text("Only for unsigned extensions that already load frameworks from the host app (@executable_path/../../Frameworks). Duplicates are always reported as build warnings.");
+                    ui.checkbox(&mut self.settings.exclude_debug_symbols, "Exclude debug symbols (.dSYM, .bcsymbolmap, .swiftmodule) from the Payload")
+                        .on_hover_text("These are sometimes copied into the .app by mistake. Excluded items are listed in the build warnings; when off, they are reported there too.");
+                    ui.checkbox(&mut self.settings.strip_bitcode, "Strip bitcode from the executable and frameworks")
+                        .on_hover_text("Removes the __LLVM segments of apps built with bitcode, like xcrun bitcode_strip -r. The App Store no longer accepts bitcode, and it often takes up most of a binary.");
+
+                    ui.heading("Channels");
+                    ui.small("Pick a channel on an app's row before building. It fills {channel} in output names, is recorded in the history, and decides which of the app's upload destinations the build goes to, instead of their \"after every build\" settings.");
+                    render_build_channels(ui, &mut self.settings.channels);
+
+                    ui.heading("Build Hooks");
+                    ui.small("Run through the system shell. Use {{IPA_BUILDER_APP_NAME}}, {{IPA_BUILDER_OUTPUT_IPA}} or any per-app variable; they are also set as environment variables.");
+                    egui::Grid::new("settings_hooks_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Pre-build:");
+                        ui.text_edit_singleline(&mut self.settings.pre_build_hook)
+                            .on_hover_text("A non-zero exit status aborts the build");
+                        ui.end_row();
+                        ui.label("Post-build:");
+                        ui.text_edit_singleline(&mut self.settings.post_build_hook)
+                            .on_hover_text("IPA_BUILDER_SUCCESS is 1 or 0. After a successful build, IPA_BUILDER_BUNDLE_ID, IPA_BUILDER_SHORT_VERSION and IPA_BUILDER_BUILD hold the app's Info.plist values.");
+                        ui.end_row();
+                    });
+
+                    ui.heading("Remote Build Agent");
+                    ui.small("Send builds to a machine running `ipa_builder agent`. Leave the URL empty to build locally. Hooks still run here.");
+                    if self.settings.network.offline {
+                        ui.colored_label(ui.visuals().warn_fg_color, "✈ Disabled by offline mode; builds run on this machine.");
+                    }
+                    egui::Grid::new("settings_agent_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Agent URL:");
+                        ui.add(egui::TextEdit::singleline(&mut self.settings.remote_agent_url).hint_text("http://buildbox:8750"));
+                        ui.end_row();
+                        ui.label("Token:");
+                        ui.add(egui::TextEdit::singleline(&mut self.settings.remote_agent_token).password(true))
+                            .on_hover_text("The agent's --token or IPA_BUILDER_AGENT_TOKEN");
+                        ui.end_row();
+                    });
+
+                    ui.heading("Network");
+                    ui.small("Used for agent uploads and URL inputs. Leave the proxy empty to use HTTPS_PROXY / HTTP_PROXY from the environment.");
+                    render_network_settings(ui, &mut self.settings.network);
+                    ui.horizontal(|ui| {
+                        ui.label("Upload limit:");
+                        ui.add(egui::DragValue::new(&mut self.settings.upload_limit_kb_per_sec).clamp_range(0..=1_048_576).suffix(" KB/s"))
+                            .on_hover_text("Caps uploads of input ZIPs to the agent and of IPAs to buckets and share services, so they don't saturate a shared link. 0 is unlimited.");
+                    });
+
+                    ui.heading("Sharing");
+                    ui.small("Uploads device builds to a share service for a short install link, shown with a QR code under the last build.");
+                    render_share_settings(ui, &mut self.settings.share);
+
+                    ui.heading("Signing");
+                    ui.small("Writes a detached signature next to every IPA, so recipients can check it came from this machine.");
+                    render_artifact_signing_settings(ui, &mut self.settings.artifact_signing);
+                    ui.checkbox(&mut self.settings.write_checksum_file, "Write a SHA-256 checksum file next to every IPA")
+                        .on_hover_text("Writes <name>.ipa.sha256, which recipients can check with `shasum -a 256 -c`. The digest is also kept in the build history.");
+
+                    ui.heading("Input Cache");
+                    ui.small("Inputs given as http(s):// URLs or network paths are downloaded once and reused until they change.");
+                    ui.horizontal(|ui| {
+                        ui.label("Size limit (MB):");
+                        ui.add(egui::DragValue::new(&mut self.settings.input_cache_max_mb).clamp_range(64..=1_048_576));
+                    });
+                    self.render_input_cache_status(ui);
+                });
+            });
+        if !open {
+            self.show_settings_dialog = false;
+        }
+    }
+
+    fn render_compression_dialog(&mut self, ctx: &egui::Context) {
+        if !self.show_compression_dialog {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Compression")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!self.viewer_mode, |ui| {
+                    let compression = &mut self.settings.compression;
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut compression.method, PayloadCompression::Deflated, "Deflate");
+                        ui.radio_value(&mut compression.method, PayloadCompression::Stored, "Store (no compression)")
+                            .on_hover_text("Fastest, and the largest IPA");
+                    });
+                    ui.add_enabled_ui(compression.method == PayloadCompression::Deflated, |ui| {
+                        ui.add(egui::Slider::new(&mut compression.level, 0..=9).text("Level"))
+                            .on_hover_text("0 is fastest, 9 gives the smallest IPA. 6 is the usual default.");
+                        ui.label("Always store files with these extensions:");
                         ui.horizontal(|ui| {
-                            if ui.button("Delete").clicked() {
-                                let deleted_app_name = self.app_configs[idx].app_name.clone(); // Capture name just before removal
-                                self.app_configs.remove(idx);
-                                self.status_message = format!("Application '{}' deleted.", deleted_app_name);
-                                self.metrics_collector.record(MetricEvent::AppRemoved { app_name: deleted_app_name });
-                                close_dialog = true;
+                            if ui.add(egui::TextEdit::singleline(&mut self.compression_extensions_input).hint_text("png, jpg, car")).changed() {
+                                compression.store_extensions = parse_extension_list(&self.compression_extensions_input);
                             }
-                            if ui.button("Cancel").clicked() {
-                                close_dialog = true;
+                            if ui.button("Already-compressed formats").on_hover_text(ALREADY_COMPRESSED_EXTENSIONS.join(", ")).clicked() {
+                                compression.store_extensions = ALREADY_COMPRESSED_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+                                self.compression_extensions_input = compression.store_extensions.join(", ");
                             }
                         });
+                        ui.small("Images, asset catalogs and media are usually compressed already; storing them saves build time at almost no size cost.");
                     });
+                    ui.small("Inputs packaged straight from the ZIP keep the ZIP's own compression.");
+                });
+            });
+        if !open {
+            self.show_compression_dialog = false;
+        }
+    }
 
-                if close_dialog {
-                    self.show_delete_confirm_for_idx = None;
-                }
-            } else {
-                self.show_delete_confirm_for_idx = None; // Index out of bounds, close dialog
-                self.status_message = "Error: Could not find app to delete.".to_string();
-            }
+    fn render_input_cache_status(&mut self, ui: &mut egui::Ui) {
+        let Some(cache) = self.settings.input_cache() else {
+            ui.label("No data directory; the input cache is unavailable.");
+            return;
+        };
+        let entries = self.input_cache_entries.get_or_insert_with(|| cache.entries());
+        let total = crate::input_cache::blob_bytes(entries);
+        ui.label(format!(
+            "{} cached input(s), {} of {}",
+            entries.len(),
+            crate::size_analysis::format_size(total),
+            crate::size_analysis::format_size(cache.max_bytes)
+        ));
+        if !entries.is_empty() {
+            egui::ScrollArea::vertical().id_source("input_cache_entries").max_height(120.0).show(ui, |ui| {
+                egui::Grid::new("input_cache_grid").num_columns(4).striped(true).show(ui, |ui| {
+                    for entry in entries.iter() {
+                        ui.label(&entry.file_name).on_hover_text(&entry.source);
+                        ui.label(crate::size_analysis::format_size(entry.size));
+                        ui.label(format!("{} hit(s)", entry.hits));
+                        ui.label(format!("used {}", self.settings.time_zone.display(entry.last_used)));
+                        ui.end_row();
+                    }
+                });
+            });
         }
+        let busy = self.build_runner.is_some();
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!busy, egui::Button::new("Evict now")).on_hover_text("Drop least recently used inputs beyond the size limit").clicked() {
+                self.status_message = match cache.evict() {
+                    Ok(freed) => format!("Input cache: freed {}.", crate::size_analysis::format_size(freed)),
+                    Err(e) => e,
+                };
+                self.input_cache_entries = None;
+            }
+            if ui.add_enabled(!busy, egui::Button::new("Clear cache")).clicked() {
+                self.status_message = match cache.clear() {
+                    Ok(()) => "Input cache cleared.".to_string(),
+                    Err(e) => e,
+                };
+                self.input_cache_entries = None;
+            }
+        });
     }
 
     fn render_config_dialog(&mut self, ctx: &egui::Context) {
@@ -716,6 +4772,9 @@ impl IpaBuilderApp {
                         }
                     }
                 });
+                if ui.button("🧰 Diagnostics").on_hover_text("Check the file dialog helper, directory permissions and tools").clicked() {
+                    self.run_diagnostics();
+                }
                 
                 if ui.button("Save Configuration").clicked() {
                     let path = PathBuf::from(&self.config_dialog_output_dir_input);
@@ -723,6 +4782,7 @@ impl IpaBuilderApp {
                         self.output_directory = Some(path.to_string_lossy().into_owned());
                         self.show_config_dialog = false;
                         self.status_message = "Output directory configured.".to_string();
+                        self.restart_output_watcher();
                         // self.save_state(); // Removed, eframe::App::save handles state persistence
                         self.record_metric(MetricEvent::OutputDirectorySet);
                     } else {
@@ -734,3 +4794,877 @@ impl IpaBuilderApp {
     }
 }
 
+/// Inline progress bar, elapsed time and cancel button for the row whose build is running.
+fn render_row_build_progress(ui: &mut egui::Ui, runner: &BuildRunner) {
+    let progress = runner.progress();
+    let fraction = progress.map(|p| p.overall_fraction()).unwrap_or(0.0);
+    ui.add(egui::ProgressBar::new(fraction).desired_width(80.0).show_percentage())
+        .on_hover_text(progress.map_or_else(|| "Starting".to_string(), |p| format!("{} {}", p.phase.label(), p.detail())));
+    ui.label(format!("{}s", runner.elapsed().as_secs()));
+    if runner.is_cancelling() {
+        ui.add_enabled(false, egui::Button::new("✖")).on_disabled_hover_text("Cancelling…");
+    } else if labeled(ui.button("✖"), "Cancel build").on_hover_text("Cancel build").clicked() {
+        runner.cancel();
+    }
+}
+
+/// A horizontal bar `fraction` of the full width long, with an outline at `previous` for
+/// comparison with an earlier build.
+fn render_size_bar(ui: &mut egui::Ui, fraction: f32, previous: Option<f32>) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(200.0, 14.0), egui::Sense::hover());
+    let bar = |fraction: f32| egui::Rect::from_min_size(rect.min, egui::vec2(rect.width() * fraction.clamp(0.0, 1.0), rect.height()));
+    ui.painter().rect_filled(bar(fraction), 2.0, ui.visuals().selection.bg_fill);
+    if let Some(previous) = previous {
+        ui.painter().rect_stroke(bar(previous), 2.0, ui.visuals().widgets.active.fg_stroke);
+    }
+}
+
+/// Days shown at most by the history chart, the most recent ones.
+const CHART_MAX_DAYS: usize = 90;
+
+/// A bar per day from the first to the last of `days`, as tall as its builds, with failed
+/// builds in the error color at the bottom. Hovering shows the day's totals.
+fn render_daily_chart(ui: &mut egui::Ui, days: &[DailyTotals]) {
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return;
+    };
+    let span = ((last.day - first.day).num_days() as usize + 1).min(CHART_MAX_DAYS);
+    let start = last.day - chrono::Days::new(span as u64 - 1);
+    let mut totals = vec![None; span];
+    for totals_of_day in days.iter().filter(|d| d.day >= start) {
+        totals[(totals_of_day.day - start).num_days() as usize] = Some(*totals_of_day);
+    }
+    let most = days.iter().map(|d| d.builds).max().unwrap_or(1).max(1);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width().max(200.0), 60.0), egui::Sense::hover());
+    let slot = rect.width() / span as f32;
+    let bar_height = |count: usize| rect.height() * count as f32 / most as f32;
+    for (i, day) in totals.iter().enumerate() {
+        let Some(day) = day else {
+            continue;
+        };
+        let x = rect.left() + i as f32 * slot;
+        let bar = |count: usize| egui::Rect::from_min_max(egui::pos2(x + slot * 0.1, rect.bottom() - bar_height(count)), egui::pos2(x + slot * 0.9, rect.bottom()));
+        ui.painter().rect_filled(bar(day.builds), 1.0, ui.visuals().selection.bg_fill);
+        if day.failed > 0 {
+            ui.painter().rect_filled(bar(day.failed), 1.0, ui.visuals().error_fg_color);
+        }
+    }
+    ui.painter().hline(rect.x_range(), rect.bottom(), ui.visuals().widgets.noninteractive.bg_stroke);
+    if let Some(pos) = response.hover_pos() {
+        let i = (((pos.x - rect.left()) / slot) as usize).min(span - 1);
+        let day = start + chrono::Days::new(i as u64);
+        let text = match totals[i] {
+            Some(totals) => format!("{}: {} build(s), {} failed", day, totals.builds, totals.failed),
+            None => format!("{}: no builds", day),
+        };
+        response.on_hover_text_at_pointer(text);
+    }
+}
+
+/// One entry of the Inspect window: a collapsible folder, or a file row, with its uncompressed
+/// size and how well it compressed. `Payload/` and the app start expanded.
+fn render_content_node(ui: &mut egui::Ui, node: &ContentNode) {
+    let ratio = node.compression_ratio().map_or_else(|| "—".to_string(), |r| format!("{:.0}%", r * 100.0));
+    let summary = format!("{}  ·  {} compressed", crate::size_analysis::format_size(node.size), ratio);
+    if node.is_dir {
+        let header = format!("📁 {}  ({} files, {})", node.name, node.file_count(), summary);
+        egui::CollapsingHeader::new(header).id_source(&node.path).default_open(node.path.starts_with("Payload") && node.path.matches('/').count() < 2).show(ui, |ui| {
+            for child in &node.children {
+                render_content_node(ui, child);
+            }
+        });
+    } else {
+        ui.horizontal(|ui| {
+            ui.label(format!("📄 {}", node.name));
+            ui.weak(summary);
+        })
+        .response
+        .on_hover_text(&node.path);
+    }
+}
+
+/// Preset ranges plus a custom From/To, both `YYYY-MM-DD`, that is applied once both dates parse.
+fn render_date_range_picker(ui: &mut egui::Ui, range: &mut DateRange, inputs: &mut (String, String), today: chrono::NaiveDate) {
+    ui.horizontal(|ui| {
+        ui.label("Show:");
+        for preset in [DateRange::All, DateRange::Today, DateRange::Last7Days, DateRange::Last30Days] {
+            if ui.selectable_label(*range == preset, preset.label()).clicked() {
+                *range = preset;
+            }
+        }
+        let is_custom = matches!(range, DateRange::Custom { .. });
+        if ui.selectable_label(is_custom, "Custom").clicked() && !is_custom {
+            let from = today.checked_sub_days(chrono::Days::new(6)).unwrap_or(today);
+            *inputs = (from.to_string(), today.to_string());
+            *range = DateRange::Custom { from, to: today };
+        }
+    });
+    if let DateRange::Custom { .. } = range {
+        ui.horizontal(|ui| {
+            ui.label("From:");
+            let from_changed = ui.add(egui::TextEdit::singleline(&mut inputs.0).hint_text("YYYY-MM-DD").desired_width(90.0)).changed();
+            ui.label("To:");
+            let to_changed = ui.add(egui::TextEdit::singleline(&mut inputs.1).hint_text("YYYY-MM-DD").desired_width(90.0)).changed();
+            match (inputs.0.trim().parse::<chrono::NaiveDate>(), inputs.1.trim().parse::<chrono::NaiveDate>()) {
+                (Ok(from), Ok(to)) if from <= to => {
+                    if from_changed || to_changed {
+                        *range = DateRange::Custom { from, to };
+                    }
+                }
+                (Ok(_), Ok(_)) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, "From is after To");
+                }
+                _ => {
+                    ui.colored_label(ui.visuals().warn_fg_color, "Dates are YYYY-MM-DD");
+                }
+            }
+        });
+    }
+}
+
+/// Local time, UTC or a named IANA zone for every timestamp shown or exported.
+fn render_time_zone_setting(ui: &mut egui::Ui, zone: &mut DisplayTimeZone) {
+    ui.horizontal(|ui| {
+        ui.label("Show times in:");
+        if ui.radio(*zone == DisplayTimeZone::Local, "Local time").clicked() {
+            *zone = DisplayTimeZone::Local;
+        }
+        if ui.radio(*zone == DisplayTimeZone::Utc, "UTC").clicked() {
+            *zone = DisplayTimeZone::Utc;
+        }
+        let named = matches!(zone, DisplayTimeZone::Named(_));
+        if ui.radio(named, "Time zone").clicked() && !named {
+            *zone = DisplayTimeZone::Named(String::new());
+        }
+        if let DisplayTimeZone::Named(name) = zone {
+            ui.add(egui::TextEdit::singleline(name).hint_text("Europe/Paris").desired_width(140.0));
+        }
+    });
+    if let Some(problem) = zone.problem() {
+        ui.colored_label(ui.visuals().warn_fg_color, problem);
+    }
+    ui.small(format!("Now: {} — history is stored in UTC either way.", zone.display(Utc::now())));
+}
+
+/// Tool and key for the detached signature of every IPA.
+/// Hover text of the top bar's offline indicator.
+fn offline_mode_summary() -> String {
+    let mut text = "Offline mode is on. Disabled:".to_string();
+    for feature in OFFLINE_DISABLED_FEATURES {
+        text.push_str("\n• ");
+        text.push_str(feature);
+    }
+    text
+}
+
+fn render_network_settings(ui: &mut egui::Ui, network: &mut NetworkSettings) {
+    ui.checkbox(&mut network.offline, "✈ Offline mode")
+        .on_hover_text("For air-gapped machines: no network request is made at all");
+    if network.offline {
+        for feature in OFFLINE_DISABLED_FEATURES {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("• {}", feature));
+        }
+        return;
+    }
+    egui::Grid::new("settings_network_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Proxy:");
+        ui.add(egui::TextEdit::singleline(&mut network.proxy_url).hint_text("http://proxy.corp:3128"))
+            .on_hover_text("user:password@ may be included in the URL");
+        ui.end_row();
+        ui.label("CA bundle:");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut network.ca_bundle_path).hint_text("/path/to/corporate-ca.pem"))
+                .on_hover_text("PEM certificates trusted in addition to the built-in roots, e.g. your TLS-inspecting proxy's CA");
+            if labeled(ui.button("Browse..."), "Browse for a CA bundle").clicked() {
+                if let Ok(Some(path)) = native_dialog::FileDialog::new().add_filter("PEM certificates", &["pem", "crt", "cer"]).show_open_single_file() {
+                    network.ca_bundle_path = path.to_string_lossy().to_string();
+                }
+            }
+        });
+        ui.end_row();
+    });
+    if let Err(e) = network.http_agent() {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+}
+
+fn render_share_settings(ui: &mut egui::Ui, share: &mut crate::share::ShareSettings) {
+    ui.horizontal(|ui| {
+        for service in ShareService::ALL {
+            ui.radio_value(&mut share.service, service, service.label());
+        }
+    });
+    if share.service == ShareService::Diawi {
+        render_diawi_settings(ui, &mut share.diawi);
+    }
+    if share.service != ShareService::None {
+        ui.checkbox(&mut share.upload_after_build, "Upload every successful build")
+            .on_hover_text("Simulator builds are never uploaded");
+        if let Err(e) = share.validate() {
+            ui.colored_label(ui.visuals().warn_fg_color, e);
+        }
+    }
+}
+
+fn render_diawi_settings(ui: &mut egui::Ui, diawi: &mut DiawiSettings) {
+    egui::Grid::new("settings_diawi_grid").num_columns(2).show(ui, |ui| {
+        ui.label("API token:");
+        ui.add(egui::TextEdit::singleline(&mut diawi.token).password(true))
+            .on_hover_text("Create one on diawi.com under Profile → API access");
+        ui.end_row();
+        ui.label("Password:");
+        ui.add(egui::TextEdit::singleline(&mut diawi.password).password(true))
+            .on_hover_text("Testers enter it before installing. Leave empty for none.");
+        ui.end_row();
+        ui.label("Comment:");
+        ui.add(egui::TextEdit::singleline(&mut diawi.comment).hint_text("Shown on the install page"));
+        ui.end_row();
+    });
+    ui.checkbox(&mut diawi.find_by_udid, "Let testers find the app by their device's UDID");
+    ui.checkbox(&mut diawi.wall_of_apps, "List on the account's Wall of Apps");
+}
+
+fn render_artifact_signing_settings(ui: &mut egui::Ui, signing: &mut ArtifactSigning) {
+    ui.horizontal(|ui| {
+        for tool in SignatureTool::ALL {
+            ui.radio_value(&mut signing.tool, tool, tool.label());
+        }
+    });
+    match signing.tool {
+        SignatureTool::None => {}
+        SignatureTool::Minisign => {
+            ui.horizontal(|ui| {
+                let label = ui.label("Secret key:");
+                ui.add(egui::TextEdit::singleline(&mut signing.key).hint_text("/path/to/minisign.key"))
+                    .labelled_by(label.id)
+                    .on_hover_text("Create it with `minisign -G -W`: a key with a password cannot be used without a terminal.");
+                if labeled(ui.button("Browse..."), "Browse for the minisign secret key").clicked() {
+                    if let Ok(Some(path)) = native_dialog::FileDialog::new().add_filter("minisign keys", &["key"]).show_open_single_file() {
+                        signing.key = path.to_string_lossy().to_string();
+                    }
+                }
+            });
+            ui.small("Writes <name>.ipa.minisig; verify with `minisign -Vm <name>.ipa -p <public key>`.");
+        }
+        SignatureTool::Gpg => {
+            ui.horizontal(|ui| {
+                let label = ui.label("Key:");
+                ui.add(egui::TextEdit::singleline(&mut signing.key).hint_text("build@example.com"))
+                    .labelled_by(label.id)
+                    .on_hover_text("Key id, fingerprint or email; empty for the default key. Passphrases are asked by gpg-agent.");
+            });
+            ui.small("Writes <name>.ipa.asc; verify with `gpg --verify <name>.ipa.asc`.");
+        }
+    }
+}
+
+/// Organization name, logo and colors applied to exported install pages and QR codes.
+#[cfg(feature = "ota-server")]
+fn render_ota_server_settings(ui: &mut egui::Ui, settings: &mut crate::settings::OtaServerSettings) {
+    ui.small("iOS only installs over HTTPS with a certificate the device trusts, e.g. one made with mkcert whose root is installed on the test devices. Changes apply the next time the server starts.");
+    egui::Grid::new("ota_server_settings_grid").num_columns(3).show(ui, |ui| {
+        let label = ui.label("Port:");
+        ui.add(egui::DragValue::new(&mut settings.port).clamp_range(1..=u16::MAX)).labelled_by(label.id);
+        ui.end_row();
+        for (label, name, value) in [("Certificate (PEM):", "certificate", &mut settings.cert_path), ("Private key (PEM):", "private key", &mut settings.key_path)] {
+            let label = ui.label(label);
+            ui.text_edit_singleline(value).labelled_by(label.id);
+            if labeled(ui.button("Browse..."), &format!("Browse for the {}", name)).clicked() {
+                if let Ok(Some(path)) = native_dialog::FileDialog::new().add_filter("PEM", &["pem", "crt", "key"]).show_open_single_file() {
+                    *value = path.to_string_lossy().into_owned();
+                }
+            }
+            ui.end_row();
+        }
+        let label = ui.label("Public URL:");
+        let default_url = crate::ota_server::landing_url(settings, settings.port);
+        ui.add(egui::TextEdit::singleline(&mut settings.public_url).hint_text(default_url))
+            .labelled_by(label.id)
+            .on_hover_text("Address devices reach this machine at, matching the certificate's host name");
+        ui.end_row();
+    });
+    ui.checkbox(&mut settings.start_on_launch, "Start serving when IPA Builder opens");
+}
+
+fn render_branding_settings(ui: &mut egui::Ui, branding: &mut crate::branding::Branding) {
+    egui::Grid::new("settings_branding_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Organization:");
+        ui.add(egui::TextEdit::singleline(&mut branding.org_name).hint_text("Shown in page titles and footers"));
+        ui.end_row();
+        ui.label("Logo:");
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut branding.logo_path);
+            if ui.button("Browse...").clicked() {
+                if let Ok(Some(path)) = native_dialog::FileDialog::new().add_filter("Images", &["png", "jpg", "jpeg", "svg"]).show_open_single_file() {
+                    branding.logo_path = path.to_string_lossy().into_owned();
+                }
+            }
+        });
+        ui.end_row();
+        for (label, color, hint) in [
+            ("Accent color:", &mut branding.accent_color, "Buttons and links, in light and dark mode"),
+            ("QR code color:", &mut branding.qr_color, "Drawn on white; light colors fall back to black"),
+        ] {
+            ui.label(label);
+            ui.horizontal(|ui| {
+                let mut rgb = crate::branding::parse_hex_color(color).unwrap_or([0, 0, 0]);
+                if ui.color_edit_button_srgb(&mut rgb).on_hover_text(hint).changed() {
+                    *color = crate::branding::to_hex(rgb);
+                }
+                ui.add(egui::TextEdit::singleline(color).desired_width(70.0));
+            });
+            ui.end_row();
+        }
+    });
+    for problem in branding.problems() {
+        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", problem));
+    }
+}
+
+/// Shows why an output IPA name is not portable, with a button applying the sanitized suggestion.
+/// Splits `png, .JPG car` into `["png", "jpg", "car"]`.
+fn parse_extension_list(input: &str) -> Vec<String> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Shows the [`AppConfig::validate`] message for `field`, if any, in red below its input.
+fn render_field_error(ui: &mut egui::Ui, errors: &ConfigErrors, field: ConfigField) {
+    if let Some(message) = errors.get(&field) {
+        ui.colored_label(ui.visuals().error_fg_color, message);
+    }
+}
+
+fn render_ipa_name_hint(ui: &mut egui::Ui, ipa_name: &mut String) {
+    if let Err(reason) = validate_ipa_file_name(ipa_name.trim()) {
+        let suggestion = sanitize_ipa_file_name(ipa_name);
+        ui.horizontal_wrapped(|ui| {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", reason));
+            if ui.small_button(format!("Use '{}'", suggestion)).clicked() {
+                *ipa_name = suggestion;
+            }
+        });
+    }
+}
+
+/// The "Info.plist overrides" section of the Edit dialog. Empty fields keep the bundle's value,
+/// which is shown as the hint when known.
+fn render_plist_overrides(ui: &mut egui::Ui, overrides: &mut PlistOverrides, current: &BundleInfo) {
+    let title = if overrides.is_empty() { "Info.plist overrides".to_string() } else { "Info.plist overrides (active)".to_string() };
+    egui::CollapsingHeader::new(title).id_source("edit_plist_overrides").show(ui, |ui| {
+        ui.small("Written into the copied Info.plist before packaging. Leave a field empty to keep the app's value.");
+        egui::Grid::new("edit_plist_overrides_grid").num_columns(2).show(ui, |ui| {
+            for (label, value, hint) in [
+                ("Display name:", &mut overrides.display_name, &current.display_name),
+                ("Bundle ID:", &mut overrides.bundle_identifier, &current.bundle_identifier),
+                ("Version:", &mut overrides.short_version, &current.short_version),
+                ("Build number:", &mut overrides.bundle_version, &current.bundle_version),
+            ] {
+                let label = ui.label(label);
+                ui.add(egui::TextEdit::singleline(value).hint_text(hint.as_deref().unwrap_or("unchanged"))).labelled_by(label.id);
+                ui.end_row();
+            }
+        });
+        if let Err(reason) = overrides.validate() {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", reason));
+        }
+    });
+}
+
+/// Extra executable extensions and per-path overrides of the permissions files get in the IPA.
+fn render_permission_rules(ui: &mut egui::Ui, rules: &mut PermissionRules, extensions_input: &mut String) {
+    let title = if rules.is_empty() { "File permissions".to_string() } else { format!("File permissions ({})", rules.summary()) };
+    egui::CollapsingHeader::new(title).id_source("edit_permission_rules").show(ui, |ui| {
+        ui.small("Mach-O binaries and .dylib files are always made executable (0755), everything else 0644. Add rules for scripts and other files that must stay executable.");
+        ui.horizontal(|ui| {
+            let label = ui.label("Also executable:");
+            if ui.add(egui::TextEdit::singleline(extensions_input).hint_text("sh, node")).labelled_by(label.id).changed() {
+                rules.executable_extensions = parse_extension_list(extensions_input);
+            }
+        });
+        let mut remove = None;
+        egui::Grid::new("edit_permission_overrides_grid").num_columns(3).show(ui, |ui| {
+            for (i, rule) in rules.overrides.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut rule.pattern).hint_text("*/Scripts/*").desired_width(240.0))
+                    .on_hover_text("Matched against the path in the IPA, e.g. Payload/Shop.app/Scripts/run.sh; * also matches across folders");
+                egui::ComboBox::from_id_source(("permission_override_mode", i)).selected_text(rule.mode.label()).show_ui(ui, |ui| {
+                    for mode in PermissionMode::ALL {
+                        ui.selectable_value(&mut rule.mode, mode, mode.label());
+                    }
+                });
+                if labeled(ui.small_button("🗑"), "Remove this override").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = remove {
+            rules.overrides.remove(i);
+        }
+        if ui.button("➕ Add override").on_hover_text("The first matching override decides, before the rules above").clicked() {
+            rules.overrides.push(PermissionOverride::default());
+        }
+        if !rules.is_empty() {
+            ui.small("Inputs with permission rules are always extracted, not packaged straight from the ZIP.");
+        }
+    });
+}
+
+/// Identity, password file, provisioning profile and entitlements the app is signed with while
+/// packaging. Returns the entitlements file when its "View" button was clicked.
+fn render_bundle_signing(ui: &mut egui::Ui, signing: &mut BundleSigning) -> Option<PathBuf> {
+    let mut view = None;
+    let title = if signing.is_enabled() { "Code signing (active)" } else { "Code signing" };
+    egui::CollapsingHeader::new(title).id_source("edit_bundle_signing").show(ui, |ui| {
+        ui.small("Signs the app, its frameworks and extensions before packaging, without Apple's tools. Leave the identity empty to package the app as it is.");
+        egui::Grid::new("edit_bundle_signing_grid").num_columns(3).show(ui, |ui| {
+            for (label, name, value, extensions) in [
+                ("Identity (.p12/.pem):", "signing identity", &mut signing.identity_path, &["p12", "pfx", "pem"][..]),
+                ("Password file:", "password file", &mut signing.password_file, &[][..]),
+                ("Provisioning profile:", "provisioning profile", &mut signing.provisioning_profile, &["mobileprovision"][..]),
+                ("Entitlements:", "entitlements", &mut signing.entitlements, &["entitlements", "plist"][..]),
+            ] {
+                let label = ui.label(label);
+                ui.text_edit_singleline(value).labelled_by(label.id);
+                if labeled(ui.button("Browse..."), &format!("Browse for the {}", name)).clicked() {
+                    let dialog = native_dialog::FileDialog::new();
+                    let dialog = if extensions.is_empty() { dialog } else { dialog.add_filter("Signing files", extensions) };
+                    if let Ok(Some(path)) = dialog.show_open_single_file() {
+                        *value = path.to_string_lossy().to_string();
+                    }
+                }
+                if name == "entitlements" && !value.trim().is_empty() && ui.button("🔑 View").clicked() {
+                    view = Some(PathBuf::from(value.trim()));
+                }
+                ui.end_row();
+            }
+        });
+        ui.small("Entitlements replace the main app's; leave empty to keep the ones it was built with.");
+        if signing.is_enabled() {
+            if let Err(reason) = signing.validate() {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", reason));
+            }
+        }
+    });
+    view
+}
+
+/// Returns whether "Test key" was clicked.
+fn render_app_store_connect(ui: &mut egui::Ui, asc: &mut AppStoreConnect, checking: bool, check_result: Option<&Result<(), String>>) -> bool {
+    let mut test = false;
+    ui.checkbox(&mut asc.enabled, "Upload to App Store Connect (TestFlight)")
+        .on_hover_text("Uploads with Apple's Transporter, authenticated with an App Store Connect API key");
+    if !asc.enabled {
+        return false;
+    }
+    egui::Grid::new("edit_app_store_connect_grid").num_columns(3).show(ui, |ui| {
+        let label = ui.label("Issuer ID:");
+        ui.add(egui::TextEdit::singleline(&mut asc.issuer_id).hint_text("57246542-96fe-1a63-e053-0824d011072a").desired_width(300.0))
+            .labelled_by(label.id);
+        ui.end_row();
+        let label = ui.label("Key ID:");
+        ui.add(egui::TextEdit::singleline(&mut asc.key_id).hint_text("2X9R4HXF34").desired_width(300.0)).labelled_by(label.id);
+        ui.end_row();
+        let label = ui.label("Private key (.p8):");
+        ui.add(egui::TextEdit::singleline(&mut asc.private_key_path).desired_width(300.0)).labelled_by(label.id);
+        if labeled(ui.button("Browse..."), "Browse for the API private key").clicked() {
+            if let Ok(Some(path)) = native_dialog::FileDialog::new().add_filter("API keys", &["p8"]).show_open_single_file() {
+                asc.private_key_path = path.to_string_lossy().into_owned();
+            }
+        }
+        ui.end_row();
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut asc.upload_after_build, "Upload after every build");
+        if checking {
+            ui.label("Checking…");
+        } else if ui.button("Test key").on_hover_text("Makes one App Store Connect API request with the key").clicked() {
+            test = true;
+        }
+        match check_result {
+            Some(Ok(())) => {
+                ui.label("✔ The key works.");
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("✖ {}", e));
+            }
+            None => {}
+        }
+    });
+    test
+}
+
+/// Release channels and the upload destinations of each.
+fn render_build_channels(ui: &mut egui::Ui, channels: &mut Vec<BuildChannel>) {
+    let mut remove = None;
+    egui::Grid::new("settings_channels_grid").num_columns(5).show(ui, |ui| {
+        for (i, channel) in channels.iter_mut().enumerate() {
+            ui.add(egui::TextEdit::singleline(&mut channel.name).hint_text("beta").desired_width(100.0));
+            ui.checkbox(&mut channel.app_store_connect, "App Store Connect").on_hover_text("For apps with an App Store Connect API key");
+            ui.checkbox(&mut channel.share, "Share service").on_hover_text("The share service under Sharing");
+            ui.checkbox(&mut channel.object_storage, "S3 bucket").on_hover_text("For apps uploading to a bucket");
+            if labeled(ui.small_button("🗑"), "Remove this channel").clicked() {
+                remove = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = remove {
+        channels.remove(i);
+    }
+    if let Err(e) = crate::channels::validate(channels) {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+    if ui.button("➕ Add channel").clicked() {
+        channels.push(BuildChannel::default());
+    }
+}
+
+/// Which build events notify through which channels, the size budget and the email recipients.
+fn render_notification_preferences(ui: &mut egui::Ui, prefs: &mut NotificationPreferences, recipients_input: &mut String) {
+    egui::CollapsingHeader::new("Notifications").id_source("edit_notifications").show(ui, |ui| {
+        egui::Grid::new("edit_notifications_grid").num_columns(5).show(ui, |ui| {
+            ui.label("");
+            ui.label("Toast");
+            ui.label("Desktop");
+            ui.label("Webhook");
+            ui.label("Email");
+            ui.end_row();
+            for event in NotificationEvent::ALL {
+                ui.label(event.label());
+                let channels = prefs.channels_mut(event);
+                ui.checkbox(&mut channels.toast, "").on_hover_text("A message in the corner of this window");
+                ui.checkbox(&mut channels.desktop, "").on_hover_text("The system's notification center");
+                ui.checkbox(&mut channels.webhook, "").on_hover_text("The webhooks in the settings and this app's own");
+                ui.checkbox(&mut channels.email, "").on_hover_text("The recipients below, through the sendmail command in the settings");
+                ui.end_row();
+            }
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label("Size budget:");
+            ui.add(egui::DragValue::new(&mut prefs.size_budget_mb).clamp_range(0..=u64::MAX).suffix(" MB"))
+                .labelled_by(label.id)
+                .on_hover_text("A successful build whose IPA is larger raises \"Over size budget\". 0 is no budget.");
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label("Icon URL:");
+            ui.add(egui::TextEdit::singleline(&mut prefs.icon_url).hint_text("https://example.com/icon.png").desired_width(300.0))
+                .labelled_by(label.id)
+                .on_hover_text("Shown in Slack and Discord messages; must be reachable from the internet");
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label("Email to:");
+            if ui.add(egui::TextEdit::singleline(recipients_input).hint_text("qa@example.com, dev@example.com").desired_width(300.0)).labelled_by(label.id).changed() {
+                prefs.email_recipients = recipients_input.split([',', ';']).map(str::trim).filter(|a| !a.is_empty()).map(str::to_string).collect();
+            }
+        });
+    });
+}
+
+fn render_email_settings(ui: &mut egui::Ui, email: &mut EmailSettings) {
+    egui::Grid::new("settings_email_grid").num_columns(2).show(ui, |ui| {
+        let label = ui.label("Mail command:");
+        ui.add(egui::TextEdit::singleline(&mut email.sendmail_command).hint_text("sendmail"))
+            .labelled_by(label.id)
+            .on_hover_text("A sendmail-compatible program such as sendmail or msmtp. It is run with -t -i and the message on stdin.");
+        ui.end_row();
+        let label = ui.label("From:");
+        ui.add(egui::TextEdit::singleline(&mut email.from).hint_text("builds@example.com"))
+            .labelled_by(label.id)
+            .on_hover_text("Leave empty to let the mail program choose");
+        ui.end_row();
+    });
+}
+
+/// URLs notified after builds, with the events each wants and its signing secret.
+fn render_webhooks(ui: &mut egui::Ui, webhooks: &mut Vec<Webhook>, id_source: &str) {
+    let mut remove = None;
+    egui::Grid::new(id_source).num_columns(6).show(ui, |ui| {
+        for (i, webhook) in webhooks.iter_mut().enumerate() {
+            if ui.add(egui::TextEdit::singleline(&mut webhook.url).hint_text("https://hooks.example.com/builds").desired_width(260.0)).changed() {
+                let detected = WebhookFormat::detect(&webhook.url);
+                if detected != WebhookFormat::Json {
+                    webhook.format = detected;
+                }
+            }
+            egui::ComboBox::from_id_source((id_source, "format", i)).selected_text(webhook.format.label()).show_ui(ui, |ui| {
+                for format in WebhookFormat::ALL {
+                    ui.selectable_value(&mut webhook.format, format, format.label());
+                }
+            });
+            ui.checkbox(&mut webhook.on_success, "Success");
+            ui.checkbox(&mut webhook.on_failure, "Failure");
+            ui.add(egui::TextEdit::singleline(&mut webhook.secret).password(true).hint_text("Secret").desired_width(100.0))
+                .on_hover_text(format!("Optional. Signs the body with HMAC-SHA256 in the {} header as sha256=<hex>", crate::webhook::SIGNATURE_HEADER));
+            if labeled(ui.small_button("🗑"), "Remove this webhook").clicked() {
+                remove = Some(i);
+            }
+            ui.end_row();
+            if let (false, Err(e)) = (webhook.url.trim().is_empty(), webhook.validate()) {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+                ui.end_row();
+            }
+        }
+    });
+    if let Some(i) = remove {
+        webhooks.remove(i);
+    }
+    if ui.button("➕ Add webhook").clicked() {
+        webhooks.push(Webhook::default());
+    }
+}
+
+fn render_s3_destination(ui: &mut egui::Ui, s3: &mut S3Destination) {
+    ui.checkbox(&mut s3.enabled, "Upload to an S3 bucket")
+        .on_hover_text("Uploads the IPA, its OTA manifest, checksum and signature after every build, to AWS S3, Cloudflare R2, MinIO or any S3-compatible storage");
+    if !s3.enabled {
+        return;
+    }
+    egui::Grid::new("edit_s3_destination_grid").num_columns(2).show(ui, |ui| {
+        let label = ui.label("Endpoint:");
+        ui.add(egui::TextEdit::singleline(&mut s3.endpoint).hint_text("https://<account>.r2.cloudflarestorage.com").desired_width(300.0))
+            .labelled_by(label.id)
+            .on_hover_text("Leave empty for AWS S3");
+        ui.end_row();
+        let label = ui.label("Region:");
+        ui.add(egui::TextEdit::singleline(&mut s3.region).hint_text("us-east-1").desired_width(300.0))
+            .labelled_by(label.id)
+            .on_hover_text("\"auto\" for Cloudflare R2");
+        ui.end_row();
+        let label = ui.label("Bucket:");
+        ui.add(egui::TextEdit::singleline(&mut s3.bucket).desired_width(300.0)).labelled_by(label.id);
+        ui.end_row();
+        let label = ui.label("Prefix:");
+        ui.add(egui::TextEdit::singleline(&mut s3.prefix).hint_text("builds/shop").desired_width(300.0))
+            .labelled_by(label.id)
+            .on_hover_text("Folder inside the bucket");
+        ui.end_row();
+        let label = ui.label("Access key ID:");
+        ui.add(egui::TextEdit::singleline(&mut s3.access_key_id).hint_text("AWS_ACCESS_KEY_ID").desired_width(300.0))
+            .labelled_by(label.id)
+            .on_hover_text("Leave both keys empty to use the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables");
+        ui.end_row();
+        let label = ui.label("Secret key file:");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut s3.secret_access_key_file).desired_width(220.0))
+                .labelled_by(label.id)
+                .on_hover_text("File holding the secret access key, so it isn't saved with the config");
+            if labeled(ui.button("Browse..."), "Browse for the secret access key file").clicked() {
+                if let Ok(Some(path)) = native_dialog::FileDialog::new().show_open_single_file() {
+                    s3.secret_access_key_file = path.to_string_lossy().to_string();
+                }
+            }
+        });
+        ui.end_row();
+        let label = ui.label("or environment variable:");
+        ui.add(egui::TextEdit::singleline(&mut s3.secret_access_key_env).hint_text("R2_SECRET_ACCESS_KEY").desired_width(300.0))
+            .labelled_by(label.id)
+            .on_hover_text("Name of an environment variable holding the secret access key");
+        ui.end_row();
+        let label = ui.label("Part size:");
+        ui.add(egui::DragValue::new(&mut s3.part_size_mb).clamp_range(5..=5120).suffix(" MB"))
+            .labelled_by(label.id)
+            .on_hover_text("Files larger than this are sent as a multipart upload, in parts of this size");
+        ui.end_row();
+        let label = ui.label("Parallel parts:");
+        ui.add(egui::DragValue::new(&mut s3.parallel_parts).clamp_range(1..=crate::object_storage::MAX_PARALLEL_PARTS))
+            .labelled_by(label.id)
+            .on_hover_text("Parts sent at once. The upload limit in the settings is shared between them.");
+        ui.end_row();
+    });
+    ui.checkbox(&mut s3.path_style, "Path-style URLs").on_hover_text("<endpoint>/<bucket>/<key>; needed by MinIO and most self-hosted servers");
+}
+
+fn render_ota_manifest(ui: &mut egui::Ui, ota: &mut OtaManifest, app_name: &str) {
+    ui.checkbox(&mut ota.enabled, "Write an OTA install manifest next to the IPA")
+        .on_hover_text("Writes <name>.manifest.plist for itms-services:// installs, with the bundle ID and version read from the app's Info.plist");
+    if ota.enabled {
+        egui::Grid::new("edit_ota_manifest_grid").num_columns(2).show(ui, |ui| {
+            let label = ui.label("Base URL:");
+            ui.add(egui::TextEdit::singleline(&mut ota.base_url).hint_text("https://example.com/builds").desired_width(300.0))
+                .labelled_by(label.id)
+                .on_hover_text("HTTPS folder the IPA and its manifest are uploaded to");
+            ui.end_row();
+            let label = ui.label("Title:");
+            ui.add(egui::TextEdit::singleline(&mut ota.title).hint_text(app_name).desired_width(300.0))
+                .labelled_by(label.id)
+                .on_hover_text("Shown in the install prompt on the device");
+            ui.end_row();
+        });
+    }
+}
+
+/// Latest CHANGELOG section of `config`, or `None` when it has no changelog or it can't be read.
+pub fn changelog_release_notes(config: &AppConfig) -> Option<String> {
+    let path = config.changelog_path.as_deref().filter(|p| !p.trim().is_empty())?;
+    match crate::release_notes::load_from_changelog(Path::new(path)) {
+        Ok(notes) => Some(notes),
+        Err(e) => {
+            log::warn!("{}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{write_fixture, FixtureSpec};
+
+    /// Polls the running build the way `update` does each frame, until it finishes.
+    fn wait_for_build(app: &mut IpaBuilderApp) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+        while app.build_runner.is_some() {
+            assert!(std::time::Instant::now() < deadline, "build did not finish: {}", app.status_message);
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            app.poll_build_runner();
+        }
+    }
+
+    fn metric_names(app: &IpaBuilderApp) -> Vec<String> {
+        app.metrics_collector.load_unsent_metrics().unwrap().iter().map(|entry| match &entry.event {
+            MetricEvent::IpaGenerated { success, .. } => format!("IpaGenerated(success: {})", success),
+            other => format!("{:?}", other).split([' ', '{']).next().unwrap_or_default().to_string(),
+        }).collect()
+    }
+
+    fn audit_names(app: &IpaBuilderApp) -> Vec<String> {
+        app.audit_log.entries().unwrap().iter().map(|entry| match &entry.action {
+            AuditAction::BuildTriggered { success, .. } => format!("BuildTriggered(success: {})", success),
+            AuditAction::ConfigAdded { source, .. } => format!("ConfigAdded(source: {})", source),
+            AuditAction::ConfigEdited { source, .. } => format!("ConfigEdited(source: {})", source),
+            AuditAction::ConfigDeleted { .. } => "ConfigDeleted".to_string(),
+        }).collect()
+    }
+
+    #[test]
+    fn test_added_app_builds_into_history_and_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let output_dir = dir.path().join("out");
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_fixture(&FixtureSpec::default(), &input).unwrap();
+        // Freshly written inputs are held back as possibly still being copied.
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(&input).unwrap().set_modified(an_hour_ago).unwrap();
+
+        let mut app = IpaBuilderApp::with_data_dir(&data_dir);
+        app.output_directory = Some(output_dir.to_string_lossy().into_owned());
+        app.add_app_name_input = "Shop".to_string();
+        app.add_app_output_name_input = "Shop.ipa".to_string();
+        assert!(!app.add_app_from_dialog(), "an app without an input ZIP is rejected");
+        app.add_app_zip_path_input = Some(input.to_string_lossy().into_owned());
+        assert!(app.add_app_from_dialog(), "{:?}", app.add_app_errors);
+        assert_eq!(app.add_app_name_input, "MyNewApp");
+        assert_eq!(app.bundle_infos[&app.app_configs[0].id].bundle_identifier.as_deref(), Some("com.example.runner"));
+
+        app.generate_for_config(0, None);
+        assert!(app.pending_build.is_none(), "{:?}", app.pending_build.as_ref().map(|p| &p.reason));
+        wait_for_build(&mut app);
+        let ipa = output_dir.join("Shop.ipa");
+        assert!(ipa.is_file(), "{}", app.status_message);
+        assert_eq!(app.last_generated_ipa_path.as_deref(), Some(ipa.as_path()));
+        assert!(app.app_configs[0].last_generated_at.is_some());
+        let record = &app.build_history.records()[0];
+        assert!(record.success);
+        assert_eq!(record.output_path.as_deref(), Some(ipa.to_string_lossy().as_ref()));
+        assert!(record.size_breakdown.as_ref().is_some_and(|b| b.total().bytes > 0));
+
+        std::fs::remove_file(&input).unwrap();
+        app.start_build(0, None);
+        wait_for_build(&mut app);
+        let failed = &app.build_history.records()[1];
+        assert!(!failed.success && failed.error.is_some());
+
+        // A channel deleted from the settings fails the build instead of skipping its routing.
+        app.set_channel(0, "gone".to_string());
+        app.start_build(0, None);
+        wait_for_build(&mut app);
+        assert_eq!(app.build_history.records()[2].error.as_deref(), Some("Unknown channel 'gone'"));
+        app.undo_config_change(true);
+        assert_eq!(app.app_configs[0].channel, "");
+
+        assert_eq!(metric_names(&app), ["AppAdded", "IpaGenerated(success: true)", "IpaGenerated(success: false)", "AppConfigEdited", "IpaGenerated(success: false)"]);
+        assert_eq!(audit_names(&app), [
+            "ConfigAdded(source: dialog)",
+            "BuildTriggered(success: true)",
+            "BuildTriggered(success: false)",
+            "ConfigEdited(source: channel picker)",
+            "BuildTriggered(success: false)",
+            "ConfigEdited(source: undo)",
+        ]);
+        // A restarted app sees the same history.
+        assert_eq!(IpaBuilderApp::with_data_dir(&data_dir).build_history.records().len(), 3);
+    }
+
+    #[test]
+    fn test_autocheck_zips_wait_in_the_build_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("out");
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        let inputs: Vec<PathBuf> = ["Runner.app.zip", "Runner.app 2.zip"].iter().map(|name| dir.path().join(name)).collect();
+        for input in &inputs {
+            write_fixture(&FixtureSpec::default(), input).unwrap();
+            std::fs::File::options().write(true).open(input).unwrap().set_modified(an_hour_ago).unwrap();
+        }
+
+        let mut app = IpaBuilderApp::with_data_dir(&dir.path().join("data"));
+        app.autocheck_config = Some(AutoCheckConfig {
+            watch_dir: dir.path().to_path_buf(),
+            output_dir: output_dir.clone(),
+            app_name: "Nightly".to_string(),
+            output_ipa_name: "Nightly.ipa".to_string(),
+            build_options: crate::ipa_logic::BuildOptions::default(),
+            build_inline: false,
+        });
+        // Both ZIPs arrive together, and the first is reported twice by the watcher.
+        for input in [&inputs[0], &inputs[1], &inputs[0]] {
+            app.build_queue.push_input(AUTOCHECK_CONFIG_ID, Some(input.to_string_lossy().into_owned()), None, Priority::Normal, QueueSource::AutoCheck);
+        }
+        assert_eq!(app.build_queue.len(), 2);
+
+        for built in 1..=2 {
+            app.build_queue_checked_at = None;
+            app.start_next_queued_build();
+            assert!(app.build_runner.is_some(), "{}", app.status_message);
+            assert_eq!(app.build_queue.len(), 2 - built, "one build at a time");
+            wait_for_build(&mut app);
+            assert!(!inputs[built - 1].exists(), "built ZIPs are deleted: {:?}", app.autocheck_log);
+        }
+        assert!(output_dir.join("Nightly.ipa").is_file());
+        let records = app.build_history.records();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.success && r.app_id == AUTOCHECK_CONFIG_ID));
+    }
+
+    #[test]
+    fn test_cli_builds_the_config_the_gui_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("app_state.json"),
+            r#"{"app_configs":[{"id":"a1","app_name":"Shop","input_zip_path":"missing.zip","output_ipa_name":"Shop.ipa","created_at":"2024-01-01T00:00:00Z","last_generated_at":null}],
+                "projects":[{"id":"p1","name":"Store","members":[{"config_id":"a1"}],"defaults":{"changelog_path":"CHANGELOG.md","short_version":"2.1"}}]}"#,
+        )
+        .unwrap();
+        let store = crate::state_store::StoreKind::Json.file_store(dir.path()).unwrap();
+        let state = crate::cli::SavedState::load(store.as_ref(), crate::state_store::StoreKind::Json, dir.path(), None).unwrap();
+        let cli_config = state.config_for_build("Shop").unwrap();
+        assert_eq!(cli_config.changelog_path.as_deref(), Some("CHANGELOG.md"));
+        assert_eq!(cli_config.plist_overrides.short_version, "2.1");
+
+        let mut app = IpaBuilderApp::with_data_dir(&dir.path().join("data"));
+        app.output_directory = Some(dir.path().to_string_lossy().into_owned());
+        let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.path().join("app_state.json")).unwrap()).unwrap();
+        app.app_configs = serde_json::from_value(saved["app_configs"].clone()).unwrap();
+        app.projects = serde_json::from_value(saved["projects"].clone()).unwrap();
+        app.start_build(0, None);
+        let gui_config = app.build_runner.as_ref().unwrap().config().clone();
+        wait_for_build(&mut app);
+        assert_eq!(serde_json::to_value(&cli_config).unwrap(), serde_json::to_value(&gui_config).unwrap());
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::AppConfig;
+use crate::info_plist::PlistOverrides;
+
+pub const APPS_IMPORT_FILE_NAME: &str = "apps.json";
+pub const APPS_IMPORT_FORMAT_VERSION: u32 = 1;
+
+/// Machine-writable list of app configurations, dropped into the config directory
+/// by provisioning tools and merged into the saved configs on startup or "Sync".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppsImportFile {
+    pub version: u32,
+    #[serde(default)]
+    pub apps: Vec<ImportedApp>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportedApp {
+    /// Stable identifier; when omitted, entries are matched by `app_name`.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub app_name: String,
+    pub input_zip_path: String,
+    pub output_ipa_name: String,
+    /// Hook environment variables; when present they replace the config's variables.
+    #[serde(default)]
+    pub env_vars: Option<BTreeMap<String, String>>,
+    /// Sidecar files or directories; when present they replace the config's list.
+    #[serde(default)]
+    pub sidecars: Option<Vec<String>>,
+    /// When present, replaces the config's "strip macOS junk" switch.
+    #[serde(default)]
+    pub strip_junk_files: Option<bool>,
+    /// When present, replaces the config's Info.plist overrides.
+    #[serde(default)]
+    pub plist_overrides: Option<PlistOverrides>,
+}
+
+/// Ids of the configs touched by a merge.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+pub fn load_apps_import_file(path: &Path) -> Result<AppsImportFile, String> {
+    let json_string = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: AppsImportFile = serde_json::from_str(&json_string)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if file.version != APPS_IMPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported {} version {} (expected {})",
+            path.display(),
+            file.version,
+            APPS_IMPORT_FORMAT_VERSION
+        ));
+    }
+    for (i, app) in file.apps.iter().enumerate() {
+        if app.app_name.trim().is_empty() {
+            return Err(format!("apps[{}]: app_name cannot be empty", i));
+        }
+        if app.input_zip_path.trim().is_empty() {
+            return Err(format!("apps[{}] ('{}'): input_zip_path cannot be empty", i, app.app_name));
+        }
+        if let Err(reason) = crate::naming::validate_ipa_file_name(app.output_ipa_name.trim()) {
+            return Err(format!("apps[{}] ('{}'): output_ipa_name is invalid: {}", i, app.app_name, reason));
+        }
+        if let Some(Err(reason)) = app.plist_overrides.as_ref().map(PlistOverrides::validate) {
+            return Err(format!("apps[{}] ('{}'): plist_overrides: {}", i, app.app_name, reason));
+        }
+        for key in app.env_vars.iter().flat_map(|vars| vars.keys()) {
+            if let Err(reason) = crate::hooks::validate_env_var_name(key) {
+                return Err(format!("apps[{}] ('{}'): env_vars: {}", i, app.app_name, reason));
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// Merges the declared apps into `configs`. Existing configs are matched by id, or by
+/// name when the entry has no id; configs that are not declared are left untouched.
+pub fn merge_imported_apps(configs: &mut Vec<AppConfig>, file: &AppsImportFile) -> MergeSummary {
+    let mut summary = MergeSummary::default();
+    for app in &file.apps {
+        let app_name = app.app_name.trim().to_string();
+        let input_zip_path = app.input_zip_path.trim().to_string();
+        let output_ipa_name = app.output_ipa_name.trim().to_string();
+
+        let existing = match &app.id {
+            Some(id) => configs.iter_mut().find(|c| &c.id == id),
+            None => configs.iter_mut().find(|c| c.app_name == app_name),
+        };
+
+        match existing {
+            Some(config) => {
+                let env_vars = app.env_vars.clone().unwrap_or_else(|| config.env_vars.clone());
+                let sidecars = app.sidecars.clone().unwrap_or_else(|| config.sidecars.clone());
+                let strip_junk_files = app.strip_junk_files.unwrap_or(config.strip_junk_files);
+                let plist_overrides = app.plist_overrides.clone().unwrap_or_else(|| config.plist_overrides.clone());
+                if config.app_name != app_name
+                    || config.input_zip_path != input_zip_path
+                    || config.output_ipa_name != output_ipa_name
+                    || config.env_vars != env_vars
+                    || config.sidecars != sidecars
+                    || config.strip_junk_files != strip_junk_files
+                    || config.plist_overrides != plist_overrides
+                {
+                    config.app_name = app_name;
+                    config.input_zip_path = input_zip_path;
+                    config.output_ipa_name = output_ipa_name;
+                    config.env_vars = env_vars;
+                    config.sidecars = sidecars;
+                    config.strip_junk_files = strip_junk_files;
+                    config.plist_overrides = plist_overrides;
+                    summary.updated.push(config.id.clone());
+                }
+            }
+            None => {
+                let id = app.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+                summary.added.push(id.clone());
+                configs.push(AppConfig {
+                    id,
+                    app_name,
+                    input_zip_path,
+                    output_ipa_name,
+                    env_vars: app.env_vars.clone().unwrap_or_default(),
+                    sidecars: app.sidecars.clone().unwrap_or_default(),
+                    strip_junk_files: app.strip_junk_files.unwrap_or(true),
+                    plist_overrides: app.plist_overrides.clone().unwrap_or_default(),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imported(id: Option<&str>, name: &str, ipa: &str) -> ImportedApp {
+        ImportedApp {
+            id: id.map(str::to_string),
+            app_name: name.to_string(),
+            input_zip_path: format!("/builds/{}.zip", name),
+            output_ipa_name: ipa.to_string(),
+            env_vars: None,
+            sidecars: None,
+            strip_junk_files: None,
+            plist_overrides: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_adds_and_updates() {
+        let mut configs = Vec::new();
+        let file = AppsImportFile {
+            version: APPS_IMPORT_FORMAT_VERSION,
+            apps: vec![imported(Some("fixed-id"), "Alpha", "alpha.ipa"), imported(None, "Beta", "beta.ipa")],
+        };
+        let summary = merge_imported_apps(&mut configs, &file);
+        assert_eq!(summary.added.len(), 2);
+        assert_eq!(summary.added[0], "fixed-id");
+        assert_eq!(configs[1].app_name, "Beta");
+
+        // Re-merging the same file is a no-op.
+        let summary = merge_imported_apps(&mut configs, &file);
+        assert_eq!(summary, MergeSummary::default());
+        assert_eq!(configs.len(), 2);
+
+        let file = AppsImportFile {
+            version: APPS_IMPORT_FORMAT_VERSION,
+            apps: vec![imported(Some("fixed-id"), "Alpha Renamed", "alpha2.ipa")],
+        };
+        let summary = merge_imported_apps(&mut configs, &file);
+        assert_eq!(summary.updated, vec!["fixed-id".to_string()]);
+        assert_eq!(configs[0].app_name, "Alpha Renamed");
+        assert_eq!(configs[0].output_ipa_name, "alpha2.ipa");
+        assert_eq!(configs.len(), 2);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(APPS_IMPORT_FILE_NAME);
+        std::fs::write(&path, r#"{"version":1,"apps":[{"app_name":"A","input_zip_path":"a.zip","output_ipa_name":"a.zip"}]}"#).unwrap();
+        assert!(load_apps_import_file(&path).is_err());
+
+        std::fs::write(&path, r#"{"version":1,"apps":[{"app_name":"A","input_zip_path":"a.zip","output_ipa_name":"a.ipa"}]}"#).unwrap();
+        assert_eq!(load_apps_import_file(&path).unwrap().apps.len(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use crate::artifact_signing::ArtifactSigning;
+use crate::build_history::BuildRecord;
+use crate::retention::RetentionOutcome;
+
+/// An IPA the tool produced, as recorded in the build history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub built_at: DateTime<Utc>,
+    /// Size on disk when it still exists, otherwise the size recorded at build time.
+    pub size_bytes: u64,
+    /// Size recorded at build time, which [`remove_artifacts`] checks the file against.
+    pub recorded_size_bytes: Option<u64>,
+    pub sha256: Option<String>,
+    pub exists: bool,
+}
+
+impl Artifact {
+    /// Whether the file on disk is still the one that was built: same size and SHA-256 as
+    /// recorded, where recorded. A later build or a person may have put another file there.
+    fn is_unchanged(&self) -> io::Result<bool> {
+        let size = fs::metadata(&self.path)?.len();
+        if self.recorded_size_bytes.is_some_and(|recorded| recorded != size) {
+            return Ok(false);
+        }
+        match &self.sha256 {
+            Some(sha256) => Ok(crate::transfer::sha256_file(&self.path)?.eq_ignore_ascii_case(sha256)),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Every artifact of one app, newest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppStorage {
+    pub app_id: String,
+    pub app_name: String,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl AppStorage {
+    /// Bytes taken by the artifacts still on disk.
+    pub fn bytes_on_disk(&self) -> u64 {
+        self.artifacts.iter().filter(|a| a.exists).map(|a| a.size_bytes).sum()
+    }
+
+    /// Artifacts still on disk that a newer build of the app replaced. The newest build is
+    /// never one of them, even when its file is gone.
+    pub fn superseded(&self) -> impl Iterator<Item = &Artifact> {
+        self.artifacts.iter().skip(1).filter(|a| a.exists)
+    }
+
+    pub fn superseded_bytes(&self) -> u64 {
+        self.superseded().map(|a| a.size_bytes).sum()
+    }
+}
+
+/// Groups the successful builds in `records` by app, apps using the most storage first. A path
+/// built more than once counts once, as its latest build, since the file was overwritten.
+pub fn build_ledger(records: &[BuildRecord]) -> Vec<AppStorage> {
+    let mut apps: Vec<AppStorage> = Vec::new();
+    let mut app_indices: HashMap<&str, usize> = HashMap::new();
+    let mut seen_paths = std::collections::HashSet::new();
+    for record in records.iter().rev().filter(|r| r.success) {
+        let Some(path) = record.output_path.as_deref().map(PathBuf::from) else { continue };
+        if !seen_paths.insert(path.clone()) {
+            continue;
+        }
+        let metadata = fs::metadata(&path).ok().filter(|m| m.is_file());
+        let artifact = Artifact {
+            built_at: record.timestamp,
+            size_bytes: metadata.as_ref().map(|m| m.len()).or(record.size_bytes).unwrap_or(0),
+            recorded_size_bytes: record.size_bytes,
+            sha256: record.sha256.clone(),
+            exists: metadata.is_some(),
+            path,
+        };
+        let idx = *app_indices.entry(record.app_id.as_str()).or_insert_with(|| {
+            apps.push(AppStorage { app_id: record.app_id.clone(), app_name: record.app_name.clone(), artifacts: Vec::new() });
+            apps.len() - 1
+        });
+        apps[idx].artifacts.push(artifact);
+    }
+    apps.sort_by_key(|app| std::cmp::Reverse(app.bytes_on_disk()));
+    apps
+}
+
+/// What [`remove_artifacts`] deleted and left alone.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cleanup {
+    pub outcome: RetentionOutcome,
+    /// Files whose size or SHA-256 no longer matches the build history, which were kept.
+    pub changed: Vec<PathBuf>,
+}
+
+impl Cleanup {
+    /// `Deleted 2 old builds (1.2 GB freed); kept 1 changed since it was built: Shop.ipa`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.outcome.summary().unwrap_or_else(|| "Nothing to clean up".to_string());
+        if !self.changed.is_empty() {
+            let names: Vec<String> = self.changed.iter().map(|p| p.file_name().unwrap_or_default().to_string_lossy().into_owned()).collect();
+            let since = if names.len() == 1 { "it was" } else { "they were" };
+            summary.push_str(&format!("; kept {} changed since {} built: {}", names.len(), since, names.join(", ")));
+        }
+        summary
+    }
+}
+
+/// Deletes `artifacts` along with their detached signatures and checksum files. Files already
+/// gone are skipped, and so are files that no longer match the size and SHA-256 recorded when
+/// they were built, which [`Cleanup::changed`] lists.
+pub fn remove_artifacts<'a>(artifacts: impl IntoIterator<Item = &'a Artifact>) -> io::Result<Cleanup> {
+    let mut cleanup = Cleanup::default();
+    let outcome = &mut cleanup.outcome;
+    for artifact in artifacts {
+        if !artifact.path.is_file() {
+            continue;
+        }
+        if !artifact.is_unchanged()? {
+            cleanup.changed.push(artifact.path.clone());
+            continue;
+        }
+        for signature in ArtifactSigning::existing_signatures(&artifact.path) {
+            fs::remove_file(signature)?;
+        }
+        for companion in [crate::transfer::checksum_path(&artifact.path), crate::ota_manifest::manifest_path(&artifact.path)] {
+            if companion.is_file() {
+                fs::remove_file(companion)?;
+            }
+        }
+        fs::remove_file(&artifact.path)?;
+        outcome.bytes += artifact.size_bytes;
+        outcome.removed.push(artifact.path.clone());
+    }
+    Ok(cleanup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_tracks_existing_and_superseded_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        for (name, size) in [("Shop (2).ipa", 2048), ("Shop (3).ipa", 3072), ("Blog.ipa", 1024)] {
+            fs::write(path(name), vec![0u8; size]).unwrap();
+        }
+        fs::write(path("Shop (2).ipa.minisig"), "signature").unwrap();
+        let record = |app: &str, name: &str, minutes: i64, success: bool| BuildRecord {
+            timestamp: Utc::now() - chrono::Duration::minutes(60 - minutes),
+            app_id: app.to_lowercase(),
+            app_name: app.to_string(),
+            success,
+            duration_ms: 1000,
+            output_path: Some(path(name).display().to_string()),
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: crate::transfer::sha256_file(&path(name)).ok().or(Some(format!("sha-{}", minutes))),
+            size_bytes: fs::metadata(path(name)).map(|m| m.len()).ok().or(Some(500)),
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        };
+        let records = [
+            record("Shop", "Shop.ipa", 0, true),
+            record("Shop", "Shop (2).ipa", 1, true),
+            record("Blog", "Blog.ipa", 2, true),
+            record("Shop", "Shop (3).ipa", 3, true),
+            record("Shop", "Shop (2).ipa", 4, false),
+            record("Blog", "Blog.ipa", 5, true),
+        ];
+
+        let ledger = build_ledger(&records);
+        assert_eq!(ledger.iter().map(|a| a.app_name.as_str()).collect::<Vec<_>>(), ["Shop", "Blog"]);
+        let shop = &ledger[0];
+        assert_eq!(shop.artifacts.len(), 3);
+        assert_eq!(shop.artifacts.iter().map(|a| a.exists).collect::<Vec<_>>(), [true, true, false]);
+        assert_eq!(shop.artifacts[2].size_bytes, 500);
+        assert_eq!(shop.bytes_on_disk(), 5120);
+        assert_eq!(shop.superseded_bytes(), 2048);
+        assert_eq!(ledger[1].artifacts.len(), 1);
+        assert_eq!(ledger[1].artifacts[0].sha256, crate::transfer::sha256_file(&path("Blog.ipa")).ok());
+        assert_eq!(ledger[1].superseded().count(), 0);
+
+        let cleanup = remove_artifacts(shop.superseded()).unwrap();
+        assert_eq!(cleanup.outcome.removed, vec![path("Shop (2).ipa")]);
+        assert!(!path("Shop (2).ipa.minisig").exists() && path("Shop (3).ipa").exists());
+        assert_eq!(build_ledger(&records)[0].superseded_bytes(), 0);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_the_newest_build_and_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let artifact = |name: &str, contents: &[u8], exists: bool| {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            let artifact = Artifact {
+                built_at: Utc::now(),
+                size_bytes: contents.len() as u64,
+                recorded_size_bytes: Some(contents.len() as u64),
+                sha256: crate::transfer::sha256_file(&path).ok(),
+                exists,
+                path: path.clone(),
+            };
+            if !exists {
+                fs::remove_file(&path).unwrap();
+            }
+            artifact
+        };
+        let app = AppStorage {
+            app_id: "shop".to_string(),
+            app_name: "Shop".to_string(),
+            artifacts: vec![artifact("Shop (4).ipa", b"four", false), artifact("Shop (3).ipa", b"three", true), artifact("Shop (2).ipa", b"two", true), artifact("Shop.ipa", b"one", true)],
+        };
+        // The newest build was deleted by hand; the next one is still superseded by it.
+        let superseded: Vec<&Artifact> = app.superseded().collect();
+        assert_eq!(superseded.iter().map(|a| a.path.file_name().unwrap().to_str().unwrap()).collect::<Vec<_>>(), ["Shop (3).ipa", "Shop (2).ipa", "Shop.ipa"]);
+
+        // Same size, other contents, and another size: both were replaced after the build.
+        fs::write(dir.path().join("Shop (2).ipa"), b"TWO").unwrap();
+        fs::write(dir.path().join("Shop.ipa"), b"rebuilt").unwrap();
+        let cleanup = remove_artifacts(superseded).unwrap();
+        assert_eq!(cleanup.outcome.removed, [dir.path().join("Shop (3).ipa")]);
+        assert_eq!(cleanup.changed, [dir.path().join("Shop (2).ipa"), dir.path().join("Shop.ipa")]);
+        assert!(dir.path().join("Shop (2).ipa").exists() && dir.path().join("Shop.ipa").exists());
+        assert!(cleanup.summary().ends_with("; kept 2 changed since they were built: Shop (2).ipa, Shop.ipa"));
+    }
+}
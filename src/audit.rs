@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Write};
+
+use crate::time_display::DisplayTimeZone;
+
+/// Actions that are recorded in the audit log. Unlike `MetricEvent`, these always
+/// carry enough detail to answer "who changed what" during a compliance review.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AuditAction {
+    BuildTriggered {
+        app_id: String,
+        app_name: String,
+        success: bool,
+        output_path: Option<String>,
+        error: Option<String>,
+    },
+    ConfigAdded {
+        app_id: String,
+        app_name: String,
+        source: String,
+    },
+    ConfigEdited {
+        app_id: String,
+        app_name: String,
+        source: String,
+    },
+    ConfigDeleted {
+        app_id: String,
+        app_name: String,
+    },
+}
+
+impl AuditAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            AuditAction::BuildTriggered { .. } => "build",
+            AuditAction::ConfigAdded { .. } => "config_added",
+            AuditAction::ConfigEdited { .. } => "config_edited",
+            AuditAction::ConfigDeleted { .. } => "config_deleted",
+        }
+    }
+
+    fn app(&self) -> (&str, &str) {
+        match self {
+            AuditAction::BuildTriggered { app_id, app_name, .. }
+            | AuditAction::ConfigAdded { app_id, app_name, .. }
+            | AuditAction::ConfigEdited { app_id, app_name, .. }
+            | AuditAction::ConfigDeleted { app_id, app_name } => (app_id, app_name),
+        }
+    }
+
+    fn details(&self) -> String {
+        match self {
+            AuditAction::BuildTriggered { success: true, output_path, .. } => {
+                format!("succeeded: {}", output_path.as_deref().unwrap_or(""))
+            }
+            AuditAction::BuildTriggered { success: false, error, .. } => {
+                format!("failed: {}", error.as_deref().unwrap_or(""))
+            }
+            AuditAction::ConfigAdded { source, .. } | AuditAction::ConfigEdited { source, .. } => {
+                format!("via {}", source)
+            }
+            AuditAction::ConfigDeleted { .. } => String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    pub host: String,
+    pub action: AuditAction,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            user: whoami::username(),
+            host: whoami::fallible::hostname().unwrap_or_else(|_| "unknown".to_string()),
+            action,
+        }
+    }
+}
+
+/// Append-only log of user actions, stored as JSONL next to (but separate from) the metrics.
+#[derive(Debug)]
+pub struct AuditLog {
+    log_file_path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(file_path: PathBuf) -> Self {
+        if let Some(parent_dir) = file_path.parent() {
+            if !parent_dir.exists() {
+                if let Err(e) = std::fs::create_dir_all(parent_dir) {
+                    log::error!("Failed to create directory for audit log {}: {}", parent_dir.display(), e);
+                }
+            }
+        }
+        Self { log_file_path: file_path }
+    }
+
+    pub fn record(&self, action: AuditAction) {
+        let entry = AuditEntry::new(action);
+        let json_string = match serde_json::to_string(&entry) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to serialize audit entry: {}", e);
+                return;
+            }
+        };
+        match OpenOptions::new().create(true).append(true).open(&self.log_file_path) {
+            Ok(mut file) => {
+                if let Err(e) = writeln!(file, "{}", json_string) {
+                    log::error!("Failed to write audit entry to {}: {}", self.log_file_path.display(), e);
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to open audit log {}: {}", self.log_file_path.display(), e);
+            }
+        }
+    }
+
+    pub fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+        if !self.log_file_path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for line in io::BufReader::new(File::open(&self.log_file_path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("Failed to parse audit line '{}': {}", line, e),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Writes the whole log as CSV (timestamp, user, host, action, app id, app name, details),
+    /// with timestamps in `zone`.
+    pub fn export_csv(&self, dest: &Path, zone: &DisplayTimeZone) -> io::Result<usize> {
+        let entries = self.entries()?;
+        let mut writer = io::BufWriter::new(File::create(dest)?);
+        writeln!(writer, "timestamp,user,host,action,app_id,app_name,details")?;
+        for entry in &entries {
+            let (app_id, app_name) = entry.action.app();
+            let fields = [
+                zone.rfc3339(entry.timestamp),
+                entry.user.clone(),
+                entry.host.clone(),
+                entry.action.kind().to_string(),
+                app_id.to_string(),
+                app_name.to_string(),
+                entry.action.details(),
+            ];
+            let line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        Ok(entries.len())
+    }
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_export_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path().join("audit.jsonl"));
+        log.record(AuditAction::ConfigAdded {
+            app_id: "1".to_string(),
+            app_name: "Shop, Prod".to_string(),
+            source: "dialog".to_string(),
+        });
+        log.record(AuditAction::ConfigDeleted { app_id: "1".to_string(), app_name: "Shop, Prod".to_string() });
+
+        let export_path = dir.path().join("audit.csv");
+        assert_eq!(log.export_csv(&export_path, &DisplayTimeZone::Utc).unwrap(), 2);
+        let csv = std::fs::read_to_string(export_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains(",config_added,1,\"Shop, Prod\",via dialog"));
+        assert!(lines[2].contains(",config_deleted,"));
+    }
+}
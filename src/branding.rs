@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_ACCENT: [u8; 3] = [0x00, 0x7a, 0xff];
+const DEFAULT_QR: [u8; 3] = [0x00, 0x00, 0x00];
+/// QR codes with modules lighter than this (relative luminance) are hard to scan on white.
+const MAX_QR_LUMINANCE: f32 = 0.4;
+
+/// How artifacts shared outside the team (install pages and their QR codes) present it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Branding {
+    /// Shown in page titles and footers; empty for none.
+    pub org_name: String,
+    /// PNG, JPEG or SVG copied next to the page and shown above the title; empty for none.
+    pub logo_path: String,
+    /// `#rrggbb` used for buttons and links, in light and dark mode.
+    pub accent_color: String,
+    /// `#rrggbb` of the QR code modules, drawn on white.
+    pub qr_color: String,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            org_name: String::new(),
+            logo_path: String::new(),
+            accent_color: to_hex(DEFAULT_ACCENT),
+            qr_color: to_hex(DEFAULT_QR),
+        }
+    }
+}
+
+impl Branding {
+    /// The accent color, or the default blue when it is not a valid `#rrggbb`.
+    pub fn accent_rgb(&self) -> [u8; 3] {
+        parse_hex_color(&self.accent_color).unwrap_or(DEFAULT_ACCENT)
+    }
+
+    /// The QR color, or black when it is invalid or too light to scan.
+    pub fn qr_rgb(&self) -> [u8; 3] {
+        parse_hex_color(&self.qr_color)
+            .filter(|rgb| relative_luminance(*rgb) <= MAX_QR_LUMINANCE)
+            .unwrap_or(DEFAULT_QR)
+    }
+
+    /// Settings that will be ignored or make an export fail, for display next to the fields.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if parse_hex_color(&self.accent_color).is_none() {
+            problems.push(format!("Accent color '{}' is not #rrggbb; the default is used.", self.accent_color));
+        }
+        match parse_hex_color(&self.qr_color) {
+            None => problems.push(format!("QR color '{}' is not #rrggbb; black is used.", self.qr_color)),
+            Some(rgb) if relative_luminance(rgb) > MAX_QR_LUMINANCE => {
+                problems.push("QR color is too light to scan reliably; black is used.".to_string())
+            }
+            Some(_) => {}
+        }
+        let logo = self.logo_path.trim();
+        if !logo.is_empty() && !std::path::Path::new(logo).is_file() {
+            problems.push(format!("Logo '{}' does not exist; install page exports will fail.", logo));
+        }
+        problems
+    }
+}
+
+/// Parses `#rrggbb` (the `#` is optional).
+pub fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// WCAG relative luminance, from 0.0 (black) to 1.0 (white).
+fn relative_luminance(rgb: [u8; 3]) -> f32 {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_fall_back_when_invalid_or_unscannable() {
+        let mut branding = Branding { accent_color: "#E4002B".to_string(), qr_color: "1d3557".to_string(), ..Default::default() };
+        assert_eq!(branding.accent_rgb(), [0xe4, 0x00, 0x2b]);
+        assert_eq!(branding.qr_rgb(), [0x1d, 0x35, 0x57]);
+        assert!(branding.problems().is_empty());
+
+        branding.accent_color = "red".to_string();
+        branding.qr_color = "#ffee88".to_string();
+        assert_eq!(branding.accent_rgb(), DEFAULT_ACCENT);
+        assert_eq!(branding.qr_rgb(), DEFAULT_QR);
+        assert_eq!(branding.problems().len(), 2);
+        assert_eq!(to_hex([0xe4, 0x00, 0x2b]), "#e4002b");
+    }
+}
@@ -0,0 +1,514 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
+use crate::database;
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::io;
+
+use crate::size_analysis::SizeBreakdown;
+use crate::time_display::DisplayTimeZone;
+
+/// File name of the build history database in the data directory.
+pub const BUILD_HISTORY_DB_FILE_NAME: &str = "builds.sqlite";
+
+/// One IPA generation, successful or not.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuildRecord {
+    pub timestamp: DateTime<Utc>,
+    pub app_id: String,
+    pub app_name: String,
+    pub success: bool,
+    pub duration_ms: u128,
+    pub output_path: Option<String>,
+    #[serde(default)]
+    pub release_notes: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// SHA-256 of the produced IPA.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Size of the produced IPA in bytes.
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+    /// When the provisioning profile embedded in the IPA expires; `None` for unsigned builds.
+    #[serde(default)]
+    pub profile_expires_at: Option<DateTime<Utc>>,
+    /// Where the IPA's bytes went; `None` for failed builds and builds recorded before it existed.
+    #[serde(default)]
+    pub size_breakdown: Option<SizeBreakdown>,
+    /// The release channel the build was made for, if any.
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+impl BuildRecord {
+    /// Case-insensitive match of `query` against the output file, checksum and release notes.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return false;
+        }
+        [self.output_path.as_deref(), self.sha256.as_deref(), self.release_notes.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+/// Days of build history shown by the history window and its statistics. Days are calendar
+/// days in the display time zone.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateRange {
+    #[default]
+    All,
+    Today,
+    Last7Days,
+    Last30Days,
+    /// From `from` to `to`, both included.
+    Custom { from: NaiveDate, to: NaiveDate },
+}
+
+impl DateRange {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateRange::All => "All time",
+            DateRange::Today => "Today",
+            DateRange::Last7Days => "Last 7 days",
+            DateRange::Last30Days => "Last 30 days",
+            DateRange::Custom { .. } => "Custom",
+        }
+    }
+
+    /// Whether a build on `day` falls in the range, when it is `today`.
+    pub fn contains(&self, day: NaiveDate, today: NaiveDate) -> bool {
+        let since = |days: u64| today.checked_sub_days(Days::new(days - 1)).unwrap_or(NaiveDate::MIN);
+        match *self {
+            DateRange::All => true,
+            DateRange::Today => day == today,
+            DateRange::Last7Days => day >= since(7) && day <= today,
+            DateRange::Last30Days => day >= since(30) && day <= today,
+            DateRange::Custom { from, to } => day >= from && day <= to,
+        }
+    }
+
+    /// UTC dates bounding the range in any time zone: from the day before its first day
+    /// (included) to two days after its last (excluded); `None` for an open end.
+    fn utc_bounds(&self, today: NaiveDate) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        let (first, last) = match *self {
+            DateRange::All => return (None, None),
+            DateRange::Today => (today, today),
+            DateRange::Last7Days => (today - Days::new(6), today),
+            DateRange::Last30Days => (today - Days::new(29), today),
+            DateRange::Custom { from, to } => (from, to),
+        };
+        (first.checked_sub_days(Days::new(1)), last.checked_add_days(Days::new(2)))
+    }
+}
+
+/// Builds on one day, for the history chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyTotals {
+    pub day: NaiveDate,
+    pub builds: usize,
+    pub failed: usize,
+}
+
+/// Totals over a set of build records.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildStats {
+    pub builds: usize,
+    pub failed: usize,
+    pub total_duration_ms: u128,
+    /// Sum of the sizes recorded for successful builds.
+    pub total_size_bytes: u64,
+}
+
+impl BuildStats {
+    pub fn add(&mut self, record: &BuildRecord) {
+        self.builds += 1;
+        self.total_duration_ms += record.duration_ms;
+        if record.success {
+            self.total_size_bytes += record.size_bytes.unwrap_or(0);
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    pub fn avg_duration_ms(&self) -> Option<u128> {
+        (self.builds > 0).then(|| self.total_duration_ms / self.builds as u128)
+    }
+
+    /// Percentage of builds that succeeded.
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.builds > 0).then(|| (self.builds - self.failed) as f64 * 100.0 / self.builds as f64)
+    }
+}
+
+/// Totals of the `records` made for a channel, per channel name, most built first.
+pub fn stats_by_channel<'a>(records: impl IntoIterator<Item = &'a BuildRecord>) -> Vec<(String, BuildStats)> {
+    let mut per_channel: BTreeMap<&str, BuildStats> = BTreeMap::new();
+    for record in records {
+        if let Some(channel) = &record.channel {
+            per_channel.entry(channel.as_str()).or_default().add(record);
+        }
+    }
+    let mut per_channel: Vec<(String, BuildStats)> = per_channel.into_iter().map(|(name, s)| (name.to_string(), s)).collect();
+    per_channel.sort_by_key(|(_, s)| std::cmp::Reverse(s.builds));
+    per_channel
+}
+
+/// Totals of `records` overall and per app name, apps with the most builds first.
+pub fn stats<'a>(records: impl IntoIterator<Item = &'a BuildRecord>) -> (BuildStats, Vec<(String, BuildStats)>) {
+    let mut total = BuildStats::default();
+    let mut per_app: BTreeMap<&str, BuildStats> = BTreeMap::new();
+    for record in records {
+        total.add(record);
+        per_app.entry(record.app_name.as_str()).or_default().add(record);
+    }
+    let mut per_app: Vec<(String, BuildStats)> = per_app.into_iter().map(|(name, s)| (name.to_string(), s)).collect();
+    per_app.sort_by_key(|(_, s)| std::cmp::Reverse(s.builds));
+    (total, per_app)
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS builds (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        app_id TEXT NOT NULL,
+        app_name TEXT NOT NULL,
+        success INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        size_bytes INTEGER,
+        output_path TEXT,
+        record TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS builds_timestamp ON builds (timestamp);
+    CREATE INDEX IF NOT EXISTS builds_app_timestamp ON builds (app_id, timestamp);
+    CREATE INDEX IF NOT EXISTS builds_output_path ON builds (output_path);";
+
+/// Build history stored in a SQLite database in the data directory, one row per build with
+/// the full record as JSON, and kept in memory for the UI.
+#[derive(Debug)]
+pub struct BuildHistory {
+    db_path: PathBuf,
+    /// `None` when the database couldn't be opened; builds are then only kept in memory.
+    connection: Option<Connection>,
+    records: Vec<BuildRecord>,
+}
+
+impl BuildHistory {
+    /// Opens the database at `db_path`. On first use, the records of `builds.jsonl` next to it,
+    /// where the history was kept before, are imported and the file renamed to
+    /// `builds.jsonl.migrated`.
+    pub fn new(db_path: PathBuf) -> Self {
+        let connection = match database::open(&db_path, SCHEMA) {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                log::error!("Failed to open build history {}: {}", db_path.display(), e);
+                None
+            }
+        };
+        let mut history = Self { db_path, connection, records: Vec::new() };
+        if let Err(e) = history.import_legacy_jsonl() {
+            log::error!("Failed to import {}: {}", database::legacy_jsonl_path(&history.db_path).display(), e);
+        }
+        if let Err(e) = history.load() {
+            log::error!("Failed to load build history {}: {}", history.db_path.display(), e);
+        }
+        history
+    }
+
+    fn import_legacy_jsonl(&mut self) -> io::Result<()> {
+        let legacy = database::legacy_jsonl_path(&self.db_path);
+        let Some(connection) = self.connection.as_mut().filter(|_| legacy.is_file()) else {
+            return Ok(());
+        };
+        let records: Vec<BuildRecord> = database::read_jsonl(&legacy)?;
+        let tx = connection.transaction().map_err(io::Error::other)?;
+        for record in &records {
+            insert(&tx, record).map_err(io::Error::other)?;
+        }
+        tx.commit().map_err(io::Error::other)?;
+        database::retire_legacy_jsonl(&legacy)?;
+        log::info!("Imported {} builds from {}", records.len(), legacy.display());
+        Ok(())
+    }
+
+    fn load(&mut self) -> io::Result<()> {
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+        let mut statement = connection.prepare("SELECT record FROM builds ORDER BY id").map_err(io::Error::other)?;
+        for json in statement.query_map([], |row| row.get::<_, String>(0)).map_err(io::Error::other)? {
+            let json = json.map_err(io::Error::other)?;
+            match serde_json::from_str::<BuildRecord>(&json) {
+                Ok(record) => self.records.push(record),
+                Err(e) => log::warn!("Failed to parse build record '{}': {}", json, e),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, record: BuildRecord) {
+        if let Some(connection) = &self.connection {
+            if let Err(e) = insert(connection, &record) {
+                log::error!("Failed to write build record to {}: {}", self.db_path.display(), e);
+            }
+        }
+        self.records.push(record);
+    }
+
+    fn connection(&self) -> io::Result<&Connection> {
+        self.connection
+            .as_ref()
+            .ok_or_else(|| io::Error::other(format!("The build history {} is not open", self.db_path.display())))
+    }
+
+    /// Points the records of the IPA at `from` to `to` after it was renamed. Returns how many
+    /// records changed.
+    pub fn rename_output(&mut self, from: &str, to: &str) -> io::Result<usize> {
+        let mut changed = 0;
+        for record in self.records.iter_mut().filter(|r| r.output_path.as_deref() == Some(from)) {
+            record.output_path = Some(to.to_string());
+            changed += 1;
+        }
+        if changed > 0 {
+            self.connection()?
+                .execute(
+                    "UPDATE builds SET output_path = ?2, record = json_set(record, '$.output_path', ?2) WHERE output_path = ?1",
+                    [from, to],
+                )
+                .map_err(io::Error::other)?;
+        }
+        Ok(changed)
+    }
+
+    /// Deletes the records of builds before `cutoff` and returns how many there were.
+    pub fn prune_before(&mut self, cutoff: DateTime<Utc>) -> io::Result<usize> {
+        let deleted = self
+            .connection()?
+            .execute("DELETE FROM builds WHERE timestamp < ?1", [database::timestamp(cutoff)])
+            .map_err(io::Error::other)?;
+        self.records.retain(|r| r.timestamp >= cutoff);
+        Ok(deleted)
+    }
+
+    /// Records of builds from `since` (included) until `until` (excluded), optionally of one
+    /// app, oldest first, read from the database rather than memory.
+    pub fn records_between(&self, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>, app_id: Option<&str>) -> io::Result<Vec<BuildRecord>> {
+        let mut statement = self
+            .connection()?
+            .prepare(
+                "SELECT record FROM builds WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2)
+                 AND (?3 IS NULL OR app_id = ?3) ORDER BY timestamp, id",
+            )
+            .map_err(io::Error::other)?;
+        let rows = statement
+            .query_map(params![since.map(database::timestamp), until.map(database::timestamp), app_id], |row| row.get::<_, String>(0))
+            .map_err(io::Error::other)?;
+        let mut records = Vec::new();
+        for json in rows {
+            records.push(serde_json::from_str(&json.map_err(io::Error::other)?).map_err(io::Error::other)?);
+        }
+        Ok(records)
+    }
+
+    /// Builds per day of `range` in `zone`, optionally of one app, for the history chart. SQLite
+    /// totals builds per UTC hour, which are then put into days of the zone here.
+    pub fn daily_totals(&self, app_id: Option<&str>, range: DateRange, zone: &DisplayTimeZone, today: NaiveDate) -> io::Result<Vec<DailyTotals>> {
+        let (since, until) = range.utc_bounds(today);
+        let mut statement = self
+            .connection()?
+            .prepare(
+                "SELECT substr(timestamp, 1, 13) AS hour, COUNT(*), SUM(success = 0) FROM builds
+                 WHERE (?1 IS NULL OR app_id = ?1) AND (?2 IS NULL OR timestamp >= ?2) AND (?3 IS NULL OR timestamp < ?3)
+                 GROUP BY hour ORDER BY hour",
+            )
+            .map_err(io::Error::other)?;
+        let rows = statement
+            .query_map(params![app_id, since.map(|d| d.to_string()), until.map(|d| d.to_string())], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+            })
+            .map_err(io::Error::other)?;
+        let mut days: BTreeMap<NaiveDate, DailyTotals> = BTreeMap::new();
+        for row in rows {
+            let (hour, builds, failed) = row.map_err(io::Error::other)?;
+            let Ok(start) = NaiveDateTime::parse_from_str(&format!("{}:00", hour), "%Y-%m-%dT%H:%M") else {
+                continue;
+            };
+            let day = zone.date(start.and_utc());
+            if range.contains(day, today) {
+                let totals = days.entry(day).or_insert(DailyTotals { day, builds: 0, failed: 0 });
+                totals.builds += builds as usize;
+                totals.failed += failed as usize;
+            }
+        }
+        Ok(days.into_values().collect())
+    }
+
+    /// All records, oldest first.
+    pub fn records(&self) -> &[BuildRecord] {
+        &self.records
+    }
+
+    /// Average duration of the app's last ten successful builds.
+    pub fn average_duration_ms(&self, app_id: &str) -> Option<u128> {
+        let recent: Vec<u128> = self.records.iter().rev().filter(|r| r.app_id == app_id && r.success).take(10).map(|r| r.duration_ms).collect();
+        (!recent.is_empty()).then(|| recent.iter().sum::<u128>() / recent.len() as u128)
+    }
+
+    /// Records whose artifact matches `query` (see [`BuildRecord::matches`]), newest first.
+    pub fn search(&self, query: &str) -> Vec<&BuildRecord> {
+        self.records.iter().rev().filter(|r| r.matches(query)).collect()
+    }
+}
+
+fn insert(connection: &Connection, record: &BuildRecord) -> rusqlite::Result<usize> {
+    let json = serde_json::to_string(record).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    connection.execute(
+        "INSERT INTO builds (timestamp, app_id, app_name, success, duration_ms, size_bytes, output_path, record)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            database::timestamp(record.timestamp),
+            record.app_id,
+            record.app_name,
+            record.success,
+            i64::try_from(record.duration_ms).unwrap_or(i64::MAX),
+            record.size_bytes.map(|size| i64::try_from(size).unwrap_or(i64::MAX)),
+            record.output_path,
+            json
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_survive_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BUILD_HISTORY_DB_FILE_NAME);
+        let mut history = BuildHistory::new(path.clone());
+        history.record(BuildRecord {
+            timestamp: Utc::now(),
+            app_id: "1".to_string(),
+            app_name: "Shop".to_string(),
+            success: true,
+            duration_ms: 1200,
+            output_path: Some("/out/shop.ipa".to_string()),
+            release_notes: Some("- Fixed login".to_string()),
+            error: None,
+            warnings: Vec::new(),
+            sha256: Some("9f86d081884c7d65".to_string()),
+            size_bytes: Some(48_000_000),
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        });
+
+        let reloaded = BuildHistory::new(path);
+        assert_eq!(reloaded.records().len(), 1);
+        assert_eq!(reloaded.records()[0].release_notes.as_deref(), Some("- Fixed login"));
+
+        for query in ["SHOP.IPA", "9f86d0", "fixed login"] {
+            assert_eq!(reloaded.search(query).len(), 1, "{}", query);
+        }
+        assert!(reloaded.search("checkout").is_empty());
+        assert!(reloaded.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_date_ranges_and_stats() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let today = day(10);
+        assert!(DateRange::Today.contains(day(10), today) && !DateRange::Today.contains(day(9), today));
+        assert!(DateRange::Last7Days.contains(day(4), today) && !DateRange::Last7Days.contains(day(3), today));
+        assert!(!DateRange::Last7Days.contains(day(11), today));
+        let custom = DateRange::Custom { from: day(1), to: day(2) };
+        assert!(custom.contains(day(2), today) && !custom.contains(day(3), today));
+
+        let record = |app_name: &str, success: bool, duration_ms: u128| BuildRecord {
+            timestamp: Utc::now(),
+            app_id: String::new(),
+            app_name: app_name.to_string(),
+            success,
+            duration_ms,
+            output_path: None,
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: None,
+            size_bytes: success.then_some(100),
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        };
+        let records = [record("Shop", true, 1000), record("Kiosk", false, 400), record("Kiosk", true, 1600)];
+        let (total, per_app) = stats(&records);
+        assert_eq!((total.builds, total.failed, total.total_size_bytes), (3, 1, 200));
+        assert_eq!(total.avg_duration_ms(), Some(1000));
+        assert_eq!(per_app[0].0, "Kiosk");
+        assert_eq!(per_app[0].1.success_rate(), Some(50.0));
+        assert_eq!(stats(&[]).0.avg_duration_ms(), None);
+
+        let beta = |success: bool| BuildRecord { channel: Some("beta".to_string()), ..record("Shop", success, 500) };
+        let per_channel = stats_by_channel(&[beta(true), record("Shop", true, 500), beta(false)]);
+        assert_eq!(per_channel.len(), 1);
+        assert_eq!((per_channel[0].0.as_str(), per_channel[0].1.builds, per_channel[0].1.failed), ("beta", 2, 1));
+    }
+
+    #[test]
+    fn test_jsonl_import_range_queries_and_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let at = |d: u32, h: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap().and_hms_opt(h, 30, 0).unwrap().and_utc();
+        let record = |app_id: &str, timestamp: DateTime<Utc>, success: bool| BuildRecord {
+            timestamp,
+            app_id: app_id.to_string(),
+            app_name: app_id.to_uppercase(),
+            success,
+            duration_ms: 1000,
+            output_path: Some(format!("/out/{}.ipa", app_id)),
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: None,
+            size_bytes: None,
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        };
+        let lines: Vec<String> = [record("shop", at(1, 9), true), record("shop", at(9, 23), false)]
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap())
+            .collect();
+        std::fs::write(dir.path().join("builds.jsonl"), lines.join("\n")).unwrap();
+
+        let path = dir.path().join(BUILD_HISTORY_DB_FILE_NAME);
+        let mut history = BuildHistory::new(path.clone());
+        assert!(dir.path().join("builds.jsonl.migrated").exists());
+        history.record(record("kiosk", at(10, 8), true));
+        history.record(record("shop", at(10, 12), true));
+        assert_eq!(history.records().len(), 4);
+
+        let shop = history.records_between(Some(at(5, 0)), None, Some("shop")).unwrap();
+        assert_eq!(shop.iter().map(|r| r.timestamp).collect::<Vec<_>>(), [at(9, 23), at(10, 12)]);
+        assert_eq!(history.records_between(None, Some(at(2, 0)), None).unwrap().len(), 1);
+
+        // 23:30 UTC on the 9th is already the 10th in Paris.
+        let paris = DisplayTimeZone::Named("Europe/Paris".to_string());
+        let totals = history.daily_totals(None, DateRange::Last7Days, &paris, at(10, 0).date_naive()).unwrap();
+        assert_eq!(totals, [DailyTotals { day: at(10, 0).date_naive(), builds: 3, failed: 1 }]);
+        let utc = history.daily_totals(Some("shop"), DateRange::All, &DisplayTimeZone::Utc, at(10, 0).date_naive()).unwrap();
+        assert_eq!(utc.iter().map(|t| t.builds).collect::<Vec<_>>(), [1, 1, 1]);
+
+        assert_eq!(history.rename_output("/out/kiosk.ipa", "/out/Kiosk-1.0.ipa").unwrap(), 1);
+        assert_eq!(history.prune_before(at(2, 0)).unwrap(), 1);
+        let reloaded = BuildHistory::new(path);
+        assert_eq!(reloaded.records().len(), 3);
+        assert_eq!(reloaded.search("Kiosk-1.0").len(), 1);
+    }
+}
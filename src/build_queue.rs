@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+
+/// How urgently a queued build runs. Variants are in run order: every high-priority build runs
+/// before any normal one, and low-priority builds only run when nothing else is queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::High => "⏫ High",
+            Priority::Normal => "Normal",
+            Priority::Low => "⏬ Low",
+        }
+    }
+}
+
+/// What put a build in the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueSource {
+    /// A Generate click while another build was running, or a "Queue with … priority" choice.
+    Manual,
+    /// The config's input ZIP changed and it has `auto_rebuild` on.
+    AutoRebuild,
+    /// AutoCheck found a new ZIP in its watch folder; see [`QueuedBuild::input_path`].
+    AutoCheck,
+}
+
+impl QueueSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            QueueSource::Manual => "manual",
+            QueueSource::AutoRebuild => "input changed",
+            QueueSource::AutoCheck => "AutoCheck",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedBuild {
+    pub id: u64,
+    pub config_id: String,
+    /// Input ZIP to build instead of the config's own, for AutoCheck builds, whose config is
+    /// not one of the saved ones.
+    pub input_path: Option<String>,
+    pub release_notes: Option<String>,
+    pub priority: Priority,
+    pub source: QueueSource,
+    pub queued_at: DateTime<Utc>,
+    /// Why the build could not start at the last attempt, e.g. its input still being written.
+    pub waiting_for: Option<String>,
+}
+
+/// Builds waiting for the single build slot, kept in run order: by priority, then in the order
+/// they were queued or arranged by dragging.
+#[derive(Debug, Default)]
+pub struct BuildQueue {
+    jobs: Vec<QueuedBuild>,
+    next_id: u64,
+}
+
+impl BuildQueue {
+    /// The queued builds in the order they will run.
+    pub fn jobs(&self) -> &[QueuedBuild] {
+        &self.jobs
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn contains_config(&self, config_id: &str) -> bool {
+        self.jobs.iter().any(|j| j.config_id == config_id)
+    }
+
+    /// Queues a build of `config_id` behind the builds of the same or higher priority. A config
+    /// is only queued once: queuing it again keeps its place, takes the new release notes if
+    /// any, and moves it up if `priority` is higher. Returns the job's id.
+    pub fn push(&mut self, config_id: &str, release_notes: Option<String>, priority: Priority, source: QueueSource) -> u64 {
+        self.push_input(config_id, None, release_notes, priority, source)
+    }
+
+    /// [`Self::push`] for a build of `input_path` with `config_id`'s settings. Builds of the
+    /// same config with different inputs are queued separately.
+    pub fn push_input(&mut self, config_id: &str, input_path: Option<String>, release_notes: Option<String>, priority: Priority, source: QueueSource) -> u64 {
+        if let Some(job) = self.jobs.iter_mut().find(|j| j.config_id == config_id && j.input_path == input_path) {
+            let id = job.id;
+            if release_notes.is_some() {
+                job.release_notes = release_notes;
+            }
+            if priority < job.priority {
+                self.set_priority(id, priority);
+            }
+            return id;
+        }
+        self.next_id += 1;
+        let job = QueuedBuild {
+            id: self.next_id,
+            config_id: config_id.to_string(),
+            input_path,
+            release_notes,
+            priority,
+            source,
+            queued_at: Utc::now(),
+            waiting_for: None,
+        };
+        let at = self.insert_position(priority);
+        self.jobs.insert(at, job);
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<QueuedBuild> {
+        let at = self.jobs.iter().position(|j| j.id == id)?;
+        Some(self.jobs.remove(at))
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&QueuedBuild) -> bool) {
+        self.jobs.retain(keep);
+    }
+
+    /// Moves the job to the end of its new priority's builds, so raising a build to high
+    /// priority lets it jump the queue.
+    pub fn set_priority(&mut self, id: u64, priority: Priority) {
+        if let Some(mut job) = self.remove(id) {
+            job.priority = priority;
+            let at = self.insert_position(priority);
+            self.jobs.insert(at, job);
+        }
+    }
+
+    /// Moves the job at `from` to position `to`, as when dragged in the queue panel. Dropped
+    /// between builds of another priority, it takes on their priority, so the queue stays in
+    /// run order.
+    pub fn move_job(&mut self, from: usize, to: usize) {
+        if from >= self.jobs.len() {
+            return;
+        }
+        let mut job = self.jobs.remove(from);
+        let to = to.min(self.jobs.len());
+        if let Some(previous) = to.checked_sub(1).map(|i| self.jobs[i].priority) {
+            job.priority = job.priority.max(previous);
+        }
+        if let Some(next) = self.jobs.get(to).map(|j| j.priority) {
+            job.priority = job.priority.min(next);
+        }
+        self.jobs.insert(to, job);
+    }
+
+    /// Takes the first build in run order that `readiness` accepts, considering only builds of
+    /// `at_least` priority or higher. Low-priority builds are skipped while any other build is
+    /// queued. Builds passed over keep the reason in `waiting_for`.
+    pub fn take_ready(&mut self, at_least: Priority, mut readiness: impl FnMut(&QueuedBuild) -> Result<(), String>) -> Option<QueuedBuild> {
+        let others_queued = self.jobs.first().is_some_and(|j| j.priority != Priority::Low);
+        for i in 0..self.jobs.len() {
+            let job = &mut self.jobs[i];
+            if job.priority > at_least || (job.priority == Priority::Low && others_queued) {
+                break;
+            }
+            match readiness(job) {
+                Ok(()) => return Some(self.jobs.remove(i)),
+                Err(reason) => job.waiting_for = Some(reason),
+            }
+        }
+        None
+    }
+
+    fn insert_position(&self, priority: Priority) -> usize {
+        self.jobs.iter().position(|j| j.priority > priority).unwrap_or(self.jobs.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(queue: &BuildQueue) -> Vec<(&str, Priority)> {
+        queue.jobs().iter().map(|j| (j.config_id.as_str(), j.priority)).collect()
+    }
+
+    #[test]
+    fn test_priorities_and_reordering() {
+        let mut queue = BuildQueue::default();
+        queue.push("nightly", None, Priority::Low, QueueSource::Manual);
+        queue.push("shop", None, Priority::Normal, QueueSource::AutoRebuild);
+        queue.push("blog", None, Priority::Normal, QueueSource::Manual);
+        let hotfix = queue.push("hotfix", None, Priority::High, QueueSource::Manual);
+        assert_eq!(queue.push("hotfix", Some("Fix login".to_string()), Priority::Low, QueueSource::Manual), hotfix);
+        assert_eq!(order(&queue), [("hotfix", Priority::High), ("shop", Priority::Normal), ("blog", Priority::Normal), ("nightly", Priority::Low)]);
+        assert_eq!(queue.jobs()[0].release_notes.as_deref(), Some("Fix login"));
+
+        // Queuing again with a higher priority, or raising it, jumps the queue.
+        queue.push("blog", None, Priority::High, QueueSource::Manual);
+        assert_eq!(order(&queue)[..2], [("hotfix", Priority::High), ("blog", Priority::High)]);
+        let shop = queue.jobs()[2].id;
+        queue.set_priority(shop, Priority::High);
+        assert_eq!(order(&queue)[2], ("shop", Priority::High));
+
+        // Dragging into another priority's builds takes on their priority.
+        queue.move_job(3, 0);
+        assert_eq!(order(&queue), [("nightly", Priority::High), ("hotfix", Priority::High), ("blog", Priority::High), ("shop", Priority::High)]);
+        queue.move_job(0, 9);
+        assert_eq!(order(&queue)[3], ("nightly", Priority::High));
+        queue.set_priority(queue.jobs()[3].id, Priority::Low);
+        queue.move_job(1, 0);
+        assert_eq!(order(&queue)[..2], [("blog", Priority::High), ("hotfix", Priority::High)]);
+
+        // Not-ready builds are passed over; low priority waits for an otherwise empty queue.
+        let ready = |job: &QueuedBuild| if job.config_id == "blog" { Err("still being written".to_string()) } else { Ok(()) };
+        assert_eq!(queue.take_ready(Priority::High, ready).unwrap().config_id, "hotfix");
+        assert_eq!(queue.jobs()[0].waiting_for.as_deref(), Some("still being written"));
+        assert_eq!(queue.take_ready(Priority::Normal, ready).unwrap().config_id, "shop");
+        assert!(queue.take_ready(Priority::Low, ready).is_none());
+        assert!(queue.remove(queue.jobs()[0].id).is_some());
+        assert_eq!(queue.take_ready(Priority::Low, ready).unwrap().config_id, "nightly");
+        assert!(queue.is_empty());
+    }
+}
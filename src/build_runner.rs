@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app::AppConfig;
+use crate::ipa_logic::{BuildOptions, BuildOutput, BuildProgress, IpaError};
+
+enum BuildMessage {
+    Progress(BuildProgress),
+    Finished(FinishedBuild),
+}
+
+/// Outcome of a background build.
+pub struct FinishedBuild {
+    pub result: Result<BuildOutput, IpaError>,
+    /// SHA-256 of the IPA, computed on the worker so large files don't stall the UI.
+    pub sha256: Option<String>,
+}
+
+/// The watchdog never fires before a build has run this long, so quick builds that take a few
+/// seconds more than usual don't raise alarms.
+const WATCHDOG_MIN_LIMIT: Duration = Duration::from_secs(60);
+
+/// How long a build may run before the watchdog warns: `multiplier` times the app's `average`
+/// build time, but at least [`WATCHDOG_MIN_LIMIT`]. `None` without history or when `multiplier`
+/// is 0 (off).
+pub fn watchdog_limit(average: Option<Duration>, multiplier: f32) -> Option<Duration> {
+    if multiplier <= 0.0 {
+        return None;
+    }
+    average.map(|avg| avg.mul_f32(multiplier).max(WATCHDOG_MIN_LIMIT))
+}
+
+/// A manual build held back because its input ZIP did not pass
+/// [`crate::autocheck::input_readiness`].
+pub struct PendingBuild {
+    pub config_id: String,
+    pub release_notes: Option<String>,
+    pub reason: String,
+    /// Set once the user chose to wait: the check is repeated and the build starts when it passes.
+    pub waiting_since: Option<Instant>,
+    pub last_checked: Instant,
+}
+
+/// Runs one IPA generation on a worker thread so the UI stays responsive, relaying progress
+/// over a channel like [`crate::autocheck::AutoCheckRunner`].
+pub struct BuildRunner {
+    config: AppConfig,
+    options: BuildOptions,
+    started: Instant,
+    progress: Option<BuildProgress>,
+    rx: mpsc::Receiver<BuildMessage>,
+    /// Average of earlier builds and the elapsed time past which the build counts as hanging.
+    watchdog: Option<(Duration, Duration)>,
+}
+
+impl BuildRunner {
+    pub fn start(config: AppConfig, output_dir: PathBuf, options: BuildOptions) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread_config = config.clone();
+        let thread_options = options.clone();
+        thread::spawn(move || {
+            let progress_tx = tx.clone();
+            let result = crate::ipa_logic::generate_ipa_with_progress(&thread_config, &output_dir, &thread_options, &|p| {
+                let _ = progress_tx.send(BuildMessage::Progress(p));
+            });
+            let sha256 = result.as_ref().ok().and_then(|output| match output.sha256.clone().map_or_else(|| crate::transfer::sha256_file(&output.ipa_path), Ok) {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    log::warn!("Failed to hash {}: {}", output.ipa_path.display(), e);
+                    None
+                }
+            });
+            let _ = tx.send(BuildMessage::Finished(FinishedBuild { result, sha256 }));
+        });
+        Self { config, options, started: Instant::now(), progress: None, rx, watchdog: None }
+    }
+
+    /// A build that failed before it could start; [`Self::poll`] reports `error` right away.
+    pub fn failed(config: AppConfig, options: BuildOptions, error: IpaError) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let _ = tx.send(BuildMessage::Finished(FinishedBuild { result: Err(error), sha256: None }));
+        Self { config, options, started: Instant::now(), progress: None, rx, watchdog: None }
+    }
+
+    /// Warns through [`Self::overdue`] once the build runs longer than `limit`, typically from
+    /// [`watchdog_limit`] for `average`.
+    pub fn with_watchdog(mut self, average: Duration, limit: Duration) -> Self {
+        self.watchdog = Some((average, limit));
+        self
+    }
+
+    /// The usual build time when the build has passed its watchdog limit.
+    pub fn overdue(&self) -> Option<Duration> {
+        self.watchdog.filter(|(_, limit)| self.elapsed() > *limit).map(|(average, _)| average)
+    }
+
+    /// Silences the watchdog until the build has taken twice as long as it has so far.
+    pub fn keep_waiting(&mut self) {
+        let elapsed = self.elapsed();
+        if let Some((_, limit)) = &mut self.watchdog {
+            *limit = elapsed * 2;
+        }
+    }
+
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    pub fn options(&self) -> &BuildOptions {
+        &self.options
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Asks the build to stop; [`Self::poll`] then returns [`IpaError::Cancelled`] once it has
+    /// cleaned up.
+    pub fn cancel(&self) {
+        self.options.cancel.cancel();
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.options.cancel.is_cancelled()
+    }
+
+    /// Latest progress report, if the build has reported any yet.
+    pub fn progress(&self) -> Option<BuildProgress> {
+        self.progress
+    }
+
+    /// Applies pending progress reports and returns the outcome once the build has finished.
+    pub fn poll(&mut self) -> Option<FinishedBuild> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(BuildMessage::Progress(p)) => self.progress = Some(p),
+                Ok(BuildMessage::Finished(finished)) => return Some(finished),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    return Some(FinishedBuild { result: Err(IpaError::WorkerStopped), sha256: None })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_limit() {
+        assert_eq!(watchdog_limit(Some(Duration::from_secs(40)), 3.0), Some(Duration::from_secs(120)));
+        assert_eq!(watchdog_limit(Some(Duration::from_secs(5)), 3.0), Some(WATCHDOG_MIN_LIMIT));
+        assert_eq!(watchdog_limit(Some(Duration::from_secs(40)), 0.0), None);
+        assert_eq!(watchdog_limit(None, 3.0), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = crate::build_history::BuildHistory::new(dir.path().join(crate::build_history::BUILD_HISTORY_DB_FILE_NAME));
+        for (app_id, success, duration_ms) in [("a", true, 1000), ("a", false, 90_000), ("b", true, 5000), ("a", true, 3000)] {
+            history.record(crate::build_history::BuildRecord {
+                timestamp: chrono::Utc::now(),
+                app_id: app_id.to_string(),
+                app_name: String::new(),
+                success,
+                duration_ms,
+                output_path: None,
+                release_notes: None,
+                error: None,
+                warnings: Vec::new(),
+                sha256: None,
+                size_bytes: None,
+                profile_expires_at: None,
+                size_breakdown: None,
+                channel: None,
+            });
+        }
+        assert_eq!(history.average_duration_ms("a"), Some(2000));
+        assert_eq!(history.average_duration_ms("c"), None);
+    }
+}
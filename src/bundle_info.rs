@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The `Info.plist` keys IPA Builder cares about.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BundleInfo {
+    pub bundle_identifier: Option<String>,
+    pub short_version: Option<String>,
+    pub bundle_version: Option<String>,
+    pub display_name: Option<String>,
+    pub minimum_os_version: Option<String>,
+}
+
+impl BundleInfo {
+    /// Parses an `Info.plist` in XML or binary format.
+    pub fn from_plist_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let dict = crate::info_plist::parse_dictionary(bytes).map_err(|e| format!("Info.plist: {}", e))?;
+        let string = |key: &str| dict.get(key).and_then(|v| v.as_string()).map(str::to_string);
+        Ok(Self {
+            bundle_identifier: string("CFBundleIdentifier"),
+            short_version: string("CFBundleShortVersionString"),
+            bundle_version: string("CFBundleVersion"),
+            display_name: string("CFBundleDisplayName").or_else(|| string("CFBundleName")),
+            minimum_os_version: string("MinimumOSVersion"),
+        })
+    }
+
+    /// `1.2.0 (42)`, or whichever part is known.
+    pub fn version_label(&self) -> String {
+        match (&self.short_version, &self.bundle_version) {
+            (Some(short), Some(build)) => format!("{} ({})", short, build),
+            (Some(v), None) | (None, Some(v)) => v.clone(),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+}
+
+/// Reads the main app's `Payload/<App>.app/Info.plist` from an IPA.
+pub fn read_from_ipa(ipa_path: &Path) -> Result<BundleInfo, String> {
+    let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    let plist_name = archive
+        .file_names()
+        .find(|name| is_main_info_plist(name))
+        .map(str::to_string)
+        .ok_or("IPA has no Payload/<App>.app/Info.plist")?;
+    read_plist_entry(&mut archive, &plist_name)
+}
+
+/// Reads the `Info.plist` of the outermost `.app` in an input ZIP such as `Runner.app.zip`,
+/// skipping those of embedded extensions and frameworks.
+pub fn read_from_input_zip(zip_path: &Path) -> Result<BundleInfo, String> {
+    let file = File::open(zip_path).map_err(|e| format!("Failed to open {}: {}", zip_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid ZIP archive: {}", e))?;
+    let plist_name = archive
+        .file_names()
+        .filter(|name| is_app_info_plist(name))
+        .min_by_key(|name| name.matches('/').count())
+        .map(str::to_string)
+        .ok_or("ZIP has no <App>.app/Info.plist")?;
+    read_plist_entry(&mut archive, &plist_name)
+}
+
+fn read_plist_entry(archive: &mut zip::ZipArchive<File>, plist_name: &str) -> Result<BundleInfo, String> {
+    let mut bytes = Vec::new();
+    archive
+        .by_name(plist_name)
+        .map_err(|e| e.to_string())?
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    BundleInfo::from_plist_bytes(&bytes)
+}
+
+fn is_main_info_plist(name: &str) -> bool {
+    let mut parts = name.split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some("Payload"), Some(app), Some("Info.plist"), None) if app.ends_with(".app")
+    )
+}
+
+/// `Info.plist` directly inside a `.app` that is not nested in another bundle.
+fn is_app_info_plist(name: &str) -> bool {
+    let Some(bundle) = name.strip_suffix("/Info.plist") else {
+        return false;
+    };
+    let mut components = bundle.split('/').rev();
+    components.next().is_some_and(|app| app.ends_with(".app"))
+        && components.all(|c| !c.ends_with(".app") && !c.ends_with(".appex") && !c.ends_with(".framework"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_xml_and_binary_plists() {
+        let mut dict = plist::Dictionary::new();
+        dict.insert("CFBundleIdentifier".into(), "com.example.shop".into());
+        dict.insert("CFBundleShortVersionString".into(), "1.2.0".into());
+        dict.insert("CFBundleVersion".into(), "42".into());
+        dict.insert("CFBundleName".into(), "Shop".into());
+        let value = plist::Value::Dictionary(dict);
+
+        let mut xml = Vec::new();
+        value.to_writer_xml(&mut xml).unwrap();
+        let mut binary = Vec::new();
+        value.to_writer_binary(&mut binary).unwrap();
+
+        for bytes in [xml, binary] {
+            let info = BundleInfo::from_plist_bytes(&bytes).unwrap();
+            assert_eq!(info.bundle_identifier.as_deref(), Some("com.example.shop"));
+            assert_eq!(info.display_name.as_deref(), Some("Shop"));
+            assert_eq!(info.version_label(), "1.2.0 (42)");
+        }
+        assert!(is_main_info_plist("Payload/Shop.app/Info.plist"));
+        assert!(!is_main_info_plist("Payload/Shop.app/PlugIns/Widget.appex/Info.plist"));
+        assert!(is_app_info_plist("Runner.app/Info.plist") && is_app_info_plist("build/ios/Runner.app/Info.plist"));
+        assert!(!is_app_info_plist("Runner.app/Watch/Companion.app/Info.plist"));
+        assert!(!is_app_info_plist("Runner.app/Frameworks/App.framework/Info.plist"));
+    }
+}
@@ -0,0 +1,849 @@
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::AppConfig;
+use crate::audit::{AuditAction, AuditLog};
+use crate::build_history::{BuildHistory, BuildRecord, BUILD_HISTORY_DB_FILE_NAME};
+use crate::codesign::{verify_ipa_signature, SignatureReport};
+use crate::fixtures::{write_fixture, FixtureSpec};
+use crate::config_utils::{get_apps_import_file_path, get_config_dir_path, get_data_dir_path};
+use crate::ipa_logic::BuildPhase;
+use crate::metrics::{MetricEvent, MetricsCollector, METRICS_DB_FILE_NAME};
+use crate::naming_audit::AuditStatus;
+use crate::projects::Project;
+use crate::settings::AppSettings;
+use crate::state_store::{load_or_migrate, StateStore, StoreKind, STORE_ENV};
+use crate::validator::{validate_ipa, Severity};
+
+const EXIT_OK: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+/// The `--config` id or name matches no saved app.
+const EXIT_NOT_FOUND: i32 = 3;
+
+const USAGE: &str = "Usage:
+  ipa_builder list [--json]
+  ipa_builder add --name <name> --input <zip|url> --output <name.ipa> [--changelog <path>] [--json]
+  ipa_builder build --config <id|name> [--output-dir <dir>] [--notes <text>] [--channel <name>] [--deterministic] [--checksum] [--json]
+  ipa_builder watch --config <id|name> [--output-dir <dir>] [--interval <seconds>] [--deterministic] [--checksum] [--json]
+  ipa_builder verify-signature <path/to/app.ipa> [--json]
+  ipa_builder validate <path/to/app.ipa> [--json]
+  ipa_builder naming-audit [--output-dir <dir>] [--rename] [--json]
+  ipa_builder diagnostics [--json]
+  ipa_builder history [--since <YYYY-MM-DD>] [--until <YYYY-MM-DD>] [--config <id|name>] [--json]
+  ipa_builder prune-history --older-than <days>
+  ipa_builder fixture --output <Runner.app.zip> [--name <app>] [--prefix <dir>] [--size-kb <n>] [--depth <n>] [--frameworks <n>] [--symlinks] [--unicode]
+  ipa_builder agent [--listen <addr>] [--token <token>] [--uploads-dir <dir>]
+
+Exit codes: 0 success, 1 failure, 2 usage error, 3 unknown config.";
+
+/// Runs a command-line subcommand if `args` (without the program name) starts with one.
+/// Returns the process exit code, or `None` when the GUI should be started instead.
+/// A leading `--cli` never starts the GUI, so scripts fail loudly on a typo.
+pub fn run_from_args(args: &[String]) -> Option<i32> {
+    let forced = args.first().is_some_and(|a| a == "--cli");
+    let args = if forced { &args[1..] } else { args };
+    let Some((command, rest)) = args.split_first() else {
+        return forced.then(|| usage_error(""));
+    };
+    match command.as_str() {
+        "list" => Some(list_command(rest)),
+        "add" => Some(add_command(rest)),
+        "build" => Some(build_command(rest)),
+        "watch" => Some(watch_command(rest)),
+        "verify-signature" => Some(verify_signature_command(rest)),
+        "validate" => Some(validate_command(rest)),
+        "naming-audit" => Some(naming_audit_command(rest)),
+        "diagnostics" => Some(diagnostics_command(rest)),
+        "history" => Some(history_command(rest)),
+        "prune-history" => Some(prune_history_command(rest)),
+        "fixture" => Some(fixture_command(rest)),
+        "agent" => Some(agent_command(rest)),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Some(EXIT_OK)
+        }
+        _ if forced => Some(usage_error(&format!("unknown command '{}'", command))),
+        _ => None,
+    }
+}
+
+fn usage_error(message: &str) -> i32 {
+    if !message.is_empty() {
+        eprintln!("error: {}", message);
+    }
+    eprintln!("{}", USAGE);
+    EXIT_USAGE
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).map(String::as_str)
+}
+
+fn print_json<T: Serialize>(value: &T) -> i32 {
+    match serde_json::to_string_pretty(value) {
+        Ok(s) => {
+            println!("{}", s);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("Failed to serialize output: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+/// The parts of the app state the headless commands use. Everything else the GUI keeps there
+/// is carried through unchanged when the state is saved back.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct SavedState {
+    #[serde(default)]
+    output_directory: Option<String>,
+    #[serde(default)]
+    app_configs: Vec<AppConfig>,
+    #[serde(default)]
+    settings: AppSettings,
+    #[serde(default)]
+    projects: Vec<Project>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SavedState {
+    /// Loads the state from `store` (an empty state when nothing was saved yet) and merges the
+    /// `apps.json` drop-in file like the GUI does on startup.
+    pub(crate) fn load(store: &dyn StateStore, kind: StoreKind, config_dir: &Path, apps_import_file: Option<&Path>) -> Result<Self, String> {
+        let mut state: SavedState = match load_or_migrate(store, kind, config_dir)? {
+            Some(value) => serde_json::from_value(value).map_err(|e| format!("Failed to parse {}: {}", store.location(), e))?,
+            None => SavedState::default(),
+        };
+        if let Some(import_path) = apps_import_file.filter(|p| p.exists()) {
+            let file = crate::app_import::load_apps_import_file(import_path)?;
+            let summary = crate::app_import::merge_imported_apps(&mut state.app_configs, &file);
+            log::info!("Synced {}: {} added, {} updated", import_path.display(), summary.added.len(), summary.updated.len());
+        }
+        Ok(state)
+    }
+
+    fn save(&self, store: &mut dyn StateStore) -> Result<(), String> {
+        let value = serde_json::to_value(self).map_err(|e| format!("Failed to serialize app state: {}", e))?;
+        store.save(&value)
+    }
+
+    /// Finds a config by exact id, then by case-insensitive name.
+    fn find_config(&self, id_or_name: &str) -> Option<&AppConfig> {
+        self.app_configs
+            .iter()
+            .find(|c| c.id == id_or_name)
+            .or_else(|| self.app_configs.iter().find(|c| c.app_name.eq_ignore_ascii_case(id_or_name)))
+    }
+
+    /// The config a build of `id_or_name` uses: with its project's defaults filled in, as the GUI
+    /// builds it.
+    pub(crate) fn config_for_build(&self, id_or_name: &str) -> Option<AppConfig> {
+        self.find_config(id_or_name).map(|config| crate::projects::with_project_defaults(&self.projects, config.clone()))
+    }
+}
+
+/// Opens the state store chosen with `IPA_BUILDER_STATE_STORE` and loads the state from it.
+fn load_state() -> Result<(Box<dyn StateStore>, SavedState), String> {
+    let config_dir = get_config_dir_path().ok_or_else(|| "Could not determine the config directory".to_string())?;
+    let kind = StoreKind::from_env()?;
+    let store = kind
+        .file_store(&config_dir)
+        .ok_or_else(|| format!("The app state is kept in eframe storage, which only the GUI can read; set {} to json, toml or sqlite", STORE_ENV))?;
+    let state = SavedState::load(store.as_ref(), kind, &config_dir, get_apps_import_file_path().as_deref())?;
+    Ok((store, state))
+}
+
+fn list_command(args: &[String]) -> i32 {
+    let state = match load_state() {
+        Ok((_, state)) => state,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+    if has_flag(args, "--json") {
+        return print_json(&state.app_configs);
+    }
+    if state.app_configs.is_empty() {
+        println!("No apps configured.");
+    }
+    for config in &state.app_configs {
+        let last = config
+            .last_generated_at
+            .map_or_else(|| "never built".to_string(), |t| format!("built {}", state.settings.time_zone.display(t)));
+        println!("{}  {}  {} -> {} ({})", config.id, config.app_name, config.input_zip_path, config.output_ipa_name, last);
+    }
+    EXIT_OK
+}
+
+fn add_command(args: &[String]) -> i32 {
+    let (name, input, output) = match (flag_value(args, "--name"), flag_value(args, "--input"), flag_value(args, "--output")) {
+        (Some(n), Some(i), Some(o)) if !n.trim().is_empty() && !i.trim().is_empty() => (n.trim(), i.trim(), o.trim()),
+        _ => return usage_error("add needs --name, --input and --output"),
+    };
+    if let Err(reason) = crate::naming::validate_ipa_file_name(output) {
+        eprintln!("error: output filename is invalid: {} (try '{}')", reason, crate::naming::sanitize_ipa_file_name(output));
+        return EXIT_USAGE;
+    }
+    let result = load_state().and_then(|(mut store, mut state)| {
+        if state.find_config(name).is_some() {
+            return Err(format!("An app named '{}' already exists", name));
+        }
+        let config = AppConfig {
+            app_name: name.to_string(),
+            input_zip_path: input.to_string(),
+            output_ipa_name: output.to_string(),
+            changelog_path: flag_value(args, "--changelog").map(str::to_string),
+            ..Default::default()
+        };
+        state.app_configs.push(config.clone());
+        state.save(store.as_mut())?;
+        Ok(config)
+    });
+    match result {
+        Ok(config) => {
+            if let Some(data_dir) = get_data_dir_path() {
+                AuditLog::new(data_dir.join("audit.jsonl")).record(AuditAction::ConfigAdded {
+                    app_id: config.id.clone(),
+                    app_name: config.app_name.clone(),
+                    source: "cli".to_string(),
+                });
+                MetricsCollector::new(data_dir.join(METRICS_DB_FILE_NAME)).record(MetricEvent::AppAdded { app_name: config.app_name.clone() });
+            }
+            if has_flag(args, "--json") {
+                print_json(&config)
+            } else {
+                println!("Added '{}' ({})", config.app_name, config.id);
+                EXIT_OK
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+/// Outcome of one headless build, printed as JSON with `--json`.
+#[derive(Serialize, Debug)]
+struct BuildReport {
+    app_id: String,
+    app_name: String,
+    success: bool,
+    /// Release channel the build was made for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+    ipa_path: Option<PathBuf>,
+    sha256: Option<String>,
+    /// URL of the IPA in the config's S3 bucket, when it was uploaded there.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uploaded_url: Option<String>,
+    warnings: Vec<String>,
+    is_simulator_build: bool,
+    duration_ms: u128,
+    error: Option<String>,
+}
+
+fn build_command(args: &[String]) -> i32 {
+    let Some(id_or_name) = flag_value(args, "--config") else {
+        return usage_error("build needs --config <id|name>");
+    };
+    let json = has_flag(args, "--json");
+    let report = match build_config(id_or_name, args) {
+        Ok(report) => report,
+        Err(code) => return code,
+    };
+    if json {
+        print_json(&report);
+    } else if let Some(path) = &report.ipa_path {
+        for warning in &report.warnings {
+            eprintln!("warning: {}", warning);
+        }
+        if let Some(url) = &report.uploaded_url {
+            eprintln!("Uploaded to {}", url);
+        }
+        println!("{}", path.display());
+    } else {
+        eprintln!("error: {}", report.error.as_deref().unwrap_or("build failed"));
+    }
+    if report.success { EXIT_OK } else { EXIT_FAILED }
+}
+
+/// Builds the config matching `id_or_name` from freshly loaded state, records it like the
+/// GUI does and saves the new `last_generated_at`. Setup problems are returned as exit codes.
+fn build_config(id_or_name: &str, args: &[String]) -> Result<BuildReport, i32> {
+    let (mut store, mut state) = load_state().map_err(|e| {
+        eprintln!("error: {}", e);
+        EXIT_FAILED
+    })?;
+    let Some(mut config) = state.config_for_build(id_or_name) else {
+        eprintln!("error: no app with id or name '{}'; see `ipa_builder list`", id_or_name);
+        return Err(EXIT_NOT_FOUND);
+    };
+    let Some(output_dir) = flag_value(args, "--output-dir").map(str::to_string).or_else(|| state.output_directory.clone()) else {
+        eprintln!("error: no output directory configured; pass --output-dir");
+        return Err(EXIT_USAGE);
+    };
+
+    if let Some(channel) = flag_value(args, "--channel") {
+        config.channel = channel.to_string();
+    }
+    if !config.channel.trim().is_empty() {
+        match crate::channels::find(&state.settings.channels, &config.channel) {
+            Some(channel) => channel.route(&mut config),
+            None => {
+                eprintln!("error: {}; the settings define: {}", crate::ipa_logic::IpaError::UnknownChannel(config.channel.trim().to_string()), state.settings.channels.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", "));
+                return Err(EXIT_USAGE);
+            }
+        }
+    }
+    let channel = Some(config.channel.trim().to_string()).filter(|c| !c.is_empty());
+
+    let mut options = state.settings.build_options();
+    options.release_notes = flag_value(args, "--notes")
+        .map(str::to_string)
+        .or_else(|| crate::app::changelog_release_notes(&config));
+    options.deterministic |= has_flag(args, "--deterministic");
+    options.write_checksum |= has_flag(args, "--checksum");
+
+    let started = Instant::now();
+    let last_phase: Cell<Option<BuildPhase>> = Cell::new(None);
+    let result = crate::ipa_logic::generate_ipa_with_progress(&config, Path::new(&output_dir), &options, &|p| {
+        if last_phase.get() != Some(p.phase) {
+            last_phase.set(Some(p.phase));
+            eprintln!("{}...", p.phase.label());
+        }
+    });
+    let duration_ms = started.elapsed().as_millis();
+
+    let event = crate::webhook::BuildEvent::new(&config, result.as_ref().map_err(|e| e.to_string()), duration_ms, None);
+    let channels = config.notifications.channels_for(&event);
+    if channels.webhook {
+        let webhooks: Vec<_> = state.settings.webhooks.iter().chain(&config.webhooks).cloned().collect();
+        for failure in crate::webhook::deliver_all(&webhooks, &event, &state.settings.network) {
+            eprintln!("warning: webhook failed: {}", failure);
+        }
+    }
+    if channels.email {
+        if let Err(e) = crate::notifications::send_email(&state.settings.email, &config.notifications.email_recipients, &event) {
+            eprintln!("warning: email notification failed: {}", e);
+        }
+    }
+
+    let report = match result {
+        Ok(output) => BuildReport {
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            success: true,
+            channel: channel.clone(),
+            sha256: output.sha256.clone().or_else(|| crate::transfer::sha256_file(&output.ipa_path).ok()),
+            uploaded_url: output.uploaded_url,
+            ipa_path: Some(output.ipa_path),
+            warnings: output.warnings,
+            is_simulator_build: output.is_simulator_build,
+            duration_ms,
+            error: None,
+        },
+        Err(e) => BuildReport {
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            success: false,
+            channel,
+            ipa_path: None,
+            sha256: None,
+            uploaded_url: None,
+            warnings: Vec::new(),
+            is_simulator_build: false,
+            duration_ms,
+            error: Some(e.to_string()),
+        },
+    };
+
+    if let Some(cleanup) = report.ipa_path.as_deref().and_then(|path| config.clean_up_old_builds(path)) {
+        eprintln!("{}", cleanup);
+    }
+    if report.success {
+        if let Some(saved) = state.app_configs.iter_mut().find(|c| c.id == config.id) {
+            saved.record_successful_build(&config);
+        }
+        if let Err(e) = state.save(store.as_mut()) {
+            log::warn!("{}", e);
+        }
+    }
+    if let Some(data_dir) = get_data_dir_path() {
+        record_build(&data_dir, &report, options.release_notes);
+    }
+    Ok(report)
+}
+
+fn record_build(data_dir: &Path, report: &BuildReport, release_notes: Option<String>) {
+    let output_path = report.ipa_path.as_ref().map(|p| p.display().to_string());
+    let size_bytes = report.ipa_path.as_ref().and_then(|p| std::fs::metadata(p).ok()).map(|m| m.len());
+    MetricsCollector::new(data_dir.join(METRICS_DB_FILE_NAME)).record(MetricEvent::IpaGenerated {
+        app_name: report.app_name.clone(),
+        success: report.success,
+        duration_ms: report.duration_ms,
+        output_size_bytes: size_bytes.unwrap_or(0),
+        channel: report.channel.clone(),
+    });
+    AuditLog::new(data_dir.join("audit.jsonl")).record(AuditAction::BuildTriggered {
+        app_id: report.app_id.clone(),
+        app_name: report.app_name.clone(),
+        success: report.success,
+        output_path: output_path.clone(),
+        error: report.error.clone(),
+    });
+    BuildHistory::new(data_dir.join(BUILD_HISTORY_DB_FILE_NAME)).record(BuildRecord {
+        timestamp: Utc::now(),
+        app_id: report.app_id.clone(),
+        app_name: report.app_name.clone(),
+        success: report.success,
+        duration_ms: report.duration_ms,
+        output_path,
+        release_notes,
+        error: report.error.clone(),
+        warnings: report.warnings.clone(),
+        sha256: report.sha256.clone(),
+        size_bytes,
+        profile_expires_at: report.ipa_path.as_deref().and_then(crate::provisioning::profile_expiry),
+        size_breakdown: report.ipa_path.as_deref().and_then(|p| crate::size_analysis::size_breakdown(p).ok()),
+        channel: report.channel.clone(),
+    });
+}
+
+/// Rebuilds the config every time its input changes, until the process is stopped. A change
+/// is only acted on once the input has looked the same for two polls, so half-copied files
+/// are not built. With `--json`, each build is printed as one line.
+fn watch_command(args: &[String]) -> i32 {
+    let Some(id_or_name) = flag_value(args, "--config") else {
+        return usage_error("watch needs --config <id|name>");
+    };
+    let interval = match flag_value(args, "--interval").map(str::parse::<u64>) {
+        None => Duration::from_secs(2),
+        Some(Ok(secs)) if secs > 0 => Duration::from_secs(secs),
+        Some(_) => return usage_error("--interval must be a positive number of seconds"),
+    };
+    let json = has_flag(args, "--json");
+    let (input, network) = match load_state() {
+        Ok((_, state)) => match state.find_config(id_or_name) {
+            Some(config) => (config.input_zip_path.clone(), state.settings.network.clone()),
+            None => {
+                eprintln!("error: no app with id or name '{}'; see `ipa_builder list`", id_or_name);
+                return EXIT_NOT_FOUND;
+            }
+        },
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+
+    eprintln!("Watching {} (Ctrl+C to stop)", input);
+    let mut built = crate::input_cache::current_validator(&input, &network).ok();
+    let mut pending: Option<String> = None;
+    loop {
+        std::thread::sleep(interval);
+        let current = match crate::input_cache::current_validator(&input, &network) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("{}", e);
+                continue;
+            }
+        };
+        if built.as_ref() == Some(&current) {
+            pending = None;
+            continue;
+        }
+        if pending.as_ref() != Some(&current) {
+            pending = Some(current);
+            continue;
+        }
+        pending = None;
+        built = Some(current);
+        match build_config(id_or_name, args) {
+            Ok(report) if json => match serde_json::to_string(&report) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("Failed to serialize output: {}", e),
+            },
+            Ok(report) => match (&report.ipa_path, &report.error) {
+                (Some(path), _) => println!("{}", path.display()),
+                (None, error) => eprintln!("error: {}", error.as_deref().unwrap_or("build failed")),
+            },
+            // The config was deleted or the state became unreadable; nothing left to watch.
+            Err(code) => return code,
+        }
+    }
+}
+
+fn verify_signature_command(args: &[String]) -> i32 {
+    let json = args.iter().any(|a| a == "--json");
+    let ipa = match args.iter().find(|a| !a.starts_with("--")) {
+        Some(p) => p,
+        None => {
+            eprintln!("Usage: ipa_builder verify-signature <path/to/app.ipa> [--json]");
+            return EXIT_USAGE;
+        }
+    };
+
+    match verify_ipa_signature(Path::new(ipa)) {
+        Ok(report) => {
+            if json {
+                match serde_json::to_string_pretty(&report) {
+                    Ok(s) => println!("{}", s),
+                    Err(e) => {
+                        eprintln!("Failed to serialize report: {}", e);
+                        return EXIT_FAILED;
+                    }
+                }
+            } else {
+                print!("{}", format_signature_report(&report));
+            }
+            if report.is_valid() { EXIT_OK } else { EXIT_FAILED }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+fn validate_command(args: &[String]) -> i32 {
+    let Some(ipa) = args.iter().find(|a| !a.starts_with("--")) else {
+        return usage_error("validate needs the path of an IPA");
+    };
+    let report = match validate_ipa(Path::new(ipa)) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+    if has_flag(args, "--json") {
+        if print_json(&report) != EXIT_OK {
+            return EXIT_FAILED;
+        }
+    } else {
+        for finding in &report.findings {
+            let marker = match finding.severity {
+                Severity::Error => "✖",
+                Severity::Warning => "⚠",
+            };
+            println!("  {} {}", marker, finding);
+        }
+        println!("{}", if report.is_valid() { "IPA structure OK" } else { "IPA structure problems found" });
+    }
+    if report.is_valid() { EXIT_OK } else { EXIT_FAILED }
+}
+
+/// Lists the IPAs in the output directory that are not named after their app's current
+/// output name, and with `--rename` renames them. Fails when any are left to rename.
+fn naming_audit_command(args: &[String]) -> i32 {
+    let state = match load_state() {
+        Ok((_, state)) => state,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+    let Some(output_dir) = flag_value(args, "--output-dir").map(str::to_string).or(state.output_directory) else {
+        return usage_error("no output directory configured; pass --output-dir");
+    };
+    let mut history = get_data_dir_path().map(|dir| BuildHistory::new(dir.join(BUILD_HISTORY_DB_FILE_NAME)));
+    let records = history.as_ref().map(BuildHistory::records).unwrap_or_default();
+    let mut audit = match crate::naming_audit::audit_output_dir(Path::new(&output_dir), &state.app_configs, records) {
+        Ok(audit) => audit,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+
+    let mut rename_error = None;
+    if has_flag(args, "--rename") {
+        let renames = audit.iter().filter_map(|e| match &e.status {
+            AuditStatus::Rename { to } => Some((e.path.as_path(), to.as_str())),
+            _ => None,
+        });
+        let (done, error) = crate::naming_audit::rename_ipas(renames);
+        for (from, to) in &done {
+            eprintln!("Renamed {} -> {}", from.display(), to.display());
+            if let Some(history) = history.as_mut() {
+                if let Err(e) = history.rename_output(&from.display().to_string(), &to.display().to_string()) {
+                    log::warn!("Failed to update the build history: {}", e);
+                }
+            }
+            if let Some(entry) = audit.iter_mut().find(|e| &e.path == from) {
+                entry.path = to.clone();
+                entry.status = AuditStatus::UpToDate;
+            }
+        }
+        rename_error = error;
+    }
+
+    if has_flag(args, "--json") {
+        if print_json(&audit) != EXIT_OK {
+            return EXIT_FAILED;
+        }
+    } else {
+        for entry in &audit {
+            let app = entry.app_name.as_deref().unwrap_or("-");
+            match &entry.status {
+                AuditStatus::UpToDate => println!("  ✔ {}  ({})", entry.file_name(), app),
+                AuditStatus::Rename { to } => println!("  ✏ {} -> {}  ({})", entry.file_name(), to, app),
+                AuditStatus::Unresolved { reason } => println!("  ? {}  ({}): {}", entry.file_name(), app, reason),
+                AuditStatus::Unmatched => println!("  - {}  (no matching app)", entry.file_name()),
+            }
+        }
+    }
+    if let Some(e) = rename_error {
+        eprintln!("error: {}", e);
+        return EXIT_FAILED;
+    }
+    let stragglers = audit.iter().filter(|e| matches!(e.status, AuditStatus::Rename { .. })).count();
+    if stragglers > 0 {
+        eprintln!("{} IPA(s) not named after their app's current output name; pass --rename to fix", stragglers);
+        return EXIT_FAILED;
+    }
+    EXIT_OK
+}
+
+/// Lists recorded builds between two UTC dates (both included), optionally of one app, with
+/// their totals.
+fn history_command(args: &[String]) -> i32 {
+    let date = |flag: &str| flag_value(args, flag).map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| format!("{} must be a date like 2024-03-01", flag)));
+    let (since, until) = match (date("--since").transpose(), date("--until").transpose()) {
+        (Ok(since), Ok(until)) => (since, until),
+        (Err(e), _) | (_, Err(e)) => return usage_error(&e),
+    };
+    let app_id = match flag_value(args, "--config") {
+        Some(id_or_name) => match load_state() {
+            Ok((_, state)) => match state.find_config(id_or_name) {
+                Some(config) => Some(config.id.clone()),
+                None => {
+                    eprintln!("error: no app with id or name '{}'; see `ipa_builder list`", id_or_name);
+                    return EXIT_NOT_FOUND;
+                }
+            },
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return EXIT_FAILED;
+            }
+        },
+        None => None,
+    };
+    let Some(data_dir) = get_data_dir_path() else {
+        eprintln!("error: Could not determine the data directory");
+        return EXIT_FAILED;
+    };
+    let start_of = |day: NaiveDate| day.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    let records = match BuildHistory::new(data_dir.join(BUILD_HISTORY_DB_FILE_NAME)).records_between(
+        since.and_then(start_of),
+        until.and_then(|d| d.succ_opt()).and_then(start_of),
+        app_id.as_deref(),
+    ) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_FAILED;
+        }
+    };
+    if has_flag(args, "--json") {
+        return print_json(&records);
+    }
+    for record in &records {
+        let outcome = if record.success { "✔" } else { "✖" };
+        let output = record.output_path.as_deref().or(record.error.as_deref()).unwrap_or("");
+        println!("{} {}  {}  {:.1}s  {}", outcome, record.timestamp.format("%Y-%m-%d %H:%M"), record.app_name, record.duration_ms as f64 / 1000.0, output);
+    }
+    let (total, _) = crate::build_history::stats(&records);
+    match total.success_rate() {
+        Some(rate) => println!("{} build(s), {:.0}% succeeded", total.builds, rate),
+        None => println!("No builds in this range."),
+    }
+    EXIT_OK
+}
+
+/// Deletes build history and usage metrics older than `--older-than` days.
+fn prune_history_command(args: &[String]) -> i32 {
+    let days = match flag_value(args, "--older-than").map(str::parse::<u32>) {
+        Some(Ok(days)) if days > 0 => days,
+        _ => return usage_error("prune-history needs --older-than <days>, a positive number"),
+    };
+    let Some(data_dir) = get_data_dir_path() else {
+        eprintln!("error: Could not determine the data directory");
+        return EXIT_FAILED;
+    };
+    let cutoff = Utc::now() - chrono::Duration::days(days.into());
+    let builds = BuildHistory::new(data_dir.join(BUILD_HISTORY_DB_FILE_NAME)).prune_before(cutoff);
+    let metrics = MetricsCollector::new(data_dir.join(METRICS_DB_FILE_NAME)).prune_before(cutoff);
+    match (builds, metrics) {
+        (Ok(builds), Ok(metrics)) => {
+            println!("Deleted {} build(s) and {} metric(s) older than {} days", builds, metrics, days);
+            EXIT_OK
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+/// Runs the Diagnostics view's environment checks; fails if any check fails.
+fn diagnostics_command(args: &[String]) -> i32 {
+    let output_dir = load_state().ok().and_then(|(_, state)| state.output_directory);
+    let checks = crate::diagnostics::run_checks(output_dir.as_deref().map(Path::new));
+    if has_flag(args, "--json") {
+        if print_json(&checks) != EXIT_OK {
+            return EXIT_FAILED;
+        }
+    } else {
+        print!("{}", crate::diagnostics::report(&checks));
+    }
+    if checks.iter().any(|c| c.status == crate::diagnostics::CheckStatus::Fail) {
+        return EXIT_FAILED;
+    }
+    EXIT_OK
+}
+
+/// Writes a mock `Runner.app.zip` to try a setup without a real Flutter build.
+/// Serves builds for remote GUIs until killed. The token may also come from
+/// `IPA_BUILDER_AGENT_TOKEN`.
+fn agent_command(args: &[String]) -> i32 {
+    if !args.len().is_multiple_of(2) || args.chunks(2).any(|pair| !["--listen", "--token", "--uploads-dir"].contains(&pair[0].as_str())) {
+        return usage_error("agent takes --listen <addr>, --token <token> and --uploads-dir <dir>");
+    }
+    let listen = flag_value(args, "--listen").unwrap_or(crate::agent::DEFAULT_LISTEN_ADDR);
+    let token = flag_value(args, "--token").map(str::to_string).or_else(|| std::env::var(crate::agent::TOKEN_ENV_VAR).ok()).filter(|t| !t.is_empty());
+    let uploads_dir = flag_value(args, "--uploads-dir").map(PathBuf::from).unwrap_or_else(|| std::env::temp_dir().join("ipa-builder-agent-uploads"));
+    if let Err(e) = std::fs::create_dir_all(&uploads_dir) {
+        eprintln!("error: cannot create {}: {}", uploads_dir.display(), e);
+        return EXIT_FAILED;
+    }
+    let server = match tiny_http::Server::http(listen) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("error: cannot listen on {}: {}", listen, e);
+            return EXIT_FAILED;
+        }
+    };
+    if token.is_none() && !listen.starts_with("127.0.0.1") && !listen.starts_with("localhost") {
+        eprintln!("warning: listening on {} without --token; anyone who can reach it can run builds", listen);
+    }
+    println!("IPA Builder agent listening on http://{}", listen);
+    crate::agent::serve(server, token, uploads_dir);
+    EXIT_OK
+}
+
+fn fixture_command(args: &[String]) -> i32 {
+    let Some(output) = flag_value(args, "--output") else {
+        return usage_error("fixture needs --output <Runner.app.zip>");
+    };
+    let mut spec = FixtureSpec { symlinks: has_flag(args, "--symlinks"), unicode_names: has_flag(args, "--unicode"), ..Default::default() };
+    if let Some(name) = flag_value(args, "--name") {
+        spec.app_name = name.to_string();
+    }
+    if let Some(prefix) = flag_value(args, "--prefix") {
+        spec.prefix = prefix.to_string();
+    }
+    for (flag, target) in [("--depth", &mut spec.depth), ("--frameworks", &mut spec.frameworks)] {
+        match flag_value(args, flag).map(str::parse::<usize>) {
+            None => {}
+            Some(Ok(n)) => *target = n,
+            Some(Err(_)) => return usage_error(&format!("{} must be a number", flag)),
+        }
+    }
+    match flag_value(args, "--size-kb").map(str::parse::<u64>) {
+        None => {}
+        Some(Ok(kb)) => spec.resource_bytes = kb * 1024,
+        Some(Err(_)) => return usage_error("--size-kb must be a number of kilobytes"),
+    }
+    match write_fixture(&spec, Path::new(output)) {
+        Ok(entries) => {
+            println!("Wrote {} ({} entries)", output, entries);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+pub fn format_signature_report(report: &SignatureReport) -> String {
+    let mut out = format!(
+        "{} ({})\n",
+        report.app_bundle,
+        if report.cryptographically_verified {
+            "verified with codesign"
+        } else {
+            "signature blobs parsed only; run on macOS for cryptographic verification"
+        }
+    );
+    for binary in &report.binaries {
+        let ok = binary.signed && binary.valid != Some(false) && binary.problem.is_none();
+        let mut details = vec![binary.architectures.join("+")];
+        if let Some(id) = &binary.identifier {
+            details.push(id.clone());
+        }
+        if let Some(team) = &binary.team_id {
+            details.push(format!("team {}", team));
+        }
+        if let Some(authority) = &binary.authority {
+            details.push(authority.clone());
+        }
+        if let Some(problem) = &binary.problem {
+            details.push(problem.clone());
+        }
+        out.push_str(&format!("  {} {} — {}\n", if ok { "✔" } else { "✖" }, binary.path, details.join(", ")));
+    }
+    if let Some(seal) = &report.seal {
+        for problem in seal.warnings() {
+            out.push_str(&format!("  ✖ {}\n", problem));
+        }
+    }
+    out.push_str(if report.is_valid() { "Signature OK\n" } else { "Signature problems found\n" });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_state_keeps_gui_fields_and_merges_apps_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("app_state.json");
+        std::fs::write(
+            &state_path,
+            r#"{"output_directory":"/out","dark_mode":true,"app_configs":[{"id":"a1","app_name":"Shop","input_zip_path":"shop.zip","output_ipa_name":"shop.ipa","created_at":"2024-01-01T00:00:00Z","last_generated_at":null}]}"#,
+        )
+        .unwrap();
+        let apps_json = dir.path().join("apps.json");
+        std::fs::write(&apps_json, r#"{"version":1,"apps":[{"app_name":"Kiosk","input_zip_path":"kiosk.zip","output_ipa_name":"kiosk.ipa"}]}"#).unwrap();
+
+        let mut store = StoreKind::Json.file_store(dir.path()).unwrap();
+        let state = SavedState::load(store.as_ref(), StoreKind::Json, dir.path(), Some(&apps_json)).unwrap();
+        assert_eq!(state.find_config("a1").unwrap().app_name, "Shop");
+        assert_eq!(state.find_config("kiosk").unwrap().input_zip_path, "kiosk.zip");
+        assert!(state.find_config("missing").is_none());
+
+        state.save(store.as_mut()).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+        assert_eq!(saved["dark_mode"], true);
+        assert_eq!(saved["app_configs"].as_array().unwrap().len(), 2);
+
+        assert_eq!(run_from_args(&["--cli".to_string(), "bogus".to_string()]), Some(EXIT_USAGE));
+        assert_eq!(run_from_args(&["--viewer".to_string()]), None);
+    }
+}
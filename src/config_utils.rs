@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 use directories_next::ProjectDirs;
-use crate::app::IpaBuilderApp; 
+use crate::app::IpaBuilderApp;
+use crate::state_store::{load_or_migrate, StateStore, StoreKind};
 
 const QUALIFIER: &str = "com";
 const ORGANIZATION: &str = "i2sac";
@@ -24,6 +25,11 @@ pub fn get_config_dir_path() -> Option<PathBuf> { // Renamed for clarity and con
     })
 }
 
+// Get the path to the optional apps.json drop-in file used for CI provisioning
+pub fn get_apps_import_file_path() -> Option<PathBuf> {
+    get_config_dir_path().map(|d| d.join(crate::app_import::APPS_IMPORT_FILE_NAME))
+}
+
 // Get the path to the data directory (e.g., for metrics)
 pub fn get_data_dir_path() -> Option<PathBuf> {
     get_project_dirs().map(|proj_dirs| {
@@ -37,42 +43,45 @@ pub fn get_data_dir_path() -> Option<PathBuf> {
     })
 }
 
-// Load application state
+// Save application state where `load_app_state` and the headless CLI commands read it
+pub fn save_app_state(app: &IpaBuilderApp) -> Result<(), String> {
+    let kind = StoreKind::from_env()?;
+    let config_dir = get_config_dir_path().ok_or_else(|| "Could not determine config file path".to_string())?;
+    let Some(mut store) = kind.file_store(&config_dir) else {
+        // eframe storage is written by `App::save`.
+        return Ok(());
+    };
+    let state = serde_json::to_value(app).map_err(|e| format!("Failed to serialize app state: {}", e))?;
+    store.save(&state)
+}
+
+// Load application state from the store chosen with IPA_BUILDER_STATE_STORE
 pub fn load_app_state(cc: &eframe::CreationContext<'_>) -> Result<IpaBuilderApp, String> {
-    let config_file_path = get_config_dir_path().map(|d| d.join("app_state.json"));
-    if let Some(config_path) = config_file_path {
-        if config_path.exists() {
-            log::info!("Loading app state from: {}", config_path.display());
-            match std::fs::read_to_string(&config_path) {
-                Ok(json_string) => {
-                    match serde_json::from_str::<IpaBuilderApp>(&json_string) {
-                        Ok(mut loaded_app) => {
-                            log::info!("App state loaded successfully.");
-                            loaded_app.post_load_setup(cc); 
-                            Ok(loaded_app)
-                        }
-                        Err(e) => {
-                            let msg = format!("Failed to deserialize app state from {}: {}. Using default.", config_path.display(), e);
-                            log::error!("{}", msg);
-                            Err(msg) 
-                        }
-                    }
-                }
-                Err(e) => {
-                    let msg = format!("Failed to read app state file {}: {}. Using default.", config_path.display(), e);
-                    log::error!("{}", msg);
-                    Err(msg)
-                }
-            }
-        } else {
-            log::info!("No app state file found at {}. Using default.", config_path.display());
-            let mut app = IpaBuilderApp::default();
-            app.post_load_setup(cc);
-            Ok(app) 
+    let kind = StoreKind::from_env()?;
+    let config_dir = get_config_dir_path().ok_or_else(|| "Could not determine config file path. Using default app state.".to_string())?;
+    let state = match (kind.file_store(&config_dir), cc.storage) {
+        (Some(store), _) => {
+            log::info!("Loading app state from: {}", store.location());
+            load_or_migrate(store.as_ref(), kind, &config_dir)?
+        }
+        (None, Some(storage)) => {
+            log::info!("Loading app state from: {}", storage.location());
+            load_or_migrate(storage, kind, &config_dir)?
+        }
+        (None, None) => return Err("eframe storage is unavailable. Using default app state.".to_string()),
+    };
+    let mut app = match state {
+        Some(state) => serde_json::from_value::<IpaBuilderApp>(state).map_err(|e| {
+            let msg = format!("Failed to deserialize app state: {}. Using default.", e);
+            log::error!("{}", msg);
+            msg
+        })?,
+        None => {
+            log::info!("No saved app state found. Using default.");
+            IpaBuilderApp::default()
         }
-    } else {
-        let msg = "Could not determine config file path. Using default app state.".to_string();
-        log::warn!("{}", msg);
-        Err(msg)
-    }
+    };
+    log::info!("App state loaded successfully.");
+    app.post_load_setup(cc);
+    Ok(app)
 }
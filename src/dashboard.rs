@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::AppConfig;
+use crate::build_history::BuildRecord;
+use crate::time_display::DisplayTimeZone;
+
+/// Column the dashboard table is ordered by; `Saved` keeps the order configs were added in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortColumn {
+    #[default]
+    Saved,
+    Name,
+    Created,
+    LastGenerated,
+    Builds,
+    LastSize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct TableSort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl TableSort {
+    /// Sorts by `column`, or flips the direction when it already is the sort column.
+    pub fn toggle(&mut self, column: SortColumn) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            *self = Self { column, descending: false };
+        }
+    }
+
+    /// `▲`/`▼` after the header of the sort column.
+    pub fn indicator(&self, column: SortColumn) -> &'static str {
+        match (self.column == column, self.descending) {
+            (false, _) => "",
+            (true, false) => " ▲",
+            (true, true) => " ▼",
+        }
+    }
+}
+
+/// One row of the dashboard table, with the columns computed from the build history. This is
+/// also what "Export table…" writes.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DashboardRow {
+    /// Position in the saved configs.
+    #[serde(skip)]
+    pub index: usize,
+    pub id: String,
+    pub app_name: String,
+    pub input_zip_path: String,
+    pub output_ipa_name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_generated_at: Option<DateTime<Utc>>,
+    pub builds: usize,
+    pub failed_builds: usize,
+    /// Size of the IPA from the latest successful build that recorded one.
+    pub last_size_bytes: Option<u64>,
+    /// Profile expiry of the latest successful build; `None` when it was unsigned.
+    pub profile_expires_at: Option<DateTime<Utc>>,
+}
+
+/// The columns of a row that come from the build history.
+#[derive(Debug, Clone, Copy, Default)]
+struct HistoryColumns {
+    builds: usize,
+    failed_builds: usize,
+    last_size_bytes: Option<u64>,
+    profile_expires_at: Option<DateTime<Utc>>,
+}
+
+/// Rows for the configs at `indices` (the ones matching the search), in `sort` order.
+pub fn dashboard_rows(configs: &[AppConfig], indices: &[usize], history: &[BuildRecord], sort: TableSort) -> Vec<DashboardRow> {
+    let mut stats: HashMap<&str, HistoryColumns> = HashMap::new();
+    for record in history {
+        let entry = stats.entry(record.app_id.as_str()).or_default();
+        entry.builds += 1;
+        if !record.success {
+            entry.failed_builds += 1;
+            continue;
+        }
+        if record.size_bytes.is_some() {
+            entry.last_size_bytes = record.size_bytes;
+        }
+        entry.profile_expires_at = record.profile_expires_at;
+    }
+
+    let mut rows: Vec<DashboardRow> = indices
+        .iter()
+        .filter_map(|&index| configs.get(index).map(|config| (index, config)))
+        .map(|(index, config)| {
+            let HistoryColumns { builds, failed_builds, last_size_bytes, profile_expires_at } = stats.get(config.id.as_str()).copied().unwrap_or_default();
+            DashboardRow {
+                index,
+                id: config.id.clone(),
+                app_name: config.app_name.clone(),
+                input_zip_path: config.input_zip_path.clone(),
+                output_ipa_name: config.output_ipa_name.clone(),
+                created_at: config.created_at,
+                last_generated_at: config.last_generated_at,
+                builds,
+                failed_builds,
+                last_size_bytes,
+                profile_expires_at,
+            }
+        })
+        .collect();
+
+    match sort.column {
+        SortColumn::Saved => {}
+        SortColumn::Name => rows.sort_by_key(|r| r.app_name.to_lowercase()),
+        SortColumn::Created => rows.sort_by_key(|r| r.created_at),
+        SortColumn::LastGenerated => rows.sort_by_key(|r| r.last_generated_at),
+        SortColumn::Builds => rows.sort_by_key(|r| r.builds),
+        SortColumn::LastSize => rows.sort_by_key(|r| r.last_size_bytes),
+    }
+    if sort.descending {
+        rows.reverse();
+    }
+    rows
+}
+
+/// Writes `rows` to `dest` as a JSON array when it ends in `.json`, as CSV otherwise. Times are
+/// RFC 3339 with the offset of `zone`.
+pub fn export_rows(rows: &[DashboardRow], dest: &Path, zone: &DisplayTimeZone) -> Result<(), String> {
+    let is_json = dest.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let contents = if is_json {
+        let mut value = serde_json::to_value(rows).map_err(|e| format!("Failed to serialize table: {}", e))?;
+        if let Some(items) = value.as_array_mut() {
+            for (row, item) in rows.iter().zip(items) {
+                item["created_at"] = zone.rfc3339(row.created_at).into();
+                item["last_generated_at"] = row.last_generated_at.map(|t| zone.rfc3339(t)).into();
+                item["profile_expires_at"] = row.profile_expires_at.map(|t| zone.rfc3339(t)).into();
+            }
+        }
+        serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to serialize table: {}", e))?
+    } else {
+        let mut csv = String::from("id,app_name,input_zip_path,output_ipa_name,created_at,last_generated_at,builds,failed_builds,last_size_bytes\n");
+        for row in rows {
+            let fields = [
+                row.id.clone(),
+                row.app_name.clone(),
+                row.input_zip_path.clone(),
+                row.output_ipa_name.clone(),
+                zone.rfc3339(row.created_at),
+                row.last_generated_at.map(|t| zone.rfc3339(t)).unwrap_or_default(),
+                row.builds.to_string(),
+                row.failed_builds.to_string(),
+                row.last_size_bytes.map(|s| s.to_string()).unwrap_or_default(),
+            ];
+            csv.push_str(&fields.iter().map(|f| crate::audit::csv_field(f)).collect::<Vec<_>>().join(","));
+            csv.push('\n');
+        }
+        csv
+    };
+    fs::write(dest, contents).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(app_id: &str, success: bool, size_bytes: Option<u64>) -> BuildRecord {
+        BuildRecord {
+            timestamp: Utc::now(),
+            app_id: app_id.to_string(),
+            app_name: String::new(),
+            success,
+            duration_ms: 0,
+            output_path: None,
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: None,
+            size_bytes,
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        }
+    }
+
+    #[test]
+    fn test_rows_are_filtered_sorted_and_exported() {
+        let configs: Vec<AppConfig> = ["Shop, EU", "Admin", "Kiosk"]
+            .iter()
+            .map(|name| AppConfig { id: name.to_lowercase(), app_name: name.to_string(), ..Default::default() })
+            .collect();
+        let history = vec![
+            record("admin", true, Some(10)),
+            record("shop, eu", true, Some(300)),
+            record("shop, eu", false, None),
+            record("shop, eu", true, Some(200)),
+        ];
+
+        let mut sort = TableSort::default();
+        sort.toggle(SortColumn::Builds);
+        sort.toggle(SortColumn::Builds);
+        let rows = dashboard_rows(&configs, &[0, 1], &history, sort);
+        let names: Vec<&str> = rows.iter().map(|r| r.app_name.as_str()).collect();
+        assert_eq!(names, vec!["Shop, EU", "Admin"]);
+        assert_eq!((rows[0].index, rows[0].builds, rows[0].failed_builds, rows[0].last_size_bytes), (0, 3, 1, Some(200)));
+
+        let dir = tempfile::tempdir().unwrap();
+        export_rows(&rows, &dir.path().join("apps.csv"), &DisplayTimeZone::Utc).unwrap();
+        let csv = fs::read_to_string(dir.path().join("apps.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.lines().nth(1).unwrap().starts_with("\"shop, eu\",\"Shop, EU\","));
+        assert!(csv.lines().nth(1).unwrap().ends_with(",3,1,200"));
+
+        export_rows(&rows, &dir.path().join("apps.JSON"), &DisplayTimeZone::Named("Asia/Tokyo".to_string())).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.path().join("apps.JSON")).unwrap()).unwrap();
+        assert_eq!(json[1]["app_name"], "Admin");
+        assert_eq!(json[1]["last_size_bytes"], 10);
+        assert!(json[1]["created_at"].as_str().unwrap().ends_with("+09:00"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config_utils::{get_config_dir_path, get_data_dir_path};
+
+/// Free temporary space below which builds of large apps are likely to fail.
+const MIN_TEMP_SPACE: u64 = 500 * 1024 * 1024;
+/// Free temporary space below which a warning is shown; extraction needs about twice the ZIP.
+const LOW_TEMP_SPACE: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Something optional is missing, or a limit is close.
+    Warn,
+    /// Part of IPA Builder won't work until it is fixed.
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn icon(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✔",
+            CheckStatus::Warn => "⚠",
+            CheckStatus::Fail => "✖",
+        }
+    }
+}
+
+/// One environment check of the Diagnostics view, with how to fix it when it doesn't pass.
+#[derive(Serialize, Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into(), hint: None }
+    }
+
+    fn problem(name: &'static str, status: CheckStatus, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into(), hint: Some(hint.into()) }
+    }
+}
+
+/// Checks what IPA Builder relies on from the machine: the file dialog helper on Linux, write
+/// access to its directories and `output_dir`, free temporary space, and the signing and
+/// device tools.
+pub fn run_checks(output_dir: Option<&Path>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        checks.push(check_file_dialogs());
+    }
+    checks.push(check_dir("Config directory", get_config_dir_path().as_deref(), "Your apps and settings can't be saved."));
+    checks.push(check_state_store());
+    checks.push(check_dir("Data directory", get_data_dir_path().as_deref(), "Metrics, the audit log and the build history can't be saved."));
+    checks.push(match output_dir {
+        Some(dir) => check_dir("Output directory", Some(dir), "Builds will fail. Choose another directory in Settings."),
+        None => Check::problem("Output directory", CheckStatus::Warn, "Not chosen yet", "Choose one in the first-run dialog."),
+    });
+    checks.push(check_temp_space(&std::env::temp_dir()));
+    if cfg!(target_os = "macos") {
+        checks.push(if crate::codesign::codesign_available() {
+            Check::pass("codesign", "Available")
+        } else {
+            Check::problem(
+                "codesign",
+                CheckStatus::Warn,
+                "Not found",
+                "Signature checks and batch re-signing need Xcode's command line tools: run `xcode-select --install`.",
+            )
+        });
+    }
+    checks.push(check_tool(
+        "libimobiledevice",
+        &["ideviceinstaller", "idevice_id"],
+        "Only needed to install on USB devices: `brew install libimobiledevice ideviceinstaller`, or `apt install libimobiledevice-utils ideviceinstaller`.",
+    ));
+    checks
+}
+
+/// native_dialog opens its dialogs on Linux through zenity or kdialog; without either, Browse
+/// buttons silently do nothing.
+fn check_file_dialogs() -> Check {
+    match ["zenity", "kdialog"].into_iter().find_map(|tool| find_on_path(tool, std::env::var_os("PATH").as_deref())) {
+        Some(path) => Check::pass("File dialogs", format!("Using {}", path.display())),
+        None => Check::problem(
+            "File dialogs",
+            CheckStatus::Fail,
+            "Neither zenity nor kdialog is installed, so Browse buttons do nothing",
+            "Install zenity (`sudo apt install zenity`, `sudo dnf install zenity`) or kdialog, or type paths into the fields instead.",
+        ),
+    }
+}
+
+fn check_dir(name: &'static str, dir: Option<&Path>, consequence: &str) -> Check {
+    let Some(dir) = dir else {
+        return Check::problem(name, CheckStatus::Fail, "No home directory found", format!("Set HOME. {}", consequence));
+    };
+    let writable = std::fs::create_dir_all(dir).and_then(|_| tempfile::NamedTempFile::new_in(dir));
+    match writable {
+        Ok(_) => Check::pass(name, dir.display().to_string()),
+        Err(e) => Check::problem(
+            name,
+            CheckStatus::Fail,
+            format!("{} is not writable: {}", dir.display(), e),
+            format!("Check its owner and permissions. {}", consequence),
+        ),
+    }
+}
+
+/// The store chosen with `IPA_BUILDER_STATE_STORE`, and whether it can be read.
+fn check_state_store() -> Check {
+    const NAME: &str = "State store";
+    let hint = format!("Set {} to json, toml, sqlite or eframe, or unset it to use app_state.json.", crate::state_store::STORE_ENV);
+    let kind = match crate::state_store::StoreKind::from_env() {
+        Ok(kind) => kind,
+        Err(e) => return Check::problem(NAME, CheckStatus::Fail, e, hint),
+    };
+    let Some(store) = get_config_dir_path().and_then(|dir| kind.file_store(&dir)) else {
+        return Check::pass(NAME, "eframe storage (GUI only; headless commands can't read it)");
+    };
+    match store.load() {
+        Ok(_) => Check::pass(NAME, store.location()),
+        Err(e) => Check::problem(NAME, CheckStatus::Fail, e, format!("Your apps can't be loaded. Fix or move the file away. {}", hint)),
+    }
+}
+
+fn check_temp_space(temp_dir: &Path) -> Check {
+    const NAME: &str = "Temporary space";
+    let hint = "Inputs are extracted to the temporary directory. Free up space there, or point TMPDIR (TEMP on Windows) at a larger disk.";
+    match fs2::available_space(temp_dir) {
+        Ok(free) => {
+            let detail = format!("{} free in {}", crate::size_analysis::format_size(free), temp_dir.display());
+            if free < MIN_TEMP_SPACE {
+                Check::problem(NAME, CheckStatus::Fail, detail, hint)
+            } else if free < LOW_TEMP_SPACE {
+                Check::problem(NAME, CheckStatus::Warn, detail, hint)
+            } else {
+                Check::pass(NAME, detail)
+            }
+        }
+        Err(e) => Check::problem(NAME, CheckStatus::Warn, format!("Could not read the free space of {}: {}", temp_dir.display(), e), hint),
+    }
+}
+
+fn check_tool(name: &'static str, executables: &[&str], hint: &str) -> Check {
+    let path = std::env::var_os("PATH");
+    let missing: Vec<&str> = executables.iter().copied().filter(|tool| find_on_path(tool, path.as_deref()).is_none()).collect();
+    if missing.is_empty() {
+        Check::pass(name, "Installed")
+    } else {
+        Check::problem(name, CheckStatus::Warn, format!("Not found on PATH: {}", missing.join(", ")), hint)
+    }
+}
+
+/// The first `name` executable in the directories of `path`, a `PATH`-style list.
+fn find_on_path(name: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) { &[".exe", ".cmd", ".bat"] } else { &[""] };
+    std::env::split_paths(path?)
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", name, ext))))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Plain-text report of `checks`, for pasting into a support request.
+pub fn report(checks: &[Check]) -> String {
+    let mut text = format!("IPA Builder {} on {} ({})\n", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH);
+    for check in checks {
+        text.push_str(&format!("{} {}: {}\n", check.status.icon(), check.name, check.detail));
+        if let Some(hint) = &check.hint {
+            text.push_str(&format!("    {}\n", hint));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_find_tools_and_unwritable_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join(if cfg!(windows) { "zenity.exe" } else { "zenity" });
+        std::fs::write(&tool, "").unwrap();
+        let path = std::env::join_paths([dir.path().join("missing"), dir.path().to_path_buf()]).unwrap();
+        assert_eq!(find_on_path("zenity", Some(&path)), Some(tool.clone()));
+        assert_eq!(find_on_path("kdialog", Some(&path)), None);
+
+        assert_eq!(check_dir("Output directory", Some(dir.path()), "").status, CheckStatus::Pass);
+        // A file where the directory should be can't be created or written to.
+        let blocked = check_dir("Output directory", Some(&tool.join("out")), "Builds will fail.");
+        assert_eq!(blocked.status, CheckStatus::Fail);
+        assert!(blocked.hint.unwrap().ends_with("Builds will fail."));
+        assert_ne!(check_temp_space(dir.path()).detail, "");
+        let missing = Check::problem("libimobiledevice", CheckStatus::Warn, "Not found on PATH: ideviceinstaller", "Install it.");
+        assert!(report(&[missing]).contains("⚠ libimobiledevice: Not found on PATH: ideviceinstaller\n    Install it.\n"));
+    }
+}
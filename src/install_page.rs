@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::branding::{self, Branding};
+use crate::bundle_info::{self, BundleInfo};
+use crate::ota_manifest::{self, percent_encode};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.plist";
+const INDEX_FILE_NAME: &str = "index.html";
+const QR_FILE_NAME: &str = "qr.png";
+
+/// Writes a self-contained install page bundle for `ipa_path` into `<ipa stem>_install/` next to
+/// the IPA: a copy of the IPA, an OTA `manifest.plist`, a QR code pointing at the page, and an
+/// `index.html` with the install link, release notes and SHA-256 checksum, styled with
+/// `branding` and following the viewer's light or dark appearance.
+///
+/// `base_url` is the HTTPS URL the folder will be served from; iOS refuses OTA installs over
+/// plain HTTP.
+pub fn export_install_page(ipa_path: &Path, base_url: &str, release_notes: Option<&str>, branding: &Branding) -> Result<PathBuf, String> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    if !base_url.starts_with("https://") {
+        return Err("Install page base URL must start with https://".to_string());
+    }
+    let ipa_file_name = ipa_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid IPA path: {}", ipa_path.display()))?;
+    let stem = ipa_path.file_stem().unwrap_or_default().to_string_lossy();
+    let bundle_dir = ipa_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(format!("{}_install", stem));
+
+    let info = bundle_info::read_from_ipa(ipa_path)?;
+    let title = info.display_name.clone().unwrap_or_else(|| stem.to_string());
+    let checksum = crate::transfer::sha256_file(ipa_path).map_err(|e| format!("Failed to hash IPA: {}", e))?;
+
+    fs::create_dir_all(&bundle_dir).map_err(|e| format!("Failed to create {}: {}", bundle_dir.display(), e))?;
+    fs::copy(ipa_path, bundle_dir.join(ipa_file_name)).map_err(|e| format!("Failed to copy IPA: {}", e))?;
+
+    let ipa_url = format!("{}/{}", base_url, percent_encode(ipa_file_name));
+    let manifest_url = format!("{}/{}", base_url, MANIFEST_FILE_NAME);
+    let page_url = format!("{}/{}", base_url, INDEX_FILE_NAME);
+
+    fs::write(bundle_dir.join(MANIFEST_FILE_NAME), manifest_plist(&ipa_url, &info, &title)?)
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+    write_qr_png(&page_url, &bundle_dir.join(QR_FILE_NAME), branding.qr_rgb())?;
+    let logo = copy_logo(branding, &bundle_dir)?;
+
+    let install_link = ota_manifest::install_link(&manifest_url);
+    let page = IndexPage { title: &title, info: &info, install_link: &install_link, ipa_file_name, checksum: &checksum, release_notes };
+    let html = render_index_html(&page, branding, logo.as_deref());
+    fs::write(bundle_dir.join(INDEX_FILE_NAME), html).map_err(|e| format!("Failed to write install page: {}", e))?;
+
+    Ok(bundle_dir)
+}
+
+/// Builds the `manifest.plist` that `itms-services://` downloads to install `ipa_url`.
+pub fn manifest_plist(ipa_url: &str, info: &BundleInfo, title: &str) -> Result<Vec<u8>, String> {
+    let bundle_identifier = info.bundle_identifier.as_deref().unwrap_or_default();
+    let version = info.short_version.as_deref().or(info.bundle_version.as_deref()).unwrap_or("1.0");
+    ota_manifest::manifest_plist(ipa_url, bundle_identifier, version, title)
+}
+
+/// Copies the branding logo into the bundle as `logo.<ext>`, returning that file name.
+fn copy_logo(branding: &Branding, bundle_dir: &Path) -> Result<Option<String>, String> {
+    let logo = branding.logo_path.trim();
+    if logo.is_empty() {
+        return Ok(None);
+    }
+    let logo = Path::new(logo);
+    let extension = logo.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_else(|| "png".to_string());
+    let file_name = format!("logo.{}", extension);
+    fs::copy(logo, bundle_dir.join(&file_name)).map_err(|e| format!("Failed to copy logo {}: {}", logo.display(), e))?;
+    Ok(Some(file_name))
+}
+
+fn write_qr_png(data: &str, dest: &Path, color: [u8; 3]) -> Result<(), String> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    code.render::<image::Rgb<u8>>()
+        .dark_color(image::Rgb(color))
+        .light_color(image::Rgb([255, 255, 255]))
+        .min_dimensions(256, 256)
+        .build()
+        .save(dest)
+        .map_err(|e| format!("Failed to write QR code: {}", e))
+}
+
+struct IndexPage<'a> {
+    title: &'a str,
+    info: &'a BundleInfo,
+    install_link: &'a str,
+    ipa_file_name: &'a str,
+    checksum: &'a str,
+    release_notes: Option<&'a str>,
+}
+
+fn render_index_html(page: &IndexPage, branding: &Branding, logo_file_name: Option<&str>) -> String {
+    let notes = page
+        .release_notes
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| format!("<h2>Release notes</h2>\n<pre>{}</pre>\n", html_escape(n)))
+        .unwrap_or_default();
+    let org_name = branding.org_name.trim();
+    let title_suffix = if org_name.is_empty() { String::new() } else { format!(" · {}", html_escape(org_name)) };
+    let logo = logo_file_name
+        .map(|name| format!("<img class=\"logo\" src=\"{}\" alt=\"{}\">\n", html_escape(&percent_encode(name)), html_escape(org_name)))
+        .unwrap_or_default();
+    let footer = if org_name.is_empty() { String::new() } else { format!("<footer>Distributed by {}</footer>\n", html_escape(org_name)) };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="color-scheme" content="light dark">
+<title>Install {title}{title_suffix}</title>
+<style>
+:root {{ --accent: {accent}; --bg: #ffffff; --fg: #1d1d1f; --panel: #f4f4f4; --muted: #6e6e73; }}
+@media (prefers-color-scheme: dark) {{
+:root {{ --bg: #1c1c1e; --fg: #f5f5f7; --panel: #2c2c2e; --muted: #98989d; }}
+}}
+body {{ font-family: -apple-system, sans-serif; max-width: 40em; margin: 2em auto; padding: 0 1em; background: var(--bg); color: var(--fg); }}
+a {{ color: var(--accent); }}
+.logo {{ max-height: 64px; max-width: 60%; }}
+.install {{ display: inline-block; padding: .8em 1.6em; background: var(--accent); color: #fff; border-radius: .5em; text-decoration: none; }}
+.qr {{ background: #fff; padding: 12px; border-radius: 12px; }}
+pre {{ white-space: pre-wrap; background: var(--panel); padding: 1em; }}
+code {{ word-break: break-all; }}
+footer {{ margin-top: 3em; color: var(--muted); font-size: .9em; }}
+</style>
+</head>
+<body>
+{logo}<h1>{title}</h1>
+<p>Version {version}</p>
+<p><a class="install" href="{install_link}">Install on iPhone / iPad</a></p>
+<p><img class="qr" src="{qr}" alt="QR code for this page" width="256" height="256"></p>
+{notes}<h2>Download</h2>
+<p><a href="{ipa_href}">{ipa_name}</a></p>
+<p>SHA-256: <code>{checksum}</code></p>
+{footer}</body>
+</html>
+"#,
+        title = html_escape(page.title),
+        title_suffix = title_suffix,
+        accent = branding::to_hex(branding.accent_rgb()),
+        logo = logo,
+        version = html_escape(&page.info.version_label()),
+        install_link = html_escape(page.install_link),
+        qr = QR_FILE_NAME,
+        notes = notes,
+        ipa_href = html_escape(&percent_encode(page.ipa_file_name)),
+        ipa_name = html_escape(page.ipa_file_name),
+        checksum = page.checksum,
+        footer = footer,
+    )
+}
+
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    #[test]
+    fn test_export_install_page_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop Beta.ipa");
+        let mut info = plist::Dictionary::new();
+        info.insert("CFBundleIdentifier".into(), "com.example.shop".into());
+        info.insert("CFBundleShortVersionString".into(), "1.2.0".into());
+        let mut info_bytes = Vec::new();
+        plist::Value::Dictionary(info).to_writer_xml(&mut info_bytes).unwrap();
+        let mut zip = zip::ZipWriter::new(File::create(&ipa).unwrap());
+        zip.start_file("Payload/Shop.app/Info.plist", FileOptions::default()).unwrap();
+        zip.write_all(&info_bytes).unwrap();
+        zip.finish().unwrap();
+
+        let logo = dir.path().join("acme.png");
+        fs::write(&logo, b"png").unwrap();
+        let branding = Branding {
+            org_name: "Acme Mobile".to_string(),
+            logo_path: logo.to_string_lossy().into_owned(),
+            accent_color: "#e4002b".to_string(),
+            ..Default::default()
+        };
+
+        assert!(export_install_page(&ipa, "http://example.com", None, &branding).is_err());
+        let bundle = export_install_page(&ipa, "https://example.com/builds/", Some("<b>Fixed</b> login"), &branding).unwrap();
+        assert_eq!(bundle, dir.path().join("Shop Beta_install"));
+        for name in ["Shop Beta.ipa", MANIFEST_FILE_NAME, QR_FILE_NAME, INDEX_FILE_NAME, "logo.png"] {
+            assert!(bundle.join(name).is_file(), "missing {}", name);
+        }
+
+        let manifest = fs::read_to_string(bundle.join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(manifest.contains("https://example.com/builds/Shop%20Beta.ipa"));
+        assert!(manifest.contains("com.example.shop"));
+        let html = fs::read_to_string(bundle.join(INDEX_FILE_NAME)).unwrap();
+        assert!(html.contains("itms-services://?action=download-manifest&amp;url=https%3A%2F%2Fexample.com%2Fbuilds%2Fmanifest.plist"));
+        assert!(html.contains("&lt;b&gt;Fixed&lt;/b&gt; login"));
+        assert!(html.contains(&crate::transfer::sha256_file(&ipa).unwrap()));
+        assert!(html.contains("--accent: #e4002b"));
+        assert!(html.contains("prefers-color-scheme: dark"));
+        assert!(html.contains("Distributed by Acme Mobile"));
+    }
+}
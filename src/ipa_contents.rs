@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::path::Path;
+
+/// A file or folder in an IPA, as shown by the Inspect window. A folder's sizes are the sums
+/// of everything below it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentNode {
+    pub name: String,
+    /// Path inside the archive, `/`-separated; empty for the root.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub compressed_size: u64,
+    /// Largest first, then by name.
+    pub children: Vec<ContentNode>,
+}
+
+impl ContentNode {
+    /// Compressed size as a fraction of the original, e.g. `0.25` for a file deflated to a
+    /// quarter. `None` for empty entries.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.size > 0).then(|| self.compressed_size as f64 / self.size as f64)
+    }
+
+    pub fn file_count(&self) -> usize {
+        if self.is_dir {
+            self.children.iter().map(ContentNode::file_count).sum()
+        } else {
+            1
+        }
+    }
+
+    fn insert(&mut self, parts: &[&str], is_dir: bool, size: u64, compressed_size: u64) {
+        let Some((first, rest)) = parts.split_first() else {
+            return;
+        };
+        self.size += size;
+        self.compressed_size += compressed_size;
+        let child = match self.children.iter().position(|c| c.name == *first) {
+            Some(i) => &mut self.children[i],
+            None => {
+                let path = if self.path.is_empty() { first.to_string() } else { format!("{}/{}", self.path, first) };
+                self.children.push(ContentNode { name: first.to_string(), path, is_dir: is_dir || !rest.is_empty(), ..Default::default() });
+                self.children.last_mut().expect("just pushed")
+            }
+        };
+        if rest.is_empty() {
+            child.size += size;
+            child.compressed_size += compressed_size;
+        } else {
+            child.insert(rest, is_dir, size, compressed_size);
+        }
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        self.children.iter_mut().for_each(ContentNode::sort);
+    }
+}
+
+/// Lists the entries of the IPA (or any ZIP) at `path` as a tree, from the central directory
+/// only, so nothing is decompressed.
+pub fn read_contents(path: &Path) -> Result<ContentNode, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    let mut root = ContentNode { is_dir: true, ..Default::default() };
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(|e| format!("Unreadable entry {}: {}", i, e))?;
+        let parts: Vec<&str> = entry.name().split('/').filter(|p| !p.is_empty()).collect();
+        root.insert(&parts, entry.is_dir(), entry.size(), entry.compressed_size());
+    }
+    root.sort();
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    #[test]
+    fn test_contents_tree_sums_folder_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipa = dir.path().join("Shop.ipa");
+        let mut zip = zip::ZipWriter::new(File::create(&ipa).unwrap());
+        zip.add_directory("Payload/Shop.app/", FileOptions::default()).unwrap();
+        for (name, bytes, method) in [
+            ("Payload/Shop.app/Shop", vec![0u8; 4000], zip::CompressionMethod::Deflated),
+            ("Payload/Shop.app/Assets.car", vec![7u8; 100], zip::CompressionMethod::Stored),
+            ("Payload/Shop.app/Info.plist", vec![1u8; 10], zip::CompressionMethod::Stored),
+            ("BuildInfo.json", vec![], zip::CompressionMethod::Stored),
+        ] {
+            zip.start_file(name, FileOptions::default().compression_method(method)).unwrap();
+            zip.write_all(&bytes).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let root = read_contents(&ipa).unwrap();
+        assert_eq!(root.size, 4110);
+        assert_eq!(root.file_count(), 4);
+        let app = &root.children[0].children[0];
+        assert_eq!((app.path.as_str(), app.is_dir, app.size), ("Payload/Shop.app", true, 4110));
+        let names: Vec<_> = app.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Shop", "Assets.car", "Info.plist"]);
+        assert!(app.children[0].compression_ratio().unwrap() < 0.1);
+        assert_eq!(app.children[1].compression_ratio(), Some(1.0));
+        assert_eq!(root.children[1].compression_ratio(), None);
+    }
+}
@@ -1,8 +1,38 @@
+mod accessibility;
 mod app;
-mod autocheck;
-mod ipa_logic;
-mod metrics;
+mod app_import;
+mod artifact_ledger;
+mod audit;
+mod branding;
+mod build_history;
+mod build_queue;
+mod build_runner;
+mod bundle_info;
+mod cli;
+mod dashboard;
+mod diagnostics;
+mod install_page;
+mod ipa_contents;
+mod migration;
+mod naming_audit;
+#[cfg(feature = "ota-server")]
+mod ota_server;
 mod config_utils;
+mod projects;
+mod qr;
+mod provisioning;
+mod release_manifest;
+mod release_notes;
+mod resign;
+mod settings;
+mod state_store;
+mod taskbar;
+mod time_display;
+mod toasts;
+mod undo;
+
+// Modules of the core library, reachable as `crate::<module>` like the app's own.
+use ipa_builder_core::{agent, app_config, app_store_connect, artifact_signing, autocheck, bundle_signing, channels, codesign, compression, database, device_install, dsym, fixtures, hooks, info_plist, input_cache, input_watch, ipa_logic, metrics, naming, network, notifications, object_storage, ota_manifest, output_watch, permissions, retention, share, size_analysis, thinning, transfer, validator, webhook};
 
 use app::IpaBuilderApp;
 use std::sync::Arc;
@@ -24,6 +54,16 @@ fn main() -> Result<(), eframe::Error> {
     env_logger::init(); // Initialize logger
     log::info!("Starting IPA Builder application");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(exit_code) = cli::run_from_args(&args) {
+        std::process::exit(exit_code);
+    }
+
+    let viewer_mode = args.iter().any(|arg| arg == "--viewer");
+    if viewer_mode {
+        log::info!("Viewer mode enforced by --viewer flag");
+    }
+
     let mut viewport_builder = egui::ViewportBuilder::default()
         .with_inner_size([800.0, 600.0]) // Default window size
         .with_min_inner_size([600.0, 400.0]); // Minimum window size
@@ -43,11 +83,11 @@ fn main() -> Result<(), eframe::Error> {
     };
 
     eframe::run_native(
-        "IPA Builder",
+        taskbar::APP_TITLE,
         options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             // Attempt to load previously saved app state
-            let app_state = match config_utils::load_app_state(cc) {
+            let mut app_state = match config_utils::load_app_state(cc) {
                 Ok(state) => state,
                 Err(e) => {
                     log::warn!("Failed to load app state: {}. Using default.", e);
@@ -56,6 +96,9 @@ fn main() -> Result<(), eframe::Error> {
                     app
                 }
             };
+            if viewer_mode {
+                app_state.lock_viewer_mode();
+            }
             Box::new(app_state)
         }),
     )
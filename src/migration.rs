@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+
+use crate::build_history::BUILD_HISTORY_DB_FILE_NAME;
+use crate::metrics::METRICS_DB_FILE_NAME;
+
+/// Bumped when the archive layout changes incompatibly.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+pub const APP_STATE_FILE_NAME: &str = "app_state.json";
+/// Files carried over from the config directory, besides the app state.
+const CONFIG_FILES: &[&str] = &[crate::app_import::APPS_IMPORT_FILE_NAME];
+/// Files carried over from the data directory.
+const DATA_FILES: &[&str] = &[METRICS_DB_FILE_NAME, "audit.jsonl", BUILD_HISTORY_DB_FILE_NAME];
+/// Data files from before metrics and build history moved to SQLite, with the database each
+/// is imported into when it is next opened.
+const LEGACY_DATA_FILES: &[(&str, &str)] = &[("metrics.jsonl", METRICS_DB_FILE_NAME), ("builds.jsonl", BUILD_HISTORY_DB_FILE_NAME)];
+
+/// Describes an "Export everything" archive.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub builder_version: String,
+    pub exported_at: DateTime<Utc>,
+    pub host: String,
+    /// Archive entries, e.g. `config/app_state.json` or `data/builds.jsonl`.
+    pub entries: Vec<String>,
+}
+
+/// Writes app state (already serialized by the caller), settings, metrics, audit log and build
+/// history into a single zip at `dest`, for restoring on another machine with [`import_archive`].
+pub fn export_archive(dest: &Path, app_state_json: &str, config_dir: &Path, data_dir: &Path) -> Result<ArchiveManifest, String> {
+    let mut files: Vec<(String, Vec<u8>)> = vec![(format!("config/{}", APP_STATE_FILE_NAME), app_state_json.as_bytes().to_vec())];
+    let legacy: Vec<&str> = LEGACY_DATA_FILES.iter().map(|(name, _)| *name).collect();
+    for (prefix, dir, names) in [("config", config_dir, CONFIG_FILES), ("data", data_dir, DATA_FILES), ("data", data_dir, &legacy)] {
+        for name in names {
+            let path = dir.join(name);
+            if path.is_file() {
+                let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                files.push((format!("{}/{}", prefix, name), bytes));
+            }
+        }
+    }
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        builder_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now(),
+        host: whoami::fallible::hostname().unwrap_or_else(|_| "unknown".to_string()),
+        entries: files.iter().map(|(name, _)| name.clone()).collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+
+    let file = File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, bytes) in std::iter::once((MANIFEST_ENTRY.to_string(), manifest_json)).chain(files) {
+        zip.start_file(name.as_str(), options)
+            .and_then(|_| zip.write_all(&bytes).map_err(zip::result::ZipError::Io))
+            .map_err(|e| format!("Failed to write {} to archive: {}", name, e))?;
+    }
+    zip.finish().map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(manifest)
+}
+
+/// Restores an archive written by [`export_archive`] into `config_dir` and `data_dir`. Only the
+/// known entries are extracted; files they replace are kept next to them with a `.bak` suffix.
+/// The caller reloads the app state from `config_dir/app_state.json` afterwards. Metrics or
+/// build history from an older archive replace the local database, which is moved to `.bak`.
+pub fn import_archive(archive_path: &Path, config_dir: &Path, data_dir: &Path) -> Result<ArchiveManifest, String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("{} is not a valid archive: {}", archive_path.display(), e))?;
+
+    let manifest: ArchiveManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_ENTRY)?)
+        .map_err(|e| format!("Invalid {}: {}", MANIFEST_ENTRY, e))?;
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Archive format {} was written by a newer IPA Builder ({}); update before importing.",
+            manifest.format_version, manifest.builder_version
+        ));
+    }
+
+    let app_state_entry = format!("config/{}", APP_STATE_FILE_NAME);
+    let app_state = read_entry(&mut archive, &app_state_entry)?;
+    serde_json::from_slice::<serde_json::Value>(&app_state).map_err(|e| format!("Invalid {}: {}", app_state_entry, e))?;
+
+    let mut restores = vec![(config_dir.join(APP_STATE_FILE_NAME), app_state)];
+    for (prefix, dir, names) in [("config", config_dir, CONFIG_FILES), ("data", data_dir, DATA_FILES)] {
+        for name in names {
+            let entry = format!("{}/{}", prefix, name);
+            if manifest.entries.contains(&entry) {
+                restores.push((dir.join(name), read_entry(&mut archive, &entry)?));
+            }
+        }
+    }
+    let mut superseded = Vec::new();
+    for (legacy, database) in LEGACY_DATA_FILES {
+        let entry = format!("data/{}", legacy);
+        if manifest.entries.contains(&entry) {
+            restores.push((data_dir.join(legacy), read_entry(&mut archive, &entry)?));
+            superseded.push(data_dir.join(database));
+        }
+    }
+
+    for dir in [config_dir, data_dir] {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    for (path, bytes) in restores {
+        if path.exists() {
+            let mut backup = path.clone().into_os_string();
+            backup.push(".bak");
+            fs::copy(&path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+        fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Restored {}", path.display());
+    }
+    for path in superseded.into_iter().filter(|p| p.exists()) {
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        fs::rename(&path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    }
+    Ok(manifest)
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = archive.by_name(name).map_err(|_| format!("Archive is missing {}", name))?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_then_import_on_fresh_machine() {
+        let old = tempfile::tempdir().unwrap();
+        let (old_config, old_data) = (old.path().join("config"), old.path().join("data"));
+        fs::create_dir_all(&old_config).unwrap();
+        fs::create_dir_all(&old_data).unwrap();
+        fs::write(old_data.join(BUILD_HISTORY_DB_FILE_NAME), "builds").unwrap();
+        fs::write(old_data.join(METRICS_DB_FILE_NAME), "").unwrap();
+
+        let archive = old.path().join("export.zip");
+        let manifest = export_archive(&archive, "{\"app_configs\":[]}", &old_config, &old_data).unwrap();
+        assert_eq!(manifest.entries, vec!["config/app_state.json", "data/metrics.sqlite", "data/builds.sqlite"]);
+
+        let new = tempfile::tempdir().unwrap();
+        let (new_config, new_data) = (new.path().join("config"), new.path().join("data"));
+        fs::create_dir_all(&new_data).unwrap();
+        fs::write(new_data.join(BUILD_HISTORY_DB_FILE_NAME), "local").unwrap();
+
+        import_archive(&archive, &new_config, &new_data).unwrap();
+        assert_eq!(fs::read_to_string(new_config.join(APP_STATE_FILE_NAME)).unwrap(), "{\"app_configs\":[]}");
+        assert_eq!(fs::read_to_string(new_data.join(BUILD_HISTORY_DB_FILE_NAME)).unwrap(), "builds");
+        assert_eq!(fs::read_to_string(new_data.join("builds.sqlite.bak")).unwrap(), "local");
+        assert!(!new_data.join("audit.jsonl").exists());
+
+        // An archive from before SQLite replaces the database with its JSONL history.
+        fs::remove_file(old_data.join(BUILD_HISTORY_DB_FILE_NAME)).unwrap();
+        fs::write(old_data.join("builds.jsonl"), "{\"app_name\":\"Shop\"}\n").unwrap();
+        export_archive(&archive, "{\"app_configs\":[]}", &old_config, &old_data).unwrap();
+        import_archive(&archive, &new_config, &new_data).unwrap();
+        assert_eq!(fs::read_to_string(new_data.join("builds.jsonl")).unwrap(), "{\"app_name\":\"Shop\"}\n");
+        assert!(!new_data.join(BUILD_HISTORY_DB_FILE_NAME).exists());
+        assert_eq!(fs::read_to_string(new_data.join("builds.sqlite.bak")).unwrap(), "builds");
+
+        assert!(import_archive(&old_data.join("builds.jsonl"), &new_config, &new_data).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::app::AppConfig;
+use crate::artifact_signing::ArtifactSigning;
+use crate::build_history::BuildRecord;
+use crate::info_plist::BundleVariables;
+use crate::naming::{resolve_output_path, validate_ipa_file_name, OutputExistsPolicy};
+use crate::retention::is_output_variant;
+
+/// How an IPA in the output directory was tied to a config.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedBy {
+    /// Its SHA-256 is that of a successful build in the history.
+    Checksum,
+    /// Its `BuildInfo.json` names the config's app and input ZIP.
+    BuildInfo,
+}
+
+impl MatchedBy {
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchedBy::Checksum => "build history checksum",
+            MatchedBy::BuildInfo => "BuildInfo.json",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditStatus {
+    /// Named after its config's current output name, or a numbered or timestamped variant of it.
+    UpToDate,
+    /// Named some other way; `to` is the name the config would give it today.
+    Rename { to: String },
+    /// Tied to a config, but its current name can't be worked out, e.g. a `{build}` template
+    /// and no readable `Info.plist`.
+    Unresolved { reason: String },
+    /// Not built from any current config, or from several with the same name.
+    Unmatched,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AuditEntry {
+    pub path: PathBuf,
+    pub app_id: Option<String>,
+    pub app_name: Option<String>,
+    pub matched_by: Option<MatchedBy>,
+    #[serde(flatten)]
+    pub status: AuditStatus,
+}
+
+impl AuditEntry {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+    }
+}
+
+/// Checks every IPA directly in `output_dir` against the output name of the config it was
+/// built from. Files are tied to configs by checksum first, hashing only files whose size is
+/// that of a build in `history`, then by their `BuildInfo.json`. Sorted by file name.
+pub fn audit_output_dir(output_dir: &Path, configs: &[AppConfig], history: &[BuildRecord]) -> Result<Vec<AuditEntry>, String> {
+    let entries = fs::read_dir(output_dir).map_err(|e| format!("Failed to read {}: {}", output_dir.display(), e))?;
+    let mut known_sizes: HashMap<u64, Vec<&BuildRecord>> = HashMap::new();
+    for record in history.iter().filter(|r| r.success && r.sha256.is_some()) {
+        known_sizes.entry(record.size_bytes.unwrap_or_default()).or_default().push(record);
+    }
+
+    let mut audit = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_ipa = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ipa"));
+        if !is_ipa || !path.is_file() {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+        let candidates = known_sizes.get(&size).into_iter().chain(known_sizes.get(&0)).flatten();
+        let by_checksum = match candidates.clone().next() {
+            Some(_) => crate::transfer::sha256_file(&path).ok().and_then(|hash| {
+                candidates
+                    .filter(|r| r.sha256.as_deref() == Some(hash.as_str()))
+                    .filter_map(|r| configs.iter().find(|c| c.id == r.app_id).map(|config| (config, r.channel.clone())))
+                    .next_back()
+            }),
+            None => None,
+        };
+        let matched = match by_checksum {
+            Some((config, channel)) => Some((config, MatchedBy::Checksum, channel)),
+            None => match_by_build_info(&path, configs).map(|(config, channel)| (config, MatchedBy::BuildInfo, channel)),
+        };
+        let status = match &matched {
+            Some((config, _, channel)) => status_for(&path, config, channel.as_deref().unwrap_or_default()),
+            None => AuditStatus::Unmatched,
+        };
+        audit.push(AuditEntry {
+            path,
+            app_id: matched.as_ref().map(|(c, _, _)| c.id.clone()),
+            app_name: matched.as_ref().map(|(c, _, _)| c.app_name.clone()),
+            matched_by: matched.as_ref().map(|(_, by, _)| *by),
+            status,
+        });
+    }
+    audit.sort_by_key(AuditEntry::file_name);
+    Ok(audit)
+}
+
+/// The only config with the app name in the IPA's `BuildInfo.json`, narrowed down by the input
+/// ZIP's file name when several configs share the name, along with the channel it was built for.
+fn match_by_build_info<'a>(ipa: &Path, configs: &'a [AppConfig]) -> Option<(&'a AppConfig, Option<String>)> {
+    let info = crate::ipa_logic::read_build_info(ipa).ok().flatten()?;
+    let named: Vec<&AppConfig> = configs.iter().filter(|c| c.app_name == info.app_name).collect();
+    if let [only] = named.as_slice() {
+        return Some((only, info.channel));
+    }
+    let input_name = |c: &AppConfig| Path::new(&c.input_zip_path).file_name().map(|n| n.to_string_lossy().into_owned());
+    let same_input: Vec<&AppConfig> = named.into_iter().filter(|c| input_name(c).as_deref() == Some(info.source_zip.as_str())).collect();
+    match same_input.as_slice() {
+        [only] => Some((only, info.channel)),
+        _ => None,
+    }
+}
+
+/// `channel` is the one the IPA was built for, so a `{channel}` template keeps each channel's
+/// builds under their own names.
+fn status_for(ipa: &Path, config: &AppConfig, channel: &str) -> AuditStatus {
+    let template = crate::channels::expand_name(config.output_ipa_name.trim(), channel);
+    let expected = if BundleVariables::is_used_in(&template) {
+        match crate::bundle_info::read_from_ipa(ipa) {
+            Ok(info) => BundleVariables {
+                bundle_id: info.bundle_identifier.unwrap_or_default(),
+                short_version: info.short_version.unwrap_or_default(),
+                build: info.bundle_version.unwrap_or_default(),
+            }
+            .expand(&template),
+            Err(e) => return AuditStatus::Unresolved { reason: e },
+        }
+    } else {
+        template
+    };
+    if let Err(reason) = validate_ipa_file_name(&expected) {
+        return AuditStatus::Unresolved { reason: format!("'{}' is not a valid file name: {}", expected, reason) };
+    }
+    let current = ipa.file_name().unwrap_or_default().to_string_lossy();
+    if is_output_variant(&current, &expected) {
+        AuditStatus::UpToDate
+    } else {
+        AuditStatus::Rename { to: expected }
+    }
+}
+
+/// Renames each IPA to its `to` name, numbered like `Shop (2).ipa` if that name is taken, along
+/// with its detached signatures, checksum file and OTA manifest. Stops at the first failure; the renames done
+/// so far are returned with it.
+pub fn rename_ipas<'a>(renames: impl IntoIterator<Item = (&'a Path, &'a str)>) -> (Vec<(PathBuf, PathBuf)>, Option<String>) {
+    let mut done = Vec::new();
+    for (from, to) in renames {
+        match rename_ipa(from, to) {
+            Ok(target) => done.push((from.to_path_buf(), target)),
+            Err(e) => return (done, Some(e)),
+        }
+    }
+    (done, None)
+}
+
+fn rename_ipa(from: &Path, to: &str) -> Result<PathBuf, String> {
+    let dir = from.parent().ok_or_else(|| format!("{} has no parent directory", from.display()))?;
+    let target = resolve_output_path(dir, to, OutputExistsPolicy::NumberSuffix, chrono::Local::now().naive_local()).map_err(|e| e.to_string())?;
+    let failed = |e: std::io::Error| format!("Failed to rename {}: {}", from.display(), e);
+    let signatures = ArtifactSigning::existing_signatures(from);
+    let checksum = crate::transfer::checksum_path(from);
+    fs::rename(from, &target).map_err(failed)?;
+    let target_name = target.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    for signature in signatures {
+        let suffix = signature.file_name().unwrap_or_default().to_string_lossy()[from.file_name().unwrap_or_default().len()..].to_string();
+        fs::rename(&signature, target.with_file_name(format!("{}{}", target_name, suffix))).map_err(failed)?;
+    }
+    if checksum.is_file() {
+        // The checksum file names the IPA, so it is rewritten rather than just renamed.
+        let contents = fs::read_to_string(&checksum).map_err(failed)?;
+        let hash = contents.split_whitespace().next().unwrap_or_default();
+        fs::write(crate::transfer::checksum_path(&target), format!("{}  {}\n", hash, target_name)).map_err(failed)?;
+        fs::remove_file(&checksum).map_err(failed)?;
+    }
+    crate::ota_manifest::rename_with_ipa(from, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{write_fixture, FixtureSpec};
+    use chrono::Utc;
+
+    #[test]
+    fn test_stragglers_are_matched_and_renamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_fixture(&FixtureSpec::default(), &input).unwrap();
+        let out = dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let mut config = AppConfig {
+            app_name: "Shop".to_string(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: "shop.ipa".to_string(),
+            ..Default::default()
+        };
+        let built = crate::ipa_logic::generate_ipa(&config, &out).unwrap();
+        let record = BuildRecord {
+            timestamp: Utc::now(),
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            success: true,
+            duration_ms: 1,
+            output_path: Some(built.display().to_string()),
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: crate::transfer::sha256_file(&built).ok(),
+            size_bytes: fs::metadata(&built).ok().map(|m| m.len()),
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        };
+        // A build from before the history existed, told apart only by its BuildInfo.json.
+        let older = out.join("shop-old.ipa");
+        config.output_ipa_name = "shop-old.ipa".to_string();
+        crate::ipa_logic::generate_ipa(&config, &out).unwrap();
+        fs::write(out.join("notes.txt"), "not an IPA").unwrap();
+        fs::write(out.join("stranger.ipa"), "not a zip").unwrap();
+        crate::transfer::write_checksum_file(&older).unwrap();
+
+        config.output_ipa_name = "Shop-{short_version}+{build}.ipa".to_string();
+        let configs = [config];
+        let audit = audit_output_dir(&out, &configs, std::slice::from_ref(&record)).unwrap();
+        let summary: Vec<_> = audit.iter().map(|e| (e.file_name(), e.matched_by, e.status.clone())).collect();
+        let rename = AuditStatus::Rename { to: "Shop-1.0.0+1.ipa".to_string() };
+        assert_eq!(summary, [
+            ("shop-old.ipa".to_string(), Some(MatchedBy::BuildInfo), rename.clone()),
+            ("shop.ipa".to_string(), Some(MatchedBy::Checksum), rename),
+            ("stranger.ipa".to_string(), None, AuditStatus::Unmatched),
+        ]);
+
+        let renames = audit.iter().filter_map(|e| match &e.status {
+            AuditStatus::Rename { to } => Some((e.path.as_path(), to.as_str())),
+            _ => None,
+        });
+        let (done, error) = rename_ipas(renames);
+        assert_eq!(error, None);
+        assert_eq!(done[0].1, out.join("Shop-1.0.0+1.ipa"));
+        assert_eq!(done[1].1, out.join("Shop-1.0.0+1 (2).ipa"));
+        let checksum = fs::read_to_string(out.join("Shop-1.0.0+1.ipa.sha256")).unwrap();
+        assert!(checksum.ends_with("  Shop-1.0.0+1.ipa\n"), "{}", checksum);
+        assert!(!out.join("shop-old.ipa.sha256").exists());
+
+        let audit = audit_output_dir(&out, &configs, &[]).unwrap();
+        assert!(audit.iter().filter(|e| e.app_id.is_some()).all(|e| e.status == AuditStatus::UpToDate));
+    }
+
+    #[test]
+    fn test_channel_templates_use_the_channel_each_ipa_was_built_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_fixture(&FixtureSpec::default(), &input).unwrap();
+        let out = dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let mut config = AppConfig {
+            app_name: "Shop".to_string(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: "Shop-{channel}.ipa".to_string(),
+            channel: "beta".to_string(),
+            ..Default::default()
+        };
+        let beta = crate::ipa_logic::generate_ipa(&config, &out).unwrap();
+        assert_eq!(beta, out.join("Shop-beta.ipa"));
+        config.output_ipa_name = "shop.ipa".to_string();
+        config.channel = "alpha".to_string();
+        let alpha = crate::ipa_logic::generate_ipa(&config, &out).unwrap();
+
+        // The checksum match takes the channel from the history, not from the IPA.
+        let record = BuildRecord {
+            timestamp: Utc::now(),
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            success: true,
+            duration_ms: 1,
+            output_path: Some(alpha.display().to_string()),
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: crate::transfer::sha256_file(&alpha).ok(),
+            size_bytes: fs::metadata(&alpha).ok().map(|m| m.len()),
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: Some("alpha".to_string()),
+        };
+        config.output_ipa_name = "Shop-{channel}.ipa".to_string();
+        config.channel = "release".to_string();
+        let audit = audit_output_dir(&out, &[config], &[record]).unwrap();
+        let summary: Vec<_> = audit.iter().map(|e| (e.file_name(), e.matched_by, e.status.clone())).collect();
+        assert_eq!(summary, [
+            ("Shop-beta.ipa".to_string(), Some(MatchedBy::BuildInfo), AuditStatus::UpToDate),
+            ("shop.ipa".to_string(), Some(MatchedBy::Checksum), AuditStatus::Rename { to: "Shop-alpha.ipa".to_string() }),
+        ]);
+    }
+}
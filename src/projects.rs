@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::AppConfig;
+
+/// Related configs built together, e.g. an app, its watch app and its beta variant.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    /// In the order they were added, which is also the build order between independent members.
+    pub members: Vec<ProjectMember>,
+    pub defaults: ProjectDefaults,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self { id: Uuid::new_v4().to_string(), name: String::new(), members: Vec::new(), defaults: ProjectDefaults::default() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ProjectMember {
+    pub config_id: String,
+    /// Config ids of members that must be built first.
+    pub depends_on: Vec<String>,
+}
+
+/// Settings every member inherits unless its own config sets them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ProjectDefaults {
+    /// Used by members without a CHANGELOG of their own.
+    pub changelog_path: String,
+    /// `CFBundleShortVersionString` for members without a version override, so an app and its
+    /// extensions ship the same marketing version.
+    pub short_version: String,
+}
+
+impl Project {
+    pub fn contains(&self, config_id: &str) -> bool {
+        self.members.iter().any(|m| m.config_id == config_id)
+    }
+
+    /// Adds `config_id` as a member, or removes it and every dependency on it.
+    pub fn set_member(&mut self, config_id: &str, member: bool) {
+        if member && !self.contains(config_id) {
+            self.members.push(ProjectMember { config_id: config_id.to_string(), depends_on: Vec::new() });
+        } else if !member {
+            self.members.retain(|m| m.config_id != config_id);
+            for m in &mut self.members {
+                m.depends_on.retain(|id| id != config_id);
+            }
+        }
+    }
+
+    /// Member config ids in an order where every member comes after its dependencies; members
+    /// that don't depend on each other keep their order. Fails on a dependency cycle or a member
+    /// whose config was deleted.
+    pub fn build_order(&self, configs: &[AppConfig]) -> Result<Vec<String>, String> {
+        let name = |id: &str| configs.iter().find(|c| c.id == id).map(|c| c.app_name.clone());
+        if let Some(missing) = self.members.iter().find(|m| name(&m.config_id).is_none()) {
+            return Err(format!("Project '{}' refers to a deleted app ({})", self.name, missing.config_id));
+        }
+
+        let mut remaining: VecDeque<&ProjectMember> = self.members.iter().collect();
+        let mut order: Vec<String> = Vec::new();
+        while !remaining.is_empty() {
+            let ready = remaining
+                .iter()
+                .position(|m| m.depends_on.iter().all(|dep| order.contains(dep) || !self.contains(dep)));
+            match ready {
+                Some(i) => order.push(remaining.remove(i).unwrap().config_id.clone()),
+                None => {
+                    let names: Vec<String> = remaining.iter().filter_map(|m| name(&m.config_id)).collect();
+                    return Err(format!("Project '{}' has a dependency cycle between {}", self.name, names.join(", ")));
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// `config` with the project defaults filled in where it leaves them unset.
+    pub fn apply_defaults(&self, mut config: AppConfig) -> AppConfig {
+        if config.changelog_path.as_deref().is_none_or(|p| p.trim().is_empty()) && !self.defaults.changelog_path.trim().is_empty() {
+            config.changelog_path = Some(self.defaults.changelog_path.trim().to_string());
+        }
+        if config.plist_overrides.short_version.trim().is_empty() {
+            config.plist_overrides.short_version = self.defaults.short_version.trim().to_string();
+        }
+        config
+    }
+}
+
+/// `config` with the defaults of the first project it belongs to.
+pub fn with_project_defaults(projects: &[Project], config: AppConfig) -> AppConfig {
+    match projects.iter().find(|p| p.contains(&config.id)) {
+        Some(project) => project.apply_defaults(config),
+        None => config,
+    }
+}
+
+/// A "Build project" in progress: the members still to build, one after another.
+#[derive(Debug, Clone)]
+pub struct ProjectRun {
+    pub project_name: String,
+    pub remaining: VecDeque<String>,
+    pub total: usize,
+}
+
+impl ProjectRun {
+    pub fn new(project: &Project, order: Vec<String>) -> Self {
+        Self { project_name: project.name.clone(), total: order.len(), remaining: order.into() }
+    }
+
+    /// 1-based position of the member being built, for "Building project X (2/3)".
+    pub fn position(&self) -> usize {
+        self.total - self.remaining.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_members_build_after_their_dependencies() {
+        let configs: Vec<AppConfig> = ["app", "watch", "beta"]
+            .iter()
+            .map(|id| AppConfig { id: id.to_string(), app_name: id.to_uppercase(), ..Default::default() })
+            .collect();
+        let mut project = Project { name: "Shop".to_string(), ..Default::default() };
+        for id in ["watch", "beta", "app"] {
+            project.set_member(id, true);
+        }
+        project.members[0].depends_on = vec!["app".to_string()];
+        assert_eq!(project.build_order(&configs).unwrap(), vec!["beta", "app", "watch"]);
+
+        project.members[2].depends_on = vec!["watch".to_string()];
+        assert!(project.build_order(&configs).unwrap_err().contains("cycle between WATCH, APP"));
+        project.set_member("app", false);
+        assert_eq!(project.build_order(&configs).unwrap(), vec!["watch", "beta"]);
+        assert!(project.build_order(&configs[..1]).is_err());
+
+        project.defaults.short_version = "2.1.0".to_string();
+        project.defaults.changelog_path = "CHANGELOG.md".to_string();
+        let mut watch = configs[1].clone();
+        watch.changelog_path = Some("watch/CHANGELOG.md".to_string());
+        let watch = with_project_defaults(&[project], watch);
+        assert_eq!(watch.changelog_path.as_deref(), Some("watch/CHANGELOG.md"));
+        assert_eq!(watch.plist_overrides.short_version, "2.1.0");
+        let app = with_project_defaults(&[], configs[0].clone());
+        assert!(app.plist_overrides.short_version.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Builds whose profile expires within this many days are flagged in the table and history.
+pub const EXPIRY_WARNING_DAYS: i64 = 14;
+/// Below this many days the warning turns red.
+pub const EXPIRY_CRITICAL_DAYS: i64 = 7;
+
+/// The parts of an `embedded.mobileprovision` that decide whether testers can still install.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProfileInfo {
+    pub name: Option<String>,
+    pub team_name: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    /// `Enterprise`, `Ad Hoc`, `Development` or `App Store`.
+    pub kind: String,
+    /// Devices listed in `ProvisionedDevices`; 0 for enterprise and App Store profiles.
+    #[serde(default)]
+    pub device_count: usize,
+}
+
+impl ProfileInfo {
+    /// Parses a provisioning profile: a CMS-signed XML plist. The plist is taken straight from
+    /// the signed data, which is stored unencrypted, so no CMS parser is needed.
+    pub fn from_profile_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let start = find(bytes, b"<?xml").ok_or("No plist found in the provisioning profile")?;
+        let end = find(&bytes[start..], b"</plist>").ok_or("Truncated plist in the provisioning profile")? + start + b"</plist>".len();
+        let value = plist::Value::from_reader(Cursor::new(&bytes[start..end])).map_err(|e| format!("Invalid provisioning profile: {}", e))?;
+        let dict = value.as_dictionary().ok_or("Provisioning profile is not a dictionary")?;
+        let expiration = dict.get("ExpirationDate").and_then(|v| v.as_date()).ok_or("Provisioning profile has no ExpirationDate")?;
+
+        let get_task_allow = dict
+            .get("Entitlements")
+            .and_then(|v| v.as_dictionary())
+            .and_then(|e| e.get("get-task-allow"))
+            .and_then(|v| v.as_boolean())
+            .unwrap_or(false);
+        let kind = if dict.get("ProvisionsAllDevices").and_then(|v| v.as_boolean()).unwrap_or(false) {
+            "Enterprise"
+        } else if get_task_allow {
+            "Development"
+        } else if dict.contains_key("ProvisionedDevices") {
+            "Ad Hoc"
+        } else {
+            "App Store"
+        };
+
+        let string = |key: &str| dict.get(key).and_then(|v| v.as_string()).map(str::to_string);
+        Ok(Self {
+            name: string("Name"),
+            team_name: string("TeamName"),
+            expires_at: DateTime::<Utc>::from(SystemTime::from(expiration)),
+            kind: kind.to_string(),
+            device_count: dict.get("ProvisionedDevices").and_then(|v| v.as_array()).map_or(0, Vec::len),
+        })
+    }
+}
+
+/// Reads the main app's `embedded.mobileprovision` from an IPA. `Ok(None)` for unsigned builds,
+/// which have none.
+pub fn read_from_ipa(ipa_path: &Path) -> Result<Option<ProfileInfo>, String> {
+    let file = File::open(ipa_path).map_err(|e| format!("Failed to open {}: {}", ipa_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    let name = archive.file_names().find(|name| is_main_profile(name)).map(str::to_string);
+    read_profile_entry(&mut archive, name)
+}
+
+/// Expiry of the profile in the IPA at `ipa_path`, for the build history. Unreadable profiles
+/// are logged and treated as absent.
+pub fn profile_expiry(ipa_path: &Path) -> Option<DateTime<Utc>> {
+    match read_from_ipa(ipa_path) {
+        Ok(profile) => profile.map(|p| p.expires_at),
+        Err(e) => {
+            log::warn!("Could not read the provisioning profile of {}: {}", ipa_path.display(), e);
+            None
+        }
+    }
+}
+
+/// Reads the `embedded.mobileprovision` of the outermost `.app` in an input ZIP, like
+/// [`crate::bundle_info::read_from_input_zip`] does for its `Info.plist`. `Ok(None)` when the
+/// app isn't signed yet.
+pub fn read_from_input_zip(zip_path: &Path) -> Result<Option<ProfileInfo>, String> {
+    let file = File::open(zip_path).map_err(|e| format!("Failed to open {}: {}", zip_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid ZIP archive: {}", e))?;
+    let name = archive
+        .file_names()
+        .filter(|name| is_app_profile(name))
+        .min_by_key(|name| name.matches('/').count())
+        .map(str::to_string);
+    read_profile_entry(&mut archive, name)
+}
+
+fn read_profile_entry(archive: &mut zip::ZipArchive<File>, name: Option<String>) -> Result<Option<ProfileInfo>, String> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let mut bytes = Vec::new();
+    archive
+        .by_name(&name)
+        .map_err(|e| e.to_string())?
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    ProfileInfo::from_profile_bytes(&bytes).map(Some)
+}
+
+/// `Profile expired 3 days ago` or `Profile expires in 5 days` when `expires_at` is less than
+/// [`EXPIRY_WARNING_DAYS`] away; `None` otherwise.
+pub fn expiry_warning(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> Option<String> {
+    let remaining = expires_at.signed_duration_since(now);
+    let days = remaining.num_days();
+    let plural = |n: i64| if n == 1 { "" } else { "s" };
+    if remaining.num_seconds() <= 0 {
+        Some(match -days {
+            0 => "Profile expired today".to_string(),
+            ago => format!("Profile expired {} day{} ago", ago, plural(ago)),
+        })
+    } else if days < EXPIRY_WARNING_DAYS {
+        Some(match days {
+            0 => "Profile expires today".to_string(),
+            left => format!("Profile expires in {} day{}", left, plural(left)),
+        })
+    } else {
+        None
+    }
+}
+
+/// Whether `expires_at` is past or less than [`EXPIRY_CRITICAL_DAYS`] away.
+pub fn is_expiry_critical(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    expires_at.signed_duration_since(now).num_days() < EXPIRY_CRITICAL_DAYS
+}
+
+fn is_main_profile(name: &str) -> bool {
+    let mut parts = name.split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some("Payload"), Some(app), Some("embedded.mobileprovision"), None) if app.ends_with(".app")
+    )
+}
+
+/// `embedded.mobileprovision` directly inside a `.app` that is not nested in another bundle.
+fn is_app_profile(name: &str) -> bool {
+    let Some(bundle) = name.strip_suffix("/embedded.mobileprovision") else {
+        return false;
+    };
+    let mut components = bundle.split('/').rev();
+    components.next().is_some_and(|app| app.ends_with(".app"))
+        && components.all(|c| !c.ends_with(".app") && !c.ends_with(".appex") && !c.ends_with(".framework"))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_profile_expiry_is_read_and_warned_about() {
+        let expires_at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let mut dict = plist::Dictionary::new();
+        dict.insert("Name".into(), "Shop Ad Hoc".into());
+        dict.insert("ExpirationDate".into(), plist::Value::Date(SystemTime::from(expires_at).into()));
+        dict.insert("ProvisionedDevices".into(), plist::Value::Array(vec!["00008030-001".into()]));
+        let mut xml = Vec::new();
+        plist::Value::Dictionary(dict).to_writer_xml(&mut xml).unwrap();
+        // Stand-ins for the CMS envelope around the plist.
+        let profile = [b"0\x80\x06\x09*\x86H".as_slice(), &xml, b"\xa0\x82\x03\x00signature"].concat();
+
+        let info = ProfileInfo::from_profile_bytes(&profile).unwrap();
+        assert_eq!(info.expires_at, expires_at);
+        assert_eq!(info.kind, "Ad Hoc");
+        assert_eq!(info.name.as_deref(), Some("Shop Ad Hoc"));
+        assert_eq!(info.device_count, 1);
+        assert!(ProfileInfo::from_profile_bytes(b"not a profile").is_err());
+
+        let days = |n: i64| expires_at - chrono::Duration::days(n);
+        assert_eq!(expiry_warning(expires_at, days(30)), None);
+        assert_eq!(expiry_warning(expires_at, days(5)).as_deref(), Some("Profile expires in 5 days"));
+        assert_eq!(expiry_warning(expires_at, days(-1)).as_deref(), Some("Profile expired 1 day ago"));
+        assert!(!is_expiry_critical(expires_at, days(10)) && is_expiry_critical(expires_at, days(5)) && is_expiry_critical(expires_at, days(-1)));
+        assert!(is_main_profile("Payload/Shop.app/embedded.mobileprovision"));
+        assert!(!is_main_profile("Payload/Shop.app/PlugIns/Widget.appex/embedded.mobileprovision"));
+        assert!(is_app_profile("build/Shop.app/embedded.mobileprovision"));
+        assert!(!is_app_profile("Shop.app/PlugIns/Widget.appex/embedded.mobileprovision"));
+    }
+}
@@ -0,0 +1,63 @@
+use egui::{Color32, ColorImage, TextureHandle, TextureOptions};
+
+/// Modules of white border around the code; scanners need at least 4.
+const QUIET_ZONE: usize = 4;
+
+/// Encodes `data` as a QR code image with one pixel per module, in `color` on white. Drawn with
+/// nearest filtering it stays sharp at any size.
+pub fn color_image(data: &str, color: [u8; 3]) -> Result<ColorImage, String> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    let width = code.width();
+    let size = width + 2 * QUIET_ZONE;
+    let dark = Color32::from_rgb(color[0], color[1], color[2]);
+    let mut pixels = vec![Color32::WHITE; size * size];
+    for (i, module) in code.to_colors().into_iter().enumerate() {
+        if module == qrcode::Color::Dark {
+            pixels[(i / width + QUIET_ZONE) * size + i % width + QUIET_ZONE] = dark;
+        }
+    }
+    Ok(ColorImage { size: [size, size], pixels })
+}
+
+/// A link shown as a QR code, for a tester to scan with their phone from the screen.
+pub struct QrView {
+    pub title: String,
+    pub data: String,
+    texture: Option<Result<TextureHandle, String>>,
+}
+
+impl QrView {
+    pub fn new(title: impl Into<String>, data: impl Into<String>) -> Self {
+        Self { title: title.into(), data: data.into(), texture: None }
+    }
+
+    /// The code's texture, encoded and uploaded on first use.
+    pub fn texture(&mut self, ctx: &egui::Context, color: [u8; 3]) -> Result<&TextureHandle, &str> {
+        let data = &self.data;
+        let texture = self
+            .texture
+            .get_or_insert_with(|| color_image(data, color).map(|image| ctx.load_texture("qr_code", image, TextureOptions::NEAREST)));
+        texture.as_ref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_has_quiet_zone_and_finder_pattern() {
+        let image = color_image("https://192.168.1.20:8443/", [0x1d, 0x35, 0x57]).unwrap();
+        let [size, height] = image.size;
+        assert_eq!(size, height);
+        // Version 2 at the smallest: 25 modules plus the border on both sides.
+        assert!(size >= 25 + 2 * QUIET_ZONE, "{}", size);
+        let at = |x: usize, y: usize| image.pixels[y * size + x];
+        assert!((0..size).all(|i| at(i, 0) == Color32::WHITE && at(0, i) == Color32::WHITE));
+        // Top-left finder pattern: a dark ring, a light ring, then a dark centre.
+        let dark = Color32::from_rgb(0x1d, 0x35, 0x57);
+        assert_eq!(at(QUIET_ZONE, QUIET_ZONE), dark);
+        assert_eq!(at(QUIET_ZONE + 1, QUIET_ZONE + 1), Color32::WHITE);
+        assert_eq!(at(QUIET_ZONE + 3, QUIET_ZONE + 3), dark);
+    }
+}
@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::build_history::BuildRecord;
+use crate::time_display::DisplayTimeZone;
+
+/// One IPA of a release manifest, as it is on disk when the manifest is written.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReleaseArtifact {
+    pub file_name: String,
+    pub app_name: String,
+    pub bundle_id: Option<String>,
+    /// `CFBundleShortVersionString`.
+    pub version: Option<String>,
+    /// `CFBundleVersion`.
+    pub build: Option<String>,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub built_at: DateTime<Utc>,
+}
+
+/// A list of built IPAs handed to a distribution team, who can check each file against its
+/// checksum, and the manifest itself against its signature.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReleaseManifest {
+    pub generated_at: DateTime<Utc>,
+    pub generator: String,
+    pub artifacts: Vec<ReleaseArtifact>,
+}
+
+impl ReleaseManifest {
+    /// The manifest of the IPAs of `records`. Every IPA is hashed again, so it fails when one
+    /// is missing or no longer matches the checksum recorded when it was built.
+    pub fn from_records(records: &[&BuildRecord], now: DateTime<Utc>) -> Result<Self, String> {
+        let mut artifacts = Vec::new();
+        for record in records {
+            let Some(path) = record.output_path.as_deref().filter(|_| record.success) else {
+                return Err(format!("The build of {} from {} produced no IPA", record.app_name, record.timestamp.format("%Y-%m-%d %H:%M")));
+            };
+            let path = Path::new(path);
+            let sha256 = crate::transfer::sha256_file(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if record.sha256.as_ref().is_some_and(|recorded| !recorded.eq_ignore_ascii_case(&sha256)) {
+                return Err(format!("{} changed since it was built; its checksum no longer matches the build history", path.display()));
+            }
+            let size_bytes = fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+            let info = crate::bundle_info::read_from_ipa(path).unwrap_or_else(|e| {
+                log::warn!("No version for {} in the release manifest: {}", path.display(), e);
+                Default::default()
+            });
+            artifacts.push(ReleaseArtifact {
+                file_name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                app_name: record.app_name.clone(),
+                bundle_id: info.bundle_identifier,
+                version: info.short_version,
+                build: info.bundle_version,
+                sha256,
+                size_bytes,
+                built_at: record.timestamp,
+            });
+        }
+        Ok(Self { generated_at: now, generator: format!("IPA Builder {}", env!("CARGO_PKG_VERSION")), artifacts })
+    }
+
+    /// Writes the manifest to `dest` as JSON when it ends in `.json`, as CSV of the artifacts
+    /// otherwise. Times are RFC 3339 with the offset of `zone`.
+    pub fn write(&self, dest: &Path, zone: &DisplayTimeZone) -> Result<(), String> {
+        let is_json = dest.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let contents = if is_json {
+            let mut value = serde_json::to_value(self).map_err(|e| format!("Failed to serialize the manifest: {}", e))?;
+            value["generated_at"] = zone.rfc3339(self.generated_at).into();
+            if let Some(items) = value["artifacts"].as_array_mut() {
+                for (artifact, item) in self.artifacts.iter().zip(items) {
+                    item["built_at"] = zone.rfc3339(artifact.built_at).into();
+                }
+            }
+            serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to serialize the manifest: {}", e))?
+        } else {
+            let mut csv = String::from("file_name,app_name,bundle_id,version,build,sha256,size_bytes,built_at\n");
+            for artifact in &self.artifacts {
+                let fields = [
+                    artifact.file_name.clone(),
+                    artifact.app_name.clone(),
+                    artifact.bundle_id.clone().unwrap_or_default(),
+                    artifact.version.clone().unwrap_or_default(),
+                    artifact.build.clone().unwrap_or_default(),
+                    artifact.sha256.clone(),
+                    artifact.size_bytes.to_string(),
+                    zone.rfc3339(artifact.built_at),
+                ];
+                csv.push_str(&fields.iter().map(|f| crate::audit::csv_field(f)).collect::<Vec<_>>().join(","));
+                csv.push('\n');
+            }
+            csv
+        };
+        fs::write(dest, contents).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppConfig;
+    use crate::fixtures::{write_fixture, FixtureSpec};
+
+    #[test]
+    fn test_manifest_lists_verified_ipas() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Runner.app.zip");
+        write_fixture(&FixtureSpec::default(), &input).unwrap();
+        let config = AppConfig {
+            app_name: "Shop, EU".to_string(),
+            input_zip_path: input.to_string_lossy().into_owned(),
+            output_ipa_name: "shop.ipa".to_string(),
+            ..Default::default()
+        };
+        let built = crate::ipa_logic::generate_ipa(&config, dir.path()).unwrap();
+        let mut record = BuildRecord {
+            timestamp: "2024-03-10T09:30:00Z".parse().unwrap(),
+            app_id: config.id.clone(),
+            app_name: config.app_name.clone(),
+            success: true,
+            duration_ms: 1,
+            output_path: Some(built.display().to_string()),
+            release_notes: None,
+            error: None,
+            warnings: Vec::new(),
+            sha256: crate::transfer::sha256_file(&built).ok(),
+            size_bytes: None,
+            profile_expires_at: None,
+            size_breakdown: None,
+            channel: None,
+        };
+        let manifest = ReleaseManifest::from_records(&[&record], Utc::now()).unwrap();
+        let artifact = &manifest.artifacts[0];
+        assert_eq!((artifact.file_name.as_str(), artifact.version.as_deref(), artifact.build.as_deref()), ("shop.ipa", Some("1.0.0"), Some("1")));
+        assert_eq!(artifact.size_bytes, fs::metadata(&built).unwrap().len());
+
+        let csv_path = dir.path().join("release.csv");
+        manifest.write(&csv_path, &DisplayTimeZone::Utc).unwrap();
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("shop.ipa,\"Shop, EU\","), "{}", row);
+        assert!(row.ends_with(&format!(",{},{},2024-03-10T09:30:00+00:00", artifact.sha256, artifact.size_bytes)), "{}", row);
+        let json_path = dir.path().join("release.json");
+        manifest.write(&json_path, &DisplayTimeZone::Utc).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["artifacts"][0]["sha256"], artifact.sha256.as_str());
+
+        record.sha256 = Some("0".repeat(64));
+        assert!(ReleaseManifest::from_records(&[&record], Utc::now()).unwrap_err().contains("changed since it was built"));
+        record.success = false;
+        assert!(ReleaseManifest::from_records(&[&record], Utc::now()).unwrap_err().contains("produced no IPA"));
+    }
+}
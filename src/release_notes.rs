@@ -0,0 +1,50 @@
+use std::path::Path;
+
+/// Reads the latest section of a CHANGELOG file for use as release notes.
+pub fn load_from_changelog(path: &Path) -> Result<String, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read changelog {}: {}", path.display(), e))?;
+    latest_changelog_section(&text)
+        .ok_or_else(|| format!("No release notes found in {}", path.display()))
+}
+
+/// Returns the first `## ` section of a Markdown changelog (Keep a Changelog style), without
+/// its heading. Falls back to the whole text when the file has no second-level headings.
+/// A leading `## [Unreleased]` section is skipped when it is empty.
+pub fn latest_changelog_section(text: &str) -> Option<String> {
+    let mut sections: Vec<Vec<&str>> = Vec::new();
+    let mut preamble: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("## ") {
+            sections.push(Vec::new());
+        } else if let Some(current) = sections.last_mut() {
+            current.push(line);
+        } else if !line.starts_with("# ") {
+            preamble.push(line);
+        }
+    }
+
+    let body = if sections.is_empty() {
+        preamble.join("\n")
+    } else {
+        sections
+            .iter()
+            .map(|lines| lines.join("\n"))
+            .find(|s| !s.trim().is_empty())?
+    };
+    let trimmed = body.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_changelog_section() {
+        let changelog = "# Changelog\n\n## [Unreleased]\n\n## [1.2.0] - 2026-10-01\n### Fixed\n- Login crash\n\n## [1.1.0] - 2026-09-01\n- Old stuff\n";
+        assert_eq!(latest_changelog_section(changelog).as_deref(), Some("### Fixed\n- Login crash"));
+        assert_eq!(latest_changelog_section("Just some notes\n").as_deref(), Some("Just some notes"));
+        assert_eq!(latest_changelog_section("# Changelog\n\n"), None);
+    }
+}
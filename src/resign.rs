@@ -0,0 +1,253 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use tempfile::tempdir;
+
+use crate::codesign::{payload_app_dir, signable_binaries};
+
+/// Identity and provisioning used to re-sign an IPA.
+#[derive(Debug, Clone, Default)]
+pub struct ResignOptions {
+    /// `codesign` identity: a certificate common name or SHA-1 hash.
+    pub identity: String,
+    /// Replaces `embedded.mobileprovision` in the app bundle when set.
+    pub provisioning_profile: Option<PathBuf>,
+    /// Entitlements plist for the main app; when `None` the existing entitlements are preserved.
+    pub entitlements: Option<PathBuf>,
+}
+
+/// Re-signs every binary in `input_ipa` with `options` and writes the result to `output_ipa`.
+///
+/// Nested frameworks, dylibs and app extensions are signed before the app bundle itself,
+/// as `codesign` requires. Signing shells out to `codesign`, so this only works on macOS.
+pub fn resign_ipa(input_ipa: &Path, output_ipa: &Path, options: &ResignOptions) -> Result<PathBuf, String> {
+    if options.identity.trim().is_empty() {
+        return Err("No signing identity selected".to_string());
+    }
+    if !crate::codesign::codesign_available() {
+        return Err("Re-signing requires the macOS codesign tool".to_string());
+    }
+    if let Some(profile) = &options.provisioning_profile {
+        if !profile.is_file() {
+            return Err(format!("Provisioning profile not found: {}", profile.display()));
+        }
+    }
+
+    let work_dir = tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let file = File::open(input_ipa).map_err(|e| format!("Failed to open {}: {}", input_ipa.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid IPA archive: {}", e))?;
+    archive
+        .extract(work_dir.path())
+        .map_err(|e| format!("Failed to extract {}: {}", input_ipa.display(), e))?;
+
+    let app_dir = payload_app_dir(work_dir.path())?;
+    if let Some(profile) = &options.provisioning_profile {
+        fs::copy(profile, app_dir.join("embedded.mobileprovision"))
+            .map_err(|e| format!("Failed to embed provisioning profile: {}", e))?;
+    }
+
+    for bundle in signing_order(&app_dir) {
+        let is_main_app = bundle == app_dir;
+        run_codesign_sign(&bundle, options, is_main_app)?;
+    }
+
+    crate::ipa_logic::write_payload_zip(work_dir.path(), output_ipa).map_err(|e| e.to_string())?;
+    Ok(output_ipa.to_path_buf())
+}
+
+/// Bundles and loose dylibs to sign, innermost first and the app bundle last.
+pub fn signing_order(app_dir: &Path) -> Vec<PathBuf> {
+    let mut bundles: Vec<PathBuf> = signable_binaries(app_dir)
+        .into_iter()
+        .map(|(bundle, _)| bundle)
+        .filter(|bundle| bundle != app_dir)
+        .collect();
+    bundles.dedup();
+    bundles.push(app_dir.to_path_buf());
+    bundles
+}
+
+fn run_codesign_sign(path: &Path, options: &ResignOptions, is_main_app: bool) -> Result<(), String> {
+    let mut cmd = Command::new("codesign");
+    cmd.args(["--force", "--sign", options.identity.trim()]);
+    match (&options.entitlements, is_main_app) {
+        (Some(entitlements), true) => {
+            cmd.arg("--entitlements").arg(entitlements);
+        }
+        _ => {
+            cmd.arg("--preserve-metadata=identifier,entitlements");
+        }
+    }
+    let output = cmd
+        .arg(path)
+        .output()
+        .map_err(|e| format!("failed to run codesign: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "codesign failed for {}: {}",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Lists valid code-signing identities from the keychain (`security find-identity`).
+pub fn list_signing_identities() -> Result<Vec<String>, String> {
+    let output = Command::new("security")
+        .args(["find-identity", "-v", "-p", "codesigning"])
+        .output()
+        .map_err(|e| format!("failed to run security: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_find_identity_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Extracts the quoted identity names from `security find-identity` output lines such as
+/// `  1) 0123ABCD... "Apple Distribution: Example Corp (ABCDE12345)"`.
+fn parse_find_identity_output(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let start = line.find('"')?;
+            let end = line.rfind('"')?;
+            (end > start + 1).then(|| line[start + 1..end].to_string())
+        })
+        .collect()
+}
+
+/// `.ipa` files directly inside `dir`, sorted by name.
+pub fn list_ipas(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut ipas: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ipa")))
+        .collect();
+    ipas.sort();
+    Ok(ipas)
+}
+
+#[derive(Debug, Clone)]
+pub enum BatchResignMessage {
+    Started { index: usize },
+    Finished { index: usize, result: Result<PathBuf, String> },
+    Done { cancelled: bool },
+}
+
+/// Re-signs a list of IPAs one after another on a background thread, reporting per-file progress.
+pub struct BatchResignRunner {
+    files: Vec<PathBuf>,
+    cancel_flag: Arc<AtomicBool>,
+    join_handle: Option<thread::JoinHandle<()>>,
+    rx: mpsc::Receiver<BatchResignMessage>,
+}
+
+impl BatchResignRunner {
+    pub fn start(input_dir: &Path, output_dir: &Path, options: ResignOptions) -> Result<Self, String> {
+        if !output_dir.is_dir() {
+            return Err(format!("Output directory is invalid: {}", output_dir.display()));
+        }
+        if options.identity.trim().is_empty() {
+            return Err("No signing identity selected".to_string());
+        }
+        let files = list_ipas(input_dir)?;
+        if files.is_empty() {
+            return Err(format!("No .ipa files found in {}", input_dir.display()));
+        }
+        if input_dir.canonicalize().ok() == output_dir.canonicalize().ok() {
+            return Err("Output directory must differ from the input directory".to_string());
+        }
+
+        let (tx, rx) = mpsc::channel::<BatchResignMessage>();
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let cancel_flag_thread = Arc::clone(&cancel_flag);
+        let thread_files = files.clone();
+        let output_dir = output_dir.to_path_buf();
+
+        let join_handle = thread::spawn(move || {
+            for (index, input) in thread_files.iter().enumerate() {
+                if cancel_flag_thread.load(Ordering::Relaxed) {
+                    let _ = tx.send(BatchResignMessage::Done { cancelled: true });
+                    return;
+                }
+                let _ = tx.send(BatchResignMessage::Started { index });
+                let output = output_dir.join(input.file_name().unwrap_or_default());
+                let result = resign_ipa(input, &output, &options);
+                match &result {
+                    Ok(path) => log::info!("Re-signed {} -> {}", input.display(), path.display()),
+                    Err(e) => log::error!("Re-signing {} failed: {}", input.display(), e),
+                }
+                let _ = tx.send(BatchResignMessage::Finished { index, result });
+            }
+            let _ = tx.send(BatchResignMessage::Done { cancelled: false });
+        });
+
+        Ok(Self {
+            files,
+            cancel_flag,
+            join_handle: Some(join_handle),
+            rx,
+        })
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn try_recv(&self) -> Option<BatchResignMessage> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Stops after the IPA currently being signed.
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BatchResignRunner {
+    fn drop(&mut self) {
+        self.cancel();
+        if let Some(handle) = self.join_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_order_puts_app_bundle_last() {
+        let dir = tempdir().unwrap();
+        let app_dir = dir.path().join("App.app");
+        let framework = app_dir.join("Frameworks/Lib.framework");
+        fs::create_dir_all(&framework).unwrap();
+        let binary = ipa_builder_core::macho::test_support::thin_macho(ipa_builder_core::macho::CPU_TYPE_ARM64, None);
+        fs::write(app_dir.join("App"), &binary).unwrap();
+        fs::write(framework.join("Lib"), &binary).unwrap();
+        fs::write(app_dir.join("Frameworks/libswiftCore.dylib"), &binary).unwrap();
+
+        let order = signing_order(&app_dir);
+        assert_eq!(order, vec![framework, app_dir.join("Frameworks/libswiftCore.dylib"), app_dir.clone()]);
+    }
+
+    #[test]
+    fn test_parse_find_identity_output() {
+        let stdout = "  1) 0123456789ABCDEF \"Apple Distribution: Example Corp (ABCDE12345)\"\n  2) FEDCBA9876543210 \"Apple Development: Jane Doe (XYZ9876543)\"\n     2 valid identities found\n";
+        assert_eq!(
+            parse_find_identity_output(stdout),
+            vec![
+                "Apple Distribution: Example Corp (ABCDE12345)".to_string(),
+                "Apple Development: Jane Doe (XYZ9876543)".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agent::RemoteAgent;
+use crate::artifact_signing::ArtifactSigning;
+use crate::branding::Branding;
+use crate::channels::BuildChannel;
+use crate::compression::CompressionSettings;
+use crate::config_utils::get_data_dir_path;
+use crate::input_cache::InputCache;
+use crate::ipa_logic::{BuildOptions, CancelToken};
+use crate::network::NetworkSettings;
+use crate::notifications::EmailSettings;
+use crate::share::ShareSettings;
+use crate::time_display::DisplayTimeZone;
+use crate::webhook::Webhook;
+
+/// Application-wide preferences shown in the Settings window.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Build the IPA in a local temp directory and copy it to the output directory
+    /// afterwards, for output directories on SMB/NFS shares.
+    pub stage_output_locally: bool,
+    pub output_copy_attempts: u32,
+    /// HTTPS URL that exported install page bundles will be uploaded to.
+    pub install_page_base_url: String,
+    /// Organization name, logo and colors used on exported install pages.
+    pub branding: Branding,
+    /// Shell commands run around every build; see `hooks.rs` for the available variables.
+    pub pre_build_hook: String,
+    pub post_build_hook: String,
+    pub dedupe_frameworks: bool,
+    pub exclude_debug_symbols: bool,
+    pub strip_bitcode: bool,
+    pub package_from_input_zip: bool,
+    pub compression: CompressionSettings,
+    pub deterministic_output: bool,
+    /// Warn when a build runs this many times longer than the app's average; 0 turns it off.
+    pub build_watchdog_multiplier: f32,
+    /// `http(s)://host:port` of an `ipa_builder agent` that builds instead of this machine.
+    pub remote_agent_url: String,
+    pub remote_agent_token: String,
+    /// Upload limit in KiB/s for inputs sent to the agent; 0 is unlimited.
+    pub upload_limit_kb_per_sec: u32,
+    /// Proxy and extra trusted CAs for every outgoing HTTP request.
+    pub network: NetworkSettings,
+    /// minisign or GPG signature written next to every IPA built here.
+    pub artifact_signing: ArtifactSigning,
+    /// Write `<name>.ipa.sha256` next to every IPA built here.
+    pub write_checksum_file: bool,
+    /// Built-in HTTPS server for installing builds over the air on the local network.
+    pub ota_server: OtaServerSettings,
+    /// Share service (Diawi) builds are uploaded to for a short install link.
+    pub share: ShareSettings,
+    /// Release channels builds can be made for, picked on each app's row.
+    pub channels: Vec<BuildChannel>,
+    /// Notified after every build; configs can add their own.
+    pub webhooks: Vec<Webhook>,
+    /// How email notifications are sent.
+    pub email: EmailSettings,
+    /// Size limit of the cache for URL and network-share inputs; least recently used
+    /// downloads are evicted beyond it.
+    pub input_cache_max_mb: u64,
+    /// Zone timestamps are shown and exported in; they are always stored in UTC.
+    pub time_zone: DisplayTimeZone,
+    /// Show "2 hours ago" in the table, with the exact time on hover.
+    pub relative_times: bool,
+    /// High-contrast palette; see `accessibility::apply_high_contrast`.
+    pub high_contrast: bool,
+    /// No animations, and a static indicator instead of spinners.
+    pub reduce_motion: bool,
+    /// Wait for every metrics write to reach the disk; see `MetricsCollector::sync_writes`.
+    pub sync_metrics_writes: bool,
+    /// Build history and metrics older than this are deleted on startup; 0 keeps everything.
+    pub history_retention_days: u32,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            stage_output_locally: false,
+            output_copy_attempts: 4,
+            install_page_base_url: String::new(),
+            branding: Branding::default(),
+            pre_build_hook: String::new(),
+            post_build_hook: String::new(),
+            dedupe_frameworks: false,
+            exclude_debug_symbols: false,
+            strip_bitcode: false,
+            package_from_input_zip: false,
+            compression: CompressionSettings::default(),
+            deterministic_output: false,
+            build_watchdog_multiplier: 3.0,
+            remote_agent_url: String::new(),
+            remote_agent_token: String::new(),
+            upload_limit_kb_per_sec: 0,
+            network: NetworkSettings::default(),
+            artifact_signing: ArtifactSigning::default(),
+            write_checksum_file: false,
+            ota_server: OtaServerSettings::default(),
+            share: ShareSettings::default(),
+            channels: BuildChannel::defaults(),
+            webhooks: Vec::new(),
+            email: EmailSettings::default(),
+            input_cache_max_mb: 5120,
+            time_zone: DisplayTimeZone::default(),
+            relative_times: true,
+            high_contrast: false,
+            reduce_motion: false,
+            sync_metrics_writes: true,
+            history_retention_days: 0,
+        }
+    }
+}
+
+/// How the OTA server (`ota_server.rs`, built with the `ota-server` feature) is reached. iOS only
+/// installs over HTTPS with a certificate the device trusts, so one must be provided, e.g. made
+/// with mkcert and its root installed on the test devices.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct OtaServerSettings {
+    /// Start serving when IPA Builder opens.
+    pub start_on_launch: bool,
+    pub port: u16,
+    /// PEM certificate chain and private key for the server.
+    pub cert_path: String,
+    pub key_path: String,
+    /// URL devices reach the server at, matching the certificate's host name. Empty uses
+    /// `https://<host name>.local:<port>/`.
+    pub public_url: String,
+}
+
+impl Default for OtaServerSettings {
+    fn default() -> Self {
+        Self { start_on_launch: false, port: 8443, cert_path: String::new(), key_path: String::new(), public_url: String::new() }
+    }
+}
+
+impl AppSettings {
+    pub fn build_options(&self) -> BuildOptions {
+        BuildOptions {
+            stage_output_locally: self.stage_output_locally,
+            output_copy_attempts: self.output_copy_attempts,
+            release_notes: None,
+            pre_build_hook: Some(self.pre_build_hook.clone()).filter(|h| !h.trim().is_empty()),
+            post_build_hook: Some(self.post_build_hook.clone()).filter(|h| !h.trim().is_empty()),
+            dedupe_frameworks: self.dedupe_frameworks,
+            exclude_debug_symbols: self.exclude_debug_symbols,
+            strip_bitcode: self.strip_bitcode,
+            package_from_input_zip: self.package_from_input_zip,
+            compression: self.compression.clone(),
+            deterministic: self.deterministic_output,
+            remote_agent: Some(self.remote_agent_url.trim())
+                .filter(|url| !url.is_empty() && !self.network.offline)
+                .map(|url| RemoteAgent {
+                    url: url.to_string(),
+                    token: Some(self.remote_agent_token.trim().to_string()).filter(|t| !t.is_empty()),
+                    upload_bytes_per_sec: self.upload_bytes_per_sec(),
+                    network: self.network.clone(),
+                }),
+            input_cache: self.input_cache(),
+            artifact_signing: self.artifact_signing.clone(),
+            write_checksum: self.write_checksum_file,
+            network: self.network.clone(),
+            upload_bytes_per_sec: self.upload_bytes_per_sec(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// The upload limit in bytes per second, for every upload; 0 is unlimited.
+    pub fn upload_bytes_per_sec(&self) -> u64 {
+        u64::from(self.upload_limit_kb_per_sec) * 1024
+    }
+
+    /// The input cache under the data directory, if there is one.
+    pub fn input_cache(&self) -> Option<InputCache> {
+        get_data_dir_path().map(|dir| InputCache {
+            network: self.network.clone(),
+            ..InputCache::new(dir.join("input_cache"), self.input_cache_max_mb * 1024 * 1024)
+        })
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{Map, Value};
+
+/// Environment variable choosing where the app state is kept: `json` (the default), `toml`,
+/// `sqlite` or `eframe`.
+pub const STORE_ENV: &str = "IPA_BUILDER_STATE_STORE";
+const TOML_FILE_NAME: &str = "app_state.toml";
+const SQLITE_FILE_NAME: &str = "app_state.sqlite";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    /// `app_state.json` in the config directory.
+    Json,
+    /// `app_state.toml` in the config directory, for hand-editing.
+    Toml,
+    /// `app_state.sqlite` in the config directory, with one row per app config.
+    Sqlite,
+    /// eframe's own storage (`app.ron` in the data directory). GUI only.
+    Eframe,
+}
+
+impl StoreKind {
+    /// The kind named by [`STORE_ENV`], [`StoreKind::Json`] when it isn't set.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var(STORE_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value),
+            _ => Ok(StoreKind::Json),
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(StoreKind::Json),
+            "toml" => Ok(StoreKind::Toml),
+            "sqlite" => Ok(StoreKind::Sqlite),
+            "eframe" => Ok(StoreKind::Eframe),
+            other => Err(format!("Unknown {} '{}': use json, toml, sqlite or eframe", STORE_ENV, other)),
+        }
+    }
+
+    /// The file store of this kind in `config_dir`; `None` for [`StoreKind::Eframe`], which
+    /// only exists while the GUI runs.
+    pub fn file_store(self, config_dir: &Path) -> Option<Box<dyn StateStore>> {
+        match self {
+            StoreKind::Json => Some(Box::new(JsonFileStore { path: config_dir.join(crate::migration::APP_STATE_FILE_NAME) })),
+            StoreKind::Toml => Some(Box::new(TomlFileStore { path: config_dir.join(TOML_FILE_NAME) })),
+            StoreKind::Sqlite => Some(Box::new(SqliteStore { path: config_dir.join(SQLITE_FILE_NAME) })),
+            StoreKind::Eframe => None,
+        }
+    }
+}
+
+/// Where the app state (configs, settings and everything else the GUI persists) is loaded
+/// from and saved to. The state is passed as JSON so the GUI and the headless commands, which
+/// only know part of it, can share a store.
+pub trait StateStore {
+    /// Where the state is kept, for the log and Diagnostics.
+    fn location(&self) -> String;
+    /// The saved state, `None` when nothing was saved yet.
+    fn load(&self) -> Result<Option<Value>, String>;
+    fn save(&mut self, state: &Value) -> Result<(), String>;
+}
+
+/// Loads the state from `store`. A store that is still empty is seeded from `app_state.json`
+/// in `config_dir`, so switching away from the default keeps your apps; the JSON file is left
+/// in place.
+pub fn load_or_migrate(store: &(impl StateStore + ?Sized), kind: StoreKind, config_dir: &Path) -> Result<Option<Value>, String> {
+    if let Some(state) = store.load()? {
+        return Ok(Some(state));
+    }
+    if kind == StoreKind::Json {
+        return Ok(None);
+    }
+    let json = JsonFileStore { path: config_dir.join(crate::migration::APP_STATE_FILE_NAME) };
+    let state = json.load()?;
+    if state.is_some() {
+        log::info!("{} is empty; loading the state from {}", store.location(), json.location());
+    }
+    Ok(state)
+}
+
+/// Writes through a temporary file so a crash never leaves half a state behind.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub struct JsonFileStore {
+    pub path: PathBuf,
+}
+
+impl StateStore for JsonFileStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Option<Value>, String> {
+        read_if_exists(&self.path)?
+            .map(|text| serde_json::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", self.path.display(), e)))
+            .transpose()
+    }
+
+    fn save(&mut self, state: &Value) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(state).map_err(|e| format!("Failed to serialize app state: {}", e))?;
+        write_atomically(&self.path, &json)
+    }
+}
+
+pub struct TomlFileStore {
+    pub path: PathBuf,
+}
+
+impl StateStore for TomlFileStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Option<Value>, String> {
+        read_if_exists(&self.path)?
+            .map(|text| toml::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", self.path.display(), e)))
+            .transpose()
+    }
+
+    fn save(&mut self, state: &Value) -> Result<(), String> {
+        // TOML has no null; unset fields are left out and read back as their defaults.
+        let toml = toml::to_string_pretty(&without_nulls(state)).map_err(|e| format!("Failed to serialize app state as TOML: {}", e))?;
+        write_atomically(&self.path, &toml)
+    }
+}
+
+fn without_nulls(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter().filter(|(_, v)| !v.is_null()).map(|(k, v)| (k.clone(), without_nulls(v))).collect()),
+        Value::Array(items) => Value::Array(items.iter().map(without_nulls).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Keeps each app config in its own row of `app_configs`, so thousands of apps can be queried
+/// with any SQLite client; the rest of the state is one row per top-level field of `app_state`.
+pub struct SqliteStore {
+    pub path: PathBuf,
+}
+
+impl SqliteStore {
+    fn open(&self) -> Result<Connection, String> {
+        let connection = Connection::open(&self.path).map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS app_state (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+                 CREATE TABLE IF NOT EXISTS app_configs (
+                     position INTEGER PRIMARY KEY,
+                     id TEXT NOT NULL,
+                     app_name TEXT NOT NULL,
+                     config TEXT NOT NULL
+                 );
+                 CREATE INDEX IF NOT EXISTS app_configs_id ON app_configs (id);",
+            )
+            .map_err(|e| format!("Failed to set up {}: {}", self.path.display(), e))?;
+        Ok(connection)
+    }
+
+    fn read(connection: &Connection) -> rusqlite::Result<Option<Value>> {
+        let has_state: Option<i64> = connection.query_row("SELECT 1 FROM app_state LIMIT 1", [], |row| row.get(0)).optional()?;
+        if has_state.is_none() {
+            return Ok(None);
+        }
+        let parse = |text: String| serde_json::from_str(&text).unwrap_or(Value::Null);
+        let mut state = Map::new();
+        let mut fields = connection.prepare("SELECT key, value FROM app_state")?;
+        for row in fields.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+            let (key, value) = row?;
+            state.insert(key, parse(value));
+        }
+        let mut configs = connection.prepare("SELECT config FROM app_configs ORDER BY position")?;
+        let configs = configs.query_map([], |row| row.get::<_, String>(0))?.map(|config| config.map(parse)).collect::<rusqlite::Result<Vec<_>>>()?;
+        state.insert("app_configs".to_string(), Value::Array(configs));
+        Ok(Some(Value::Object(state)))
+    }
+
+    fn write(connection: &mut Connection, state: &Map<String, Value>) -> rusqlite::Result<()> {
+        let tx = connection.transaction()?;
+        tx.execute("DELETE FROM app_state", [])?;
+        tx.execute("DELETE FROM app_configs", [])?;
+        {
+            let mut insert_field = tx.prepare("INSERT INTO app_state (key, value) VALUES (?1, ?2)")?;
+            for (key, value) in state.iter().filter(|(key, _)| *key != "app_configs") {
+                insert_field.execute(params![key, value.to_string()])?;
+            }
+            let mut insert_config = tx.prepare("INSERT INTO app_configs (position, id, app_name, config) VALUES (?1, ?2, ?3, ?4)")?;
+            let configs = state.get("app_configs").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            for (position, config) in configs.iter().enumerate() {
+                let text = |field: &str| config.get(field).and_then(Value::as_str).unwrap_or_default().to_string();
+                insert_config.execute(params![position as i64, text("id"), text("app_name"), config.to_string()])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Option<Value>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        Self::read(&self.open()?).map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))
+    }
+
+    fn save(&mut self, state: &Value) -> Result<(), String> {
+        let state = state.as_object().ok_or_else(|| "App state is not a JSON object".to_string())?;
+        Self::write(&mut self.open()?, state).map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+/// eframe's storage, kept under [`eframe::APP_KEY`] and flushed by eframe itself.
+impl StateStore for dyn eframe::Storage + '_ {
+    fn location(&self) -> String {
+        "eframe storage (app.ron in the data directory)".to_string()
+    }
+
+    fn load(&self) -> Result<Option<Value>, String> {
+        self.get_string(eframe::APP_KEY)
+            .map(|text| serde_json::from_str(&text).map_err(|e| format!("Failed to parse the app state in eframe storage: {}", e)))
+            .transpose()
+    }
+
+    fn save(&mut self, state: &Value) -> Result<(), String> {
+        self.set_string(eframe::APP_KEY, state.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stores_round_trip_and_migrate_from_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = serde_json::json!({
+            "output_directory": null,
+            "dark_mode": true,
+            "settings": { "time_zone": "Europe/Paris", "upload_limit_kb_per_sec": 0 },
+            "app_configs": [
+                { "id": "a1", "app_name": "Shop", "last_generated_at": null, "build_counter": 3 },
+                { "id": "b2", "app_name": "Blog", "last_generated_at": "2024-01-01T00:00:00Z", "build_counter": 0 }
+            ]
+        });
+
+        for kind in [StoreKind::Json, StoreKind::Toml, StoreKind::Sqlite] {
+            let mut store = kind.file_store(dir.path()).unwrap();
+            assert_eq!(store.load().unwrap(), None, "{:?}", kind);
+            store.save(&state).unwrap();
+            let loaded = store.load().unwrap().unwrap();
+            assert_eq!(loaded["settings"], state["settings"], "{:?}", kind);
+            assert_eq!(loaded["app_configs"][1], state["app_configs"][1], "{:?}", kind);
+            assert_eq!(loaded["app_configs"][0]["build_counter"], 3, "{:?}", kind);
+        }
+
+        // Configs are rows of their own in SQLite.
+        let connection = Connection::open(dir.path().join(SQLITE_FILE_NAME)).unwrap();
+        let name: String = connection.query_row("SELECT app_name FROM app_configs WHERE id = 'b2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Blog");
+
+        // A new store starts from app_state.json.
+        let empty = tempfile::tempdir().unwrap();
+        JsonFileStore { path: empty.path().join(crate::migration::APP_STATE_FILE_NAME) }.save(&state).unwrap();
+        let sqlite = StoreKind::Sqlite.file_store(empty.path()).unwrap();
+        let migrated = load_or_migrate(sqlite.as_ref(), StoreKind::Sqlite, empty.path()).unwrap().unwrap();
+        assert_eq!(migrated["dark_mode"], true);
+
+        assert_eq!(StoreKind::parse(" SQLite ").unwrap(), StoreKind::Sqlite);
+        assert!(StoreKind::parse("redis").unwrap_err().contains(STORE_ENV));
+    }
+}
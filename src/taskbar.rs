@@ -0,0 +1,61 @@
+use eframe::egui;
+
+pub const APP_TITLE: &str = "IPA Builder";
+
+/// A running background job. eframe exposes no native taskbar progress bar or Dock badge, so
+/// progress is shown in the window title, which the Windows/Linux taskbar and the macOS Dock's
+/// window list display while the app is in the background.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobProgress {
+    pub label: String,
+    pub done: usize,
+    /// `None` for jobs without measurable steps.
+    pub total: Option<usize>,
+}
+
+pub fn window_title(progress: Option<&JobProgress>) -> String {
+    match progress {
+        None => APP_TITLE.to_string(),
+        Some(JobProgress { label, done, total: Some(total) }) if *total > 0 => {
+            format!("[{}%] {} ({}/{}) — {}", done * 100 / total, label, done, total, APP_TITLE)
+        }
+        Some(job) => format!("[…] {} — {}", job.label, APP_TITLE),
+    }
+}
+
+/// Keeps the window title in sync with the running job and asks for attention when a job
+/// finishes while the window is in the background.
+#[derive(Debug, Default)]
+pub struct TaskbarProgress {
+    shown_title: Option<String>,
+    busy: bool,
+}
+
+impl TaskbarProgress {
+    pub fn update(&mut self, ctx: &egui::Context, progress: Option<JobProgress>) {
+        let title = window_title(progress.as_ref());
+        if self.shown_title.as_deref() != Some(title.as_str()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.shown_title = Some(title);
+        }
+        let busy = progress.is_some();
+        if self.busy && !busy && !ctx.input(|i| i.focused) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Informational));
+        }
+        self.busy = busy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_title() {
+        assert_eq!(window_title(None), "IPA Builder");
+        let job = JobProgress { label: "Re-signing".to_string(), done: 3, total: Some(8) };
+        assert_eq!(window_title(Some(&job)), "[37%] Re-signing (3/8) — IPA Builder");
+        let job = JobProgress { label: "Building Shop".to_string(), done: 0, total: None };
+        assert_eq!(window_title(Some(&job)), "[…] Building Shop — IPA Builder");
+    }
+}
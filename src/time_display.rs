@@ -0,0 +1,134 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// How timestamps are shown in tables, lists and exports.
+pub const DISPLAY_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Time zone timestamps are displayed in. Everything is still stored in UTC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayTimeZone {
+    /// The time zone of the machine IPA Builder runs on.
+    #[default]
+    Local,
+    Utc,
+    /// An IANA name such as `Europe/Paris`; UTC is used while it is not a known zone.
+    Named(String),
+}
+
+impl DisplayTimeZone {
+    /// `time` as `2024-05-01 14:30` in this zone.
+    pub fn display(&self, time: DateTime<Utc>) -> String {
+        self.format(time, DISPLAY_FORMAT)
+    }
+
+    /// Calendar day of `time` in this zone.
+    pub fn date(&self, time: DateTime<Utc>) -> NaiveDate {
+        match self {
+            DisplayTimeZone::Local => time.with_timezone(&Local).date_naive(),
+            DisplayTimeZone::Utc => time.date_naive(),
+            DisplayTimeZone::Named(name) => match Tz::from_str(name.trim()) {
+                Ok(tz) => time.with_timezone(&tz).date_naive(),
+                Err(_) => time.date_naive(),
+            },
+        }
+    }
+
+    /// `time` in RFC 3339 with this zone's offset, e.g. `2024-05-01T14:30:00+02:00`.
+    pub fn rfc3339(&self, time: DateTime<Utc>) -> String {
+        match self {
+            DisplayTimeZone::Local => time.with_timezone(&Local).to_rfc3339(),
+            DisplayTimeZone::Utc => time.to_rfc3339(),
+            DisplayTimeZone::Named(name) => match Tz::from_str(name.trim()) {
+                Ok(tz) => time.with_timezone(&tz).to_rfc3339(),
+                Err(_) => time.to_rfc3339(),
+            },
+        }
+    }
+
+    pub fn format(&self, time: DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            DisplayTimeZone::Local => time.with_timezone(&Local).format(fmt).to_string(),
+            DisplayTimeZone::Utc => time.format(fmt).to_string(),
+            DisplayTimeZone::Named(name) => match Tz::from_str(name.trim()) {
+                Ok(tz) => time.with_timezone(&tz).format(fmt).to_string(),
+                Err(_) => time.format(fmt).to_string(),
+            },
+        }
+    }
+
+    /// `time` as `2024-05-01 14:30 (Europe/Paris)`, for tooltips that have room for the zone.
+    pub fn display_with_zone(&self, time: DateTime<Utc>) -> String {
+        match self {
+            DisplayTimeZone::Local => format!("{} (local time)", self.display(time)),
+            DisplayTimeZone::Utc => format!("{} UTC", self.display(time)),
+            DisplayTimeZone::Named(name) if self.problem().is_none() => format!("{} ({})", self.display(time), name.trim()),
+            DisplayTimeZone::Named(_) => format!("{} UTC", self.display(time)),
+        }
+    }
+
+    /// Why a named zone is not used, for display next to the setting.
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            DisplayTimeZone::Named(name) if Tz::from_str(name.trim()).is_err() => {
+                Some(format!("'{}' is not a known time zone (e.g. Europe/Paris); UTC is shown.", name))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How long before `now` `time` was, e.g. `just now`, `5 minutes ago`, `yesterday` or
+/// `3 months ago`. Times in the future, from a clock that was ahead, read `just now`.
+pub fn relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(time);
+    let ago = |count: i64, unit: &str| format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" });
+    match elapsed.num_seconds() {
+        ..=59 => "just now".to_string(),
+        60..=3599 => ago(elapsed.num_minutes(), "minute"),
+        3600..=86_399 => ago(elapsed.num_hours(), "hour"),
+        86_400..=172_799 => "yesterday".to_string(),
+        _ => match elapsed.num_days() {
+            days @ ..=29 => ago(days, "day"),
+            days @ 30..=364 => ago(days / 30, "month"),
+            days => ago(days / 365, "year"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_named_zones_convert_and_fall_back_to_utc() {
+        let time = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let paris = DisplayTimeZone::Named("Europe/Paris".to_string());
+        assert_eq!(paris.display(time), "2024-07-01 14:00");
+        assert_eq!(paris.rfc3339(time), "2024-07-01T14:00:00+02:00");
+        assert_eq!(DisplayTimeZone::Utc.display(time), "2024-07-01 12:00");
+        assert!(paris.problem().is_none());
+
+        let unknown = DisplayTimeZone::Named("Mars/Olympus".to_string());
+        assert_eq!(unknown.display(time), "2024-07-01 12:00");
+        assert!(unknown.problem().is_some());
+    }
+
+    #[test]
+    fn test_relative_times() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let before = |seconds: i64| relative(now - chrono::Duration::seconds(seconds), now);
+        assert_eq!(before(-30), "just now");
+        assert_eq!(before(59), "just now");
+        assert_eq!(before(60), "1 minute ago");
+        assert_eq!(before(2 * 3600 + 59), "2 hours ago");
+        assert_eq!(before(30 * 3600), "yesterday");
+        assert_eq!(before(6 * 86_400), "6 days ago");
+        assert_eq!(before(65 * 86_400), "2 months ago");
+        assert_eq!(before(800 * 86_400), "2 years ago");
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+
+/// How long a toast stays up unless the pointer is over it.
+const TOAST_LIFETIME: Duration = Duration::from_secs(8);
+/// Toasts beyond this many push out the oldest.
+const MAX_TOASTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub title: String,
+    pub text: String,
+    shown_at: Instant,
+}
+
+/// Short-lived messages stacked in the bottom-right corner of the window, newest at the bottom.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, kind: ToastKind, title: String, text: String) {
+        self.push_at(kind, title, text, Instant::now());
+    }
+
+    fn push_at(&mut self, kind: ToastKind, title: String, text: String, now: Instant) {
+        self.toasts.push(Toast { kind, title, text, shown_at: now });
+        let excess = self.toasts.len().saturating_sub(MAX_TOASTS);
+        self.toasts.drain(..excess);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    /// Drops the toasts older than [`TOAST_LIFETIME`].
+    fn expire(&mut self, now: Instant) {
+        self.toasts.retain(|t| now.duration_since(t.shown_at) < TOAST_LIFETIME);
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let mut dismissed = None;
+        let mut hovered = false;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .interactable(true)
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    let color = match toast.kind {
+                        ToastKind::Success => ui.visuals().text_color(),
+                        ToastKind::Warning => ui.visuals().warn_fg_color,
+                        ToastKind::Error => ui.visuals().error_fg_color,
+                    };
+                    let response = egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, egui::RichText::new(&toast.title).strong());
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                    dismissed = Some(i);
+                                }
+                            });
+                        });
+                        ui.label(&toast.text);
+                    });
+                    hovered |= ui.rect_contains_pointer(response.response.rect);
+                }
+            });
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+        if hovered {
+            // Keep every toast up while the pointer rests on one, so it can be read.
+            for toast in &mut self.toasts {
+                toast.shown_at = now;
+            }
+        }
+        self.expire(now);
+        if !self.toasts.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_and_are_capped() {
+        let start = Instant::now();
+        let mut toasts = Toasts::default();
+        for i in 0..6 {
+            toasts.push_at(ToastKind::Success, format!("Build {}", i), String::new(), start + Duration::from_secs(i));
+        }
+        let titles: Vec<&str> = toasts.toasts.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Build 2", "Build 3", "Build 4", "Build 5"]);
+        toasts.expire(start + Duration::from_secs(11));
+        assert_eq!(toasts.toasts.len(), 2);
+        toasts.expire(start + Duration::from_secs(20));
+        assert!(toasts.is_empty());
+    }
+}
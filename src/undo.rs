@@ -0,0 +1,232 @@
+use crate::app::AppConfig;
+use crate::projects::{Project, ProjectMember};
+
+/// How many changes Undo can go back.
+const UNDO_LIMIT: usize = 100;
+
+/// A deleted config's place in a project, to put it back on undo.
+#[derive(Debug, Clone)]
+pub struct Membership {
+    pub project_id: String,
+    /// The config's own entry and its position in `members`, if it was a member.
+    pub member: Option<(usize, ProjectMember)>,
+    /// Members that depended on the config.
+    pub dependents: Vec<String>,
+}
+
+/// One user change to the app list, with what is needed to revert and repeat it.
+#[derive(Debug, Clone)]
+pub enum ConfigChange {
+    Added(AppConfig),
+    Edited { before: Box<AppConfig>, after: Box<AppConfig> },
+    Deleted { index: usize, config: AppConfig, memberships: Vec<Membership> },
+}
+
+/// What undoing or redoing a change did, for the caller to update what it derives from configs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Applied {
+    Inserted(String),
+    Replaced(String),
+    Removed(String),
+}
+
+impl ConfigChange {
+    /// Records the deletion of the config at `index`, with its project memberships, before it
+    /// is removed from `configs` and `projects`.
+    pub fn deleted(index: usize, configs: &[AppConfig], projects: &[Project]) -> Option<Self> {
+        let config = configs.get(index)?.clone();
+        let memberships = projects
+            .iter()
+            .map(|p| Membership {
+                project_id: p.id.clone(),
+                member: p.members.iter().position(|m| m.config_id == config.id).map(|i| (i, p.members[i].clone())),
+                dependents: p.members.iter().filter(|m| m.depends_on.contains(&config.id)).map(|m| m.config_id.clone()).collect(),
+            })
+            .filter(|m| m.member.is_some() || !m.dependents.is_empty())
+            .collect();
+        Some(ConfigChange::Deleted { index, config, memberships })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ConfigChange::Added(config) => format!("add '{}'", config.app_name),
+            ConfigChange::Edited { after, .. } => format!("edit of '{}'", after.app_name),
+            ConfigChange::Deleted { config, .. } => format!("delete '{}'", config.app_name),
+        }
+    }
+
+    /// Reverts the change when `undo`, repeats it otherwise. Configs are found by id, so other
+    /// changes made since, such as builds, are kept. Fails if the config is no longer where the
+    /// change expects it, e.g. after `apps.json` removed it.
+    fn apply(&self, undo: bool, configs: &mut Vec<AppConfig>, projects: &mut [Project]) -> Result<Applied, String> {
+        let position = |id: &str| configs.iter().position(|c| c.id == id);
+        match (self, undo) {
+            (ConfigChange::Added(config), true) | (ConfigChange::Deleted { config, .. }, false) => {
+                let at = position(&config.id).ok_or_else(|| format!("'{}' no longer exists", config.app_name))?;
+                configs.remove(at);
+                for project in projects.iter_mut() {
+                    project.set_member(&config.id, false);
+                }
+                Ok(Applied::Removed(config.id.clone()))
+            }
+            (ConfigChange::Added(config), false) | (ConfigChange::Deleted { config, .. }, true) => {
+                if position(&config.id).is_some() {
+                    return Err(format!("'{}' already exists", config.app_name));
+                }
+                let index = match self {
+                    ConfigChange::Deleted { index, .. } => (*index).min(configs.len()),
+                    _ => configs.len(),
+                };
+                configs.insert(index, config.clone());
+                if let ConfigChange::Deleted { memberships, .. } = self {
+                    restore_memberships(&config.id, memberships, projects);
+                }
+                Ok(Applied::Inserted(config.id.clone()))
+            }
+            (ConfigChange::Edited { before, after }, _) => {
+                let target = if undo { before } else { after };
+                let at = position(&target.id).ok_or_else(|| format!("'{}' no longer exists", target.app_name))?;
+                let current = &configs[at];
+                configs[at] = AppConfig {
+                    last_generated_at: current.last_generated_at,
+                    build_counter: current.build_counter,
+                    ..AppConfig::clone(target)
+                };
+                Ok(Applied::Replaced(target.id.clone()))
+            }
+        }
+    }
+}
+
+fn restore_memberships(config_id: &str, memberships: &[Membership], projects: &mut [Project]) {
+    for membership in memberships {
+        let Some(project) = projects.iter_mut().find(|p| p.id == membership.project_id) else {
+            continue;
+        };
+        if let Some((at, member)) = &membership.member {
+            if !project.contains(config_id) {
+                project.members.insert((*at).min(project.members.len()), member.clone());
+            }
+        }
+        for dependent in project.members.iter_mut().filter(|m| membership.dependents.contains(&m.config_id)) {
+            if !dependent.depends_on.iter().any(|id| id == config_id) {
+                dependent.depends_on.push(config_id.to_string());
+            }
+        }
+    }
+}
+
+/// Changes to the app list that Undo (Ctrl+Z) and Redo (Ctrl+Shift+Z) step through.
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    undo: Vec<ConfigChange>,
+    redo: Vec<ConfigChange>,
+}
+
+impl UndoStack {
+    /// Records a change that was just made; it can no longer be followed by a Redo.
+    pub fn push(&mut self, change: ConfigChange) {
+        self.redo.clear();
+        self.undo.push(change);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// What Undo would revert, e.g. "edit of 'Shop'".
+    pub fn undo_label(&self) -> Option<String> {
+        self.undo.last().map(ConfigChange::label)
+    }
+
+    pub fn redo_label(&self) -> Option<String> {
+        self.redo.last().map(ConfigChange::label)
+    }
+
+    /// Reverts the last change. A change that can't be reverted is dropped along with the
+    /// rest of the redo history, and its error returned.
+    pub fn undo(&mut self, configs: &mut Vec<AppConfig>, projects: &mut [Project]) -> Option<Result<(ConfigChange, Applied), String>> {
+        let change = self.undo.pop()?;
+        Some(match change.apply(true, configs, projects) {
+            Ok(applied) => {
+                self.redo.push(change.clone());
+                Ok((change, applied))
+            }
+            Err(e) => {
+                self.redo.clear();
+                Err(format!("Cannot undo {}: {}", change.label(), e))
+            }
+        })
+    }
+
+    pub fn redo(&mut self, configs: &mut Vec<AppConfig>, projects: &mut [Project]) -> Option<Result<(ConfigChange, Applied), String>> {
+        let change = self.redo.pop()?;
+        Some(match change.apply(false, configs, projects) {
+            Ok(applied) => {
+                self.undo.push(change.clone());
+                Ok((change, applied))
+            }
+            Err(e) => {
+                self.redo.clear();
+                Err(format!("Cannot redo {}: {}", change.label(), e))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(name: &str) -> AppConfig {
+        AppConfig { app_name: name.to_string(), ..Default::default() }
+    }
+
+    fn names(configs: &[AppConfig]) -> Vec<&str> {
+        configs.iter().map(|c| c.app_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_undo_and_redo_add_edit_delete() {
+        let (shop, blog) = (config("Shop"), config("Blog"));
+        let mut configs = vec![shop.clone(), blog.clone()];
+        let mut project = Project { id: "p".to_string(), name: "Suite".to_string(), ..Default::default() };
+        project.set_member(&shop.id, true);
+        project.set_member(&blog.id, true);
+        project.members[1].depends_on.push(shop.id.clone());
+        let mut projects = vec![project.clone()];
+        let mut stack = UndoStack::default();
+
+        let widget = config("Widget");
+        configs.push(widget.clone());
+        stack.push(ConfigChange::Added(widget.clone()));
+        let renamed = AppConfig { app_name: "Shop Pro".to_string(), ..shop.clone() };
+        stack.push(ConfigChange::Edited { before: Box::new(shop.clone()), after: Box::new(renamed.clone()) });
+        configs[0] = renamed;
+        stack.push(ConfigChange::deleted(0, &configs, &projects).unwrap());
+        configs.remove(0);
+        projects[0].set_member(&shop.id, false);
+        assert_eq!(stack.undo_label().as_deref(), Some("delete 'Shop Pro'"));
+
+        // A build after the edit is kept when the edit is undone.
+        configs[0].build_counter = 7;
+        let (_, applied) = stack.undo(&mut configs, &mut projects).unwrap().unwrap();
+        assert_eq!(applied, Applied::Inserted(shop.id.clone()));
+        assert_eq!(projects, [project.clone()]);
+        assert!(stack.undo(&mut configs, &mut projects).unwrap().is_ok());
+        assert_eq!(names(&configs), ["Shop", "Blog", "Widget"]);
+        assert!(stack.undo(&mut configs, &mut projects).unwrap().is_ok());
+        assert_eq!(names(&configs), ["Shop", "Blog"]);
+        assert!(stack.undo(&mut configs, &mut projects).is_none());
+
+        assert!(stack.redo(&mut configs, &mut projects).unwrap().is_ok());
+        assert!(stack.redo(&mut configs, &mut projects).unwrap().is_ok());
+        assert_eq!(names(&configs), ["Shop Pro", "Blog", "Widget"]);
+        assert_eq!(stack.redo_label().as_deref(), Some("delete 'Shop Pro'"));
+
+        // A new change ends the redo history; a change whose config is gone is dropped.
+        stack.push(ConfigChange::Added(config("Other")));
+        assert!(stack.redo_label().is_none());
+        assert!(stack.undo(&mut configs, &mut projects).unwrap().unwrap_err().contains("no longer exists"));
+        assert_eq!(stack.undo_label().as_deref(), Some("edit of 'Shop Pro'"));
+    }
+}